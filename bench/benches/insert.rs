@@ -1,7 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
-use tui_textarea::{CursorMove, Input, Key, TextArea};
+use std::collections::HashMap;
+use tui_textarea::{CursorMove, Input, Key, TextArea, TextAreaTheme};
 use tui_textarea_bench::{dummy_terminal, TerminalExt, LOREM, SEED};
 
 #[inline]
@@ -83,6 +84,28 @@ fn append_long_lorem(repeat: usize) -> usize {
     textarea.lines().len()
 }
 
+// Typing at the start of a paragraph reflows all the rows after the cursor
+#[inline]
+fn wrap_paragraph(repeat: usize) -> usize {
+    let mut textarea = TextArea::new(vec![String::new()], HashMap::new(), 80, TextAreaTheme::default());
+    textarea.insert_str_wrapped(LOREM.join(" ").repeat(repeat));
+    textarea.move_cursor(CursorMove::Jump(0, 0));
+    let mut term = dummy_terminal();
+
+    for line in LOREM {
+        for c in line.chars() {
+            textarea.input(Input {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+            });
+            term.draw_textarea(&textarea);
+        }
+    }
+
+    textarea.lines().len()
+}
+
 fn append(c: &mut Criterion) {
     c.bench_function("insert::append::1_lorem", |b| {
         b.iter(|| black_box(append_lorem(1)))
@@ -120,5 +143,14 @@ fn long(c: &mut Criterion) {
     });
 }
 
-criterion_group!(insert, append, random, long);
+fn wrap(c: &mut Criterion) {
+    c.bench_function("insert::wrap::1_lorem", |b| {
+        b.iter(|| black_box(wrap_paragraph(1)))
+    });
+    c.bench_function("insert::wrap::10_lorem", |b| {
+        b.iter(|| black_box(wrap_paragraph(10)))
+    });
+}
+
+criterion_group!(insert, append, random, long, wrap);
 criterion_main!(insert);
@@ -15,7 +15,7 @@ use std::fs;
 use std::io;
 use std::io::{BufRead, Write};
 use std::path::PathBuf;
-use tui_textarea::{CursorMove, Input, Key, TextArea};
+use tui_textarea::{CursorMove, Input, InputKind, Key, TextArea};
 
 macro_rules! error {
     ($fmt: expr $(, $args:tt)*) => {{
@@ -258,6 +258,10 @@ impl<'a> Editor<'a> {
             if search_height > 0 {
                 let textarea = &mut self.buffers[self.current].textarea;
                 match crossterm::event::read()?.into() {
+                    Input {
+                        kind: InputKind::Release,
+                        ..
+                    } => {}
                     Input {
                         key: Key::Char('g' | 'n'),
                         ctrl: true,
@@ -308,6 +312,10 @@ impl<'a> Editor<'a> {
                 }
             } else {
                 match crossterm::event::read()?.into() {
+                    Input {
+                        kind: InputKind::Release,
+                        ..
+                    } => {}
                     Input {
                         key: Key::Char('q'),
                         ctrl: true,
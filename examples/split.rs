@@ -8,7 +8,7 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::widgets::{Block, Borders};
 use ratatui::Terminal;
 use std::io;
-use tui_textarea::{Input, Key, TextArea};
+use tui_textarea::{Input, InputKind, Key, TextArea};
 
 fn inactivate(textarea: &mut TextArea<'_>) {
     textarea.set_cursor_line_style(Style::default());
@@ -59,6 +59,10 @@ fn main() -> io::Result<()> {
             }
         })?;
         match crossterm::event::read()?.into() {
+            Input {
+                kind: InputKind::Release,
+                ..
+            } => {}
             Input { key: Key::Esc, .. } => break,
             Input {
                 key: Key::Char('x'),
@@ -17,7 +17,7 @@ use tui::style::{Color, Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::{Block, Borders, Paragraph};
 use tui::Terminal;
-use tui_textarea::{CursorMove, Input, Key, TextArea};
+use tui_textarea::{CursorMove, Input, InputKind, Key, TextArea};
 
 macro_rules! error {
     ($fmt: expr $(, $args:tt)*) => {{
@@ -260,6 +260,10 @@ impl<'a> Editor<'a> {
             if search_height > 0 {
                 let textarea = &mut self.buffers[self.current].textarea;
                 match crossterm::event::read()?.into() {
+                    Input {
+                        kind: InputKind::Release,
+                        ..
+                    } => {}
                     Input {
                         key: Key::Char('g' | 'n'),
                         ctrl: true,
@@ -310,6 +314,10 @@ impl<'a> Editor<'a> {
                 }
             } else {
                 match crossterm::event::read()?.into() {
+                    Input {
+                        kind: InputKind::Release,
+                        ..
+                    } => {}
                     Input {
                         key: Key::Char('q'),
                         ctrl: true,
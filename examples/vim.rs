@@ -11,7 +11,7 @@ use std::fmt;
 use std::fs;
 use std::io;
 use std::io::BufRead;
-use tui_textarea::{CursorMove, Input, Key, Scrolling, TextArea};
+use tui_textarea::{CursorMove, Input, InputKind, Key, Scrolling, TextArea};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Mode {
@@ -85,7 +85,7 @@ impl Vim {
     }
 
     fn transition(&self, input: Input, textarea: &mut TextArea<'_>) -> Transition {
-        if input.key == Key::Null {
+        if input.key == Key::Null || input.kind == InputKind::Release {
             return Transition::Nop;
         }
 
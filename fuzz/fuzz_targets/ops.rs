@@ -0,0 +1,38 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use tui_textarea::{Op, TextArea};
+use tui_textarea_bench::{dummy_terminal, TerminalExt};
+
+fn fuzz(data: &[u8]) -> Result<()> {
+    let mut term = dummy_terminal();
+    let mut data = Unstructured::new(data);
+    let text = <&str>::arbitrary(&mut data)?;
+    let mut textarea = TextArea::from(text.lines());
+    for _ in 0..100 {
+        let op = Op::arbitrary(&mut data)?;
+        let desc = format!("{:?}", op);
+        let modified = textarea.apply_op(op);
+        if let Err(err) = textarea.check_invariants() {
+            panic!("invariant broken after {}: {}", desc, err);
+        }
+
+        // Undo and redo of the edit must restore the same text
+        if modified {
+            let lines = textarea.lines().to_vec();
+            let mut t = textarea.clone();
+            if t.undo() {
+                t.redo();
+                assert_eq!(t.lines(), lines, "undo round-trip after {}", desc);
+            }
+        }
+
+        term.draw_textarea(&mut textarea);
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = fuzz(data);
+});
@@ -0,0 +1,88 @@
+use crate::ratatui::style::{Color, Modifier, Style};
+
+/// Built-in accessible style presets which can be switched at runtime with
+/// [`TextArea::set_accessibility`](crate::TextArea::set_accessibility). Both presets keep the selection, search
+/// matches, links and the cursor distinguishable with text modifiers so that they do not rely on color alone.
+///
+/// This type is marked as `#[non_exhaustive]` since more presets may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Accessibility {
+    /// The styles set by the application. This is the default.
+    Standard,
+    /// White text on black with bright colors. The selection is reversed, search matches are bold, links are underlined,
+    /// and the cursor is bold and underlined.
+    HighContrast,
+    /// No colors. The selection is reversed, search matches are bold and italic, links are underlined, and the cursor
+    /// is reversed and underlined so that it is visible in the selection.
+    Monochrome,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self::Standard
+    }
+}
+
+// Styles replaced by an accessibility preset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PresetStyles {
+    pub(crate) text: Style,
+    pub(crate) select: Style,
+    pub(crate) search: Style,
+    pub(crate) link: Style,
+    pub(crate) cursor: Style,
+    pub(crate) cursor_line: Style,
+}
+
+impl PresetStyles {
+    pub(crate) fn preset(mode: Accessibility) -> Option<Self> {
+        let styles = match mode {
+            Accessibility::Standard => return None,
+            Accessibility::HighContrast => Self {
+                text: Style::default().fg(Color::White).bg(Color::Black),
+                select: Style::default().add_modifier(Modifier::REVERSED),
+                search: Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::LightYellow)
+                    .add_modifier(Modifier::BOLD),
+                link: Style::default().fg(Color::LightCyan).add_modifier(Modifier::UNDERLINED),
+                cursor: Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                // Not underlined so that links in the cursor line are distinguishable
+                cursor_line: Style::default(),
+            },
+            Accessibility::Monochrome => Self {
+                text: Style::default().fg(Color::Reset).bg(Color::Reset),
+                select: Style::default().add_modifier(Modifier::REVERSED),
+                search: Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC),
+                link: Style::default().add_modifier(Modifier::UNDERLINED),
+                cursor: Style::default().add_modifier(Modifier::REVERSED | Modifier::UNDERLINED),
+                cursor_line: Style::default(),
+            },
+        };
+        Some(styles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_do_not_rely_on_color() {
+        for mode in [Accessibility::HighContrast, Accessibility::Monochrome] {
+            let s = PresetStyles::preset(mode).unwrap();
+            let modifiers = [s.select, s.search, s.link, s.cursor].map(|s| s.add_modifier);
+            for (i, m) in modifiers.iter().enumerate() {
+                assert!(!m.is_empty(), "{:?} {}", mode, i);
+                for (j, n) in modifiers.iter().enumerate().skip(i + 1) {
+                    assert_ne!(m, n, "{:?} {} {}", mode, i, j);
+                }
+            }
+        }
+        assert_eq!(PresetStyles::preset(Accessibility::Standard), None);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::history::Edit;
+use std::collections::{BTreeMap, VecDeque};
+
+// Max number of positions kept in the change list
+const MAX_CHANGES: usize = 100;
+
+/// ID of an anchor created by [`TextArea::create_anchor`](crate::TextArea::create_anchor). IDs of removed anchors are
+/// not reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AnchorId(usize);
+
+/// Positions which move with the text around them as it is edited.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Anchors {
+    next_id: usize,
+    positions: BTreeMap<AnchorId, (usize, usize)>,
+}
+
+impl Anchors {
+    pub(crate) fn create(&mut self, pos: (usize, usize)) -> AnchorId {
+        let id = AnchorId(self.next_id);
+        self.next_id += 1;
+        self.positions.insert(id, pos);
+        id
+    }
+
+    pub(crate) fn get(&self, id: AnchorId) -> Option<(usize, usize)> {
+        self.positions.get(&id).copied()
+    }
+
+    pub(crate) fn set(&mut self, id: AnchorId, pos: (usize, usize)) {
+        self.positions.insert(id, pos);
+    }
+
+    pub(crate) fn remove(&mut self, id: AnchorId) -> bool {
+        self.positions.remove(&id).is_some()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (AnchorId, (usize, usize))> + '_ {
+        self.positions.iter().map(|(id, pos)| (*id, *pos))
+    }
+
+    /// Move all anchors by the function.
+    pub(crate) fn map(&mut self, mut f: impl FnMut((usize, usize)) -> (usize, usize)) {
+        for pos in self.positions.values_mut() {
+            *pos = f(*pos);
+        }
+    }
+
+    /// Move all anchors after the edit was applied to the text.
+    pub(crate) fn shift(&mut self, edit: &Edit) {
+        self.map(|pos| edit.shift_pos(pos));
+    }
+
+    /// Keep all anchors in the text after it was replaced.
+    pub(crate) fn clamp(&mut self, lines: &[String]) {
+        self.map(|(row, col)| {
+            let row = row.min(lines.len() - 1);
+            (row, col.min(lines[row].chars().count()))
+        });
+    }
+}
+
+/// Positions of recent edits, newest first. They move with the text as anchors do. The index points to the position
+/// the cursor last jumped to.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChangeList {
+    positions: VecDeque<(usize, usize)>,
+    index: Option<usize>,
+}
+
+impl ChangeList {
+    /// Record the cursor position just after a new edit. Successive edits in the same row are merged into one change.
+    pub(crate) fn record(&mut self, pos: (usize, usize)) {
+        self.index = None;
+        match self.positions.front_mut() {
+            Some(last) if last.0 == pos.0 => *last = pos,
+            _ => {
+                self.positions.push_front(pos);
+                self.positions.truncate(MAX_CHANGES);
+            }
+        }
+    }
+
+    pub(crate) fn last(&self) -> Option<(usize, usize)> {
+        self.positions.front().copied()
+    }
+
+    /// Step to the next older change.
+    pub(crate) fn older(&mut self) -> Option<(usize, usize)> {
+        let i = self.index.map_or(0, |i| i + 1);
+        let pos = *self.positions.get(i)?;
+        self.index = Some(i);
+        Some(pos)
+    }
+
+    /// Step to the next newer change.
+    pub(crate) fn newer(&mut self) -> Option<(usize, usize)> {
+        let i = self.index?.checked_sub(1)?;
+        self.index = Some(i);
+        Some(self.positions[i])
+    }
+
+    pub(crate) fn shift(&mut self, edit: &Edit) {
+        for pos in self.positions.iter_mut() {
+            *pos = edit.shift_pos(*pos);
+        }
+    }
+
+    pub(crate) fn clamp(&mut self, lines: &[String]) {
+        for (row, col) in self.positions.iter_mut() {
+            *row = (*row).min(lines.len() - 1);
+            *col = (*col).min(lines[*row].chars().count());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_and_clamp() {
+        let mut anchors = Anchors::default();
+        let a = anchors.create((0, 3));
+        let b = anchors.create((5, 1));
+        assert_ne!(a, b);
+
+        anchors.clamp(&["ab".to_string(), "cde".to_string()]);
+        assert_eq!(anchors.get(a), Some((0, 2)));
+        assert_eq!(anchors.get(b), Some((1, 1)));
+
+        assert!(anchors.remove(a));
+        assert!(!anchors.remove(a));
+        assert_eq!(anchors.iter().count(), 1);
+        let c = anchors.create((0, 0));
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn change_list() {
+        let mut changes = ChangeList::default();
+        assert_eq!(changes.older(), None);
+        changes.record((0, 1));
+        changes.record((0, 3));
+        changes.record((2, 0));
+        assert_eq!(changes.last(), Some((2, 0)));
+        assert_eq!(changes.older(), Some((2, 0)));
+        assert_eq!(changes.older(), Some((0, 3)));
+        assert_eq!(changes.older(), None);
+        assert_eq!(changes.newer(), Some((2, 0)));
+        assert_eq!(changes.newer(), None);
+    }
+}
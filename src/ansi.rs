@@ -0,0 +1,93 @@
+use crate::ratatui::style::{Color, Modifier, Style};
+
+const MODIFIERS: &[(Modifier, u8)] = &[
+    (Modifier::BOLD, 1),
+    (Modifier::DIM, 2),
+    (Modifier::ITALIC, 3),
+    (Modifier::UNDERLINED, 4),
+    (Modifier::SLOW_BLINK, 5),
+    (Modifier::RAPID_BLINK, 6),
+    (Modifier::REVERSED, 7),
+    (Modifier::HIDDEN, 8),
+    (Modifier::CROSSED_OUT, 9),
+];
+
+// SGR parameters of the color. `base` is 30 for foreground and 40 for background
+fn color_params(color: Color, base: u8) -> Option<String> {
+    let code = |offset: u8| Some((base + offset).to_string());
+    let bright = |offset: u8| Some((base + 60 + offset).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => code(0),
+        Color::Red => code(1),
+        Color::Green => code(2),
+        Color::Yellow => code(3),
+        Color::Blue => code(4),
+        Color::Magenta => code(5),
+        Color::Cyan => code(6),
+        Color::Gray => code(7),
+        Color::DarkGray => bright(0),
+        Color::LightRed => bright(1),
+        Color::LightGreen => bright(2),
+        Color::LightYellow => bright(3),
+        Color::LightBlue => bright(4),
+        Color::LightMagenta => bright(5),
+        Color::LightCyan => bright(6),
+        Color::White => bright(7),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+        Color::Indexed(i) => Some(format!("{};5;{}", base + 8, i)),
+    }
+}
+
+/// Write the text to `out` wrapped with the SGR escape sequences of the style. Unstyled text is written as is.
+pub(crate) fn write_ansi(out: &mut String, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    let mut params = vec![];
+    for (modifier, code) in MODIFIERS {
+        if style.add_modifier.contains(*modifier) {
+            params.push(code.to_string());
+        }
+    }
+    params.extend(style.fg.and_then(|c| color_params(c, 30)));
+    params.extend(style.bg.and_then(|c| color_params(c, 40)));
+
+    if params.is_empty() {
+        out.push_str(text);
+    } else {
+        out.push_str("\x1b[");
+        out.push_str(&params.join(";"));
+        out.push('m');
+        out.push_str(text);
+        out.push_str("\x1b[0m");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sgr_params() {
+        let ansi = |style| {
+            let mut s = String::new();
+            write_ansi(&mut s, "x", style);
+            s
+        };
+        assert_eq!(ansi(Style::default()), "x");
+        assert_eq!(ansi(Style::default().fg(Color::Reset)), "x");
+        assert_eq!(
+            ansi(Style::default().fg(Color::White).bg(Color::Indexed(42))),
+            "\x1b[97;48;5;42mx\x1b[0m",
+        );
+        assert_eq!(
+            ansi(
+                Style::default()
+                    .add_modifier(Modifier::ITALIC | Modifier::UNDERLINED)
+                    .bg(Color::Rgb(1, 2, 3))
+            ),
+            "\x1b[3;4;48;2;1;2;3mx\x1b[0m",
+        );
+    }
+}
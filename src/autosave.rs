@@ -0,0 +1,92 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+type AutosaveFn = dyn Fn(&[String]) + Send + Sync;
+
+/// Idle-time autosave state. The text becomes dirty on every edit and the callback is called with the text on a tick
+/// after the text was not edited for the interval.
+#[derive(Clone)]
+pub(crate) struct Autosave {
+    interval: Duration,
+    callback: Arc<AutosaveFn>,
+    last_edit: Option<Instant>,
+}
+
+impl Autosave {
+    pub(crate) fn new<F>(interval: Duration, callback: F) -> Self
+    where
+        F: Fn(&[String]) + Send + Sync + 'static,
+    {
+        Self {
+            interval,
+            callback: Arc::new(callback),
+            last_edit: None,
+        }
+    }
+
+    pub(crate) fn touch(&mut self, now: Instant) {
+        self.last_edit = Some(now);
+    }
+
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.last_edit.is_some()
+    }
+
+    /// Call the callback when the text is dirty and has been idle for the interval. Returns `true` when it was called.
+    pub(crate) fn tick(&mut self, now: Instant, lines: &[String]) -> bool {
+        match self.last_edit {
+            Some(t) if now.saturating_duration_since(t) >= self.interval => self.flush(lines),
+            _ => false,
+        }
+    }
+
+    /// Call the callback when the text is dirty regardless of the interval.
+    pub(crate) fn flush(&mut self, lines: &[String]) -> bool {
+        if self.last_edit.take().is_none() {
+            return false;
+        }
+        (self.callback)(lines);
+        true
+    }
+}
+
+impl fmt::Debug for Autosave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Autosave")
+            .field("interval", &self.interval)
+            .field("last_edit", &self.last_edit)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn tick_after_idle() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let c = count.clone();
+        let mut autosave = Autosave::new(Duration::from_secs(2), move |_| {
+            c.fetch_add(1, Ordering::SeqCst);
+        });
+        let lines = ["abc".to_string()];
+        let start = Instant::now();
+
+        assert!(!autosave.tick(start, &lines));
+        autosave.touch(start);
+        assert!(autosave.is_dirty());
+        assert!(!autosave.tick(start + Duration::from_secs(1), &lines));
+        assert!(autosave.tick(start + Duration::from_secs(2), &lines));
+        assert!(!autosave.is_dirty());
+        assert!(!autosave.tick(start + Duration::from_secs(5), &lines));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        autosave.touch(start);
+        assert!(autosave.flush(&lines));
+        assert!(!autosave.flush(&lines));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+}
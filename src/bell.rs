@@ -0,0 +1,45 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Why an operation could not proceed. Passed to the handler set by
+/// [`TextArea::set_bell_handler`](crate::TextArea::set_bell_handler).
+///
+/// This type is marked as `#[non_exhaustive]` since more reasons may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BellReason {
+    /// The cursor cannot move further since it is at the edge of the text.
+    BufferEdge,
+    /// No match of the search pattern was found.
+    NoMatch,
+    /// No edit is left to undo.
+    NothingToUndo,
+    /// No edit is left to redo.
+    NothingToRedo,
+}
+
+type BellFn = dyn Fn(BellReason) + Send + Sync;
+
+#[derive(Clone, Default)]
+pub(crate) struct Bell(Option<Arc<BellFn>>);
+
+impl Bell {
+    pub(crate) fn new<F>(handler: F) -> Self
+    where
+        F: Fn(BellReason) + Send + Sync + 'static,
+    {
+        Self(Some(Arc::new(handler)))
+    }
+
+    pub(crate) fn ring(&self, reason: BellReason) {
+        if let Some(handler) = &self.0 {
+            handler(reason);
+        }
+    }
+}
+
+impl fmt::Debug for Bell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Bell").field(&self.0.is_some()).finish()
+    }
+}
@@ -0,0 +1,262 @@
+use crate::codeblock::{CodeBlock, FenceCache};
+use crate::highlight::OverlayRules;
+use crate::ratatui::style::Style;
+use crate::ratatui::text::{Line, Span};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Mutex;
+
+// Scrolling through a huge text should not keep the spans of all lines
+const MAX_CACHED_LINES: usize = 1024;
+
+/// Revision counter of each line. The revision of a line is bumped when the line is edited so that the spans rendered
+/// for the previous revision are not reused.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LineRevisions {
+    revs: Vec<u64>,
+    next: u64,
+}
+
+impl LineRevisions {
+    fn bump(&mut self) -> u64 {
+        self.next += 1;
+        self.next
+    }
+
+    /// Revision of the line at the row. All lines get new revisions when the number of lines changed without being
+    /// tracked by [`LineRevisions::track`].
+    pub(crate) fn get(&mut self, row: usize, len: usize) -> u64 {
+        if self.revs.len() != len {
+            let mut revs = Vec::with_capacity(len);
+            for _ in 0..len {
+                revs.push(self.bump());
+            }
+            self.revs = revs;
+        }
+        self.revs[row]
+    }
+
+    /// Move the revisions by the row shifts of an edit and bump the revisions of the edited rows.
+    pub(crate) fn track(&mut self, edited: (usize, usize), shifts: &[(usize, isize)], len: usize) {
+        if self.revs.is_empty() {
+            return; // Revisions are given on the first call of `LineRevisions::get`
+        }
+        for &(at, delta) in shifts {
+            let at = at.min(self.revs.len());
+            if delta < 0 {
+                let end = (at + delta.unsigned_abs()).min(self.revs.len());
+                self.revs.drain(at..end);
+            } else {
+                let added: Vec<_> = (0..delta).map(|_| self.bump()).collect();
+                self.revs.splice(at..at, added);
+            }
+        }
+        let (start, end) = edited;
+        for row in start..=end {
+            if row < self.revs.len() {
+                self.revs[row] = self.bump();
+            }
+        }
+        // Edits not reported through the row shifts are caught by `LineRevisions::get`
+        if self.revs.len() != len {
+            self.revs.clear();
+        }
+    }
+}
+
+/// Everything besides the line itself which changes the spans of a line. The cursor, the selection and hops are not
+/// included since lines with them are never cached.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RenderState {
+    pub(crate) line_number: Option<(Style, u8)>,
+    pub(crate) cursor_style: Style,
+    pub(crate) select_style: Style,
+    pub(crate) tab_len: u8,
+    pub(crate) mask: Option<char>,
+    pub(crate) overlay_rules: OverlayRules,
+    pub(crate) search: Option<(String, Style)>,
+    pub(crate) highlight_patterns: Vec<(String, String, Style)>,
+    pub(crate) link_style: Style,
+    pub(crate) url_detection: bool,
+    pub(crate) hashtag_style: Option<Style>,
+    pub(crate) mention_style: Option<Style>,
+    pub(crate) tokens: Vec<(String, String, Style)>,
+    pub(crate) conceal: bool,
+}
+
+// Spans of a line with what they were built from. The text is compared as well as the revision so that an edit which
+// was not tracked never shows stale spans.
+#[derive(Debug)]
+struct CachedLine {
+    revision: u64,
+    text: String,
+    links: Vec<(usize, usize)>,
+    code: bool,
+    spans: Line<'static>,
+}
+
+#[derive(Debug, Default)]
+struct Cached {
+    revisions: LineRevisions,
+    state: Option<RenderState>,
+    lines: HashMap<usize, CachedLine>,
+    fences: FenceCache,
+}
+
+/// Spans of rendered lines keyed by row so that redraws only rebuild the spans of changed lines. The cache is behind a
+/// mutex since lines are rendered through a shared reference and the textarea should stay `Sync`.
+#[derive(Debug, Default)]
+pub(crate) struct LineCache(Mutex<Cached>);
+
+impl Clone for LineCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl LineCache {
+    /// Track an edit of the lines. See [`LineRevisions::track`]. Fence states from the edited row are dropped.
+    pub(crate) fn track(&mut self, edited: (usize, usize), shifts: &[(usize, isize)], len: usize) {
+        let cached = self.0.get_mut().unwrap_or_else(|e| e.into_inner());
+        cached.revisions.track(edited, shifts, len);
+        let first = shifts.iter().map(|s| s.0).fold(edited.0, usize::min);
+        cached.fences.invalidate(first);
+    }
+
+    /// Whether each row in the range is in a fenced code block. See [`FenceCache`].
+    pub(crate) fn code_rows(&self, lines: &[String], range: Range<usize>) -> Vec<bool> {
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let Cached { revisions, fences, .. } = &mut *cached;
+        fences.code_rows(lines, range, &mut |row| revisions.get(row, lines.len()))
+    }
+
+    /// Fenced code block containing the row. See [`FenceCache`].
+    pub(crate) fn code_block_at(&self, lines: &[String], row: usize) -> Option<CodeBlock> {
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let Cached { revisions, fences, .. } = &mut *cached;
+        fences.code_block_at(lines, row, &mut |row| revisions.get(row, lines.len()))
+    }
+
+    /// Revision of the line at the row in the `len` lines.
+    pub(crate) fn revision(&self, row: usize, len: usize) -> u64 {
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        cached.revisions.get(row, len)
+    }
+
+    /// Get the spans of the line at the row from the cache or build and cache them. `len` is the number of lines,
+    /// `links` are the column ranges of the links in the line and `code` is whether the line is in a fenced code block.
+    /// The whole cache is dropped when the render state changed since the last call.
+    pub(crate) fn get_or_build<'a>(
+        &self,
+        state: &RenderState,
+        (row, len): (usize, usize),
+        text: &str,
+        (links, code): (Vec<(usize, usize)>, bool),
+        build: impl FnOnce() -> Line<'a>,
+    ) -> Line<'static> {
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if cached.state.as_ref() != Some(state) {
+            cached.lines.clear();
+            cached.state = Some(state.clone());
+        }
+        let revision = cached.revisions.get(row, len);
+        if let Some(c) = cached.lines.get(&row) {
+            if c.revision == revision && c.links == links && c.code == code && c.text == text {
+                return c.spans.clone();
+            }
+        }
+
+        let spans = into_owned(build());
+        if cached.lines.len() >= MAX_CACHED_LINES {
+            cached.lines.clear();
+        }
+        let line = CachedLine {
+            revision,
+            text: text.to_string(),
+            links,
+            code,
+            spans: spans.clone(),
+        };
+        cached.lines.insert(row, line);
+        spans
+    }
+}
+
+fn into_owned(line: Line<'_>) -> Line<'static> {
+    let spans: Vec<_> = line
+        .spans
+        .into_iter()
+        .map(|s| Span::styled(s.content.into_owned(), s.style))
+        .collect();
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_revisions() {
+        let mut revs = LineRevisions::default();
+        let first: Vec<_> = (0..3).map(|row| revs.get(row, 3)).collect();
+
+        // A row inserted after the first row and an edit in the first row
+        revs.track((0, 1), &[(1, 1)], 4);
+        assert_ne!(revs.get(0, 4), first[0]);
+        assert_ne!(revs.get(1, 4), first[1]);
+        assert_eq!(revs.get(2, 4), first[1]);
+        assert_eq!(revs.get(3, 4), first[2]);
+
+        // Rows removed
+        revs.track((0, 0), &[(1, -2)], 2);
+        assert_eq!(revs.get(1, 2), first[2]);
+
+        // The lines changed without tracking
+        revs.get(0, 5);
+        assert!((0..5).all(|row| revs.get(row, 5) > first[2]));
+    }
+
+    #[test]
+    fn line_cache() {
+        let state = RenderState {
+            line_number: None,
+            cursor_style: Style::default(),
+            select_style: Style::default(),
+            tab_len: 4,
+            mask: None,
+            overlay_rules: OverlayRules::default(),
+            search: None,
+            highlight_patterns: vec![],
+            link_style: Style::default(),
+            url_detection: false,
+            hashtag_style: None,
+            mention_style: None,
+            tokens: vec![],
+            conceal: false,
+        };
+        let mut cache = LineCache::default();
+        let line = cache.get_or_build(&state, (0, 2), "abc", (vec![], false), || Line::from("abc"));
+        assert_eq!(line, Line::from("abc"));
+        let line = cache.get_or_build(&state, (0, 2), "abc", (vec![], false), || unreachable!());
+        assert_eq!(line, Line::from("abc"));
+
+        // The line was edited
+        cache.track((0, 0), &[], 2);
+        let line = cache.get_or_build(&state, (0, 2), "abc", (vec![], false), || Line::from("x"));
+        assert_eq!(line, Line::from("x"));
+
+        // A link was added
+        let line = cache.get_or_build(&state, (0, 2), "abc", (vec![(0, 1)], false), || Line::from("y"));
+        assert_eq!(line, Line::from("y"));
+
+        // A fence was added above the line
+        let line = cache.get_or_build(&state, (0, 2), "abc", (vec![(0, 1)], true), || Line::from("c"));
+        assert_eq!(line, Line::from("c"));
+
+        // Render state changed
+        let mut changed = state.clone();
+        changed.tab_len = 8;
+        let line = cache.get_or_build(&changed, (0, 2), "abc", (vec![(0, 1)], false), || Line::from("z"));
+        assert_eq!(line, Line::from("z"));
+    }
+}
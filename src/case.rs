@@ -0,0 +1,64 @@
+/// Case transformation applied to the selected text by
+/// [`TextArea::transform_selection`](crate::TextArea::transform_selection).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Case {
+    /// `hello World` to `HELLO WORLD`
+    Upper,
+    /// `hello World` to `hello world`
+    Lower,
+    /// `hello World` to `Hello World`. The first letter of each word is uppercased and the rest are lowercased.
+    Title,
+    /// `hello World` to `HELLO wORLD`
+    Toggle,
+}
+
+// Convert the char only when the result is one char so that the columns of the text do not change. For example
+// `ß` is kept as it is by `Upper` since it would be `SS`.
+fn convert<I: Iterator<Item = char>>(c: char, mut converted: I) -> char {
+    match (converted.next(), converted.next()) {
+        (Some(d), None) => d,
+        _ => c,
+    }
+}
+
+impl Case {
+    /// Transform the chars. `prev` is the char before them, which decides if the first char starts a word in `Title`.
+    /// The number of chars never changes.
+    pub(crate) fn apply(self, chars: &mut [char], mut prev: Option<char>) {
+        for c in chars.iter_mut() {
+            let upper = match self {
+                Case::Upper => true,
+                Case::Lower => false,
+                Case::Title => !prev.map_or(false, char::is_alphanumeric),
+                Case::Toggle => c.is_lowercase(),
+            };
+            prev = Some(*c);
+            *c = if upper {
+                convert(*c, c.to_uppercase())
+            } else {
+                convert(*c, c.to_lowercase())
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply() {
+        for (case, prev, input, want) in [
+            (Case::Upper, None, "hello World", "HELLO WORLD"),
+            (Case::Lower, None, "hello World", "hello world"),
+            (Case::Title, None, "hello wORLD 2nd-try", "Hello World 2nd-Try"),
+            (Case::Title, Some('a'), "bc de", "bc De"),
+            (Case::Toggle, None, "hello World", "HELLO wORLD"),
+            (Case::Upper, None, "straße", "STRAßE"),
+        ] {
+            let mut chars = input.chars().collect::<Vec<_>>();
+            case.apply(&mut chars, prev);
+            assert_eq!(chars.into_iter().collect::<String>(), want, "{:?} {:?}", case, input);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::ops::Range;
+
+/// Fenced code block in the text, which starts with a line of ```` ``` ```` and ends with the next one. Returned by
+/// [`TextArea::code_block_at`](crate::TextArea::code_block_at).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// Row of the opening fence.
+    pub start: usize,
+    /// Row of the closing fence. When the block is not closed, this is the last row of the text.
+    pub end: usize,
+    /// Info string after the opening fence such as `rust`. This is empty when it is not given.
+    pub info: String,
+    /// If the closing fence exists.
+    pub closed: bool,
+}
+
+impl CodeBlock {
+    /// Rows of the code between the fences.
+    pub fn content_rows(&self) -> Range<usize> {
+        if self.closed {
+            self.start + 1..self.end
+        } else {
+            self.start + 1..self.end + 1
+        }
+    }
+
+    /// Check if the row is in the block including the fences.
+    pub fn contains(&self, row: usize) -> bool {
+        self.start <= row && row <= self.end
+    }
+}
+
+// Info string of the line when it is a fence. Fences may be indented by up to 3 spaces as in CommonMark
+pub(crate) fn fence_info(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    trimmed.strip_prefix("```").map(|info| info.trim_matches(|c| c == '`' || c == ' '))
+}
+
+/// Find the fenced code blocks in the lines. A fence which is not closed makes a block to the end of the lines.
+pub(crate) fn find_code_blocks(lines: &[String]) -> Vec<CodeBlock> {
+    let mut blocks = vec![];
+    let mut open: Option<(usize, &str)> = None;
+    for (row, line) in lines.iter().enumerate() {
+        let info = match fence_info(line) {
+            Some(info) => info,
+            None => continue,
+        };
+        match open.take() {
+            Some((start, info)) => blocks.push(CodeBlock {
+                start,
+                end: row,
+                info: info.to_string(),
+                closed: true,
+            }),
+            None => open = Some((row, info)),
+        }
+    }
+    if let Some((start, info)) = open {
+        blocks.push(CodeBlock {
+            start,
+            end: lines.len() - 1,
+            info: info.to_string(),
+            closed: false,
+        });
+    }
+    blocks
+}
+
+/// Rows in fenced code blocks, including the fences, for each row in the range.
+pub(crate) fn code_rows(lines: &[String], range: Range<usize>) -> Vec<bool> {
+    let mut rows = Vec::with_capacity(range.len());
+    let mut open = false;
+    for (row, line) in lines.iter().enumerate().take(range.end) {
+        let fence = fence_info(line).is_some();
+        if row >= range.start {
+            rows.push(open || fence);
+        }
+        if fence {
+            open = !open;
+        }
+    }
+    rows
+}
+
+// Fence state of a row scanned from the top of the text
+#[derive(Clone, Copy, Debug)]
+struct FenceRow {
+    // Revision of the line when it was scanned
+    revision: u64,
+    fence: bool,
+    // A code block is open after the row
+    open: bool,
+}
+
+/// Fence states of the rows from the top of the text so that code blocks are found without scanning the text from the
+/// first row on each frame and keystroke. The state of a row depends on all rows before it, so an edit drops the
+/// states from the edited row. The cache is also dropped when the revision of the last scanned line changed, which
+/// means that the lines were replaced without an edit.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FenceCache {
+    rows: Vec<FenceRow>,
+}
+
+impl FenceCache {
+    /// Drop the states of the row and the rows after it.
+    pub(crate) fn invalidate(&mut self, row: usize) {
+        self.rows.truncate(row);
+    }
+
+    fn open_before(&self, row: usize) -> bool {
+        row.checked_sub(1).map_or(false, |r| self.rows[r].open)
+    }
+
+    // Scan the rows until `end`. `revision` returns the revision of the line at the row
+    fn scan(&mut self, lines: &[String], end: usize, revision: &mut impl FnMut(usize) -> u64) {
+        if let Some(last) = self.rows.len().checked_sub(1) {
+            if last >= lines.len() || self.rows[last].revision != revision(last) {
+                self.rows.clear();
+            }
+        }
+        for row in self.rows.len()..end.min(lines.len()) {
+            let fence = fence_info(&lines[row]).is_some();
+            let open = self.open_before(row) != fence;
+            let revision = revision(row);
+            self.rows.push(FenceRow { revision, fence, open });
+        }
+    }
+
+    /// Same as [`code_rows`] but the rows before the edited rows are not scanned again.
+    pub(crate) fn code_rows(
+        &mut self,
+        lines: &[String],
+        range: Range<usize>,
+        revision: &mut impl FnMut(usize) -> u64,
+    ) -> Vec<bool> {
+        self.scan(lines, range.end, revision);
+        let end = range.end.min(self.rows.len());
+        (range.start..end)
+            .map(|row| self.open_before(row) || self.rows[row].fence)
+            .collect()
+    }
+
+    /// Find the fenced code block containing the row. Only the lines in the block are scanned once the states of the
+    /// rows before it are cached.
+    pub(crate) fn code_block_at(
+        &mut self,
+        lines: &[String],
+        row: usize,
+        revision: &mut impl FnMut(usize) -> u64,
+    ) -> Option<CodeBlock> {
+        if row >= lines.len() {
+            return None;
+        }
+        self.scan(lines, row + 1, revision);
+        let (fence, open_before) = (self.rows[row].fence, self.open_before(row));
+        if !fence && !open_before {
+            return None;
+        }
+        // The opening fence is the nearest fence which has no block open before it
+        let start = (0..=row)
+            .rev()
+            .find(|&r| self.rows[r].fence && !self.open_before(r))
+            .unwrap_or(row);
+        let end = if fence && open_before {
+            Some(row)
+        } else {
+            (row + 1..lines.len()).find(|&r| fence_info(&lines[r]).is_some())
+        };
+        Some(CodeBlock {
+            start,
+            end: end.unwrap_or(lines.len() - 1),
+            info: fence_info(&lines[start]).unwrap_or_default().to_string(),
+            closed: end.is_some(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn fences() {
+        assert_eq!(fence_info("```"), Some(""));
+        assert_eq!(fence_info("```rust"), Some("rust"));
+        assert_eq!(fence_info("   ``` sh"), Some("sh"));
+        assert_eq!(fence_info("    ```"), None);
+        assert_eq!(fence_info("``"), None);
+        assert_eq!(fence_info("a ```"), None);
+    }
+
+    #[test]
+    fn blocks() {
+        let lines = lines(&["a", "```rust", "# x", "```", "b", "```", "c"]);
+        let blocks = find_code_blocks(&lines);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!((blocks[0].start, blocks[0].end, blocks[0].info.as_str()), (1, 3, "rust"));
+        assert_eq!(blocks[0].content_rows(), 2..3);
+        assert_eq!((blocks[1].start, blocks[1].end, blocks[1].closed), (5, 6, false));
+        assert_eq!(blocks[1].content_rows(), 6..7);
+
+        assert_eq!(
+            code_rows(&lines, 0..lines.len()),
+            [false, true, true, true, false, true, true]
+        );
+        assert_eq!(code_rows(&lines, 2..5), [true, true, false]);
+    }
+
+    #[test]
+    fn fence_cache() {
+        let mut lines = lines(&["a", "```rust", "# x", "```", "b", "```", "c"]);
+        let mut revs: Vec<u64> = (0..lines.len() as u64).collect();
+        let mut cache = FenceCache::default();
+        let blocks = find_code_blocks(&lines);
+        for row in 0..lines.len() {
+            let want = blocks.iter().find(|b| b.contains(row)).cloned();
+            assert_eq!(cache.code_block_at(&lines, row, &mut |r| revs[r]), want, "{}", row);
+        }
+        assert_eq!(cache.code_rows(&lines, 0..lines.len(), &mut |r| revs[r]), code_rows(&lines, 0..lines.len()));
+        assert_eq!(cache.code_rows(&lines, 2..5, &mut |r| revs[r]), [true, true, false]);
+
+        // Removing the closing fence of the first block with an edit
+        lines[3] = "x".to_string();
+        revs[3] = 10;
+        cache.invalidate(3);
+        assert_eq!(cache.code_rows(&lines, 0..lines.len(), &mut |r| revs[r]), code_rows(&lines, 0..lines.len()));
+        let blocks = find_code_blocks(&lines);
+        for row in 0..lines.len() {
+            let want = blocks.iter().find(|b| b.contains(row)).cloned();
+            assert_eq!(cache.code_block_at(&lines, row, &mut |r| revs[r]), want, "{}", row);
+        }
+
+        // Lines replaced without an edit are caught by the revision of the last scanned line
+        lines[6] = "```".to_string();
+        revs = (20..20 + lines.len() as u64).collect();
+        assert_eq!(cache.code_rows(&lines, 0..lines.len(), &mut |r| revs[r]), code_rows(&lines, 0..lines.len()));
+    }
+}
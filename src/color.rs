@@ -0,0 +1,164 @@
+use crate::ratatui::style::Color;
+use std::env;
+
+// Default RGB values of xterm for the 16 ANSI colors in the order of their indices
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// Levels of each component in the 6x6x6 color cube of the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Colors which the terminal can show. Theme colors which the terminal does not support are mapped to the nearest
+/// supported colors with [`TextAreaTheme::degrade`](crate::TextAreaTheme::degrade) so that the same theme works on
+/// basic terminals, for example over SSH.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSupport {
+    /// 24-bit RGB colors are supported. No color is mapped.
+    TrueColor,
+    /// The 256-color palette is supported. RGB colors are mapped to the palette.
+    Ansi256,
+    /// Only the 16 ANSI colors are supported. RGB and 256-color palette colors are mapped to them.
+    Ansi16,
+}
+
+impl Default for ColorSupport {
+    fn default() -> Self {
+        Self::TrueColor
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => ANSI16[i as usize].1,
+        16..=231 => {
+            let i = i - 16;
+            let l = |n: u8| CUBE_LEVELS[n as usize];
+            (l(i / 36), l(i / 6 % 6), l(i % 6))
+        }
+        _ => {
+            let v = 8 + 10 * (i - 232);
+            (v, v, v)
+        }
+    }
+}
+
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap() as u8
+    };
+    let cube = 16 + 36 * level(rgb.0) + 6 * level(rgb.1) + level(rgb.2);
+    let avg = ((rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3) as u8;
+    let gray = 232 + (avg.saturating_sub(3) / 10).min(23);
+    if distance(indexed_rgb(gray), rgb) < distance(indexed_rgb(cube), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn nearest_ansi16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16.iter().min_by_key(|(_, c)| distance(*c, rgb)).unwrap().0
+}
+
+impl ColorSupport {
+    /// Guess the color support of the terminal from the `COLORTERM` and `TERM` environment variables. `COLORTERM` set
+    /// to `truecolor` or `24bit` means [`ColorSupport::TrueColor`], and `TERM` containing `256color` means
+    /// [`ColorSupport::Ansi256`]. Otherwise [`ColorSupport::Ansi16`] is returned.
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").ok();
+        let term = env::var("TERM").ok();
+        Self::from_env(colorterm.as_deref(), term.as_deref())
+    }
+
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            Self::TrueColor
+        } else if term.map_or(false, |t| t.contains("256color")) {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// Map the color to the nearest color which the terminal supports. Named ANSI colors and [`Color::Reset`] are
+    /// returned as they are.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::ColorSupport;
+    ///
+    /// assert_eq!(ColorSupport::Ansi256.map(Color::Rgb(0, 0, 255)), Color::Indexed(21));
+    /// assert_eq!(ColorSupport::Ansi16.map(Color::Rgb(250, 10, 10)), Color::LightRed);
+    /// assert_eq!(ColorSupport::Ansi16.map(Color::Indexed(1)), Color::Red);
+    /// assert_eq!(ColorSupport::TrueColor.map(Color::Rgb(1, 2, 3)), Color::Rgb(1, 2, 3));
+    /// ```
+    pub fn map(self, color: Color) -> Color {
+        match (self, color) {
+            (Self::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_ansi256((r, g, b))),
+            (Self::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi16((r, g, b)),
+            (Self::Ansi16, Color::Indexed(i)) if i < 16 => ANSI16[i as usize].0,
+            (Self::Ansi16, Color::Indexed(i)) => nearest_ansi16(indexed_rgb(i)),
+            _ => color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_to_ansi256() {
+        let s = ColorSupport::Ansi256;
+        assert_eq!(s.map(Color::Rgb(0, 0, 0)), Color::Indexed(16));
+        assert_eq!(s.map(Color::Rgb(255, 255, 255)), Color::Indexed(231));
+        assert_eq!(s.map(Color::Rgb(0x5f, 0x87, 0xaf)), Color::Indexed(67));
+        // Grays between the cube levels use the grayscale ramp
+        assert_eq!(s.map(Color::Rgb(0x3a, 0x3a, 0x3a)), Color::Indexed(237));
+        assert_eq!(s.map(Color::Indexed(100)), Color::Indexed(100));
+        assert_eq!(s.map(Color::Cyan), Color::Cyan);
+    }
+
+    #[test]
+    fn map_to_ansi16() {
+        let s = ColorSupport::Ansi16;
+        assert_eq!(s.map(Color::Rgb(10, 10, 10)), Color::Black);
+        assert_eq!(s.map(Color::Rgb(0, 180, 0)), Color::Green);
+        assert_eq!(s.map(Color::Rgb(0x80, 0x80, 0x80)), Color::DarkGray);
+        assert_eq!(s.map(Color::Indexed(9)), Color::LightRed);
+        assert_eq!(s.map(Color::Indexed(231)), Color::White);
+        assert_eq!(s.map(Color::Indexed(233)), Color::Black);
+        assert_eq!(s.map(Color::Indexed(238)), Color::DarkGray);
+        assert_eq!(s.map(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn from_env() {
+        assert_eq!(ColorSupport::from_env(Some("truecolor"), Some("xterm")), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::from_env(None, Some("xterm-256color")), ColorSupport::Ansi256);
+        assert_eq!(ColorSupport::from_env(None, Some("vt100")), ColorSupport::Ansi16);
+        assert_eq!(ColorSupport::from_env(None, None), ColorSupport::Ansi16);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::cursor::CursorMove;
+use crate::scroll::{Placement, Scrolling};
+use crate::textarea::TextArea;
+
+/// Editor commands which can be run on a textarea by name. This is useful for building a command palette over the
+/// textarea. See also: [`TextArea::commands`] and [`TextArea::run_command`]
+///
+/// This type is marked as `#[non_exhaustive]` since more commands may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    InsertNewline,
+    InsertTab,
+    DeleteChar,
+    DeleteNextChar,
+    DeleteWord,
+    DeleteNextWord,
+    DeleteLineByEnd,
+    DeleteLineByHead,
+    MoveForward,
+    MoveBack,
+    MoveUp,
+    MoveDown,
+    MoveHead,
+    MoveEnd,
+    MoveTop,
+    MoveBottom,
+    MoveWordForward,
+    MoveWordBack,
+    MoveParagraphForward,
+    MoveParagraphBack,
+    MoveParagraphEnd,
+    MoveParagraphStart,
+    ScrollPageDown,
+    ScrollPageUp,
+    ScrollHalfPageDown,
+    ScrollHalfPageUp,
+    RecenterCenter,
+    RecenterTop,
+    RecenterBottom,
+    StartSelection,
+    CancelSelection,
+    SelectAll,
+    SelectParagraph,
+    Copy,
+    Cut,
+    Paste,
+    DuplicateLine,
+    DuplicateSelection,
+    Undo,
+    Redo,
+    GotoLastChange,
+    GotoNextChange,
+    StartLink,
+    InsertLink,
+    LinkifyUrl,
+}
+
+/// Name and description of a [`Command`], returned from [`TextArea::commands`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommandInfo {
+    /// Stable identifier of the command such as `"delete_word"`. Pass it to [`TextArea::run_command`].
+    pub id: &'static str,
+    /// Short human readable description of the command.
+    pub description: &'static str,
+    /// The command itself.
+    pub command: Command,
+}
+
+const COMMANDS: &[(Command, &str, &str)] = &[
+    (Command::InsertNewline, "insert_newline", "Insert a newline at the cursor"),
+    (Command::InsertTab, "insert_tab", "Insert a tab or spaces at the cursor"),
+    (Command::DeleteChar, "delete_char", "Delete the character before the cursor"),
+    (Command::DeleteNextChar, "delete_next_char", "Delete the character at the cursor"),
+    (Command::DeleteWord, "delete_word", "Delete the word before the cursor"),
+    (Command::DeleteNextWord, "delete_next_word", "Delete the word after the cursor"),
+    (Command::DeleteLineByEnd, "delete_line_by_end", "Delete from the cursor to the end of the line"),
+    (Command::DeleteLineByHead, "delete_line_by_head", "Delete from the head of the line to the cursor"),
+    (Command::MoveForward, "move_forward", "Move the cursor forward by one character"),
+    (Command::MoveBack, "move_back", "Move the cursor back by one character"),
+    (Command::MoveUp, "move_up", "Move the cursor up by one line"),
+    (Command::MoveDown, "move_down", "Move the cursor down by one line"),
+    (Command::MoveHead, "move_head", "Move the cursor to the head of the line"),
+    (Command::MoveEnd, "move_end", "Move the cursor to the end of the line"),
+    (Command::MoveTop, "move_top", "Move the cursor to the top of the text"),
+    (Command::MoveBottom, "move_bottom", "Move the cursor to the bottom of the text"),
+    (Command::MoveWordForward, "move_word_forward", "Move the cursor to the next word"),
+    (Command::MoveWordBack, "move_word_back", "Move the cursor to the previous word"),
+    (Command::MoveParagraphForward, "move_paragraph_forward", "Move the cursor to the next paragraph"),
+    (Command::MoveParagraphBack, "move_paragraph_back", "Move the cursor to the previous paragraph"),
+    (Command::MoveParagraphEnd, "move_paragraph_end", "Move the cursor to the end of the paragraph"),
+    (Command::MoveParagraphStart, "move_paragraph_start", "Move the cursor to the start of the paragraph"),
+    (Command::ScrollPageDown, "scroll_page_down", "Scroll down by one page"),
+    (Command::ScrollPageUp, "scroll_page_up", "Scroll up by one page"),
+    (Command::ScrollHalfPageDown, "scroll_half_page_down", "Scroll down by half a page"),
+    (Command::ScrollHalfPageUp, "scroll_half_page_up", "Scroll up by half a page"),
+    (Command::RecenterCenter, "recenter_center", "Scroll the cursor line to the center of the viewport"),
+    (Command::RecenterTop, "recenter_top", "Scroll the cursor line to the top of the viewport"),
+    (Command::RecenterBottom, "recenter_bottom", "Scroll the cursor line to the bottom of the viewport"),
+    (Command::StartSelection, "start_selection", "Start selecting text at the cursor"),
+    (Command::CancelSelection, "cancel_selection", "Cancel the current selection"),
+    (Command::SelectAll, "select_all", "Select the whole text"),
+    (Command::SelectParagraph, "select_paragraph", "Select the paragraph at the cursor"),
+    (Command::Copy, "copy", "Copy the selected text"),
+    (Command::Cut, "cut", "Cut the selected text"),
+    (Command::Paste, "paste", "Paste the yanked text"),
+    (Command::DuplicateLine, "duplicate_line", "Duplicate the current line below it"),
+    (Command::DuplicateSelection, "duplicate_selection", "Duplicate the selected text after it"),
+    (Command::Undo, "undo", "Undo the last edit"),
+    (Command::Redo, "redo", "Redo the last undone edit"),
+    (Command::GotoLastChange, "goto_last_change", "Move to the last edit position, then older ones"),
+    (Command::GotoNextChange, "goto_next_change", "Move to the next newer edit position"),
+    (Command::StartLink, "start_link", "Start a link at the cursor"),
+    (Command::InsertLink, "insert_link", "Insert a link ending at the cursor"),
+    (Command::LinkifyUrl, "linkify_url", "Convert the URL at the cursor into a link"),
+];
+
+impl Command {
+    /// Get the stable identifier of the command.
+    /// ```
+    /// use tuipaz_textarea::Command;
+    ///
+    /// assert_eq!(Command::DeleteWord.id(), "delete_word");
+    /// ```
+    pub fn id(self) -> &'static str {
+        self.info().id
+    }
+
+    /// Get the human readable description of the command.
+    pub fn description(self) -> &'static str {
+        self.info().description
+    }
+
+    /// Find the command by its identifier.
+    /// ```
+    /// use tuipaz_textarea::Command;
+    ///
+    /// assert_eq!(Command::from_id("undo"), Some(Command::Undo));
+    /// assert_eq!(Command::from_id("unknown"), None);
+    /// ```
+    pub fn from_id(id: &str) -> Option<Self> {
+        COMMANDS.iter().find(|(_, i, _)| *i == id).map(|(c, _, _)| *c)
+    }
+
+    /// Get the [`CommandInfo`] of the command.
+    pub fn info(self) -> CommandInfo {
+        let (command, id, description) = *COMMANDS
+            .iter()
+            .find(|(c, _, _)| *c == self)
+            .expect("every command is listed in COMMANDS");
+        CommandInfo {
+            id,
+            description,
+            command,
+        }
+    }
+
+    pub(crate) fn all() -> impl Iterator<Item = CommandInfo> {
+        COMMANDS.iter().map(|(c, _, _)| c.info())
+    }
+
+    // Returns the cursor movement of the command if it only moves the cursor
+    pub(crate) fn cursor_move(self) -> Option<CursorMove> {
+        let m = match self {
+            Command::MoveForward => CursorMove::Forward,
+            Command::MoveBack => CursorMove::Back,
+            Command::MoveUp => CursorMove::Up,
+            Command::MoveDown => CursorMove::Down,
+            Command::MoveHead => CursorMove::Head,
+            Command::MoveEnd => CursorMove::End,
+            Command::MoveTop => CursorMove::Top,
+            Command::MoveBottom => CursorMove::Bottom,
+            Command::MoveWordForward => CursorMove::WordForward,
+            Command::MoveWordBack => CursorMove::WordBack,
+            Command::MoveParagraphForward => CursorMove::ParagraphForward,
+            Command::MoveParagraphBack => CursorMove::ParagraphBack,
+            Command::MoveParagraphEnd => CursorMove::ParagraphEnd,
+            Command::MoveParagraphStart => CursorMove::ParagraphStart,
+            _ => return None,
+        };
+        Some(m)
+    }
+
+    // Returns if the command modified the text or not
+    pub(crate) fn run(self, textarea: &mut TextArea<'_>) -> bool {
+        let m = match self {
+            Command::InsertNewline => {
+                textarea.insert_newline();
+                return true;
+            }
+            Command::InsertTab => return textarea.insert_tab(),
+            Command::DeleteChar => return textarea.delete_char(),
+            Command::DeleteNextChar => return textarea.delete_next_char(),
+            Command::DeleteWord => return textarea.delete_word(),
+            Command::DeleteNextWord => return textarea.delete_next_word(),
+            Command::DeleteLineByEnd => return textarea.delete_line_by_end(),
+            Command::DeleteLineByHead => return textarea.delete_line_by_head(),
+            Command::ScrollPageDown => {
+                textarea.scroll(Scrolling::PageDown);
+                return false;
+            }
+            Command::ScrollPageUp => {
+                textarea.scroll(Scrolling::PageUp);
+                return false;
+            }
+            Command::ScrollHalfPageDown => {
+                textarea.scroll(Scrolling::HalfPageDown);
+                return false;
+            }
+            Command::ScrollHalfPageUp => {
+                textarea.scroll(Scrolling::HalfPageUp);
+                return false;
+            }
+            Command::RecenterCenter => {
+                textarea.recenter_viewport(Placement::Center);
+                return false;
+            }
+            Command::RecenterTop => {
+                textarea.recenter_viewport(Placement::Top);
+                return false;
+            }
+            Command::RecenterBottom => {
+                textarea.recenter_viewport(Placement::Bottom);
+                return false;
+            }
+            Command::StartSelection => {
+                textarea.start_selection();
+                return false;
+            }
+            Command::CancelSelection => {
+                textarea.cancel_selection();
+                return false;
+            }
+            Command::SelectAll => {
+                textarea.select_all();
+                return false;
+            }
+            Command::SelectParagraph => {
+                textarea.select_paragraph();
+                return false;
+            }
+            Command::Copy => {
+                textarea.copy();
+                return false;
+            }
+            Command::Cut => return textarea.cut(),
+            Command::Paste => return textarea.paste(),
+            Command::DuplicateLine => {
+                textarea.duplicate_line();
+                return true;
+            }
+            Command::DuplicateSelection => {
+                textarea.duplicate_selection();
+                return true;
+            }
+            Command::Undo => return textarea.undo(),
+            Command::Redo => return textarea.redo(),
+            Command::GotoLastChange => {
+                textarea.goto_last_change();
+                return false;
+            }
+            Command::GotoNextChange => {
+                textarea.goto_next_change();
+                return false;
+            }
+            Command::StartLink => {
+                textarea.init_link();
+                return false;
+            }
+            Command::InsertLink => {
+                textarea.insert_link();
+                return false;
+            }
+            Command::LinkifyUrl => {
+                textarea.linkify_url(textarea.cursor());
+                return false;
+            }
+            command => command
+                .cursor_move()
+                .expect("other commands move the cursor"),
+        };
+        textarea.move_cursor(m);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_unique() {
+        let mut ids = COMMANDS.iter().map(|(_, id, _)| *id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), COMMANDS.len());
+        for (c, id, _) in COMMANDS {
+            assert_eq!(Command::from_id(id), Some(*c));
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::outline::parse_heading;
+use std::ops::Range;
+
+// Find `[label](target)` and `[[note]]` links. The brackets and the target are concealed and the label is shown.
+fn find_link_markup(line: &str, ranges: &mut Vec<Range<usize>>) {
+    let mut i = 0;
+    while let Some(start) = line[i..].find('[').map(|s| i + s) {
+        let rest = &line[start..];
+        if let Some(inner) = rest.strip_prefix("[[") {
+            if let Some(len) = inner.find("]]").filter(|l| *l > 0) {
+                let close = start + 2 + len;
+                ranges.push(start..start + 2);
+                ranges.push(close..close + 2);
+                i = close + 2;
+                continue;
+            }
+        } else if let Some(len) = rest[1..].find(']').filter(|l| *l > 0) {
+            let close = start + 1 + len;
+            if line[close..].starts_with("](") {
+                if let Some(paren) = line[close + 2..].find(')') {
+                    let end = close + 2 + paren + 1;
+                    ranges.push(start..start + 1);
+                    ranges.push(close..end);
+                    i = end;
+                    continue;
+                }
+            }
+        }
+        i = start + 1;
+    }
+}
+
+// Find pairs of `**` around non-empty text
+fn find_emphasis_markup(line: &str, ranges: &mut Vec<Range<usize>>) {
+    let mut i = 0;
+    while let Some(open) = line[i..].find("**").map(|s| i + s) {
+        let close = match line[open + 2..].find("**") {
+            Some(0) => {
+                i = open + 2;
+                continue;
+            }
+            Some(len) => open + 2 + len,
+            None => break,
+        };
+        ranges.push(open..open + 2);
+        ranges.push(close..close + 2);
+        i = close + 2;
+    }
+}
+
+/// Find the byte ranges of markup characters in the line which are hidden in conceal mode: `#`s of headings and the
+/// following space, brackets and targets of `[label](target)` links, brackets of `[[note]]` links and `**` around
+/// emphasized text. The ranges are sorted and do not overlap.
+pub(crate) fn find_concealed(line: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    if let Some((level, _)) = parse_heading(line) {
+        let level = level as usize;
+        let end = if line[level..].starts_with(' ') {
+            level + 1
+        } else {
+            level
+        };
+        ranges.push(0..end);
+    }
+    find_link_markup(line, &mut ranges);
+    find_emphasis_markup(line, &mut ranges);
+
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<Range<usize>> = vec![];
+    for r in ranges {
+        match merged.last_mut() {
+            Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+            _ => merged.push(r),
+        }
+    }
+    merged
+}
+
+/// Convert the column in characters of the line into the column in the rendered line where the concealed characters
+/// are hidden. A column in a concealed range is moved to the next visible character.
+pub(crate) fn display_col(line: &str, col: usize, concealed: &[Range<usize>]) -> usize {
+    line.char_indices()
+        .take(col)
+        .filter(|(i, _)| !concealed.iter().any(|r| r.contains(i)))
+        .count()
+}
+
+/// Convert the column in the rendered line where the concealed characters are hidden into the column in characters
+/// of the line. This is the inverse of [`display_col`] for visible characters.
+pub(crate) fn logical_col(line: &str, display_col: usize, concealed: &[Range<usize>]) -> usize {
+    let mut visible = 0;
+    for (col, (i, _)) in line.char_indices().enumerate() {
+        if concealed.iter().any(|r| r.contains(&i)) {
+            continue;
+        }
+        if visible == display_col {
+            return col;
+        }
+        visible += 1;
+    }
+    line.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visible(line: &str) -> String {
+        let concealed = find_concealed(line);
+        line.char_indices()
+            .filter(|(i, _)| !concealed.iter().any(|r| r.contains(i)))
+            .map(|(_, c)| c)
+            .collect()
+    }
+
+    #[test]
+    fn conceal_markup() {
+        for (line, want) in [
+            ("## Title", "Title"),
+            ("#tag", "#tag"),
+            (
+                "see [docs](https://docs.rs) and [[Note]]",
+                "see docs and Note",
+            ),
+            ("**bold** and **", "bold and **"),
+            ("[not a link] [] [x](", "[not a link] [] [x]("),
+            ("# **[a](b)**", "a"),
+            ("****", "****"),
+        ] {
+            assert_eq!(visible(line), want, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn map_columns() {
+        let line = "a [b](c) d";
+        let concealed = find_concealed(line);
+        assert_eq!(display_col(line, 0, &concealed), 0);
+        assert_eq!(display_col(line, 3, &concealed), 2);
+        assert_eq!(display_col(line, 9, &concealed), 4);
+        assert_eq!(display_col(line, 10, &concealed), 5);
+        assert_eq!(logical_col(line, 2, &concealed), 3);
+        assert_eq!(logical_col(line, 4, &concealed), 9);
+        assert_eq!(logical_col(line, 5, &concealed), 10);
+    }
+}
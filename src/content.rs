@@ -0,0 +1,257 @@
+use crate::group::Editor;
+use crate::input::{Input, InputResult};
+use crate::ratatui::buffer::Buffer;
+use crate::ratatui::layout::Rect;
+use crate::ratatui::style::Style;
+use crate::ratatui::widgets::{Block, Widget};
+use crate::textarea::TextArea;
+use crate::textinput::TextInput;
+
+/// Common operations of the editor widgets [`TextArea`] and [`TextInput`]. Host code handling "the focused editor" can
+/// be generic over this trait instead of matching on each widget type.
+/// ```
+/// use ratatui::style::Color;
+/// use tuipaz_textarea::{ContentArea, Input, Key, TextArea, TextInput};
+///
+/// fn type_char(editor: &mut impl ContentArea<'static>, c: char) {
+///     editor.input(Input { key: Key::Char(c), ..Input::default() });
+/// }
+///
+/// let mut textarea = TextArea::default();
+/// let mut input = TextInput::new(String::new(), 40, Color::White, String::new());
+/// type_char(&mut textarea, 'a');
+/// type_char(&mut input, 'a');
+/// assert_eq!(textarea.content(), input.content());
+/// ```
+pub trait ContentArea<'a> {
+    /// Get the cursor position `(row, col)`.
+    fn cursor(&self) -> (usize, usize);
+
+    /// Handle a key input with the default key mappings. Returns `true` when the text was modified.
+    fn input(&mut self, input: Input) -> bool;
+
+    /// Handle a key input with the default key mappings and return what happened.
+    fn input_result(&mut self, input: Input) -> InputResult;
+
+    /// Get the text. Lines are joined with `\n`.
+    fn content(&self) -> String;
+
+    /// Set the placeholder text shown when the text is empty.
+    fn set_placeholder_text(&mut self, placeholder: &str);
+
+    /// Get the placeholder text.
+    fn placeholder_text(&self) -> &str;
+
+    /// Set the block around the widget.
+    fn set_block(&mut self, block: Block<'a>);
+
+    /// Get the block around the widget.
+    fn block(&self) -> Option<&Block<'a>>;
+
+    /// Set the style of the cursor.
+    fn set_cursor_style(&mut self, style: Style);
+
+    /// Render the widget in the area of the buffer.
+    fn render(&'a self, area: Rect, buf: &mut Buffer);
+}
+
+impl<'a> ContentArea<'a> for TextArea<'a> {
+    fn cursor(&self) -> (usize, usize) {
+        TextArea::cursor(self)
+    }
+
+    fn input(&mut self, input: Input) -> bool {
+        TextArea::input(self, input)
+    }
+
+    fn input_result(&mut self, input: Input) -> InputResult {
+        TextArea::input_result(self, input)
+    }
+
+    fn content(&self) -> String {
+        self.unsegmented_lines().join("\n")
+    }
+
+    fn set_placeholder_text(&mut self, placeholder: &str) {
+        TextArea::set_placeholder_text(self, placeholder);
+    }
+
+    fn placeholder_text(&self) -> &str {
+        TextArea::placeholder_text(self)
+    }
+
+    fn set_block(&mut self, block: Block<'a>) {
+        TextArea::set_block(self, block);
+    }
+
+    fn block(&self) -> Option<&Block<'a>> {
+        TextArea::block(self)
+    }
+
+    fn set_cursor_style(&mut self, style: Style) {
+        TextArea::set_cursor_style(self, style);
+    }
+
+    fn render(&'a self, area: Rect, buf: &mut Buffer) {
+        self.widget().render(area, buf);
+    }
+}
+
+impl<'a> ContentArea<'a> for TextInput<'a> {
+    fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    fn input(&mut self, input: Input) -> bool {
+        TextInput::input(self, input)
+    }
+
+    fn input_result(&mut self, input: Input) -> InputResult {
+        TextInput::input_result(self, input)
+    }
+
+    fn content(&self) -> String {
+        self.text().to_string()
+    }
+
+    fn set_placeholder_text(&mut self, placeholder: &str) {
+        TextInput::set_placeholder_text(self, placeholder);
+    }
+
+    fn placeholder_text(&self) -> &str {
+        &self.placeholder
+    }
+
+    fn set_block(&mut self, block: Block<'a>) {
+        TextInput::set_block(self, block);
+    }
+
+    fn block(&self) -> Option<&Block<'a>> {
+        TextInput::block(self)
+    }
+
+    fn set_cursor_style(&mut self, style: Style) {
+        TextInput::set_cursor_style(self, style);
+    }
+
+    fn render(&'a self, area: Rect, buf: &mut Buffer) {
+        self.widget().render(area, buf);
+    }
+}
+
+impl<'a> ContentArea<'a> for Editor<'a> {
+    fn cursor(&self) -> (usize, usize) {
+        match self {
+            Self::TextArea(textarea) => textarea.cursor(),
+            Self::TextInput(input) => input.cursor,
+        }
+    }
+
+    fn input(&mut self, input: Input) -> bool {
+        Editor::input(self, input)
+    }
+
+    fn input_result(&mut self, input: Input) -> InputResult {
+        match self {
+            Self::TextArea(textarea) => textarea.input_result(input),
+            Self::TextInput(input_) => input_.input_result(input),
+        }
+    }
+
+    fn content(&self) -> String {
+        self.text()
+    }
+
+    fn set_placeholder_text(&mut self, placeholder: &str) {
+        match self {
+            Self::TextArea(textarea) => textarea.set_placeholder_text(placeholder),
+            Self::TextInput(input) => input.set_placeholder_text(placeholder),
+        }
+    }
+
+    fn placeholder_text(&self) -> &str {
+        match self {
+            Self::TextArea(textarea) => textarea.placeholder_text(),
+            Self::TextInput(input) => &input.placeholder,
+        }
+    }
+
+    fn set_block(&mut self, block: Block<'a>) {
+        match self {
+            Self::TextArea(textarea) => textarea.set_block(block),
+            Self::TextInput(input) => input.set_block(block),
+        }
+    }
+
+    fn block(&self) -> Option<&Block<'a>> {
+        match self {
+            Self::TextArea(textarea) => textarea.block(),
+            Self::TextInput(input) => input.block(),
+        }
+    }
+
+    fn set_cursor_style(&mut self, style: Style) {
+        match self {
+            Self::TextArea(textarea) => textarea.set_cursor_style(style),
+            Self::TextInput(input) => input.set_cursor_style(style),
+        }
+    }
+
+    fn render(&'a self, area: Rect, buf: &mut Buffer) {
+        self.widget().render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Key;
+    use crate::ratatui::style::Color;
+
+    fn type_str<'a>(editor: &mut impl ContentArea<'a>, s: &str) {
+        for c in s.chars() {
+            editor.input(Input {
+                key: Key::Char(c),
+                ..Input::default()
+            });
+        }
+    }
+
+    #[test]
+    fn generic_editors() {
+        let mut textarea = TextArea::default();
+        let mut input = TextInput::new(String::new(), 40, Color::White, String::new());
+        let mut editor = Editor::from(TextArea::default());
+        type_str(&mut textarea, "abc");
+        type_str(&mut input, "abc");
+        type_str(&mut editor, "abc");
+        assert_eq!(textarea.content(), "abc");
+        assert_eq!(input.content(), "abc");
+        assert_eq!(editor.content(), "abc");
+        assert_eq!(ContentArea::cursor(&input), (0, 3));
+        assert_eq!(ContentArea::cursor(&editor), (0, 3));
+
+        ContentArea::set_placeholder_text(&mut input, "hint");
+        assert_eq!(ContentArea::placeholder_text(&input), "hint");
+        let enter = Input {
+            key: Key::Enter,
+            ..Input::default()
+        };
+        assert_eq!(
+            ContentArea::input_result(&mut input, enter),
+            InputResult::Submitted
+        );
+    }
+
+    #[test]
+    fn content_keeps_wrapped_lines() {
+        let mut textarea = TextArea::default();
+        textarea.set_max_col(9);
+        type_str(&mut textarea, "aa bb cc dd");
+        assert_eq!(textarea.lines().len(), 2);
+        assert_eq!(textarea.content(), textarea.lines().join("\n"));
+        let editor = Editor::from(textarea);
+        assert_eq!(editor.content(), editor.text());
+        assert_eq!(editor.text().lines().count(), 2);
+    }
+}
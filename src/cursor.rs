@@ -1,5 +1,7 @@
+use crate::textobject::matching_pair;
 use crate::widget::Viewport;
 use crate::word::{find_word_start_backward, find_word_start_forward};
+use crate::wrap::is_wrapped;
 #[cfg(feature = "arbitrary")]
 use arbitrary::Arbitrary;
 use std::cmp;
@@ -8,7 +10,7 @@ use std::cmp;
 ///
 /// This type is marked as `#[non_exhaustive]` since more variations may be supported in the future.
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
 pub enum CursorMove {
     /// Move cursor forward by one character. When the cursor is at the end of line, it moves to the head of next line.
@@ -174,6 +176,33 @@ pub enum CursorMove {
     /// assert_eq!(textarea.cursor(), (0, 0));
     /// ```
     ParagraphBack,
+    /// Move cursor to the last character of the current paragraph. When the cursor is already there or is between
+    /// paragraphs, it moves to the last character of the next paragraph.
+    /// ```
+    /// use tui_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["aaa", "bb", "", "ccc"]);
+    ///
+    /// textarea.move_cursor(CursorMove::ParagraphEnd);
+    /// assert_eq!(textarea.cursor(), (1, 1));
+    /// textarea.move_cursor(CursorMove::ParagraphEnd);
+    /// assert_eq!(textarea.cursor(), (3, 2));
+    /// ```
+    ParagraphEnd,
+    /// Move cursor to the first character of the current paragraph. When the cursor is already there or is between
+    /// paragraphs, it moves to the first character of the previous paragraph.
+    /// ```
+    /// use tui_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["aaa", "", "bbb", "ccc"]);
+    ///
+    /// textarea.move_cursor(CursorMove::Bottom);
+    /// textarea.move_cursor(CursorMove::ParagraphStart);
+    /// assert_eq!(textarea.cursor(), (2, 0));
+    /// textarea.move_cursor(CursorMove::ParagraphStart);
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    /// ```
+    ParagraphStart,
     /// Move cursor to (row, col) position. When the position points outside the text, the cursor position is made fit
     /// within the text. Note that row and col are 0-based. (0, 0) means the first character of the first line.
     ///
@@ -223,6 +252,106 @@ pub enum CursorMove {
     /// assert_eq!(textarea.cursor(), (7, 0));
     /// ```
     InViewport,
+    /// Move cursor up to the first row of the previous logical line. A logical line is a sequence of rows made by hard
+    /// wrapping one line at the max column. When the text is not wrapped, this is the same as [`CursorMove::Up`].
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tui_textarea::{CursorMove, TextArea, TextAreaTheme};
+    ///
+    /// // The first line is wrapped at the max column
+    /// let lines = ["aaa bbb", "ccc", "ddd"].map(String::from).to_vec();
+    /// let mut textarea = TextArea::new(lines, HashMap::new(), 9, TextAreaTheme::default());
+    ///
+    /// textarea.move_cursor(CursorMove::Bottom);
+    /// textarea.move_cursor(CursorMove::LogicalLineUp);
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    /// ```
+    LogicalLineUp,
+    /// Move cursor down to the first row of the next logical line. See [`CursorMove::LogicalLineUp`] for logical
+    /// lines.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tui_textarea::{CursorMove, TextArea, TextAreaTheme};
+    ///
+    /// let lines = ["aaa bbb", "ccc", "ddd"].map(String::from).to_vec();
+    /// let mut textarea = TextArea::new(lines, HashMap::new(), 9, TextAreaTheme::default());
+    ///
+    /// textarea.move_cursor(CursorMove::LogicalLineDown);
+    /// assert_eq!(textarea.cursor(), (2, 0));
+    /// ```
+    LogicalLineDown,
+    /// Move cursor to the column in the current line. Note that the column is 0-based. When the column is after the
+    /// end of the line, the cursor moves to the end of the line.
+    /// ```
+    /// use tui_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["abcd"]);
+    ///
+    /// textarea.move_cursor(CursorMove::Column(2));
+    /// assert_eq!(textarea.cursor(), (0, 2));
+    /// textarea.move_cursor(CursorMove::Column(10));
+    /// assert_eq!(textarea.cursor(), (0, 4));
+    /// ```
+    Column(u16),
+    /// Move cursor to the bracket matching the bracket at the cursor. When the cursor is not on a bracket, the first
+    /// bracket after the cursor in the line is used. Brackets are `()`, `[]`, `{}` and `<>`, and the pair can span
+    /// multiple lines. The cursor does not move when no matching bracket is found.
+    /// ```
+    /// use tui_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["if (a(b)) {", "}"]);
+    ///
+    /// textarea.move_cursor(CursorMove::MatchingPair);
+    /// assert_eq!(textarea.cursor(), (0, 8));
+    /// textarea.move_cursor(CursorMove::MatchingPair);
+    /// assert_eq!(textarea.cursor(), (0, 3));
+    /// textarea.move_cursor(CursorMove::Column(10));
+    /// textarea.move_cursor(CursorMove::MatchingPair);
+    /// assert_eq!(textarea.cursor(), (1, 0));
+    /// ```
+    MatchingPair,
+    /// Move cursor to the first non-whitespace character of the current line. When the line only has whitespaces,
+    /// the cursor moves to the end of the line.
+    /// ```
+    /// use tui_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["    foo"]);
+    ///
+    /// textarea.move_cursor(CursorMove::End);
+    /// textarea.move_cursor(CursorMove::FirstNonBlank);
+    /// assert_eq!(textarea.cursor(), (0, 4));
+    /// ```
+    FirstNonBlank,
+}
+
+/// How `Up` and `Down` keys move the cursor in hard wrapped text. See [`TextArea::set_line_movement`].
+///
+/// [`TextArea::set_line_movement`]: crate::TextArea::set_line_movement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineMovement {
+    /// Move by rows of the text, which are the visual lines. This is the default.
+    Visual,
+    /// Move by logical lines, skipping rows made by hard wrapping. See [`CursorMove::LogicalLineUp`].
+    Logical,
+}
+
+impl Default for LineMovement {
+    fn default() -> Self {
+        Self::Visual
+    }
+}
+
+// Find the range of rows of the logical line containing the row
+fn logical_line(row: usize, lines: &[String], width: usize, tab_len: u8) -> (usize, usize) {
+    let mut start = row;
+    while start > 0 && is_wrapped(&lines[start - 1], &lines[start], width, tab_len) {
+        start -= 1;
+    }
+    let mut end = row;
+    while end + 1 < lines.len() && is_wrapped(&lines[end], &lines[end + 1], width, tab_len) {
+        end += 1;
+    }
+    (start, end)
 }
 
 impl CursorMove {
@@ -231,6 +360,7 @@ impl CursorMove {
         (row, col): (usize, usize),
         lines: &[String],
         viewport: &Viewport,
+        (wrap_width, tab_len): (usize, u8),
     ) -> Option<(usize, usize)> {
         use CursorMove::*;
 
@@ -303,6 +433,28 @@ impl CursorMove {
                 }
                 Some((0, fit_col(col, &lines[0])))
             }
+            ParagraphEnd => {
+                let last_col = |row: usize| lines[row].chars().count().saturating_sub(1);
+                let is_last = |row: usize| row + 1 == lines.len() || lines[row + 1].is_empty();
+                let mut row = row;
+                if lines[row].is_empty() || is_last(row) && col >= last_col(row) {
+                    row = (row + 1..lines.len()).find(|r| !lines[*r].is_empty())?;
+                }
+                while !is_last(row) {
+                    row += 1;
+                }
+                Some((row, last_col(row)))
+            }
+            ParagraphStart => {
+                let mut row = row;
+                if lines[row].is_empty() || col == 0 && (row == 0 || lines[row - 1].is_empty()) {
+                    row = (0..row).rev().find(|r| !lines[*r].is_empty())?;
+                }
+                while row > 0 && !lines[row - 1].is_empty() {
+                    row -= 1;
+                }
+                Some((row, 0))
+            }
             Jump(row, col) => {
                 let row = cmp::min(*row as usize, lines.len() - 1);
                 let col = fit_col(*col as usize, &lines[row]);
@@ -318,6 +470,23 @@ impl CursorMove {
 
                 Some((row, col))
             }
+            LogicalLineUp => {
+                let (start, _) = logical_line(row, lines, wrap_width, tab_len);
+                let (row, _) = logical_line(start.checked_sub(1)?, lines, wrap_width, tab_len);
+                Some((row, fit_col(col, &lines[row])))
+            }
+            LogicalLineDown => {
+                let (_, end) = logical_line(row, lines, wrap_width, tab_len);
+                let row = end + 1;
+                Some((row, fit_col(col, lines.get(row)?)))
+            }
+            Column(col) => Some((row, fit_col(*col as usize, &lines[row]))),
+            MatchingPair => matching_pair(lines, (row, col)),
+            FirstNonBlank => {
+                let line = &lines[row];
+                let col = line.chars().position(|c| !c.is_whitespace());
+                Some((row, col.unwrap_or_else(|| line.chars().count())))
+            }
         }
     }
 }
@@ -0,0 +1,389 @@
+use crate::group::{Editor, EditorGroup, EditorId};
+use crate::input::{Input, InputKind, InputResult, Key};
+use crate::ratatui::buffer::Buffer;
+use crate::ratatui::layout::Rect;
+use crate::ratatui::style::{Color, Style};
+use crate::ratatui::text::{Line, Span};
+use crate::ratatui::widgets::{Paragraph, Widget};
+use crate::textarea::TextArea;
+use crate::textinput::TextInput;
+use std::fmt;
+
+/// A function validating the text of a form field. It returns an error message when the text is invalid.
+pub type Validator = fn(&str) -> Result<(), String>;
+
+/// A validation error of a form field, returned from [`Form::validate`] and [`Form::submit`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// Name of the field.
+    pub name: String,
+    /// Error message returned from the validator.
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+/// Values of all fields of a form, returned from [`Form::submit`] when all fields are valid.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormSubmission {
+    values: Vec<(String, String)>,
+}
+
+impl FormSubmission {
+    /// Get the value of the field with the name. Lines of a textarea field are joined with `\n`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Iterate over `(name, value)` pairs of the fields in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    id: EditorId,
+    name: String,
+    label: String,
+    validator: Option<Validator>,
+    error: Option<String>,
+}
+
+/// A form of labeled fields. Each field is a [`TextInput`] or a [`TextArea`] with a unique name and an optional
+/// [`Validator`].
+///
+/// `Tab` and `Shift+Tab` move the focus between fields. `Enter` in a text input field moves the focus to the next
+/// field, or returns [`InputResult::Submitted`] from [`Form::input`] at the last field. Call [`Form::submit`] then to
+/// validate all fields and collect their values.
+/// ```
+/// use ratatui::style::Color;
+/// use tuipaz_textarea::{Form, Input, InputKind, InputResult, Key, TextInput};
+///
+/// let mut form = Form::new();
+/// let text_input = || TextInput::new(String::new(), 40, Color::White, String::new());
+/// form.add_input("name", "Name", text_input());
+/// form.add_input("age", "Age", text_input());
+/// form.set_validator("age", |s| s.parse::<u8>().map(|_| ()).map_err(|e| e.to_string()));
+///
+/// let key = |key| Input { key, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+/// form.input(key(Key::Char('A')));
+/// form.input(key(Key::Enter));
+/// form.input(key(Key::Char('7')));
+/// assert_eq!(form.input(key(Key::Enter)), InputResult::Submitted);
+///
+/// let submission = form.submit().unwrap();
+/// assert_eq!(submission.get("name"), Some("A"));
+/// assert_eq!(submission.get("age"), Some("7"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Form<'a> {
+    group: EditorGroup<'a>,
+    fields: Vec<Field>,
+    label_style: Style,
+    error_style: Style,
+}
+
+impl<'a> Default for Form<'a> {
+    fn default() -> Self {
+        Self {
+            group: EditorGroup::new(),
+            fields: vec![],
+            label_style: Style::default(),
+            error_style: Style::default().fg(Color::Red),
+        }
+    }
+}
+
+impl<'a> Form<'a> {
+    /// Create an empty form.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a single-line field at the end of the form. The name identifies the field in [`FormSubmission`].
+    pub fn add_input(
+        &mut self,
+        name: impl Into<String>,
+        label: impl Into<String>,
+        input: TextInput<'a>,
+    ) -> EditorId {
+        self.add_field(name.into(), label.into(), input.into())
+    }
+
+    /// Add a multi-line field at the end of the form. The name identifies the field in [`FormSubmission`].
+    pub fn add_textarea(
+        &mut self,
+        name: impl Into<String>,
+        label: impl Into<String>,
+        textarea: TextArea<'a>,
+    ) -> EditorId {
+        self.add_field(name.into(), label.into(), textarea.into())
+    }
+
+    fn add_field(&mut self, name: String, label: String, editor: Editor<'a>) -> EditorId {
+        let id = self.group.push(editor);
+        self.fields.push(Field {
+            id,
+            name,
+            label,
+            validator: None,
+            error: None,
+        });
+        id
+    }
+
+    /// Set the validator of the field with the name. Returns `false` when no field has the name.
+    pub fn set_validator(&mut self, name: &str, validator: Validator) -> bool {
+        match self.fields.iter_mut().find(|f| f.name == name) {
+            Some(field) => {
+                field.validator = Some(validator);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the editor of the field with the name.
+    pub fn field(&self, name: &str) -> Option<&Editor<'a>> {
+        let id = self.fields.iter().find(|f| f.name == name)?.id;
+        self.group.get(id)
+    }
+
+    /// Get the mutable editor of the field with the name.
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Editor<'a>> {
+        let id = self.fields.iter().find(|f| f.name == name)?.id;
+        self.group.get_mut(id)
+    }
+
+    /// Get the name of the focused field.
+    pub fn focused(&self) -> Option<&str> {
+        let id = self.group.focused()?;
+        self.fields
+            .iter()
+            .find(|f| f.id == id)
+            .map(|f| f.name.as_str())
+    }
+
+    /// Move the focus to the field with the name. Returns `false` when no field has the name.
+    pub fn focus(&mut self, name: &str) -> bool {
+        match self.fields.iter().find(|f| f.name == name) {
+            Some(field) => self.group.focus(field.id),
+            None => false,
+        }
+    }
+
+    /// Get the group of the field editors to customize focus styles.
+    pub fn group_mut(&mut self) -> &mut EditorGroup<'a> {
+        &mut self.group
+    }
+
+    /// Handle a key input. See [`Form`] for the key mappings. Other inputs are passed to the focused field.
+    pub fn input(&mut self, input: impl Into<Input>) -> InputResult {
+        let input = input.into();
+        if input.kind == InputKind::Release {
+            return InputResult::Unchanged;
+        }
+        let is_last = self.group.focused() == self.fields.last().map(|f| f.id);
+        match self.group.focused_editor_mut() {
+            None => InputResult::Unchanged,
+            Some(Editor::TextArea(textarea)) if !matches!(input.key, Key::Tab | Key::BackTab) => {
+                textarea.input_result(input)
+            }
+            Some(Editor::TextInput(_)) if input.key == Key::Enter => {
+                if is_last {
+                    return InputResult::Submitted;
+                }
+                self.group.focus_next();
+                InputResult::Unchanged
+            }
+            Some(_) => {
+                if self.group.input(input) {
+                    InputResult::Changed
+                } else {
+                    InputResult::Unchanged
+                }
+            }
+        }
+    }
+
+    /// Validate all fields with their validators. The errors are returned in the order of the fields and are also
+    /// rendered under the labels until the next validation.
+    pub fn validate(&mut self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        for field in self.fields.iter_mut() {
+            let text = match self.group.get(field.id) {
+                Some(editor) => editor.text(),
+                None => continue,
+            };
+            field.error = match field.validator {
+                Some(validator) => validator(&text).err(),
+                None => None,
+            };
+            if let Some(message) = &field.error {
+                errors.push(FieldError {
+                    name: field.name.clone(),
+                    message: message.clone(),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Validate all fields and collect their values. When some fields are invalid, all their errors are returned.
+    pub fn submit(&mut self) -> Result<FormSubmission, Vec<FieldError>> {
+        let errors = self.validate();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let values = self
+            .fields
+            .iter()
+            .filter_map(|f| Some((f.name.clone(), self.group.get(f.id)?.text())))
+            .collect();
+        Ok(FormSubmission { values })
+    }
+
+    /// Set the style of the field labels.
+    pub fn set_label_style(&mut self, style: Style) {
+        self.label_style = style;
+    }
+
+    /// Set the style of the validation error messages. The default style is red.
+    pub fn set_error_style(&mut self, style: Style) {
+        self.error_style = style;
+    }
+
+    /// Build a widget to render the form. Each field is rendered under its label. A text input field takes one row
+    /// (three rows with a block) and textarea fields share the remaining height.
+    pub fn widget(&'a self) -> impl Widget + 'a {
+        FormRenderer(self)
+    }
+}
+
+struct FormRenderer<'a>(&'a Form<'a>);
+
+impl<'a> Widget for FormRenderer<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let form = self.0;
+        let fields = form
+            .fields
+            .iter()
+            .filter_map(|f| Some((f, form.group.get(f.id)?)))
+            .collect::<Vec<_>>();
+
+        let input_height = |editor: &Editor<'_>| match editor {
+            Editor::TextInput(input) if input.block().is_some() => Some(3),
+            Editor::TextInput(_) => Some(1),
+            Editor::TextArea(_) => None,
+        };
+        let fixed: u16 = fields
+            .iter()
+            .map(|(_, e)| 1 + input_height(e).unwrap_or(0))
+            .sum();
+        let textareas = fields
+            .iter()
+            .filter(|(_, e)| input_height(e).is_none())
+            .count() as u16;
+        let textarea_height = area
+            .height
+            .saturating_sub(fixed)
+            .checked_div(textareas)
+            .unwrap_or(0);
+
+        let bottom = area.y + area.height;
+        let mut y = area.y;
+        for (field, editor) in fields {
+            if y >= bottom {
+                break;
+            }
+            let mut spans = vec![Span::styled(field.label.as_str(), form.label_style)];
+            if let Some(message) = &field.error {
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(message.as_str(), form.error_style));
+            }
+            let label_area = Rect {
+                y,
+                height: 1,
+                ..area
+            };
+            Paragraph::new(Line::from(spans)).render(label_area, buf);
+            y += 1;
+
+            let height = input_height(editor)
+                .unwrap_or(textarea_height)
+                .min(bottom.saturating_sub(y));
+            editor.widget().render(Rect { y, height, ..area }, buf);
+            y += height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: Key) -> Input {
+        Input {
+            key,
+            ctrl: false,
+            alt: false,
+            shift: false,
+            kind: InputKind::Press,
+        }
+    }
+
+    fn not_empty(s: &str) -> Result<(), String> {
+        if s.is_empty() {
+            Err("required".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn navigation_and_validation() {
+        let mut form = Form::new();
+        form.add_input(
+            "title",
+            "Title",
+            TextInput::new(String::new(), 20, Color::White, String::new()),
+        );
+        form.add_textarea("body", "Body", TextArea::default());
+        assert!(form.set_validator("title", not_empty));
+        assert!(form.set_validator("body", not_empty));
+        assert!(!form.set_validator("unknown", not_empty));
+
+        // Enter moves to the next field from the text input
+        assert_eq!(form.input(key(Key::Enter)), InputResult::Unchanged);
+        assert_eq!(form.focused(), Some("body"));
+        // Enter in the textarea inserts a newline
+        assert_eq!(form.input(key(Key::Enter)), InputResult::Changed);
+        assert_eq!(form.input(key(Key::Char('x'))), InputResult::Changed);
+
+        let errors = form.submit().unwrap_err();
+        assert_eq!(
+            errors,
+            [FieldError {
+                name: "title".to_string(),
+                message: "required".to_string(),
+            }],
+        );
+
+        form.input(key(Key::Tab));
+        assert_eq!(form.focused(), Some("title"));
+        form.input(key(Key::Char('t')));
+        let submission = form.submit().unwrap();
+        assert_eq!(
+            submission.iter().collect::<Vec<_>>(),
+            [("title", "t"), ("body", "\nx")],
+        );
+    }
+}
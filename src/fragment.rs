@@ -0,0 +1,104 @@
+use crate::links::{Link, LinkKeys};
+use crate::util::Pos;
+use std::collections::{BTreeMap, HashMap};
+
+/// A piece of text with the links in it, which can be moved between textareas. It is extracted by
+/// [`TextArea::extract_selection`](crate::TextArea::extract_selection) and inserted by
+/// [`TextArea::insert_fragment`](crate::TextArea::insert_fragment).
+///
+/// Positions of the links are relative to the start of the fragment. Rows are counted from the first line of the
+/// fragment and columns on the first line are counted from the start of the fragment. IDs of the links are the IDs in
+/// the textarea where the fragment was extracted. New IDs are assigned when the fragment is inserted, and the stable
+/// keys of the links move to the new IDs unless other links in the textarea still have them.
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    /// Lines of the text. There is at least one line.
+    pub lines: Vec<String>,
+    /// Links in the text.
+    pub links: Vec<Link>,
+    /// Stable keys of the links by their IDs in the fragment.
+    pub keys: HashMap<usize, String>,
+}
+
+impl Fragment {
+    /// Get the text of the fragment joined with `\n`.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+/// Extract the text between the positions with the links entirely in it.
+pub(crate) fn extract(
+    lines: &[String],
+    links: &BTreeMap<usize, Link>,
+    link_keys: &LinkKeys,
+    start: &Pos,
+    end: &Pos,
+) -> Fragment {
+    let text = if start.row == end.row {
+        vec![lines[start.row][start.offset..end.offset].to_string()]
+    } else {
+        let mut text = vec![lines[start.row][start.offset..].to_string()];
+        text.extend(lines[start.row + 1..end.row].iter().cloned());
+        text.push(lines[end.row][..end.offset].to_string());
+        text
+    };
+
+    let mut links: Vec<Link> = links
+        .values()
+        .filter(|l| !l.deleted && start.row <= l.row && l.row <= end.row)
+        .filter(|l| l.row != start.row || l.start_col >= start.col)
+        .filter(|l| l.row != end.row || l.end_col < end.col)
+        .map(|l| {
+            let mut link = *l;
+            link.row -= start.row;
+            if l.row == start.row {
+                link.start_col -= start.col;
+                link.end_col -= start.col;
+            }
+            link.edited = false;
+            link
+        })
+        .collect();
+    links.sort_by_key(|l| l.id);
+
+    let keys = links
+        .iter()
+        .filter_map(|l| link_keys.key(l.id).map(|key| (l.id, key.to_string())))
+        .collect();
+
+    Fragment { lines: text, links, keys }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_links_in_range() {
+        let lines = ["a [b] [c]", "[d] e"].map(String::from);
+        let links = BTreeMap::from([
+            (0, Link::new(0, 0, 2, 4)),
+            (1, Link::new(1, 0, 6, 8)),
+            (2, Link::new(2, 1, 0, 2)),
+        ]);
+
+        let mut keys = LinkKeys::default();
+        keys.insert(1, "c".to_string()).unwrap();
+        keys.insert(2, "d".to_string()).unwrap();
+
+        let f = extract(&lines, &links, &keys, &Pos::new(0, 2, 2), &Pos::new(1, 2, 2));
+        assert_eq!(f.lines, ["[b] [c]", "[d"]);
+        let pos: Vec<_> = f
+            .links
+            .iter()
+            .map(|l| (l.id, l.row, l.start_col, l.end_col))
+            .collect();
+        assert_eq!(pos, [(0, 0, 0, 2), (1, 0, 4, 6)]);
+        assert_eq!(f.keys, HashMap::from([(1, "c".to_string())]));
+
+        let f = extract(&lines, &links, &keys, &Pos::new(0, 6, 6), &Pos::new(1, 3, 3));
+        let ids: Vec<_> = f.links.iter().map(|l| l.id).collect();
+        assert_eq!(ids, [1, 2]);
+    }
+}
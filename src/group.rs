@@ -0,0 +1,527 @@
+use crate::input::{Input, InputKind, Key};
+use crate::ratatui::buffer::Buffer;
+use crate::ratatui::layout::Rect;
+use crate::ratatui::style::{Modifier, Style};
+use crate::ratatui::widgets::{Block, Widget};
+use crate::textarea::{TextArea, TextAreaTheme};
+use crate::textinput::TextInput;
+
+/// ID of an editor in an [`EditorGroup`]. It is assigned by [`EditorGroup::push`] and is unique in the group. IDs of
+/// removed editors are not reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EditorId(usize);
+
+impl EditorId {
+    /// Get the ID as an integer.
+    pub fn get(self) -> usize {
+        self.0
+    }
+}
+
+/// An editor widget owned by an [`EditorGroup`].
+#[derive(Clone, Debug)]
+pub enum Editor<'a> {
+    /// Multi-line editor.
+    TextArea(Box<TextArea<'a>>),
+    /// Single-line editor.
+    TextInput(Box<TextInput<'a>>),
+}
+
+impl<'a> From<TextArea<'a>> for Editor<'a> {
+    fn from(textarea: TextArea<'a>) -> Self {
+        Self::TextArea(Box::new(textarea))
+    }
+}
+
+impl<'a> From<TextInput<'a>> for Editor<'a> {
+    fn from(input: TextInput<'a>) -> Self {
+        Self::TextInput(Box::new(input))
+    }
+}
+
+impl<'a> Editor<'a> {
+    /// Handle a key input with the default key mappings of the editor. Returns `true` when the text was modified.
+    pub fn input(&mut self, input: impl Into<Input>) -> bool {
+        match self {
+            Self::TextArea(textarea) => textarea.input(input),
+            Self::TextInput(input_) => input_.input(input),
+        }
+    }
+
+    /// Get the text of the editor. Lines of a textarea are joined with `\n`.
+    pub fn text(&self) -> String {
+        match self {
+            Self::TextArea(textarea) => textarea.unsegmented_lines().join("\n"),
+            Self::TextInput(input) => input.text().to_string(),
+        }
+    }
+
+    /// Get the inner textarea if this editor is a textarea.
+    pub fn as_textarea(&self) -> Option<&TextArea<'a>> {
+        match self {
+            Self::TextArea(textarea) => Some(textarea.as_ref()),
+            Self::TextInput(_) => None,
+        }
+    }
+
+    /// Get the mutable inner textarea if this editor is a textarea.
+    pub fn as_textarea_mut(&mut self) -> Option<&mut TextArea<'a>> {
+        match self {
+            Self::TextArea(textarea) => Some(textarea.as_mut()),
+            Self::TextInput(_) => None,
+        }
+    }
+
+    /// Get the inner text input if this editor is a text input.
+    pub fn as_text_input(&self) -> Option<&TextInput<'a>> {
+        match self {
+            Self::TextArea(_) => None,
+            Self::TextInput(input) => Some(input.as_ref()),
+        }
+    }
+
+    /// Get the mutable inner text input if this editor is a text input.
+    pub fn as_text_input_mut(&mut self) -> Option<&mut TextInput<'a>> {
+        match self {
+            Self::TextArea(_) => None,
+            Self::TextInput(input) => Some(input.as_mut()),
+        }
+    }
+
+    /// Apply the colors of the theme to the editor.
+    pub fn set_theme(&mut self, theme: TextAreaTheme) {
+        match self {
+            Self::TextArea(textarea) => textarea.set_theme(theme),
+            Self::TextInput(input) => input.set_theme(theme),
+        }
+    }
+
+    /// Build a widget to render the editor.
+    pub fn widget(&'a self) -> impl Widget + 'a {
+        EditorRenderer(self)
+    }
+
+    fn host_styles(&self) -> HostStyles<'a> {
+        let (cursor, block) = match self {
+            Self::TextArea(textarea) => (textarea.cursor_style(), textarea.block()),
+            Self::TextInput(input) => (input.cursor_style(), input.block()),
+        };
+        HostStyles {
+            cursor,
+            block: block.cloned(),
+        }
+    }
+
+    fn set_styles(&mut self, cursor: Style, block: Option<Block<'a>>) {
+        match self {
+            Self::TextArea(textarea) => {
+                textarea.set_cursor_style(cursor);
+                if let Some(block) = block {
+                    textarea.set_block(block);
+                }
+            }
+            Self::TextInput(input) => {
+                input.set_cursor_style(cursor);
+                if let Some(block) = block {
+                    input.set_block(block);
+                }
+            }
+        }
+    }
+
+    // Styles are always derived from the host's styles so that focus changes never accumulate on them
+    fn apply_focus(&mut self, focused: bool, styles: &FocusStyles, host: &HostStyles<'a>) {
+        let (cursor, border) = if focused {
+            (styles.cursor.unwrap_or(host.cursor), styles.focused)
+        } else {
+            (Style::default(), Some(styles.unfocused))
+        };
+        let block = host.block.clone().map(|block| match border {
+            Some(style) => block.border_style(style),
+            None => block,
+        });
+        self.set_styles(cursor, block);
+    }
+}
+
+// Cursor style and block of an editor set by the application before it was pushed to the group
+#[derive(Clone, Debug)]
+struct HostStyles<'a> {
+    cursor: Style,
+    block: Option<Block<'a>>,
+}
+
+struct EditorRenderer<'a>(&'a Editor<'a>);
+
+impl<'a> Widget for EditorRenderer<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        match self.0 {
+            Editor::TextArea(textarea) => textarea.widget().render(area, buf),
+            Editor::TextInput(input) => input.widget().render(area, buf),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FocusStyles {
+    // `None` keeps the host's style
+    cursor: Option<Style>,
+    focused: Option<Style>,
+    unfocused: Style,
+}
+
+impl Default for FocusStyles {
+    fn default() -> Self {
+        Self {
+            cursor: None,
+            focused: None,
+            unfocused: Style::default().add_modifier(Modifier::DIM),
+        }
+    }
+}
+
+/// A group of editors where one of them has the focus. Key inputs are routed to the focused editor. `Tab` moves the
+/// focus to the next editor and `Shift+Tab` moves it to the previous one, wrapping around.
+///
+/// The cursor is only shown in the focused editor. Borders of the editors' blocks are styled with the focused or the
+/// unfocused style. The cursor style and the block an editor has when it is pushed are kept as its own styles. They are
+/// the base of the focus styles and are restored when the editor is removed from the group, so set them before pushing
+/// the editor.
+/// ```
+/// use tuipaz_textarea::{EditorGroup, Input, InputKind, Key, TextArea};
+///
+/// let mut group = EditorGroup::new();
+/// let first = group.push(TextArea::default());
+/// let second = group.push(TextArea::default());
+/// assert_eq!(group.focused(), Some(first));
+///
+/// let key = |key| Input { key, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+/// group.input(key(Key::Tab));
+/// assert_eq!(group.focused(), Some(second));
+///
+/// group.input(key(Key::Char('a')));
+/// assert_eq!(group.get(second).unwrap().text(), "a");
+/// assert_eq!(group.get(first).unwrap().text(), "");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EditorGroup<'a> {
+    editors: Vec<(EditorId, Editor<'a>, HostStyles<'a>)>,
+    focused: usize,
+    next_id: usize,
+    styles: FocusStyles,
+}
+
+impl<'a> EditorGroup<'a> {
+    /// Create an empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the editor at the end of the group and return its ID. The first editor pushed to the group gets the focus.
+    pub fn push(&mut self, editor: impl Into<Editor<'a>>) -> EditorId {
+        let id = EditorId(self.next_id);
+        self.next_id += 1;
+        let mut editor = editor.into();
+        let host = editor.host_styles();
+        editor.apply_focus(self.editors.is_empty(), &self.styles, &host);
+        self.editors.push((id, editor, host));
+        id
+    }
+
+    /// Remove the editor with the ID from the group. The editor's own cursor style and block are restored. When the
+    /// focused editor is removed, the focus moves to the next editor.
+    pub fn remove(&mut self, id: EditorId) -> Option<Editor<'a>> {
+        let index = self.index_of(id)?;
+        let (_, mut editor, host) = self.editors.remove(index);
+        editor.set_styles(host.cursor, host.block);
+        if index < self.focused || self.focused >= self.editors.len() {
+            self.focused = self.focused.saturating_sub(1);
+        }
+        self.apply_styles();
+        Some(editor)
+    }
+
+    /// Get the editor with the ID.
+    pub fn get(&self, id: EditorId) -> Option<&Editor<'a>> {
+        self.editors.iter().find(|(i, _, _)| *i == id).map(|(_, e, _)| e)
+    }
+
+    /// Get the mutable editor with the ID.
+    pub fn get_mut(&mut self, id: EditorId) -> Option<&mut Editor<'a>> {
+        self.editors
+            .iter_mut()
+            .find(|(i, _, _)| *i == id)
+            .map(|(_, e, _)| e)
+    }
+
+    /// Iterate over the editors in the order they were pushed. This is useful for rendering.
+    pub fn iter(&self) -> impl Iterator<Item = (EditorId, &Editor<'a>)> {
+        self.editors.iter().map(|(i, e, _)| (*i, e))
+    }
+
+    /// Get the number of editors in the group.
+    pub fn len(&self) -> usize {
+        self.editors.len()
+    }
+
+    /// Check if the group has no editor.
+    pub fn is_empty(&self) -> bool {
+        self.editors.is_empty()
+    }
+
+    /// Get the ID of the focused editor. `None` is returned when the group is empty.
+    pub fn focused(&self) -> Option<EditorId> {
+        self.editors.get(self.focused).map(|(i, _, _)| *i)
+    }
+
+    /// Get the focused editor.
+    pub fn focused_editor(&self) -> Option<&Editor<'a>> {
+        self.editors.get(self.focused).map(|(_, e, _)| e)
+    }
+
+    /// Get the mutable focused editor.
+    pub fn focused_editor_mut(&mut self) -> Option<&mut Editor<'a>> {
+        self.editors.get_mut(self.focused).map(|(_, e, _)| e)
+    }
+
+    /// Move the focus to the editor with the ID. Returns `false` when no editor has the ID.
+    pub fn focus(&mut self, id: EditorId) -> bool {
+        match self.index_of(id) {
+            Some(index) => {
+                self.set_focus(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the focus to the next editor. The focus wraps around to the first editor.
+    pub fn focus_next(&mut self) {
+        if !self.editors.is_empty() {
+            self.set_focus((self.focused + 1) % self.editors.len());
+        }
+    }
+
+    /// Move the focus to the previous editor. The focus wraps around to the last editor.
+    pub fn focus_prev(&mut self) {
+        if !self.editors.is_empty() {
+            let len = self.editors.len();
+            self.set_focus((self.focused + len - 1) % len);
+        }
+    }
+
+    /// Handle a key input. `Tab` and `Shift+Tab` (or `BackTab`) move the focus. Other inputs are passed to the focused editor.
+    /// Returns `true` when the text of the focused editor was modified.
+    pub fn input(&mut self, input: impl Into<Input>) -> bool {
+        let input = input.into();
+        match input {
+            Input {
+                kind: InputKind::Release,
+                ..
+            } => false,
+            Input {
+                key: Key::BackTab,
+                ctrl: false,
+                alt: false,
+                ..
+            } => {
+                self.focus_prev();
+                false
+            }
+            Input {
+                key: Key::Tab,
+                ctrl: false,
+                alt: false,
+                shift,
+                ..
+            } => {
+                if shift {
+                    self.focus_prev();
+                } else {
+                    self.focus_next();
+                }
+                false
+            }
+            input => match self.focused_editor_mut() {
+                Some(editor) => editor.input(input),
+                None => false,
+            },
+        }
+    }
+
+    /// Set the style of the cursor in the focused editor. The cursor of unfocused editors is hidden. By default the
+    /// focused editor shows its own cursor style.
+    pub fn set_cursor_style(&mut self, style: Style) {
+        self.styles.cursor = Some(style);
+        self.apply_styles();
+    }
+
+    /// Apply the colors of the theme to all editors in the group.
+    pub fn set_theme(&mut self, theme: TextAreaTheme) {
+        for (_, editor, _) in self.editors.iter_mut() {
+            editor.set_theme(theme);
+        }
+    }
+
+    /// Set the border style of the focused editor's block. By default the focused editor's block keeps its own border
+    /// style.
+    pub fn set_focused_style(&mut self, style: Style) {
+        self.styles.focused = Some(style);
+        self.apply_styles();
+    }
+
+    /// Set the border style of unfocused editors' blocks. The default style is dimmed.
+    pub fn set_unfocused_style(&mut self, style: Style) {
+        self.styles.unfocused = style;
+        self.apply_styles();
+    }
+
+    fn index_of(&self, id: EditorId) -> Option<usize> {
+        self.editors.iter().position(|(i, _, _)| *i == id)
+    }
+
+    fn set_focus(&mut self, index: usize) {
+        if index == self.focused {
+            return;
+        }
+        let (_, editor, host) = &mut self.editors[self.focused];
+        editor.apply_focus(false, &self.styles, host);
+        self.focused = index;
+        let (_, editor, host) = &mut self.editors[index];
+        editor.apply_focus(true, &self.styles, host);
+    }
+
+    fn apply_styles(&mut self) {
+        let focused = self.focused;
+        for (i, (_, editor, host)) in self.editors.iter_mut().enumerate() {
+            editor.apply_focus(i == focused, &self.styles, host);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ratatui::style::Color;
+
+    fn key(key: Key, shift: bool) -> Input {
+        Input {
+            key,
+            ctrl: false,
+            alt: false,
+            shift,
+            kind: InputKind::Press,
+        }
+    }
+
+    #[test]
+    fn cycle_and_route() {
+        let mut group = EditorGroup::new();
+        assert_eq!(group.focused(), None);
+        assert!(!group.input(key(Key::Char('a'), false)));
+
+        let a = group.push(TextArea::default());
+        let b = group.push(TextInput::new(
+            String::new(),
+            20,
+            Color::White,
+            String::new(),
+        ));
+        let c = group.push(TextArea::default());
+
+        group.input(key(Key::Tab, true));
+        assert_eq!(group.focused(), Some(c));
+        group.input(key(Key::Tab, false));
+        assert_eq!(group.focused(), Some(a));
+        group.input(key(Key::BackTab, false));
+        assert_eq!(group.focused(), Some(c));
+        group.input(key(Key::Tab, false));
+        group.input(key(Key::Tab, false));
+        assert!(group.input(key(Key::Char('x'), false)));
+        assert_eq!(group.get(b).unwrap().text(), "x");
+
+        // Cursor is hidden in unfocused editors
+        let cursor =
+            |group: &EditorGroup, id| group.get(id).unwrap().as_textarea().unwrap().cursor_style();
+        assert_eq!(cursor(&group, a), Style::default());
+        assert!(group.focus(a));
+        assert_eq!(
+            cursor(&group, a),
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+        assert_eq!(cursor(&group, c), Style::default());
+    }
+
+    #[test]
+    fn remove_focused() {
+        let mut group = EditorGroup::new();
+        let a = group.push(TextArea::default());
+        let b = group.push(TextArea::default());
+        let c = group.push(TextArea::default());
+
+        group.focus(c);
+        assert!(group.remove(c).is_some());
+        assert_eq!(group.focused(), Some(b));
+        group.remove(a);
+        assert_eq!(group.focused(), Some(b));
+        assert!(group.remove(a).is_none());
+
+        // IDs are not reused
+        let d = group.push(TextArea::default());
+        assert_ne!(d, c);
+        assert_eq!(group.len(), 2);
+    }
+
+    #[test]
+    fn keep_host_styles() {
+        let host_cursor = Style::default().fg(Color::Red);
+        let block = Block::default().border_style(Style::default().fg(Color::Blue));
+        let mut textarea = TextArea::default();
+        textarea.set_cursor_style(host_cursor);
+        textarea.set_block(block.clone());
+
+        let mut group = EditorGroup::new();
+        let a = group.push(textarea);
+        let b = group.push(TextArea::default());
+        fn styles<'a>(group: &EditorGroup<'a>, id: EditorId) -> (Style, Option<Block<'a>>) {
+            let textarea = group.get(id).unwrap().as_textarea().unwrap();
+            (textarea.cursor_style(), textarea.block().cloned())
+        }
+        assert_eq!(styles(&group, a), (host_cursor, Some(block.clone())));
+
+        // Focus changes are always based on the host's styles
+        group.focus(b);
+        group.focus(a);
+        assert_eq!(styles(&group, a), (host_cursor, Some(block.clone())));
+        let cursor = Style::default().fg(Color::Green);
+        group.set_cursor_style(cursor);
+        assert_eq!(styles(&group, a).0, cursor);
+        group.focus(b);
+        let dim = block.clone().border_style(Style::default().add_modifier(Modifier::DIM));
+        assert_eq!(styles(&group, a), (Style::default(), Some(dim)));
+
+        // The host's styles are restored on removal
+        let editor = group.remove(a).unwrap();
+        let textarea = editor.as_textarea().unwrap();
+        assert_eq!(textarea.cursor_style(), host_cursor);
+        assert_eq!(textarea.block(), Some(&block));
+    }
+
+    #[test]
+    fn set_theme() {
+        let mut group = EditorGroup::new();
+        let a = group.push(TextArea::default());
+        let b = group.push(TextInput::new(String::new(), 20, Color::White, String::new()));
+        let theme = TextAreaTheme {
+            text: Color::Green,
+            select: Color::Gray,
+            links: Color::Cyan,
+        };
+        group.set_theme(theme);
+
+        let textarea = group.get(a).unwrap().as_textarea().unwrap();
+        assert_eq!(textarea.style().fg, Some(Color::Green));
+        let input = group.get(b).unwrap().as_text_input().unwrap();
+        assert_eq!(input.placeholder_style.fg, Some(Color::Green));
+    }
+}
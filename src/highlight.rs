@@ -1,60 +1,126 @@
 use crate::links::Link;
 use crate::ratatui::style::Style;
 use crate::ratatui::text::Span;
-use crate::util::{num_digits, spaces};
+use crate::tags::{find_tags, TagKind};
+use crate::tokens::TokenProvider;
+use crate::urls::find_urls;
+use crate::util::{byte_offset, num_digits, Padding};
 use log::info;
 #[cfg(feature = "ratatui")]
 use ratatui::text::Line;
 use std::borrow::Cow;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::fmt;
 use std::iter;
+use std::ops::Range;
+use std::sync::Arc;
 use unicode_width::UnicodeWidthChar as _;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
-enum Boundary {
-    Cursor(Style),
-    Link(Style),
-    Select(Style),
-    Search(Style),
-    Hop((Style, usize)),
-    End,
+/// A decoration drawn over the text of a textarea. Overlays are composed in the order of their priorities. See
+/// [`TextArea::set_overlay_priority`](crate::TextArea::set_overlay_priority) and
+/// [`TextArea::set_overlay_merge`](crate::TextArea::set_overlay_merge).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Overlay {
+    /// Links and detected URLs in the text.
+    Link,
+    /// Selected text.
+    Selection,
+    /// Matches of text search.
+    Search,
+    /// Hop targets.
+    Hop,
+    /// The character under the cursor.
+    Cursor,
+    /// `#tags`, `@mentions`, tokens and decorations added by custom highlight passes. See
+    /// [`TextArea::set_tag_style`](crate::TextArea::set_tag_style),
+    /// [`TextArea::add_token_provider`](crate::TextArea::add_token_provider) and
+    /// [`TextArea::add_highlight_pass`](crate::TextArea::add_highlight_pass).
+    Custom,
 }
 
-impl Boundary {
-    fn cmp(&self, other: &Boundary) -> Ordering {
-        fn rank(b: &Boundary) -> u8 {
-            match b {
-                Boundary::Cursor(_) => 4,
-                Boundary::Search(_) => 3,
-                Boundary::Hop(_) => 3,
-                Boundary::Select(_) => 2,
-                Boundary::Link(_) => 1,
-                Boundary::End => 0,
-            }
+impl Overlay {
+    fn index(self) -> usize {
+        match self {
+            Self::Link => 0,
+            Self::Selection => 1,
+            Self::Search => 2,
+            Self::Hop => 3,
+            Self::Cursor => 4,
+            Self::Custom => 5,
         }
-        rank(self).cmp(&rank(other))
     }
+}
 
-    fn style(&self) -> Option<Style> {
-        match self {
-            Boundary::Cursor(s) => Some(*s),
-            Boundary::Link(s) => Some(*s),
-            Boundary::Select(s) => Some(*s),
-            Boundary::Search(s) => Some(*s),
-            Boundary::Hop((s, _)) => Some(*s),
-            Boundary::End => None,
+/// How the style of an overlay is combined with the style below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StyleMerge {
+    /// The style of the overlay replaces the style below it.
+    Replace,
+    /// Only the attributes set in the style of the overlay are applied to the style below it with `Style::patch`.
+    Patch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct OverlayRules {
+    priorities: [u8; 6],
+    merges: [StyleMerge; 6],
+}
+
+impl Default for OverlayRules {
+    fn default() -> Self {
+        Self {
+            // Custom < Link < Selection < Search = Hop < Cursor
+            priorities: [1, 2, 3, 3, 4, 0],
+            merges: [StyleMerge::Patch; 6],
         }
     }
+}
 
-    fn idx(&self) -> Option<usize> {
-        match self {
-            Boundary::Hop((_, idx)) => Some(*idx),
-            _ => None,
-        }
+impl OverlayRules {
+    pub fn priority(&self, overlay: Overlay) -> u8 {
+        self.priorities[overlay.index()]
+    }
+
+    pub fn set_priority(&mut self, overlay: Overlay, priority: u8) {
+        self.priorities[overlay.index()] = priority;
+    }
+
+    pub fn merge(&self, overlay: Overlay) -> StyleMerge {
+        self.merges[overlay.index()]
+    }
+
+    pub fn set_merge(&mut self, overlay: Overlay, merge: StyleMerge) {
+        self.merges[overlay.index()] = merge;
+    }
+
+    pub fn set_all_merges(&mut self, merge: StyleMerge) {
+        self.merges = [merge; 6];
+    }
+
+    pub fn all_merges(&self, merge: StyleMerge) -> bool {
+        self.merges.iter().all(|m| *m == merge)
+    }
+
+    fn compose(&self, base: Style, mut layers: Vec<(Overlay, Style)>) -> Style {
+        // Stable sort keeps the order of overlays with the same priority
+        layers.sort_by_key(|(o, _)| self.priority(*o));
+        layers
+            .into_iter()
+            .fold(base, |style, (overlay, s)| match self.merge(overlay) {
+                StyleMerge::Replace => s,
+                StyleMerge::Patch => style.patch(s),
+            })
     }
 }
 
+#[derive(Debug, Clone)]
+struct Highlight {
+    overlay: Overlay,
+    style: Style,
+    start: usize,
+    end: usize,
+    hop_idx: Option<usize>,
+}
+
 struct DisplayTextBuilder {
     tab_len: u8,
     width: usize,
@@ -92,7 +158,6 @@ impl DisplayTextBuilder {
             return Cow::Owned(h);
         }
 
-        let tab = spaces(self.tab_len);
         let mut buf = String::new();
         for (i, c) in s.char_indices() {
             if c == '\t' {
@@ -102,7 +167,7 @@ impl DisplayTextBuilder {
                 }
                 if self.tab_len > 0 {
                     let len = self.tab_len as usize - (self.width % self.tab_len as usize);
-                    buf.push_str(&tab[..len]);
+                    Padding(len).push_to(&mut buf);
                     self.width += len;
                 }
             } else {
@@ -121,25 +186,60 @@ impl DisplayTextBuilder {
     }
 }
 
+/// Display width of the first `col` characters of the line, expanding tabs in the same way as the rendered text.
+pub(crate) fn display_width(line: &str, col: usize, tab_len: u8) -> usize {
+    let mut width = 0;
+    for c in line.chars().take(col) {
+        width += if c == '\t' && tab_len > 0 {
+            tab_len as usize - width % tab_len as usize
+        } else {
+            c.width().unwrap_or(0)
+        };
+    }
+    width
+}
+
+type PassFn = dyn Fn(&mut LineHighlighter<'_>, usize) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct HighlightPass {
+    pub id: usize,
+    pub run: Arc<PassFn>,
+}
+
+impl fmt::Debug for HighlightPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HighlightPass")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A builder of the styled line of a textarea. Custom highlight passes registered with
+/// [`TextArea::add_highlight_pass`](crate::TextArea::add_highlight_pass) receive it for each rendered line and add
+/// decorations with [`LineHighlighter::decorate`].
 pub struct LineHighlighter<'a> {
     line: &'a str,
     spans: Vec<Span<'a>>,
-    boundaries: Vec<(Boundary, usize)>, // TODO: Consider smallvec
+    highlights: Vec<Highlight>,
+    rules: OverlayRules,
     style_begin: Style,
     cursor_at_end: bool,
     cursor_style: Style,
-    link_style: Style,
+    // Byte range of the character under the cursor and whether the cursor covers all its cells
+    cursor: Option<Range<usize>>,
+    wide_cursor: bool,
     tab_len: u8,
     mask: Option<char>,
     select_at_end: bool,
     select_style: Style,
+    concealed: Vec<Range<usize>>,
 }
 
 impl<'a> LineHighlighter<'a> {
-    pub fn new(
+    pub(crate) fn new(
         line: &'a str,
         cursor_style: Style,
-        link_style: Style,
         tab_len: u8,
         mask: Option<char>,
         select_style: Style,
@@ -147,74 +247,139 @@ impl<'a> LineHighlighter<'a> {
         Self {
             line,
             spans: vec![],
-            boundaries: vec![],
+            highlights: vec![],
+            rules: OverlayRules::default(),
             style_begin: Style::default(),
             cursor_at_end: false,
             cursor_style,
-            link_style,
+            cursor: None,
+            wide_cursor: true,
             tab_len,
             mask,
             select_at_end: false,
             select_style,
+            concealed: vec![],
+        }
+    }
+
+    /// Get the text of the line being highlighted.
+    pub fn line(&self) -> &'a str {
+        self.line
+    }
+
+    /// Decorate the byte range of the line with the style. The range is usually a match of a regular expression on
+    /// [`LineHighlighter::line`]. Decorations are composed as [`Overlay::Custom`] with other overlays. Ranges which
+    /// are empty, out of the line or not on character boundaries are ignored.
+    pub fn decorate(&mut self, range: Range<usize>, style: Style) {
+        let Range { start, end } = range;
+        if start < end
+            && end <= self.line.len()
+            && self.line.is_char_boundary(start)
+            && self.line.is_char_boundary(end)
+        {
+            self.push(Overlay::Custom, style, start, end);
         }
     }
 
-    pub fn line_number(&mut self, row: usize, lnum_len: u8, style: Style) {
-        let pad = spaces(lnum_len - num_digits(row + 1) + 1);
+    pub(crate) fn set_overlay_rules(&mut self, rules: OverlayRules) {
+        self.rules = rules;
+    }
+
+    fn push(&mut self, overlay: Overlay, style: Style, start: usize, end: usize) {
+        self.highlights.push(Highlight {
+            overlay,
+            style,
+            start,
+            end,
+            hop_idx: None,
+        });
+    }
+
+    pub(crate) fn line_number(&mut self, row: usize, lnum_len: u8, style: Style) {
+        let pad = Padding((lnum_len as usize + 1).saturating_sub(num_digits(row + 1) as usize));
         self.spans
             .push(Span::styled(format!("{}{} ", pad, row + 1), style));
     }
 
-    pub fn cursor_line(&mut self, cursor_col: usize, style: Style) {
+    /// Set if the cursor covers all display cells of the character under it, such as an expanded tab. Otherwise only
+    /// the first cell is styled with the cursor style.
+    pub(crate) fn set_wide_cursor(&mut self, enabled: bool) {
+        self.wide_cursor = enabled;
+    }
+
+    pub(crate) fn cursor_line(&mut self, cursor_col: usize, style: Style) {
         if let Some((start, c)) = self.line.char_indices().nth(cursor_col) {
-            self.boundaries
-                .push((Boundary::Cursor(self.cursor_style), start));
-            self.boundaries.push((Boundary::End, start + c.len_utf8()));
+            let end = start + c.len_utf8();
+            self.push(Overlay::Cursor, self.cursor_style, start, end);
+            self.cursor = Some(start..end);
         } else {
             self.cursor_at_end = true;
         }
         self.style_begin = style;
     }
 
-    pub fn search(&mut self, matches: impl Iterator<Item = (usize, usize)>, style: Style) {
+    pub(crate) fn search(&mut self, matches: impl Iterator<Item = (usize, usize)>, style: Style) {
         for (start, end) in matches {
             if start != end {
-                self.boundaries.push((Boundary::Search(style), start));
-                self.boundaries.push((Boundary::End, end));
+                self.push(Overlay::Search, style, start, end);
             }
         }
     }
 
-    pub fn hop(
-        &mut self, 
-        matches: impl Iterator<Item = (usize, usize)>, 
-        style: Style, 
-        mut count: usize
-    ) -> (Vec<(usize, usize)>, usize) {
-        let mut start_vec = vec![];
-        for (start, end) in matches {
-            if start != end {
-                count += 1;
-                self.boundaries.push((Boundary::Hop((style, count)), start));
-                self.boundaries.push((Boundary::End, end));
-                start_vec.push((start, count));
-            }
+    pub(crate) fn hop(&mut self, targets: impl Iterator<Item = (usize, usize, usize)>, style: Style) {
+        for (start, end, label) in targets {
+            self.highlights.push(Highlight {
+                overlay: Overlay::Hop,
+                style,
+                start,
+                end,
+                hop_idx: Some(label),
+            });
         }
+    }
 
-        (start_vec, count)
+    pub(crate) fn links<'l>(&mut self, links: impl IntoIterator<Item = &'l Link>, style: Style) {
+        for link in links.into_iter().filter(|link| !link.deleted) {
+            let start = byte_offset(self.line, link.start_col);
+            let end = byte_offset(self.line, link.end_col + 1);
+            self.push(Overlay::Link, style, start, end);
+        }
+    }
+
+    pub(crate) fn urls(&mut self, style: Style) {
+        for range in find_urls(self.line) {
+            self.push(Overlay::Link, style, range.start, range.end);
+        }
+    }
+
+    pub(crate) fn conceal(&mut self, ranges: Vec<Range<usize>>) {
+        self.concealed = ranges;
+    }
+
+    pub(crate) fn tokens(&mut self, providers: &[TokenProvider]) {
+        for provider in providers {
+            for range in provider.find(self.line) {
+                self.push(Overlay::Custom, provider.style(), range.start, range.end);
+            }
+        }
     }
 
-    pub fn links(&mut self, links: &HashMap<usize, Link>, row: usize, style: Style) {
-        for link in links.values().filter(|link| !link.deleted) {
-            if link.row == row {
-                self.boundaries
-                    .push((Boundary::Link(style), link.start_col));
-                self.boundaries.push((Boundary::End, link.end_col + 1));
+    pub(crate) fn tags(&mut self, hashtag: Option<Style>, mention: Option<Style>) {
+        if hashtag.is_none() && mention.is_none() {
+            return;
+        }
+        for (kind, range) in find_tags(self.line) {
+            let style = match kind {
+                TagKind::Hashtag => hashtag,
+                TagKind::Mention => mention,
+            };
+            if let Some(style) = style {
+                self.push(Overlay::Custom, style, range.start, range.end);
             }
         }
     }
 
-    pub fn selection(
+    pub(crate) fn selection(
         &mut self,
         current_row: usize,
         start_row: usize,
@@ -238,82 +403,86 @@ impl<'a> LineHighlighter<'a> {
             return;
         };
         if start != end {
-            self.boundaries
-                .push((Boundary::Select(self.select_style), start));
-            self.boundaries.push((Boundary::End, end));
+            self.push(Overlay::Selection, self.select_style, start, end);
         }
     }
 
-    pub fn into_spans(self) -> Line<'a> {
+    pub(crate) fn into_spans(self) -> Line<'a> {
         let Self {
             line,
             mut spans,
-            mut boundaries,
+            highlights,
+            rules,
             tab_len,
             style_begin,
             cursor_style,
-            link_style,
             cursor_at_end,
+            cursor,
+            wide_cursor,
             mask,
             select_at_end,
             select_style,
+            concealed,
+            ..
         } = self;
         let mut builder = DisplayTextBuilder::new(tab_len, mask, None);
 
-        if boundaries.is_empty() {
-            let built = builder.build(line);
-            if !built.is_empty() {
-                spans.push(Span::styled(built, style_begin));
-            }
-            if cursor_at_end {
-                spans.push(Span::styled(" ", cursor_style));
-            } else if select_at_end {
-                spans.push(Span::styled(" ", select_style));
-            }
-            return Line::from(spans);
+        // Split the line at every edge of the highlights and compose the overlays covering each piece
+        let mut edges = vec![0, line.len()];
+        for h in highlights.iter() {
+            edges.push(h.start);
+            edges.push(h.end);
         }
+        for r in concealed.iter() {
+            edges.push(r.start);
+            edges.push(r.end);
+        }
+        edges.sort_unstable();
+        edges.dedup();
 
-        boundaries.sort_unstable_by(|(l, i), (r, j)| match i.cmp(j) {
-            Ordering::Equal => l.cmp(r),
-            o => o,
-        });
-
-        let mut style = style_begin;
-        let mut hop_idx: Option<usize> = None;
-        let mut start = 0;
-        let mut style_stack = vec![];
-
-        for (next_boundary, end) in boundaries {
-            if start < end {
-                if let Some(idx) = hop_idx {
-                    let hm = format!("{:<2}", idx);
-                    builder.set_hop_mask(hm);
-                    hop_idx = None;
+        for piece in edges.windows(2) {
+            let (start, end) = (piece[0], piece[1]);
+            if start >= end || end > line.len() {
+                continue;
+            }
+            if concealed.iter().any(|r| r.start <= start && end <= r.end) {
+                continue;
+            }
+            let layers: Vec<_> = highlights
+                .iter()
+                .filter(|h| h.start <= start && end <= h.end)
+                .map(|h| (h.overlay, h.style))
+                .collect();
+            let hop_idx = highlights
+                .iter()
+                .find(|h| h.start == start && h.hop_idx.is_some())
+                .and_then(|h| h.hop_idx);
+            if let Some(idx) = hop_idx {
+                builder.set_hop_mask(format!("{:<2}", idx));
+            }
+            let text = builder.build(&line[start..end]);
+            // A narrow cursor on a character displayed in several cells only covers the first one
+            let split = text.char_indices().nth(1).map(|(i, _)| i);
+            match split.filter(|_| !wide_cursor && cursor == Some(start..end)) {
+                Some(i) => {
+                    let rest = layers.iter().copied().filter(|(o, _)| *o != Overlay::Cursor).collect();
+                    spans.push(Span::styled(text[..i].to_string(), rules.compose(style_begin, layers)));
+                    spans.push(Span::styled(text[i..].to_string(), rules.compose(style_begin, rest)));
                 }
-                spans.push(Span::styled(builder.build(&line[start..end]), style));
-                builder.clear_hop_mask();
+                None => spans.push(Span::styled(text, rules.compose(style_begin, layers))),
             }
-            
-            style = if let Some(s) = next_boundary.style() {
-                style_stack.push(style);
-                s
-            } else {
-                style_stack.pop().unwrap_or(style_begin)
-            };
-
-            hop_idx = next_boundary.idx();
-            
-            start = end;
+            builder.clear_hop_mask();
         }
 
-        if start != line.len() {
-            spans.push(Span::styled(builder.build(&line[start..]), style));
+        let mut layers = vec![];
+        if select_at_end {
+            layers.push((Overlay::Selection, select_style));
         }
-
         if cursor_at_end {
-            spans.push(Span::styled(" ", cursor_style));
-        } else if select_at_end {
-            spans.push(Span::styled(" ", select_style));
+            layers.push((Overlay::Cursor, cursor_style));
+        }
+        if !layers.is_empty() {
+            spans.push(Span::styled(" ", rules.compose(style_begin, layers)));
         }
 
         Line::from(spans)
@@ -431,7 +600,6 @@ mod tests {
 
     const DEFAULT: Style = Style::new();
     const CUR: Style = Style::new().bg(Color::Red); // Cursor
-    const LINK: Style = Style::new().bg(Color::Cyan); // Links
     #[allow(unused)]
     const SEARCH: Style = Style::new().bg(Color::Green);
     const SEL: Style = Style::new().bg(Color::Blue);
@@ -447,7 +615,7 @@ mod tests {
         ];
         for test in tests {
             let (line, want) = test;
-            let lh = LineHighlighter::new(line, CUR, LINK, 4, None, SEL);
+            let lh = LineHighlighter::new(line, CUR, 4, None, SEL);
             assert_spans(lh, want, test);
         }
     }
@@ -466,7 +634,7 @@ mod tests {
 
         for test in tests {
             let (line, col, want) = test;
-            let mut lh = LineHighlighter::new(line, CUR, LINK, 4, None, SEL);
+            let mut lh = LineHighlighter::new(line, CUR, 4, None, SEL);
             lh.cursor_line(col, LINE);
             assert_spans(lh, want, test);
         }
@@ -481,7 +649,7 @@ mod tests {
         ];
         for test in tests {
             let (row, len, want) = test;
-            let mut lh = LineHighlighter::new("", CUR, LINK, 4, None, SEL);
+            let mut lh = LineHighlighter::new("", CUR, 4, None, SEL);
             lh.line_number(row, len, LNUM);
             assert_spans(lh, want, test);
         }
@@ -545,7 +713,7 @@ mod tests {
 
         for test in tests {
             let (line, matches, want) = test;
-            let mut lh = LineHighlighter::new(line, CUR, LINK, 4, None, SEL);
+            let mut lh = LineHighlighter::new(line, CUR, 4, None, SEL);
             lh.search(matches.iter().copied(), SEARCH);
             assert_spans(lh, want, test);
         }
@@ -585,7 +753,7 @@ mod tests {
 
         for test in tests {
             let (line, (row, start_row, start_off, end_row, end_off), want) = test;
-            let mut lh = LineHighlighter::new(line, CUR, LINK, 4, None, SEL);
+            let mut lh = LineHighlighter::new(line, CUR, 4, None, SEL);
             lh.selection(row, start_row, start_off, end_row, end_off);
             assert_spans(lh, want, test);
         }
@@ -597,7 +765,7 @@ mod tests {
             (
                 "cursor on selection",
                 {
-                    let mut lh = LineHighlighter::new("abcde", CUR, LINK, 4, None, SEL);
+                    let mut lh = LineHighlighter::new("abcde", CUR, 4, None, SEL);
                     lh.cursor_line(2, LINE);
                     lh.selection(0, 0, 1, 0, 4);
                     lh
@@ -607,7 +775,7 @@ mod tests {
             (
                 "cursor + selection + search",
                 {
-                    let mut lh = LineHighlighter::new("abcdefg", CUR, LINK, 4, None, SEL);
+                    let mut lh = LineHighlighter::new("abcdefg", CUR, 4, None, SEL);
                     lh.cursor_line(3, LINE);
                     lh.selection(0, 0, 2, 0, 5);
                     lh.search([(1, 2), (5, 6)].into_iter(), SEARCH);
@@ -626,7 +794,7 @@ mod tests {
             (
                 "selection + cursor at end",
                 {
-                    let mut lh = LineHighlighter::new("ab", CUR, LINK, 4, None, SEL);
+                    let mut lh = LineHighlighter::new("ab", CUR, 4, None, SEL);
                     lh.cursor_line(2, LINE);
                     lh.selection(0, 0, 1, 2, 0);
                     lh
@@ -636,7 +804,7 @@ mod tests {
             (
                 "cursor at start of selection",
                 {
-                    let mut lh = LineHighlighter::new("abcd", CUR, LINK, 4, None, SEL);
+                    let mut lh = LineHighlighter::new("abcd", CUR, 4, None, SEL);
                     lh.cursor_line(1, LINE);
                     lh.selection(0, 0, 1, 0, 3);
                     lh
@@ -646,7 +814,7 @@ mod tests {
             (
                 "cursor at end of selection",
                 {
-                    let mut lh = LineHighlighter::new("abcd", CUR, LINK, 4, None, SEL);
+                    let mut lh = LineHighlighter::new("abcd", CUR, 4, None, SEL);
                     lh.cursor_line(2, LINE);
                     lh.selection(0, 0, 1, 0, 3);
                     lh
@@ -656,7 +824,7 @@ mod tests {
             (
                 "cursor covers selection",
                 {
-                    let mut lh = LineHighlighter::new("abc", CUR, LINK, 4, None, SEL);
+                    let mut lh = LineHighlighter::new("abc", CUR, 4, None, SEL);
                     lh.cursor_line(1, LINE);
                     lh.selection(0, 0, 1, 0, 2);
                     lh
@@ -669,4 +837,60 @@ mod tests {
             assert_spans(lh, want, what);
         }
     }
+
+    #[test]
+    fn into_spans_overlay_rules() {
+        let link = Style::new().fg(Color::Cyan);
+        let links = [Link {
+            id: 0,
+            row: 0,
+            start_col: 1,
+            end_col: 3,
+            edited: false,
+            deleted: false,
+        }];
+        let highlighter = |rules| {
+            let mut lh = LineHighlighter::new("abcde", CUR, 4, None, SEL);
+            lh.set_overlay_rules(rules);
+            lh.links(&links, link);
+            lh.selection(0, 0, 0, 0, 3);
+            lh
+        };
+
+        // Selection background is combined with link foreground by default
+        let rules = OverlayRules::default();
+        assert_spans(
+            highlighter(rules),
+            &[("a", SEL), ("bc", link.bg(Color::Blue)), ("d", link), ("e", DEFAULT)],
+            "default",
+        );
+
+        // Selection replaces link
+        let mut rules = OverlayRules::default();
+        rules.set_all_merges(StyleMerge::Replace);
+        assert_spans(
+            highlighter(rules),
+            &[("a", SEL), ("bc", SEL), ("d", link), ("e", DEFAULT)],
+            "replace",
+        );
+
+        // Link is drawn over selection
+        let mut rules = OverlayRules::default();
+        rules.set_all_merges(StyleMerge::Replace);
+        rules.set_priority(Overlay::Link, 3);
+        assert_spans(
+            highlighter(rules),
+            &[("a", SEL), ("bc", link), ("d", link), ("e", DEFAULT)],
+            "priority",
+        );
+
+        // Only selection replaces the style below it
+        let mut rules = OverlayRules::default();
+        rules.set_merge(Overlay::Selection, StyleMerge::Replace);
+        assert_spans(
+            highlighter(rules),
+            &[("a", SEL), ("bc", SEL), ("d", link), ("e", DEFAULT)],
+            "replace selection",
+        );
+    }
 }
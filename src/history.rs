@@ -1,7 +1,8 @@
 use log::info;
 
+use crate::links::KeyChanges;
 use crate::{util::Pos, Link};
-use std::collections::{VecDeque, HashMap};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 pub type MaybeLinkIds = Option<Vec<usize>>;
 
@@ -17,13 +18,23 @@ pub enum EditKind {
     DeleteChunk((Vec<String>, MaybeLinkIds)),
     InsertNewline,
     DeleteNewline,
+    CreateLink(Link),
+    DeleteLink(Link),
+    /// All lines before and after they were replaced at once
+    ReplaceLines((Vec<String>, Vec<String>)),
+    /// The link before and after its range was changed
+    UpdateLink((Link, Link)),
+    /// Rows from the row replaced at once, with the links in them before and after they were replaced
+    SpliceLines((usize, Vec<String>, Vec<String>, Vec<Link>, Vec<Link>)),
+    /// Edits undone and redone as one step with the links before and after them
+    Group((Vec<Edit>, BTreeMap<usize, Link>, BTreeMap<usize, Link>)),
 }
 
 impl EditKind {
     pub(crate) fn apply(
         &mut self,
         lines: &mut Vec<String>,
-        links: &mut HashMap<usize, Link>,
+        links: &mut BTreeMap<usize, Link>,
         before: &Pos,
         after: &Pos,
     ) {
@@ -58,7 +69,7 @@ impl EditKind {
                 }
             }
             EditKind::DeleteLine((_, link_vec)) => {
-                lines.remove(before.row);
+                lines.remove(after.row);
                 if let Some(l) = link_vec {
                     for id in l {
                         let link = links.get_mut(id).expect("link should be present");
@@ -140,6 +151,118 @@ impl EditKind {
                 let line = lines.remove(before.row);
                 lines[before.row - 1].push_str(&line);
             }
+            EditKind::CreateLink(link) => {
+                let link = links.entry(link.id).or_insert(*link);
+                link.deleted = false;
+            }
+            EditKind::DeleteLink(link) => {
+                if let Some(link) = links.get_mut(&link.id) {
+                    link.deleted = true;
+                }
+            }
+            EditKind::ReplaceLines((_, after)) => {
+                *lines = after.clone();
+            }
+            EditKind::UpdateLink((_, after)) => {
+                links.insert(after.id, *after);
+            }
+            EditKind::SpliceLines((row, old, new, _, links_after)) => {
+                lines.splice(*row..*row + old.len(), new.iter().cloned());
+                let end = *row + old.len();
+                for link in links.values_mut().filter(|l| l.row >= end) {
+                    link.row = link.row + new.len() - old.len();
+                }
+                for link in links_after {
+                    links.insert(link.id, *link);
+                }
+            }
+            EditKind::Group((edits, _, links_after)) => {
+                for edit in edits.iter_mut() {
+                    edit.redo(lines, links);
+                }
+                // Link bookkeeping of the edits is replaced with the exact state after the group
+                *links = links_after.clone();
+            }
+        }
+    }
+
+    // Rows inserted or removed by the edit as `(row, delta)`. A positive delta inserts rows at `row` and a negative
+    // delta removes rows from `row`. Rows after them are shifted by the delta.
+    fn row_shifts(&self, before: &Pos, after: &Pos, follow_text: bool, shifts: &mut Vec<(usize, isize)>) {
+        match self {
+            // A split at the head of a row inserts an empty row above it and joining a row into an empty row removes
+            // the empty row when rows follow their text
+            EditKind::InsertNewline if follow_text && before.col == 0 => shifts.push((before.row, 1)),
+            EditKind::InsertNewline => shifts.push((before.row + 1, 1)),
+            EditKind::DeleteNewline if follow_text && after.col == 0 => shifts.push((after.row, -1)),
+            EditKind::DeleteNewline => shifts.push((before.row, -1)),
+            EditKind::InsertLine(_) => shifts.push((before.row, 1)),
+            EditKind::DeleteLine(_) => shifts.push((after.row, -1)),
+            EditKind::InsertChunk((c, _)) => shifts.push((before.row + 1, c.len() as isize - 1)),
+            EditKind::SpliceLines((row, old, new, _, _)) => {
+                let delta = new.len() as isize - old.len() as isize;
+                match delta.cmp(&0) {
+                    std::cmp::Ordering::Greater => shifts.push((row + old.len(), delta)),
+                    std::cmp::Ordering::Less => shifts.push((row + new.len(), delta)),
+                    std::cmp::Ordering::Equal => {}
+                }
+            }
+            EditKind::DeleteChunk((c, _)) => shifts.push((after.row + 1, 1 - c.len() as isize)),
+            EditKind::Group((edits, _, _)) => {
+                for edit in edits {
+                    edit.kind.row_shifts(&edit.before, &edit.after, follow_text, shifts);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Move the position `(row, col)` in the text before the edit to the same place in the text after the edit.
+    // Positions at the insertion point move with the inserted text and positions in deleted text move to its start.
+    fn shift_pos(&self, before: &Pos, after: &Pos, pos: (usize, usize)) -> (usize, usize) {
+        let len = |s: &str| s.chars().count();
+        let chunk_end = |start: &Pos, c: &[String]| (start.row + c.len() - 1, len(&c[c.len() - 1]));
+        let inserted = |start: (usize, usize), end: (usize, usize)| {
+            if pos < start {
+                pos
+            } else if pos.0 == start.0 {
+                (end.0, end.1 + pos.1 - start.1)
+            } else {
+                (pos.0 + end.0 - start.0, pos.1)
+            }
+        };
+        let deleted = |start: (usize, usize), end: (usize, usize)| {
+            if pos <= start {
+                pos
+            } else if pos <= end {
+                start
+            } else if pos.0 == end.0 {
+                (start.0, start.1 + pos.1 - end.1)
+            } else {
+                (pos.0 - (end.0 - start.0), pos.1)
+            }
+        };
+        let (b, a) = ((before.row, before.col), (after.row, after.col));
+        match self {
+            EditKind::InsertChar(_) => inserted(b, (b.0, b.1 + 1)),
+            EditKind::InsertStr((s, _)) => inserted(b, (b.0, b.1 + len(s))),
+            EditKind::InsertNewline => inserted(b, (b.0 + 1, 0)),
+            EditKind::InsertLine(_) => inserted((b.0, 0), (b.0 + 1, 0)),
+            EditKind::InsertChunk((c, _)) => inserted(b, chunk_end(before, c)),
+            EditKind::DeleteChar(_) => deleted(a, (a.0, a.1 + 1)),
+            EditKind::DeleteStr((s, _)) => deleted(a, (a.0, a.1 + len(s))),
+            EditKind::DeleteNewline => deleted(a, (b.0, 0)),
+            EditKind::DeleteLine(_) => deleted((a.0, 0), (b.0, 0)),
+            EditKind::DeleteChunk((c, _)) => deleted(a, chunk_end(after, c)),
+            EditKind::Group((edits, _, _)) => edits.iter().fold(pos, |pos, e| e.shift_pos(pos)),
+            // Positions in the replaced rows stay at the same row and column, and are clamped by the caller
+            EditKind::SpliceLines((row, old, new, _, _)) if pos.0 >= row + old.len() => {
+                (pos.0 + new.len() - old.len(), pos.1)
+            }
+            EditKind::SpliceLines(_) => pos,
+            EditKind::ReplaceLines(_) | EditKind::CreateLink(_) | EditKind::DeleteLink(_) | EditKind::UpdateLink(_) => {
+                pos
+            }
         }
     }
 
@@ -156,34 +279,144 @@ impl EditKind {
             DeleteChunk((c, l)) => InsertChunk((c, l)),
             InsertNewline => DeleteNewline,
             DeleteNewline => InsertNewline,
+            CreateLink(l) => DeleteLink(l),
+            DeleteLink(l) => CreateLink(l),
+            ReplaceLines((before, after)) => ReplaceLines((after, before)),
+            UpdateLink((before, after)) => UpdateLink((after, before)),
+            SpliceLines((row, old, new, before, after)) => SpliceLines((row, new, old, after, before)),
+            Group((edits, before, after)) => {
+                Group((edits.iter().rev().map(Edit::inverted).collect(), after, before))
+            }
         }
     }
 }
 
+/// The state of the view when an edit was made. It is restored on undo/redo so that the view does not jump.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EditContext {
+    /// Scroll top `(row, col)` of the viewport.
+    pub scroll_top: (u16, u16),
+    /// Selection `(start, cursor)` which was replaced by the edit.
+    pub selection: Option<((usize, usize), (usize, usize))>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Edit {
     kind: EditKind,
     before: Pos,
     after: Pos,
+    context: EditContext,
+    // Rows of the segments of long lines before and after the edit
+    soft_breaks: Option<(BTreeSet<usize>, BTreeSet<usize>)>,
+    // Stable keys of the links whose key the edit changed, before and after the edit
+    link_keys: Option<(KeyChanges, KeyChanges)>,
+    // Metadata of the lines removed by the edit by their rows before it, and of the lines removed by undoing it by
+    // their rows after it
+    line_meta: Option<(BTreeMap<usize, String>, BTreeMap<usize, String>)>,
 }
 
 impl Edit {
-    pub fn new(kind: EditKind, before: Pos, after: Pos) -> Self {
+    pub fn new(kind: EditKind, before: Pos, after: Pos, context: EditContext) -> Self {
         Self {
             kind,
             before,
             after,
+            context,
+            soft_breaks: None,
+            link_keys: None,
+            line_meta: None,
+        }
+    }
+
+    /// Record the rows of the segments of long lines before and after the edit. They are restored on undo and redo
+    /// since the rows joined by the edit would come back as real lines otherwise.
+    pub(crate) fn set_soft_breaks(&mut self, before: BTreeSet<usize>, after: BTreeSet<usize>) {
+        self.soft_breaks = Some((before, after));
+    }
+
+    /// Rows of the segments of long lines after redoing the edit when they were recorded.
+    pub(crate) fn soft_breaks_after(&self) -> Option<&BTreeSet<usize>> {
+        self.soft_breaks.as_ref().map(|(_, after)| after)
+    }
+
+    /// Record the stable keys of the links before and after the edit. They are restored on undo and redo since keys
+    /// carried by pasted or inserted links move between link IDs.
+    pub(crate) fn set_link_keys(&mut self, before: KeyChanges, after: KeyChanges) {
+        self.link_keys = Some((before, after));
+    }
+
+    /// Stable keys of the links after redoing the edit when they were recorded.
+    pub(crate) fn link_keys_after(&self) -> Option<&KeyChanges> {
+        self.link_keys.as_ref().map(|(_, after)| after)
+    }
+
+    /// Record the metadata of the lines removed by the edit. They are put back on undo.
+    pub(crate) fn set_removed_line_meta(&mut self, removed: BTreeMap<usize, String>) {
+        if !removed.is_empty() {
+            self.line_meta = Some((removed, BTreeMap::new()));
+        }
+    }
+
+    /// Move the metadata of lines as their text moves by redoing the edit. The metadata of the lines removed by the
+    /// edit is dropped and returned, and the metadata recorded for the lines it brings back is put back.
+    pub(crate) fn shift_line_meta(&self, meta: &mut BTreeMap<usize, String>) -> BTreeMap<usize, String> {
+        let mut removed = BTreeMap::new();
+        if let EditKind::Group((edits, _, _)) = &self.kind {
+            for edit in edits {
+                removed.extend(edit.shift_line_meta(meta));
+            }
+            return removed;
+        }
+        for (at, delta) in self.text_row_shifts() {
+            let count = if delta < 0 { delta.unsigned_abs() } else { 0 };
+            for (row, value) in meta.split_off(&at) {
+                if row < at + count {
+                    removed.insert(row, value);
+                } else {
+                    meta.insert((row as isize + delta) as usize, value);
+                }
+            }
+        }
+        if let Some((_, restored)) = &self.line_meta {
+            meta.extend(restored.clone());
         }
+        removed
     }
 
-    pub fn redo(&mut self, lines: &mut Vec<String>, links: &mut HashMap<usize, Link>) {
+    pub fn redo(&mut self, lines: &mut Vec<String>, links: &mut BTreeMap<usize, Link>) {
         self.kind.apply(lines, links, &self.before, &self.after);
     }
 
-    pub fn undo(&mut self, lines: &mut Vec<String>, links: &mut HashMap<usize, Link>) {
+    pub fn undo(&mut self, lines: &mut Vec<String>, links: &mut BTreeMap<usize, Link>) {
         self.kind.invert().apply(lines, links, &self.after, &self.before); // Undo is redo of inverted edit
     }
 
+    /// Rows inserted or removed by redoing the edit as `(row, delta)`.
+    pub fn row_shifts(&self) -> Vec<(usize, isize)> {
+        let mut shifts = vec![];
+        self.kind.row_shifts(&self.before, &self.after, false, &mut shifts);
+        shifts
+    }
+
+    /// Same as [`Edit::row_shifts`] but rows follow their text. A newline inserted at the head of a row inserts an
+    /// empty row above it instead of moving the text to a new row, and the inverse removes the empty row. This is for
+    /// data attached to the text of a row such as paragraph IDs.
+    pub fn text_row_shifts(&self) -> Vec<(usize, isize)> {
+        let mut shifts = vec![];
+        self.kind.row_shifts(&self.before, &self.after, true, &mut shifts);
+        shifts
+    }
+
+    /// Move the position `(row, col)` in the text before redoing the edit to the same place in the text after it.
+    pub fn shift_pos(&self, pos: (usize, usize)) -> (usize, usize) {
+        self.kind.shift_pos(&self.before, &self.after, pos)
+    }
+
+    #[cfg(feature = "journal")]
+    pub(crate) fn parts(&self) -> (&EditKind, &Pos, &Pos) {
+        (&self.kind, &self.before, &self.after)
+    }
+
     pub fn cursor_before(&self) -> (usize, usize) {
         (self.before.row, self.before.col)
     }
@@ -191,13 +424,51 @@ impl Edit {
     pub fn cursor_after(&self) -> (usize, usize) {
         (self.after.row, self.after.col)
     }
+
+    pub fn context(&self) -> EditContext {
+        self.context
+    }
+
+    /// Links added or moved by redoing the edit with their rows before it. `None` is returned when the edit replaces
+    /// the links at once.
+    pub(crate) fn moved_links(&self) -> Option<Vec<(Option<usize>, Link)>> {
+        match &self.kind {
+            EditKind::Group(_) | EditKind::SpliceLines(_) => None,
+            EditKind::CreateLink(link) => Some(vec![(None, *link)]),
+            EditKind::UpdateLink((before, after)) => Some(vec![(Some(before.row), *after)]),
+            _ => Some(vec![]),
+        }
+    }
+
+    /// Check if the edit restores the links by itself on undo and redo.
+    pub fn restores_links(&self) -> bool {
+        matches!(self.kind, EditKind::Group(_) | EditKind::SpliceLines(_))
+    }
+
+    // Edit which undoes this edit when it is redone
+    fn inverted(&self) -> Self {
+        Self {
+            kind: self.kind.invert(),
+            before: self.after.clone(),
+            after: self.before.clone(),
+            context: self.context,
+            soft_breaks: self.soft_breaks.clone().map(|(before, after)| (after, before)),
+            link_keys: self.link_keys.clone().map(|(before, after)| (after, before)),
+            line_meta: self.line_meta.clone().map(|(before, after)| (after, before)),
+        }
+    }
 }
 
+/// Cursor before and after the edit, the context of the edit and whether the edit restored the links by itself
+pub type Restore = ((usize, usize), (usize, usize), EditContext, bool);
+
 #[derive(Clone, Debug)]
 pub struct History {
     index: usize,
     max_items: usize,
     edits: VecDeque<Edit>,
+    group: Option<Vec<Edit>>,
+    applied: Option<Edit>,
 }
 
 impl History {
@@ -206,10 +477,87 @@ impl History {
             index: 0,
             max_items,
             edits: VecDeque::new(),
+            group: None,
+            applied: None,
+        }
+    }
+
+    /// Start collecting pushed edits into one group until [`History::end_group`] is called.
+    pub fn begin_group(&mut self) {
+        self.group.get_or_insert_with(Vec::new);
+    }
+
+    /// Whether edits are being collected into a group by [`History::begin_group`].
+    pub fn is_grouping(&self) -> bool {
+        self.group.is_some()
+    }
+
+    /// Push the edits collected since [`History::begin_group`] as one edit. The links before and after the edits are
+    /// restored on undo and redo instead of the link bookkeeping of each edit.
+    pub fn end_group(&mut self, links_before: BTreeMap<usize, Link>, links_after: BTreeMap<usize, Link>) {
+        let edits = match self.group.take() {
+            Some(edits) if !edits.is_empty() => edits,
+            _ => return,
+        };
+        let first = &edits[0];
+        let (before, context) = (first.before.clone(), first.context);
+        let last = &edits[edits.len() - 1];
+        let after = last.after.clone();
+        let soft_breaks = if edits.iter().any(|e| e.soft_breaks.is_some()) {
+            let before = first.soft_breaks.as_ref().map(|s| s.0.clone()).unwrap_or_default();
+            let after = last.soft_breaks.as_ref().map(|s| s.1.clone()).unwrap_or_default();
+            Some((before, after))
+        } else {
+            None
+        };
+        let mut link_keys = None;
+        for keys in edits.iter().filter_map(|e| e.link_keys.as_ref()) {
+            merge_link_keys(&mut link_keys, keys);
+        }
+        let kind = EditKind::Group((edits, links_before, links_after));
+        let mut edit = Edit::new(kind, before, after, context);
+        edit.soft_breaks = soft_breaks;
+        edit.link_keys = link_keys;
+        self.push(edit);
+    }
+
+    /// Merge the last edit into the edit before it when both are groups so that a group ended in several steps is
+    /// undone as one edit. Nothing is merged while grouping or when the last edit was undone.
+    pub(crate) fn merge_last_groups(&mut self) {
+        let len = self.edits.len();
+        if self.group.is_some() || self.index != len || len < 2 {
+            return;
+        }
+        let is_group = |e: &Edit| matches!(e.kind, EditKind::Group(_));
+        if !is_group(&self.edits[len - 2]) || !is_group(&self.edits[len - 1]) {
+            return;
+        }
+        let last = self.edits.pop_back().expect("last edit should be present");
+        self.index -= 1;
+        let prev = self.edits.back_mut().expect("previous edit should be present");
+        if let (EditKind::Group((edits, _, links_after)), EditKind::Group((more, _, after))) =
+            (&mut prev.kind, last.kind)
+        {
+            edits.extend(more);
+            *links_after = after;
+        }
+        prev.after = last.after;
+        if prev.soft_breaks.is_some() || last.soft_breaks.is_some() {
+            let before = prev.soft_breaks.take().map(|s| s.0).unwrap_or_default();
+            let after = last.soft_breaks.map(|s| s.1).unwrap_or_default();
+            prev.soft_breaks = Some((before, after));
+        }
+        if let Some(keys) = &last.link_keys {
+            merge_link_keys(&mut prev.link_keys, keys);
         }
     }
 
     pub fn push(&mut self, edit: Edit) {
+        if let Some(group) = &mut self.group {
+            group.push(edit);
+            return;
+        }
+
         if self.max_items == 0 {
             return;
         }
@@ -227,29 +575,50 @@ impl History {
         self.edits.push_back(edit);
     }
 
+    /// Record the stable keys of the links before and after the last pushed edit. See [`Edit::set_link_keys`].
+    pub(crate) fn set_link_keys(&mut self, before: KeyChanges, after: KeyChanges) {
+        let edit = match &mut self.group {
+            Some(group) => group.last_mut(),
+            None => match self.index.checked_sub(1) {
+                Some(i) => self.edits.get_mut(i),
+                None => None,
+            },
+        };
+        if let Some(edit) = edit {
+            edit.set_link_keys(before, after);
+        }
+    }
+
     pub fn redo(
         &mut self,
         lines: &mut Vec<String>,
-        links: &mut HashMap<usize, Link>
-    ) -> Option<((usize, usize), (usize, usize))> {
+        links: &mut BTreeMap<usize, Link>
+    ) -> Option<Restore> {
         if self.index == self.edits.len() {
             return None;
         }
         let edit = &mut self.edits[self.index];
         edit.redo(lines, links);
+        self.applied = Some(edit.clone());
         self.index += 1;
-        Some((edit.cursor_before(), edit.cursor_after()))
+        Some((edit.cursor_before(), edit.cursor_after(), edit.context(), edit.restores_links()))
     }
 
     pub fn undo(
         &mut self,
         lines: &mut Vec<String>,
-        links: &mut HashMap<usize, Link>
-    ) -> Option<((usize, usize), (usize, usize))> {
+        links: &mut BTreeMap<usize, Link>
+    ) -> Option<Restore> {
         self.index = self.index.checked_sub(1)?;
         let edit = &mut self.edits[self.index];
         edit.undo(lines, links);
-        Some((edit.cursor_before(), edit.cursor_after()))
+        self.applied = Some(edit.inverted());
+        Some((edit.cursor_before(), edit.cursor_after(), edit.context(), edit.restores_links()))
+    }
+
+    /// Take the edit applied to the text by the last undo or redo. For undo, it is the inverted edit.
+    pub fn take_applied(&mut self) -> Option<Edit> {
+        self.applied.take()
     }
 
     pub fn max_items(&self) -> usize {
@@ -257,6 +626,15 @@ impl History {
     }
 }
 
+// Keys changed by several edits are restored to the state before the first and after the last of them
+fn merge_link_keys(into: &mut Option<(KeyChanges, KeyChanges)>, (before, after): &(KeyChanges, KeyChanges)) {
+    let (keys_before, keys_after) = into.get_or_insert_with(Default::default);
+    for (id, key) in before {
+        keys_before.entry(*id).or_insert_with(|| key.clone());
+    }
+    keys_after.extend(after.iter().map(|(id, key)| (*id, key.clone())));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -709,7 +1087,7 @@ mod tests {
                 Pos::new(row, col, offset)
             };
             let mut lines: Vec<_> = before.iter().map(|s| s.to_string()).collect();
-            let mut links: HashMap<usize, Link> = HashMap::new();
+            let mut links: BTreeMap<usize, Link> = BTreeMap::new();
             let chunk: Vec<_> = input.iter().map(|s| s.to_string()).collect();
             let after_pos = {
                 let row = row + input.len() - 1;
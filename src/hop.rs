@@ -1,30 +1,53 @@
-use std::{cell::{RefCell, Cell}, rc::Rc};
-
 use crate::ratatui::style::{Color, Style};
-use log::info;
 use regex::Regex;
+use std::ops::Range;
 
-#[derive(Clone, Debug)]
-pub struct MatchIndex {
-    pub(crate) idx: usize,
-    pub(crate) pos: (usize, usize)
+// Labels start at 11 to ensure all labels are double-digit
+const FIRST_LABEL: usize = 11;
+
+/// A match of the hop pattern labeled with a number. Typing the label and passing it to
+/// [`TextArea::complete_hop`](crate::TextArea::complete_hop) moves the cursor to the match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HopTarget {
+    /// Label shown over the match.
+    pub label: usize,
+    /// Row of the match.
+    pub row: usize,
+    /// Character column where the match starts.
+    pub start_col: usize,
+    /// Character column where the match ends (exclusive).
+    pub end_col: usize,
+    // Byte range of the match for rendering
+    pub(crate) start: usize,
+    pub(crate) end: usize,
 }
 
-impl MatchIndex {
-    fn new(idx: usize,  pos: (usize, usize)) -> Self {
-        Self {
-            idx,
-            pos,
-        }
-    }
+/// State of hopping in a textarea. See [`TextArea::hop_state`](crate::TextArea::hop_state).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HopState {
+    /// Not hopping.
+    Inactive,
+    /// Hopping was started but no target is labeled yet because the pattern is not set or has no match.
+    Pending,
+    /// Targets are labeled and waiting for a label.
+    Labeled,
+}
+
+/// The result of a completed hop returned by [`TextArea::complete_hop`](crate::TextArea::complete_hop).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HopOutcome {
+    /// The cursor position `(row, col)` before hopping.
+    pub from: (usize, usize),
+    /// The target the cursor hopped to.
+    pub target: HopTarget,
 }
 
 #[derive(Clone, Debug)]
-pub struct Hop {
-    pub pat: Option<Regex>,
-    pub style: Style,
-    pub count: Cell<usize>,
-    pub match_indexes: Rc<RefCell<Vec<MatchIndex>>>,
+pub(crate) struct Hop {
+    pat: Option<Regex>,
+    pub(crate) style: Style,
+    active: bool,
+    targets: Vec<HopTarget>,
 }
 
 impl Default for Hop {
@@ -32,24 +55,18 @@ impl Default for Hop {
         Self {
             pat: None,
             style: Style::default().bg(Color::Red),
-            // count set to 10 to ensure all idx are double-digit
-            count: Cell::new(10),
-            match_indexes: Rc::new(RefCell::new(Vec::new())),
+            active: false,
+            targets: vec![],
         }
     }
 }
 
 impl Hop {
-    pub fn matches<'a>(
-        &'a self,
-        line: &'a str,
-    ) -> Option<impl Iterator<Item = (usize, usize)> + 'a> {
-        let pat = self.pat.as_ref()?;
-        let matches = pat.find_iter(line).map(|m| (m.start(), m.end()));
-        Some(matches)
+    pub(crate) fn pattern(&self) -> Option<&Regex> {
+        self.pat.as_ref()
     }
 
-    pub fn set_pattern(&mut self, query: &str) -> Result<(), regex::Error> {
+    pub(crate) fn set_pattern(&mut self, query: &str) -> Result<(), regex::Error> {
         match &self.pat {
             Some(r) if r.as_str() == query => {}
             _ if query.is_empty() => self.pat = None,
@@ -58,30 +75,100 @@ impl Hop {
         Ok(())
     }
 
-    pub fn clear_pattern(&mut self) {
+    pub(crate) fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub(crate) fn clear(&mut self) {
         self.pat = None;
+        self.active = false;
+        self.targets.clear();
     }
 
-    pub fn reset_count(&self) {
-        self.count.set(10);
+    pub(crate) fn is_active(&self) -> bool {
+        self.active
     }
 
-    pub fn set_count(&self, count: usize) {
-        self.count.set(count);
+    pub(crate) fn state(&self) -> HopState {
+        match (self.active, self.targets.is_empty()) {
+            (false, _) => HopState::Inactive,
+            (true, true) => HopState::Pending,
+            (true, false) => HopState::Labeled,
+        }
+    }
+
+    pub(crate) fn targets(&self) -> &[HopTarget] {
+        &self.targets
     }
 
-    pub fn get_count(&self) -> usize {
-        self.count.get()
+    pub(crate) fn target(&self, label: usize) -> Option<HopTarget> {
+        self.targets.iter().find(|t| t.label == label).copied()
     }
 
-    pub fn set_match_indexes(&self, matches: Vec<(usize, usize)>, row: usize) {
-        for (start, count) in matches {
-            let match_idx = MatchIndex::new(count, (row, start));
-            self.match_indexes.borrow_mut().push(match_idx);
+    /// Label the non-empty matches of the pattern in the rows in order.
+    pub(crate) fn label(&mut self, lines: &[String], rows: Range<usize>) {
+        self.targets.clear();
+        let pat = match (&self.pat, self.active) {
+            (Some(pat), true) => pat,
+            _ => return,
+        };
+        let mut label = FIRST_LABEL;
+        for row in rows {
+            let line = &lines[row];
+            for m in pat.find_iter(line).filter(|m| m.start() != m.end()) {
+                let start_col = line[..m.start()].chars().count();
+                let end_col = start_col + m.as_str().chars().count();
+                self.targets.push(HopTarget {
+                    label,
+                    row,
+                    start_col,
+                    end_col,
+                    start: m.start(),
+                    end: m.end(),
+                });
+                label += 1;
+            }
         }
     }
 
-    pub fn clear_match_indexes(&self) {
-        self.match_indexes.borrow_mut().drain(..);
+    pub(crate) fn row_targets(
+        &self,
+        row: usize,
+    ) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.targets
+            .iter()
+            .filter(move |t| t.row == row)
+            .map(|t| (t.start, t.end, t.label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label() {
+        let lines = ["aé foo".to_string(), "foo x foo".to_string()];
+        let mut hop = Hop::default();
+        hop.set_pattern("foo").unwrap();
+        hop.label(&lines, 0..2);
+        assert!(hop.targets().is_empty());
+        assert_eq!(hop.state(), HopState::Inactive);
+
+        hop.start();
+        hop.label(&lines, 0..2);
+        assert_eq!(hop.state(), HopState::Labeled);
+        let found: Vec<_> = hop
+            .targets()
+            .iter()
+            .map(|t| (t.label, t.row, t.start_col, t.end_col))
+            .collect();
+        assert_eq!(found, [(11, 0, 3, 6), (12, 1, 0, 3), (13, 1, 6, 9)]);
+        assert_eq!(hop.targets()[0].start, 4);
+        assert_eq!(hop.row_targets(1).count(), 2);
+
+        hop.clear();
+        assert_eq!(hop.state(), HopState::Inactive);
+        assert!(hop.pattern().is_none());
     }
 }
@@ -0,0 +1,74 @@
+use crate::util::Pos;
+use std::fmt;
+use std::sync::Arc;
+
+type IncludeFn = dyn Fn(&str) -> Option<Vec<String>> + Send + Sync;
+
+/// Callback resolving the ID of an include line into the lines to show beneath it.
+#[derive(Clone)]
+pub(crate) struct IncludeResolver(Arc<IncludeFn>);
+
+impl IncludeResolver {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn(&str) -> Option<Vec<String>> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn resolve(&self, id: &str) -> Option<Vec<String>> {
+        (self.0)(id)
+    }
+}
+
+impl fmt::Debug for IncludeResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncludeResolver").finish_non_exhaustive()
+    }
+}
+
+/// Parse an include line `!include(id)` and return the ID. Whitespaces around the marker are allowed.
+pub(crate) fn parse_include(line: &str) -> Option<&str> {
+    let id = line
+        .trim()
+        .strip_prefix("!include(")?
+        .strip_suffix(')')?
+        .trim();
+    if id.is_empty() || id.contains(|c| c == '(' || c == ')') {
+        return None;
+    }
+    Some(id)
+}
+
+pub(crate) fn collect_includes(lines: &[String]) -> Vec<(String, Pos)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(row, line)| {
+            let id = parse_include(line)?;
+            let offset = line.len() - line.trim_start().len();
+            let col = line[..offset].chars().count();
+            Some((id.to_string(), Pos::new(row, col, offset)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_include_lines() {
+        for (line, want) in [
+            ("!include(note-1)", Some("note-1")),
+            ("  !include( a b ) ", Some("a b")),
+            ("!include()", None),
+            ("!include(a", None),
+            ("!include(a(b))", None),
+            ("see !include(a)", None),
+            ("include(a)", None),
+        ] {
+            assert_eq!(parse_include(line), want, "{line:?}");
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+/// A table of compose sequences. Each sequence is a dead key followed by a base character, like `'` then `e` for `é`.
+///
+/// [`ComposeTable::default`] provides common accents (`'`, `` ` ``, `^`, `"`, `~`) on Latin vowels, `~n` and `,c`.
+/// Set the table with [`TextArea::set_compose_table`](crate::TextArea::set_compose_table) to enable composition.
+/// ```
+/// use tuipaz_textarea::ComposeTable;
+///
+/// let mut table = ComposeTable::default();
+/// assert_eq!(table.get('\'', 'e'), Some('é'));
+///
+/// table.insert('/', 'o', 'ø');
+/// assert_eq!(table.get('/', 'o'), Some('ø'));
+/// assert!(table.is_dead_key('/'));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComposeTable {
+    sequences: HashMap<(char, char), char>,
+}
+
+const DEFAULT_SEQUENCES: &[(char, &str, &str)] = &[
+    ('\'', "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+    ('`', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    ('^', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ('"', "aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+    ('~', "anoANO", "ãñõÃÑÕ"),
+    (',', "cC", "çÇ"),
+];
+
+impl Default for ComposeTable {
+    fn default() -> Self {
+        let mut table = Self::empty();
+        for (dead, bases, composed) in DEFAULT_SEQUENCES {
+            for (base, c) in bases.chars().zip(composed.chars()) {
+                table.insert(*dead, base, c);
+            }
+        }
+        table
+    }
+}
+
+impl ComposeTable {
+    /// Create a table without any sequence.
+    pub fn empty() -> Self {
+        Self {
+            sequences: HashMap::new(),
+        }
+    }
+
+    /// Add a sequence composing `dead` followed by `base` into `composed`.
+    pub fn insert(&mut self, dead: char, base: char, composed: char) {
+        self.sequences.insert((dead, base), composed);
+    }
+
+    /// Remove the sequence of `dead` followed by `base`.
+    pub fn remove(&mut self, dead: char, base: char) -> Option<char> {
+        self.sequences.remove(&(dead, base))
+    }
+
+    /// Get the character composed from `dead` followed by `base`.
+    pub fn get(&self, dead: char, base: char) -> Option<char> {
+        self.sequences.get(&(dead, base)).copied()
+    }
+
+    /// Check if the character starts any sequence in the table.
+    pub fn is_dead_key(&self, c: char) -> bool {
+        self.sequences.keys().any(|(d, _)| *d == c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_table() {
+        let table = ComposeTable::default();
+        assert_eq!(table.get('`', 'A'), Some('À'));
+        assert_eq!(table.get('~', 'n'), Some('ñ'));
+        assert_eq!(table.get(',', 'C'), Some('Ç'));
+        assert_eq!(table.get('\'', 'x'), None);
+        assert!(table.is_dead_key('^'));
+        assert!(!table.is_dead_key('a'));
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Input, Key};
+use super::{Input, InputKind, Key};
 use crate::crossterm::event::{
     Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
 };
@@ -26,6 +26,7 @@ impl From<KeyCode> for Key {
             KeyCode::Up => Key::Up,
             KeyCode::Down => Key::Down,
             KeyCode::Tab => Key::Tab,
+            KeyCode::BackTab => Key::BackTab,
             KeyCode::Delete => Key::Delete,
             KeyCode::Home => Key::Home,
             KeyCode::End => Key::End,
@@ -41,12 +42,13 @@ impl From<KeyCode> for Key {
 impl From<KeyEvent> for Input {
     /// Convert [`crossterm::event::KeyEvent`] into [`Input`].
     fn from(key: KeyEvent) -> Self {
-        if key.kind == KeyEventKind::Release {
-            // On Windows or when `crossterm::event::PushKeyboardEnhancementFlags` is set,
-            // key release event can be reported. Ignore it. (#14)
-            return Self::default();
-        }
-
+        // On Windows or when `crossterm::event::PushKeyboardEnhancementFlags` is set, key repeat and key release
+        // events can be reported. Keep the kind so that they are not handled as fresh presses. (#14)
+        let kind = match key.kind {
+            KeyEventKind::Press => InputKind::Press,
+            KeyEventKind::Repeat => InputKind::Repeat,
+            KeyEventKind::Release => InputKind::Release,
+        };
         let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
         let alt = key.modifiers.contains(KeyModifiers::ALT);
         let shift = key.modifiers.contains(KeyModifiers::SHIFT);
@@ -57,6 +59,7 @@ impl From<KeyEvent> for Input {
             ctrl,
             alt,
             shift,
+            kind,
         }
     }
 }
@@ -84,6 +87,7 @@ impl From<MouseEvent> for Input {
             ctrl,
             alt,
             shift,
+            kind: InputKind::Press,
         }
     }
 }
@@ -151,6 +155,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn key_event_kind_to_input() {
+        for (kind, to) in [
+            (KeyEventKind::Press, InputKind::Press),
+            (KeyEventKind::Repeat, InputKind::Repeat),
+            (KeyEventKind::Release, InputKind::Release),
+        ] {
+            let from = KeyEvent {
+                kind,
+                ..key_event(KeyCode::Char('a'), KeyModifiers::empty())
+            };
+            let input = Input::from(from);
+            assert_eq!(input.key, Key::Char('a'), "{:?}", from);
+            assert_eq!(input.kind, to, "{:?}", from);
+        }
+    }
+
     #[test]
     fn mouse_to_input() {
         for (from, to) in [
@@ -211,7 +232,14 @@ mod tests {
     fn ignore_key_release_event() {
         let mut from = key_event(KeyCode::Char('a'), KeyModifiers::empty());
         from.kind = KeyEventKind::Release;
-        let to = input(Key::Null, false, false, false);
+        let to = Input {
+            kind: InputKind::Release,
+            ..input(Key::Char('a'), false, false, false)
+        };
         assert_eq!(Input::from(from), to, "{:?} -> {:?}", from, to);
+
+        let mut textarea = crate::TextArea::default();
+        assert!(!textarea.input(from));
+        assert_eq!(textarea.lines(), [""]);
     }
 }
@@ -1,3 +1,4 @@
+mod compose;
 #[cfg(any(feature = "crossterm", feature = "tuirs-crossterm"))]
 mod crossterm;
 #[cfg(any(feature = "termion", feature = "tuirs-termion"))]
@@ -8,11 +9,13 @@ mod termwiz;
 #[cfg(feature = "arbitrary")]
 use arbitrary::Arbitrary;
 
+pub use compose::ComposeTable;
+
 /// Backend-agnostic key input kind.
 ///
 /// This type is marked as `#[non_exhaustive]` since more keys may be supported in the future.
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
 pub enum Key {
     /// Normal letter key input
@@ -33,6 +36,8 @@ pub enum Key {
     Down,
     /// Tab key
     Tab,
+    /// Shift+Tab key, which is reported as a separate key by some terminals
+    BackTab,
     /// Delete key
     Delete,
     /// Home key
@@ -88,7 +93,7 @@ impl Default for Key {
 /// Creating `Input` instance directly can cause backend-agnostic input as follows.
 ///
 /// ```
-/// use tuipaz_textarea::{TextArea, Input, Key};
+/// use tuipaz_textarea::{TextArea, Input, InputKind, Key};
 ///
 /// let mut textarea = TextArea::default();
 ///
@@ -98,6 +103,7 @@ impl Default for Key {
 ///     ctrl: true,
 ///     alt: false,
 ///     shift: false,
+///     kind: InputKind::Press,
 /// });
 /// ```
 #[derive(Debug, Clone, Copy, Default, PartialEq, Hash)]
@@ -111,6 +117,112 @@ pub struct Input {
     pub alt: bool,
     /// Shift modifier key. `true` means Alt key was pressed.
     pub shift: bool,
+    /// Kind of the key event. Backends which don't report the kind always set [`InputKind::Press`].
+    pub kind: InputKind,
+}
+
+/// Kind of a key event. Some terminals report key repeats and key releases in addition to key presses.
+///
+/// [`TextArea`](crate::TextArea) always ignores [`InputKind::Release`]. How [`InputKind::Repeat`] is handled can be
+/// configured with [`TextArea::set_repeat_behavior`](crate::TextArea::set_repeat_behavior).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub enum InputKind {
+    /// The key was pressed.
+    Press,
+    /// The key is held and the terminal repeated the key event.
+    Repeat,
+    /// The key was released.
+    Release,
+}
+
+impl Default for InputKind {
+    fn default() -> Self {
+        InputKind::Press
+    }
+}
+
+/// How [`TextArea`](crate::TextArea) handles repeated key events ([`InputKind::Repeat`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RepeatBehavior {
+    /// Handle repeats the same as presses. This is the default.
+    Press,
+    /// Ignore repeats.
+    Ignore,
+    /// Handle repeats the same as presses, but move the cursor by the given number of steps when a cursor key is
+    /// repeated. This makes holding arrow keys move faster.
+    Accelerate(u8),
+}
+
+impl Default for RepeatBehavior {
+    fn default() -> Self {
+        RepeatBehavior::Press
+    }
+}
+
+/// What happened as the result of handling a key input with [`TextArea::input_result`](crate::TextArea::input_result)
+/// or [`TextInput::input_result`](crate::TextInput::input_result).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputResult {
+    /// The input did not modify text contents. The cursor or the selection may have changed.
+    Unchanged,
+    /// The input modified text contents.
+    Changed,
+    /// Enter was pressed while [`EnterBehavior::Submit`] is set, or in a [`TextInput`](crate::TextInput). The text
+    /// was not modified.
+    Submitted,
+    /// Esc was pressed in a [`TextInput`](crate::TextInput). The text was not modified.
+    Cancelled,
+}
+
+impl InputResult {
+    /// Returns `true` when the text contents were modified by the input.
+    pub fn is_changed(self) -> bool {
+        self == InputResult::Changed
+    }
+}
+
+/// Detailed effects of handling a key input with [`TextArea::input_ext`](crate::TextArea::input_ext). Each field
+/// reports one kind of change so that hosts can react to an input without diffing the textarea state themselves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct InputEffects {
+    /// Text contents were modified.
+    pub text_changed: bool,
+    /// The cursor moved.
+    pub cursor_moved: bool,
+    /// The selected range was started, changed or cancelled.
+    pub selection_changed: bool,
+    /// A new link was created.
+    pub link_created: bool,
+    /// One or more links were deleted.
+    pub link_deleted: bool,
+    /// The cursor moved onto the link with this ID.
+    pub link_entered: Option<usize>,
+    /// Enter was pressed while [`EnterBehavior::Submit`] is set.
+    pub submitted: bool,
+}
+
+impl InputEffects {
+    /// Returns `true` when the input had no effect at all.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How [`TextArea`](crate::TextArea) handles the Enter key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnterBehavior {
+    /// Enter inserts a newline. This is the default.
+    Newline,
+    /// Enter and Ctrl+M are reported as [`InputResult::Submitted`] without modifying the text, and Shift+Enter
+    /// inserts a newline. This is useful for chat-style message boxes.
+    Submit,
+}
+
+impl Default for EnterBehavior {
+    fn default() -> Self {
+        EnterBehavior::Newline
+    }
 }
 
 #[cfg(test)]
@@ -124,6 +236,7 @@ mod tests {
             ctrl,
             alt,
             shift,
+            kind: InputKind::Press,
         }
     }
 
@@ -1,4 +1,4 @@
-use super::{Input, Key};
+use super::{Input, InputKind, Key};
 use termion::event::{Event, Key as KeyEvent, MouseButton, MouseEvent};
 
 impl From<Event> for Input {
@@ -37,7 +37,7 @@ impl From<KeyEvent> for Input {
             KeyEvent::End => Key::End,
             KeyEvent::PageUp => Key::PageUp,
             KeyEvent::PageDown => Key::PageDown,
-            KeyEvent::BackTab => Key::Tab,
+            KeyEvent::BackTab => Key::BackTab,
             KeyEvent::Delete => Key::Delete,
             KeyEvent::Esc => Key::Esc,
             KeyEvent::F(x) => Key::F(x),
@@ -49,6 +49,7 @@ impl From<KeyEvent> for Input {
             ctrl,
             alt,
             shift: false,
+            kind: InputKind::Press,
         }
     }
 }
@@ -77,6 +78,7 @@ impl From<MouseEvent> for Input {
             ctrl: false,
             alt: false,
             shift: false,
+            kind: InputKind::Press,
         }
     }
 }
@@ -104,7 +106,7 @@ mod tests {
             (KeyEvent::Char('\n'), input(Key::Enter, false, false, false)),
             (KeyEvent::Char('\r'), input(Key::Enter, false, false, false)),
             (KeyEvent::F(1), input(Key::F(1), false, false, false)),
-            (KeyEvent::BackTab, input(Key::Tab, false, false, false)),
+            (KeyEvent::BackTab, input(Key::BackTab, false, false, false)),
             (KeyEvent::Null, input(Key::Null, false, false, false)),
         ] {
             assert_eq!(Input::from(from), to, "{:?} -> {:?}", from, to);
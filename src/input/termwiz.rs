@@ -1,4 +1,4 @@
-use super::{Input, Key};
+use super::{Input, InputKind, Key};
 use termwiz::input::{
     InputEvent, KeyCode, KeyEvent, Modifiers, MouseButtons, MouseEvent, PixelMouseEvent,
 };
@@ -56,6 +56,7 @@ impl From<KeyEvent> for Input {
             ctrl,
             alt,
             shift,
+            kind: InputKind::Press,
         }
     }
 }
@@ -93,6 +94,7 @@ impl From<MouseEvent> for Input {
             ctrl,
             alt,
             shift,
+            kind: InputKind::Press,
         }
     }
 }
@@ -116,6 +118,7 @@ impl From<PixelMouseEvent> for Input {
             ctrl,
             alt,
             shift,
+            kind: InputKind::Press,
         }
     }
 }
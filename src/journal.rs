@@ -0,0 +1,334 @@
+use crate::history::{Edit, EditContext, EditKind};
+use crate::util::Pos;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// Each edit is written as one line of tab-separated fields: the kind, the positions before and after the edit as
+// `row,col,offset`, and the text of the edit. Tabs, newlines and backslashes in the text are escaped. Links are not
+// journaled since they are restored by the host from its own storage.
+
+/// Journal file which the edits applied to a textarea are appended to. See
+/// [`TextArea::set_journal`](crate::TextArea::set_journal).
+#[derive(Debug)]
+pub(crate) struct Journal {
+    path: PathBuf,
+    // `None` for a clone so that two textareas do not write to the same journal
+    file: Option<File>,
+    error: Option<io::Error>,
+}
+
+impl Clone for Journal {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            file: None,
+            error: None,
+        }
+    }
+}
+
+impl Journal {
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Some(file),
+            error: None,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append the edit. After writing failed once, nothing is written until the journal is reset.
+    pub(crate) fn append(&mut self, edit: &Edit) {
+        let file = match (&mut self.file, &self.error) {
+            (Some(file), None) => file,
+            _ => return,
+        };
+        let mut entries = String::new();
+        encode_edit(edit, &mut entries);
+        if entries.is_empty() {
+            return;
+        }
+        if let Err(err) = file.write_all(entries.as_bytes()) {
+            self.error = Some(err);
+        }
+    }
+
+    /// Truncate the journal after the text was saved.
+    pub(crate) fn reset(&mut self) -> io::Result<()> {
+        self.error = None;
+        self.file = Some(File::create(&self.path)?);
+        Ok(())
+    }
+
+    pub(crate) fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+}
+
+fn escape(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+// Groups are flattened since redoing a group redoes its edits in order
+fn encode_edit(edit: &Edit, out: &mut String) {
+    let (kind, before, after) = edit.parts();
+    let mut buf = [0; 4];
+    let splice;
+    let (tag, texts): (&str, Vec<&str>) = match kind {
+        EditKind::InsertChar((c, _)) => ("ic", vec![c.encode_utf8(&mut buf)]),
+        EditKind::DeleteChar((c, _)) => ("dc", vec![c.encode_utf8(&mut buf)]),
+        EditKind::InsertLine((s, _)) => ("il", vec![s]),
+        EditKind::DeleteLine((s, _)) => ("dl", vec![s]),
+        EditKind::InsertStr((s, _)) => ("is", vec![s]),
+        EditKind::DeleteStr((s, _)) => ("ds", vec![s]),
+        EditKind::InsertChunk((c, _)) => ("ik", c.iter().map(String::as_str).collect()),
+        EditKind::DeleteChunk((c, _)) => ("dk", c.iter().map(String::as_str).collect()),
+        EditKind::InsertNewline => ("in", vec![]),
+        EditKind::DeleteNewline => ("dn", vec![]),
+        // Only the lines after the edit are needed to redo it
+        EditKind::ReplaceLines((_, lines)) => ("rl", lines.iter().map(String::as_str).collect()),
+        // The replaced rows are written as `row,count` since only the number of them is needed to redo it
+        EditKind::SpliceLines((row, old, new, _, _)) => {
+            splice = format!("{},{}", row, old.len());
+            let rows = new.iter().map(String::as_str);
+            ("sl", std::iter::once(splice.as_str()).chain(rows).collect())
+        }
+        EditKind::Group((edits, _, _)) => {
+            for edit in edits {
+                encode_edit(edit, out);
+            }
+            return;
+        }
+        EditKind::CreateLink(_) | EditKind::DeleteLink(_) | EditKind::UpdateLink(_) => return,
+    };
+    out.push_str(tag);
+    for pos in [before, after] {
+        out.push_str(&format!("\t{},{},{}", pos.row, pos.col, pos.offset));
+    }
+    for text in texts {
+        out.push('\t');
+        escape(text, out);
+    }
+    out.push('\n');
+}
+
+fn parse_pos(s: &str) -> Option<Pos> {
+    let mut nums = s.split(',').map(|n| n.parse().ok());
+    let pos = Pos::new(nums.next()??, nums.next()??, nums.next()??);
+    nums.next().is_none().then(|| pos)
+}
+
+fn decode_edit(line: &str) -> Option<Edit> {
+    let mut fields = line.split('\t');
+    let tag = fields.next()?;
+    let before = parse_pos(fields.next()?)?;
+    let after = parse_pos(fields.next()?)?;
+    let texts = fields.map(unescape).collect::<Option<Vec<_>>>()?;
+    let text = || match texts.as_slice() {
+        [s] => Some(s.clone()),
+        _ => None,
+    };
+    let chunk = || (texts.len() > 1).then(|| texts.clone());
+    let char_ = || {
+        let s = text()?;
+        let mut chars = s.chars();
+        let c = chars.next()?;
+        chars.next().is_none().then(|| c)
+    };
+    let kind = match tag {
+        "ic" => EditKind::InsertChar((char_()?, None)),
+        "dc" => EditKind::DeleteChar((char_()?, None)),
+        "il" => EditKind::InsertLine((text()?, None)),
+        "dl" => EditKind::DeleteLine((text()?, None)),
+        "is" => EditKind::InsertStr((text()?, None)),
+        "ds" => EditKind::DeleteStr((text()?, None)),
+        "ik" => EditKind::InsertChunk((chunk()?, None)),
+        "dk" => EditKind::DeleteChunk((chunk()?, None)),
+        "in" if texts.is_empty() => EditKind::InsertNewline,
+        "dn" if texts.is_empty() => EditKind::DeleteNewline,
+        "rl" if !texts.is_empty() => EditKind::ReplaceLines((vec![], texts)),
+        "sl" if !texts.is_empty() => {
+            let (row, count) = texts[0].split_once(',')?;
+            let (row, count) = (row.parse().ok()?, count.parse().ok()?);
+            EditKind::SpliceLines((
+                row,
+                vec![String::new(); count],
+                texts[1..].to_vec(),
+                vec![],
+                vec![],
+            ))
+        }
+        _ => return None,
+    };
+    Some(Edit::new(kind, before, after, EditContext::default()))
+}
+
+// Check the edit can be redone on the lines without panicking
+fn can_apply(edit: &Edit, lines: &[String]) -> bool {
+    let (kind, before, after) = edit.parts();
+    let at = |pos: &Pos| {
+        lines
+            .get(pos.row)
+            .map_or(false, |l| l.is_char_boundary(pos.offset))
+    };
+    match kind {
+        EditKind::InsertChar(_)
+        | EditKind::InsertStr(_)
+        | EditKind::InsertChunk(_)
+        | EditKind::InsertNewline => at(before),
+        EditKind::InsertLine(_) => before.row <= lines.len(),
+        EditKind::DeleteChar((c, _)) => {
+            at(after) && lines[after.row][after.offset..].starts_with(*c)
+        }
+        EditKind::DeleteStr((s, _)) => {
+            at(after) && lines[after.row][after.offset..].starts_with(s.as_str())
+        }
+        EditKind::DeleteLine(_) => after.row < lines.len(),
+        EditKind::DeleteChunk((c, _)) => {
+            at(after)
+                && after.row + c.len() <= lines.len()
+                && lines[after.row + c.len() - 1].starts_with(c[c.len() - 1].as_str())
+        }
+        EditKind::DeleteNewline => before.row > 0 && before.row < lines.len(),
+        EditKind::ReplaceLines(_) => true,
+        EditKind::SpliceLines((row, old, _, _, _)) => row + old.len() <= lines.len(),
+        EditKind::Group(_)
+        | EditKind::CreateLink(_)
+        | EditKind::DeleteLink(_)
+        | EditKind::UpdateLink(_) => false,
+    }
+}
+
+/// Replay the journal written by [`TextArea::set_journal`](crate::TextArea::set_journal) on the lines of the text
+/// when the journal was started or last reset, and return the recovered lines. An incomplete entry at the end of the
+/// journal, which is left when the application crashed while writing it, is ignored. An error with
+/// [`io::ErrorKind::InvalidData`] is returned when an entry is broken or does not match the lines.
+///
+/// This function is available when the `journal` feature is enabled.
+/// ```no_run
+/// use tuipaz_textarea::{recover_from_journal, TextArea};
+///
+/// let saved = std::fs::read_to_string("note.md").unwrap();
+/// let base: Vec<String> = saved.lines().map(String::from).collect();
+/// let lines = recover_from_journal("note.md.journal", base).unwrap();
+/// let textarea = TextArea::from(lines);
+/// ```
+pub fn recover_from_journal(
+    path: impl AsRef<Path>,
+    base_lines: Vec<String>,
+) -> io::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)?;
+    let mut lines = base_lines;
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    let mut links = BTreeMap::new();
+    // The last entry is incomplete when it does not end with a newline
+    let complete = match content.rfind('\n') {
+        Some(i) => &content[..i],
+        None => return Ok(lines),
+    };
+    for (i, entry) in complete.split('\n').enumerate() {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid journal entry at line {}", i + 1),
+            )
+        };
+        let mut edit = decode_edit(entry).ok_or_else(invalid)?;
+        if !can_apply(&edit, &lines) {
+            return Err(invalid());
+        }
+        edit.redo(&mut lines, &mut links);
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(kind: EditKind, before: (usize, usize, usize), after: (usize, usize, usize)) -> Edit {
+        let pos = |(r, c, o)| Pos::new(r, c, o);
+        Edit::new(kind, pos(before), pos(after), EditContext::default())
+    }
+
+    #[test]
+    fn escape_roundtrip() {
+        for s in ["", "abc", "a\tb\\n\nc\r", "\\"] {
+            let mut escaped = String::new();
+            escape(s, &mut escaped);
+            assert!(!escaped.contains(|c| c == '\t' || c == '\n'));
+            assert_eq!(unescape(&escaped).as_deref(), Some(s));
+        }
+        assert_eq!(unescape("\\x"), None);
+    }
+
+    #[test]
+    fn replay() {
+        let edits = [
+            edit(
+                EditKind::InsertStr(("hé\t".to_string(), None)),
+                (0, 0, 0),
+                (0, 3, 4),
+            ),
+            edit(EditKind::InsertNewline, (0, 3, 4), (1, 0, 0)),
+            edit(
+                EditKind::InsertChunk((vec!["x".to_string(), "y".to_string()], None)),
+                (1, 0, 0),
+                (2, 1, 1),
+            ),
+            edit(EditKind::DeleteChar(('é', None)), (0, 2, 3), (0, 1, 1)),
+        ];
+        let mut journal = String::new();
+        for e in &edits {
+            encode_edit(e, &mut journal);
+        }
+        // Incomplete entry written at crash
+        journal.push_str("is\t0,0");
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("tuipaz-journal-test-{}", std::process::id()));
+        std::fs::write(&path, journal).unwrap();
+        let lines = recover_from_journal(&path, vec!["abc".to_string()]).unwrap();
+        assert_eq!(lines, ["h\t", "x", "yabc"]);
+
+        std::fs::write(&path, "ds\t0,0,0\t0,0,0\tzzz\n").unwrap();
+        let err = recover_from_journal(&path, vec!["abc".to_string()]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,411 @@
+use crate::command::Command;
+use crate::input::{Input, Key};
+use std::collections::HashMap;
+
+/// A key with modifiers, used as a key of [`KeyBindings`]. Unlike [`Input`], it does not have the kind of the key
+/// event so that presses and repeats are bound to the same command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    /// Typed key.
+    pub key: Key,
+    /// Ctrl modifier key.
+    pub ctrl: bool,
+    /// Alt modifier key.
+    pub alt: bool,
+    /// Shift modifier key.
+    pub shift: bool,
+}
+
+impl KeyChord {
+    /// Create a key chord without modifiers.
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    /// Create a key chord with Ctrl modifier.
+    pub fn ctrl(key: Key) -> Self {
+        Self {
+            ctrl: true,
+            ..Self::new(key)
+        }
+    }
+
+    /// Create a key chord with Alt modifier.
+    pub fn alt(key: Key) -> Self {
+        Self {
+            alt: true,
+            ..Self::new(key)
+        }
+    }
+
+    /// Add Shift modifier to the key chord.
+    pub fn with_shift(self) -> Self {
+        Self {
+            shift: true,
+            ..self
+        }
+    }
+}
+
+impl From<Input> for KeyChord {
+    fn from(input: Input) -> Self {
+        let Input {
+            key,
+            ctrl,
+            alt,
+            shift,
+            ..
+        } = input;
+        Self {
+            key,
+            ctrl,
+            alt,
+            shift,
+        }
+    }
+}
+
+/// A configurable keymap of [`TextArea`](crate::TextArea). Key inputs bound to commands in this table are handled by
+/// [`TextArea::input`](crate::TextArea::input) before the built-in key mappings. Key inputs not in this table fall
+/// back to the built-in key mappings.
+///
+/// [`KeyBindings::default`] contains the following bindings.
+///
+/// | Mappings | Description                                          |
+/// |----------|------------------------------------------------------|
+/// | `Ctrl+L` | Scroll the cursor line to the center of the viewport |
+/// | `Alt+T`  | Scroll the cursor line to the top of the viewport    |
+/// | `Alt+E`  | Scroll the cursor line to the bottom of the viewport |
+///
+/// Half-page scrolling with `Ctrl+D` and `Ctrl+U` is opted in with [`KeyBindings::bind_half_page_scroll`] since these
+/// keys delete a character and undo in the built-in key mappings.
+///
+/// ```
+/// use tuipaz_textarea::{Command, Input, InputKind, Key, KeyBindings, KeyChord, TextArea};
+///
+/// let mut textarea = TextArea::from(["hello"]);
+///
+/// let mut bindings = KeyBindings::default();
+/// bindings.bind(KeyChord::ctrl(Key::Char('g')), Command::MoveEnd);
+/// textarea.set_key_bindings(bindings);
+///
+/// textarea.input(Input { key: Key::Char('g'), ctrl: true, alt: false, shift: false, kind: InputKind::Press });
+/// assert_eq!(textarea.cursor(), (0, 5));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBindings {
+    bindings: HashMap<KeyChord, Command>,
+    fallback: bool,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut bindings = Self::empty();
+        bindings.bind(KeyChord::ctrl(Key::Char('l')), Command::RecenterCenter);
+        bindings.bind(KeyChord::alt(Key::Char('t')), Command::RecenterTop);
+        bindings.bind(KeyChord::alt(Key::Char('e')), Command::RecenterBottom);
+        bindings
+    }
+}
+
+impl KeyBindings {
+    /// Create a keymap without any binding. All key inputs are handled by the built-in key mappings.
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+            fallback: true,
+        }
+    }
+
+    /// Create a keymap of the preset. Unlike [`KeyBindings::default`], key inputs not in the preset do not fall back
+    /// to the built-in key mappings except for typing characters, `Tab`, `Enter`, `Backspace`, `Delete` and mouse
+    /// scrolling. See [`Keymap`] for the bindings of each preset.
+    /// ```
+    /// use tuipaz_textarea::{Command, Key, KeyBindings, KeyChord, Keymap};
+    ///
+    /// let bindings = KeyBindings::preset(Keymap::Standard);
+    /// assert_eq!(bindings.get(KeyChord::ctrl(Key::Char('v'))), Some(Command::Paste));
+    /// assert!(!bindings.fallback());
+    /// ```
+    pub fn preset(keymap: Keymap) -> Self {
+        let mut bindings = Self::empty();
+        bindings.fallback = false;
+        match keymap {
+            Keymap::Emacs => bindings.bind_emacs(),
+            Keymap::Vim => bindings.bind_vim(),
+            Keymap::Standard => bindings.bind_standard(),
+        }
+        bindings
+    }
+
+    /// Bind `Ctrl+D` and `Ctrl+U` to scrolling down and up by half page. Since `Ctrl+U` is undo in the built-in key
+    /// mappings, undo is moved to `Ctrl+Z`. Deleting the next character stays on `Delete`.
+    /// ```
+    /// use tuipaz_textarea::{Command, Key, KeyBindings, KeyChord};
+    ///
+    /// let mut bindings = KeyBindings::default();
+    /// assert_eq!(bindings.get(KeyChord::ctrl(Key::Char('d'))), None);
+    ///
+    /// bindings.bind_half_page_scroll();
+    /// assert_eq!(bindings.get(KeyChord::ctrl(Key::Char('d'))), Some(Command::ScrollHalfPageDown));
+    /// assert_eq!(bindings.get(KeyChord::ctrl(Key::Char('z'))), Some(Command::Undo));
+    /// ```
+    pub fn bind_half_page_scroll(&mut self) {
+        self.bind(KeyChord::ctrl(Key::Char('d')), Command::ScrollHalfPageDown);
+        self.bind(KeyChord::ctrl(Key::Char('u')), Command::ScrollHalfPageUp);
+        self.bind(KeyChord::ctrl(Key::Char('z')), Command::Undo);
+    }
+
+    /// Set whether key inputs not in this keymap fall back to the built-in key mappings. When disabled, only typing
+    /// characters, `Tab`, `Enter`, `Backspace`, `Delete` and mouse scrolling are handled without bindings.
+    pub fn set_fallback(&mut self, enabled: bool) {
+        self.fallback = enabled;
+    }
+
+    /// Get whether key inputs not in this keymap fall back to the built-in key mappings. See
+    /// [`KeyBindings::set_fallback`].
+    pub fn fallback(&self) -> bool {
+        self.fallback
+    }
+
+    /// Bind the key chord to the command. The previous command bound to the chord is returned.
+    pub fn bind(&mut self, chord: KeyChord, command: Command) -> Option<Command> {
+        self.bindings.insert(chord, command)
+    }
+
+    /// Remove the binding of the key chord. The removed command is returned.
+    pub fn unbind(&mut self, chord: KeyChord) -> Option<Command> {
+        self.bindings.remove(&chord)
+    }
+
+    /// Get the command bound to the key chord.
+    pub fn get(&self, chord: KeyChord) -> Option<Command> {
+        self.bindings.get(&chord).copied()
+    }
+
+    /// Iterate over all bindings in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (KeyChord, Command)> + '_ {
+        self.bindings.iter().map(|(k, c)| (*k, *c))
+    }
+
+    // Bind the chord with and without Shift so that the movement can extend the selection
+    fn bind_move(&mut self, chord: KeyChord, command: Command) {
+        self.bind(chord, command);
+        self.bind(chord.with_shift(), command);
+    }
+
+    // Keys common to all presets
+    fn bind_common(&mut self) {
+        use Command::*;
+        self.bind_move(KeyChord::new(Key::Left), MoveBack);
+        self.bind_move(KeyChord::new(Key::Right), MoveForward);
+        self.bind_move(KeyChord::new(Key::Up), MoveUp);
+        self.bind_move(KeyChord::new(Key::Down), MoveDown);
+        self.bind_move(KeyChord::new(Key::Home), MoveHead);
+        self.bind_move(KeyChord::new(Key::End), MoveEnd);
+        self.bind(KeyChord::new(Key::PageDown), ScrollPageDown);
+        self.bind(KeyChord::new(Key::PageUp), ScrollPageUp);
+        self.bind(KeyChord::new(Key::Copy), Copy);
+        self.bind(KeyChord::new(Key::Cut), Cut);
+        self.bind(KeyChord::new(Key::Paste), Paste);
+    }
+
+    fn bind_emacs(&mut self) {
+        use Command::*;
+        self.bind_common();
+        let ctrl = |c| KeyChord::ctrl(Key::Char(c));
+        let alt = |c| KeyChord::alt(Key::Char(c));
+        self.bind_move(ctrl('f'), MoveForward);
+        self.bind_move(ctrl('b'), MoveBack);
+        self.bind_move(ctrl('n'), MoveDown);
+        self.bind_move(ctrl('p'), MoveUp);
+        self.bind_move(ctrl('a'), MoveHead);
+        self.bind_move(ctrl('e'), MoveEnd);
+        self.bind_move(alt('f'), MoveWordForward);
+        self.bind_move(alt('b'), MoveWordBack);
+        self.bind_move(alt('}'), MoveParagraphForward);
+        self.bind_move(alt('{'), MoveParagraphBack);
+        self.bind_move(alt('<'), MoveTop);
+        self.bind_move(alt('>'), MoveBottom);
+        self.bind(ctrl('v'), ScrollPageDown);
+        self.bind(alt('v'), ScrollPageUp);
+        self.bind(ctrl('l'), RecenterCenter);
+        self.bind(ctrl('m'), InsertNewline);
+        self.bind(ctrl('j'), InsertNewline);
+        self.bind(ctrl('h'), DeleteChar);
+        self.bind(ctrl('d'), DeleteNextChar);
+        self.bind(KeyChord::alt(Key::Backspace), DeleteWord);
+        self.bind(alt('d'), DeleteNextWord);
+        self.bind(ctrl('k'), DeleteLineByEnd);
+        self.bind(ctrl(' '), StartSelection);
+        self.bind(ctrl('g'), CancelSelection);
+        self.bind(ctrl('w'), Cut);
+        self.bind(alt('w'), Copy);
+        self.bind(ctrl('y'), Paste);
+        self.bind(ctrl('/'), Undo);
+        self.bind(ctrl('?'), Redo);
+    }
+
+    fn bind_vim(&mut self) {
+        use Command::*;
+        self.bind_common();
+        let ctrl = |c| KeyChord::ctrl(Key::Char(c));
+        self.bind_move(KeyChord::ctrl(Key::Right), MoveWordForward);
+        self.bind_move(KeyChord::ctrl(Key::Left), MoveWordBack);
+        self.bind_move(KeyChord::ctrl(Key::Home), MoveTop);
+        self.bind_move(KeyChord::ctrl(Key::End), MoveBottom);
+        self.bind(ctrl('m'), InsertNewline);
+        self.bind(ctrl('j'), InsertNewline);
+        self.bind(ctrl('i'), InsertTab);
+        self.bind(ctrl('h'), DeleteChar);
+        self.bind(ctrl('w'), DeleteWord);
+        self.bind(ctrl('u'), DeleteLineByHead);
+        self.bind(ctrl('f'), ScrollPageDown);
+        self.bind(ctrl('b'), ScrollPageUp);
+        self.bind(ctrl('d'), ScrollHalfPageDown);
+        self.bind(ctrl('r'), Redo);
+        self.bind(ctrl('z'), Undo);
+    }
+
+    fn bind_standard(&mut self) {
+        use Command::*;
+        self.bind_common();
+        let ctrl = |c| KeyChord::ctrl(Key::Char(c));
+        self.bind_move(KeyChord::ctrl(Key::Right), MoveWordForward);
+        self.bind_move(KeyChord::ctrl(Key::Left), MoveWordBack);
+        self.bind_move(KeyChord::ctrl(Key::Down), MoveParagraphForward);
+        self.bind_move(KeyChord::ctrl(Key::Up), MoveParagraphBack);
+        self.bind_move(KeyChord::ctrl(Key::Home), MoveTop);
+        self.bind_move(KeyChord::ctrl(Key::End), MoveBottom);
+        self.bind(KeyChord::ctrl(Key::Backspace), DeleteWord);
+        self.bind(KeyChord::ctrl(Key::Delete), DeleteNextWord);
+        self.bind(ctrl('a'), SelectAll);
+        self.bind(ctrl('c'), Copy);
+        self.bind(ctrl('x'), Cut);
+        self.bind(ctrl('v'), Paste);
+        self.bind(ctrl('z'), Undo);
+        self.bind(ctrl('y'), Redo);
+        self.bind(ctrl('z').with_shift(), Redo);
+    }
+}
+
+/// Keymap presets of [`TextArea`](crate::TextArea) applied by [`TextArea::set_keymap`](crate::TextArea::set_keymap).
+/// All presets move the cursor with arrow keys, `Home`, `End`, `PageUp` and `PageDown`. Movement keys extend the
+/// selection while `Shift` is held.
+///
+/// This type is marked as `#[non_exhaustive]` since more presets may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Keymap {
+    /// Emacs key bindings.
+    ///
+    /// | Mappings                     | Description                       |
+    /// |------------------------------|-----------------------------------|
+    /// | `Ctrl+F`, `Ctrl+B`           | Move forward or back by character |
+    /// | `Ctrl+N`, `Ctrl+P`           | Move down or up by line           |
+    /// | `Ctrl+A`, `Ctrl+E`           | Move to head or end of line       |
+    /// | `Alt+F`, `Alt+B`             | Move forward or back by word      |
+    /// | `Alt+}`, `Alt+{`             | Move forward or back by paragraph |
+    /// | `Alt+<`, `Alt+>`             | Move to top or bottom of text     |
+    /// | `Ctrl+V`, `Alt+V`            | Scroll down or up by page         |
+    /// | `Ctrl+L`                     | Recenter the cursor line          |
+    /// | `Ctrl+M`, `Ctrl+J`           | Insert newline                    |
+    /// | `Ctrl+H`, `Ctrl+D`           | Delete one character              |
+    /// | `Alt+Backspace`, `Alt+D`     | Delete one word                   |
+    /// | `Ctrl+K`                     | Delete until end of line          |
+    /// | `Ctrl+Space`, `Ctrl+G`       | Start or cancel selection         |
+    /// | `Ctrl+W`, `Alt+W`, `Ctrl+Y`  | Cut, copy or paste                |
+    /// | `Ctrl+/`, `Ctrl+?`           | Undo or redo                      |
+    Emacs,
+    /// Key bindings of Vim's insert mode. Modal editing is not provided by the keymap. See `examples/vim.rs` for
+    /// building it on top of the textarea.
+    ///
+    /// | Mappings                     | Description                    |
+    /// |------------------------------|--------------------------------|
+    /// | `Ctrl+Right`, `Ctrl+Left`    | Move forward or back by word   |
+    /// | `Ctrl+Home`, `Ctrl+End`      | Move to top or bottom of text  |
+    /// | `Ctrl+M`, `Ctrl+J`           | Insert newline                 |
+    /// | `Ctrl+I`                     | Insert tab                     |
+    /// | `Ctrl+H`                     | Delete one character           |
+    /// | `Ctrl+W`                     | Delete one word                |
+    /// | `Ctrl+U`                     | Delete until head of line      |
+    /// | `Ctrl+F`, `Ctrl+B`           | Scroll down or up by page      |
+    /// | `Ctrl+D`                     | Scroll down by half page       |
+    /// | `Ctrl+Z`, `Ctrl+R`           | Undo or redo                   |
+    Vim,
+    /// Standard (CUA) key bindings of desktop applications.
+    ///
+    /// | Mappings                        | Description                       |
+    /// |---------------------------------|-----------------------------------|
+    /// | `Ctrl+Right`, `Ctrl+Left`       | Move forward or back by word      |
+    /// | `Ctrl+Down`, `Ctrl+Up`          | Move forward or back by paragraph |
+    /// | `Ctrl+Home`, `Ctrl+End`         | Move to top or bottom of text     |
+    /// | `Ctrl+Backspace`, `Ctrl+Delete` | Delete one word                   |
+    /// | `Ctrl+A`                        | Select all                        |
+    /// | `Ctrl+C`, `Ctrl+X`, `Ctrl+V`    | Copy, cut or paste                |
+    /// | `Ctrl+Z`                        | Undo                              |
+    /// | `Ctrl+Y`, `Ctrl+Shift+Z`        | Redo                              |
+    Standard,
+}
+
+// Key inputs handled without bindings when the keymap does not fall back to the built-in key mappings
+pub(crate) fn is_plain_input(input: &Input) -> bool {
+    match input.key {
+        Key::Char(_) | Key::Tab | Key::BackTab => !input.ctrl && !input.alt,
+        Key::Enter | Key::Backspace | Key::Delete => !input.ctrl && !input.alt,
+        Key::MouseScrollDown | Key::MouseScrollUp => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputKind;
+
+    #[test]
+    fn chord_ignores_input_kind() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind_half_page_scroll();
+        for kind in [InputKind::Press, InputKind::Repeat] {
+            let input = Input {
+                key: Key::Char('d'),
+                ctrl: true,
+                alt: false,
+                shift: false,
+                kind,
+            };
+            assert_eq!(
+                bindings.get(input.into()),
+                Some(Command::ScrollHalfPageDown),
+            );
+        }
+        assert_eq!(
+            bindings.get(KeyChord::ctrl(Key::Char('d')).with_shift()),
+            None
+        );
+    }
+
+    #[test]
+    fn presets() {
+        let emacs = KeyBindings::preset(Keymap::Emacs);
+        let standard = KeyBindings::preset(Keymap::Standard);
+        let ctrl = |c| KeyChord::ctrl(Key::Char(c));
+        assert_eq!(emacs.get(ctrl('y')), Some(Command::Paste));
+        assert_eq!(standard.get(ctrl('y')), Some(Command::Redo));
+        assert_eq!(
+            standard.get(KeyChord::new(Key::Right).with_shift()),
+            Some(Command::MoveForward),
+        );
+        for keymap in [Keymap::Emacs, Keymap::Vim, Keymap::Standard] {
+            let bindings = KeyBindings::preset(keymap);
+            assert!(!bindings.fallback(), "{keymap:?}");
+            assert_eq!(bindings.get(KeyChord::new(Key::Char('a'))), None);
+        }
+    }
+}
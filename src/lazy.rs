@@ -0,0 +1,441 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+// Bytes read at once while indexing lines
+const INDEX_BLOCK: usize = 64 * 1024;
+
+#[derive(Clone, Debug)]
+enum Piece {
+    /// Lines of the file which were not edited. `start` is the row in the file.
+    File { start: usize, len: usize },
+    /// Lines replaced by edits
+    Edited(Vec<String>),
+}
+
+impl Piece {
+    fn len(&self) -> usize {
+        match self {
+            Self::File { len, .. } => *len,
+            Self::Edited(lines) => lines.len(),
+        }
+    }
+
+    fn split_at(&self, at: usize) -> (Self, Self) {
+        match self {
+            Self::File { start, len } => (
+                Self::File {
+                    start: *start,
+                    len: at,
+                },
+                Self::File {
+                    start: start + at,
+                    len: len - at,
+                },
+            ),
+            Self::Edited(lines) => (
+                Self::Edited(lines[..at].to_vec()),
+                Self::Edited(lines[at..].to_vec()),
+            ),
+        }
+    }
+}
+
+/// Loader of a huge file which reads only the lines needed for the viewport. Offsets of lines are indexed on demand and
+/// the text of lines is read from the file each time it is requested, so opening a file of hundreds of megabytes
+/// neither blocks nor loads the whole file in memory. Edited lines are kept in memory as dirty regions until the file
+/// is saved.
+///
+/// Lines are split at `\n` and a trailing `\r` is removed. Invalid UTF-8 sequences are replaced with `U+FFFD`. A
+/// trailing newline at the end of the file does not make an empty last line, and it is kept on save.
+///
+/// The host loads the window around the viewport in a [`TextArea`](crate::TextArea) and writes the edited lines back
+/// before loading another window.
+/// ```no_run
+/// use tuipaz_textarea::{LazyFile, TextArea};
+///
+/// let mut file = LazyFile::open("huge.log").unwrap();
+/// let (top, lines) = file.window(0, 50).unwrap();
+/// let len = lines.len();
+/// let mut textarea = TextArea::from(lines);
+///
+/// textarea.insert_str("NOTE: ", (0, 0));
+///
+/// file.replace_lines(top..top + len, textarea.lines().to_vec()).unwrap();
+/// file.save().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct LazyFile {
+    path: PathBuf,
+    file: File,
+    file_len: u64,
+    // Byte offsets where the indexed lines start in the file
+    offsets: Vec<u64>,
+    // Bytes of the file scanned for newlines
+    indexed: u64,
+    // Rows of the file appended to the pieces
+    file_rows: usize,
+    pieces: Vec<Piece>,
+    margin: usize,
+}
+
+impl LazyFile {
+    /// Open the file at the path. Nothing is read from the file until lines are requested.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        let file_len = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            file_len,
+            offsets: if file_len > 0 { vec![0] } else { vec![] },
+            indexed: 0,
+            file_rows: 0,
+            pieces: vec![],
+            margin: 100,
+        })
+    }
+
+    /// Get the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Set the number of lines loaded before and after the viewport by [`LazyFile::window`]. The default value is 100.
+    pub fn set_margin(&mut self, margin: usize) {
+        self.margin = margin;
+    }
+
+    /// Get the number of lines loaded before and after the viewport.
+    pub fn margin(&self) -> usize {
+        self.margin
+    }
+
+    /// Check if the whole file was indexed.
+    pub fn is_indexed(&self) -> bool {
+        self.indexed == self.file_len
+    }
+
+    /// Get the number of lines including edits. `None` is returned while the file is not indexed to the end. See
+    /// [`LazyFile::index`].
+    pub fn line_count(&self) -> Option<usize> {
+        self.is_indexed().then(|| self.known_lines())
+    }
+
+    /// Index the lines of the next `max_bytes` bytes of the file. Call this method periodically (e.g. on each tick of
+    /// the event loop) to know the number of lines without blocking. Returns `true` when the whole file was indexed.
+    pub fn index(&mut self, max_bytes: usize) -> io::Result<bool> {
+        let end = self.indexed.saturating_add(max_bytes as u64);
+        self.index_until(|this| this.indexed >= end)?;
+        Ok(self.is_indexed())
+    }
+
+    /// Get the lines in the range of rows, reading them from the file as needed. The range is clamped to the number of
+    /// lines.
+    pub fn lines(&mut self, rows: Range<usize>) -> io::Result<Vec<String>> {
+        let end = rows.end;
+        self.index_until(|this| this.known_lines() >= end)?;
+        let rows = rows.start.min(self.known_lines())..end.min(self.known_lines());
+
+        let mut lines = Vec::with_capacity(rows.len());
+        let mut row = 0;
+        for i in 0..self.pieces.len() {
+            let len = self.pieces[i].len();
+            let start = rows.start.max(row);
+            let end = rows.end.min(row + len);
+            if start < end {
+                match &self.pieces[i] {
+                    Piece::File {
+                        start: file_row, ..
+                    } => {
+                        let file_row = *file_row;
+                        self.read_lines(file_row + start - row..file_row + end - row, &mut lines)?;
+                    }
+                    Piece::Edited(edited) => {
+                        lines.extend_from_slice(&edited[start - row..end - row])
+                    }
+                }
+            }
+            row += len;
+            if row >= rows.end {
+                break;
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Get the lines around the viewport whose top row is `top` and whose height is `height`, with the margin before
+    /// and after it. The first row of the returned lines is returned with them.
+    pub fn window(&mut self, top: usize, height: usize) -> io::Result<(usize, Vec<String>)> {
+        let start = top.saturating_sub(self.margin);
+        let end = top.saturating_add(height).saturating_add(self.margin);
+        let lines = self.lines(start..end)?;
+        Ok((start, lines))
+    }
+
+    /// Replace the lines in the range of rows with the lines. The replaced region is kept in memory as a dirty region
+    /// until [`LazyFile::save`] is called. An error with [`io::ErrorKind::InvalidInput`] is returned when the range is
+    /// out of the lines.
+    pub fn replace_lines(&mut self, rows: Range<usize>, lines: Vec<String>) -> io::Result<()> {
+        let end = rows.end;
+        self.index_until(|this| this.known_lines() >= end)?;
+        if rows.start > rows.end || rows.end > self.known_lines() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("rows {:?} are out of {} lines", rows, self.known_lines()),
+            ));
+        }
+        let first = self.split_at(rows.start);
+        let last = self.split_at(rows.end);
+        let edited = (!lines.is_empty()).then(|| Piece::Edited(lines));
+        self.pieces.splice(first..last, edited);
+        Ok(())
+    }
+
+    /// Get the ranges of rows which were edited and not saved yet.
+    pub fn dirty_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        let mut row = 0;
+        for piece in &self.pieces {
+            let len = piece.len();
+            if let Piece::Edited(_) = piece {
+                match ranges.last_mut() {
+                    Some(last) if last.end == row => last.end += len,
+                    _ => ranges.push(row..row + len),
+                }
+            }
+            row += len;
+        }
+        ranges
+    }
+
+    /// Check if any line was edited and not saved yet. Deleting lines also makes the file dirty.
+    pub fn is_dirty(&self) -> bool {
+        let mut next = 0;
+        for piece in &self.pieces {
+            match piece {
+                Piece::File { start, len } if *start == next => next += len,
+                _ => return true,
+            }
+        }
+        false
+    }
+
+    /// Write the lines with the edits to the file. The file is written to a temporary file next to it and then renamed
+    /// so that the original file is not broken when writing fails. The lines which were not edited are copied from the
+    /// original file without loading them in memory.
+    pub fn save(&mut self) -> io::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        self.save_to(&tmp)?;
+        fs::rename(&tmp, &self.path)?;
+
+        let margin = self.margin;
+        *self = Self::open(&self.path)?;
+        self.margin = margin;
+        Ok(())
+    }
+
+    /// Write the lines with the edits to another file. The opened file stays unchanged and the edits are still dirty.
+    pub fn save_to(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.index_until(|_| false)?;
+        let trailing_newline = self.file_len > 0 && self.byte_at(self.file_len - 1)? == b'\n';
+
+        let mut out = BufWriter::new(File::create(path)?);
+        // Whether the output so far ends with a newline or is empty
+        let mut at_line_start = true;
+        for i in 0..self.pieces.len() {
+            if !at_line_start {
+                out.write_all(b"\n")?;
+            }
+            match &self.pieces[i] {
+                Piece::File { start, len } => {
+                    let (start, end) = (self.offsets[*start], self.line_end(start + len - 1));
+                    self.file.seek(SeekFrom::Start(start))?;
+                    io::copy(&mut (&self.file).take(end - start), &mut out)?;
+                    at_line_start = end < self.file_len || trailing_newline;
+                }
+                Piece::Edited(lines) => {
+                    out.write_all(lines.join("\n").as_bytes())?;
+                    at_line_start = false;
+                }
+            }
+        }
+        if trailing_newline && !at_line_start {
+            out.write_all(b"\n")?;
+        }
+        out.flush()
+    }
+
+    fn known_lines(&self) -> usize {
+        self.pieces.iter().map(Piece::len).sum()
+    }
+
+    // Byte offset where the line in the file ends including its newline
+    fn line_end(&self, file_row: usize) -> u64 {
+        self.offsets
+            .get(file_row + 1)
+            .copied()
+            .unwrap_or(self.file_len)
+    }
+
+    fn byte_at(&mut self, offset: u64) -> io::Result<u8> {
+        let mut b = [0];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+
+    // Index lines of the file until the predicate is satisfied or the end of the file
+    fn index_until(&mut self, done: impl Fn(&Self) -> bool) -> io::Result<()> {
+        let mut buf = vec![0; INDEX_BLOCK];
+        while !self.is_indexed() && !done(self) {
+            self.file.seek(SeekFrom::Start(self.indexed))?;
+            let n = self.file.read(&mut buf)?;
+            if n == 0 {
+                // The file was truncated after it was opened
+                self.file_len = self.indexed;
+            }
+            for (i, b) in buf[..n].iter().enumerate() {
+                let next = self.indexed + i as u64 + 1;
+                if *b == b'\n' && next < self.file_len {
+                    self.offsets.push(next);
+                }
+            }
+            self.indexed += n as u64;
+            self.append_file_rows();
+        }
+        Ok(())
+    }
+
+    // Append the newly indexed rows to the pieces. Rows not indexed yet always follow all pieces since they were never
+    // edited.
+    fn append_file_rows(&mut self) {
+        let complete = if self.is_indexed() {
+            self.offsets.len()
+        } else {
+            // The last line may continue in the bytes not scanned yet
+            self.offsets.len().saturating_sub(1)
+        };
+        if complete <= self.file_rows {
+            return;
+        }
+        let (start, added) = (self.file_rows, complete - self.file_rows);
+        match self.pieces.last_mut() {
+            Some(Piece::File { start: s, len }) if *s + *len == start => *len += added,
+            _ => self.pieces.push(Piece::File { start, len: added }),
+        }
+        self.file_rows = complete;
+    }
+
+    // Split the pieces at the row and return the index of the piece starting at the row
+    fn split_at(&mut self, at: usize) -> usize {
+        let mut row = 0;
+        for i in 0..self.pieces.len() {
+            if row == at {
+                return i;
+            }
+            let len = self.pieces[i].len();
+            if at < row + len {
+                let (head, tail) = self.pieces[i].split_at(at - row);
+                self.pieces[i] = head;
+                self.pieces.insert(i + 1, tail);
+                return i + 1;
+            }
+            row += len;
+        }
+        self.pieces.len()
+    }
+
+    fn read_lines(&mut self, file_rows: Range<usize>, lines: &mut Vec<String>) -> io::Result<()> {
+        let start = self.offsets[file_rows.start];
+        let end = self.line_end(file_rows.end - 1);
+        let mut bytes = vec![0; (end - start) as usize];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut bytes)?;
+        let text = String::from_utf8_lossy(&bytes);
+        let text = text.strip_suffix('\n').unwrap_or(&text);
+        lines.extend(
+            text.split('\n')
+                .map(|l| l.strip_suffix('\r').unwrap_or(l).to_string()),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("tuipaz-lazy-{}-{}", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_lines() {
+        let path = temp_file("read", "a\r\nb\n\nd\n");
+        let mut file = LazyFile::open(&path).unwrap();
+        assert_eq!(file.line_count(), None);
+        assert_eq!(file.lines(1..3).unwrap(), ["b", ""]);
+        assert_eq!(file.lines(0..10).unwrap(), ["a", "b", "", "d"]);
+        assert_eq!(file.line_count(), Some(4));
+
+        file.set_margin(1);
+        assert_eq!(
+            file.window(2, 1).unwrap(),
+            (1, vec!["b".to_string(), "".to_string(), "d".to_string()])
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn index_incrementally() {
+        let content: String = (0..10000).map(|i| format!("line {}\n", i)).collect();
+        let path = temp_file("index", &content);
+        let mut file = LazyFile::open(&path).unwrap();
+        assert_eq!(file.lines(5..6).unwrap(), ["line 5"]);
+        assert!(!file.is_indexed());
+        while !file.index(1000).unwrap() {}
+        assert_eq!(file.line_count(), Some(10000));
+        assert_eq!(file.lines(9999..10001).unwrap(), ["line 9999"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn edit_and_save() {
+        let content: String = (0..10).map(|i| format!("{}\n", i)).collect();
+        let path = temp_file("save", &content);
+        let mut file = LazyFile::open(&path).unwrap();
+        assert!(!file.is_dirty());
+
+        file.replace_lines(2..4, vec!["x".to_string()]).unwrap();
+        file.replace_lines(5..6, vec![]).unwrap();
+        file.replace_lines(8..8, vec!["y".to_string(), "z".to_string()])
+            .unwrap();
+        assert!(file.is_dirty());
+        assert_eq!(file.dirty_ranges(), [2..3, 8..10]);
+        assert_eq!(file.lines(0..20).unwrap().join(","), "0,1,x,4,5,7,8,9,y,z");
+        assert!(file.replace_lines(0..20, vec![]).is_err());
+
+        file.save().unwrap();
+        assert!(!file.is_dirty());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "0\n1\nx\n4\n5\n7\n8\n9\ny\nz\n"
+        );
+
+        // No trailing newline is kept
+        fs::write(&path, "a\nb").unwrap();
+        let mut file = LazyFile::open(&path).unwrap();
+        file.replace_lines(1..2, vec!["c".to_string()]).unwrap();
+        file.save().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\nc");
+        fs::remove_file(&path).unwrap();
+    }
+}
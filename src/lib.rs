@@ -4,19 +4,58 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
+mod accessibility;
+mod anchor;
+mod ansi;
+mod autosave;
+mod bell;
+mod cache;
+mod case;
+mod codeblock;
+mod color;
+mod command;
+mod conceal;
+mod content;
 mod cursor;
+mod form;
+mod fragment;
+mod group;
 mod highlight;
 mod history;
 mod hop;
+mod include;
 mod input;
+#[cfg(feature = "journal")]
+mod journal;
+mod keymap;
+mod lazy;
 mod links;
+mod modal;
+mod observer;
+mod ops;
+mod outline;
+mod paragraph;
+mod paste;
+mod popup;
+mod prompt;
+mod recording;
 mod scroll;
 mod search;
+mod segment;
+mod session;
+mod status;
+mod tags;
 mod textarea;
 mod textinput;
+mod textobject;
+#[cfg(feature = "theme-config")]
+mod theme;
+mod tokens;
+mod urls;
 mod util;
 mod widget;
 mod word;
+mod wrap;
 
 #[cfg(feature = "ratatui")]
 #[allow(clippy::single_component_path_imports)]
@@ -26,10 +65,43 @@ use ratatui;
 #[allow(clippy::single_component_path_imports)]
 use crossterm;
 
-pub use cursor::CursorMove;
-pub use input::{Input, Key};
-pub use links::Link;
-pub use scroll::Scrolling;
+pub use accessibility::Accessibility;
+pub use anchor::AnchorId;
+pub use bell::BellReason;
+pub use case::Case;
+pub use codeblock::CodeBlock;
+pub use color::ColorSupport;
+pub use command::{Command, CommandInfo};
+pub use content::ContentArea;
+pub use cursor::{CursorMove, LineMovement};
+pub use form::{FieldError, Form, FormSubmission, Validator};
+pub use fragment::Fragment;
+pub use group::{Editor, EditorGroup, EditorId};
+pub use highlight::{LineHighlighter, Overlay, StyleMerge};
+pub use hop::{HopOutcome, HopState, HopTarget};
+pub use input::{ComposeTable, EnterBehavior, Input, InputEffects, InputKind, InputResult, Key, RepeatBehavior};
+#[cfg(feature = "journal")]
+pub use journal::recover_from_journal;
+pub use keymap::{KeyBindings, KeyChord, Keymap};
+pub use lazy::LazyFile;
+pub use links::{Link, LinkError, LinkKeyError, LinkKeyMode, LinkRendering};
+pub use modal::Mode;
+pub use observer::EditorState;
+pub use ops::{InvariantViolation, Op};
+pub use outline::{Heading, Outline};
+pub use paragraph::{Paragraph, ParagraphId};
+pub use popup::{popup_area, Popup};
+pub use prompt::{Prompt, PromptAction, PromptResult};
+pub use recording::{InputRecording, RecordedInput};
+pub use scroll::{Placement, Scrolling};
+pub use session::SessionState;
+pub use tags::TagKind;
 pub use textarea::TextArea;
 pub use textarea::TextAreaTheme;
+pub use textarea::{Links, YankContent, YankedLink};
 pub use textinput::TextInput;
+pub use textobject::TextObject;
+#[cfg(feature = "theme-config")]
+pub use theme::{ThemeError, ThemeWatcher};
+pub use tokens::{Token, TokenProvider};
+pub use util::{Pos, Position, TextRange};
@@ -1,3 +1,13 @@
+use crate::util::{Position, TextRange};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Link {
     pub id: usize,
@@ -21,6 +31,21 @@ impl Link {
     }
 
 
+    /// Position of the first character of the link.
+    pub fn start(&self) -> Position {
+        Position::new(self.row, self.start_col)
+    }
+
+    /// Position of the last character of the link.
+    pub fn end(&self) -> Position {
+        Position::new(self.row, self.end_col)
+    }
+
+    /// Range of the text of the link. The end of the range is the position after the last character.
+    pub fn range(&self) -> TextRange {
+        TextRange::new(self.start(), (self.row, self.end_col + 1))
+    }
+
     pub(crate) fn toggle_edited(&mut self) {
         self.edited = !self.edited;
     }
@@ -35,3 +60,314 @@ impl PartialEq for Link {
         self.id == other.id
     }
 }
+
+/// Error returned when a link cannot be added, updated or removed through [`Links`](crate::Links).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkError {
+    /// No link has the ID or the link was deleted.
+    NoSuchLink(usize),
+    /// The range is outside its line or reversed.
+    OutOfBounds {
+        row: usize,
+        start_col: usize,
+        end_col: usize,
+    },
+    /// The range overlaps the existing link with the ID.
+    Overlap(usize),
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchLink(id) => write!(f, "no link has ID {}", id),
+            Self::OutOfBounds {
+                row,
+                start_col,
+                end_col,
+            } => write!(f, "range {}..={} at row {} is out of bounds", start_col, end_col, row),
+            Self::Overlap(id) => write!(f, "range overlaps link {}", id),
+        }
+    }
+}
+
+impl Error for LinkError {}
+
+/// How stable keys are assigned to links. Link IDs are sequential numbers which are only meaningful inside one
+/// textarea. Keys are strings which stay the same across sessions and buffers so that applications can refer to links
+/// from outside. See [`TextArea::set_link_key`](crate::TextArea::set_link_key).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LinkKeyMode {
+    /// Keys are only set by the application.
+    Manual,
+    /// Every link without a key is given a random UUID (version 4) key.
+    Uuid,
+}
+
+impl Default for LinkKeyMode {
+    fn default() -> Self {
+        Self::Manual
+    }
+}
+
+/// Error returned when a key cannot be set to a link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkKeyError {
+    /// No link has the ID.
+    NoSuchLink(usize),
+    /// The key is empty.
+    EmptyKey,
+    /// The key is already used by another link.
+    DuplicateKey { key: String, id: usize },
+}
+
+impl fmt::Display for LinkKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchLink(id) => write!(f, "no link has ID {}", id),
+            Self::EmptyKey => write!(f, "link key is empty"),
+            Self::DuplicateKey { key, id } => write!(f, "key {:?} is already used by link {}", key, id),
+        }
+    }
+}
+
+impl Error for LinkKeyError {}
+
+/// Link IDs ordered by their rows so that an edit only visits the links at or after the edited row instead of all
+/// links. Edits moving many links at once invalidate the index and it is rebuilt on the next edit.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LinkRows {
+    rows: BTreeSet<(usize, usize)>,
+    stale: bool,
+}
+
+impl LinkRows {
+    pub(crate) fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub(crate) fn is_synced(&self, links: &BTreeMap<usize, Link>) -> bool {
+        !self.stale && self.rows.len() == links.len()
+    }
+
+    /// Rebuild the index when links were moved or added without updating it.
+    pub(crate) fn sync(&mut self, links: &BTreeMap<usize, Link>) {
+        if self.is_synced(links) {
+            return;
+        }
+        self.rows = links.values().map(|l| (l.row, l.id)).collect();
+        self.stale = false;
+    }
+
+    /// IDs of the links in the rows ordered by their rows. All links are checked when the index is not synced.
+    pub(crate) fn ids<R: RangeBounds<usize>>(&self, links: &BTreeMap<usize, Link>, rows: R) -> Vec<usize> {
+        if !self.is_synced(links) {
+            let mut ids: Vec<_> = links
+                .values()
+                .filter(|l| rows.contains(&l.row))
+                .map(|l| (l.row, l.id))
+                .collect();
+            ids.sort_unstable();
+            return ids.into_iter().map(|(_, id)| id).collect();
+        }
+        let start = match rows.start_bound() {
+            Bound::Included(&row) => Bound::Included((row, 0)),
+            Bound::Excluded(&row) => Bound::Excluded((row, usize::MAX)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match rows.end_bound() {
+            Bound::Included(&row) => Bound::Included((row, usize::MAX)),
+            Bound::Excluded(&row) => Bound::Excluded((row, 0)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        if let (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) = (&start, &end) {
+            if s > e {
+                return vec![];
+            }
+        }
+        self.rows.range((start, end)).map(|(_, id)| *id).collect()
+    }
+
+    pub(crate) fn insert(&mut self, link: &Link) {
+        self.rows.insert((link.row, link.id));
+    }
+
+    /// Update the row of the link moved from the row `from`.
+    pub(crate) fn moved(&mut self, id: usize, from: usize, to: usize) {
+        if from != to && self.rows.remove(&(from, id)) {
+            self.rows.insert((to, id));
+        }
+    }
+}
+
+/// Stable keys of some link IDs. `None` means the link has no key.
+pub(crate) type KeyChanges = BTreeMap<usize, Option<String>>;
+
+/// Two-way map between link IDs and their stable keys.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LinkKeys {
+    keys: HashMap<usize, String>,
+    ids: HashMap<String, usize>,
+}
+
+impl LinkKeys {
+    pub(crate) fn key(&self, id: usize) -> Option<&str> {
+        self.keys.get(&id).map(String::as_str)
+    }
+
+    pub(crate) fn id(&self, key: &str) -> Option<usize> {
+        self.ids.get(key).copied()
+    }
+
+    pub(crate) fn contains_id(&self, id: usize) -> bool {
+        self.keys.contains_key(&id)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.keys.iter().map(|(id, key)| (*id, key.as_str()))
+    }
+
+    /// Set the key of the link replacing its previous key. The key must not be empty nor used by another link.
+    pub(crate) fn insert(&mut self, id: usize, key: String) -> Result<(), LinkKeyError> {
+        if key.is_empty() {
+            return Err(LinkKeyError::EmptyKey);
+        }
+        match self.ids.get(&key) {
+            Some(&other) if other == id => return Ok(()),
+            Some(&other) => return Err(LinkKeyError::DuplicateKey { key, id: other }),
+            None => {}
+        }
+        self.remove(id);
+        self.ids.insert(key.clone(), id);
+        self.keys.insert(id, key);
+        Ok(())
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) -> Option<String> {
+        let key = self.keys.remove(&id)?;
+        self.ids.remove(&key);
+        Some(key)
+    }
+
+    /// Keys of the IDs whose key differs between `self` and `after` as `(before, after)`. `None` is returned when no
+    /// key changed.
+    pub(crate) fn changes(&self, after: &LinkKeys) -> Option<(KeyChanges, KeyChanges)> {
+        let mut before_keys = KeyChanges::new();
+        let mut after_keys = KeyChanges::new();
+        let ids = self.keys.keys().chain(after.keys.keys());
+        for &id in ids {
+            let (b, a) = (self.key(id), after.key(id));
+            if b != a {
+                before_keys.insert(id, b.map(str::to_string));
+                after_keys.insert(id, a.map(str::to_string));
+            }
+        }
+        (!before_keys.is_empty()).then(|| (before_keys, after_keys))
+    }
+
+    /// Set the keys of the IDs to the recorded ones. Keys taken by other links in the meantime are not restored.
+    pub(crate) fn restore(&mut self, keys: &KeyChanges) {
+        for &id in keys.keys() {
+            self.remove(id);
+        }
+        for (&id, key) in keys {
+            if let Some(key) = key {
+                let _ = self.insert(id, key.clone());
+            }
+        }
+    }
+
+    /// Give a new random UUID key to the link.
+    pub(crate) fn insert_uuid(&mut self, id: usize) {
+        loop {
+            if self.insert(id, new_uuid()).is_ok() {
+                return;
+            }
+        }
+    }
+}
+
+/// Generate a random UUID (version 4) string such as `"936da01f-9abd-4d9d-80c7-02af85c822a8"`. The random bits come
+/// from the randomly seeded hasher of the standard library so that no extra dependency is necessary.
+pub(crate) fn new_uuid() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let state = RandomState::new();
+    let half = |salt: u8| {
+        let mut hasher = state.build_hasher();
+        hasher.write_u8(salt);
+        hasher.write_usize(count);
+        hasher.write_u128(nanos);
+        hasher.finish()
+    };
+    let mut bits = (u128::from(half(0)) << 64) | u128::from(half(1));
+    bits = (bits & !(0xf << 76)) | (0x4 << 76); // Version 4
+    bits = (bits & !(0x3 << 62)) | (0x2 << 62); // RFC 4122 variant
+    let hex = format!("{:032x}", bits);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32],
+    )
+}
+
+/// How links are written in the text exported by [`TextArea::export_plain`](crate::TextArea::export_plain).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LinkRendering {
+    /// Only the label of the link: `label`.
+    LabelOnly,
+    /// The label followed by the target in parentheses: `label (target)`.
+    LabelWithTarget,
+    /// Markdown link syntax: `[label](target)`.
+    Markdown,
+}
+
+impl LinkRendering {
+    pub(crate) fn render(self, label: &str, target: Option<&str>) -> String {
+        // Links typed with `[` and `]` include the brackets in their text
+        let label = label
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .unwrap_or(label);
+        match (self, target) {
+            (Self::LabelWithTarget, Some(target)) => format!("{} ({})", label, target),
+            (Self::Markdown, Some(target)) => format!("[{}]({})", label, target),
+            _ => label.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_rows() {
+        let mut links: BTreeMap<_, _> = [(0, 3), (1, 1), (2, 3), (3, 5)]
+            .iter()
+            .map(|&(id, row)| (id, Link::new(id, row, 0, 1)))
+            .collect();
+        let mut rows = LinkRows::default();
+
+        // Not synced yet, all links are checked
+        assert_eq!(rows.ids(&links, 2..), [0, 2, 3]);
+        rows.sync(&links);
+        assert_eq!(rows.ids(&links, 2..), [0, 2, 3]);
+        assert_eq!(rows.ids(&links, 3..=3), [0, 2]);
+        assert_eq!(rows.ids(&links, ..3), [1]);
+        assert_eq!(rows.ids(&links, 6..), Vec::<usize>::new());
+
+        links.get_mut(&1).unwrap().row = 4;
+        rows.moved(1, 1, 4);
+        assert_eq!(rows.ids(&links, 4..), [1, 3]);
+
+        links.insert(4, Link::new(4, 0, 0, 1));
+        assert_eq!(rows.ids(&links, ..), [4, 0, 2, 1, 3]);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::cursor::CursorMove;
+use crate::input::{Input, Key};
+use crate::textobject::TextObject;
+use std::fmt;
+
+/// Mode of the modal editing enabled by [`TextArea::set_modal_editing`](crate::TextArea::set_modal_editing). Modes
+/// are similar to Vim's modes.
+///
+/// This type is marked as `#[non_exhaustive]` since more modes may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// Keys are commands which move the cursor and edit the text, such as `w`, `dd` or `ciw`.
+    Normal,
+    /// Keys are handled with the default key mappings. This is the mode when modal editing is disabled.
+    Insert,
+    /// Keys move the cursor extending the selection, and `y`, `d` or `c` applies to the selected text including the
+    /// character under the cursor.
+    Visual,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => write!(f, "NORMAL"),
+            Self::Insert => write!(f, "INSERT"),
+            Self::Visual => write!(f, "VISUAL"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+// Text which an operator applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+    Motion(CursorMove),
+    // From the cursor to the end of the word like `ce`, which `cw` works as
+    WordEnd,
+    Line,
+    Object(TextObject),
+    Selection,
+}
+
+// Where insert mode starts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum InsertAt {
+    Cursor,
+    After,
+    LineStart,
+    LineEnd,
+    LineBelow,
+    LineAbove,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Move(CursorMove),
+    Operate(Operator, Target),
+    DeleteChar,
+    Paste,
+    PasteAbove,
+    Undo,
+    Redo,
+    Insert(InsertAt),
+    Visual,
+    Normal,
+    // The key was consumed as a part of a key sequence such as `dd`, or it has no meaning in the mode
+    Nop,
+}
+
+// Keys waiting for the rest of a key sequence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pending {
+    None,
+    G,
+    Operator(Operator),
+    Object(Operator, bool), // `true` for `a` objects such as `aw`
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Modal {
+    mode: Mode,
+    pending: Pending,
+}
+
+impl Default for Modal {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Normal,
+            pending: Pending::None,
+        }
+    }
+}
+
+fn motion(input: &Input) -> Option<CursorMove> {
+    let m = match input.key {
+        Key::Char('h') | Key::Left => CursorMove::Back,
+        Key::Char('l') | Key::Right => CursorMove::Forward,
+        Key::Char('j') | Key::Down => CursorMove::Down,
+        Key::Char('k') | Key::Up => CursorMove::Up,
+        Key::Char('w') => CursorMove::WordForward,
+        Key::Char('b') => CursorMove::WordBack,
+        Key::Char('0') | Key::Home => CursorMove::Head,
+        Key::Char('^') => CursorMove::FirstNonBlank,
+        Key::Char('$') | Key::End => CursorMove::End,
+        Key::Char('G') => CursorMove::Bottom,
+        Key::Char('%') => CursorMove::MatchingPair,
+        Key::Char('{') => CursorMove::ParagraphBack,
+        Key::Char('}') => CursorMove::ParagraphForward,
+        _ => return None,
+    };
+    Some(m)
+}
+
+fn text_object(c: char, around: bool) -> Option<TextObject> {
+    let obj = match c {
+        'w' if around => TextObject::AWord,
+        'w' => TextObject::InnerWord,
+        '(' | ')' | 'b' | '[' | ']' | '{' | '}' | 'B' | '<' | '>' if !around => TextObject::InnerBrackets,
+        '"' | '\'' | '`' if !around => TextObject::InnerQuotes,
+        'l' if !around => TextObject::InnerLink,
+        'p' => TextObject::Paragraph,
+        _ => return None,
+    };
+    Some(obj)
+}
+
+fn operator(c: char) -> Option<Operator> {
+    match c {
+        'd' => Some(Operator::Delete),
+        'c' => Some(Operator::Change),
+        'y' => Some(Operator::Yank),
+        _ => None,
+    }
+}
+
+impl Modal {
+    pub(crate) fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.pending = Pending::None;
+    }
+
+    /// Translate the input into an action in the current mode. `None` is returned when the input should be handled
+    /// with the default key mappings, which is the case of keys other than Esc in insert mode.
+    pub(crate) fn action(&mut self, input: &Input) -> Option<Action> {
+        if self.mode == Mode::Insert {
+            return match input.key {
+                Key::Esc => Some(Action::Normal),
+                _ => None,
+            };
+        }
+        if input.alt {
+            return Some(Action::Nop);
+        }
+
+        let pending = std::mem::replace(&mut self.pending, Pending::None);
+        if input.key == Key::Esc {
+            return Some(if self.mode == Mode::Visual { Action::Normal } else { Action::Nop });
+        }
+        if input.ctrl {
+            let action = match input.key {
+                Key::Char('r') if pending == Pending::None => Action::Redo,
+                _ => Action::Nop,
+            };
+            return Some(action);
+        }
+
+        let c = match input.key {
+            Key::Char(c) => Some(c),
+            _ => None,
+        };
+        let action = match pending {
+            Pending::G => match c {
+                Some('g') => Action::Move(CursorMove::Top),
+                _ => Action::Nop,
+            },
+            Pending::Operator(op) => match c {
+                Some(c) if operator(c) == Some(op) => Action::Operate(op, Target::Line),
+                Some(c @ ('i' | 'a')) => {
+                    self.pending = Pending::Object(op, c == 'a');
+                    Action::Nop
+                }
+                Some('w') if op == Operator::Change => Action::Operate(op, Target::WordEnd),
+                _ => match motion(input) {
+                    Some(m) => Action::Operate(op, Target::Motion(m)),
+                    None => Action::Nop,
+                },
+            },
+            Pending::Object(op, around) => match c.and_then(|c| text_object(c, around)) {
+                Some(obj) => Action::Operate(op, Target::Object(obj)),
+                None => Action::Nop,
+            },
+            Pending::None => self.start(input, c),
+        };
+        Some(action)
+    }
+
+    // Action of the first key of a key sequence
+    fn start(&mut self, input: &Input, c: Option<char>) -> Action {
+        if let Some(m) = motion(input) {
+            return Action::Move(m);
+        }
+        let visual = self.mode == Mode::Visual;
+        match c {
+            Some('g') => {
+                self.pending = Pending::G;
+                Action::Nop
+            }
+            Some('x') if visual => Action::Operate(Operator::Delete, Target::Selection),
+            Some(c) if visual => match operator(c) {
+                Some(op) => Action::Operate(op, Target::Selection),
+                None if c == 'v' => Action::Normal,
+                None => Action::Nop,
+            },
+            Some(c) if operator(c).is_some() => {
+                self.pending = Pending::Operator(operator(c).unwrap());
+                Action::Nop
+            }
+            Some('x') => Action::DeleteChar,
+            Some('D') => Action::Operate(Operator::Delete, Target::Motion(CursorMove::End)),
+            Some('C') => Action::Operate(Operator::Change, Target::Motion(CursorMove::End)),
+            Some('p') => Action::Paste,
+            Some('P') => Action::PasteAbove,
+            Some('u') => Action::Undo,
+            Some('i') => Action::Insert(InsertAt::Cursor),
+            Some('a') => Action::Insert(InsertAt::After),
+            Some('I') => Action::Insert(InsertAt::LineStart),
+            Some('A') => Action::Insert(InsertAt::LineEnd),
+            Some('o') => Action::Insert(InsertAt::LineBelow),
+            Some('O') => Action::Insert(InsertAt::LineAbove),
+            Some('v') => Action::Visual,
+            _ => Action::Nop,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(modal: &mut Modal, keys: &str) -> Vec<Option<Action>> {
+        keys.chars()
+            .map(|c| modal.action(&Input { key: Key::Char(c), ..Input::default() }))
+            .collect()
+    }
+
+    #[test]
+    fn key_sequences() {
+        let mut modal = Modal::default();
+        let nop = Some(Action::Nop);
+        assert_eq!(keys(&mut modal, "w"), [Some(Action::Move(CursorMove::WordForward))]);
+        assert_eq!(keys(&mut modal, "gg"), [nop, Some(Action::Move(CursorMove::Top))]);
+        assert_eq!(keys(&mut modal, "dd"), [nop, Some(Action::Operate(Operator::Delete, Target::Line))]);
+        assert_eq!(
+            keys(&mut modal, "cw"),
+            [nop, Some(Action::Operate(Operator::Change, Target::WordEnd))]
+        );
+        assert_eq!(
+            keys(&mut modal, "dw"),
+            [nop, Some(Action::Operate(Operator::Delete, Target::Motion(CursorMove::WordForward)))]
+        );
+        assert_eq!(
+            keys(&mut modal, "ciw"),
+            [nop, nop, Some(Action::Operate(Operator::Change, Target::Object(TextObject::InnerWord)))]
+        );
+        assert_eq!(
+            keys(&mut modal, "ya("),
+            [nop, nop, nop],
+            "no `a` object for brackets"
+        );
+        assert_eq!(keys(&mut modal, "dcx"), [nop, nop, Some(Action::DeleteChar)]);
+
+        // Insert mode passes keys through until Esc
+        modal.set_mode(Mode::Insert);
+        assert_eq!(keys(&mut modal, "dd"), [None, None]);
+        let esc = Input { key: Key::Esc, ..Input::default() };
+        assert_eq!(modal.action(&esc), Some(Action::Normal));
+
+        modal.set_mode(Mode::Visual);
+        assert_eq!(
+            keys(&mut modal, "jd"),
+            [
+                Some(Action::Move(CursorMove::Down)),
+                Some(Action::Operate(Operator::Delete, Target::Selection))
+            ]
+        );
+        assert_eq!(modal.action(&esc), Some(Action::Normal));
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Snapshot of the editor state passed to the observer set by
+/// [`TextArea::set_state_observer`](crate::TextArea::set_state_observer). Hosts can use it to update the terminal
+/// title or emit OSC sequences such as OSC 133 prompt marks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditorState {
+    /// File name set by [`TextArea::set_file_name`](crate::TextArea::set_file_name).
+    pub file_name: Option<String>,
+    /// Cursor position as `(row, col)`.
+    pub cursor: (usize, usize),
+    /// Number of lines.
+    pub lines: usize,
+    /// If the text is modified. See [`TextArea::is_modified`](crate::TextArea::is_modified).
+    pub modified: bool,
+    /// Counter increased on every edit, undo and redo. It changes when the text changes.
+    pub revision: u64,
+}
+
+type ObserverFn = dyn Fn(&EditorState) + Send + Sync;
+
+/// Debounced observer of the editor state. The callback is called on a tick after the state changed from the last
+/// reported one and then stayed the same for the interval, so that a burst of cursor moves or typing is reported once.
+#[derive(Clone)]
+pub(crate) struct StateObserver {
+    interval: Duration,
+    callback: Arc<ObserverFn>,
+    reported: Option<EditorState>,
+    pending: Option<(EditorState, Instant)>,
+}
+
+impl StateObserver {
+    pub(crate) fn new<F>(interval: Duration, callback: F) -> Self
+    where
+        F: Fn(&EditorState) + Send + Sync + 'static,
+    {
+        Self {
+            interval,
+            callback: Arc::new(callback),
+            reported: None,
+            pending: None,
+        }
+    }
+
+    /// Call the callback when the state has been unchanged for the interval since it changed. Returns `true` when it
+    /// was called.
+    pub(crate) fn tick(&mut self, now: Instant, state: EditorState) -> bool {
+        if self.reported.as_ref() == Some(&state) {
+            self.pending = None;
+            return false;
+        }
+        match &self.pending {
+            Some((pending, since)) if *pending == state => {
+                if now.saturating_duration_since(*since) < self.interval {
+                    return false;
+                }
+            }
+            _ => {
+                self.pending = Some((state, now));
+                return false;
+            }
+        }
+        self.pending = None;
+        (self.callback)(&state);
+        self.reported = Some(state);
+        true
+    }
+}
+
+impl fmt::Debug for StateObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateObserver")
+            .field("interval", &self.interval)
+            .field("reported", &self.reported)
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn debounce() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let c = count.clone();
+        let mut observer = StateObserver::new(Duration::from_millis(100), move |_| {
+            c.fetch_add(1, Ordering::SeqCst);
+        });
+        let state = |row| EditorState {
+            file_name: None,
+            cursor: (row, 0),
+            lines: 10,
+            modified: false,
+            revision: 0,
+        };
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+
+        // The first state is reported after the interval
+        assert!(!observer.tick(ms(0), state(0)));
+        assert!(observer.tick(ms(100), state(0)));
+        assert!(!observer.tick(ms(300), state(0)));
+
+        // Changes within the interval restart waiting
+        assert!(!observer.tick(ms(400), state(1)));
+        assert!(!observer.tick(ms(450), state(2)));
+        assert!(!observer.tick(ms(520), state(2)));
+        assert!(observer.tick(ms(550), state(2)));
+
+        // Going back to the reported state cancels the pending report
+        assert!(!observer.tick(ms(600), state(3)));
+        assert!(!observer.tick(ms(650), state(2)));
+        assert!(!observer.tick(ms(800), state(2)));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+}
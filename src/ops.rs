@@ -0,0 +1,141 @@
+use crate::cursor::CursorMove;
+use crate::input::Input;
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+
+/// A deterministic operation on a textarea. Each variant maps to one public editing method of
+/// [`TextArea`](crate::TextArea) so that random sequences of operations can be generated and applied with
+/// [`TextArea::apply_op`](crate::TextArea::apply_op). This is useful for fuzzing and property testing together with
+/// [`TextArea::check_invariants`](crate::TextArea::check_invariants).
+///
+/// This type is marked as `#[non_exhaustive]` since more operations may be added in the future.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub enum Op {
+    /// Handle a key input with default key mappings.
+    Input(Input),
+    /// Move the cursor.
+    Move(CursorMove),
+    /// Insert a character at the cursor.
+    InsertChar(char),
+    /// Insert a string at the cursor.
+    InsertStr(String),
+    /// Insert a newline at the cursor.
+    InsertNewline,
+    /// Insert a tab at the cursor.
+    InsertTab,
+    /// Delete the given number of characters after the cursor.
+    DeleteStr(usize),
+    /// Delete the character before the cursor.
+    DeleteChar,
+    /// Delete the character at the cursor.
+    DeleteNextChar,
+    /// Delete the word before the cursor.
+    DeleteWord,
+    /// Delete the word after the cursor.
+    DeleteNextWord,
+    /// Delete from the cursor to the end of the line.
+    DeleteLineByEnd,
+    /// Delete from the head of the line to the cursor.
+    DeleteLineByHead,
+    /// Delete the line at the cursor. `true` moves the cursor up after deleting.
+    DeleteLine(bool),
+    /// Start selecting text at the cursor.
+    StartSelection,
+    /// Cancel the selection.
+    CancelSelection,
+    /// Select the whole text.
+    SelectAll,
+    /// Copy the selected text.
+    Copy,
+    /// Cut the selected text.
+    Cut,
+    /// Paste the yanked text.
+    Paste,
+    /// Undo the last edit.
+    Undo,
+    /// Redo the last undone edit.
+    Redo,
+    /// Start a link at the cursor.
+    StartLink,
+    /// Insert a link ending at the cursor.
+    InsertLink,
+    /// Delete the link with the ID. Nothing happens when no link has the ID or the link is already deleted.
+    DeleteLink(usize),
+    /// Scroll the viewport by rows and columns.
+    Scroll(i16, i16),
+}
+
+/// A broken invariant of a textarea reported by [`TextArea::check_invariants`](crate::TextArea::check_invariants).
+///
+/// This type is marked as `#[non_exhaustive]` since more invariants may be checked in the future.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The textarea has no line. It must have at least one line.
+    NoLine,
+    /// The cursor points outside the text.
+    CursorOutOfBounds {
+        cursor: (usize, usize),
+        lines: usize,
+        line_len: Option<usize>,
+    },
+    /// The selection start points outside the text.
+    SelectionOutOfBounds { start: (usize, usize) },
+    /// The link is not stored with its own ID.
+    LinkIdMismatch { key: usize, id: usize },
+    /// The link ID is not less than the next link ID.
+    LinkIdNotAllocated { id: usize, next_link_id: usize },
+    /// The range of the link is outside its line or reversed.
+    LinkOutOfBounds {
+        id: usize,
+        row: usize,
+        start_col: usize,
+        end_col: usize,
+    },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoLine => write!(f, "textarea has no line"),
+            Self::CursorOutOfBounds {
+                cursor,
+                lines,
+                line_len: Some(len),
+            } => write!(
+                f,
+                "cursor {:?} exceeds max col {} (lines: {})",
+                cursor, len, lines,
+            ),
+            Self::CursorOutOfBounds { cursor, lines, .. } => {
+                write!(f, "cursor {:?} exceeds max lines {}", cursor, lines)
+            }
+            Self::SelectionOutOfBounds { start } => {
+                write!(f, "selection start {:?} is out of text", start)
+            }
+            Self::LinkIdMismatch { key, id } => {
+                write!(f, "link {} is stored with key {}", id, key)
+            }
+            Self::LinkIdNotAllocated { id, next_link_id } => {
+                write!(f, "link {} is not less than next link ID {}", id, next_link_id)
+            }
+            Self::LinkOutOfBounds {
+                id,
+                row,
+                start_col,
+                end_col,
+            } => write!(
+                f,
+                "link {} at row {} cols {}..={} is out of text",
+                id, row, start_col, end_col,
+            ),
+        }
+    }
+}
+
+impl Error for InvariantViolation {}
@@ -0,0 +1,256 @@
+use crate::codeblock::code_rows;
+use crate::input::{Input, InputKind, Key};
+use crate::ratatui::buffer::Buffer;
+use crate::ratatui::layout::Rect;
+use crate::ratatui::style::{Modifier, Style};
+use crate::ratatui::text::{Line, Span, Text};
+use crate::ratatui::widgets::{Block, Paragraph, Widget};
+use crate::textarea::TextArea;
+use crate::util::Padding;
+
+/// A markdown style heading (`# Title`, `## Title`, ...) found in the text of a [`TextArea`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// Level of the heading. `#` is 1 and `######` is 6.
+    pub level: u8,
+    /// Row of the line where the heading is written.
+    pub row: usize,
+    /// Text of the heading without the leading `#`s.
+    pub title: String,
+}
+
+pub(crate) fn parse_heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level as u8, rest.trim()))
+}
+
+// Headings in the lines. Lines in fenced code blocks, such as shell comments, are not headings
+pub(crate) fn parse_headings(lines: &[String]) -> Vec<Heading> {
+    let code = code_rows(lines, 0..lines.len());
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(row, _)| !code[*row])
+        .filter_map(|(row, line)| {
+            let (level, title) = parse_heading(line)?;
+            Some(Heading {
+                level,
+                row,
+                title: title.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A companion widget showing the heading structure of a [`TextArea`] as a selectable tree.
+///
+/// [`Outline::input`] moves the selection and returns the row of the selected heading when it is activated with
+/// Enter. The host passes the row back to the textarea to jump there.
+/// ```
+/// use tuipaz_textarea::{CursorMove, Input, InputKind, Key, Outline, TextArea};
+///
+/// let mut textarea = TextArea::from(["# Title", "text", "## Section", "more text"]);
+/// let mut outline = Outline::new(&textarea);
+///
+/// outline.input(Input { key: Key::Down, ctrl: false, alt: false, shift: false, kind: InputKind::Press });
+/// let enter = Input { key: Key::Enter, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+/// if let Some(row) = outline.input(enter) {
+///     textarea.move_cursor(CursorMove::Jump(row as u16, 0));
+/// }
+/// assert_eq!(textarea.cursor(), (2, 0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Outline<'a> {
+    headings: Vec<Heading>,
+    selected: usize,
+    block: Option<Block<'a>>,
+    style: Style,
+    selected_style: Style,
+}
+
+impl<'a> Outline<'a> {
+    /// Create an outline from the headings of the textarea.
+    pub fn new(textarea: &TextArea<'_>) -> Self {
+        Self {
+            headings: textarea.headings(),
+            selected: 0,
+            block: None,
+            style: Style::default(),
+            selected_style: Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+
+    /// Re-read the headings from the textarea. The selection stays on the same heading row when it still exists.
+    pub fn refresh(&mut self, textarea: &TextArea<'_>) {
+        let row = self.selected().map(|h| h.row);
+        self.headings = textarea.headings();
+        self.selected = row
+            .and_then(|row| self.headings.iter().position(|h| h.row >= row))
+            .unwrap_or(0)
+            .min(self.headings.len().saturating_sub(1));
+    }
+
+    /// Select the heading whose section contains `row`. This is useful for following the cursor of the textarea.
+    pub fn select_section_at(&mut self, row: usize) {
+        if let Some(i) = self.headings.iter().rposition(|h| h.row <= row) {
+            self.selected = i;
+        }
+    }
+
+    /// Handle a key input. Up/Down (or Ctrl+P/Ctrl+N) move the selection, Home/End jump to the first/last heading.
+    /// When Enter is pressed, the row of the selected heading is returned so that the host can move the cursor of
+    /// the textarea there.
+    pub fn input(&mut self, input: impl Into<Input>) -> Option<usize> {
+        let input = input.into();
+        if input.kind == InputKind::Release {
+            return None;
+        }
+        let last = self.headings.len().saturating_sub(1);
+        match input {
+            Input { key: Key::Up, .. }
+            | Input {
+                key: Key::Char('p'),
+                ctrl: true,
+                ..
+            } => self.selected = self.selected.saturating_sub(1),
+            Input { key: Key::Down, .. }
+            | Input {
+                key: Key::Char('n'),
+                ctrl: true,
+                ..
+            } => self.selected = (self.selected + 1).min(last),
+            Input { key: Key::Home, .. } => self.selected = 0,
+            Input { key: Key::End, .. } => self.selected = last,
+            Input {
+                key: Key::Enter, ..
+            } => return self.selected().map(|h| h.row),
+            _ => {}
+        }
+        None
+    }
+
+    /// Get all headings in the outline.
+    pub fn headings(&self) -> &[Heading] {
+        &self.headings
+    }
+
+    /// Get the currently selected heading. `None` is returned when the text has no heading.
+    pub fn selected(&self) -> Option<&Heading> {
+        self.headings.get(self.selected)
+    }
+
+    /// Set the block of the outline. By default, no block is set.
+    pub fn set_block(&mut self, block: Block<'a>) {
+        self.block = Some(block);
+    }
+
+    /// Set the style of the outline.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Set the style of the selected heading. The default style is reversed.
+    pub fn set_selected_style(&mut self, style: Style) {
+        self.selected_style = style;
+    }
+
+    /// Build a widget to render the outline.
+    pub fn widget(&'a self) -> impl Widget + 'a {
+        OutlineRenderer(self)
+    }
+}
+
+struct OutlineRenderer<'a>(&'a Outline<'a>);
+
+impl<'a> Widget for OutlineRenderer<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let outline = self.0;
+        let mut inner_area = area;
+        if let Some(b) = &outline.block {
+            inner_area = b.inner(area);
+            b.clone().render(area, buf);
+        }
+
+        let lines = outline
+            .headings
+            .iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let indent = Padding(h.level.saturating_sub(1) as usize * 2);
+                let style = if i == outline.selected {
+                    outline.selected_style
+                } else {
+                    outline.style
+                };
+                let mut spans: Vec<Span<'_>> = indent.chunks().map(Span::from).collect();
+                spans.push(Span::styled(h.title.as_str(), style));
+                Line::from(spans)
+            })
+            .collect::<Vec<_>>();
+
+        // Keep the selected heading in the viewport
+        let height = inner_area.height as usize;
+        let top = (outline.selected + 1).saturating_sub(height);
+
+        Paragraph::new(Text::from(lines))
+            .style(outline.style)
+            .scroll((top as u16, 0))
+            .render(inner_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_heading_levels() {
+        for (line, want) in [
+            ("# Title", Some((1, "Title"))),
+            ("### Sub section ", Some((3, "Sub section"))),
+            ("######", Some((6, ""))),
+            ("####### Too deep", None),
+            ("#hashtag", None),
+            ("text # not heading", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_heading(line), want, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn outline_selection_and_jump() {
+        let textarea = TextArea::from(["# A", "a", "## B", "b", "# C"]);
+        let mut outline = Outline::new(&textarea);
+        assert_eq!(outline.headings().len(), 3);
+
+        outline.select_section_at(3);
+        assert_eq!(outline.selected().unwrap().title, "B");
+
+        let input = |key| Input {
+            key,
+            ctrl: false,
+            alt: false,
+            shift: false,
+            kind: InputKind::Press,
+        };
+        assert_eq!(outline.input(input(Key::Down)), None);
+        assert_eq!(outline.input(input(Key::Enter)), Some(4));
+        outline.input(input(Key::Home));
+        assert_eq!(outline.input(input(Key::Enter)), Some(0));
+    }
+
+    #[test]
+    fn headings_in_code_blocks() {
+        let lines = ["# A", "```sh", "# comment", "```", "## B"].map(String::from);
+        let rows = parse_headings(&lines).iter().map(|h| h.row).collect::<Vec<_>>();
+        assert_eq!(rows, [0, 4]);
+    }
+}
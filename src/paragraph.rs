@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+/// ID of a paragraph. A paragraph is a logical line, which is a row and the rows continuing it (see
+/// [`TextArea::logical_lines`](crate::TextArea::logical_lines)). The ID stays with the paragraph while it is edited
+/// and moved by inserting or deleting lines above it, so it can address a paragraph more stably than a row number.
+/// IDs of removed paragraphs are not reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ParagraphId(usize);
+
+/// Paragraph returned by [`TextArea::paragraphs`](crate::TextArea::paragraphs).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Paragraph {
+    /// ID of the paragraph
+    pub id: ParagraphId,
+    /// Rows of the paragraph. The first row is where the paragraph starts and the rest continue it.
+    pub rows: Range<usize>,
+    /// Metadata value set by [`TextArea::set_paragraph_meta`](crate::TextArea::set_paragraph_meta)
+    pub meta: Option<String>,
+}
+
+/// IDs of paragraphs by their first rows, with their metadata values.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ParagraphIds {
+    next_id: usize,
+    ids: BTreeMap<usize, ParagraphId>,
+    meta: HashMap<ParagraphId, String>,
+}
+
+impl ParagraphIds {
+    pub(crate) fn get(&self, row: usize) -> Option<ParagraphId> {
+        self.ids.get(&row).copied()
+    }
+
+    pub(crate) fn row(&self, id: ParagraphId) -> Option<usize> {
+        self.ids.iter().find(|(_, i)| **i == id).map(|(row, _)| *row)
+    }
+
+    /// Give a new ID to the row unless it already has one.
+    pub(crate) fn assign(&mut self, row: usize) {
+        let next_id = &mut self.next_id;
+        self.ids.entry(row).or_insert_with(|| {
+            let id = ParagraphId(*next_id);
+            *next_id += 1;
+            id
+        });
+    }
+
+    /// Drop the ID of the row since it no longer starts a paragraph.
+    pub(crate) fn remove(&mut self, row: usize) {
+        if let Some(id) = self.ids.remove(&row) {
+            self.meta.remove(&id);
+        }
+    }
+
+    pub(crate) fn meta(&self, id: ParagraphId) -> Option<&str> {
+        self.meta.get(&id).map(String::as_str)
+    }
+
+    pub(crate) fn set_meta(&mut self, id: ParagraphId, value: String) -> Option<String> {
+        self.meta.insert(id, value)
+    }
+
+    pub(crate) fn remove_meta(&mut self, id: ParagraphId) -> Option<String> {
+        self.meta.remove(&id)
+    }
+
+    /// Move the IDs by the row shifts of an edit. IDs of removed rows are dropped.
+    pub(crate) fn shift(&mut self, shifts: &[(usize, isize)]) {
+        for &(at, delta) in shifts {
+            let moved = self.ids.split_off(&at);
+            let removed = if delta < 0 { delta.unsigned_abs() } else { 0 };
+            for (row, id) in moved {
+                if row >= at + removed {
+                    self.ids.insert((row as isize + delta) as usize, id);
+                } else {
+                    self.meta.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Move the IDs to the rows given by the function. When two IDs are moved to the same row, the first one is kept.
+    pub(crate) fn map(&mut self, f: impl Fn(usize) -> usize) {
+        for (row, id) in std::mem::take(&mut self.ids) {
+            match self.ids.get(&f(row)) {
+                Some(_) => {
+                    self.meta.remove(&id);
+                }
+                None => {
+                    self.ids.insert(f(row), id);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.ids.clear();
+        self.meta.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shift_and_map() {
+        let mut ids = ParagraphIds::default();
+        for row in 0..4 {
+            ids.assign(row);
+        }
+        let (a, b) = (ids.get(1).unwrap(), ids.get(2).unwrap());
+        ids.set_meta(b, "b".to_string());
+        ids.assign(1);
+        assert_eq!(ids.get(1), Some(a));
+
+        // Row 2 is removed and two rows are inserted at row 1
+        ids.shift(&[(2, -1), (1, 2)]);
+        assert_eq!(ids.get(3), Some(a));
+        assert_eq!(ids.row(b), None);
+        assert_eq!(ids.meta(b), None);
+        assert_eq!(ids.get(1), None);
+
+        ids.assign(1);
+        assert!(ids.get(1).unwrap() > a);
+        ids.map(|row| row / 2);
+        assert_eq!(ids.get(1), Some(a));
+        assert_eq!(ids.row(a), Some(1));
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+/// Text waiting to be inserted in batches by [`TextArea::insert_str_incremental`](crate::TextArea::insert_str_incremental).
+#[derive(Clone, Debug)]
+pub(crate) struct PendingInsert {
+    lines: VecDeque<String>,
+    total: usize,
+    /// Position `(row, col)` where the next batch is inserted. It is moved by the edits made between batches
+    pub(crate) pos: (usize, usize),
+    /// Revision of the text after the previous batch. The next batch is merged into the edit of the previous one when
+    /// no other edit was made between them
+    pub(crate) revision: Option<u64>,
+}
+
+impl PendingInsert {
+    pub(crate) fn new(s: &str, pos: (usize, usize), revision: Option<u64>) -> Self {
+        let lines: VecDeque<_> = s
+            .split('\n')
+            .map(|s| s.strip_suffix('\r').unwrap_or(s).to_string())
+            .collect();
+        Self {
+            total: lines.len(),
+            lines,
+            pos,
+            revision,
+        }
+    }
+
+    /// Take the text of the next batch of at most `size` lines. The text ends with a newline when more lines follow
+    /// so that the next batch starts at the beginning of a line.
+    pub(crate) fn next_batch(&mut self, size: usize) -> Option<String> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        let n = size.max(1).min(self.lines.len());
+        let mut batch: Vec<_> = self.lines.drain(..n).collect();
+        if !self.lines.is_empty() {
+            batch.push(String::new());
+        }
+        Some(batch.join("\n"))
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Lines inserted so far and the number of all lines.
+    pub(crate) fn progress(&self) -> (usize, usize) {
+        (self.total - self.lines.len(), self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches() {
+        let mut pending = PendingInsert::new("a\r\nb\nc\nd", (0, 0), None);
+        assert_eq!(pending.progress(), (0, 4));
+        assert_eq!(pending.next_batch(3).as_deref(), Some("a\nb\nc\n"));
+        assert_eq!(pending.progress(), (3, 4));
+        assert!(!pending.is_done());
+        assert_eq!(pending.next_batch(3).as_deref(), Some("d"));
+        assert!(pending.is_done());
+        assert_eq!(pending.next_batch(3), None);
+
+        let mut pending = PendingInsert::new("ab", (0, 0), None);
+        assert_eq!(pending.next_batch(0).as_deref(), Some("ab"));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::ratatui::buffer::Buffer;
+use crate::ratatui::layout::Rect;
+use crate::ratatui::style::Style;
+use crate::ratatui::text::Text;
+use crate::ratatui::widgets::{Block, Clear, Paragraph, Widget};
+use std::cmp;
+
+/// Compute the area of a popup of `size` `(width, height)` anchored at the cell `(x, y)` within `bounds`. The popup is
+/// placed on the row below the anchor. When there is not enough space below, it is flipped to above the anchor if
+/// there is more space there. The popup is shifted left to fit in `bounds` horizontally and shrunk when `bounds` is
+/// smaller than `size`.
+/// ```
+/// use ratatui::layout::Rect;
+/// use tuipaz_textarea::popup_area;
+///
+/// let bounds = Rect { x: 0, y: 0, width: 40, height: 20 };
+///
+/// // Below the anchor
+/// assert_eq!(popup_area((5, 3), (10, 4), bounds), Rect { x: 5, y: 4, width: 10, height: 4 });
+/// // Flipped above the anchor near the bottom edge
+/// assert_eq!(popup_area((5, 18), (10, 4), bounds), Rect { x: 5, y: 14, width: 10, height: 4 });
+/// // Shifted left near the right edge
+/// assert_eq!(popup_area((35, 3), (10, 4), bounds), Rect { x: 30, y: 4, width: 10, height: 4 });
+/// ```
+pub fn popup_area((x, y): (u16, u16), (width, height): (u16, u16), bounds: Rect) -> Rect {
+    let right = bounds.x.saturating_add(bounds.width);
+    let bottom = bounds.y.saturating_add(bounds.height);
+    let x = cmp::min(cmp::max(x, bounds.x), right);
+    let y = cmp::min(cmp::max(y, bounds.y), bottom.saturating_sub(1));
+
+    let width = cmp::min(width, bounds.width);
+    let x = cmp::min(x, right - width);
+
+    let below = bottom.saturating_sub(y.saturating_add(1));
+    let above = y - bounds.y;
+    let (y, height) = if height <= below || below >= above {
+        (y + 1, cmp::min(height, below))
+    } else {
+        let height = cmp::min(height, above);
+        (y - height, height)
+    };
+
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// A minimal popup widget to show completions, link previews or diagnostics over a textarea. The area under the popup
+/// is cleared before rendering. Compute the area with [`popup_area`] or
+/// [`TextArea::popup_area`](crate::TextArea::popup_area).
+#[derive(Clone, Debug, Default)]
+pub struct Popup<'a> {
+    text: Text<'a>,
+    block: Option<Block<'a>>,
+    style: Style,
+}
+
+impl<'a> Popup<'a> {
+    /// Create a popup showing the text.
+    pub fn new(text: impl Into<Text<'a>>) -> Self {
+        Self {
+            text: text.into(),
+            block: None,
+            style: Style::default(),
+        }
+    }
+
+    /// Set the block of the popup. By default, no block is set.
+    pub fn set_block(&mut self, block: Block<'a>) {
+        self.block = Some(block);
+    }
+
+    /// Set the style of the popup.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Get the size `(width, height)` to show the whole text including the borders of the block. Pass it to
+    /// [`popup_area`].
+    pub fn size(&self) -> (u16, u16) {
+        let width = cmp::min(self.text.width(), u16::MAX as usize) as u16;
+        let height = cmp::min(self.text.height(), u16::MAX as usize) as u16;
+        let (extra_width, extra_height) = match &self.block {
+            Some(block) => {
+                let r = Rect {
+                    x: 0,
+                    y: 0,
+                    width: 100,
+                    height: 100,
+                };
+                let inner = block.inner(r);
+                (r.width - inner.width, r.height - inner.height)
+            }
+            None => (0, 0),
+        };
+        (
+            width.saturating_add(extra_width),
+            height.saturating_add(extra_height),
+        )
+    }
+
+    /// Build a widget to render the popup.
+    pub fn widget(&'a self) -> impl Widget + 'a {
+        PopupRenderer(self)
+    }
+}
+
+struct PopupRenderer<'a>(&'a Popup<'a>);
+
+impl<'a> Widget for PopupRenderer<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup = self.0;
+        Clear.render(area, buf);
+        let mut inner = Paragraph::new(popup.text.clone()).style(popup.style);
+        if let Some(block) = &popup.block {
+            inner = inner.block(block.clone());
+        }
+        inner.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popup_area_edges() {
+        let bounds = Rect {
+            x: 2,
+            y: 1,
+            width: 20,
+            height: 10,
+        };
+        let area = |anchor, size| {
+            let r = popup_area(anchor, size, bounds);
+            (r.x, r.y, r.width, r.height)
+        };
+
+        // Fits below
+        assert_eq!(area((4, 2), (5, 3)), (4, 3, 5, 3));
+        // More space above than below
+        assert_eq!(area((4, 8), (5, 3)), (4, 5, 5, 3));
+        // Does not fit anywhere; shrunk to the larger side
+        assert_eq!(area((4, 4), (5, 9)), (4, 5, 5, 6));
+        assert_eq!(area((4, 7), (5, 9)), (4, 1, 5, 6));
+        // Wider than bounds
+        assert_eq!(area((4, 2), (30, 1)), (2, 3, 20, 1));
+        // Anchor outside bounds is clamped
+        assert_eq!(area((0, 0), (5, 3)), (2, 2, 5, 3));
+    }
+
+    #[test]
+    fn size_with_block() {
+        use crate::ratatui::widgets::Borders;
+
+        let mut popup = Popup::new("abc\nde");
+        assert_eq!(popup.size(), (3, 2));
+        popup.set_block(Block::default().borders(Borders::ALL));
+        assert_eq!(popup.size(), (5, 4));
+    }
+}
@@ -0,0 +1,446 @@
+use crate::cursor::CursorMove;
+use crate::input::{Input, InputKind, InputResult, Key};
+use crate::ratatui::style::{Color, Style};
+use crate::ratatui::widgets::Widget;
+use crate::session::SessionState;
+use crate::textarea::TextArea;
+use crate::textinput::TextInput;
+
+/// The textarea operation which receives the value of a [`Prompt`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PromptAction {
+    /// Set the value as the search pattern and search forward. The search is previewed while typing.
+    Search,
+    /// Set the value as the search pattern and search backward. The search is previewed while typing.
+    SearchBack,
+    /// Set the value as the hop pattern and start hopping.
+    Hop,
+    /// Run the command whose ID is the value with [`TextArea::run_command`]. Command IDs are completed with `Tab`.
+    Command,
+    /// Set the value as the search pattern, then ask the replacement and replace all matches with
+    /// [`TextArea::replace_all`]. The search is previewed while typing the pattern.
+    Replace,
+}
+
+impl PromptAction {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Search => "/",
+            Self::SearchBack => "?",
+            Self::Hop => "hop: ",
+            Self::Command => ":",
+            Self::Replace => "replace: ",
+        }
+    }
+}
+
+/// What happened as the result of handling a key input with [`Prompt::input`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PromptResult {
+    /// The prompt is closed. The input was not handled.
+    Closed,
+    /// The prompt is still open.
+    Pending,
+    /// Enter was pressed and the value was fed into the textarea operation. The prompt is closed. `false` means the
+    /// operation did not find anything, like no search match, no replaced match or an unknown command.
+    Accepted(bool),
+    /// The value is not a valid regular expression. The prompt stays open.
+    Invalid(regex::Error),
+    /// Esc was pressed. The prompt is closed and the state of the textarea when the prompt was opened is restored.
+    Cancelled,
+}
+
+/// A single-line prompt (label and [`TextInput`]) to ask a value for a textarea operation like text search or hop.
+///
+/// While the prompt is open, the host routes key inputs to [`Prompt::input`] instead of [`TextArea::input`] so that
+/// editing the textarea is suspended. Enter feeds the value into the pending operation. Esc cancels the prompt and
+/// restores the cursor, the scroll position, the selection and the search pattern of the textarea. `Tab` completes
+/// the value from the candidates set by [`Prompt::set_completions`].
+/// ```
+/// use tuipaz_textarea::{Input, InputKind, Key, Prompt, PromptAction, PromptResult, TextArea};
+///
+/// let mut textarea = TextArea::from(["hello", "world"]);
+/// let mut prompt = Prompt::default();
+///
+/// prompt.open(PromptAction::Search, &textarea);
+/// let key = |key| Input { key, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+/// for c in "wor".chars() {
+///     prompt.input(key(Key::Char(c)), &mut textarea);
+/// }
+/// assert_eq!(prompt.input(key(Key::Enter), &mut textarea), PromptResult::Accepted(true));
+/// assert_eq!(textarea.cursor(), (1, 0));
+/// assert!(!prompt.is_open());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Prompt<'a> {
+    input: TextInput<'a>,
+    action: Option<PromptAction>,
+    saved: Option<SessionState>,
+    pattern: Option<String>,
+    completions: Vec<String>,
+    completing: Option<(String, usize)>,
+}
+
+impl<'a> Default for Prompt<'a> {
+    fn default() -> Self {
+        Self {
+            input: TextInput::new(String::new(), u16::MAX, Color::Reset, String::new()),
+            action: None,
+            saved: None,
+            pattern: None,
+            completions: vec![],
+            completing: None,
+        }
+    }
+}
+
+impl<'a> Prompt<'a> {
+    /// Open the prompt for the action with an empty value. The state of the textarea is saved to restore it on
+    /// cancellation. For [`PromptAction::Command`], the completions are set to the command IDs of the textarea. For
+    /// other actions, the completions are cleared. Call [`Prompt::set_completions`] after opening the prompt.
+    pub fn open(&mut self, action: PromptAction, textarea: &TextArea<'_>) {
+        self.action = Some(action);
+        self.input.set_prompt(action.label());
+        self.saved = Some(textarea.session_state());
+        self.pattern = None;
+        self.set_value("");
+        if action == PromptAction::Command {
+            self.completions = textarea
+                .commands()
+                .iter()
+                .map(|c| c.id.to_string())
+                .collect();
+        } else {
+            self.completions = vec![];
+        }
+        self.completing = None;
+    }
+
+    /// Close the prompt without feeding the value nor restoring the textarea state.
+    pub fn close(&mut self) {
+        self.action = None;
+        self.input.set_prompt("");
+        self.saved = None;
+        self.pattern = None;
+        self.completing = None;
+    }
+
+    /// Check if the prompt is open.
+    pub fn is_open(&self) -> bool {
+        self.action.is_some()
+    }
+
+    /// Get the pending action of the open prompt.
+    pub fn action(&self) -> Option<PromptAction> {
+        self.action
+    }
+
+    /// Get the current value of the prompt.
+    pub fn value(&self) -> &str {
+        self.input.text()
+    }
+
+    /// Set the completion candidates. `Tab` replaces the value with the candidates starting with the typed text in
+    /// turn.
+    pub fn set_completions(&mut self, completions: Vec<String>) {
+        self.completions = completions;
+        self.completing = None;
+    }
+
+    /// Set the style of the label.
+    pub fn set_label_style(&mut self, style: Style) {
+        self.input.set_prompt_style(style);
+    }
+
+    /// Get the text input of the prompt to customize its styles.
+    pub fn text_input_mut(&mut self) -> &mut TextInput<'a> {
+        &mut self.input
+    }
+
+    /// Handle a key input while the prompt is open. [`PromptResult::Closed`] is returned when the prompt is not open
+    /// so that the host can pass the input to the textarea instead.
+    pub fn input(&mut self, input: impl Into<Input>, textarea: &mut TextArea<'_>) -> PromptResult {
+        let input = input.into();
+        let action = match self.action {
+            Some(action) => action,
+            None => return PromptResult::Closed,
+        };
+        if input.kind == InputKind::Release {
+            return PromptResult::Pending;
+        }
+        if input.key == Key::Tab && !self.completions.is_empty() {
+            self.complete();
+            return PromptResult::Pending;
+        }
+        match self.input.input_result(input) {
+            InputResult::Cancelled => {
+                if let Some(saved) = &self.saved {
+                    // The saved state was taken from the textarea so the search pattern is always valid
+                    let _ = textarea.restore_session_state(saved);
+                }
+                if action == PromptAction::Hop {
+                    textarea.clear_hop();
+                }
+                self.close();
+                PromptResult::Cancelled
+            }
+            InputResult::Submitted if action == PromptAction::Replace && self.pattern.is_none() => {
+                // The pattern is entered. Ask the replacement next
+                if let Err(err) = textarea.set_search_pattern(self.value()) {
+                    return PromptResult::Invalid(err);
+                }
+                self.restore_cursor(textarea);
+                if self.value().is_empty() {
+                    self.close();
+                    return PromptResult::Accepted(false);
+                }
+                self.pattern = Some(self.value().to_string());
+                self.input.set_prompt("with: ");
+                self.set_value("");
+                PromptResult::Pending
+            }
+            InputResult::Submitted => match self.accept(action, textarea) {
+                Ok(found) => {
+                    self.close();
+                    PromptResult::Accepted(found)
+                }
+                Err(err) => PromptResult::Invalid(err),
+            },
+            InputResult::Changed => {
+                self.completing = None;
+                self.preview(action, textarea);
+                PromptResult::Pending
+            }
+            InputResult::Unchanged => PromptResult::Pending,
+        }
+    }
+
+    /// Build a widget to render the prompt. The label of the action is followed by the value.
+    pub fn widget(&'a self) -> impl Widget + 'a {
+        self.input.widget()
+    }
+
+    fn set_value(&mut self, value: &str) {
+        self.input.clear();
+        self.input.cursor = (0, 0);
+        for c in value.chars() {
+            self.input.insert_char(c);
+        }
+    }
+
+    fn complete(&mut self) {
+        let (prefix, next) = match &self.completing {
+            Some((prefix, index)) => (prefix.clone(), index + 1),
+            None => (self.value().to_string(), 0),
+        };
+        let candidates = self
+            .completions
+            .iter()
+            .filter(|c| c.starts_with(&prefix))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return;
+        }
+        let index = next % candidates.len();
+        let value = candidates[index].clone();
+        self.set_value(&value);
+        self.completing = Some((prefix, index));
+    }
+
+    fn restore_cursor(&self, textarea: &mut TextArea<'_>) {
+        if let Some(saved) = &self.saved {
+            let (row, col) = saved.cursor;
+            textarea.move_cursor(CursorMove::Jump(
+                row.min(u16::MAX as usize) as u16,
+                col.min(u16::MAX as usize) as u16,
+            ));
+        }
+    }
+
+    fn preview(&self, action: PromptAction, textarea: &mut TextArea<'_>) {
+        let searching = match action {
+            PromptAction::Search | PromptAction::SearchBack => true,
+            PromptAction::Replace => self.pattern.is_none(),
+            PromptAction::Hop | PromptAction::Command => false,
+        };
+        if !searching {
+            return;
+        }
+        self.restore_cursor(textarea);
+        if textarea.set_search_pattern(self.value()).is_ok() {
+            if action != PromptAction::SearchBack {
+                textarea.search_forward(true);
+            } else {
+                textarea.search_back(true);
+            }
+        }
+    }
+
+    fn accept(
+        &self,
+        action: PromptAction,
+        textarea: &mut TextArea<'_>,
+    ) -> Result<bool, regex::Error> {
+        let value = self.value();
+        match action {
+            PromptAction::Search | PromptAction::SearchBack => {
+                textarea.set_search_pattern(value)?;
+                self.restore_cursor(textarea);
+                if value.is_empty() {
+                    return Ok(false);
+                }
+                Ok(if action == PromptAction::Search {
+                    textarea.search_forward(false)
+                } else {
+                    textarea.search_back(false)
+                })
+            }
+            PromptAction::Hop => {
+                textarea.set_hop_pattern(value)?;
+                textarea.init_hop();
+                Ok(!value.is_empty())
+            }
+            PromptAction::Command => Ok(textarea.run_command(value).is_some()),
+            PromptAction::Replace => Ok(textarea.replace_all(value) > 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: Key) -> Input {
+        Input {
+            key,
+            ctrl: false,
+            alt: false,
+            shift: false,
+            kind: InputKind::Press,
+        }
+    }
+
+    fn type_str(prompt: &mut Prompt, textarea: &mut TextArea, s: &str) {
+        for c in s.chars() {
+            assert_eq!(
+                prompt.input(key(Key::Char(c)), textarea),
+                PromptResult::Pending
+            );
+        }
+    }
+
+    #[test]
+    fn cancel_restores_state() {
+        let mut textarea = TextArea::from(["abc", "def", "abc"]);
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        let mut prompt = Prompt::default();
+        assert_eq!(
+            prompt.input(key(Key::Esc), &mut textarea),
+            PromptResult::Closed
+        );
+
+        prompt.open(PromptAction::Search, &textarea);
+        type_str(&mut prompt, &mut textarea, "ab");
+        // Search is previewed while typing
+        assert_eq!(textarea.cursor(), (2, 0));
+        assert!(textarea.search_pattern().is_some());
+
+        assert_eq!(
+            prompt.input(key(Key::Esc), &mut textarea),
+            PromptResult::Cancelled
+        );
+        assert_eq!(textarea.cursor(), (1, 1));
+        assert!(textarea.search_pattern().is_none());
+        assert!(!prompt.is_open());
+    }
+
+    #[test]
+    fn invalid_pattern_keeps_prompt_open() {
+        let mut textarea = TextArea::from(["abc"]);
+        let mut prompt = Prompt::default();
+        prompt.open(PromptAction::SearchBack, &textarea);
+        type_str(&mut prompt, &mut textarea, "(a");
+        assert!(matches!(
+            prompt.input(key(Key::Enter), &mut textarea),
+            PromptResult::Invalid(_),
+        ));
+        assert!(prompt.is_open());
+        assert_eq!(prompt.value(), "(a");
+    }
+
+    #[test]
+    fn replace_all_matches() {
+        let mut textarea = TextArea::from(["foo bar", "bar foo"]);
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        let mut prompt = Prompt::default();
+        prompt.open(PromptAction::Replace, &textarea);
+        type_str(&mut prompt, &mut textarea, "fo+");
+        // Search is previewed while typing the pattern
+        assert_eq!(textarea.cursor(), (1, 4));
+
+        assert_eq!(
+            prompt.input(key(Key::Enter), &mut textarea),
+            PromptResult::Pending
+        );
+        assert!(prompt.is_open());
+        assert_eq!(prompt.value(), "");
+        assert_eq!(textarea.cursor(), (0, 1));
+        // Typing the replacement does not search it
+        type_str(&mut prompt, &mut textarea, "bar");
+        assert_eq!(textarea.cursor(), (0, 1));
+
+        assert_eq!(
+            prompt.input(key(Key::Enter), &mut textarea),
+            PromptResult::Accepted(true)
+        );
+        assert_eq!(textarea.lines(), ["bar bar", "bar bar"]);
+        assert!(!prompt.is_open());
+
+        // Nothing to replace
+        prompt.open(PromptAction::Replace, &textarea);
+        type_str(&mut prompt, &mut textarea, "foo");
+        prompt.input(key(Key::Enter), &mut textarea);
+        type_str(&mut prompt, &mut textarea, "x");
+        assert_eq!(
+            prompt.input(key(Key::Enter), &mut textarea),
+            PromptResult::Accepted(false)
+        );
+
+        // Cancelling while asking the replacement restores the textarea
+        prompt.open(PromptAction::Replace, &textarea);
+        type_str(&mut prompt, &mut textarea, "bar");
+        prompt.input(key(Key::Enter), &mut textarea);
+        assert_eq!(
+            prompt.input(key(Key::Esc), &mut textarea),
+            PromptResult::Cancelled
+        );
+        assert_eq!(textarea.search_pattern().unwrap().as_str(), "foo");
+        assert_eq!(textarea.lines(), ["bar bar", "bar bar"]);
+    }
+
+    #[test]
+    fn complete_command() {
+        let mut textarea = TextArea::from(["abc"]);
+        let mut prompt = Prompt::default();
+        prompt.open(PromptAction::Command, &textarea);
+        type_str(&mut prompt, &mut textarea, "move_en");
+        prompt.input(key(Key::Tab), &mut textarea);
+        assert_eq!(prompt.value(), "move_end");
+        // Only one candidate; Tab keeps it
+        prompt.input(key(Key::Tab), &mut textarea);
+        assert_eq!(prompt.value(), "move_end");
+
+        assert_eq!(
+            prompt.input(key(Key::Enter), &mut textarea),
+            PromptResult::Accepted(true)
+        );
+        assert_eq!(textarea.cursor(), (0, 3));
+
+        prompt.open(PromptAction::Command, &textarea);
+        type_str(&mut prompt, &mut textarea, "nope");
+        assert_eq!(
+            prompt.input(key(Key::Enter), &mut textarea),
+            PromptResult::Accepted(false)
+        );
+    }
+}
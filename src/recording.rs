@@ -0,0 +1,62 @@
+use crate::input::Input;
+use crate::textarea::TextArea;
+
+/// A key input recorded by [`TextArea::enable_input_recording`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordedInput {
+    /// The key input.
+    pub input: Input,
+    /// Rectangle of the viewport `(row, col, width, height)` when the input was handled. Scrolling and some cursor
+    /// moves depend on it.
+    pub viewport: (u16, u16, u16, u16),
+    /// `false` when the input was handled by [`TextArea::input_without_shortcuts`].
+    pub shortcuts: bool,
+}
+
+/// A recording of the key inputs handled by a textarea, with the state of the textarea when the recording started.
+/// Pass it to [`TextArea::replay`] to reproduce the same buffer state deterministically. This is useful for
+/// attaching to bug reports.
+/// ```
+/// use tuipaz_textarea::{Input, InputKind, Key, TextArea};
+///
+/// let mut textarea = TextArea::from(["abc"]);
+/// textarea.enable_input_recording();
+///
+/// textarea.input(Input { key: Key::Char('x'), ctrl: false, alt: false, shift: false, kind: InputKind::Press });
+/// textarea.input(Input { key: Key::Enter, ctrl: false, alt: false, shift: false, kind: InputKind::Press });
+///
+/// let recording = textarea.disable_input_recording().unwrap();
+/// assert_eq!(recording.inputs().len(), 2);
+///
+/// let replayed = TextArea::replay(&recording);
+/// assert_eq!(replayed.lines(), textarea.lines());
+/// assert_eq!(replayed.cursor(), textarea.cursor());
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputRecording<'a> {
+    initial: Box<TextArea<'a>>,
+    inputs: Vec<RecordedInput>,
+}
+
+impl<'a> InputRecording<'a> {
+    pub(crate) fn new(initial: TextArea<'a>) -> Self {
+        Self {
+            initial: Box::new(initial),
+            inputs: vec![],
+        }
+    }
+
+    pub(crate) fn push(&mut self, input: RecordedInput) {
+        self.inputs.push(input);
+    }
+
+    /// Get the recorded key inputs in order.
+    pub fn inputs(&self) -> &[RecordedInput] {
+        &self.inputs
+    }
+
+    /// Get the state of the textarea when the recording started.
+    pub fn initial(&self) -> &TextArea<'a> {
+        &self.initial
+    }
+}
@@ -1,4 +1,5 @@
 use crate::widget::Viewport;
+use std::cmp;
 
 /// Specify how to scroll the textarea.
 ///
@@ -148,6 +149,31 @@ pub enum Scrolling {
     /// assert_eq!(textarea.cursor(), (11, 0));
     /// ```
     HalfPageUp,
+    /// Scroll the textarea vertically by the given number of lines. Positive values scroll down and negative values
+    /// scroll up. This is the same as `Scrolling::Delta { rows, cols: 0 }`.
+    ///
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::layout::Rect;
+    /// # use ratatui::widgets::Widget;
+    /// use tui_textarea::{TextArea, Scrolling};
+    ///
+    /// // Let's say terminal height is 8.
+    ///
+    /// // Create textarea with 20 lines "0", "1", "2", "3", ...
+    /// let mut textarea: TextArea = (0..20).into_iter().map(|i| i.to_string()).collect();
+    /// # // Call `render` at least once to populate terminal size
+    /// # let r = Rect { x: 0, y: 0, width: 24, height: 8 };
+    /// # let mut b = Buffer::empty(r.clone());
+    /// # textarea.widget().render(r, &mut b);
+    ///
+    /// textarea.scroll(Scrolling::Lines(3));
+    /// assert_eq!(textarea.cursor(), (3, 0));
+    /// ```
+    Lines(i16),
+    /// Scroll the textarea horizontally by the given number of columns. Positive values scroll right and negative
+    /// values scroll left. This is the same as `Scrolling::Delta { rows: 0, cols }`.
+    Columns(i16),
 }
 
 impl Scrolling {
@@ -170,6 +196,8 @@ impl Scrolling {
                 let (_, _, _, height) = viewport.rect();
                 (-(height as i16) / 2, 0)
             }
+            Self::Lines(rows) => (rows, 0),
+            Self::Columns(cols) => (0, cols),
         };
         viewport.scroll(rows, cols);
     }
@@ -181,6 +209,32 @@ impl From<(i16, i16)> for Scrolling {
     }
 }
 
+/// Specify where the cursor line is placed in the viewport by [`TextArea::recenter_viewport`]. These correspond to
+/// Vim's `zz`, `zt` and `zb` commands.
+///
+/// [`TextArea::recenter_viewport`]: https://docs.rs/tui-textarea/latest/tui_textarea/struct.TextArea.html#method.recenter_viewport
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Place the cursor line at the center of the viewport.
+    Center,
+    /// Place the cursor line at the top of the viewport.
+    Top,
+    /// Place the cursor line at the bottom of the viewport.
+    Bottom,
+}
+
+impl Placement {
+    pub(crate) fn scroll_top(self, row: usize, height: u16) -> u16 {
+        let height = cmp::max(height, 1) as usize;
+        let top = match self {
+            Self::Center => row.saturating_sub(height / 2),
+            Self::Top => row,
+            Self::Bottom => (row + 1).saturating_sub(height),
+        };
+        cmp::min(top, u16::MAX as usize) as u16
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +263,49 @@ mod tests {
         textarea.scroll((1, 0));
         assert_eq!(textarea.cursor(), (3, 0));
     }
+
+    #[test]
+    fn lines_and_columns() {
+        use crate::ratatui::buffer::Buffer;
+        use crate::ratatui::layout::Rect;
+        use crate::ratatui::widgets::Widget;
+        use crate::TextArea;
+
+        let mut textarea: TextArea = (0..20).map(|i| "x".repeat(i * 2)).collect();
+        let r = Rect {
+            x: 0,
+            y: 0,
+            width: 8,
+            height: 8,
+        };
+        let mut b = Buffer::empty(r);
+        textarea.widget().render(r, &mut b);
+
+        textarea.scroll(Scrolling::Lines(10));
+        assert_eq!(textarea.viewport_offset(), (10, 0));
+        assert_eq!(textarea.cursor(), (10, 0));
+
+        textarea.scroll(Scrolling::Columns(4));
+        assert_eq!(textarea.viewport_offset(), (10, 4));
+        assert_eq!(textarea.cursor(), (10, 4));
+
+        textarea.scroll(Scrolling::Lines(-3));
+        assert_eq!(textarea.viewport_offset(), (7, 4));
+        assert_eq!(textarea.cursor(), (10, 4));
+    }
+
+    #[test]
+    fn placement_scroll_top() {
+        assert_eq!(Placement::Center.scroll_top(10, 8), 6);
+        assert_eq!(Placement::Top.scroll_top(10, 8), 10);
+        assert_eq!(Placement::Bottom.scroll_top(10, 8), 3);
+
+        // Near the first line
+        assert_eq!(Placement::Center.scroll_top(2, 8), 0);
+        assert_eq!(Placement::Bottom.scroll_top(2, 8), 0);
+
+        // Viewport is not rendered yet
+        assert_eq!(Placement::Center.scroll_top(5, 0), 5);
+        assert_eq!(Placement::Bottom.scroll_top(5, 0), 5);
+    }
 }
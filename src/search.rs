@@ -1,10 +1,36 @@
 use crate::ratatui::style::{Color, Style};
 use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 
+// Scrolling through a huge text should not keep the matches of all lines
+const MAX_CACHED_ROWS: usize = 1024;
+
+// Match ranges of a row with the line they were found in. The line is compared on lookup so that an edit which was
+// not reported by `Search::invalidate` never shows stale matches.
 #[derive(Clone, Debug)]
+struct CachedMatches {
+    line: String,
+    matches: Vec<(usize, usize)>,
+}
+
+// The cache is behind a mutex since matches are cached while rendering through a shared reference, and the textarea
+// should stay `Sync`
+#[derive(Debug)]
 pub struct Search {
     pub pat: Option<Regex>,
     pub style: Style,
+    cache: Mutex<BTreeMap<usize, CachedMatches>>,
+}
+
+impl Clone for Search {
+    fn clone(&self) -> Self {
+        Self {
+            pat: self.pat.clone(),
+            style: self.style,
+            cache: Mutex::new(BTreeMap::new()),
+        }
+    }
 }
 
 impl Default for Search {
@@ -12,31 +38,109 @@ impl Default for Search {
         Self {
             pat: None,
             style: Style::default().bg(Color::Blue),
+            cache: Mutex::new(BTreeMap::new()),
         }
     }
 }
 
 impl Search {
-    pub fn matches<'a>(
-        &'a self,
-        line: &'a str,
-    ) -> Option<impl Iterator<Item = (usize, usize)> + 'a> {
+    /// Byte ranges of the matches in the line at the row. Matches are cached per row across frames until the line is
+    /// edited or the pattern changes.
+    pub fn matches(&self, row: usize, line: &str) -> Option<Vec<(usize, usize)>> {
         let pat = self.pat.as_ref()?;
-        let matches = pat.find_iter(line).map(|m| (m.start(), m.end()));
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.get(&row) {
+            if cached.line == line {
+                return Some(cached.matches.clone());
+            }
+        }
+
+        let matches: Vec<_> = pat.find_iter(line).map(|m| (m.start(), m.end())).collect();
+        if cache.len() >= MAX_CACHED_ROWS {
+            cache.clear();
+        }
+        let cached = CachedMatches {
+            line: line.to_string(),
+            matches: matches.clone(),
+        };
+        cache.insert(row, cached);
         Some(matches)
     }
 
+    /// Move the cached matches by the row shifts of an edit and drop the matches of the edited rows.
+    pub fn invalidate(&mut self, edited: (usize, usize), shifts: &[(usize, isize)]) {
+        let cache = self.cache.get_mut().unwrap_or_else(|e| e.into_inner());
+        for &(at, delta) in shifts {
+            if cache.is_empty() {
+                return;
+            }
+            let moved = cache.split_off(&at);
+            let removed = if delta < 0 { delta.unsigned_abs() } else { 0 };
+            for (row, cached) in moved {
+                if row >= at + removed {
+                    cache.insert((row as isize + delta) as usize, cached);
+                }
+            }
+        }
+        let (start, end) = edited;
+        let edited: Vec<_> = cache.range(start..=end).map(|(row, _)| *row).collect();
+        for row in edited {
+            cache.remove(&row);
+        }
+    }
+
     pub fn set_pattern(&mut self, query: &str) -> Result<(), regex::Error> {
         match &self.pat {
-            Some(r) if r.as_str() == query => {}
+            Some(r) if r.as_str() == query => return Ok(()),
             _ if query.is_empty() => self.pat = None,
             _ => self.pat = Some(Regex::new(query)?),
         }
+        self.clear_cache();
         Ok(())
     }
 
+    fn clear_cache(&mut self) {
+        self.cache.get_mut().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
     pub fn clear_pattern(&mut self) {
         self.pat = None;
+        self.clear_cache();
+    }
+
+    /// Replacements of the matches in the line starting at or after the byte offset, as byte ranges of the matches
+    /// and the replacement strings. `$1` or `${name}` in the replacement is substituted with the capture group. Empty
+    /// matches are not replaced.
+    pub fn replacements(&self, line: &str, from: usize, replacement: &str) -> Vec<(usize, usize, String)> {
+        let pat = match &self.pat {
+            Some(pat) => pat,
+            None => return vec![],
+        };
+        pat.captures_iter(line)
+            .filter_map(|caps| {
+                let m = caps.get(0)?;
+                if m.start() < from || m.start() == m.end() {
+                    return None;
+                }
+                let mut replaced = String::new();
+                caps.expand(replacement, &mut replaced);
+                Some((m.start(), m.end(), replaced))
+            })
+            .collect()
+    }
+
+    /// Replacement of the match starting at the byte offset in the line, as the byte range of the match and the
+    /// replacement string. The match is searched with the text before the offset as context, so it is the same match
+    /// found by [`Search::forward`] at the position. Empty matches are not replaced.
+    pub fn replacement_at(&self, line: &str, offset: usize, replacement: &str) -> Option<(usize, usize, String)> {
+        let caps = self.pat.as_ref()?.captures_at(line, offset)?;
+        let m = caps.get(0)?;
+        if m.start() != offset || m.start() == m.end() {
+            return None;
+        }
+        let mut replaced = String::new();
+        caps.expand(replacement, &mut replaced);
+        Some((m.start(), m.end(), replaced))
     }
 
     pub fn forward(
@@ -155,3 +259,50 @@ impl Search {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacements() {
+        let mut search = Search::default();
+        assert!(search.replacements("a=1", 0, "x").is_empty());
+        search.set_pattern(r"(\w+)=(\d*)").unwrap();
+        assert_eq!(
+            search.replacements("a=1, b=", 0, "$2=$1"),
+            [(0, 3, "1=a".to_string()), (5, 7, "=b".to_string())]
+        );
+        assert_eq!(search.replacements("a=1, b=", 1, "${1}!"), [(5, 7, "b!".to_string())]);
+
+        search.set_pattern("x*").unwrap();
+        assert!(search.replacements("ab", 0, "y").is_empty());
+        assert_eq!(search.replacement_at("ab", 0, "y"), None);
+
+        // The match at the offset is replaced even when it overlaps a match before it
+        search.set_pattern("(a)a").unwrap();
+        assert_eq!(search.replacement_at("aaa", 1, "<$1>"), Some((1, 3, "<a>".to_string())));
+        assert_eq!(search.replacement_at("aaa", 2, "<$1>"), None);
+    }
+
+    #[test]
+    fn cached_matches() {
+        let mut search = Search::default();
+        assert_eq!(search.matches(0, "ab ab"), None);
+        search.set_pattern("ab").unwrap();
+        assert_eq!(search.matches(0, "ab ab"), Some(vec![(0, 2), (3, 5)]));
+        assert_eq!(search.matches(1, "xab"), Some(vec![(1, 3)]));
+
+        // The edited line is not matched with the stale entry
+        assert_eq!(search.matches(0, "ab"), Some(vec![(0, 2)]));
+
+        // A row inserted above moves the cached matches down
+        search.invalidate((0, 1), &[(1, 1)]);
+        let cached = |search: &Search| search.cache.lock().unwrap().keys().copied().collect::<Vec<_>>();
+        assert_eq!(cached(&search), [2]);
+
+        search.set_pattern("x").unwrap();
+        assert!(cached(&search).is_empty());
+        assert_eq!(search.matches(2, "xab"), Some(vec![(0, 1)]));
+    }
+}
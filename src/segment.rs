@@ -0,0 +1,74 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Byte ranges of the segments of the line split at char boundaries so that each segment has at most `max_bytes`
+/// bytes. A segment has at least one character even when the character is longer than `max_bytes`. The line is not
+/// split inside the byte ranges in `keep` such as links. The segment ends before the range, or after it when the range
+/// starts the segment.
+pub(crate) fn segment_ranges(line: &str, max_bytes: usize, keep: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    while line.len() - start > max_bytes {
+        let mut end = start + max_bytes;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == start {
+            end = start + line[start..].chars().next().map_or(0, char::len_utf8);
+        }
+        if let Some(r) = keep.iter().find(|r| r.start < end && end < r.end) {
+            end = if r.start > start { r.start } else { r.end };
+        }
+        if end >= line.len() {
+            break;
+        }
+        ranges.push(start..end);
+        start = end;
+    }
+    if start < line.len() || ranges.is_empty() {
+        ranges.push(start..line.len());
+    }
+    ranges
+}
+
+/// Move the rows in the set by the row shifts of an edit. Rows in removed rows are dropped.
+pub(crate) fn shift_rows(rows: &mut BTreeSet<usize>, shifts: &[(usize, isize)]) {
+    for &(at, delta) in shifts {
+        if rows.is_empty() {
+            return;
+        }
+        let moved = rows.split_off(&at);
+        let removed = if delta < 0 { delta.unsigned_abs() } else { 0 };
+        for row in moved {
+            if row >= at + removed {
+                rows.insert((row as isize + delta) as usize);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments() {
+        assert_eq!(segment_ranges("", 4, &[]), [0..0]);
+        assert_eq!(segment_ranges("abcd", 4, &[]), [0..4]);
+        assert_eq!(segment_ranges("abcdefghij", 4, &[]), [0..4, 4..8, 8..10]);
+        // 'あ' is 3 bytes
+        assert_eq!(segment_ranges("aあいう", 4, &[]), [0..4, 4..7, 7..10]);
+        assert_eq!(segment_ranges("ああ", 2, &[]), [0..3, 3..6]);
+        // Kept ranges are not split
+        assert_eq!(segment_ranges("abcdefghij", 4, &[2..6]), [0..2, 2..6, 6..10]);
+        assert_eq!(segment_ranges("abcdefghij", 4, &[0..6]), [0..6, 6..10]);
+        assert_eq!(segment_ranges("abcdefghij", 4, &[5..10]), [0..4, 4..5, 5..10]);
+    }
+
+    #[test]
+    fn shift() {
+        let mut rows: BTreeSet<_> = [1, 3, 5].into_iter().collect();
+        shift_rows(&mut rows, &[(3, -1)]);
+        assert_eq!(rows.into_iter().collect::<Vec<_>>(), [1, 4]);
+    }
+}
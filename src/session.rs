@@ -0,0 +1,25 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The state of a textarea which is not a part of its text: the cursor, the scroll position, the selection, the marks
+/// and the search pattern. Save it with [`TextArea::session_state`](crate::TextArea::session_state) when closing a
+/// note and restore it with [`TextArea::restore_session_state`](crate::TextArea::restore_session_state) when
+/// reopening the note to put the user exactly where they left off.
+///
+/// When the `serde` feature is enabled, this type implements `Serialize` and `Deserialize`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionState {
+    /// Cursor position `(row, col)`.
+    pub cursor: (usize, usize),
+    /// Scroll top `(row, col)` of the viewport.
+    pub scroll_top: (u16, u16),
+    /// Start position `(row, col)` of the ongoing text selection.
+    pub selection_start: Option<(usize, usize)>,
+    /// Named positions set by [`TextArea::set_mark`](crate::TextArea::set_mark).
+    pub marks: BTreeMap<char, (usize, usize)>,
+    /// The text search pattern.
+    pub search_pattern: Option<String>,
+}
@@ -0,0 +1,62 @@
+use crate::modal::Mode;
+
+// State of the editor which can be put in block titles and footers
+pub(crate) struct Status<'a> {
+    pub(crate) file_name: Option<&'a str>,
+    pub(crate) modified: bool,
+    pub(crate) cursor: (usize, usize),
+    pub(crate) lines: usize,
+    pub(crate) mode: Mode,
+}
+
+impl<'a> Status<'a> {
+    /// Expand the placeholders in the template. See [`TextArea::set_block_title`](crate::TextArea::set_block_title)
+    /// for the placeholders. Unknown placeholders are kept as they are.
+    pub(crate) fn format(&self, template: &str) -> String {
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => break,
+            };
+            match &rest[1..end] {
+                "file" => out.push_str(self.file_name.unwrap_or("[No Name]")),
+                "modified" if self.modified => out.push_str("[+]"),
+                "modified" => {}
+                "row" => out.push_str(&(self.cursor.0 + 1).to_string()),
+                "col" => out.push_str(&(self.cursor.1 + 1).to_string()),
+                "lines" => out.push_str(&self.lines.to_string()),
+                "mode" => out.push_str(&self.mode.to_string()),
+                _ => out.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let mut status = Status {
+            file_name: Some("note.md"),
+            modified: true,
+            cursor: (2, 0),
+            lines: 10,
+            mode: Mode::Normal,
+        };
+        assert_eq!(status.format("{file}{modified} {row}:{col}/{lines}"), "note.md[+] 3:1/10");
+        assert_eq!(status.format("-- {mode} -- {unknown} {"), "-- NORMAL -- {unknown} {");
+
+        status.file_name = None;
+        status.modified = false;
+        assert_eq!(status.format("{file}{modified}"), "[No Name]");
+    }
+}
@@ -0,0 +1,111 @@
+use crate::util::Pos;
+use std::ops::Range;
+
+/// Kind of a lightweight token found in the text of a [`TextArea`](crate::TextArea).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TagKind {
+    /// `#tag`
+    Hashtag,
+    /// `@name`
+    Mention,
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '/'
+}
+
+/// Find `#tags` and `@mentions` in the line. A tag starts with `#` or `@` at the beginning of the line or after a
+/// whitespace or an opening bracket, and continues with alphanumeric characters, `_`, `-` and `/`. Trailing `-` and
+/// `/` are not included. The returned ranges are byte offsets including the leading `#` or `@`.
+pub(crate) fn find_tags(line: &str) -> Vec<(TagKind, Range<usize>)> {
+    let mut tags = vec![];
+    let mut prev = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            '#' => TagKind::Hashtag,
+            '@' => TagKind::Mention,
+            _ => {
+                prev = Some(c);
+                continue;
+            }
+        };
+        let at_boundary = match prev {
+            None => true,
+            Some(p) => p.is_whitespace() || matches!(p, '(' | '[' | '{'),
+        };
+        prev = Some(c);
+        if !at_boundary {
+            continue;
+        }
+
+        let mut last_word_end = None;
+        while let Some(&(i, c)) = chars.peek() {
+            if !is_tag_char(c) {
+                break;
+            }
+            chars.next();
+            prev = Some(c);
+            if c != '-' && c != '/' {
+                last_word_end = Some(i + c.len_utf8());
+            }
+        }
+        if let Some(end) = last_word_end {
+            tags.push((kind, start..end));
+        }
+    }
+    tags
+}
+
+pub(crate) fn collect_tags(lines: &[String]) -> Vec<(String, Pos)> {
+    let mut tags = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        for (_, range) in find_tags(line) {
+            let col = line[..range.start].chars().count();
+            tags.push((
+                line[range.clone()].to_string(),
+                Pos::new(row, col, range.start),
+            ));
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_tags_in_line() {
+        fn tags(line: &str) -> Vec<(TagKind, &str)> {
+            find_tags(line)
+                .into_iter()
+                .map(|(k, r)| (k, &line[r]))
+                .collect()
+        }
+
+        assert_eq!(
+            tags("#todo ask @alice (#work/project-a)"),
+            [
+                (TagKind::Hashtag, "#todo"),
+                (TagKind::Mention, "@alice"),
+                (TagKind::Hashtag, "#work/project-a"),
+            ],
+        );
+        // Headings, emails, anchors and bare sigils are not tags
+        assert_eq!(tags("# Title"), []);
+        assert_eq!(tags("## Title"), []);
+        assert_eq!(tags("mail foo@example.com"), []);
+        assert_eq!(tags("a#b @ #"), []);
+        // Trailing punctuation is excluded
+        assert_eq!(
+            tags("#tag-, @bob."),
+            [(TagKind::Hashtag, "#tag"), (TagKind::Mention, "@bob")]
+        );
+        // Non-ASCII
+        assert_eq!(
+            tags("@日本 #ü"),
+            [(TagKind::Mention, "@日本"), (TagKind::Hashtag, "#ü")]
+        );
+    }
+}
@@ -1,30 +1,81 @@
 use log::info;
 
-use crate::cursor::CursorMove;
-use crate::highlight::LineHighlighter;
-use crate::history::{Edit, EditKind, History};
-use crate::hop::Hop;
-use crate::input::{Input, Key};
-use crate::links::Link;
-use crate::ratatui::layout::Alignment;
+use crate::anchor::{AnchorId, Anchors, ChangeList};
+use crate::autosave::Autosave;
+use crate::accessibility::{Accessibility, PresetStyles};
+use crate::bell::{Bell, BellReason};
+use crate::cache::{LineCache, RenderState};
+use crate::case::Case;
+use crate::codeblock::{find_code_blocks, CodeBlock};
+use crate::ansi::write_ansi;
+use crate::color::ColorSupport;
+use crate::command::{Command, CommandInfo};
+use crate::conceal::{display_col, find_concealed, logical_col};
+use crate::cursor::{CursorMove, LineMovement};
+use crate::fragment::{self, Fragment};
+use crate::highlight::{
+    display_width, HighlightPass, LineHighlighter, Overlay, OverlayRules, StyleMerge,
+};
+use crate::history::{Edit, EditContext, EditKind, History};
+use crate::hop::{Hop, HopOutcome, HopState, HopTarget};
+use crate::include::{collect_includes, parse_include, IncludeResolver};
+#[cfg(feature = "journal")]
+use crate::journal::Journal;
+use crate::input::{ComposeTable, EnterBehavior, Input, InputEffects, InputKind, InputResult, Key, RepeatBehavior};
+use crate::keymap::{is_plain_input, KeyBindings, KeyChord, Keymap};
+use crate::links::{Link, LinkError, LinkKeyError, LinkKeyMode, LinkKeys, LinkRendering, LinkRows};
+use crate::modal::{Action, InsertAt, Modal, Mode, Operator, Target};
+use crate::observer::{EditorState, StateObserver};
+use crate::ops::{InvariantViolation, Op};
+use crate::outline::{parse_headings, Heading};
+use crate::paragraph::{Paragraph, ParagraphId, ParagraphIds};
+use crate::paste::PendingInsert;
+use crate::segment::{segment_ranges, shift_rows};
+use crate::tags::{collect_tags, TagKind};
+use crate::textobject::{self, TextObject};
+use crate::tokens::{collect_tokens, token_at, Token, TokenProvider};
+use crate::urls::{collect_urls, find_urls};
+use crate::recording::{InputRecording, RecordedInput};
+use crate::popup::popup_area;
+use crate::ratatui::layout::{Alignment, Rect};
 use crate::ratatui::style::{Color, Modifier, Style};
 use crate::ratatui::widgets::{Block, Widget};
-use crate::scroll::Scrolling;
+use crate::scroll::{Placement, Scrolling};
 use crate::search::Search;
-use crate::util::{spaces, Pos, log_format};
+use crate::session::SessionState;
+use crate::status::Status;
+use crate::util::{auto_pair_closer, byte_offset, is_auto_pair_closer, num_digits, quote_prefix, spaces, Padding, Pos, Position, TextRange, log_format};
 use crate::widget::{Renderer, Viewport};
 use crate::word::{find_word_end_forward, find_word_start_backward};
-use ratatui::text::Line;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use crate::wrap::{reflow, wrap_line, wrap_ranges};
+use ratatui::text::{Line, Span};
+use std::cmp::{self, Ordering};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthChar as _;
 
+// Match previewed during incremental search with the scroll top before the preview started
 #[derive(Debug, Clone, Copy)]
+struct SearchPreview {
+    pos: Option<(usize, usize)>,
+    scroll_top: (u16, u16),
+}
+
+/// A link in yanked text. See [`YankContent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct YankedLink {
-    id: usize,
-    row_offset: usize,
-    start_col_offset: usize,
-    end_col_offset: usize,
+    /// ID of the link in the textarea where the text was yanked. When the ID is not a deleted link of the textarea
+    /// where the text is pasted, a new link is created. Links set by [`TextArea::set_yank`] are always pasted as new
+    /// links.
+    pub id: usize,
+    /// Row of the link relative to the first row of the yanked text.
+    pub row_offset: usize,
+    /// Start column of the link. It is relative to the start of the yanked text on its first row.
+    pub start_col_offset: usize,
+    /// End column (inclusive) of the link. It is relative to the start of the yanked text on its first row.
+    pub end_col_offset: usize,
 }
 
 pub type MaybeLinks = Option<Vec<YankedLink>>;
@@ -33,6 +84,8 @@ pub type MaybeLinks = Option<Vec<YankedLink>>;
 enum YankText {
     Piece((String, MaybeLinks, (usize, usize))),
     Chunk((Vec<String>, MaybeLinks, (usize, usize))),
+    // Complete lines yanked linewise
+    Lines((Vec<String>, MaybeLinks, (usize, usize))),
 }
 
 impl Default for YankText {
@@ -41,11 +94,93 @@ impl Default for YankText {
     }
 }
 
+/// Rich content of the yank buffer of a textarea, returned from [`TextArea::yank`]. Unlike
+/// [`TextArea::yank_text`], it keeps whether the text spans multiple lines and the links in the text so that it can
+/// be moved to another textarea with [`TextArea::set_yank`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum YankContent {
+    /// Text within one line.
+    Piece {
+        /// Yanked text.
+        text: String,
+        /// Links in the text.
+        links: Vec<YankedLink>,
+        /// Stable keys of the links by their IDs.
+        keys: HashMap<usize, String>,
+        /// Position `(row, col)` where the text was yanked.
+        origin: (usize, usize),
+    },
+    /// Text spanning multiple lines.
+    Chunk {
+        /// Yanked lines.
+        lines: Vec<String>,
+        /// Links in the lines.
+        links: Vec<YankedLink>,
+        /// Stable keys of the links by their IDs.
+        keys: HashMap<usize, String>,
+        /// Position `(row, col)` where the text was yanked.
+        origin: (usize, usize),
+    },
+    /// Complete lines yanked linewise, such as the cursor line copied without selection. They are pasted as lines
+    /// above the cursor line by [`TextArea::paste`].
+    Lines {
+        /// Yanked lines without the newline at the end.
+        lines: Vec<String>,
+        /// Links in the lines.
+        links: Vec<YankedLink>,
+        /// Stable keys of the links by their IDs.
+        keys: HashMap<usize, String>,
+        /// Position `(row, col)` where the lines were yanked.
+        origin: (usize, usize),
+    },
+}
+
+impl From<YankText> for YankContent {
+    fn from(yank: YankText) -> Self {
+        match yank {
+            YankText::Piece((text, links, origin)) => Self::Piece {
+                text,
+                links: links.unwrap_or_default(),
+                keys: HashMap::new(),
+                origin,
+            },
+            YankText::Chunk((lines, links, origin)) => Self::Chunk {
+                lines,
+                links: links.unwrap_or_default(),
+                keys: HashMap::new(),
+                origin,
+            },
+            YankText::Lines((lines, links, origin)) => Self::Lines {
+                lines,
+                links: links.unwrap_or_default(),
+                keys: HashMap::new(),
+                origin,
+            },
+        }
+    }
+}
+
+impl From<YankContent> for YankText {
+    fn from(content: YankContent) -> Self {
+        let links = |l: Vec<YankedLink>| if l.is_empty() { None } else { Some(l) };
+        match content {
+            YankContent::Piece { text, links: l, origin, .. } => Self::Piece((text, links(l), origin)),
+            // Chunk of one line is pasted as piece
+            YankContent::Chunk { mut lines, links: l, origin, .. } if lines.len() == 1 => {
+                Self::Piece((lines.remove(0), links(l), origin))
+            }
+            YankContent::Chunk { lines, links: l, origin, .. } => Self::Chunk((lines, links(l), origin)),
+            YankContent::Lines { lines, links: l, origin, .. } => Self::Lines((lines, links(l), origin)),
+        }
+    }
+}
+
 impl ToString for YankText {
     fn to_string(&self) -> String {
         match self {
             Self::Piece((s, _, _)) => s.clone(),
             Self::Chunk((ss, _, _)) => ss.join("\n"),
+            Self::Lines((ss, _, _)) => format!("{}\n", ss.join("\n")),
         }
     }
 }
@@ -56,12 +191,12 @@ impl ToString for YankText {
 /// [`TextArea::from`] creates a textarea from an iterator of lines. [`TextArea::input`] handles key input.
 /// [`TextArea::widget`] builds a widget to render. And [`TextArea::lines`] returns line texts.
 /// ```
-/// use tuipaz_textarea::{TextArea, Input, Key};
+/// use tuipaz_textarea::{TextArea, Input, InputKind, Key};
 ///
 /// let mut textarea = TextArea::default();
 ///
 /// // Input 'a'
-/// let input = Input { key: Key::Char('a'), ctrl: false, alt: false, shift: false };
+/// let input = Input { key: Key::Char('a'), ctrl: false, alt: false, shift: false, kind: InputKind::Press };
 /// textarea.input(input);
 ///
 /// // Get widget to render.
@@ -74,24 +209,67 @@ impl ToString for YankText {
 pub struct TextArea<'a> {
     lines: Vec<String>,
     block: Option<Block<'a>>,
+    block_title: Option<String>,
+    block_footer: Option<String>,
+    file_name: Option<String>,
+    modified: bool,
     style: Style,
     cursor: (usize, usize), // 0-base
-    pub links: HashMap<usize, Link>,
+    // Column which vertical moves try to keep, with the cursor position it is valid at
+    desired_col: Option<(usize, (usize, usize))>,
+    #[deprecated(note = "use TextArea::links, TextArea::links_mut and TextArea::link instead")]
+    pub links: BTreeMap<usize, Link>,
     pending_link: Option<(usize, usize)>,
+    #[deprecated(note = "use TextArea::next_link_id instead")]
     pub next_link_id: usize,
+    link_keys: LinkKeys,
+    link_key_mode: LinkKeyMode,
+    link_rows: LinkRows,
+    line_cache: LineCache,
+    line_meta: BTreeMap<usize, String>,
+    max_line_bytes: Option<usize>,
+    soft_breaks: BTreeSet<usize>,
+    wrap_breaks: BTreeSet<usize>,
+    paragraph_ids: ParagraphIds,
+    anchors: Anchors,
+    changes: ChangeList,
+    autosave: Option<Autosave>,
+    state_observer: Option<StateObserver>,
+    revision: u64,
+    bell: Bell,
+    pending_insert: Option<PendingInsert>,
+    insert_batch_size: usize,
+    #[cfg(feature = "journal")]
+    journal: Option<Journal>,
+    #[deprecated(note = "use TextArea::take_new_link instead")]
     pub new_link: bool,
+    #[deprecated(note = "use TextArea::take_deleted_link_ids instead")]
     pub deleted_link_ids: Vec<usize>,
     tab_len: u8,
     hard_tab_indent: bool,
+    auto_pairs: bool,
+    quote_aware: bool,
+    // Positions of the closing characters inserted by auto-pairing, which are skipped when they are typed
+    auto_closers: Vec<(usize, usize)>,
     history: History,
     cursor_line_style: Style,
     line_number_style: Option<Style>,
+    accessibility: Accessibility,
+    // Styles set by the application, which are restored when the accessibility preset is turned off
+    standard_styles: Option<PresetStyles>,
     pub(crate) viewport: Viewport,
     yank: YankText,
+    // Stable keys of the links set by set_yank by their reserved IDs
+    yank_keys: HashMap<usize, String>,
+    yank_history: VecDeque<YankText>,
+    yank_history_size: usize,
+    yank_index: usize,
+    last_paste: Option<(usize, (usize, usize))>,
     search: Search,
-    pub hop: Hop, // TODO! only pub for debug pursposes
-    pub hop_pending: bool,
-    pub hopping: bool,
+    highlight_patterns: BTreeMap<String, Search>,
+    search_preview: bool,
+    preview: Option<SearchPreview>,
+    hop: Hop,
     alignment: Alignment,
     pub(crate) placeholder: String,
     pub(crate) placeholder_style: Style,
@@ -101,11 +279,41 @@ pub struct TextArea<'a> {
     cursor_style: Style,
     link_style: Style,
     max_col: u16,
+    enter_behavior: EnterBehavior,
+    modal: Option<Modal>,
+    compose: Option<ComposeTable>,
+    pending_compose: Option<char>,
+    repeat_behavior: RepeatBehavior,
+    recording: Option<InputRecording<'a>>,
+    edit_selection: Option<((usize, usize), (usize, usize))>,
+    marks: BTreeMap<char, (usize, usize)>,
+    key_bindings: KeyBindings,
+    smooth_scroll: bool,
+    scroll_target: Option<(u16, u16)>,
+    wide_cursor: bool,
+    overlay_rules: OverlayRules,
+    highlight_passes: Vec<HighlightPass>,
+    hashtag_style: Option<Style>,
+    mention_style: Option<Style>,
+    next_highlight_pass_id: usize,
+    url_detection: bool,
+    line_movement: LineMovement,
+    empty_selection_clipboard: bool,
+    token_providers: Vec<TokenProvider>,
+    include_resolver: Option<IncludeResolver>,
+    include_style: Style,
+    conceal: bool,
 }
 
+/// Colors shared by [`TextArea`] and [`TextInput`](crate::TextInput). Pass the same theme to both widgets, or to
+/// [`EditorGroup::set_theme`](crate::EditorGroup::set_theme), so that palette changes are applied from one place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TextAreaTheme {
+    /// Foreground color of the text.
     pub text: Color,
+    /// Background color of the selection.
     pub select: Color,
+    /// Foreground color of links.
     pub links: Color,
 }
 
@@ -119,6 +327,29 @@ impl Default for TextAreaTheme {
     }
 }
 
+impl TextAreaTheme {
+    /// Map the colors of the theme to the nearest colors which the terminal supports. This is useful to use the same
+    /// theme with RGB colors on terminals which only support 256 or 16 colors.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::{ColorSupport, TextArea, TextAreaTheme};
+    ///
+    /// let theme = TextAreaTheme { text: Color::Rgb(250, 250, 250), select: Color::Indexed(238), links: Color::Cyan };
+    /// let theme = theme.degrade(ColorSupport::Ansi16);
+    /// assert_eq!(theme, TextAreaTheme { text: Color::White, select: Color::DarkGray, links: Color::Cyan });
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.set_theme(theme);
+    /// ```
+    pub fn degrade(self, support: ColorSupport) -> Self {
+        Self {
+            text: support.map(self.text),
+            select: support.map(self.select),
+            links: support.map(self.links),
+        }
+    }
+}
+
 /// Convert any iterator whose elements can be converted into [`String`] into [`TextArea`]. Each [`String`] element is
 /// handled as line. Ensure that the strings don't contain any newlines. This method is useful to create [`TextArea`]
 /// from [`std::str::Lines`].
@@ -207,10 +438,17 @@ impl<'a> TextArea<'a> {
     /// let textarea = TextArea::new(lines);
     /// assert_eq!(textarea.lines(), ["hello", "...", "goodbye"]);
     /// ```
-    pub fn new(mut lines: Vec<String>, links: HashMap<usize, Link>, max_col: u16, theme: TextAreaTheme) -> Self {
+    #[allow(deprecated)]
+    pub fn new(
+        mut lines: Vec<String>,
+        links: impl IntoIterator<Item = (usize, Link)>,
+        max_col: u16,
+        theme: TextAreaTheme,
+    ) -> Self {
         if lines.is_empty() {
             lines.push(String::new());
         }
+        let links: BTreeMap<usize, Link> = links.into_iter().collect();
 
         let next_link_id = match links.keys().max() {
             Some(id) => id + 1,
@@ -218,28 +456,67 @@ impl<'a> TextArea<'a> {
         };
 
         let style = Style::new().fg(theme.text);
+        let mut paragraph_ids = ParagraphIds::default();
+        for row in 0..lines.len() {
+            paragraph_ids.assign(row);
+        }
         
         Self {
             lines,
             block: None,
+            block_title: None,
+            block_footer: None,
+            file_name: None,
+            modified: false,
             style,
             cursor: (0, 0),
+            desired_col: None,
             links,
             pending_link: None,
             next_link_id,
+            link_keys: LinkKeys::default(),
+            link_key_mode: LinkKeyMode::default(),
+            link_rows: LinkRows::default(),
+            line_cache: LineCache::default(),
+            line_meta: BTreeMap::new(),
+            max_line_bytes: None,
+            soft_breaks: BTreeSet::new(),
+            wrap_breaks: BTreeSet::new(),
+            paragraph_ids,
+            anchors: Anchors::default(),
+            changes: ChangeList::default(),
+            autosave: None,
+            state_observer: None,
+            revision: 0,
+            bell: Bell::default(),
+            pending_insert: None,
+            insert_batch_size: 1000,
+            #[cfg(feature = "journal")]
+            journal: None,
             new_link: false,
             deleted_link_ids: vec![],
             tab_len: 4,
             hard_tab_indent: false,
+            auto_pairs: false,
+            quote_aware: false,
+            auto_closers: vec![],
             history: History::new(50),
             cursor_line_style: Style::default(),
             line_number_style: None,
+            accessibility: Accessibility::Standard,
+            standard_styles: None,
             viewport: Viewport::default(),
             yank: YankText::default(),
+            yank_keys: HashMap::new(),
+            yank_history: VecDeque::new(),
+            yank_history_size: 10,
+            yank_index: 0,
+            last_paste: None,
             search: Search::default(),
+            highlight_patterns: BTreeMap::new(),
+            search_preview: false,
+            preview: None,
             hop: Hop::default(),
-            hop_pending: false,
-            hopping: false,
             alignment: Alignment::Left,
             placeholder: String::new(),
             placeholder_style: Style::default().fg(Color::DarkGray),
@@ -249,6 +526,30 @@ impl<'a> TextArea<'a> {
             cursor_style: Style::default().add_modifier(Modifier::REVERSED),
             link_style: Style::default().add_modifier(Modifier::BOLD).fg(theme.links),
             max_col,
+            enter_behavior: EnterBehavior::default(),
+            modal: None,
+            compose: None,
+            pending_compose: None,
+            repeat_behavior: RepeatBehavior::default(),
+            recording: None,
+            edit_selection: None,
+            marks: BTreeMap::new(),
+            key_bindings: KeyBindings::default(),
+            smooth_scroll: false,
+            scroll_target: None,
+            wide_cursor: true,
+            overlay_rules: OverlayRules::default(),
+            highlight_passes: vec![],
+            hashtag_style: None,
+            mention_style: None,
+            next_highlight_pass_id: 0,
+            url_detection: false,
+            line_movement: LineMovement::default(),
+            empty_selection_clipboard: false,
+            token_providers: vec![],
+            include_resolver: None,
+            include_style: Style::default().fg(Color::DarkGray),
+            conceal: false,
         }
     }
 
@@ -258,7 +559,7 @@ impl<'a> TextArea<'a> {
     /// [`Input`] so this method can take the event values directly.
     /// This method returns if the input modified text contents or not in the textarea.
     /// ```ignore
-    /// use tuipaz_textarea::{TextArea, Key, Input};
+    /// use tuipaz_textarea::{TextArea, Key, Input, InputKind};
     ///
     /// let mut textarea = TextArea::default();
     ///
@@ -284,12 +585,112 @@ impl<'a> TextArea<'a> {
     /// }
     ///
     /// // Handle backend-agnostic key input
-    /// let input = Input { key: Key::Char('a'), ctrl: false, alt: false, shift: false };
+    /// let input = Input { key: Key::Char('a'), ctrl: false, alt: false, shift: false, kind: InputKind::Press };
     /// let modified = textarea.input(input);
     /// assert!(modified);
     /// ```
     pub fn input(&mut self, input: impl Into<Input>) -> bool {
+        self.input_result(input).is_changed()
+    }
+
+    /// Handle a key input with default key mappings as [`TextArea::input`] does, but return an [`InputResult`] instead
+    /// of `bool`. When [`EnterBehavior::Submit`] is set, Enter returns [`InputResult::Submitted`] without inserting
+    /// a newline.
+    /// ```
+    /// use tuipaz_textarea::{EnterBehavior, Input, InputKind, InputResult, Key, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.set_enter_behavior(EnterBehavior::Submit);
+    ///
+    /// let enter = Input { key: Key::Enter, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+    /// assert_eq!(textarea.input_result(enter), InputResult::Submitted);
+    /// assert_eq!(textarea.lines(), [""]);
+    ///
+    /// // Shift+Enter inserts a newline
+    /// let shift_enter = Input { key: Key::Enter, ctrl: false, alt: false, shift: true, kind: InputKind::Press };
+    /// assert_eq!(textarea.input_result(shift_enter), InputResult::Changed);
+    /// assert_eq!(textarea.lines(), ["", ""]);
+    /// ```
+    pub fn input_result(&mut self, input: impl Into<Input>) -> InputResult {
         let input = input.into();
+        self.record_input(input, true);
+        self.handle_input(input)
+    }
+
+    fn handle_input(&mut self, input: Input) -> InputResult {
+        match (input.kind, self.repeat_behavior) {
+            (InputKind::Release, _) | (InputKind::Repeat, RepeatBehavior::Ignore) => return InputResult::Unchanged,
+            (InputKind::Repeat, RepeatBehavior::Accelerate(steps))
+                if matches!(input.key, Key::Up | Key::Down | Key::Left | Key::Right) =>
+            {
+                let press = Input {
+                    kind: InputKind::Press,
+                    ..input
+                };
+                let mut result = InputResult::Unchanged;
+                for _ in 0..cmp::max(steps, 1) {
+                    if self.handle_input(press).is_changed() {
+                        result = InputResult::Changed;
+                    }
+                }
+                return result;
+            }
+            _ => {}
+        }
+
+        let (input, composed) = match self.compose_input(input) {
+            (Some(input), composed) => (input, composed),
+            (None, true) => return InputResult::Changed,
+            (None, false) => return InputResult::Unchanged,
+        };
+        if let Some(action) = self.modal.as_mut().and_then(|m| m.action(&input)) {
+            return if self.apply_modal_action(action) {
+                InputResult::Changed
+            } else {
+                InputResult::Unchanged
+            };
+        }
+        if self.enter_behavior == EnterBehavior::Submit {
+            // Ctrl+M is an alias of Enter
+            match input {
+                Input {
+                    key: Key::Enter,
+                    shift: false,
+                    ..
+                }
+                | Input {
+                    key: Key::Char('m'),
+                    ctrl: true,
+                    alt: false,
+                    shift: false,
+                    ..
+                } => return InputResult::Submitted,
+                _ => {}
+            }
+        }
+
+        if let Some(command) = self.key_bindings.get(KeyChord::from(input)) {
+            let modified = match command.cursor_move() {
+                Some(m) if input.shift => {
+                    self.move_cursor_with_shift(m, true);
+                    false
+                }
+                _ => self.execute(command),
+            };
+            return if modified || composed {
+                InputResult::Changed
+            } else {
+                InputResult::Unchanged
+            };
+        }
+        if !self.key_bindings.fallback() && !is_plain_input(&input) {
+            return if composed {
+                InputResult::Changed
+            } else {
+                InputResult::Unchanged
+            };
+        }
+
         let modified = match input {
             Input {
                 key: Key::Char('m'),
@@ -314,16 +715,27 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: false,
                 ..
-            } => {
-                self.insert_char(c);
-                true
-            }
+            } => self.type_char(c),
+            Input {
+                key: Key::Tab,
+                ctrl: false,
+                alt: false,
+                shift: false,
+                ..
+            } if self.selection_start.is_some() => self.indent_lines(),
             Input {
                 key: Key::Tab,
                 ctrl: false,
                 alt: false,
+                shift: false,
                 ..
             } => self.insert_tab(),
+            Input {
+                key: Key::BackTab | Key::Tab,
+                ctrl: false,
+                alt: false,
+                ..
+            } => self.dedent_lines(),
             Input {
                 key: Key::Char('h'),
                 ctrl: true,
@@ -395,14 +807,20 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Down,
                 ctrl: false,
                 alt: false,
                 shift,
+                ..
             } => {
-                self.move_cursor_with_shift(CursorMove::Down, shift);
+                let m = match self.line_movement {
+                    LineMovement::Visual => CursorMove::Down,
+                    LineMovement::Logical => CursorMove::LogicalLineDown,
+                };
+                self.move_cursor_with_shift(m, shift);
                 false
             }
             Input {
@@ -410,14 +828,20 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Up,
                 ctrl: false,
                 alt: false,
                 shift,
+                ..
             } => {
-                self.move_cursor_with_shift(CursorMove::Up, shift);
+                let m = match self.line_movement {
+                    LineMovement::Visual => CursorMove::Up,
+                    LineMovement::Logical => CursorMove::LogicalLineUp,
+                };
+                self.move_cursor_with_shift(m, shift);
                 false
             }
             Input {
@@ -425,12 +849,14 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Right,
                 ctrl: false,
                 alt: false,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::Forward, shift);
                 false
@@ -440,12 +866,14 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Left,
                 ctrl: false,
                 alt: false,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::Back, shift);
                 false
@@ -455,6 +883,7 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Home,
@@ -466,6 +895,7 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: true,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::Head, shift);
                 false
@@ -475,6 +905,7 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             }
             | Input {
                 key: Key::End,
@@ -486,6 +917,7 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: true,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::End, shift);
                 false
@@ -495,12 +927,14 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Up | Key::Char('p'),
                 ctrl: true,
                 alt: true,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::Top, shift);
                 false
@@ -510,12 +944,14 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Down | Key::Char('n'),
                 ctrl: true,
                 alt: true,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::Bottom, shift);
                 false
@@ -525,12 +961,14 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Right,
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::WordForward, shift);
                 false
@@ -540,12 +978,14 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Left,
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::WordBack, shift);
                 false
@@ -555,18 +995,21 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Char('n'),
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Down,
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::ParagraphForward, shift);
                 false
@@ -576,18 +1019,21 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Char('p'),
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::Up,
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             } => {
                 self.move_cursor_with_shift(CursorMove::ParagraphBack, shift);
                 false
@@ -635,6 +1081,7 @@ impl<'a> TextArea<'a> {
                 ctrl: true,
                 alt: false,
                 shift,
+                ..
             }
             | Input {
                 key: Key::PageDown,
@@ -649,6 +1096,7 @@ impl<'a> TextArea<'a> {
                 ctrl: false,
                 alt: true,
                 shift,
+                ..
             }
             | Input {
                 key: Key::PageUp,
@@ -696,8 +1144,110 @@ impl<'a> TextArea<'a> {
             input,
         );
 
+        if modified || composed {
+            InputResult::Changed
+        } else {
+            InputResult::Unchanged
+        }
+    }
+
+    // Run the compose step on the input. Returns the input to handle (`None` when the input was consumed) and if the
+    // text was modified by typing a pending dead key as is.
+    fn compose_input(&mut self, input: Input) -> (Option<Input>, bool) {
+        let table = match &self.compose {
+            Some(table) => table,
+            None => return (Some(input), false),
+        };
 
-        modified
+        let c = match input {
+            Input {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            } => c,
+            Input { key: Key::Null, .. } => return (Some(input), false),
+            _ => {
+                // Any other key finishes the sequence and types the dead key as is
+                return match self.pending_compose.take() {
+                    Some(dead) => {
+                        self.insert_char(dead);
+                        (Some(input), true)
+                    }
+                    None => (Some(input), false),
+                };
+            }
+        };
+
+        let is_dead_key = table.is_dead_key(c);
+        let dead = match self.pending_compose.take() {
+            Some(dead) => dead,
+            None if is_dead_key => {
+                self.pending_compose = Some(c);
+                return (None, false);
+            }
+            None => return (Some(input), false),
+        };
+
+        if let Some(composed) = table.get(dead, c) {
+            let input = Input {
+                key: Key::Char(composed),
+                ..input
+            };
+            return (Some(input), false);
+        }
+
+        // Not a sequence. Type the dead key as is. Typing the same dead key twice types it once
+        self.insert_char(dead);
+        if c == dead {
+            return (None, true);
+        }
+        if is_dead_key {
+            self.pending_compose = Some(c);
+            return (None, true);
+        }
+        (Some(input), true)
+    }
+
+    /// Handle a key input with default key mappings as [`TextArea::input`] does, but return [`InputEffects`] which
+    /// describes everything the input changed: text, cursor, selection, links and submission.
+    /// ```
+    /// use tuipaz_textarea::{Input, InputKind, Key, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["abc"]);
+    ///
+    /// let right = Input { key: Key::Right, ctrl: false, alt: false, shift: true, kind: InputKind::Press };
+    /// let effects = textarea.input_ext(right);
+    /// assert!(effects.cursor_moved);
+    /// assert!(effects.selection_changed);
+    /// assert!(!effects.text_changed);
+    /// ```
+    #[allow(deprecated)]
+    pub fn input_ext(&mut self, input: impl Into<Input>) -> InputEffects {
+        let cursor = self.cursor;
+        let selection = self.selection_snapshot();
+        let next_link_id = self.next_link_id;
+        let link_count = self.links.values().filter(|l| !l.deleted).count();
+        let link_before = self.in_link(cursor);
+
+        let result = self.input_result(input);
+
+        let link_after = self.in_link(self.cursor);
+        InputEffects {
+            text_changed: result == InputResult::Changed,
+            cursor_moved: self.cursor != cursor,
+            selection_changed: self.selection_snapshot() != selection,
+            link_created: self.next_link_id != next_link_id,
+            link_deleted: self.links.values().filter(|l| !l.deleted).count()
+                < link_count + (self.next_link_id - next_link_id),
+            link_entered: link_after.filter(|_| link_after != link_before),
+            submitted: result == InputResult::Submitted,
+        }
+    }
+
+    fn selection_snapshot(&self) -> Option<((usize, usize), (usize, usize))> {
+        self.selection_range()
+            .map(|(s, e)| ((s.row, s.col), (e.row, e.col)))
     }
 
     /// Handle a key input without default key mappings. This method handles only
@@ -713,22 +1263,38 @@ impl<'a> TextArea<'a> {
     /// This method is useful when you want to define your own key mappings and don't want default key mappings.
     /// See 'Define your own key mappings' section in [the module document](./index.html).
     pub fn input_without_shortcuts(&mut self, input: impl Into<Input>) -> bool {
-        match input.into() {
+        let input = input.into();
+        self.record_input(input, false);
+        if input.kind == InputKind::Release {
+            return false;
+        }
+        match input {
             Input {
                 key: Key::Char(c),
                 ctrl: false,
                 alt: false,
                 ..
-            } => {
-                self.insert_char(c);
-                true
-            }
+            } => self.type_char(c),
+            Input {
+                key: Key::Tab,
+                ctrl: false,
+                alt: false,
+                shift: false,
+                ..
+            } if self.selection_start.is_some() => self.indent_lines(),
             Input {
                 key: Key::Tab,
                 ctrl: false,
                 alt: false,
+                shift: false,
                 ..
             } => self.insert_tab(),
+            Input {
+                key: Key::BackTab | Key::Tab,
+                ctrl: false,
+                alt: false,
+                ..
+            } => self.dedent_lines(),
             Input {
                 key: Key::Backspace,
                 ..
@@ -760,27 +1326,184 @@ impl<'a> TextArea<'a> {
         }
     }
 
-    fn push_history(&mut self, kind: EditKind, before: Pos, after_offset: usize) {
-        let (row, col) = self.cursor;
-        let after = Pos::new(row, col, after_offset);
-        let edit = Edit::new(kind, before, after);
-        self.history.push(edit);
+    /// Start recording key inputs handled by [`TextArea::input`], [`TextArea::input_result`],
+    /// [`TextArea::input_ext`] and [`TextArea::input_without_shortcuts`]. The current state of the textarea is saved
+    /// as the initial state of the recording. When recording is already enabled, the recording is restarted.
+    /// See [`InputRecording`] for the example.
+    pub fn enable_input_recording(&mut self) {
+        let mut initial = self.clone();
+        initial.recording = None;
+        self.recording = Some(InputRecording::new(initial));
+    }
+
+    /// Stop recording key inputs and return the recording. `None` is returned when recording is not enabled.
+    pub fn disable_input_recording(&mut self) -> Option<InputRecording<'a>> {
+        self.recording.take()
+    }
+
+    /// Get the current recording of key inputs. `None` is returned when recording is not enabled.
+    pub fn input_recording(&self) -> Option<&InputRecording<'a>> {
+        self.recording.as_ref()
     }
 
-    fn get_current_line_len(&self) -> u16 {
-        self.lines[self.cursor.0].len() as u16
+    /// Create a textarea by replaying the recorded key inputs on the initial state of the recording. The viewport at
+    /// each input is restored so that the result does not depend on the terminal size.
+    pub fn replay(recording: &InputRecording<'a>) -> Self {
+        let mut textarea = recording.initial().clone();
+        for recorded in recording.inputs() {
+            let (row, col, width, height) = recorded.viewport;
+            textarea.viewport.store(row, col, width, height);
+            if recorded.shortcuts {
+                textarea.input_result(recorded.input);
+            } else {
+                textarea.input_without_shortcuts(recorded.input);
+            }
+        }
+        textarea
+    }
+
+    /// Apply the deterministic operation to the textarea. This method returns if the operation modified text
+    /// contents or not in the textarea. Combined with [`TextArea::check_invariants`], random sequences of operations
+    /// can be checked by fuzzers and property tests.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, Op, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["abc"]);
+    ///
+    /// assert!(textarea.apply_op(Op::InsertStr("xyz".into())));
+    /// assert!(!textarea.apply_op(Op::Move(CursorMove::End)));
+    /// assert!(textarea.apply_op(Op::Undo));
+    /// assert_eq!(textarea.lines(), ["abc"]);
+    /// assert!(textarea.check_invariants().is_ok());
+    /// ```
+    #[allow(deprecated)]
+    pub fn apply_op(&mut self, op: Op) -> bool {
+        match op {
+            Op::Input(input) => return self.input(input),
+            Op::Move(m) => self.move_cursor(m),
+            Op::InsertChar(c) => {
+                self.insert_char(c);
+                return true;
+            }
+            Op::InsertStr(s) => {
+                let cursor = self.cursor;
+                return self.insert_str(s, cursor);
+            }
+            Op::InsertNewline => {
+                self.insert_newline();
+                return true;
+            }
+            Op::InsertTab => return self.insert_tab(),
+            Op::DeleteStr(chars) => return self.delete_str(chars),
+            Op::DeleteChar => return self.delete_char(),
+            Op::DeleteNextChar => return self.delete_next_char(),
+            Op::DeleteWord => return self.delete_word(),
+            Op::DeleteNextWord => return self.delete_next_word(),
+            Op::DeleteLineByEnd => return self.delete_line_by_end(),
+            Op::DeleteLineByHead => return self.delete_line_by_head(),
+            Op::DeleteLine(shift_up) => return self.delete_line(shift_up),
+            Op::StartSelection => self.start_selection(),
+            Op::CancelSelection => self.cancel_selection(),
+            Op::SelectAll => self.select_all(),
+            Op::Copy => self.copy(),
+            Op::Cut => return self.cut(),
+            Op::Paste => return self.paste(),
+            Op::Undo => return self.undo(),
+            Op::Redo => return self.redo(),
+            Op::StartLink => self.init_link(),
+            Op::InsertLink => self.insert_link(),
+            Op::DeleteLink(id) => {
+                if self.links.get(&id).map_or(false, |l| !l.deleted) {
+                    self.delete_link(id);
+                }
+            }
+            Op::Scroll(rows, cols) => self.scroll((rows, cols)),
+        }
+        false
     }
 
-    fn get_prev_line_len(&self) -> u16 {
-        self.lines[self.cursor.0 - 1].len() as u16
+    /// Check the invariants of the textarea state: there is at least one line, the cursor and the selection are
+    /// within the text, and every live link is stored with its own ID and its range is within its line.
+    #[allow(deprecated)]
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        if self.lines.is_empty() {
+            return Err(InvariantViolation::NoLine);
+        }
+
+        let in_text = |(row, col): (usize, usize)| {
+            self.lines
+                .get(row)
+                .map_or(false, |line| line.chars().count() >= col)
+        };
+
+        if !in_text(self.cursor) {
+            return Err(InvariantViolation::CursorOutOfBounds {
+                cursor: self.cursor,
+                lines: self.lines.len(),
+                line_len: self.lines.get(self.cursor.0).map(|l| l.chars().count()),
+            });
+        }
+
+        if let Some(start) = self.selection_start {
+            if !in_text(start) {
+                return Err(InvariantViolation::SelectionOutOfBounds { start });
+            }
+        }
+
+        for (key, link) in &self.links {
+            if *key != link.id {
+                return Err(InvariantViolation::LinkIdMismatch {
+                    key: *key,
+                    id: link.id,
+                });
+            }
+            if link.id >= self.next_link_id {
+                return Err(InvariantViolation::LinkIdNotAllocated {
+                    id: link.id,
+                    next_link_id: self.next_link_id,
+                });
+            }
+            if !link.deleted && (link.start_col > link.end_col || !in_text((link.row, link.end_col))) {
+                return Err(InvariantViolation::LinkOutOfBounds {
+                    id: link.id,
+                    row: link.row,
+                    start_col: link.start_col,
+                    end_col: link.end_col,
+                });
+            }
+        }
+
+        Ok(())
     }
 
-    fn check_current_row_overhang(&self) -> bool {
-        self.get_current_line_len() >= self.max_col - 1
+    fn record_input(&mut self, input: Input, shortcuts: bool) {
+        let viewport = self.viewport.rect();
+        if let Some(recording) = &mut self.recording {
+            recording.push(RecordedInput {
+                input,
+                viewport,
+                shortcuts,
+            });
+        }
     }
 
-    fn check_prev_row_space(&self) -> bool {
-        self.get_prev_line_len() < self.max_col - 1
+    fn push_history(&mut self, kind: EditKind, before: Pos, after_offset: usize) {
+        self.last_paste = None;
+        let (row, col) = self.cursor;
+        let after = Pos::new(row, col, after_offset);
+        let context = EditContext {
+            scroll_top: self.viewport.scroll_top(),
+            selection: self.edit_selection.take(),
+        };
+        let mut edit = Edit::new(kind, before, after, context);
+        let soft_breaks = (!self.soft_breaks.is_empty()).then(|| self.soft_breaks.clone());
+        let removed_meta = self.track_edit(&edit);
+        edit.set_removed_line_meta(removed_meta);
+        if soft_breaks.is_some() || !self.soft_breaks.is_empty() {
+            edit.set_soft_breaks(soft_breaks.unwrap_or_default(), self.soft_breaks.clone());
+        }
+        self.changes.record((row, col));
+        self.history.push(edit);
     }
 
     /// Insert a single character at current cursor position.
@@ -800,11 +1523,14 @@ impl<'a> TextArea<'a> {
             self.init_link();
         } else if c == ']' {
             self.insert_link();
-        } else if self.check_current_row_overhang() {
-            self.shift_lines_after_insert();
         }
+        self.insert_char_at_cursor(c);
+    }
 
+    // Insert the character without starting or finishing a link with `[` and `]`
+    fn insert_char_at_cursor(&mut self, c: char) {
         let (row, col) = self.cursor;
+        let links_before = self.begin_reflow_group(row, 1);
         self.shift_links_same_row(row, (col, col + 1));
 
         let line = &mut self.lines[row];
@@ -820,6 +1546,81 @@ impl<'a> TextArea<'a> {
             Pos::new(row, col, i),
             i + c.len_utf8(),
         );
+        self.reflow_after_insert();
+        self.end_reflow_group(links_before);
+    }
+
+    // Insert the typed character considering auto-pairing. Returns `false` when the typed closer was skipped.
+    #[allow(deprecated)]
+    fn type_char(&mut self, c: char) -> bool {
+        if !self.auto_pairs {
+            self.insert_char(c);
+            return true;
+        }
+
+        // Edits in `insert_char` clear the closers so keep them aside while typing
+        let mut closers = std::mem::take(&mut self.auto_closers);
+        let (row, col) = self.cursor;
+        let line = &self.lines[row];
+        let next = line.chars().nth(col);
+        if closers.last() == Some(&(row, col)) && next == Some(c) {
+            closers.pop();
+            // Typing `]` over the auto-inserted one still finishes the link started by `[`
+            let linked = c == ']' && self.pending_link.is_some() && {
+                self.insert_link();
+                self.pending_link.is_none()
+            };
+            self.move_cursor(CursorMove::Forward);
+            self.auto_closers = closers;
+            return linked;
+        }
+
+        let prev = col.checked_sub(1).and_then(|i| line.chars().nth(i));
+        let closer = auto_pair_closer(c).filter(|&closer| {
+            let next_ok = next.map_or(true, |n| n.is_whitespace() || is_auto_pair_closer(n));
+            // Avoid pairing an apostrophe in a word such as `don't`
+            let prev_ok = closer != c || prev.map_or(true, |p| !p.is_alphanumeric() && p != c);
+            next_ok && prev_ok
+        });
+        let closer = match closer {
+            Some(closer) => closer,
+            None => {
+                self.insert_char(c);
+                for pos in closers.iter_mut() {
+                    if pos.0 == row && pos.1 >= col {
+                        pos.1 += 1;
+                    }
+                }
+                // Reflow may move the text to the next line
+                if self.cursor == (row, col + 1) {
+                    self.auto_closers = closers;
+                }
+                return true;
+            }
+        };
+
+        let links_before = if self.history.is_grouping() {
+            None
+        } else {
+            self.history.begin_group();
+            Some(self.links.clone())
+        };
+        self.insert_char(c);
+        self.insert_char_at_cursor(closer);
+        self.move_cursor(CursorMove::Back);
+        if let Some(links_before) = links_before {
+            self.history.end_group(links_before, self.links.clone());
+        }
+        if self.cursor == (row, col + 1) {
+            for pos in closers.iter_mut() {
+                if pos.0 == row && pos.1 >= col {
+                    pos.1 += 2;
+                }
+            }
+            closers.push(self.cursor);
+            self.auto_closers = closers;
+        }
+        true
     }
 
     /// Insert a string at current cursor position. This method returns if some text was inserted or not in the textarea.
@@ -851,26 +1652,189 @@ impl<'a> TextArea<'a> {
         }
     }
 
-    fn insert_chunk(&mut self, chunk: Vec<String>, yank_pos: (usize, usize)) -> bool {
-        debug_assert!(chunk.len() > 1, "Chunk size must be > 1: {:?}", chunk);
-
+    /// Insert a string at current cursor position, hard wrapping it at the max column in the same way as typing. Long
+    /// lines in the string are broken at spaces so that pasting a long paragraph does not create over-long lines. The
+    /// first line is wrapped with the text before the cursor, and the last line is rewrapped with the rest of the line
+    /// after the cursor and the following rows of the paragraph as typing does. When
+    /// [quote aware](TextArea::set_quote_aware), the rows after the first one in a quote block get the quote prefix as
+    /// typed rows do. In a fenced code block the string is inserted as it is. The insertion is undone as one edit.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tuipaz_textarea::{CursorMove, TextArea, TextAreaTheme};
+    ///
+    /// let mut textarea = TextArea::new(vec!["> ".to_string()], HashMap::new(), 13, TextAreaTheme::default());
+    /// textarea.set_quote_aware(true);
+    /// textarea.move_cursor(CursorMove::End);
+    ///
+    /// textarea.insert_str_wrapped("a long paragraph pasted at once");
+    /// assert_eq!(textarea.lines(), ["> a long ", "> paragraph ", "> pasted at ", "> once"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn insert_str_wrapped<S: AsRef<str>>(&mut self, s: S) -> bool {
+        let links_before = if self.history.is_grouping() {
+            None
+        } else {
+            self.history.begin_group();
+            Some(self.links.clone())
+        };
+        let modified = self.delete_selection(false);
         let (row, col) = self.cursor;
-        let line = &mut self.lines[row];
-        let i = line
-            .char_indices()
-            .nth(col)
-            .map(|(i, _)| i)
-            .unwrap_or(line.len());
-        let before = Pos::new(row, col, i);
-        
-        let clen = chunk.len();
-        let (new_row, new_col) = (
-            row + clen - 1,
-            chunk[clen - 1].chars().count(),
-        );
-        self.cursor = (new_row, new_col);
-        self.shift_links_after_insert((row, col), (new_row, new_col), yank_pos);
-
+        let code = self.line_cache.code_block_at(&self.lines, row).is_some();
+        // Rows after the first one are put in the quote block
+        let mut prefix = "";
+        if self.quote_aware && !code {
+            let p = quote_prefix(&self.lines[row]);
+            if col >= p.len() && p.len() < (self.max_col as usize).saturating_sub(1) {
+                prefix = p;
+            }
+        }
+        let s = s.as_ref().replace('\n', &format!("\n{}", prefix));
+
+        let inserted = self.insert_str(s, (row, col));
+        if inserted && !code {
+            // Reflow from the last row so that the cursor at the end of the insertion is never before the reflowed
+            // row. Line breaks in the string are kept
+            let end = self.cursor.0;
+            self.reflow_row(end, true);
+            for r in (row..end).rev() {
+                self.reflow_row(r, false);
+            }
+        }
+        if let Some(links_before) = links_before {
+            self.history.end_group(links_before, self.links.clone());
+        }
+        inserted || modified
+    }
+
+    /// Insert a string at current cursor position in batches so that inserting a very large text (e.g. tens of
+    /// thousands of lines) does not block the UI. The first batch is inserted immediately and each following call of
+    /// [`TextArea::tick`] inserts the next batch of [`TextArea::insert_batch_size`] lines. The cursor is moved to the
+    /// end of the inserted text on each batch, so the host should not forward key inputs to the textarea while
+    /// [`TextArea::is_inserting`] returns `true`. Each batch is recorded in the history when it is inserted. The whole
+    /// insertion is undone as one edit unless other edits were made between the batches. Edits made between the
+    /// batches move the position where the next batch is inserted.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.set_insert_batch_size(2);
+    ///
+    /// textarea.insert_str_incremental("a\nb\nc\nd\ne");
+    /// assert_eq!(textarea.insert_progress(), Some((2, 5)));
+    ///
+    /// while textarea.is_inserting() {
+    ///     textarea.tick();
+    /// }
+    /// assert_eq!(textarea.lines(), ["a", "b", "c", "d", "e"]);
+    ///
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), [""]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn insert_str_incremental<S: AsRef<str>>(&mut self, s: S) -> bool {
+        self.finish_incremental_insert();
+        if s.as_ref().is_empty() {
+            return self.delete_selection(false);
+        }
+        let grouping = self.history.is_grouping();
+        let links_before = self.links.clone();
+        self.history.begin_group();
+        let deleted = self.delete_selection(false);
+        if !grouping {
+            self.history.end_group(links_before, self.links.clone());
+        }
+        // The first batch is merged into the deletion of the selection
+        let revision = deleted.then(|| self.revision);
+        self.pending_insert = Some(PendingInsert::new(s.as_ref(), self.cursor, revision));
+        self.insert_next_batch();
+        true
+    }
+
+    /// Check if the text passed to [`TextArea::insert_str_incremental`] is still being inserted.
+    pub fn is_inserting(&self) -> bool {
+        self.pending_insert.is_some()
+    }
+
+    /// Get the progress of the insertion started by [`TextArea::insert_str_incremental`] as the number of lines
+    /// inserted so far and the number of all lines. `None` is returned when no text is being inserted.
+    pub fn insert_progress(&self) -> Option<(usize, usize)> {
+        self.pending_insert.as_ref().map(PendingInsert::progress)
+    }
+
+    /// Insert all the remaining text of [`TextArea::insert_str_incremental`] at once. Undo and redo call this method
+    /// before changing the text. Returns `true` when some text was inserted.
+    pub fn finish_incremental_insert(&mut self) -> bool {
+        let mut inserted = false;
+        while self.insert_next_batch() {
+            inserted = true;
+        }
+        inserted
+    }
+
+    /// Set the max number of lines inserted at once by [`TextArea::insert_str_incremental`]. The default value is 1000.
+    /// The minimum value is 1.
+    pub fn set_insert_batch_size(&mut self, size: usize) {
+        self.insert_batch_size = size.max(1);
+    }
+
+    /// Get the max number of lines inserted at once by [`TextArea::insert_str_incremental`].
+    pub fn insert_batch_size(&self) -> usize {
+        self.insert_batch_size
+    }
+
+    // Insert the next batch of the pending insertion and finish the insertion after the last batch. Each batch is
+    // recorded as one closed group so that edits made between the batches are not collected into it
+    #[allow(deprecated)]
+    fn insert_next_batch(&mut self) -> bool {
+        let mut pending = match self.pending_insert.take() {
+            Some(pending) => pending,
+            None => return false,
+        };
+        let pos = self.clamp_pos(pending.pos);
+        if let Some(batch) = pending.next_batch(self.insert_batch_size) {
+            let grouping = self.history.is_grouping();
+            let merge = pending.revision == Some(self.revision);
+            let links_before = self.links.clone();
+            self.history.begin_group();
+            self.cursor = pos;
+            self.insert_str(batch, pos);
+            if !grouping {
+                self.history.end_group(links_before, self.links.clone());
+                if merge {
+                    self.history.merge_last_groups();
+                }
+            }
+        }
+
+        if !pending.is_done() {
+            pending.pos = self.cursor;
+            pending.revision = Some(self.revision);
+            self.pending_insert = Some(pending);
+        }
+        true
+    }
+
+    #[allow(deprecated)]
+    fn insert_chunk(&mut self, chunk: Vec<String>, yank_pos: (usize, usize)) -> bool {
+        debug_assert!(chunk.len() > 1, "Chunk size must be > 1: {:?}", chunk);
+
+        let (row, col) = self.cursor;
+        let line = &mut self.lines[row];
+        let i = line
+            .char_indices()
+            .nth(col)
+            .map(|(i, _)| i)
+            .unwrap_or(line.len());
+        let before = Pos::new(row, col, i);
+        
+        let clen = chunk.len();
+        let (new_row, new_col) = (
+            row + clen - 1,
+            chunk[clen - 1].chars().count(),
+        );
+        self.cursor = (new_row, new_col);
+        self.shift_links_after_insert((row, col), (new_row, new_col), yank_pos);
+
         let end_offset = chunk.last().unwrap().len();
 
         let mut edit = EditKind::InsertChunk((chunk, None));
@@ -887,6 +1851,7 @@ impl<'a> TextArea<'a> {
         }
 
         let (row, col) = self.cursor;
+        let links_before = self.begin_reflow_group(row, s.chars().count());
         let line = &mut self.lines[row];
         debug_assert!(
             !s.contains('\n'),
@@ -903,9 +1868,7 @@ impl<'a> TextArea<'a> {
             .unwrap_or(line_len);
         let end_offset = i + s_len;
 
-        let overhang = (line_len + s_len) as i32 - self.max_col as i32;
-
-        info!("insert_piece\ni: {:?}\nend_offset: {:?}\noverhang: {}\nself.max_col: {}", i, end_offset, overhang, self.max_col);
+        info!("insert_piece\ni: {:?}\nend_offset: {:?}\nself.max_col: {}", i, end_offset, self.max_col);
 
         line.insert_str(i, &s);
         info!("line: {}", &line);
@@ -915,26 +1878,18 @@ impl<'a> TextArea<'a> {
             row, col, row, self.cursor.1, insert_pos.0, insert_pos.1
         );
 
-        self.cursor.1 += s_len;
-        let start_cursor = self.cursor;
+        self.cursor.1 += s.chars().count();
 
         self.shift_links_after_insert((row, col), (row, self.cursor.1), insert_pos);
         info!("self.cursor after move: {:?}", self.cursor);
-        if overhang >= 0 {
-            let max_col = self.max_col as usize;
-            self.shift_lines_after_insert();
-            self.cursor = match start_cursor.1 >= max_col {
-                true => (start_cursor.0 + 1, end_offset - max_col),
-                false => (start_cursor.0, start_cursor.1 + s_len - 1),
-            };
-        } 
-
         self.push_history(EditKind::InsertStr((s, None)), Pos::new(row, col, i), end_offset);
+        self.reflow_after_insert();
+        self.end_reflow_group(links_before);
         true
     }
 
-    fn delete_range(&mut self, start: Pos, end: Pos, deleted_links: MaybeLinks, should_yank: bool) {
-        info!("INSIDE delete_range");
+    fn remove_range(&mut self, start: Pos, end: Pos, deleted_links: MaybeLinks, should_yank: bool) {
+        info!("INSIDE remove_range");
         self.cursor = (start.row, start.col);
 
         let link_ids = deleted_links.as_ref()
@@ -946,7 +1901,7 @@ impl<'a> TextArea<'a> {
                 .as_str()
                 .to_string();
             if should_yank {
-                self.yank = YankText::Piece((removed.clone(), deleted_links, (start.row, start.col)));
+                self.push_yank(YankText::Piece((removed.clone(), deleted_links, (start.row, start.col))));
             }
             self.push_history(EditKind::DeleteStr((removed, link_ids)), end, start.offset);
             return;
@@ -965,7 +1920,7 @@ impl<'a> TextArea<'a> {
         }
 
         if should_yank {
-            self.yank = YankText::Chunk((deleted.clone(), deleted_links, (start.row, start.col)));
+            self.push_yank(YankText::Chunk((deleted.clone(), deleted_links, (start.row, start.col))));
         }
 
         let edit = if deleted.len() == 1 {
@@ -994,6 +1949,7 @@ impl<'a> TextArea<'a> {
     /// textarea.delete_str(4); // Deletes 🐶, \n, 🐰, \n
     /// assert_eq!(textarea.lines(), ["🐱", "🐮"]);
     /// ```
+    #[allow(deprecated)]
     pub fn delete_str(&mut self, chars: usize) -> bool {
         info!("INSIDE delete_str");
         if self.delete_selection(false) {
@@ -1073,7 +2029,7 @@ impl<'a> TextArea<'a> {
                     false => Some(l),
                 };
 
-                self.yank = YankText::Piece((removed.clone(), deleted_links, (start.row, start.col)));
+                self.push_yank(YankText::Piece((removed.clone(), deleted_links, (start.row, start.col))));
                 self.push_history(
                     EditKind::DeleteStr((removed, None)),
                     Pos::new(start_row, end_col, end_offset),
@@ -1098,7 +2054,7 @@ impl<'a> TextArea<'a> {
 
             let start = Pos::new(start_row, start_col, start_offset);
             let end = Pos::new(r, col, offset);
-            self.delete_range(start, end, None, true);
+            self.remove_range(start, end, None, true);
             true
         }
 
@@ -1146,7 +2102,7 @@ impl<'a> TextArea<'a> {
                 Pos::new(row, col + chars, i + bytes),
                 i
             );
-            self.yank = YankText::Piece((removed, deleted_links, (row, start_col)));
+            self.push_yank(YankText::Piece((removed, deleted_links, (row, start_col))));
             true
         } else {
             false
@@ -1188,140 +2144,124 @@ impl<'a> TextArea<'a> {
         self.insert_piece(spaces(len).to_string(), (row, col))
     }
     
-    fn shift_lines_after_insert(&mut self) {
-        info!("shift_lines_after_insert");
-        let (start_row, start_col) = self.cursor;
-        let mut insert_at_end_of_line = false;
-        let mut word_offset = 0;
-        let max_row = self.lines.len();
-        info!("shift_lines_after_insert::max_row: {}", max_row);
-
-        while self.cursor.0 < max_row && self.check_current_row_overhang() {
-            info!("shift_lines_after_insert::current line len {}", self.lines[self.cursor.0].len());
-            info!("shift_lines_after_insert::current_row BEFORE: {}", self.cursor.0);
-            (word_offset, insert_at_end_of_line) = self.shift_overhang_newline();
-            info!("shift_lines_after_insert::current_row AFTER: {}", self.cursor.0);
-            info!("shift_lines_after_insert::word_offset: {:?}", word_offset);
+    // Start a history group when inserting `extra` chars into the row may reflow it, so that the insertion and the
+    // reflow are undone at once. The links before the insertion are returned when a group was started.
+    #[allow(deprecated)]
+    fn begin_reflow_group(&mut self, row: usize, extra: usize) -> Option<BTreeMap<usize, Link>> {
+        let limit = (self.max_col as usize).saturating_sub(1);
+        if self.history.is_grouping() || self.lines[row].chars().count() + extra <= limit {
+            return None;
         }
-        
-        info!("shift_lines_after_insert::insert_at_end_of_line: {}", insert_at_end_of_line);
-        if insert_at_end_of_line {
-            self.cursor = (start_row + 1, word_offset);
-        } else {
-            let max_line_col = self.lines[start_row].len() - 1;
-            info!("shift_lines_after_insert::max_line_col <= start_col: {}", start_col >= max_line_col);
-            self.cursor = match start_col >= max_line_col {
-                true => (start_row + 1, 0),
-                false => (start_row, start_col),
-            };
+        self.history.begin_group();
+        Some(self.links.clone())
+    }
+
+    #[allow(deprecated)]
+    fn end_reflow_group(&mut self, links_before: Option<BTreeMap<usize, Link>>) {
+        if let Some(links_before) = links_before {
+            self.history.end_group(links_before, self.links.clone());
         }
-        info!("shift_lines_after_insert:: FINAL self.cursor: {:?}", self.cursor);
     }
 
-    fn shift_overhang_newline(&mut self) -> (usize, bool) {
-        info!("shift_overhang_newline");
-        let insert_at_end_of_line = self.cursor.1 as u16 >= self.max_col - 1;
-        let max_col = (self.max_col - 1) as usize;
-        let (start_row, start_col) = (self.cursor.0 as u16, self.cursor.1 as u16);
+    // Reflow the rows from the cursor row when the row got longer than the max column by inserted text. The break
+    // points of all affected rows are computed at once and the rows are replaced with one splice, which is recorded
+    // in the history with the links in them.
+    fn reflow_after_insert(&mut self) {
+        self.reflow_row(self.cursor.0, true);
+    }
 
-        let row = self.cursor.0;
-        let current_line = &self.lines[row];
-        let mut word_start = find_word_start_backward(current_line, max_col).expect("Should find word_start");
-        info!("shift_overhang_newline::initial word_start {}", word_start);
-        
-        // If word_start is in a link move to start_col of that link 
-        // and shift link to 0th col of next line.
-        if let Some(id) = self.in_link((row, word_start)) {
-            let l = self.links.get_mut(&id)
-                .expect("Link should exist at with start_col == word_start");
-            
-            info!("shift_overhang_newline::word_start in link BEFORE: {:?}", l);
-            word_start = l.start_col;
-
-            let end_offset = l.end_col - l.start_col;
-            l.start_col = 0;
-            l.end_col = end_offset;
-            l.row += 1;
-            l.edited = true;
-            info!("shift_overhang_newline::word_start in link AFTER: {:?}", l);
-        } else if self.lines[row].chars().nth(max_col) == Some(' ') {
-            info!("shift_overhang_newline::max_col - 1 char is space");
-            word_start = max_col;
-        }
-        // If there are links after word_start, shift them to next line
-        if let Some(id_vec) = self.links_in_row_after_cursor((row, word_start)) {
-            for id in id_vec {
-                let l = self.links.get_mut(&id)
-                    .expect("Link should exist at with start_col == word_start");
-
-                info!("shift_overhang_newline::link_after_cursor BEFORE: {:?}", l);
-                let overhang = l.start_col - word_start;
-                info!("shift_overhang_newline::link overhang: {}", overhang);
-                let link_len = l.end_col - l.start_col;
-                l.start_col = overhang;
-                l.end_col = l.start_col + link_len;
-                l.row += 1;
-                l.edited = true;
-                info!("shift_overhang_newline::link_after_cursor AFTER: {:?}", l);
-            }
+    // Reflow the row when it is longer than the max column. With `paragraph`, the overhang is moved into the following
+    // rows of the paragraph as typing does. Otherwise the overhang makes new rows. The cursor must not be before the
+    // row.
+    #[allow(deprecated)]
+    fn reflow_row(&mut self, row: usize, paragraph: bool) {
+        let limit = (self.max_col as usize).saturating_sub(1);
+        // In a quote block the rows are reflowed without their quote prefix, and the prefix is put on every new row
+        let mut prefix = String::new();
+        if self.quote_aware {
+            prefix.push_str(quote_prefix(&self.lines[row]));
         }
-        
-        // String to push to next line
-        let overhang_str = self.lines[row].drain(word_start..).as_str().to_owned();
-        let word_offset = overhang_str.len();
-        info!("shift_overhang_newline::overhang_str: {:?}", overhang_str);
-        
-        if self.lines.len() <= row + 1 {
-            self.move_cursor(CursorMove::End);
-            info!("shift_overhang_newline:: inserting newline");
-            self.insert_newline();
-        } else {
-            info!("shift_overhang_newline:: NOT inserting newline");
-            self.cursor = (row + 1, 0);
+        if prefix.len() >= limit {
+            prefix.clear();
         }
-
-        self.prepend_next_line(overhang_str, self.cursor);
-
-        info!("shift_overhang_newline::insert_at_end_of_line: {}", insert_at_end_of_line);
-        // Insert NOT at end of line -> cursor jumps back to start pos
-        if !insert_at_end_of_line {
-            self.move_cursor(CursorMove::Jump(start_row, start_col));
+        let width = prefix.len();
+        let rows = if paragraph { &self.lines[row..] } else { &self.lines[row..=row] };
+        let quoted: Vec<String>;
+        let lines = if prefix.is_empty() {
+            rows
+        } else {
+            quoted = rows
+                .iter()
+                .take_while(|l| quote_prefix(l) == prefix)
+                .map(|l| l[width..].to_string())
+                .collect();
+            &quoted[..]
+        };
+        let links = &self.links;
+        let keep = |r: usize| {
+            links
+                .values()
+                .filter(|l| !l.deleted && l.row == row + r)
+                .map(|l| l.start_col.saturating_sub(width)..(l.end_col + 1).saturating_sub(width))
+                .collect()
+        };
+        let reflow = match reflow(lines, limit - width, keep) {
+            Some(reflow) => reflow,
+            None => return,
+        };
+        // Code is not hard wrapped
+        if self.line_cache.code_block_at(&self.lines, row).is_some() {
+            return;
         }
+        let rows: Vec<String> = reflow.rows.iter().map(|r| format!("{}{}", prefix, r)).collect();
+        let end = row + reflow.consumed;
+        let locate = |(r, c): (usize, usize)| {
+            let (r, c) = reflow.locate((r - row, c.saturating_sub(width)));
+            (row + r, c + width)
+        };
 
-        (word_offset, insert_at_end_of_line)
-    }
-
-    fn prepend_next_line(&mut self, s: String, insert_pos: (usize, usize)) -> bool {
-        info!("prepend_next_line");
-        if s.is_empty() {
-            return false;
+        let (cursor_row, cursor_col) = self.cursor;
+        let before = Pos::new(cursor_row, cursor_col, byte_offset(&self.lines[cursor_row], cursor_col));
+        let mut links_before = vec![];
+        let mut links_after = vec![];
+        for link in self.links.values_mut() {
+            if link.row >= end {
+                let prev_row = link.row;
+                link.row = link.row + reflow.rows.len() - reflow.consumed;
+                self.link_rows.moved(link.id, prev_row, link.row);
+                continue;
+            }
+            if link.row < row {
+                continue;
+            }
+            links_before.push(*link);
+            let (new_row, start_col) = locate((link.row, link.start_col));
+            let (end_row, end_col) = locate((link.row, link.end_col));
+            self.link_rows.moved(link.id, link.row, new_row);
+            link.row = new_row;
+            link.start_col = start_col;
+            // A link longer than the max column is cut at the end of the row
+            link.end_col = if end_row == new_row {
+                end_col
+            } else {
+                rows[new_row - row].chars().count().saturating_sub(1)
+            };
+            links_after.push(*link);
         }
+        self.cursor = locate(self.cursor);
 
-        let (row, col) = self.cursor;
-        let line = &mut self.lines[row];
-        debug_assert!(
-            !s.contains('\n'),
-            "string given to TextArea::prepend_next_line must not contain newline: {:?}",
-            line,
+        let old = self.lines.splice(row..end, rows.iter().cloned()).collect();
+        let reflow_rows = rows.len();
+        let after_offset = byte_offset(&self.lines[self.cursor.0], self.cursor.1);
+        self.push_history(
+            EditKind::SpliceLines((row, old, rows, links_before, links_after)),
+            before,
+            after_offset,
         );
-        info!("prepend_next_line::inserting '{}'...\n...into line: '{}'", s, line);
-
-        let i = line
-            .char_indices()
-            .nth(col)
-            .map(|(i, _)| i)
-            .unwrap_or(line.len());
-        line.insert_str(i, &s);
-
-        info!("prepend_next_line::self.cursor before s.chars().count(): {:?}", self.cursor);
-        self.cursor.1 += s.chars().count();
-        info!("prepend_next_line::self.cursor after s.chars().count(): {:?}", self.cursor);
-        self.shift_links_after_insert((row, col), (row, self.cursor.1), insert_pos);
-        info!("prepend_next_line::self.cursor after shift_links_after_insert: {:?}", self.cursor);
-        true
+        self.wrap_breaks.extend(row + 1..row + reflow_rows);
+        self.sync_paragraph_ids(row + 1..row + reflow_rows);
     }
 
-
     /// Insert a newline at current cursor position.
     /// ```
     /// use tuipaz_textarea::{TextArea, CursorMove};
@@ -1337,7 +2277,20 @@ impl<'a> TextArea<'a> {
         self.delete_selection(false);
 
         let (row, col) = self.cursor;
+        if self.quote_aware {
+            let prefix = quote_prefix(&self.lines[row]);
+            if !prefix.is_empty() && col >= prefix.len() {
+                let prefix = prefix.to_string();
+                self.insert_quoted_newline(prefix);
+                return;
+            }
+        }
+        self.split_line();
+    }
 
+    // Split the line at the cursor
+    fn split_line(&mut self) {
+        let (row, col) = self.cursor;
         let line = &mut self.lines[row];
         let offset = line
             .char_indices()
@@ -1354,6 +2307,29 @@ impl<'a> TextArea<'a> {
         self.push_history(EditKind::InsertNewline, Pos::new(row, col, offset), 0);
     }
 
+    // Insert a newline in a quote block. The new line gets the quote prefix. On a line which has only the prefix, the
+    // prefix is removed instead to end the quote block.
+    #[allow(deprecated)]
+    fn insert_quoted_newline(&mut self, prefix: String) {
+        let row = self.cursor.0;
+        if self.lines[row].trim_end() == prefix.trim_end() {
+            let len = self.lines[row].chars().count();
+            self.replace_line_heads(row, row, |_| (len, String::new()));
+            return;
+        }
+        let links_before = if self.history.is_grouping() {
+            None
+        } else {
+            self.history.begin_group();
+            Some(self.links.clone())
+        };
+        self.split_line();
+        self.replace_line_heads(row + 1, row + 1, |_| (0, prefix.clone()));
+        if let Some(links_before) = links_before {
+            self.history.end_group(links_before, self.links.clone());
+        }
+    }
+
     /// Delete a newline from **head** of current cursor line. This method returns if a newline was deleted or not in
     /// the textarea. When some text is selected, it is deleted instead.
     /// ```
@@ -1452,6 +2428,12 @@ impl<'a> TextArea<'a> {
         let (row, col) = self.cursor;
         info!("delete_char::self.cursor BEFORE: {:?}", (row, col));
 
+        // The head of a segment of a long line is not a line head. The last char of the previous segment is deleted
+        if col == 0 && self.soft_breaks.contains(&row) {
+            self.cursor = (row - 1, self.lines[row - 1].chars().count());
+            return self.delete_char();
+        }
+
         let delete_pos = match (row > 0, col > 0) {
             (true, false) => {
                 let row_up = row - 1;
@@ -1464,7 +2446,7 @@ impl<'a> TextArea<'a> {
 
         let mut links = vec![];
         if let Some(id) = self.in_link(delete_pos) {
-            links.push(self.delete_link(id));
+            links.push(self.mark_link_deleted(id));
         }
 
         let link_ids = match links.is_empty() {
@@ -1516,6 +2498,13 @@ impl<'a> TextArea<'a> {
 
         let before = self.cursor;
 
+        // The end of a segment of a long line is not a line end. The first char of the next segment is deleted
+        let (row, col) = before;
+        if self.soft_breaks.contains(&(row + 1)) && col == self.lines[row].chars().count() {
+            self.cursor = (row + 1, 0);
+            return self.delete_next_char();
+        }
+
         self.move_cursor_with_shift(CursorMove::Forward, false);
         if before == self.cursor {
             return false; // Cursor didn't move, meant no character at next of cursor.
@@ -1668,1828 +2657,9076 @@ impl<'a> TextArea<'a> {
     /// textarea.paste();
     /// assert_eq!(textarea.lines(), [" bbb cccaaa"]);
     /// ```
+    ///
+    /// When text is selected, the selection is replaced with the pasted text. Deleting the selection and pasting are
+    /// recorded as one edit so that one [`TextArea::undo`] restores the text, the links and the selection before
+    /// pasting.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["aaa bbb"]);
+    ///
+    /// textarea.delete_next_word();
+    /// textarea.move_cursor(CursorMove::End);
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::Back);
+    /// textarea.paste();
+    /// assert_eq!(textarea.lines(), [" bbaaa"]);
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), [" bbb"]);
+    /// ```
+    #[allow(deprecated)]
     pub fn paste(&mut self) -> bool {
-        self.delete_selection(false);
-        match self.yank.clone() {
-            YankText::Piece((s, l, pos)) => {
-                if let Some(link_ids) = l {
-                    for yanked_link in link_ids {
-                        let link = self.links.get_mut(&yanked_link.id).expect("Link to paste should be in links hashmap");
-                        
-                        match link.deleted {
-                            true => {
-                                link.deleted = false;
-                                link.edited = true;
-                                link.row = pos.0 + yanked_link.row_offset;
-                                link.start_col = pos.1 + yanked_link.start_col_offset;
-                                link.end_col = pos.1 + yanked_link.end_col_offset;
-                            },
-                            false => {
-                                let copied_link = Link::new(
-                                    self.next_link_id, 
-                                    pos.0 + yanked_link.row_offset, 
-                                    pos.1 + yanked_link.start_col_offset, 
-                                    pos.1 + yanked_link.end_col_offset,
-                                );
-                                self.links.insert(copied_link.id, copied_link);
-                                self.next_link_id += 1;
-                            },
-                        }
-                    }
-                }
-                self.insert_piece(s, pos)
-            }
-            YankText::Chunk((c, l, pos)) => {
-                if let Some(link_ids) = l {
-                    for yanked_link in link_ids {
-                        let link = self.links.get_mut(&yanked_link.id).expect("Link to paste should be in links hashmap");
-                        
-                        match link.deleted {
-                            true => {
-                                link.deleted = false;
-                                link.edited = true;
-                                link.row = pos.0 + yanked_link.row_offset;
-                                link.start_col = pos.1 + yanked_link.start_col_offset;
-                                link.end_col = pos.1 + yanked_link.end_col_offset;
-                            },
-                            false => {
-                                let copied_link = Link::new(
-                                    self.next_link_id, 
-                                    pos.0 + yanked_link.row_offset, 
-                                    pos.1 + yanked_link.start_col_offset, 
-                                    pos.1 + yanked_link.end_col_offset,
-                                );
-                                self.links.insert(copied_link.id, copied_link);
-                                self.next_link_id += 1;
-                            },
-                        }
-                    }
-                }
-                self.insert_chunk(c, pos)
+        let modified = if self.selection_range().is_none() {
+            self.cancel_selection();
+            match self.yank {
+                YankText::Lines(_) => self.paste_lines(false),
+                _ => self.paste_yank(),
             }
+        } else {
+            let links_before = self.links.clone();
+            self.history.begin_group();
+            self.delete_selection(false);
+            self.paste_yank();
+            self.history.end_group(links_before, self.links.clone());
+            true
+        };
+        if modified {
+            self.last_paste = Some((self.yank_index, self.cursor));
         }
+        modified
     }
 
-    /// Start text selection at the cursor position. If text selection is already ongoing, the start position is reset.
+    /// Paste the text yanked `index` times before the current yank. The last yanked text is at index 0. See
+    /// [`TextArea::yank_history`] for the yanked texts. The pasted text becomes the current yank. This method returns
+    /// if some text was inserted or not in the textarea.
     /// ```
-    /// use tuipaz_textarea::{TextArea, CursorMove};
+    /// use tuipaz_textarea::TextArea;
     ///
-    /// let mut textarea = TextArea::from(["aaa bbb ccc"]);
+    /// let mut textarea = TextArea::default();
     ///
-    /// textarea.start_selection();
-    /// textarea.move_cursor(CursorMove::WordForward);
-    /// textarea.copy();
-    /// assert_eq!(textarea.yank_text(), "aaa ");
+    /// textarea.set_yank_text("foo");
+    /// textarea.set_yank_text("bar");
+    /// assert!(textarea.paste_from_history(1));
+    /// assert_eq!(textarea.lines(), ["foo"]);
+    /// assert!(!textarea.paste_from_history(2));
     /// ```
-    pub fn start_selection(&mut self) {
-        self.selection_start = Some(self.cursor);
+    pub fn paste_from_history(&mut self, index: usize) -> bool {
+        let yank = match self.yank_history.get(index) {
+            Some(yank) => yank.clone(),
+            None => return false,
+        };
+        self.yank = yank;
+        self.yank_index = index;
+        self.paste()
     }
 
-    /// Stop the current text selection. This method does nothing if text selection is not ongoing.
+    /// Replace the text just pasted by [`TextArea::paste`] with the previous yanked text in the yank history, like
+    /// Emacs' `yank-pop`. After the oldest text, it cycles back to the last one. This method does nothing and returns
+    /// `false` when the last edit was not a paste or the cursor moved after it.
     /// ```
-    /// use tuipaz_textarea::{TextArea, CursorMove};
-    ///
-    /// let mut textarea = TextArea::from(["aaa bbb ccc"]);
-    ///
-    /// textarea.start_selection();
-    /// textarea.move_cursor(CursorMove::WordForward);
+    /// use tuipaz_textarea::TextArea;
     ///
-    /// // Cancel the ongoing text selection
-    /// textarea.cancel_selection();
+    /// let mut textarea = TextArea::default();
     ///
-    /// // As the result, this `copy` call does nothing
-    /// textarea.copy();
-    /// assert_eq!(textarea.yank_text(), "");
+    /// textarea.set_yank_text("foo");
+    /// textarea.set_yank_text("bar");
+    /// textarea.paste();
+    /// assert_eq!(textarea.lines(), ["bar"]);
+    /// assert!(textarea.cycle_paste());
+    /// assert_eq!(textarea.lines(), ["foo"]);
+    /// assert!(textarea.cycle_paste());
+    /// assert_eq!(textarea.lines(), ["bar"]);
     /// ```
-    pub fn cancel_selection(&mut self) {
-        self.selection_start = None;
+    pub fn cycle_paste(&mut self) -> bool {
+        let index = match self.last_paste {
+            Some((index, cursor)) if cursor == self.cursor => index,
+            _ => return false,
+        };
+        let len = self.yank_history.len();
+        if len < 2 {
+            return false;
+        }
+        self.undo();
+        self.paste_from_history((index + 1) % len)
     }
 
-    /// Select the entire text. Cursor moves to the end of the text buffer. When text selection is already ongoing,
-    /// it is canceled.
+    fn paste_yank(&mut self) -> bool {
+        let keys_before = self.link_keys.clone();
+        let modified = match self.yank.clone() {
+            YankText::Piece((s, l, pos)) => {
+                self.paste_yanked_links(l, pos, true);
+                self.insert_piece(s, pos)
+            }
+            YankText::Chunk((c, l, pos)) => {
+                self.paste_yanked_links(l, pos, true);
+                self.insert_chunk(c, pos)
+            }
+            // Lines replacing the selection are inserted with the newline at the end
+            YankText::Lines((mut c, l, _)) => {
+                let pos = self.cursor;
+                self.paste_yanked_links(l, pos, true);
+                c.push(String::new());
+                self.insert_chunk(c, pos)
+            }
+        };
+        self.record_link_keys(&keys_before);
+        modified
+    }
+
+    // Restore the yanked links deleted by cut or copy the links still in the text. Links yanked in other textareas
+    // are copied as new links. When the yanked text is inserted after this, the links are placed relative to the yank
+    // origin and marked as edited so that the insertion moves them to the cursor. Otherwise they are placed at `pos`.
+    #[allow(deprecated)]
+    fn paste_yanked_links(&mut self, links: MaybeLinks, pos: (usize, usize), before_insert: bool) {
+        for yanked_link in links.unwrap_or_default() {
+            let row = pos.0 + yanked_link.row_offset;
+            let start_col = pos.1 + yanked_link.start_col_offset;
+            let end_col = pos.1 + yanked_link.end_col_offset;
+            match self.links.get_mut(&yanked_link.id) {
+                Some(link) if link.deleted => {
+                    self.link_rows.moved(link.id, link.row, row);
+                    link.deleted = false;
+                    link.edited = before_insert;
+                    link.row = row;
+                    link.start_col = start_col;
+                    link.end_col = end_col;
+                }
+                _ => {
+                    let id = self.add_link(row, start_col, end_col);
+                    self.links.get_mut(&id).expect("link was just added").edited = before_insert;
+                    if let Some(key) = self.yank_keys.get(&yanked_link.id).cloned() {
+                        self.carry_link_key(id, key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Duplicate the current line below it and move the cursor to the same column in the copy. Links in the line are
+    /// copied as new links with fresh IDs. The map from the IDs of the original links to the IDs of their copies is
+    /// returned so that the application can copy the data associated with the links. The duplication is recorded as
+    /// one edit in the history.
     /// ```
-    /// use tuipaz_textarea::{TextArea, CursorMove};
-    ///
-    /// let mut textarea = TextArea::from(["aaa", "bbb", "ccc"]);
-    ///
-    /// textarea.select_all();
+    /// use std::collections::HashMap;
+    /// use tuipaz_textarea::{Link, TextArea, TextAreaTheme};
     ///
-    /// // Cut the entire text;
-    /// textarea.cut();
+    /// let links = HashMap::from([(0, Link { id: 0, row: 0, start_col: 4, end_col: 6, edited: false, deleted: false })]);
+    /// let mut textarea = TextArea::new(vec!["see [a]".to_string()], links, 80, TextAreaTheme::default());
     ///
-    /// assert_eq!(textarea.lines(), [""]); // Buffer is now empty
-    /// assert_eq!(textarea.yank_text(), "aaa\nbbb\nccc");
+    /// let copied = textarea.duplicate_line();
+    /// assert_eq!(textarea.lines(), ["see [a]", "see [a]"]);
+    /// assert_eq!(textarea.cursor(), (1, 0));
+    /// assert_eq!(copied[&0], 1);
     /// ```
-    pub fn select_all(&mut self) {
-        self.move_cursor(CursorMove::Jump(u16::MAX, u16::MAX));
-        self.selection_start = Some((0, 0));
-    }
-
-    /// Return if text selection is ongoing or not.
+    pub fn duplicate_line(&mut self) -> BTreeMap<usize, usize> {
+        self.cancel_selection();
+        let (row, col) = self.cursor;
+        let line = self.lines[row].clone();
+        let len = line.chars().count();
+        let src = TextRange::new((row, 0), (row, len));
+        let copied = self.duplicate_text(format!("\n{}", line), (row, len), src, (row + 1, 0));
+        self.cursor = (row + 1, col);
+        copied
+    }
+
+    /// Duplicate the selected text right after the selection and select the copy. Links entirely in the selection are
+    /// copied as new links with fresh IDs, and the map from the IDs of the original links to the IDs of their copies is
+    /// returned. When no text is selected, the current line is duplicated with [`TextArea::duplicate_line`]. The
+    /// duplication is recorded as one edit in the history.
     /// ```
-    /// use tuipaz_textarea::{TextArea};
+    /// use tuipaz_textarea::{CursorMove, TextArea, TextRange};
     ///
-    /// let mut textarea = TextArea::default();
+    /// let mut textarea = TextArea::from(["ab cd"]);
     ///
-    /// assert!(!textarea.is_selecting());
     /// textarea.start_selection();
-    /// assert!(textarea.is_selecting());
-    /// textarea.cancel_selection();
-    /// assert!(!textarea.is_selecting());
+    /// textarea.move_cursor(CursorMove::WordForward);
+    /// textarea.duplicate_selection();
+    /// assert_eq!(textarea.lines(), ["ab ab cd"]);
+    /// assert_eq!(textarea.selection(), Some(TextRange::new((0, 3), (0, 6))));
     /// ```
-    pub fn is_selecting(&self) -> bool {
-        self.selection_start.is_some()
+    pub fn duplicate_selection(&mut self) -> BTreeMap<usize, usize> {
+        let (start, end) = match self.selection_range() {
+            Some(range) => range,
+            None => return self.duplicate_line(),
+        };
+        let mut chunk: Vec<_> = self.lines[start.row..=end.row].to_vec();
+        chunk.last_mut().unwrap().truncate(end.offset);
+        chunk[0].drain(..start.offset);
+
+        self.cancel_selection();
+        let src = TextRange::new((start.row, start.col), (end.row, end.col));
+        let copied = self.duplicate_text(chunk.join("\n"), src.end.into(), src, src.end.into());
+        self.selection_start = Some(src.end.into());
+        copied
     }
 
-    fn line_offset(&self, row: usize, col: usize) -> usize {
-        let line = self
-            .lines
-            .get(row)
-            .unwrap_or(&self.lines[self.lines.len() - 1]);
-        line.char_indices()
-            .nth(col)
-            .map(|(i, _)| i)
-            .unwrap_or(line.len())
+    // Insert the text at the position and copy the links in the source range to the copy of the source text starting
+    // at `dest`. The cursor is at the end of the inserted text after this method. The insertion and the copied links
+    // are recorded as one edit.
+    #[allow(deprecated)]
+    fn duplicate_text(
+        &mut self,
+        text: String,
+        at: (usize, usize),
+        src: TextRange,
+        dest: (usize, usize),
+    ) -> BTreeMap<usize, usize> {
+        let links_before = if self.history.is_grouping() {
+            None
+        } else {
+            self.history.begin_group();
+            Some(self.links.clone())
+        };
+        let rows = text.matches('\n').count();
+        let last = text.rsplit('\n').next().unwrap_or("").chars().count();
+        let end = if rows == 0 { (at.0, at.1 + last) } else { (at.0 + rows, last) };
+
+        self.cursor = at;
+        self.insert_str(text, at);
+        // When inserting the text reflowed the line, the copied links cannot be placed
+        let copied = if self.cursor == end {
+            self.duplicate_links(src, dest)
+        } else {
+            BTreeMap::new()
+        };
+        if let Some(links_before) = links_before {
+            self.history.end_group(links_before, self.links.clone());
+        }
+        copied
     }
 
-    /// Set the style used for text selection. The default style is light blue.
+    // Create copies of the links entirely in the source range in the text copied at `dest`
+    #[allow(deprecated)]
+    fn duplicate_links(&mut self, src: TextRange, dest: (usize, usize)) -> BTreeMap<usize, usize> {
+        let originals: Vec<Link> = self
+            .links_in_range(src.start, src.end)
+            .into_iter()
+            .map(|id| self.links[&id])
+            .filter(|l| src.contains_range(&l.range()))
+            .collect();
+        let cursor = self.cursor;
+        let mut copied = BTreeMap::new();
+        for link in originals {
+            let row = dest.0 + link.row - src.start.row;
+            let shift = |col: usize| {
+                if link.row == src.start.row {
+                    col - src.start.col + dest.1
+                } else {
+                    col
+                }
+            };
+            let id = self.create_link(row, shift(link.start_col), shift(link.end_col));
+            copied.insert(link.id, id);
+        }
+        self.cursor = cursor;
+        copied
+    }
+
+    /// Sort the lines in the selection. The lines are sorted numerically when all of them are numbers, otherwise they
+    /// are sorted alphabetically. A selection ending at the head of a line does not include the line. Links move with
+    /// their lines. After sorting, the sorted lines are selected. The sort is recorded as one edit in the history. This
+    /// method returns if the order of the lines was changed.
     /// ```
-    /// use tuipaz_textarea::TextArea;
-    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::{CursorMove, TextArea};
     ///
-    /// let mut textarea = TextArea::default();
+    /// let mut textarea = TextArea::from(["10", "9", "100", "end"]);
     ///
-    /// // Change the selection color from the default to Red
-    /// textarea.set_selection_style(Style::default().bg(Color::Red));
-    /// assert_eq!(textarea.selection_style(), Style::default().bg(Color::Red));
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::Jump(2, 0));
+    /// textarea.move_cursor(CursorMove::End);
+    /// assert!(textarea.sort_selection_lines(true));
+    /// assert_eq!(textarea.lines(), ["9", "10", "100", "end"]);
+    ///
+    /// assert!(textarea.sort_selection_lines(false));
+    /// assert_eq!(textarea.lines(), ["100", "10", "9", "end"]);
     /// ```
-    pub fn set_selection_style(&mut self, style: Style) {
-        self.select_style = style;
+    #[allow(deprecated)]
+    pub fn sort_selection_lines(&mut self, ascending: bool) -> bool {
+        let (start, end) = match self.selection_range() {
+            Some(range) => range,
+            None => return false,
+        };
+        let first = start.row;
+        let last = if end.col == 0 && end.row > first { end.row - 1 } else { end.row };
+
+        let lines = &self.lines[first..=last];
+        let numbers: Option<Vec<f64>> = lines.iter().map(|l| l.trim().parse().ok()).collect();
+        let mut order: Vec<usize> = (0..lines.len()).collect();
+        // The sort is stable in both directions so that equal lines keep their order
+        order.sort_by(|&a, &b| {
+            let ord = match &numbers {
+                Some(n) => n[a].partial_cmp(&n[b]).unwrap_or(cmp::Ordering::Equal),
+                None => lines[a].cmp(&lines[b]),
+            };
+            if ascending {
+                ord
+            } else {
+                ord.reverse()
+            }
+        });
+        if order.iter().enumerate().all(|(i, &r)| i == r) {
+            return false;
+        }
+
+        let sorted: Vec<String> = order.iter().map(|&i| lines[i].clone()).collect();
+        let mut new_rows = vec![0; order.len()];
+        for (i, &r) in order.iter().enumerate() {
+            new_rows[r] = first + i;
+        }
+        let mut links_before = vec![];
+        let mut links_after = vec![];
+        for link in self.links.values_mut().filter(|l| first <= l.row && l.row <= last) {
+            links_before.push(*link);
+            let row = new_rows[link.row - first];
+            self.link_rows.moved(link.id, link.row, row);
+            link.row = row;
+            links_after.push(*link);
+        }
+
+        let (row, col) = self.cursor;
+        let before = Pos::new(row, col, byte_offset(&self.lines[row], col));
+        let old = self.lines.splice(first..=last, sorted.iter().cloned()).collect();
+        self.selection_start = Some((first, 0));
+        self.cursor = (last, self.lines[last].chars().count());
+        let after_offset = self.lines[last].len();
+        self.push_history(
+            EditKind::SpliceLines((first, old, sorted, links_before, links_after)),
+            before,
+            after_offset,
+        );
+        true
     }
 
-    /// Get the style used for text selection.
+    /// Toggle the comment prefix such as `// ` or `# ` at the head of the lines in the selection, or of the cursor line
+    /// when nothing is selected. When all the lines start with the prefix, it is removed from them. Otherwise it is
+    /// inserted at the head of every line. A selection ending at the head of a line does not include the line. Links,
+    /// the cursor and the selection are shifted by the length of the prefix. The change is recorded as one edit in the
+    /// history. This method returns if some line was changed.
     /// ```
-    /// use tuipaz_textarea::TextArea;
-    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::{CursorMove, TextArea};
     ///
-    /// let mut textarea = TextArea::default();
+    /// let mut textarea = TextArea::from(["a", "# b", "c"]);
     ///
-    /// assert_eq!(textarea.selection_style(), Style::default().bg(Color::LightBlue));
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::Jump(1, 3));
+    /// assert!(textarea.toggle_comment("# "));
+    /// assert_eq!(textarea.lines(), ["# a", "# # b", "c"]);
+    ///
+    /// assert!(textarea.toggle_comment("# "));
+    /// assert_eq!(textarea.lines(), ["a", "# b", "c"]);
     /// ```
-    pub fn selection_style(&mut self) -> Style {
-        self.select_style
-    }
-
-    fn selection_range(&self) -> Option<(Pos, Pos)> {
-        let (sr, sc) = self.selection_start?;
-        let (er, ec) = self.cursor;
-        let (so, eo) = (self.line_offset(sr, sc), self.line_offset(er, ec));
-        let s = Pos::new(sr, sc, so);
-        let e = Pos::new(er, ec, eo);
-        match (sr, so).cmp(&(er, eo)) {
-            Ordering::Less => Some((s, e)),
-            Ordering::Equal => None,
-            Ordering::Greater => Some((e, s)),
+    pub fn toggle_comment(&mut self, prefix: &str) -> bool {
+        if prefix.is_empty() {
+            return false;
+        }
+        let (first, last) = self.selected_rows();
+        let width = prefix.chars().count();
+        if self.lines[first..=last].iter().all(|l| l.starts_with(prefix)) {
+            self.replace_line_heads(first, last, |_| (width, String::new()))
+        } else {
+            self.replace_line_heads(first, last, |_| (0, prefix.to_string()))
         }
     }
 
-    fn take_selection_range(&mut self) -> Option<(Pos, Pos)> {
-        let range = self.selection_range();
-        self.cancel_selection();
-        range
+    /// Add one level of quote, `> `, at the head of the lines in the selection, or of the cursor line when nothing is
+    /// selected. Links, the cursor and the selection are shifted. The change is recorded as one edit in the history.
+    /// This method returns if some line was changed.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["> hello"]);
+    ///
+    /// assert!(textarea.increase_quote_level());
+    /// assert_eq!(textarea.lines(), ["> > hello"]);
+    /// assert!(textarea.decrease_quote_level());
+    /// assert!(textarea.decrease_quote_level());
+    /// assert_eq!(textarea.lines(), ["hello"]);
+    /// assert!(!textarea.decrease_quote_level());
+    /// ```
+    pub fn increase_quote_level(&mut self) -> bool {
+        let (first, last) = self.selected_rows();
+        self.replace_line_heads(first, last, |_| (0, "> ".to_string()))
+    }
+
+    /// Remove one level of quote, `>` and a space after it, from the head of the lines in the selection, or of the
+    /// cursor line when nothing is selected. Lines which are not quoted are kept as they are. See
+    /// [`TextArea::increase_quote_level`].
+    pub fn decrease_quote_level(&mut self) -> bool {
+        let (first, last) = self.selected_rows();
+        self.replace_line_heads(first, last, |line| {
+            let level = quote_prefix(line);
+            let removed = match level.as_bytes() {
+                [b'>', b' ', ..] => 2,
+                [b'>', ..] => 1,
+                _ => 0,
+            };
+            (removed, String::new())
+        })
     }
 
-    /// Copy the selection text to the yank buffer. When nothing is selected, this method does nothing.
-    /// To get the yanked text, use [`TextArea::yank_text`].
+    /// Indent the lines in the selection, or the cursor line when nothing is selected, by [`TextArea::indent`]. Links,
+    /// the cursor and the selection are shifted by the indent width. The change is recorded as one edit in the history.
+    /// This method returns if some line was changed. `Tab` calls this method while some text is selected.
     /// ```
-    /// use tuipaz_textarea::{TextArea, Key, Input, CursorMove};
+    /// use tuipaz_textarea::{CursorMove, TextArea};
     ///
-    /// let mut textarea = TextArea::from(["Hello World"]);
+    /// let mut textarea = TextArea::from(["a", "b"]);
     ///
-    /// // Start text selection at 'W'
-    /// textarea.move_cursor(CursorMove::WordForward);
     /// textarea.start_selection();
-    ///
-    /// // Select the word "World" and copy the selected text
+    /// textarea.move_cursor(CursorMove::Down);
     /// textarea.move_cursor(CursorMove::End);
-    /// textarea.copy();
+    /// assert!(textarea.indent_lines());
+    /// assert_eq!(textarea.lines(), ["    a", "    b"]);
     ///
-    /// assert_eq!(textarea.yank_text(), "World");
-    /// assert_eq!(textarea.lines(), ["Hello World"]); // Text does not change
+    /// assert!(textarea.dedent_lines());
+    /// assert_eq!(textarea.lines(), ["a", "b"]);
     /// ```
-    pub fn copy(&mut self) {
-        if let Some((start, end)) = self.take_selection_range() {
-            let l = self.links.iter()
-                .filter(|(_, l)| Self::link_in_range(l, &start, &end))
-                .map(|(id, l)| {
-                    let row_offset = l.row - start.row;
-                    let (start_col_offset, end_col_offset) = if row_offset == 0 {
-                        (l.start_col - start.col, l.end_col - start.col)
-                    } else {
-                        (l.start_col, l.end_col)
-                    };
-
-                    YankedLink { id: *id, row_offset, start_col_offset, end_col_offset }
-                })
-                .collect::<Vec<YankedLink>>();
-
-            let links = match l.is_empty() {
-                true => None,
-                false => Some(l),
+    pub fn indent_lines(&mut self) -> bool {
+        if self.tab_len == 0 {
+            return false;
+        }
+        let (first, last) = self.selected_rows();
+        let indent = self.indent();
+        self.replace_line_heads(first, last, |_| (0, indent.to_string()))
+    }
+
+    /// Dedent the lines in the selection, or the cursor line when nothing is selected. One tab or up to the tab length
+    /// of spaces is removed from the head of each line. See [`TextArea::indent_lines`]. `Shift+Tab` calls this method.
+    pub fn dedent_lines(&mut self) -> bool {
+        let (first, last) = self.selected_rows();
+        let tab_len = self.tab_len as usize;
+        self.replace_line_heads(first, last, |line| {
+            let removed = if line.starts_with('\t') {
+                1
+            } else {
+                line.chars().take(tab_len).take_while(|c| *c == ' ').count()
             };
-            
-            info!("textarea::copy::links: {:?}", links);
+            (removed, String::new())
+        })
+    }
 
-            if start.row == end.row {
-                let text = self.lines[start.row][start.offset..end.offset]
-                    .to_string();
+    // Rows of the lines in the selection, or the cursor row when nothing is selected. A selection ending at the head
+    // of a line does not include the line.
+    fn selected_rows(&self) -> (usize, usize) {
+        match self.selection_range() {
+            Some((start, end)) if end.col == 0 && end.row > start.row => (start.row, end.row - 1),
+            Some((start, end)) => (start.row, end.row),
+            None => (self.cursor.0, self.cursor.0),
+        }
+    }
 
-                self.yank = YankText::Piece((text, links, (start.row, start.col)));
-            } else {
-                let mut chunk = vec![self.lines[start.row][start.offset..].to_string()];
-                chunk.extend(self.lines[start.row + 1..end.row].iter().cloned());
-                chunk.push(self.lines[end.row][..end.offset].to_string());
-                self.yank = YankText::Chunk((chunk, links, (start.row, start.col)));
-            }
+    // Replace the heads of the lines in the rows. The function returns the number of chars removed from the head of
+    // the line and the text inserted there. Links, the cursor and the selection are shifted, and the change is recorded
+    // as one edit in the history. Returns `false` when no line was changed.
+    #[allow(deprecated)]
+    fn replace_line_heads(&mut self, first: usize, last: usize, f: impl Fn(&str) -> (usize, String)) -> bool {
+        let heads: Vec<(usize, String)> = self.lines[first..=last].iter().map(|l| f(l)).collect();
+        if heads.iter().all(|(removed, inserted)| *removed == 0 && inserted.is_empty()) {
+            return false;
+        }
+        let new: Vec<String> = self.lines[first..=last]
+            .iter()
+            .zip(heads.iter())
+            .map(|(line, (removed, inserted))| format!("{}{}", inserted, &line[byte_offset(line, *removed)..]))
+            .collect();
+        let shift = |row: usize, col: usize| {
+            let (removed, inserted) = &heads[row - first];
+            col.saturating_sub(*removed) + inserted.chars().count()
+        };
 
+        let mut links_before = vec![];
+        let mut links_after = vec![];
+        for link in self.links.values_mut().filter(|l| first <= l.row && l.row <= last) {
+            links_before.push(*link);
+            link.start_col = shift(link.row, link.start_col);
+            link.end_col = shift(link.row, link.end_col);
+            links_after.push(*link);
         }
-    }
 
-    pub fn link_in_range(l: &&Link, start: &Pos, end: &Pos) -> bool {
-        if start.row == end.row && l.row == start.row {
-            l.start_col >= start.col && l.end_col <= end.col 
-        } else if l.row == start.row {
-            l.start_col >= start.col 
-        } else if l.row == end.row {
-            l.end_col <= end.col
-        } else { 
-            l.row > start.row && l.row < end.row
+        let (row, col) = self.cursor;
+        let before = Pos::new(row, col, byte_offset(&self.lines[row], col));
+        let old = self.lines.splice(first..=last, new.iter().cloned()).collect();
+        if let Some((r, c)) = self.selection_start {
+            if first <= r && r <= last {
+                self.selection_start = Some((r, shift(r, c)));
+            }
+        }
+        if first <= row && row <= last {
+            self.cursor = (row, shift(row, col));
         }
+        let (row, col) = self.cursor;
+        let after_offset = byte_offset(&self.lines[row], col);
+        self.push_history(
+            EditKind::SpliceLines((first, old, new, links_before, links_after)),
+            before,
+            after_offset,
+        );
+        true
     }
 
-    /// Cut the selected text and place it in the yank buffer. This method returns whether the text was modified.
-    /// The cursor will move to the start position of the text selection.
-    /// To get the yanked text, use [`TextArea::yank_text`].
+    /// Transform the case of the selected text in place. See [`Case`] for the transformations. Chars whose converted
+    /// case is more than one char, such as `ß` in uppercase, are kept as they are so that the text keeps its columns
+    /// and links overlapping the selection are not broken. The selection is kept and the change is recorded as one
+    /// edit in the history. This method returns if some text was changed.
     /// ```
-    /// use tuipaz_textarea::{TextArea, Key, Input, CursorMove};
+    /// use tuipaz_textarea::{Case, CursorMove, TextArea};
     ///
-    /// let mut textarea = TextArea::from(["Hello World"]);
+    /// let mut textarea = TextArea::from(["hello world"]);
     ///
-    /// // Start text selection at 'W'
-    /// textarea.move_cursor(CursorMove::WordForward);
     /// textarea.start_selection();
-    ///
-    /// // Select the word "World" and copy the selected text
     /// textarea.move_cursor(CursorMove::End);
-    /// textarea.cut();
+    /// assert!(textarea.transform_selection(Case::Title));
+    /// assert_eq!(textarea.lines(), ["Hello World"]);
     ///
-    /// assert_eq!(textarea.yank_text(), "World");
-    /// assert_eq!(textarea.lines(), ["Hello "]);
+    /// assert!(textarea.transform_selection(Case::Toggle));
+    /// assert_eq!(textarea.lines(), ["hELLO wORLD"]);
     /// ```
-    pub fn cut(&mut self) -> bool {
-        self.delete_selection(true)
-    }
-
-    fn delete_selection(&mut self, should_yank: bool) -> bool {
-        info!("INSIDE delete_selection");
-        if let Some((s, e)) = self.take_selection_range() {
-            info!("selection range => s: {:?}, e: {:?}", s, e);
-            let deleted_links = self.delete_links_in_range((s.row, s.col), (e.row, e.col));
-            self.shift_links_after_delete((e.row, e.col), (s.row, s.col), 0);
-            
-            self.delete_range(s, e, deleted_links, should_yank);
-            return true;
+    #[allow(deprecated)]
+    pub fn transform_selection(&mut self, case: Case) -> bool {
+        let (start, end) = match self.selection_range() {
+            Some(range) => range,
+            None => return false,
+        };
+        let old = &self.lines[start.row..=end.row];
+        let mut new = Vec::with_capacity(old.len());
+        for (i, line) in old.iter().enumerate() {
+            let row = start.row + i;
+            let mut chars = line.chars().collect::<Vec<_>>();
+            let from = if row == start.row { start.col } else { 0 };
+            let to = if row == end.row { end.col } else { chars.len() };
+            let prev = from.checked_sub(1).map(|c| chars[c]);
+            case.apply(&mut chars[from..to], prev);
+            new.push(chars.into_iter().collect::<String>());
         }
-        false
+        if new == old {
+            return false;
+        }
+
+        // Links keep their positions since the columns do not change
+        let links = self
+            .links
+            .values()
+            .filter(|l| start.row <= l.row && l.row <= end.row)
+            .copied()
+            .collect::<Vec<_>>();
+        let (row, col) = self.cursor;
+        let before = Pos::new(row, col, byte_offset(&self.lines[row], col));
+        let old = self.lines.splice(start.row..=end.row, new.iter().cloned()).collect();
+        let after_offset = byte_offset(&self.lines[row], col);
+        self.push_history(
+            EditKind::SpliceLines((start.row, old, new, links.clone(), links)),
+            before,
+            after_offset,
+        );
+        true
     }
 
-    /// Move the cursor to the position specified by the [`CursorMove`] parameter. For each kind of cursor moves, see
-    /// the document of [`CursorMove`].
+    /// Pass the selected text, or the whole text when nothing is selected, through a formatter provided by the
+    /// application and replace it with the result. Only the text between the common prefix and suffix of the old and
+    /// new text is replaced, so links in the unchanged parts are kept and links in the changed part are deleted. The
+    /// replacement is undone as one edit. The formatted text is selected when the selection was formatted, and
+    /// otherwise the cursor stays at its position. This method returns if the text was modified or not, or the error
+    /// returned by the formatter, in which case the text is not changed.
     /// ```
-    /// use tuipaz_textarea::{TextArea, CursorMove};
+    /// use tuipaz_textarea::TextArea;
     ///
-    /// let mut textarea = TextArea::from(["abc", "def"]);
+    /// let mut textarea = TextArea::from(["fn  main( ) {", "}"]);
+    /// let squeeze = |s: &str| -> Result<String, ()> { Ok(s.replace("  ", " ").replace("( )", "()")) };
     ///
-    /// textarea.move_cursor(CursorMove::Forward);
-    /// assert_eq!(textarea.cursor(), (0, 1));
-    /// textarea.move_cursor(CursorMove::Down);
-    /// assert_eq!(textarea.cursor(), (1, 1));
+    /// assert_eq!(textarea.format_selection_with(squeeze), Ok(true));
+    /// assert_eq!(textarea.lines(), ["fn main() {", "}"]);
+    ///
+    /// assert_eq!(textarea.format_selection_with(|_| Err("syntax error")), Err("syntax error"));
+    /// assert_eq!(textarea.lines(), ["fn main() {", "}"]);
+    ///
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["fn  main( ) {", "}"]);
     /// ```
-    pub fn move_cursor(&mut self, m: CursorMove) {
-        self.move_cursor_with_shift(m, self.selection_start.is_some());
-    }
+    pub fn format_selection_with<E>(&mut self, f: impl FnOnce(&str) -> Result<String, E>) -> Result<bool, E> {
+        let selected = self.selection_range().map(|(s, e)| ((s.row, s.col), (e.row, e.col)));
+        let (start, end) = selected.unwrap_or_else(|| {
+            let last = self.lines.len() - 1;
+            ((0, 0), (last, self.lines[last].chars().count()))
+        });
+        let joined = self.lines[start.0..=end.0].join("\n");
+        let head = byte_offset(&self.lines[start.0], start.1);
+        let tail = self.lines[end.0].len() - byte_offset(&self.lines[end.0], end.1);
+        let old = &joined[head..joined.len() - tail];
+        let new = f(old)?;
+        if new == old {
+            return Ok(false);
+        }
 
-    fn move_cursor_with_shift(&mut self, m: CursorMove, shift: bool) {
-        if let Some(cursor) = m.next_cursor(self.cursor, &self.lines, &self.viewport) {
-            if shift {
-                if self.selection_start.is_none() {
-                    self.start_selection();
-                }
-            } else {
-                self.cancel_selection();
-            }
-            self.cursor = cursor;
+        let old = old.chars().collect::<Vec<_>>();
+        let new = new.chars().collect::<Vec<_>>();
+        let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        // Position after the chars inserted at the start position
+        let advance = |chars: &[char]| match chars.iter().rposition(|c| *c == '\n') {
+            Some(i) => (start.0 + chars.iter().filter(|c| **c == '\n').count(), chars.len() - i - 1),
+            None => (start.0, start.1 + chars.len()),
+        };
+        let range = (advance(&old[..prefix]), advance(&old[..old.len() - suffix]));
+        let replacement = new[prefix..new.len() - suffix].iter().collect::<String>();
+
+        let cursor = self.cursor;
+        let modified = self.replace_range(range, replacement);
+        if selected.is_some() {
+            self.selection_start = Some(start);
+            self.cursor = self.clamp_position(advance(&new).into());
+        } else {
+            self.cursor = self.clamp_position(cursor.into());
         }
+        Ok(modified)
     }
 
-    /// Undo the last modification. This method returns if the undo modified text contents or not in the textarea.
+    /// Start text selection at the cursor position. If text selection is already ongoing, the start position is reset.
     /// ```
     /// use tuipaz_textarea::{TextArea, CursorMove};
     ///
-    /// let mut textarea = TextArea::from(["abc def"]);
+    /// let mut textarea = TextArea::from(["aaa bbb ccc"]);
     ///
-    /// textarea.delete_next_word();
-    /// assert_eq!(textarea.lines(), [" def"]);
-    /// textarea.undo();
-    /// assert_eq!(textarea.lines(), ["abc def"]);
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::WordForward);
+    /// textarea.copy();
+    /// assert_eq!(textarea.yank_text(), "aaa ");
     /// ```
-    pub fn undo(&mut self) -> bool {
-        if let Some((cursor_before, cursor_after)) = self.history.undo(&mut self.lines, &mut self.links) {
-            self.cancel_selection();
-            self.shift_links_after_edit(cursor_after, cursor_before);
-            self.cursor = cursor_before;
-            true
-        } else {
-            false
-        }
+    pub fn start_selection(&mut self) {
+        self.selection_start = Some(self.cursor);
     }
 
-    /// Redo the last undo change. This method returns if the redo modified text contents or not in the textarea.
+    /// Extend the selection to the position. The anchor of the ongoing selection is kept and the cursor, which is the
+    /// active end of the selection, moves to the position. When text selection is not ongoing, it starts at the cursor.
+    /// The position is made fit within the text. This is useful for shift+click and mouse drags.
     /// ```
-    /// use tuipaz_textarea::{TextArea, CursorMove};
+    /// use tuipaz_textarea::{CursorMove, TextArea, TextRange};
     ///
-    /// let mut textarea = TextArea::from(["abc def"]);
+    /// let mut textarea = TextArea::from(["aaa bbb", "ccc ddd"]);
     ///
-    /// textarea.delete_next_word();
-    /// assert_eq!(textarea.lines(), [" def"]);
-    /// textarea.undo();
-    /// assert_eq!(textarea.lines(), ["abc def"]);
-    /// textarea.redo();
-    /// assert_eq!(textarea.lines(), [" def"]);
+    /// textarea.move_cursor(CursorMove::Jump(0, 4));
+    /// textarea.extend_selection_to((1, 3));
+    /// assert_eq!(textarea.selection(), Some(TextRange::new((0, 4), (1, 3))));
+    ///
+    /// // The anchor stays while the active end moves
+    /// textarea.extend_selection_to((0, 1));
+    /// assert_eq!(textarea.selection(), Some(TextRange::new((0, 1), (0, 4))));
+    /// assert_eq!(textarea.cursor(), (0, 1));
     /// ```
-    pub fn redo(&mut self) -> bool {
-        info!("inside textarea.rs redo");
-        if let Some((cursor_before, cursor_after)) = self.history.redo(&mut self.lines, &mut self.links) {
-            self.cancel_selection();
-            self.shift_links_after_edit(cursor_before, cursor_after);
-            self.cursor = cursor_after;
-            true
-        } else {
-            false
+    pub fn extend_selection_to(&mut self, pos: impl Into<Position>) {
+        if self.selection_start.is_none() {
+            self.start_selection();
         }
+        self.cursor = self.clamp_pos(pos.into().into());
     }
 
-    pub(crate) fn line_spans<'b>(&'b self, line: &'b str, row: usize, lnum_len: u8) -> Line<'b> {
-        let mut hl = LineHighlighter::new(
-            line,
-            self.cursor_style,
-            self.link_style,
-            self.tab_len,
-            self.mask,
-            self.select_style,
-        );
-
-        if let Some(style) = self.line_number_style {
-            hl.line_number(row, lnum_len, style);
-        }
-
-        if row == self.cursor.0 {
-            hl.cursor_line(self.cursor.1, self.cursor_line_style);
-        }
+    /// Stop the current text selection. This method does nothing if text selection is not ongoing.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["aaa bbb ccc"]);
+    ///
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::WordForward);
+    ///
+    /// // Cancel the ongoing text selection
+    /// textarea.cancel_selection();
+    ///
+    /// // As the result, this `copy` call does nothing
+    /// textarea.copy();
+    /// assert_eq!(textarea.yank_text(), "");
+    /// ```
+    pub fn cancel_selection(&mut self) {
+        self.selection_start = None;
+    }
 
-        if let Some(matches) = self.search.matches(line) {
-            hl.search(matches, self.search.style);
-        }
-        
-        let mut count = self.hop.get_count();
-        if self.hopping {
-            let mut match_vec = vec![];
+    /// Extract the selected text with the links entirely in it as a [`Fragment`]. The text is not modified. `None`
+    /// is returned when nothing is selected.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["abc", "def"]);
+    /// textarea.move_cursor(CursorMove::Forward);
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::Down);
+    ///
+    /// let fragment = textarea.extract_selection().unwrap();
+    /// assert_eq!(fragment.lines, ["bc", "d"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn extract_selection(&self) -> Option<Fragment> {
+        let (start, end) = self.selection_range()?;
+        Some(fragment::extract(&self.lines, &self.links, &self.link_keys, &start, &end))
+    }
 
-            if let Some(matches) = self.hop.matches(line) {
-                (match_vec, count) = hl.hop(matches, self.hop.style, count);
-            }
+    /// Insert the [`Fragment`] at the cursor, replacing the selection if any. New IDs are assigned to the links in the
+    /// fragment and the map from the IDs in the fragment to the new IDs is returned so that link targets can be
+    /// moved along. The stable keys of the links move to the new IDs unless links still in the text have them. The
+    /// insertion is recorded as one edit in the history.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut src = TextArea::from(["see [a]"]);
+    /// src.move_cursor(CursorMove::Jump(0, 4));
+    /// src.init_link();
+    /// src.move_cursor(CursorMove::Jump(0, 6));
+    /// src.insert_link();
+    ///
+    /// src.move_cursor(CursorMove::Jump(0, 4));
+    /// src.start_selection();
+    /// src.move_cursor(CursorMove::End);
+    /// let fragment = src.extract_selection().unwrap();
+    ///
+    /// let mut dst = TextArea::from(["> "]);
+    /// dst.move_cursor(CursorMove::End);
+    /// let ids = dst.insert_fragment(fragment);
+    /// assert_eq!(dst.lines(), ["> [a]"]);
+    /// assert_eq!(ids.len(), 1);
+    /// ```
+    #[allow(deprecated)]
+    pub fn insert_fragment(&mut self, fragment: Fragment) -> HashMap<usize, usize> {
+        let links_before = self.links.clone();
+        let keys_before = self.link_keys.clone();
+        self.history.begin_group();
+        self.delete_selection(false);
+        let (row, col) = self.cursor;
+        self.insert_str(fragment.text(), (row, col));
 
-            if self.hop_pending {
-                self.hop.set_match_indexes(match_vec, row);
+        let mut ids = HashMap::new();
+        for link in &fragment.links {
+            let (start_col, end_col) = if link.row == 0 {
+                (col + link.start_col, col + link.end_col)
+            } else {
+                (link.start_col, link.end_col)
+            };
+            let id = self.add_link(row + link.row, start_col, end_col);
+            if let Some(key) = fragment.keys.get(&link.id) {
+                self.carry_link_key(id, key.clone());
             }
-        } 
-        self.hop.set_count(count);
-
-        hl.links(&self.links, row, self.link_style);
-
-        if let Some((start, end)) = self.selection_range() {
-            hl.selection(row, start.row, start.offset, end.row, end.offset);
+            ids.insert(link.id, id);
         }
+        self.history.end_group(links_before, self.links.clone());
+        self.record_link_keys(&keys_before);
+        ids
+    }
 
-        hl.into_spans()
+    /// Append another document to the end of the text as new lines. IDs of the document's links are reassigned not
+    /// to collide with the links in this textarea and the map from the old IDs to the new IDs is returned. Deleted
+    /// links of the document are dropped. The stable keys of the document's links in `keys` move to the new IDs
+    /// unless links in this textarea have them. The cursor does not move and the appending is recorded as one edit in
+    /// the history.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut notes = TextArea::from(["# Notes"]);
+    /// let mut other = TextArea::from(["# Other", "see https://example.com"]);
+    /// let id = other.linkify_urls()[0];
+    /// other.set_link_key(id, "example").unwrap();
+    ///
+    /// let ids = notes.append_document(other.lines().to_vec(), other.links().clone(), other.link_keys());
+    /// assert_eq!(notes.lines(), ["# Notes", "# Other", "see https://example.com"]);
+    /// assert_eq!(notes.link_by_key("example"), Some(ids[&id]));
+    /// ```
+    pub fn append_document(
+        &mut self,
+        lines: Vec<String>,
+        links: impl IntoIterator<Item = (usize, Link)>,
+        keys: impl IntoIterator<Item = (usize, String)>,
+    ) -> HashMap<usize, usize> {
+        if lines.is_empty() {
+            return HashMap::new();
+        }
+        let cursor = self.cursor;
+        self.cancel_selection();
+        let row = self.lines.len() - 1;
+        self.cursor = (row, self.lines[row].chars().count());
+
+        // The first empty line of the fragment puts the document on new lines
+        let mut fragment_lines = vec![String::new()];
+        fragment_lines.extend(lines);
+        let links = links
+            .into_iter()
+            .map(|(_, l)| l)
+            .filter(|l| !l.deleted)
+            .map(|mut l| {
+                l.row += 1;
+                l
+            })
+            .collect();
+        let ids = self.insert_fragment(Fragment {
+            lines: fragment_lines,
+            links,
+            keys: keys.into_iter().collect(),
+        });
+        self.cursor = cursor;
+        ids
     }
 
-    /// insert_link inserts a link at the current cursor position in the `TextArea`.
-    /// Links are identified by unique IDs and span across one or more characters,
-    /// but can't currently span multiple lines
+    /// Paste the yanked text as complete lines above the cursor line like Vim's `P` with linewise register. A newline
+    /// at the end of the yanked text is not pasted as an empty line. Links in the yanked text are placed on the new
+    /// lines. The cursor moves to the head of the first pasted line. This method returns if some text was inserted or
+    /// not in the textarea.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
     ///
-    /// # Behavior
+    /// let mut textarea = TextArea::from(["- a", "- b", "- c"]);
+    /// textarea.set_empty_selection_clipboard(true);
     ///
-    ///  - pending_link = None` -> save cursor pos and char in self.pending_link
-    ///  - pending_link` = Some(position) -> calculates the range between the stored `pending_link` position
-    ///   and the current cursor position, creates a new `Link` object with these positions,
-    ///   adds it to the `links` vector, increments the `next_link_id`, and resets `pending_link` to `None`.
+    /// textarea.move_cursor(CursorMove::Bottom);
+    /// textarea.cut();
+    /// textarea.move_cursor(CursorMove::Top);
+    /// textarea.paste_above();
+    /// assert_eq!(textarea.lines(), ["- c", "- a", "- b"]);
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    /// ```
+    pub fn paste_above(&mut self) -> bool {
+        self.paste_lines(false)
+    }
+
+    /// Paste the yanked text as complete lines below the cursor line like Vim's `p` with linewise register. See
+    /// [`TextArea::paste_above`] for details.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
     ///
-    pub fn init_link(&mut self) {
-        self.pending_link = Some((self.cursor.0, self.cursor.1));
+    /// let mut textarea = TextArea::from(["- a", "- b"]);
+    ///
+    /// textarea.set_yank_text("- x\n");
+    /// textarea.paste_below();
+    /// assert_eq!(textarea.lines(), ["- a", "- x", "- b"]);
+    /// assert_eq!(textarea.cursor(), (1, 0));
+    /// ```
+    pub fn paste_below(&mut self) -> bool {
+        self.paste_lines(true)
     }
 
-    pub fn insert_link(&mut self) {
-        if let Some(link_start) = self.pending_link {
-            if link_start.0 == self.cursor.0 && link_start.1 <= self.cursor.1  {
-                let new_link = Link::new(
-                    self.next_link_id,
-                    link_start.0,
-                    link_start.1,
-                    self.cursor.1
-                );
-                self.links.insert(self.next_link_id, new_link);
-                self.next_link_id += 1;
-                self.pending_link = None;
-                self.new_link = true;
+    #[allow(deprecated)]
+    fn paste_lines(&mut self, below: bool) -> bool {
+        let (mut lines, yanked_links, linewise) = match self.yank.clone() {
+            YankText::Piece((s, l, _)) => (vec![s], l, false),
+            YankText::Chunk((c, l, _)) => (c, l, false),
+            YankText::Lines((c, l, _)) => (c, l, true),
+        };
+        let mut skipped_rows = 0;
+        if !linewise {
+            if lines.len() == 1 && lines[0].is_empty() {
+                return false;
+            }
+            // Newlines at the edges of the yanked lines are given by the linewise paste
+            if lines.len() > 1 && lines[lines.len() - 1].is_empty() {
+                lines.pop();
+            }
+            if lines.len() > 1 && lines[0].is_empty() {
+                lines.remove(0);
+                skipped_rows = 1;
             }
         }
-    }
 
-    pub fn delete_link(&mut self, link_id: usize) -> usize {
-        info!("inside delete_link");
-        self.deleted_link_ids.push(link_id);
-        let link = self.links.get_mut(&link_id).expect("link to delete should be present");
-        link.toggle_deleted();
-        link_id
+        self.cancel_selection();
+        let links_before = self.links.clone();
+        let keys_before = self.link_keys.clone();
+        self.history.begin_group();
+        let row = self.cursor.0;
+        let first_row = if below {
+            self.cursor = (row, self.lines[row].chars().count());
+            let mut chunk = vec![String::new()];
+            chunk.extend(lines);
+            self.insert_chunk(chunk, self.cursor);
+            row + 1
+        } else {
+            self.cursor = (row, 0);
+            lines.push(String::new());
+            self.insert_chunk(lines, self.cursor);
+            row
+        };
+
+        let yanked_links = yanked_links.map(|links| {
+            links
+                .into_iter()
+                .filter(|l| l.row_offset >= skipped_rows)
+                .map(|l| YankedLink { row_offset: l.row_offset - skipped_rows, ..l })
+                .collect()
+        });
+        self.paste_yanked_links(yanked_links, (first_row, 0), false);
+        self.history.end_group(links_before, self.links.clone());
+        self.record_link_keys(&keys_before);
+        self.cursor = (first_row, 0);
+        true
     }
 
-    /// in_link checks if the cursor's current position (`cpos`) falls within any of the defined links in the `TextArea`.
-    /// It returns the ID of the link if the cursor is inside a link; otherwise, it returns `None`.
+    /// Select the entire text. Cursor moves to the end of the text buffer. When text selection is already ongoing,
+    /// it is canceled.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
     ///
-    /// # Arguments
-    ///  - `cpos` -> A tuple containing the row and column indices of the cursor's current position.
+    /// let mut textarea = TextArea::from(["aaa", "bbb", "ccc"]);
     ///
-    /// # Returns
-    ///  - Some(id) -> ID of the link if the cursor is inside a links
-    ///  - None -> if the cursor is not inside a link.
+    /// textarea.select_all();
     ///
-    pub fn in_link(&self, cpos: (usize, usize)) -> Option<usize> {
-        for (id, link) in self.links.iter().filter(|(_, link)| !link.deleted) {
-            // No links on cursor row
-            if cpos.0 != link.row {
-                continue;
-            } else if cpos.1 >= link.start_col && cpos.1 <= link.end_col {
-                return Some(*id);
-            }
-        }
-        // No links
-        None
+    /// // Cut the entire text;
+    /// textarea.cut();
+    ///
+    /// assert_eq!(textarea.lines(), [""]); // Buffer is now empty
+    /// assert_eq!(textarea.yank_text(), "aaa\nbbb\nccc");
+    /// ```
+    pub fn select_all(&mut self) {
+        self.move_cursor(CursorMove::Jump(u16::MAX, u16::MAX));
+        self.selection_start = Some((0, 0));
     }
 
-    pub fn links_in_row_before_cursor(&self, cpos: (usize, usize)) -> Option<Vec<usize>> {
-        let mut id_vec = vec![];
-        info!("links_in_row_before_cursor::cpos: {:?}", cpos);
-        for (id, link) in self.links.iter().filter(|(_, link)| !link.deleted) {
-            info!("links_in_row_before_cursor::link: {:?}", link);
-            if cpos.0 != link.row {
-                continue;
-            } else if cpos.1 >= link.end_col {
-                id_vec.push(*id);
-            }
-        }
-        
-        info!("links_in_row_before_cursor::id_vec: {:?}", id_vec);
-        match id_vec.is_empty() {
-            true => None,
-            false => Some(id_vec),
-        }
+    /// Select the paragraph at the cursor. Paragraph is a chunk of non-empty lines. The selection starts at the head
+    /// of the first line and the cursor moves to the end of the last line. This method returns `false` and does
+    /// nothing when the cursor is on an empty line.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["aaa", "", "bbb", "ccc", "", "ddd"]);
+    ///
+    /// textarea.move_cursor(CursorMove::Jump(3, 1));
+    /// assert!(textarea.select_paragraph());
+    /// textarea.copy();
+    /// assert_eq!(textarea.yank_text(), "bbb\nccc");
+    /// ```
+    pub fn select_paragraph(&mut self) -> bool {
+        self.select_text_object(TextObject::Paragraph)
     }
 
-    pub fn links_in_row_after_cursor(&self, cpos: (usize, usize)) -> Option<Vec<usize>> {
-        let mut id_vec = vec![];
-        info!("links_in_row_after_cursor::cpos: {:?}", cpos);
-        for (id, link) in self.links.iter().filter(|(_, link)| !link.deleted) {
-            info!("links_in_row_after_cursor::link: {:?}", link);
-            if cpos.0 != link.row {
-                continue;
-            } else if cpos.1 < link.start_col {
-                id_vec.push(*id);
+    /// Get the range of the text object at the cursor as a pair of the start position and the end position. The
+    /// end position is exclusive. `None` is returned when no such text object is at the cursor.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea, TextObject};
+    ///
+    /// let mut textarea = TextArea::from(["call(foo, bar)"]);
+    /// textarea.move_cursor(CursorMove::Jump(0, 6));
+    ///
+    /// assert_eq!(textarea.text_object(TextObject::InnerWord), Some(((0, 5), (0, 8))));
+    /// assert_eq!(textarea.text_object(TextObject::InnerBrackets), Some(((0, 5), (0, 13))));
+    /// assert_eq!(textarea.text_object(TextObject::InnerQuotes), None);
+    /// ```
+    #[allow(deprecated)]
+    pub fn text_object(&self, object: TextObject) -> Option<((usize, usize), (usize, usize))> {
+        match object {
+            TextObject::InnerWord => textobject::word(&self.lines, self.cursor, false),
+            TextObject::AWord => textobject::word(&self.lines, self.cursor, true),
+            TextObject::InnerBrackets => textobject::inner_brackets(&self.lines, self.cursor),
+            TextObject::InnerQuotes => textobject::inner_quotes(&self.lines, self.cursor),
+            TextObject::InnerLink => {
+                let link = &self.links[&self.in_link(self.cursor)?];
+                Some(((link.row, link.start_col), (link.row, link.end_col + 1)))
             }
-        }
-        
-        info!("links_in_row_after_cursor::id_vec: {:?}", id_vec);
-        match id_vec.is_empty() {
-            true => None,
-            false => Some(id_vec),
+            TextObject::Paragraph => textobject::paragraph(&self.lines, self.cursor),
         }
     }
 
-    pub fn shift_links_same_row(&mut self, row: usize, (start_col, end_col): (usize, usize)) {
-        info!("shift_links_same_row");
-        let dcol = end_col as i64 - start_col as i64;
-        for l in self.links.values_mut().filter(|l| l.row == row && l.start_col >= start_col && !l.deleted) {
-            l.start_col = match (l.start_col as i64 + dcol) as usize {
-                std::usize::MAX => 0,
-                n => n
-            };
-            l.end_col = match (l.end_col as i64 + dcol) as usize {
-                std::usize::MAX => 0,
-                n => n
-            };
+    /// Select the text object at the cursor. The selection starts at the start of the text object and the cursor
+    /// moves to the end of it. This method returns `false` and does nothing when no such text object is at the
+    /// cursor or the text object is empty.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea, TextObject};
+    ///
+    /// let mut textarea = TextArea::from(["say \"hello world\""]);
+    /// textarea.move_cursor(CursorMove::Jump(0, 7));
+    ///
+    /// assert!(textarea.select_text_object(TextObject::InnerQuotes));
+    /// textarea.copy();
+    /// assert_eq!(textarea.yank_text(), "hello world");
+    /// ```
+    pub fn select_text_object(&mut self, object: TextObject) -> bool {
+        match self.text_object(object) {
+            Some((start, end)) if start != end => {
+                self.cancel_selection();
+                self.cursor = start;
+                self.start_selection();
+                self.cursor = end;
+                true
+            }
+            _ => false,
         }
     }
 
-    pub fn shift_links_after_delete(
-        &mut self,
-        (start_row, start_col): (usize, usize),
-        (end_row, end_col): (usize, usize),
-        shifted_to_prevline: usize,
-    ) {
-        let drow = end_row as i64 - start_row as i64;
-        let dcol = end_col as i64 - start_col as i64;
-
-        info!("shift_links_after_delete::{}", log_format(&(start_row, end_row), "(start_row, end_row)"));
-        info!("shift_links_after_delete::{}", log_format(&(start_col, end_col), "(start_col, end_col)"));
-        info!("shift_links_after_delete::{}", log_format(&(drow, dcol), "(drow, dcol)"));
-        for l in self.links.values_mut().filter(|l| !l.deleted) {
-            info!("link before shift{}", log_format(&l, ""));
-            let prev_start_col = l.start_col;
-            let prev_end_col = l.end_col;
-
-            if l.row >= start_row {
-                if l.row == start_row && l.start_col >= start_col {
-                    info!("shifting columns");
-                    (l.start_col, l.end_col) = match dcol >= 0 {
-                        true => {
-                            (l.start_col.saturating_add(dcol as usize),
-                            l.end_col.saturating_add(dcol as usize))
-                        },
-                        false => {
-                            let positive_dcol = dcol.unsigned_abs() as usize;
-                            (l.start_col.saturating_sub(positive_dcol),
-                            l.end_col.saturating_sub(positive_dcol))
-                        },
-                    }
-                }
-                info!("l.start_col 1: {}", l.start_col);
-                info!("l.end_col 1: {}", l.end_col);
-                let max_col = self.max_col as usize;
-
-                if l.end_col < max_col {
-                    (l.row) = match drow >= 0 {
-                        true => {
-                            l.row.saturating_add(drow as usize)
-                        },
-                        false => {
-                            let positive_drow = drow.unsigned_abs() as usize;
-                            l.row.saturating_sub(positive_drow)
-                        },
-                    }
-                } else {
-                    l.end_col = prev_end_col - shifted_to_prevline;
-                    l.start_col = prev_start_col - shifted_to_prevline;
+    /// Delete the text object at the cursor and place it in the yank buffer. This method returns whether the text
+    /// was modified. Links in the text object are deleted as [`TextArea::cut`] does.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea, TextObject};
+    ///
+    /// let mut textarea = TextArea::from(["foo bar baz"]);
+    /// textarea.move_cursor(CursorMove::Jump(0, 5));
+    ///
+    /// assert!(textarea.delete_text_object(TextObject::AWord));
+    /// assert_eq!(textarea.lines(), ["foo baz"]);
+    /// assert_eq!(textarea.yank_text(), "bar ");
+    /// ```
+    pub fn delete_text_object(&mut self, object: TextObject) -> bool {
+        self.select_text_object(object) && self.delete_selection(true)
+    }
 
-                    info!("l.start_col 2: {}", l.start_col);
-                    info!("l.end_col 2: {}", l.end_col);
-                }
-            }
-            info!("shift_links_after_delete::new pos: {:?}", l);
-        }
+    /// Return if text selection is ongoing or not.
+    /// ```
+    /// use tuipaz_textarea::{TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// assert!(!textarea.is_selecting());
+    /// textarea.start_selection();
+    /// assert!(textarea.is_selecting());
+    /// textarea.cancel_selection();
+    /// assert!(!textarea.is_selecting());
+    /// ```
+    pub fn is_selecting(&self) -> bool {
+        self.selection_start.is_some()
     }
 
-    pub fn shift_links_after_insert(
-        &mut self,
-        (start_row, start_col): (usize, usize),
-        (end_row, end_col): (usize, usize),
-        (yank_row, yank_col): (usize, usize),
-    ) {
-        let drow = end_row as i64 - start_row as i64;
-        let dcol = end_col as i64 - start_col as i64;
+    fn line_offset(&self, row: usize, col: usize) -> usize {
+        let line = self
+            .lines
+            .get(row)
+            .unwrap_or(&self.lines[self.lines.len() - 1]);
+        line.char_indices()
+            .nth(col)
+            .map(|(i, _)| i)
+            .unwrap_or(line.len())
+    }
 
-        let drow_yank = start_row as i64 - yank_row as i64;
-        let dcol_yank = start_col as i64 - yank_col as i64;
+    /// Set the style used for text selection. The default style is light blue.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    /// use ratatui::style::{Style, Color};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// // Change the selection color from the default to Red
+    /// textarea.set_selection_style(Style::default().bg(Color::Red));
+    /// assert_eq!(textarea.selection_style(), Style::default().bg(Color::Red));
+    /// ```
+    pub fn set_selection_style(&mut self, style: Style) {
+        self.select_style = style;
+    }
 
-        info!("SHIFT LINKS AFTER INSERT
-            \ncursor: {:?}
-            \nmax_col: {}
-            \n(start_row, end_row): {:?}
-            \n(start_col, end_col): {:?}
-            \n(drow, dcol): {:?}
-            \n(drow_yank, dcol_yank): {:?}
-            ", 
-            self.cursor, 
-            self.max_col, 
-            (start_row, end_row), 
-            (start_col, end_col), 
-            (drow, dcol),
-            (drow_yank, dcol_yank),
-        );
+    /// Get the style used for text selection.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    /// use ratatui::style::{Style, Color};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// assert_eq!(textarea.selection_style(), Style::default().bg(Color::LightBlue));
+    /// ```
+    pub fn selection_style(&mut self) -> Style {
+        self.select_style
+    }
 
-        for l in self.links.values_mut().filter(|l| !l.deleted) {
-            info!("shift_links_after_insert::link BEFORE: {:?}", l);
-            if l.edited {
-                info!("link edited");
-                if l.row == yank_row {
-                    (l.start_col, l.end_col) = match dcol_yank >= 0 {
-                        true => {
-                            (l.start_col.saturating_add(dcol_yank as usize),
-                            l.end_col.saturating_add(dcol_yank as usize))
-                        },
-                        false => {
-                            let positive_dcol_yank = dcol_yank.unsigned_abs() as usize;
-                            (l.start_col.saturating_sub(positive_dcol_yank),
-                            l.end_col.saturating_sub(positive_dcol_yank))
-                        },
+    fn selection_range(&self) -> Option<(Pos, Pos)> {
+        let (sr, sc) = self.selection_start?;
+        let (er, ec) = self.cursor;
+        let (so, eo) = (self.line_offset(sr, sc), self.line_offset(er, ec));
+        let s = Pos::new(sr, sc, so);
+        let e = Pos::new(er, ec, eo);
+        match (sr, so).cmp(&(er, eo)) {
+            Ordering::Less => Some((s, e)),
+            Ordering::Equal => None,
+            Ordering::Greater => Some((e, s)),
+        }
+    }
+
+    fn take_selection_range(&mut self) -> Option<(Pos, Pos)> {
+        let range = self.selection_range();
+        self.cancel_selection();
+        range
+    }
+
+    /// Copy the selection text to the yank buffer. When nothing is selected, this method does nothing.
+    /// To get the yanked text, use [`TextArea::yank_text`].
+    /// ```
+    /// use tuipaz_textarea::{TextArea, Key, Input, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["Hello World"]);
+    ///
+    /// // Start text selection at 'W'
+    /// textarea.move_cursor(CursorMove::WordForward);
+    /// textarea.start_selection();
+    ///
+    /// // Select the word "World" and copy the selected text
+    /// textarea.move_cursor(CursorMove::End);
+    /// textarea.copy();
+    ///
+    /// assert_eq!(textarea.yank_text(), "World");
+    /// assert_eq!(textarea.lines(), ["Hello World"]); // Text does not change
+    /// ```
+    #[allow(deprecated)]
+    pub fn copy(&mut self) {
+        if self.selection_start.is_none() && self.empty_selection_clipboard {
+            self.yank_line(false);
+            return;
+        }
+        if let Some((start, end)) = self.take_selection_range() {
+            let l = self.links.iter()
+                .filter(|(_, l)| Self::link_in_range(l, &start, &end))
+                .map(|(id, l)| {
+                    let row_offset = l.row - start.row;
+                    let (start_col_offset, end_col_offset) = if row_offset == 0 {
+                        (l.start_col - start.col, l.end_col - start.col)
+                    } else {
+                        (l.start_col, l.end_col)
                     };
-                } 
 
-                (l.row) = match drow_yank >= 0 {
-                    true => {
-                        l.row.saturating_add(drow_yank as usize)
-                    },
-                    false => {
-                        let positive_drow_yank = drow_yank.unsigned_abs() as usize;
-                        l.row.saturating_sub(positive_drow_yank)
-                    },
-                };
-                l.edited = false;
-            } else if l.row >= start_row {
-                // EDIT: Changed end_col to start_col
-                info!("link NOT edited");
-                if l.row == start_row && l.start_col >= start_col {
-                    (l.start_col, l.end_col) = match dcol >= 0 {
-                        true => {
-                            (l.start_col.saturating_add(dcol as usize),
-                            l.end_col.saturating_add(dcol as usize))
-                        },
-                        false => {
-                            let positive_dcol = dcol.unsigned_abs() as usize;
-                            (l.start_col.saturating_sub(positive_dcol),
-                            l.end_col.saturating_sub(positive_dcol))
-                        },
-                    }
+                    YankedLink { id: *id, row_offset, start_col_offset, end_col_offset }
+                })
+                .collect::<Vec<YankedLink>>();
+
+            let links = match l.is_empty() {
+                true => None,
+                false => Some(l),
+            };
+            
+            info!("textarea::copy::links: {:?}", links);
+
+            if start.row == end.row {
+                let text = self.lines[start.row][start.offset..end.offset]
+                    .to_string();
+
+                self.push_yank(YankText::Piece((text, links, (start.row, start.col))));
+            } else {
+                let mut chunk = vec![self.lines[start.row][start.offset..].to_string()];
+                chunk.extend(self.lines[start.row + 1..end.row].iter().cloned());
+                chunk.push(self.lines[end.row][..end.offset].to_string());
+                self.push_yank(YankText::Chunk((chunk, links, (start.row, start.col))));
+            }
+
+        }
+    }
+
+    pub fn link_in_range(l: &&Link, start: &Pos, end: &Pos) -> bool {
+        if start.row == end.row && l.row == start.row {
+            l.start_col >= start.col && l.end_col <= end.col 
+        } else if l.row == start.row {
+            l.start_col >= start.col 
+        } else if l.row == end.row {
+            l.end_col <= end.col
+        } else { 
+            l.row > start.row && l.row < end.row
+        }
+    }
+
+    /// Cut the selected text and place it in the yank buffer. This method returns whether the text was modified.
+    /// The cursor will move to the start position of the text selection.
+    /// To get the yanked text, use [`TextArea::yank_text`].
+    /// ```
+    /// use tuipaz_textarea::{TextArea, Key, Input, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["Hello World"]);
+    ///
+    /// // Start text selection at 'W'
+    /// textarea.move_cursor(CursorMove::WordForward);
+    /// textarea.start_selection();
+    ///
+    /// // Select the word "World" and copy the selected text
+    /// textarea.move_cursor(CursorMove::End);
+    /// textarea.cut();
+    ///
+    /// assert_eq!(textarea.yank_text(), "World");
+    /// assert_eq!(textarea.lines(), ["Hello "]);
+    /// ```
+    pub fn cut(&mut self) -> bool {
+        if self.selection_start.is_none() && self.empty_selection_clipboard {
+            return self.yank_line(true);
+        }
+        self.delete_selection(true)
+    }
+
+    // Yank the cursor line linewise. When cutting, the line is deleted with its newline. The last line is deleted with
+    // the previous newline. Returns if the text was modified
+    #[allow(deprecated)]
+    fn yank_line(&mut self, cut: bool) -> bool {
+        let row = self.cursor.0;
+        let links: Vec<_> = self
+            .links
+            .values()
+            .filter(|l| !l.deleted && l.row == row)
+            .map(|l| YankedLink {
+                id: l.id,
+                row_offset: 0,
+                start_col_offset: l.start_col,
+                end_col_offset: l.end_col,
+            })
+            .collect();
+        let links = (!links.is_empty()).then(|| links);
+        let yank = YankText::Lines((vec![self.lines[row].clone()], links, (row, 0)));
+        if !cut {
+            self.push_yank(yank);
+            return false;
+        }
+
+        let len = |row: usize| self.lines[row].chars().count();
+        let (start, end) = if row + 1 < self.lines.len() {
+            ((row, 0), (row + 1, 0))
+        } else if row > 0 {
+            ((row - 1, len(row - 1)), (row, len(row)))
+        } else {
+            ((row, 0), (row, len(row)))
+        };
+        self.selection_start = Some(start);
+        self.cursor = end;
+        let modified = self.delete_selection(false);
+        self.cancel_selection();
+        self.push_yank(yank);
+        // The cursor stays at the head of the line which took the place of the deleted line
+        self.cursor = (cmp::min(row, self.lines.len() - 1), 0);
+        modified
+    }
+
+    fn delete_selection(&mut self, should_yank: bool) -> bool {
+        info!("INSIDE delete_selection");
+        let selection = self.selection_start.map(|start| (start, self.cursor));
+        if let Some((s, e)) = self.take_selection_range() {
+            self.edit_selection = selection;
+            info!("selection range => s: {:?}, e: {:?}", s, e);
+            let deleted_links = self.delete_links_in_range((s.row, s.col), (e.row, e.col));
+            self.shift_links_after_delete((e.row, e.col), (s.row, s.col), 0);
+            
+            self.remove_range(s, e, deleted_links, should_yank);
+            // The selection is only for the edit of the deletion. Do not leave it to the following edits
+            self.edit_selection = None;
+            return true;
+        }
+        false
+    }
+
+    /// Move the cursor to the position specified by the [`CursorMove`] parameter. For each kind of cursor moves, see
+    /// the document of [`CursorMove`].
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["abc", "def"]);
+    ///
+    /// textarea.move_cursor(CursorMove::Forward);
+    /// assert_eq!(textarea.cursor(), (0, 1));
+    /// textarea.move_cursor(CursorMove::Down);
+    /// assert_eq!(textarea.cursor(), (1, 1));
+    /// ```
+    pub fn move_cursor(&mut self, m: CursorMove) {
+        self.move_cursor_with_shift(m, self.selection_start.is_some());
+    }
+
+    fn move_cursor_with_shift(&mut self, m: CursorMove, shift: bool) {
+        // Vertical moves start from the desired column so that moving through short lines does not lose the column.
+        // The desired column is forgotten once the cursor is moved in other ways
+        let vertical = matches!(
+            m,
+            CursorMove::Up
+                | CursorMove::Down
+                | CursorMove::LogicalLineUp
+                | CursorMove::LogicalLineDown
+                | CursorMove::InViewport
+        );
+        let from = match self.desired_col {
+            Some((col, at)) if vertical && at == self.cursor => (self.cursor.0, col),
+            _ => self.cursor,
+        };
+        if let Some(cursor) = m.next_cursor(
+            from,
+            &self.lines,
+            &self.viewport,
+            ((self.max_col as usize).saturating_sub(1), self.tab_len),
+        ) {
+            if shift {
+                if self.selection_start.is_none() {
+                    self.start_selection();
                 }
+            } else {
+                self.cancel_selection();
+            }
+            self.cursor = cursor;
+            self.desired_col = vertical.then(|| (from.1, cursor));
+        } else if matches!(
+            m,
+            CursorMove::Forward
+                | CursorMove::Back
+                | CursorMove::Up
+                | CursorMove::Down
+                | CursorMove::LogicalLineUp
+                | CursorMove::LogicalLineDown
+        ) {
+            self.bell.ring(BellReason::BufferEdge);
+        }
+    }
+
+    /// Undo the last modification. This method returns if the undo modified text contents or not in the textarea.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["abc def"]);
+    ///
+    /// textarea.delete_next_word();
+    /// assert_eq!(textarea.lines(), [" def"]);
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["abc def"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn undo(&mut self) -> bool {
+        self.finish_incremental_insert();
+        self.last_paste = None;
+        let live_before = self.live_link_ids();
+        if let Some((cursor_before, cursor_after, context, grouped)) = self.history.undo(&mut self.lines, &mut self.links) {
+            if let Some(edit) = self.history.take_applied() {
+                self.track_link_rows(&edit);
+                self.track_edit(&edit);
+                self.restore_soft_breaks(&edit);
+                self.restore_link_keys(&edit);
+            }
+            self.cancel_selection();
+            // Link-only edits don't move the cursor and don't change the text. Grouped edits restore links by themselves
+            if cursor_before != cursor_after && !grouped {
+                self.shift_links_after_edit(cursor_after, cursor_before);
+            } else {
+                self.clear_link_edit_marks();
+            }
+            self.sync_deleted_link_ids(&live_before);
+            self.cursor = cursor_before;
+            // Restore the selection replaced by the edit and the view where the edit was made
+            if let Some((start, cursor)) = context.selection {
+                self.selection_start = Some(start);
+                self.cursor = cursor;
+            }
+            let (row, col) = context.scroll_top;
+            self.viewport.set_scroll_top(row, col);
+            true
+        } else {
+            self.bell.ring(BellReason::NothingToUndo);
+            false
+        }
+    }
+
+    /// Redo the last undo change. This method returns if the redo modified text contents or not in the textarea.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, CursorMove};
+    ///
+    /// let mut textarea = TextArea::from(["abc def"]);
+    ///
+    /// textarea.delete_next_word();
+    /// assert_eq!(textarea.lines(), [" def"]);
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["abc def"]);
+    /// textarea.redo();
+    /// assert_eq!(textarea.lines(), [" def"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn redo(&mut self) -> bool {
+        self.finish_incremental_insert();
+        self.last_paste = None;
+        info!("inside textarea.rs redo");
+        let live_before = self.live_link_ids();
+        if let Some((cursor_before, cursor_after, context, grouped)) = self.history.redo(&mut self.lines, &mut self.links) {
+            if let Some(edit) = self.history.take_applied() {
+                self.track_link_rows(&edit);
+                self.track_edit(&edit);
+                self.restore_soft_breaks(&edit);
+                self.restore_link_keys(&edit);
+            }
+            self.cancel_selection();
+            if cursor_before != cursor_after && !grouped {
+                self.shift_links_after_edit(cursor_before, cursor_after);
+            } else {
+                self.clear_link_edit_marks();
+            }
+            self.sync_deleted_link_ids(&live_before);
+            self.cursor = cursor_after;
+            let (row, col) = context.scroll_top;
+            self.viewport.set_scroll_top(row, col);
+            true
+        } else {
+            self.bell.ring(BellReason::NothingToRedo);
+            false
+        }
+    }
+
+    /// Get all editor commands which can be run with [`TextArea::run_command`]. Each entry has a stable id and a
+    /// description so that a command palette can be built over the textarea.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::default();
+    /// let commands = textarea.commands();
+    ///
+    /// assert!(commands.iter().any(|c| c.id == "undo"));
+    /// ```
+    pub fn commands(&self) -> Vec<CommandInfo> {
+        Command::all().collect()
+    }
+
+    /// Run the editor command identified by `id`. `None` is returned when no command has the id. Otherwise this
+    /// method returns if the command modified text contents or not in the textarea.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["abc def"]);
+    ///
+    /// assert_eq!(textarea.run_command("delete_next_word"), Some(true));
+    /// assert_eq!(textarea.lines(), [" def"]);
+    /// assert_eq!(textarea.run_command("no_such_command"), None);
+    /// ```
+    pub fn run_command(&mut self, id: &str) -> Option<bool> {
+        Command::from_id(id).map(|c| self.execute(c))
+    }
+
+    /// Run the editor command. This method returns if the command modified text contents or not in the textarea.
+    /// ```
+    /// use tuipaz_textarea::{Command, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["abc"]);
+    ///
+    /// textarea.execute(Command::MoveEnd);
+    /// assert_eq!(textarea.cursor(), (0, 3));
+    /// ```
+    pub fn execute(&mut self, command: Command) -> bool {
+        command.run(self)
+    }
+
+    // `code` is whether the line is in a fenced code block, where markdown decorations are not applied
+    pub(crate) fn line_spans<'b>(&'b self, line: &'b str, row: usize, lnum_len: Option<u8>, code: bool) -> Line<'b> {
+        let mut hl = LineHighlighter::new(
+            line,
+            self.cursor_style,
+            self.tab_len,
+            self.mask,
+            self.select_style,
+        );
+        hl.set_overlay_rules(self.overlay_rules);
+
+        if let (Some(style), Some(lnum_len)) = (self.line_number_style, lnum_len) {
+            hl.line_number(row, lnum_len, style);
+        }
+
+        if row == self.cursor.0 {
+            hl.set_wide_cursor(self.wide_cursor);
+            hl.cursor_line(self.cursor.1, self.cursor_line_style);
+        }
+
+        for pattern in self.highlight_patterns.values() {
+            if let Some(matches) = pattern.matches(row, line) {
+                hl.search(matches.into_iter(), pattern.style);
+            }
+        }
+
+        if let Some(matches) = self.search.matches(row, line) {
+            hl.search(matches.into_iter(), self.search.style);
+        }
+        
+        if self.hop.is_active() {
+            hl.hop(self.hop.row_targets(row), self.hop.style);
+        }
+
+        self.decorate_line(&mut hl, row, code);
+
+        if !code && self.conceals(row) {
+            hl.conceal(find_concealed(line));
+        }
+
+        if let Some((start, end)) = self.selection_range() {
+            hl.selection(row, start.row, start.offset, end.row, end.offset);
+        }
+
+        hl.into_spans()
+    }
+
+    // Decorations which are a part of the content: links, URLs, tags, tokens and custom passes. Only custom passes are
+    // applied to lines in fenced code blocks
+    #[allow(deprecated)]
+    fn decorate_line(&self, hl: &mut LineHighlighter<'_>, row: usize, code: bool) {
+        if !code {
+            let links = self.link_rows.ids(&self.links, row..=row);
+            hl.links(links.iter().map(|id| &self.links[id]), self.link_style);
+            if self.url_detection {
+                hl.urls(self.link_style);
+            }
+            hl.tags(self.hashtag_style, self.mention_style);
+            hl.tokens(&self.token_providers);
+        }
+
+        for pass in self.highlight_passes.iter() {
+            (pass.run)(hl, row);
+        }
+    }
+
+    /// Get the text with ANSI escape sequences of the styles of the textarea. Links, URLs, tags, tokens and
+    /// decorations of custom highlight passes are styled as they are rendered in the textarea. The cursor, line
+    /// numbers, search matches and hop targets are omitted. The selection is included only when `selection` is
+    /// `true`. This is useful for piping the text to `less -R` or printing it outside the TUI.
+    /// ```
+    /// use ratatui::style::{Color, Style};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello", "world"]);
+    /// textarea.set_style(Style::default().fg(Color::Red));
+    ///
+    /// assert_eq!(textarea.to_ansi_string(false), "\x1b[31mhello\x1b[0m\n\x1b[31mworld\x1b[0m");
+    /// ```
+    pub fn to_ansi_string(&self, selection: bool) -> String {
+        let selection = if selection { self.selection_range() } else { None };
+        let code = self.line_cache.code_rows(&self.lines, 0..self.lines.len());
+        let mut out = String::new();
+        for (row, line) in self.lines.iter().enumerate() {
+            if row > 0 {
+                out.push('\n');
+            }
+            let mut hl = LineHighlighter::new(line, self.cursor_style, self.tab_len, self.mask, self.select_style);
+            hl.set_overlay_rules(self.overlay_rules);
+            self.decorate_line(&mut hl, row, code[row]);
+            if let Some((start, end)) = &selection {
+                hl.selection(row, start.row, start.offset, end.row, end.offset);
+            }
+            for span in hl.into_spans().spans.iter() {
+                write_ansi(&mut out, &span.content, self.style.patch(span.style));
+            }
+        }
+        out
+    }
+
+    /// Build the styled lines for the rows in `range` exactly as the widget would draw them. Line numbers, links,
+    /// search matches, selection and the cursor are all applied. This is useful for embedding the content of the
+    /// textarea in another widget (e.g. a preview pane or a popup) without rendering the whole textarea. Rows outside
+    /// the text are ignored.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["hello", "world", "!"]);
+    ///
+    /// let lines = textarea.styled_lines(1..10);
+    /// assert_eq!(lines.len(), 2);
+    /// ```
+    pub fn styled_lines(&'a self, range: Range<usize>) -> Vec<Line<'a>> {
+        self.render_lines(range, true)
+    }
+
+    // Styled lines with or without the gutter of line numbers. The gutter is hidden when the area is too narrow
+    #[allow(deprecated)]
+    pub(crate) fn render_lines(&'a self, range: Range<usize>, gutter: bool) -> Vec<Line<'a>> {
+        let lnum_len = Some(num_digits(self.lines.len())).filter(|_| gutter);
+        let end = cmp::min(range.end, self.lines.len());
+        let start = cmp::min(range.start, end);
+        let state = self.render_state(lnum_len);
+        let selected = self.selection_range().map(|(s, e)| s.row..=e.row);
+        let code = self.line_cache.code_rows(&self.lines, start..end);
+        // Lines with the cursor, the selection or hops change without edits. Custom highlight passes may depend on
+        // anything so nothing is cached while they are registered
+        let cacheable = |row: usize| {
+            row != self.cursor.0
+                && !self.hop.is_active()
+                && self.highlight_passes.is_empty()
+                && !selected.as_ref().map_or(false, |r| r.contains(&row))
+        };
+        self.lines[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let row = start + i;
+                if !cacheable(row) {
+                    return self.line_spans(line.as_str(), row, lnum_len, code[i]);
+                }
+                let links = self
+                    .link_rows
+                    .ids(&self.links, row..=row)
+                    .into_iter()
+                    .map(|id| &self.links[&id])
+                    .filter(|l| !l.deleted)
+                    .map(|l| (l.start_col, l.end_col))
+                    .collect();
+                self.line_cache
+                    .get_or_build(&state, (row, self.lines.len()), line, (links, code[i]), || {
+                        self.line_spans(line.as_str(), row, lnum_len, code[i])
+                    })
+            })
+            .collect()
+    }
+
+    // State of the textarea which changes the spans of lines other than the cursor line and the selected lines
+    fn render_state(&self, lnum_len: Option<u8>) -> RenderState {
+        RenderState {
+            line_number: self.line_number_style.zip(lnum_len),
+            cursor_style: self.cursor_style,
+            select_style: self.select_style,
+            tab_len: self.tab_len,
+            mask: self.mask,
+            overlay_rules: self.overlay_rules,
+            search: self.search.pat.as_ref().map(|p| (p.as_str().to_string(), self.search.style)),
+            highlight_patterns: self
+                .highlight_patterns
+                .iter()
+                .filter_map(|(key, s)| Some((key.clone(), s.pat.as_ref()?.as_str().to_string(), s.style)))
+                .collect(),
+            link_style: self.link_style,
+            url_detection: self.url_detection,
+            hashtag_style: self.hashtag_style,
+            mention_style: self.mention_style,
+            tokens: self
+                .token_providers
+                .iter()
+                .map(|t| (t.kind().to_string(), t.pattern().as_str().to_string(), t.style()))
+                .collect(),
+            conceal: self.conceal,
+        }
+    }
+
+    /// Get the revision of the line at the row. The revision changes when the line is edited, so applications can
+    /// use it to cache what they compute from the line. `None` is returned when the row is out of the text.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["abc", "def"]);
+    /// let first = textarea.line_revision(0).unwrap();
+    /// let second = textarea.line_revision(1).unwrap();
+    ///
+    /// textarea.insert_char('x');
+    /// assert_ne!(textarea.line_revision(0), Some(first));
+    /// assert_eq!(textarea.line_revision(1), Some(second));
+    /// ```
+    pub fn line_revision(&self, row: usize) -> Option<u64> {
+        if row >= self.lines.len() {
+            return None;
+        }
+        Some(self.line_cache.revision(row, self.lines.len()))
+    }
+
+    // Lines of the included content rendered beneath the include line at the row
+    pub(crate) fn include_lines(&self, row: usize) -> Vec<Line<'static>> {
+        let resolver = match &self.include_resolver {
+            Some(resolver) => resolver,
+            None => return vec![],
+        };
+        let id = match self.lines.get(row).and_then(|l| parse_include(l)) {
+            Some(id) => id,
+            None => return vec![],
+        };
+        let pad = Padding(self.gutter_width() as usize);
+        resolver
+            .resolve(id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|line| Line::from(Span::styled(format!("{}{}", pad, line), self.include_style)))
+            .collect()
+    }
+
+    // Number of screen rows from the top of the `start` row to the top of the `end` row including included content
+    pub(crate) fn screen_rows(&self, start: usize, end: usize) -> usize {
+        let rows = end.saturating_sub(start);
+        if self.include_resolver.is_none() {
+            return rows;
+        }
+        rows + (start..end).map(|row| self.include_lines(row).len()).sum::<usize>()
+    }
+
+    /// insert_link inserts a link at the current cursor position in the `TextArea`.
+    /// Links are identified by unique IDs and span across one or more characters,
+    /// but can't currently span multiple lines
+    ///
+    /// # Behavior
+    ///
+    ///  - pending_link = None` -> save cursor pos and char in self.pending_link
+    ///  - pending_link` = Some(position) -> calculates the range between the stored `pending_link` position
+    ///   and the current cursor position, creates a new `Link` object with these positions,
+    ///   adds it to the `links` vector, increments the `next_link_id`, and resets `pending_link` to `None`.
+    ///
+    pub fn init_link(&mut self) {
+        self.pending_link = Some((self.cursor.0, self.cursor.1));
+    }
+
+    pub fn insert_link(&mut self) {
+        if let Some(link_start) = self.pending_link {
+            if link_start.0 == self.cursor.0 && link_start.1 <= self.cursor.1  {
+                self.create_link(link_start.0, link_start.1, self.cursor.1);
+                self.pending_link = None;
+            }
+        }
+    }
+
+    /// Delete the link with the ID without changing the text. The deletion is recorded in the edit history so that it
+    /// can be undone.
+    #[allow(deprecated)]
+    pub fn delete_link(&mut self, link_id: usize) -> usize {
+        self.mark_link_deleted(link_id);
+        let link = self.links[&link_id];
+
+        let (row, col) = self.cursor;
+        let offset = self.line_offset(row, col);
+        self.push_history(EditKind::DeleteLink(link), Pos::new(row, col, offset), offset);
+        link_id
+    }
+
+    // Deleting links as a part of text edits. The link IDs are recorded in the edit itself
+    #[allow(deprecated)]
+    fn mark_link_deleted(&mut self, link_id: usize) -> usize {
+        info!("inside delete_link");
+        self.deleted_link_ids.push(link_id);
+        let link = self.links.get_mut(&link_id).expect("link to delete should be present");
+        link.toggle_deleted();
+        link_id
+    }
+
+    /// in_link checks if the cursor's current position (`cpos`) falls within any of the defined links in the `TextArea`.
+    /// It returns the ID of the link if the cursor is inside a link; otherwise, it returns `None`.
+    ///
+    /// # Arguments
+    ///  - `cpos` -> A tuple containing the row and column indices of the cursor's current position.
+    ///
+    /// # Returns
+    ///  - Some(id) -> ID of the link if the cursor is inside a links
+    ///  - None -> if the cursor is not inside a link.
+    ///
+    #[allow(deprecated)]
+    pub fn in_link(&self, cpos: impl Into<Position>) -> Option<usize> {
+        let cpos: (usize, usize) = cpos.into().into();
+        for id in self.link_rows.ids(&self.links, cpos.0..=cpos.0) {
+            let link = &self.links[&id];
+            if !link.deleted && cpos.1 >= link.start_col && cpos.1 <= link.end_col {
+                return Some(id);
+            }
+        }
+        // No links
+        None
+    }
+
+    /// Get the bare URL (`https://...`) at the position `(row, col)`. URLs are found regardless of
+    /// [`TextArea::set_url_detection`]. `None` is returned when no URL is at the position.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["see https://example.com."]);
+    ///
+    /// assert_eq!(textarea.in_url((0, 8)).as_deref(), Some("https://example.com"));
+    /// assert_eq!(textarea.in_url((0, 23)), None);
+    /// ```
+    pub fn in_url(&self, pos: impl Into<Position>) -> Option<String> {
+        let Position { row, col } = pos.into();
+        let line = self.lines.get(row)?;
+        let offset = self.line_offset(row, col);
+        find_urls(line)
+            .into_iter()
+            .find(|r| r.start <= offset && offset < r.end)
+            .map(|r| line[r].to_string())
+    }
+
+    /// Convert the bare URL at the position `(row, col)` into a [`Link`] and return the ID of the new link. The link
+    /// creation is recorded in the edit history. `None` is returned when no URL is at the position or some part of the
+    /// URL is already in a link.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["see https://example.com"]);
+    ///
+    /// let id = textarea.linkify_url((0, 8)).unwrap();
+    /// assert_eq!(textarea.in_link((0, 8)), Some(id));
+    /// assert_eq!(textarea.linkify_url((0, 8)), None);
+    /// ```
+    #[allow(deprecated)]
+    pub fn linkify_url(&mut self, pos: impl Into<Position>) -> Option<usize> {
+        let Position { row, col } = pos.into();
+        let line = self.lines.get(row)?;
+        let offset = self.line_offset(row, col);
+        let range = find_urls(line)
+            .into_iter()
+            .find(|r| r.start <= offset && offset < r.end)?;
+        let start_col = line[..range.start].chars().count();
+        let end_col = start_col + line[range].chars().count() - 1;
+        // Links must not overlap each other
+        let overlaps = self
+            .links
+            .values()
+            .any(|l| !l.deleted && l.row == row && l.start_col <= end_col && start_col <= l.end_col);
+        if overlaps {
+            return None;
+        }
+        Some(self.create_link(row, start_col, end_col))
+    }
+
+    /// Convert all bare URLs in the text into [`Link`]s. URLs already in links are skipped. The IDs of the new links
+    /// are returned in the order of their positions.
+    pub fn linkify_urls(&mut self) -> Vec<usize> {
+        let mut ids = vec![];
+        for (_, pos) in collect_urls(&self.lines) {
+            if let Some(id) = self.linkify_url((pos.row, pos.col)) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// Get a handle to add, update and remove links with validation. See [`Links`].
+    pub fn links_mut(&mut self) -> Links<'_, 'a> {
+        Links { textarea: self }
+    }
+
+    /// Get all links keyed by their IDs. Links deleted from the text are included with [`Link::deleted`] set so that
+    /// they can be restored by undo.
+    #[allow(deprecated)]
+    pub fn links(&self) -> &BTreeMap<usize, Link> {
+        &self.links
+    }
+
+    /// Get the link with the ID. Deleted links are not returned.
+    #[allow(deprecated)]
+    pub fn link(&self, id: usize) -> Option<&Link> {
+        self.links.get(&id).filter(|l| !l.deleted)
+    }
+
+    /// Get the IDs of the links in ascending order. Deleted links are not included.
+    #[allow(deprecated)]
+    pub fn link_ids(&self) -> Vec<usize> {
+        let mut ids: Vec<_> = self.links.values().filter(|l| !l.deleted).map(|l| l.id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Get the ID which will be given to the next new link.
+    #[allow(deprecated)]
+    pub fn next_link_id(&self) -> usize {
+        self.next_link_id
+    }
+
+    /// Return if a link was created since the last call, and reset the flag.
+    #[allow(deprecated)]
+    pub fn take_new_link(&mut self) -> bool {
+        std::mem::take(&mut self.new_link)
+    }
+
+    /// Take the IDs of the links deleted since the last call.
+    #[allow(deprecated)]
+    pub fn take_deleted_link_ids(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.deleted_link_ids)
+    }
+
+    /// Set how stable keys are assigned to links. In [`LinkKeyMode::Uuid`] mode every link without a key, including
+    /// the existing ones, is given a random UUID key. The default is [`LinkKeyMode::Manual`].
+    /// ```
+    /// use tuipaz_textarea::{TextArea, LinkKeyMode};
+    ///
+    /// let mut textarea = TextArea::from(["see https://example.com"]);
+    /// textarea.set_link_key_mode(LinkKeyMode::Uuid);
+    ///
+    /// let id = textarea.linkify_urls()[0];
+    /// let key = textarea.link_key(id).unwrap().to_string();
+    /// assert_eq!(textarea.link_by_key(&key), Some(id));
+    /// ```
+    #[allow(deprecated)]
+    pub fn set_link_key_mode(&mut self, mode: LinkKeyMode) {
+        self.link_key_mode = mode;
+        if mode == LinkKeyMode::Uuid {
+            let mut ids: Vec<_> = self.links.keys().copied().collect();
+            ids.sort_unstable();
+            for id in ids {
+                if !self.link_keys.contains_id(id) {
+                    self.link_keys.insert_uuid(id);
+                }
+            }
+        }
+    }
+
+    /// Get how stable keys are assigned to links. See [`TextArea::set_link_key_mode`].
+    pub fn link_key_mode(&self) -> LinkKeyMode {
+        self.link_key_mode
+    }
+
+    /// Set an application-supplied stable key to the link with the ID, replacing its previous key. Keys must be unique
+    /// in the textarea so an error is returned when the key is empty, already used by another link, or when no link
+    /// has the ID.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, LinkKeyError};
+    ///
+    /// let mut textarea = TextArea::from(["https://a.com https://b.com"]);
+    /// let ids = textarea.linkify_urls();
+    ///
+    /// assert_eq!(textarea.set_link_key(ids[0], "a"), Ok(()));
+    /// assert_eq!(
+    ///     textarea.set_link_key(ids[1], "a"),
+    ///     Err(LinkKeyError::DuplicateKey { key: "a".to_string(), id: ids[0] }),
+    /// );
+    /// ```
+    #[allow(deprecated)]
+    pub fn set_link_key(&mut self, id: usize, key: impl Into<String>) -> Result<(), LinkKeyError> {
+        if !self.links.contains_key(&id) {
+            return Err(LinkKeyError::NoSuchLink(id));
+        }
+        self.link_keys.insert(id, key.into())
+    }
+
+    /// Remove the stable key of the link with the ID. The removed key is returned.
+    pub fn remove_link_key(&mut self, id: usize) -> Option<String> {
+        self.link_keys.remove(id)
+    }
+
+    /// Get the stable key of the link with the ID.
+    pub fn link_key(&self, id: usize) -> Option<&str> {
+        self.link_keys.key(id)
+    }
+
+    /// Get the ID of the link with the stable key. `None` is returned when the link no longer exists in the textarea.
+    #[allow(deprecated)]
+    pub fn link_by_key(&self, key: &str) -> Option<usize> {
+        self.link_keys
+            .id(key)
+            .filter(|id| self.links.get(id).map_or(false, |l| !l.deleted))
+    }
+
+    /// Get the map from link IDs to their stable keys.
+    pub fn link_keys(&self) -> HashMap<usize, String> {
+        self.link_keys.iter().map(|(id, key)| (id, key.to_string())).collect()
+    }
+
+    // Add a new link with the next link ID. The link is given a key when keys are assigned automatically.
+    #[allow(deprecated)]
+    fn add_link(&mut self, row: usize, start_col: usize, end_col: usize) -> usize {
+        let id = self.next_link_id;
+        self.links.insert(id, Link::new(id, row, start_col, end_col));
+        self.link_rows.insert(&self.links[&id]);
+        self.next_link_id += 1;
+        if self.link_key_mode == LinkKeyMode::Uuid {
+            self.link_keys.insert_uuid(id);
+        }
+        id
+    }
+
+    // Give the stable key carried by a pasted or inserted link to its new ID. The key of a link deleted from the text
+    // moves since the link was moved, while the key of a link still in the text stays with it.
+    #[allow(deprecated)]
+    fn carry_link_key(&mut self, id: usize, key: String) {
+        if let Some(holder) = self.link_keys.id(&key) {
+            if self.links.get(&holder).map_or(false, |l| !l.deleted) {
+                return;
+            }
+            self.link_keys.remove(holder);
+        }
+        let _ = self.link_keys.insert(id, key);
+    }
+
+    // Record the stable keys changed since `before` in the last edit so that undo and redo move them back
+    fn record_link_keys(&mut self, before: &LinkKeys) {
+        if let Some((before, after)) = before.changes(&self.link_keys) {
+            self.history.set_link_keys(before, after);
+        }
+    }
+
+    #[allow(deprecated)]
+    fn create_link(&mut self, row: usize, start_col: usize, end_col: usize) -> usize {
+        let id = self.add_link(row, start_col, end_col);
+        self.new_link = true;
+
+        let (row, col) = self.cursor;
+        let offset = self.line_offset(row, col);
+        self.push_history(EditKind::CreateLink(self.links[&id]), Pos::new(row, col, offset), offset);
+        id
+    }
+
+    #[allow(deprecated)]
+    pub fn links_in_row_before_cursor(&self, cpos: impl Into<Position>) -> Option<Vec<usize>> {
+        let cpos: (usize, usize) = cpos.into().into();
+        info!("links_in_row_before_cursor::cpos: {:?}", cpos);
+        let id_vec: Vec<_> = self
+            .link_rows
+            .ids(&self.links, cpos.0..=cpos.0)
+            .into_iter()
+            .filter(|id| {
+                let link = &self.links[id];
+                !link.deleted && cpos.1 >= link.end_col
+            })
+            .collect();
+        
+        info!("links_in_row_before_cursor::id_vec: {:?}", id_vec);
+        match id_vec.is_empty() {
+            true => None,
+            false => Some(id_vec),
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn links_in_row_after_cursor(&self, cpos: impl Into<Position>) -> Option<Vec<usize>> {
+        let cpos: (usize, usize) = cpos.into().into();
+        info!("links_in_row_after_cursor::cpos: {:?}", cpos);
+        let id_vec: Vec<_> = self
+            .link_rows
+            .ids(&self.links, cpos.0..=cpos.0)
+            .into_iter()
+            .filter(|id| {
+                let link = &self.links[id];
+                !link.deleted && cpos.1 < link.start_col
+            })
+            .collect();
+        
+        info!("links_in_row_after_cursor::id_vec: {:?}", id_vec);
+        match id_vec.is_empty() {
+            true => None,
+            false => Some(id_vec),
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn shift_links_same_row(&mut self, row: usize, (start_col, end_col): (usize, usize)) {
+        info!("shift_links_same_row");
+        let dcol = end_col as i64 - start_col as i64;
+        self.link_rows.sync(&self.links);
+        for id in self.link_rows.ids(&self.links, row..=row) {
+            let l = self.links.get_mut(&id).expect("indexed link should be present");
+            if l.start_col < start_col || l.deleted {
+                continue;
+            }
+            l.start_col = match (l.start_col as i64 + dcol) as usize {
+                std::usize::MAX => 0,
+                n => n
+            };
+            l.end_col = match (l.end_col as i64 + dcol) as usize {
+                std::usize::MAX => 0,
+                n => n
+            };
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn shift_links_after_delete(
+        &mut self,
+        (start_row, start_col): (usize, usize),
+        (end_row, end_col): (usize, usize),
+        shifted_to_prevline: usize,
+    ) {
+        let drow = end_row as i64 - start_row as i64;
+        let dcol = end_col as i64 - start_col as i64;
+
+        info!("shift_links_after_delete::{}", log_format(&(start_row, end_row), "(start_row, end_row)"));
+        info!("shift_links_after_delete::{}", log_format(&(start_col, end_col), "(start_col, end_col)"));
+        info!("shift_links_after_delete::{}", log_format(&(drow, dcol), "(drow, dcol)"));
+        // Links before the start row are never moved
+        self.link_rows.sync(&self.links);
+        for id in self.link_rows.ids(&self.links, start_row..) {
+            let l = self.links.get_mut(&id).expect("indexed link should be present");
+            if l.deleted {
+                continue;
+            }
+            let prev_row = l.row;
+            let prev_start_col = l.start_col;
+            let prev_end_col = l.end_col;
+
+            if l.row == start_row && l.start_col >= start_col {
+                (l.start_col, l.end_col) = match dcol >= 0 {
+                    true => {
+                        (l.start_col.saturating_add(dcol as usize),
+                        l.end_col.saturating_add(dcol as usize))
+                    },
+                    false => {
+                        let positive_dcol = dcol.unsigned_abs() as usize;
+                        (l.start_col.saturating_sub(positive_dcol),
+                        l.end_col.saturating_sub(positive_dcol))
+                    },
+                }
+            }
+            let max_col = self.max_col as usize;
+
+            if l.end_col < max_col {
+                (l.row) = match drow >= 0 {
+                    true => {
+                        l.row.saturating_add(drow as usize)
+                    },
+                    false => {
+                        let positive_drow = drow.unsigned_abs() as usize;
+                        l.row.saturating_sub(positive_drow)
+                    },
+                }
+            } else {
+                l.end_col = prev_end_col - shifted_to_prevline;
+                l.start_col = prev_start_col - shifted_to_prevline;
+            }
+            self.link_rows.moved(id, prev_row, l.row);
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn shift_links_after_insert(
+        &mut self,
+        (start_row, start_col): (usize, usize),
+        (end_row, end_col): (usize, usize),
+        (yank_row, yank_col): (usize, usize),
+    ) {
+        let drow = end_row as i64 - start_row as i64;
+        let dcol = end_col as i64 - start_col as i64;
+
+        let drow_yank = start_row as i64 - yank_row as i64;
+        let dcol_yank = start_col as i64 - yank_col as i64;
+
+        info!("SHIFT LINKS AFTER INSERT
+            \ncursor: {:?}
+            \nmax_col: {}
+            \n(start_row, end_row): {:?}
+            \n(start_col, end_col): {:?}
+            \n(drow, dcol): {:?}
+            \n(drow_yank, dcol_yank): {:?}
+            ", 
+            self.cursor, 
+            self.max_col, 
+            (start_row, end_row), 
+            (start_col, end_col), 
+            (drow, dcol),
+            (drow_yank, dcol_yank),
+        );
+
+        // Links pasted by the insertion wait at the rows from the yank origin and are marked as edited. Other links to
+        // shift are at the edited row and the rows after it
+        self.link_rows.sync(&self.links);
+        for id in self.link_rows.ids(&self.links, cmp::min(start_row, yank_row)..) {
+            let l = self.links.get_mut(&id).expect("indexed link should be present");
+            if l.deleted {
+                continue;
+            }
+            let prev_row = l.row;
+            if l.edited {
+                if l.row == yank_row {
+                    (l.start_col, l.end_col) = match dcol_yank >= 0 {
+                        true => {
+                            (l.start_col.saturating_add(dcol_yank as usize),
+                            l.end_col.saturating_add(dcol_yank as usize))
+                        },
+                        false => {
+                            let positive_dcol_yank = dcol_yank.unsigned_abs() as usize;
+                            (l.start_col.saturating_sub(positive_dcol_yank),
+                            l.end_col.saturating_sub(positive_dcol_yank))
+                        },
+                    };
+                } 
+
+                (l.row) = match drow_yank >= 0 {
+                    true => {
+                        l.row.saturating_add(drow_yank as usize)
+                    },
+                    false => {
+                        let positive_drow_yank = drow_yank.unsigned_abs() as usize;
+                        l.row.saturating_sub(positive_drow_yank)
+                    },
+                };
+                l.edited = false;
+            } else if l.row > start_row || (l.row == start_row && l.start_col >= start_col) {
+                // Links before the insertion point on the same row stay
+                if l.row == start_row && l.start_col >= start_col {
+                    (l.start_col, l.end_col) = match dcol >= 0 {
+                        true => {
+                            (l.start_col.saturating_add(dcol as usize),
+                            l.end_col.saturating_add(dcol as usize))
+                        },
+                        false => {
+                            let positive_dcol = dcol.unsigned_abs() as usize;
+                            (l.start_col.saturating_sub(positive_dcol),
+                            l.end_col.saturating_sub(positive_dcol))
+                        },
+                    }
+                }
+
+                (l.row) = match drow >= 0 {
+                    true => {
+                        l.row.saturating_add(drow as usize)
+                    },
+                    false => {
+                        let positive_drow = drow.unsigned_abs() as usize;
+                        l.row.saturating_sub(positive_drow)
+                    },
+                }
+            }
+            self.link_rows.moved(id, prev_row, l.row);
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn shift_links_after_edit(
+        &mut self,
+        (start_row, start_col): (usize, usize),
+        (end_row, end_col): (usize, usize)
+    ) {
+        info!("SHIFT LINKS AFTER EDIT");
+        let drow = end_row as i64 - start_row as i64;
+        let dcol = end_col as i64 - start_col as i64;
+        // Dont shift links that were in the edit or are currently deleted. Links restored by the edit are marked as
+        // edited and are in the edited rows
+        self.link_rows.sync(&self.links);
+        for id in self.link_rows.ids(&self.links, cmp::min(start_row, end_row)..) {
+            let l = self.links.get_mut(&id).expect("indexed link should be present");
+            if l.deleted {
+                continue;
+            }
+            let prev_row = l.row;
+            if l.row >= start_row && !l.edited {
+                if (l.row == end_row && l.start_col >= start_col)
+                    || (l.row == start_row && end_row != start_row)
+                {
+                    (l.start_col, l.end_col) = match dcol >= 0 {
+                        true => {
+                            (l.start_col.saturating_add(dcol as usize),
+                            l.end_col.saturating_add(dcol as usize))
+                        },
+                        false => {
+                            let positive_dcol = dcol.unsigned_abs() as usize;
+                            (l.start_col.saturating_sub(positive_dcol),
+                            l.end_col.saturating_sub(positive_dcol))
+                        },
+                    }
+                }
+
+                (l.row) = match drow >= 0 {
+                    true => {
+                        l.row.saturating_add(drow as usize)
+                    },
+                    false => {
+                        let positive_drow = drow.unsigned_abs() as usize;
+                        l.row.saturating_sub(positive_drow)
+                    },
+                };
+            } else {
+                // The mark of the links restored by the edit is done
+                l.edited = false;
+            }
+            self.link_rows.moved(id, prev_row, l.row);
+        }
+    }
+
+
+    #[allow(deprecated)]
+    pub fn shift_links_prevline(
+        &mut self,
+        (row, col): (usize, usize),
+        dcol: usize
+    ) {
+        info!("SHIFT LINKS PREVLINE");
+        info!("{}", log_format(&dcol, "dcol"));
+        info!("{}", log_format(&(row, col), "(row, col)"));
+        self.link_rows.sync(&self.links);
+        for id in self.link_rows.ids(&self.links, row..) {
+            let l = self.links.get_mut(&id).expect("indexed link should be present");
+            let prev_row = l.row;
+            l.row = l.row.saturating_sub(1);
+            l.start_col = l.start_col.saturating_add(dcol);
+            l.end_col = l.end_col.saturating_add(dcol);
+            self.link_rows.moved(id, prev_row, l.row);
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn shift_links_newline(
+        &mut self,
+        (row, col): (usize, usize),
+    ) {
+        self.link_rows.sync(&self.links);
+        for id in self.link_rows.ids(&self.links, row..) {
+            let l = self.links.get_mut(&id).expect("indexed link should be present");
+            let prev_row = l.row;
+            if l.edited {
+                info!("shift_links_newline::ignoring edited link");
+                break;
+            } else if l.row > row {
+                l.row = l.row.saturating_add(1);
+            } else if l.start_col >= col {
+                l.row = l.row.saturating_add(1);
+                l.start_col = l.start_col.saturating_sub(col);
+                l.end_col = l.end_col.saturating_sub(col);
+            }
+            self.link_rows.moved(id, prev_row, l.row);
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn delete_links_in_range(&mut self, start: (usize, usize), end: (usize, usize)) -> MaybeLinks {
+        let mut deleted_links = Vec::new();
+        for id in self.link_rows.ids(&self.links, start.0..=end.0) {
+            let link = &self.links[&id];
+            if (link.row == start.0 && link.end_col < start.1)
+                || (link.row == end.0 && link.start_col > end.1)
+            {
+                continue;
+            } else {
+                let row_offset = link.row - start.0;
+                let (start_col_offset, end_col_offset) = if row_offset == 0 {
+                    (link.start_col.saturating_sub(start.1), link.end_col - start.1)
+                } else {
+                    (link.start_col, link.end_col)
+                };
+                
+                
+                let deleted_link = YankedLink {
+                    id,
+                    row_offset,
+                    start_col_offset,
+                    end_col_offset,
+                };
+
+                deleted_links.push(deleted_link);
+            }
+        }
+
+        if deleted_links.is_empty() {
+            None
+        } else {
+            for dl in deleted_links.iter() {
+                self.mark_link_deleted(dl.id);
+            }
+            Some(deleted_links)
+        }
+    }
+
+    ///cBuild a ratatui (or tui-rs) widget to render the current state of the textarea. The widget instance returned
+    /// from this method can be rendered with [`ratatui::terminal::Frame::render_widget`].
+    /// ```no_run
+    /// use ratatui::backend::CrosstermBackend;
+    /// use ratatui::layout::{Constraint, Direction, Layout};
+    /// use ratatui::Terminal;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// let layout = Layout::default()
+    ///     .direction(Direction::Vertical)
+    ///     .constraints([Constraint::Min(1)].as_ref());
+    /// let backend = CrosstermBackend::new(std::io::stdout());
+    /// let mut term = Terminal::new(backend).unwrap();
+    ///
+    /// loop {
+    ///     term.draw(|f| {
+    ///         let chunks = layout.split(f.size());
+    ///         let widget = textarea.widget();
+    ///         f.render_widget(widget, chunks[0]);
+    ///     }).unwrap();
+    ///
+    ///     // ...
+    /// }
+    /// ```
+    pub fn widget(&'a self) -> impl Widget + 'a {
+        Renderer::new(self)
+    }
+
+    /// Set the style of textarea. By default, textarea is not styled.
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// let style = Style::default().fg(Color::Red);
+    /// textarea.set_style(style);
+    /// assert_eq!(textarea.style(), style);
+    /// ```
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Get the current style of textarea.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Apply the colors of the theme to the text, selection and link styles. Other attributes of the styles are kept.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::{TextArea, TextAreaTheme};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.set_theme(TextAreaTheme { text: Color::White, select: Color::Gray, links: Color::Cyan });
+    /// assert_eq!(textarea.style().fg, Some(Color::White));
+    /// assert_eq!(textarea.selection_style().bg, Some(Color::Gray));
+    /// ```
+    pub fn set_theme(&mut self, theme: TextAreaTheme) {
+        self.style = self.style.fg(theme.text);
+        self.select_style = self.select_style.bg(theme.select);
+        self.link_style = self.link_style.fg(theme.links);
+    }
+
+    /// Switch the styles to the accessibility preset. The styles of the text, selection, search matches, links, cursor
+    /// and cursor line are replaced with the preset, and the previous styles are restored when
+    /// [`Accessibility::Standard`] is set again.
+    /// ```
+    /// use ratatui::style::{Modifier, Style};
+    /// use tuipaz_textarea::{Accessibility, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// let style = textarea.selection_style();
+    ///
+    /// textarea.set_accessibility(Accessibility::Monochrome);
+    /// assert_eq!(textarea.selection_style(), Style::default().add_modifier(Modifier::REVERSED));
+    ///
+    /// textarea.set_accessibility(Accessibility::Standard);
+    /// assert_eq!(textarea.selection_style(), style);
+    /// ```
+    pub fn set_accessibility(&mut self, mode: Accessibility) {
+        if let Some(standard) = self.standard_styles.take() {
+            self.apply_preset_styles(standard);
+        }
+        if let Some(preset) = PresetStyles::preset(mode) {
+            self.standard_styles = Some(PresetStyles {
+                text: self.style,
+                select: self.select_style,
+                search: self.search.style,
+                link: self.link_style,
+                cursor: self.cursor_style,
+                cursor_line: self.cursor_line_style,
+            });
+            self.apply_preset_styles(preset);
+        }
+        self.accessibility = mode;
+    }
+
+    fn apply_preset_styles(&mut self, styles: PresetStyles) {
+        self.style = styles.text;
+        self.select_style = styles.select;
+        self.search.style = styles.search;
+        self.link_style = styles.link;
+        self.cursor_style = styles.cursor;
+        self.cursor_line_style = styles.cursor_line;
+    }
+
+    /// Get the accessibility preset set by [`TextArea::set_accessibility`].
+    pub fn accessibility(&self) -> Accessibility {
+        self.accessibility
+    }
+
+    /// Set the block of textarea. By default, no block is set.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    /// use ratatui::widgets::{Block, Borders};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// let block = Block::default().borders(Borders::ALL).title("Block Title");
+    /// textarea.set_block(block);
+    /// assert!(textarea.block().is_some());
+    /// ```
+    pub fn set_block(&mut self, block: Block<'a>) {
+        self.block = Some(block);
+    }
+
+    /// Remove the block of textarea which was set by [`TextArea::set_block`].
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    /// use ratatui::widgets::{Block, Borders};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// let block = Block::default().borders(Borders::ALL).title("Block Title");
+    /// textarea.set_block(block);
+    /// textarea.remove_block();
+    /// assert!(textarea.block().is_none());
+    /// ```
+    pub fn remove_block(&mut self) {
+        self.block = None;
+    }
+
+    /// Get the block of textarea if exists.
+    pub fn block<'s>(&'s self) -> Option<&'s Block<'a>> {
+        self.block.as_ref()
+    }
+
+    /// Set the template of the title added to the block on each rendering. Placeholders in the template are replaced
+    /// with the state of the editor so that the block does not need to be rebuilt every frame. An empty template
+    /// removes the title. The title is not rendered when no block is set.
+    ///
+    /// - `{file}`: the file name set by [`TextArea::set_file_name`], or `[No Name]`
+    /// - `{modified}`: `[+]` when the text is modified (see [`TextArea::is_modified`]), otherwise nothing
+    /// - `{row}`, `{col}`: the 1-based cursor position
+    /// - `{lines}`: the number of lines
+    /// - `{mode}`: the [`Mode`] of modal editing such as `NORMAL`
+    /// ```
+    /// use ratatui::widgets::{Block, Borders};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello"]);
+    /// textarea.set_block(Block::default().borders(Borders::ALL));
+    /// textarea.set_file_name("note.md");
+    /// textarea.set_block_title(" {file}{modified} ");
+    /// textarea.set_block_footer(" {row}:{col} ");
+    ///
+    /// textarea.insert_char('!');
+    /// assert_eq!(textarea.status_text(" {file}{modified} "), " note.md[+] ");
+    /// ```
+    pub fn set_block_title(&mut self, template: impl Into<String>) {
+        self.block_title = Some(template.into()).filter(|t| !t.is_empty());
+    }
+
+    /// Set the template of the footer rendered on the bottom border of the block. The placeholders are the same as
+    /// [`TextArea::set_block_title`]. An empty template removes the footer. The footer is not rendered when the block
+    /// has no bottom border.
+    pub fn set_block_footer(&mut self, template: impl Into<String>) {
+        self.block_footer = Some(template.into()).filter(|t| !t.is_empty());
+    }
+
+    /// Expand the placeholders of [`TextArea::set_block_title`] in the template with the current state of the
+    /// editor. This is useful to render a status line outside the block.
+    pub fn status_text(&self, template: &str) -> String {
+        let status = Status {
+            file_name: self.file_name.as_deref(),
+            modified: self.modified,
+            cursor: self.cursor,
+            lines: self.lines.len(),
+            mode: self.mode(),
+        };
+        status.format(template)
+    }
+
+    /// Describe the state of the editor in one sentence for screen readers or an announcement line. It consists of the
+    /// cursor position, the selection, the mode of modal editing when it is enabled, and the text of the cursor line.
+    /// The phrasing is stable so that hosts can pass it to assistive technologies as is.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["hello", "world"]);
+    /// assert_eq!(textarea.describe_state(), "Line 1 of 2, column 1. No selection. Text: hello");
+    ///
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::Down);
+    /// assert_eq!(
+    ///     textarea.describe_state(),
+    ///     "Line 2 of 2, column 1. Selected 6 characters on 2 lines. Text: world",
+    /// );
+    /// ```
+    pub fn describe_state(&self) -> String {
+        let (row, col) = self.cursor;
+        let mut desc = format!("Line {} of {}, column {}. ", row + 1, self.lines.len(), col + 1);
+        match self.selection_range() {
+            Some((start, end)) => {
+                // Newlines between the lines are counted as characters
+                let chars = if start.row == end.row {
+                    end.col - start.col
+                } else {
+                    let middle: usize = self.lines[start.row + 1..end.row]
+                        .iter()
+                        .map(|l| l.chars().count() + 1)
+                        .sum();
+                    self.lines[start.row].chars().count() - start.col + 1 + middle + end.col
+                };
+                let plural = |n: usize| if n == 1 { "" } else { "s" };
+                desc.push_str(&format!("Selected {} character{}", chars, plural(chars)));
+                if start.row != end.row {
+                    desc.push_str(&format!(" on {} lines", end.row - start.row + 1));
+                }
+                desc.push_str(". ");
+            }
+            None => desc.push_str("No selection. "),
+        }
+        if self.modal_editing() {
+            let mode = match self.mode() {
+                Mode::Normal => "Normal",
+                Mode::Insert => "Insert",
+                Mode::Visual => "Visual",
+            };
+            desc.push_str(&format!("{} mode. ", mode));
+        }
+        let line = &self.lines[row];
+        if line.trim().is_empty() {
+            desc.push_str("Blank line");
+        } else {
+            desc.push_str("Text: ");
+            desc.push_str(line);
+        }
+        desc
+    }
+
+    // Title and footer of the block expanded for the rendering
+    pub(crate) fn block_status(&self) -> (Option<String>, Option<String>) {
+        let title = self.block_title.as_ref().map(|t| self.status_text(t));
+        let footer = self.block_footer.as_ref().map(|t| self.status_text(t));
+        (title, footer)
+    }
+
+    /// Set the file name shown by the `{file}` placeholder of [`TextArea::set_block_title`].
+    pub fn set_file_name(&mut self, name: impl Into<String>) {
+        self.file_name = Some(name.into());
+    }
+
+    /// Get the file name set by [`TextArea::set_file_name`].
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Check if the text was edited since the textarea was created or [`TextArea::set_modified`] was called with
+    /// `false`. Undo and redo also mark the text modified.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello"]);
+    /// assert!(!textarea.is_modified());
+    /// textarea.insert_char('!');
+    /// assert!(textarea.is_modified());
+    ///
+    /// // After saving the text
+    /// textarea.set_modified(false);
+    /// assert!(!textarea.is_modified());
+    /// ```
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Set if the text is modified. Call this with `false` after the text was saved. See [`TextArea::is_modified`].
+    pub fn set_modified(&mut self, modified: bool) {
+        self.modified = modified;
+    }
+
+    /// Set the length of tab character. Setting 0 disables tab inputs.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, Input, InputKind, Key};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// let tab_input = Input { key: Key::Tab, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+    ///
+    /// textarea.set_tab_length(8);
+    /// textarea.input(tab_input.clone());
+    /// assert_eq!(textarea.lines(), ["        "]);
+    ///
+    /// textarea.set_tab_length(2);
+    /// textarea.input(tab_input);
+    /// assert_eq!(textarea.lines(), ["          "]);
+    /// ```
+    pub fn set_tab_length(&mut self, len: u8) {
+        self.tab_len = len;
+    }
+
+    /// Get how many spaces are used for representing tab character. The default value is 4.
+    pub fn tab_length(&self) -> u8 {
+        self.tab_len
+    }
+
+    /// Set how the Enter key is handled by [`TextArea::input_result`]. By default, Enter inserts a newline. With
+    /// [`EnterBehavior::Submit`], Enter is reported as [`InputResult::Submitted`] and Shift+Enter inserts a newline.
+    /// ```
+    /// use tuipaz_textarea::{EnterBehavior, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_enter_behavior(EnterBehavior::Submit);
+    /// assert_eq!(textarea.enter_behavior(), EnterBehavior::Submit);
+    /// ```
+    pub fn set_enter_behavior(&mut self, behavior: EnterBehavior) {
+        self.enter_behavior = behavior;
+    }
+
+    /// Get how the Enter key is handled.
+    pub fn enter_behavior(&self) -> EnterBehavior {
+        self.enter_behavior
+    }
+
+    /// Enable or disable modal editing. When it is enabled, [`TextArea::input`] handles keys depending on the
+    /// [`Mode`] like Vim. Editing starts in [`Mode::Normal`], where keys like `w`, `b`, `dd`, `ciw` or `p` move the
+    /// cursor and edit the text, and `i`, `a` or `o` switch to [`Mode::Insert`]. In insert mode, keys are handled
+    /// with the default key mappings until Esc switches back to normal mode. `v` starts [`Mode::Visual`], where the
+    /// cursor moves extending the selection. Modal editing is disabled by default.
+    /// ```
+    /// use tuipaz_textarea::{Input, Key, Mode, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["foo bar baz"]);
+    /// textarea.set_modal_editing(true);
+    /// let mut keys = |keys: &str| {
+    ///     for c in keys.chars() {
+    ///         textarea.input(Input { key: Key::Char(c), ..Input::default() });
+    ///     }
+    /// };
+    ///
+    /// keys("wciwqux");
+    /// assert_eq!(textarea.mode(), Mode::Insert);
+    /// textarea.input(Input { key: Key::Esc, ..Input::default() });
+    /// assert_eq!(textarea.mode(), Mode::Normal);
+    /// assert_eq!(textarea.lines(), ["foo qux baz"]);
+    /// ```
+    pub fn set_modal_editing(&mut self, enabled: bool) {
+        if enabled == self.modal.is_some() {
+            return;
+        }
+        self.modal = if enabled { Some(Modal::default()) } else { None };
+        self.cancel_selection();
+    }
+
+    /// Check if modal editing is enabled. See [`TextArea::set_modal_editing`].
+    pub fn modal_editing(&self) -> bool {
+        self.modal.is_some()
+    }
+
+    /// Get the current mode of modal editing. This is useful to render a mode indicator. [`Mode::Insert`] is returned
+    /// when modal editing is disabled since keys are handled as in insert mode.
+    pub fn mode(&self) -> Mode {
+        self.modal.as_ref().map_or(Mode::Insert, |m| m.mode())
+    }
+
+    /// Switch the mode of modal editing. Entering [`Mode::Visual`] starts text selection at the cursor and leaving it
+    /// cancels the selection. This method does nothing when modal editing is disabled.
+    pub fn set_mode(&mut self, mode: Mode) {
+        let prev = match &mut self.modal {
+            Some(modal) => modal.mode(),
+            None => return,
+        };
+        if prev == Mode::Visual && mode != Mode::Visual {
+            self.cancel_selection();
+        } else if prev != Mode::Visual && mode == Mode::Visual {
+            self.start_selection();
+        }
+        if let Some(modal) = &mut self.modal {
+            modal.set_mode(mode);
+        }
+    }
+
+    // Apply the action of modal editing. Returns if the text was modified
+    fn apply_modal_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::Move(m) => {
+                self.move_cursor(m);
+                false
+            }
+            Action::Operate(op, target) => self.operate(op, target),
+            Action::DeleteChar => self.delete_next_char(),
+            // Lines yanked linewise are pasted below or above the cursor line as Vim does
+            Action::Paste => match self.yank {
+                YankText::Lines(_) => self.paste_below(),
+                _ => self.paste(),
+            },
+            Action::PasteAbove => match self.yank {
+                YankText::Lines(_) => self.paste_above(),
+                _ => self.paste(),
+            },
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::Insert(at) => {
+                let modified = match at {
+                    InsertAt::Cursor => false,
+                    InsertAt::After => {
+                        if self.cursor.1 < self.lines[self.cursor.0].chars().count() {
+                            self.move_cursor(CursorMove::Forward);
+                        }
+                        false
+                    }
+                    InsertAt::LineStart => {
+                        self.move_cursor(CursorMove::FirstNonBlank);
+                        false
+                    }
+                    InsertAt::LineEnd => {
+                        self.move_cursor(CursorMove::End);
+                        false
+                    }
+                    InsertAt::LineBelow => {
+                        self.move_cursor(CursorMove::End);
+                        self.insert_newline();
+                        true
+                    }
+                    InsertAt::LineAbove => {
+                        self.move_cursor(CursorMove::Head);
+                        self.insert_newline();
+                        self.move_cursor(CursorMove::Up);
+                        true
+                    }
+                };
+                self.set_mode(Mode::Insert);
+                modified
+            }
+            Action::Visual => {
+                self.set_mode(Mode::Visual);
+                false
+            }
+            Action::Normal => {
+                if self.mode() == Mode::Insert && self.cursor.1 > 0 {
+                    self.move_cursor(CursorMove::Back);
+                }
+                self.set_mode(Mode::Normal);
+                false
+            }
+            Action::Nop => false,
+        }
+    }
+
+    // Apply the operator of modal editing to the target text. Deleted text is yanked as Vim does
+    fn operate(&mut self, op: Operator, target: Target) -> bool {
+        match target {
+            Target::Motion(m) => {
+                self.cancel_selection();
+                self.start_selection();
+                self.move_cursor(m);
+            }
+            Target::WordEnd => {
+                let (row, col) = self.cursor;
+                let line = &self.lines[row];
+                // On spaces it works as `w` does
+                let end = match line.chars().nth(col) {
+                    Some(c) if !c.is_whitespace() => find_word_end_forward(line, col).unwrap_or(line.chars().count()),
+                    _ => return self.operate(op, Target::Motion(CursorMove::WordForward)),
+                };
+                self.cancel_selection();
+                self.start_selection();
+                self.cursor.1 = end;
+            }
+            Target::Line if op == Operator::Change => {
+                let len = self.lines[self.cursor.0].chars().count();
+                self.cancel_selection();
+                self.cursor.1 = 0;
+                self.start_selection();
+                self.cursor.1 = len;
+            }
+            Target::Line => {
+                self.cancel_selection();
+                let modified = self.yank_line(op == Operator::Delete);
+                self.set_mode(Mode::Normal);
+                return modified;
+            }
+            Target::Object(obj) => {
+                if !self.select_text_object(obj) {
+                    return false;
+                }
+            }
+            Target::Selection => self.select_cursor_char(),
+        }
+
+        let modified = match op {
+            Operator::Delete | Operator::Change => self.cut(),
+            Operator::Yank => {
+                let start = self.selection().map(|r| r.start);
+                self.copy();
+                if let Some(start) = start {
+                    self.cursor = start.into();
+                }
+                false
+            }
+        };
+        self.cancel_selection();
+        self.set_mode(if op == Operator::Change { Mode::Insert } else { Mode::Normal });
+        modified
+    }
+
+    // The selection of visual mode includes the character under the cursor as Vim does, so the end of the selection is
+    // moved over it. At the end of a line, the newline is included
+    fn select_cursor_char(&mut self) {
+        let start = match self.selection_start {
+            Some(start) => start,
+            None => return,
+        };
+        let next = |(row, col): (usize, usize)| {
+            if col < self.lines[row].chars().count() {
+                (row, col + 1)
+            } else if row + 1 < self.lines.len() {
+                (row + 1, 0)
+            } else {
+                (row, col)
+            }
+        };
+        if self.cursor >= start {
+            self.cursor = next(self.cursor);
+        } else {
+            self.selection_start = Some(next(start));
+        }
+    }
+
+    /// Set the keymap consulted by [`TextArea::input`] before the built-in key mappings. See [`KeyBindings`] for the
+    /// default bindings. Set [`KeyBindings::empty`] to use only the built-in key mappings.
+    pub fn set_key_bindings(&mut self, bindings: KeyBindings) {
+        self.key_bindings = bindings;
+    }
+
+    /// Get the keymap consulted by [`TextArea::input`].
+    pub fn key_bindings(&self) -> &KeyBindings {
+        &self.key_bindings
+    }
+
+    /// Get the mutable keymap consulted by [`TextArea::input`] to add or remove bindings.
+    /// ```
+    /// use tuipaz_textarea::{Command, Key, KeyChord, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.key_bindings_mut().bind(KeyChord::alt(Key::Char('a')), Command::SelectAll);
+    /// assert_eq!(textarea.key_bindings().get(KeyChord::alt(Key::Char('a'))), Some(Command::SelectAll));
+    /// ```
+    pub fn key_bindings_mut(&mut self) -> &mut KeyBindings {
+        &mut self.key_bindings
+    }
+
+    /// Replace the keymap with the preset. This is a shortcut of setting [`KeyBindings::preset`] with
+    /// [`TextArea::set_key_bindings`]. Key inputs not in the preset are not handled by the built-in key mappings
+    /// except for typing text. See [`Keymap`] for the bindings of each preset.
+    /// ```
+    /// use tuipaz_textarea::{Input, InputKind, Key, Keymap, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["hello"]);
+    /// textarea.set_keymap(Keymap::Standard);
+    ///
+    /// let ctrl = |c| Input { key: Key::Char(c), ctrl: true, alt: false, shift: false, kind: InputKind::Press };
+    /// textarea.input(ctrl('a'));
+    /// textarea.input(ctrl('x'));
+    /// assert_eq!(textarea.lines(), [""]);
+    /// textarea.input(ctrl('z'));
+    /// assert_eq!(textarea.lines(), ["hello"]);
+    /// ```
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.key_bindings = KeyBindings::preset(keymap);
+    }
+
+    /// Set the compose table for dead key sequences. When a table is set, typing a dead key such as `'` waits for the
+    /// next character and inserts the composed character (`'` then `e` inserts `é`). When the next character does not
+    /// make a sequence, the dead key is inserted as is. Passing `None` disables composition, which is the default.
+    /// ```
+    /// use tuipaz_textarea::{ComposeTable, Input, InputKind, Key, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.set_compose_table(Some(ComposeTable::default()));
+    ///
+    /// textarea.input(Input { key: Key::Char('\''), ctrl: false, alt: false, shift: false, kind: InputKind::Press });
+    /// assert_eq!(textarea.pending_compose(), Some('\''));
+    /// textarea.input(Input { key: Key::Char('e'), ctrl: false, alt: false, shift: false, kind: InputKind::Press });
+    /// assert_eq!(textarea.lines(), ["é"]);
+    /// ```
+    pub fn set_compose_table(&mut self, table: Option<ComposeTable>) {
+        self.compose = table;
+        self.pending_compose = None;
+    }
+
+    /// Get the compose table set by [`TextArea::set_compose_table`].
+    pub fn compose_table(&self) -> Option<&ComposeTable> {
+        self.compose.as_ref()
+    }
+
+    /// Get the dead key waiting for the next character to compose with.
+    pub fn pending_compose(&self) -> Option<char> {
+        self.pending_compose
+    }
+
+    /// Set how repeated key events ([`InputKind::Repeat`]) are handled. By default, repeats are handled the same as
+    /// presses. Key release events are always ignored.
+    /// ```
+    /// use tuipaz_textarea::{Input, InputKind, Key, RepeatBehavior, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["hello world"]);
+    /// textarea.set_repeat_behavior(RepeatBehavior::Accelerate(3));
+    ///
+    /// let right = Input { key: Key::Right, ctrl: false, alt: false, shift: false, kind: InputKind::Repeat };
+    /// textarea.input(right);
+    /// assert_eq!(textarea.cursor(), (0, 3));
+    /// ```
+    pub fn set_repeat_behavior(&mut self, behavior: RepeatBehavior) {
+        self.repeat_behavior = behavior;
+    }
+
+    /// Get how repeated key events are handled.
+    pub fn repeat_behavior(&self) -> RepeatBehavior {
+        self.repeat_behavior
+    }
+
+    /// Set if a hard tab is used or not for indent. When `true` is set, typing a tab key inserts a hard tab instead of
+    /// spaces. By default, hard tab is disabled.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_hard_tab_indent(true);
+    /// textarea.insert_tab();
+    /// assert_eq!(textarea.lines(), ["\t"]);
+    /// ```
+    pub fn set_hard_tab_indent(&mut self, enabled: bool) {
+        self.hard_tab_indent = enabled;
+    }
+
+    /// Get if a hard tab is used for indent or not.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// assert!(!textarea.hard_tab_indent());
+    /// textarea.set_hard_tab_indent(true);
+    /// assert!(textarea.hard_tab_indent());
+    /// ```
+    pub fn hard_tab_indent(&self) -> bool {
+        self.hard_tab_indent
+    }
+
+    /// Set if typing an opening bracket or quote inserts the closing one after the cursor. Typing the closing character
+    /// right before the inserted one moves the cursor over it instead of inserting another one. Pairs are `()`, `[]`,
+    /// `{}`, `""`, `''` and ``` `` ```. Brackets are paired only when the cursor is followed by whitespace, the end of
+    /// the line or a closing character, and quotes are not paired after a word character. Typing `[` and `]` over
+    /// the inserted `]` still creates a link. Only typed keys are paired, not [`TextArea::insert_char`]. By default,
+    /// auto-pairing is disabled.
+    /// ```
+    /// use tuipaz_textarea::{Input, Key, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.set_auto_pairs(true);
+    /// let mut type_str = |s: &str| {
+    ///     for c in s.chars() {
+    ///         textarea.input(Input { key: Key::Char(c), ..Input::default() });
+    ///     }
+    /// };
+    ///
+    /// type_str("f(");
+    /// type_str("x)");
+    /// assert_eq!(textarea.lines(), ["f(x)"]);
+    /// assert_eq!(textarea.cursor(), (0, 4));
+    /// ```
+    pub fn set_auto_pairs(&mut self, enabled: bool) {
+        self.auto_pairs = enabled;
+        self.auto_closers.clear();
+    }
+
+    /// Get if auto-pairing of brackets and quotes is enabled. See [`TextArea::set_auto_pairs`].
+    pub fn auto_pairs(&self) -> bool {
+        self.auto_pairs
+    }
+
+    /// Set if editing is aware of quote blocks, whose lines start with `> ` as in replies of emails. When enabled, a
+    /// newline inserted in a quoted line starts the new line with the same quote prefix, and a newline on a line which
+    /// has only the prefix removes the prefix to end the quote block. Rows hard wrapped at the max column while typing
+    /// also get the prefix, and [`TextArea::logical_lines`] removes it again when joining them. This is disabled by
+    /// default.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["> hello"]);
+    /// textarea.set_quote_aware(true);
+    /// textarea.move_cursor(CursorMove::End);
+    ///
+    /// textarea.insert_newline();
+    /// textarea.insert_str("world", textarea.cursor());
+    /// assert_eq!(textarea.lines(), ["> hello", "> world"]);
+    /// ```
+    pub fn set_quote_aware(&mut self, enabled: bool) {
+        self.quote_aware = enabled;
+    }
+
+    /// Get if editing is aware of quote blocks. See [`TextArea::set_quote_aware`].
+    pub fn quote_aware(&self) -> bool {
+        self.quote_aware
+    }
+
+    /// Get a string for indent. It consists of spaces by default. When hard tab is enabled, it is a tab character.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// assert_eq!(textarea.indent(), "    ");
+    /// textarea.set_tab_length(2);
+    /// assert_eq!(textarea.indent(), "  ");
+    /// textarea.set_hard_tab_indent(true);
+    /// assert_eq!(textarea.indent(), "\t");
+    /// ```
+    pub fn indent(&self) -> &'static str {
+        if self.hard_tab_indent {
+            "\t"
+        } else {
+            spaces(self.tab_len)
+        }
+    }
+
+    /// Set how many modifications are remembered for undo/redo. Setting 0 disables undo/redo.
+    pub fn set_max_histories(&mut self, max: usize) {
+        self.history = History::new(max);
+    }
+
+    /// Get how many modifications are remembered for undo/redo. The default value is 50.
+    pub fn max_histories(&self) -> usize {
+        self.history.max_items()
+    }
+
+    /// Set the style of line at cursor. By default, the cursor line is styled with underline. To stop styling the
+    /// cursor line, set the default style.
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// let style = Style::default().fg(Color::Red);
+    /// textarea.set_cursor_line_style(style);
+    /// assert_eq!(textarea.cursor_line_style(), style);
+    ///
+    /// // Disable cursor line style
+    /// textarea.set_cursor_line_style(Style::default());
+    /// ```
+    pub fn set_cursor_line_style(&mut self, style: Style) {
+        self.cursor_line_style = style;
+    }
+
+    /// Get the style of cursor line. By default it is styled with underline.
+    pub fn cursor_line_style(&self) -> Style {
+        self.cursor_line_style
+    }
+
+    /// Set the style of line number. By setting the style with this method, line numbers are drawn in textarea, meant
+    /// that line numbers are disabled by default. If you want to show line numbers but don't want to style them, set
+    /// the default style.
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// // Show line numbers in dark gray background
+    /// let style = Style::default().bg(Color::DarkGray);
+    /// textarea.set_line_number_style(style);
+    /// assert_eq!(textarea.line_number_style(), Some(style));
+    /// ```
+    pub fn set_line_number_style(&mut self, style: Style) {
+        self.line_number_style = Some(style);
+    }
+
+    /// Remove the style of line number which was set by [`TextArea::set_line_number_style`]. After calling this
+    /// method, Line numbers will no longer be shown.
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_line_number_style(Style::default().bg(Color::DarkGray));
+    /// textarea.remove_line_number();
+    /// assert_eq!(textarea.line_number_style(), None);
+    /// ```
+    pub fn remove_line_number(&mut self) {
+        self.line_number_style = None;
+    }
+
+    /// Get the style of line number if set.
+    pub fn line_number_style(&self) -> Option<Style> {
+        self.line_number_style
+    }
+
+    /// Get the width of the line number gutter in cells. The gutter has the digits of the last line number with one
+    /// space on each side, and its width is 0 when line numbers are not shown. Hosts can use this to reserve layout
+    /// space consistently with the rendered text.
+    /// ```
+    /// use ratatui::style::Style;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(vec!["line"; 100]);
+    /// assert_eq!(textarea.gutter_width(), 0);
+    ///
+    /// textarea.set_line_number_style(Style::default());
+    /// assert_eq!(textarea.gutter_width(), 5);
+    /// ```
+    pub fn gutter_width(&self) -> u16 {
+        if self.line_number_style.is_none() {
+            return 0;
+        }
+        num_digits(self.lines.len()) as u16 + 2
+    }
+
+    /// Get the smallest `(width, height)` of the area where the textarea is rendered without degrading. It is the
+    /// size of the block borders, the gutter of line numbers and one cell of text. This is useful for layout
+    /// negotiation.
+    ///
+    /// In smaller areas, the content is clipped. The gutter is hidden when no text column is left beside it, and the
+    /// block is not rendered when nothing is left inside it. Nothing is rendered in a zero-sized area.
+    /// ```
+    /// use ratatui::style::Style;
+    /// use ratatui::widgets::{Block, Borders};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello"]);
+    /// assert_eq!(textarea.min_size(), (1, 1));
+    ///
+    /// textarea.set_block(Block::default().borders(Borders::ALL));
+    /// textarea.set_line_number_style(Style::default());
+    /// assert_eq!(textarea.min_size(), (2 + 3 + 1, 2 + 1));
+    /// ```
+    pub fn min_size(&self) -> (u16, u16) {
+        let (width, height) = match &self.block {
+            Some(b) => {
+                let area = Rect { x: 0, y: 0, width: u16::MAX, height: u16::MAX };
+                let inner = b.inner(area);
+                (area.width - inner.width, area.height - inner.height)
+            }
+            None => (0, 0),
+        };
+        (width.saturating_add(self.gutter_width()).saturating_add(1), height.saturating_add(1))
+    }
+
+    // Area of the text in the render area, and whether the block and the gutter are rendered
+    pub(crate) fn render_layout(&self, area: Rect) -> (Rect, bool, bool) {
+        let (inner, block) = match &self.block {
+            Some(b) => {
+                let inner = b.inner(area);
+                if inner.width == 0 || inner.height == 0 {
+                    (area, false)
+                } else {
+                    (inner, true)
+                }
+            }
+            None => (area, false),
+        };
+        let gutter = self.line_number_style.is_some() && inner.width > self.gutter_width();
+        (inner, block, gutter)
+    }
+
+    /// Set the callback to resolve include lines like `!include(note-id)`. The callback receives the ID in the
+    /// parentheses and returns the lines to show beneath the include line, or `None` when the ID is unknown. The
+    /// included lines are rendered read-only with the include style. They are not a part of the text, so the cursor
+    /// never enters them and they are not affected by edits or undo/redo.
+    ///
+    /// The callback is called on every rendering, so it should be cheap (e.g. look up a cache in the host).
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["# Index", "!include(intro)"]);
+    ///
+    /// textarea.set_include_resolver(|id| match id {
+    ///     "intro" => Some(vec!["Welcome to my notes".to_string()]),
+    ///     _ => None,
+    /// });
+    /// ```
+    pub fn set_include_resolver<F>(&mut self, resolver: F)
+    where
+        F: Fn(&str) -> Option<Vec<String>> + Send + Sync + 'static,
+    {
+        self.include_resolver = Some(IncludeResolver::new(resolver));
+    }
+
+    /// Remove the callback set by [`TextArea::set_include_resolver`]. After calling this method, include lines are
+    /// rendered as normal text.
+    pub fn remove_include_resolver(&mut self) {
+        self.include_resolver = None;
+    }
+
+    /// Set the style of the included lines. The default style is dark gray.
+    pub fn set_include_style(&mut self, style: Style) {
+        self.include_style = style;
+    }
+
+    /// Get the style of the included lines.
+    pub fn include_style(&self) -> Style {
+        self.include_style
+    }
+
+    /// Set whether [`TextArea::copy`] and [`TextArea::cut`] operate on the whole cursor line including its newline
+    /// when nothing is selected. This is disabled by default and they do nothing without selection. The line is yanked
+    /// linewise (see [`YankContent::Lines`]), so [`TextArea::paste`] inserts it above the cursor line wherever the
+    /// cursor is in the line.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["aaa", "bbb", "ccc"]);
+    /// textarea.set_empty_selection_clipboard(true);
+    ///
+    /// textarea.cut();
+    /// assert_eq!(textarea.lines(), ["bbb", "ccc"]);
+    /// assert_eq!(textarea.yank_text(), "aaa\n");
+    ///
+    /// textarea.move_cursor(CursorMove::Jump(1, 2));
+    /// textarea.paste();
+    /// assert_eq!(textarea.lines(), ["bbb", "aaa", "ccc"]);
+    /// ```
+    pub fn set_empty_selection_clipboard(&mut self, enabled: bool) {
+        self.empty_selection_clipboard = enabled;
+    }
+
+    /// Get whether copy and cut operate on the cursor line without selection. See
+    /// [`TextArea::set_empty_selection_clipboard`].
+    pub fn empty_selection_clipboard(&self) -> bool {
+        self.empty_selection_clipboard
+    }
+
+    /// Enable or disable highlighting bare URLs such as `https://example.com` in the text with the link style. URLs
+    /// can be converted into [`Link`]s with [`TextArea::linkify_url`] and [`TextArea::linkify_urls`]. This is
+    /// disabled by default.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_url_detection(true);
+    /// assert!(textarea.url_detection());
+    /// ```
+    pub fn set_url_detection(&mut self, enabled: bool) {
+        self.url_detection = enabled;
+    }
+
+    /// Get whether bare URLs are highlighted. See [`TextArea::set_url_detection`].
+    pub fn url_detection(&self) -> bool {
+        self.url_detection
+    }
+
+    /// Set how `Up` and `Down` keys (and `Ctrl+P`, `Ctrl+N`) move the cursor. With [`LineMovement::Logical`], rows
+    /// made by hard wrapping a line at the max column are skipped and the cursor moves to the previous or next
+    /// logical line. The default is [`LineMovement::Visual`].
+    /// ```
+    /// use tuipaz_textarea::{LineMovement, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_line_movement(LineMovement::Logical);
+    /// assert_eq!(textarea.line_movement(), LineMovement::Logical);
+    /// ```
+    pub fn set_line_movement(&mut self, movement: LineMovement) {
+        self.line_movement = movement;
+    }
+
+    /// Get how `Up` and `Down` keys move the cursor. See [`TextArea::set_line_movement`].
+    pub fn line_movement(&self) -> LineMovement {
+        self.line_movement
+    }
+
+    /// Add a token provider to detect and highlight tokens such as ticket IDs, email addresses or file paths. The
+    /// tokens are composed as [`Overlay::Custom`]. When tokens of multiple providers overlap, the style of the
+    /// provider added later is applied on top.
+    /// ```
+    /// use ratatui::style::{Color, Style};
+    /// use tuipaz_textarea::{TextArea, TokenProvider};
+    ///
+    /// let mut textarea = TextArea::from(["mail foo@example.com about /etc/hosts"]);
+    /// textarea.add_token_provider(TokenProvider::email(Style::default().fg(Color::Blue)));
+    /// textarea.add_token_provider(TokenProvider::file_path(Style::default().fg(Color::Green)));
+    ///
+    /// let tokens = textarea.tokens();
+    /// assert_eq!(tokens[0].text, "foo@example.com");
+    /// assert_eq!(tokens[1].kind, "file_path");
+    /// ```
+    pub fn add_token_provider(&mut self, provider: TokenProvider) {
+        self.token_providers.push(provider);
+    }
+
+    /// Remove the token providers of the kind. Returns `false` when no provider has the kind.
+    pub fn remove_token_provider(&mut self, kind: &str) -> bool {
+        let len = self.token_providers.len();
+        self.token_providers.retain(|p| p.kind() != kind);
+        self.token_providers.len() != len
+    }
+
+    /// Get the token providers in the order they were added.
+    pub fn token_providers(&self) -> &[TokenProvider] {
+        &self.token_providers
+    }
+
+    /// Enable or disable conceal mode. In conceal mode, markup characters are hidden on rendering except for the cursor
+    /// line, which is always rendered as is so that the markup can be edited. The hidden characters are `#`s of
+    /// headings, brackets and targets of `[label](target)` links, brackets of `[[note]]` links and `**` around
+    /// emphasized text. This is disabled by default.
+    ///
+    /// The text is not changed by this mode. Use [`TextArea::display_col`] and [`TextArea::logical_col`] to map
+    /// columns between the text and the rendered lines.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["cursor line", "see [docs](https://docs.rs)"]);
+    ///
+    /// textarea.set_conceal(true);
+    /// assert!(textarea.conceal());
+    /// assert_eq!(textarea.display_col(1, 5), 4);
+    /// ```
+    pub fn set_conceal(&mut self, enabled: bool) {
+        self.conceal = enabled;
+    }
+
+    /// Get whether conceal mode is enabled. See [`TextArea::set_conceal`].
+    pub fn conceal(&self) -> bool {
+        self.conceal
+    }
+
+    // Markup in the line at the row is hidden unless the line is in a fenced code block
+    fn conceals(&self, row: usize) -> bool {
+        self.conceal && row != self.cursor.0 && self.mask.is_none()
+    }
+
+    fn concealed_ranges(&self, row: usize) -> Option<Vec<Range<usize>>> {
+        if !self.conceals(row) || self.line_cache.code_block_at(&self.lines, row).is_some() {
+            return None;
+        }
+        Some(find_concealed(self.lines.get(row)?))
+    }
+
+    /// Convert the column in characters of the line at the row into the column in characters of the rendered line.
+    /// They differ when some markup characters before the column are hidden in conceal mode. A column in the hidden
+    /// characters is mapped to the next visible character. See [`TextArea::set_conceal`].
+    pub fn display_col(&self, row: usize, col: usize) -> usize {
+        match (self.concealed_ranges(row), self.lines.get(row)) {
+            (Some(ranges), Some(line)) => display_col(line, col, &ranges),
+            _ => col,
+        }
+    }
+
+    /// Convert the column in characters of the rendered line at the row into the column in characters of the line.
+    /// This is the inverse of [`TextArea::display_col`]. It is useful to move the cursor to the position clicked by
+    /// the mouse in conceal mode.
+    pub fn logical_col(&self, row: usize, col: usize) -> usize {
+        match (self.concealed_ranges(row), self.lines.get(row)) {
+            (Some(ranges), Some(line)) => logical_col(line, col, &ranges),
+            _ => col,
+        }
+    }
+
+    /// Set the style of `#tags` or `@mentions` in the text. Tags are not highlighted until their style is set. The
+    /// style is composed as [`Overlay::Custom`].
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::{TagKind, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// let style = Style::default().fg(Color::Cyan);
+    /// textarea.set_tag_style(TagKind::Hashtag, style);
+    /// assert_eq!(textarea.tag_style(TagKind::Hashtag), Some(style));
+    /// assert_eq!(textarea.tag_style(TagKind::Mention), None);
+    /// ```
+    pub fn set_tag_style(&mut self, kind: TagKind, style: Style) {
+        *self.tag_style_mut(kind) = Some(style);
+    }
+
+    /// Remove the style of `#tags` or `@mentions` set by [`TextArea::set_tag_style`]. After calling this method, the
+    /// tags will no longer be highlighted.
+    pub fn remove_tag_style(&mut self, kind: TagKind) {
+        *self.tag_style_mut(kind) = None;
+    }
+
+    /// Get the style of `#tags` or `@mentions` if set.
+    pub fn tag_style(&self, kind: TagKind) -> Option<Style> {
+        match kind {
+            TagKind::Hashtag => self.hashtag_style,
+            TagKind::Mention => self.mention_style,
+        }
+    }
+
+    fn tag_style_mut(&mut self, kind: TagKind) -> &mut Option<Style> {
+        match kind {
+            TagKind::Hashtag => &mut self.hashtag_style,
+            TagKind::Mention => &mut self.mention_style,
+        }
+    }
+
+    /// Set the placeholder text. The text is set in the textarea when no text is input. Setting a non-empty string `""`
+    /// enables the placeholder. The default value is an empty string so the placeholder is disabled by default.
+    /// To customize the text style, see [`TextArea::set_placeholder_style`].
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// assert_eq!(textarea.placeholder_text(), "");
+    /// assert!(textarea.placeholder_style().is_none());
+    ///
+    /// textarea.set_placeholder_text("Hello");
+    /// assert_eq!(textarea.placeholder_text(), "Hello");
+    /// assert!(textarea.placeholder_style().is_some());
+    /// ```
+    pub fn set_placeholder_text(&mut self, placeholder: impl Into<String>) {
+        self.placeholder = placeholder.into();
+    }
+
+    /// Set the style of the placeholder text. The default style is a dark gray text.
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// assert_eq!(textarea.placeholder_style(), None); // When the placeholder is disabled
+    ///
+    /// textarea.set_placeholder_text("Enter your message"); // Enable placeholder by setting non-empty text
+    ///
+    /// let style = Style::default().bg(Color::Blue);
+    /// textarea.set_placeholder_style(style);
+    /// assert_eq!(textarea.placeholder_style(), Some(style));
+    /// ```
+    pub fn set_placeholder_style(&mut self, style: Style) {
+        self.placeholder_style = style;
+    }
+
+    /// Get the placeholder text. An empty string means the placeholder is disabled. The default value is an empty string.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::default();
+    /// assert_eq!(textarea.placeholder_text(), "");
+    /// ```
+    pub fn placeholder_text(&self) -> &'_ str {
+        self.placeholder.as_str()
+    }
+
+    /// Get the placeholder style. When the placeholder text is empty, it returns `None` since the placeholder is disabled.
+    /// The default style is a dark gray text.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// assert_eq!(textarea.placeholder_style(), None);
+    ///
+    /// textarea.set_placeholder_text("hello");
+    /// assert!(textarea.placeholder_style().is_some());
+    /// ```
+    pub fn placeholder_style(&self) -> Option<Style> {
+        if self.placeholder.is_empty() {
+            None
+        } else {
+            Some(self.placeholder_style)
+        }
+    }
+
+    /// Specify a character masking the text. All characters in the textarea will be replaced by this character.
+    /// This API is useful for making a kind of credentials form such as a password input.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_mask_char('*');
+    /// assert_eq!(textarea.mask_char(), Some('*'));
+    /// textarea.set_mask_char('●');
+    /// assert_eq!(textarea.mask_char(), Some('●'));
+    /// ```
+    pub fn set_mask_char(&mut self, mask: char) {
+        self.mask = Some(mask);
+    }
+
+    /// Clear the masking character previously set by [`TextArea::set_mask_char`].
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_mask_char('*');
+    /// assert_eq!(textarea.mask_char(), Some('*'));
+    /// textarea.clear_mask_char();
+    /// assert_eq!(textarea.mask_char(), None);
+    /// ```
+    pub fn clear_mask_char(&mut self) {
+        self.mask = None;
+    }
+
+    /// Get the character to mask text. When no character is set, `None` is returned.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// assert_eq!(textarea.mask_char(), None);
+    /// textarea.set_mask_char('*');
+    /// assert_eq!(textarea.mask_char(), Some('*'));
+    /// ```
+    pub fn mask_char(&self) -> Option<char> {
+        self.mask
+    }
+
+    /// Set the style of cursor. By default, a cursor is rendered in the reversed color. Setting the same style as
+    /// cursor line hides a cursor.
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// let style = Style::default().bg(Color::Red);
+    /// textarea.set_cursor_style(style);
+    /// assert_eq!(textarea.cursor_style(), style);
+    /// ```
+    pub fn set_cursor_style(&mut self, style: Style) {
+        self.cursor_style = style;
+    }
+
+    /// Register a custom highlight pass and return its ID. The pass is called with the [`LineHighlighter`] and the row
+    /// of each rendered line, and adds decorations with [`LineHighlighter::decorate`]. Passes run in the order they
+    /// were added, after the built-in passes. Their decorations are composed as [`Overlay::Custom`].
+    /// ```
+    /// use ratatui::style::{Color, Style};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["see #todo"]);
+    ///
+    /// // Color hashtags
+    /// let id = textarea.add_highlight_pass(|hl, _row| {
+    ///     let line = hl.line();
+    ///     if let Some(start) = line.find('#') {
+    ///         let end = line[start..].find(' ').map(|i| start + i).unwrap_or(line.len());
+    ///         hl.decorate(start..end, Style::default().fg(Color::Magenta));
+    ///     }
+    /// });
+    /// assert!(textarea.remove_highlight_pass(id));
+    /// ```
+    pub fn add_highlight_pass<F>(&mut self, pass: F) -> usize
+    where
+        F: Fn(&mut LineHighlighter<'_>, usize) + Send + Sync + 'static,
+    {
+        let id = self.next_highlight_pass_id;
+        self.next_highlight_pass_id += 1;
+        self.highlight_passes.push(HighlightPass {
+            id,
+            run: Arc::new(pass),
+        });
+        id
+    }
+
+    /// Remove the custom highlight pass with the ID returned from [`TextArea::add_highlight_pass`]. Returns `false`
+    /// when no pass has the ID.
+    pub fn remove_highlight_pass(&mut self, id: usize) -> bool {
+        let len = self.highlight_passes.len();
+        self.highlight_passes.retain(|p| p.id != id);
+        self.highlight_passes.len() != len
+    }
+
+    /// Remove all custom highlight passes.
+    pub fn clear_highlight_passes(&mut self) {
+        self.highlight_passes.clear();
+    }
+
+    /// Set the priority of the overlay. When overlays overlap, they are composed from the lowest priority to the
+    /// highest priority so that the overlay with the highest priority is drawn on top. Overlays with the same priority
+    /// are composed in the order of [`Overlay`] variants. The default priorities are link (1) < selection (2) < search
+    /// (3) = hop (3) < cursor (4).
+    /// ```
+    /// use tuipaz_textarea::{Overlay, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// // Draw links over the selection
+    /// textarea.set_overlay_priority(Overlay::Link, 3);
+    /// assert_eq!(textarea.overlay_priority(Overlay::Link), 3);
+    /// ```
+    pub fn set_overlay_priority(&mut self, overlay: Overlay, priority: u8) {
+        self.overlay_rules.set_priority(overlay, priority);
+    }
+
+    /// Get the priority of the overlay. See [`TextArea::set_overlay_priority`].
+    pub fn overlay_priority(&self, overlay: Overlay) -> u8 {
+        self.overlay_rules.priority(overlay)
+    }
+
+    /// Set how the style of the overlay is combined with the styles of the text and the overlays with lower
+    /// priorities below it. By default, all overlays patch the styles below them. See also
+    /// [`TextArea::set_style_patching`].
+    /// ```
+    /// use tuipaz_textarea::{Overlay, StyleMerge, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// // Hide the foreground color of links in the selection
+    /// textarea.set_overlay_merge(Overlay::Selection, StyleMerge::Replace);
+    /// assert_eq!(textarea.overlay_merge(Overlay::Selection), StyleMerge::Replace);
+    /// ```
+    pub fn set_overlay_merge(&mut self, overlay: Overlay, merge: StyleMerge) {
+        self.overlay_rules.set_merge(overlay, merge);
+    }
+
+    /// Get how the style of the overlay is combined with the styles below it. See [`TextArea::set_overlay_merge`].
+    pub fn overlay_merge(&self, overlay: Overlay) -> StyleMerge {
+        self.overlay_rules.merge(overlay)
+    }
+
+    /// Set if all overlays patch the styles below them (`true`) or replace them (`false`). With patching, each overlay
+    /// only changes the attributes set in its style. For example, the selection with a background color keeps the
+    /// foreground color and the modifiers of a link in it. Patching is enabled by default. Set `false` to restore the
+    /// behavior where the overlay on top decides the whole style. Use [`TextArea::set_overlay_merge`] to configure
+    /// each overlay.
+    /// ```
+    /// use tuipaz_textarea::{Overlay, StyleMerge, TextArea};
+    ///
+    /// let mut textarea = TextArea::default();
+    /// assert!(textarea.style_patching());
+    ///
+    /// textarea.set_style_patching(false);
+    /// assert!(!textarea.style_patching());
+    /// assert_eq!(textarea.overlay_merge(Overlay::Cursor), StyleMerge::Replace);
+    /// ```
+    pub fn set_style_patching(&mut self, enabled: bool) {
+        let merge = if enabled {
+            StyleMerge::Patch
+        } else {
+            StyleMerge::Replace
+        };
+        self.overlay_rules.set_all_merges(merge);
+    }
+
+    /// Check if all overlays patch the styles below them. See [`TextArea::set_style_patching`].
+    pub fn style_patching(&self) -> bool {
+        self.overlay_rules.all_merges(StyleMerge::Patch)
+    }
+
+    /// Set if the cursor covers the full display width of the character under it, such as a tab expanded to several
+    /// cells. When `false`, only the first cell of the character is styled with the cursor style. A wide character
+    /// (e.g. CJK characters and emojis) is one glyph drawn with the style of its first cell, so the cursor always
+    /// covers it. The default value is `true`.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["\tx"]);
+    /// textarea.set_tab_length(4);
+    ///
+    /// assert!(textarea.wide_cursor());
+    /// assert_eq!(textarea.styled_lines(0..1)[0].spans[0].content, "    ");
+    /// textarea.set_wide_cursor(false);
+    /// assert_eq!(textarea.styled_lines(0..1)[0].spans[0].content, " ");
+    /// ```
+    pub fn set_wide_cursor(&mut self, enabled: bool) {
+        self.wide_cursor = enabled;
+    }
+
+    /// Check if the cursor covers the full display width of the character under it. See [`TextArea::set_wide_cursor`].
+    pub fn wide_cursor(&self) -> bool {
+        self.wide_cursor
+    }
+
+    /// Get the style of cursor.
+    pub fn cursor_style(&self) -> Style {
+        self.cursor_style
+    }
+
+    /// Get slice of line texts. This method borrows the content, but not moves. Note that the returned slice will
+    /// never be empty because an empty text means a slice containing one empty line. This is correct since any text
+    /// file must end with a newline. When long lines are segmented by [`TextArea::set_max_line_bytes`], each segment is
+    /// a separate element. Use [`TextArea::logical_lines`] to get the original lines.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// assert_eq!(textarea.lines(), [""]);
+    ///
+    /// textarea.insert_char('a');
+    /// assert_eq!(textarea.lines(), ["a"]);
+    ///
+    /// textarea.insert_newline();
+    /// assert_eq!(textarea.lines(), ["a", ""]);
+    ///
+    /// textarea.insert_char('b');
+    /// assert_eq!(textarea.lines(), ["a", "b"]);
+    /// ```
+    pub fn lines(&'a self) -> &'a [String] {
+        &self.lines
+    }
+
+    /// Get the fenced code block containing the position `(row, col)`, such as the cursor position. A code block starts
+    /// with a line of ```` ``` ```` and ends with the next one, or at the end of the text when it is not closed. Lines in
+    /// code blocks are not hard wrapped, and links, URLs, tags, tokens and conceal mode are not applied to them. This is
+    /// useful to copy the code in the block or to pass it to an external formatter.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["text", "```rust", "fn main() {}", "```"]);
+    ///
+    /// let block = textarea.code_block_at((2, 0)).unwrap();
+    /// assert_eq!((block.start, block.end, block.info.as_str()), (1, 3, "rust"));
+    /// assert_eq!(&textarea.lines()[block.content_rows()], ["fn main() {}"]);
+    /// assert_eq!(textarea.code_block_at((0, 0)), None);
+    /// ```
+    pub fn code_block_at(&self, pos: impl Into<Position>) -> Option<CodeBlock> {
+        self.line_cache.code_block_at(&self.lines, pos.into().row)
+    }
+
+    /// Get all fenced code blocks in the text in order of rows. See [`TextArea::code_block_at`].
+    pub fn code_blocks(&self) -> Vec<CodeBlock> {
+        find_code_blocks(&self.lines)
+    }
+
+    /// Get the markdown style headings (lines starting with `#`) in the text. Lines in fenced code blocks are not
+    /// headings. This is used by [`crate::Outline`] to build the outline of the text.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["# Title", "text", "## Section"]);
+    /// let headings = textarea.headings();
+    ///
+    /// assert_eq!(headings.len(), 2);
+    /// assert_eq!((headings[1].level, headings[1].row), (2, 2));
+    /// assert_eq!(headings[1].title, "Section");
+    /// ```
+    pub fn headings(&self) -> Vec<Heading> {
+        parse_headings(&self.lines)
+    }
+
+    /// Get the include lines (`!include(id)`) in the text with their IDs and positions. See
+    /// [`TextArea::set_include_resolver`].
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["# Index", "  !include(intro)"]);
+    /// let includes = textarea.includes();
+    ///
+    /// assert_eq!(includes[0].0, "intro");
+    /// assert_eq!((includes[0].1.row, includes[0].1.col), (1, 2));
+    /// ```
+    pub fn includes(&self) -> Vec<(String, Pos)> {
+        collect_includes(&self.lines)
+    }
+
+    /// Get the `#tags` and `@mentions` in the text with their positions. Each tag text includes the leading `#` or
+    /// `@`. A tag starts at the beginning of a line or after a whitespace, so headings like `# Title` and email
+    /// addresses are not tags.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["# Title", "ask @alice about #todo"]);
+    /// let tags = textarea.tags();
+    ///
+    /// assert_eq!(tags.len(), 2);
+    /// assert_eq!(tags[0].0, "@alice");
+    /// assert_eq!((tags[1].1.row, tags[1].1.col), (1, 17));
+    /// ```
+    pub fn tags(&self) -> Vec<(String, Pos)> {
+        collect_tags(&self.lines)
+    }
+
+    /// Get the bare URLs (`https://...`) in the text with their positions. See [`TextArea::set_url_detection`].
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["docs:", "(https://docs.rs)"]);
+    /// let urls = textarea.urls();
+    ///
+    /// assert_eq!(urls.len(), 1);
+    /// assert_eq!(urls[0].0, "https://docs.rs");
+    /// assert_eq!((urls[0].1.row, urls[0].1.col), (1, 1));
+    /// ```
+    pub fn urls(&self) -> Vec<(String, Pos)> {
+        collect_urls(&self.lines)
+    }
+
+    /// Get the tokens found by the token providers in the text. Tokens are ordered by their rows, and then by the
+    /// order of their providers. See [`TextArea::add_token_provider`].
+    pub fn tokens(&self) -> Vec<Token> {
+        collect_tokens(&self.token_providers, &self.lines)
+    }
+
+    /// Get the token at the position `(row, col)`. When tokens of multiple providers overlap at the position, the
+    /// token of the provider added first is returned.
+    /// ```
+    /// use ratatui::style::Style;
+    /// use tuipaz_textarea::{CursorMove, TextArea, TokenProvider};
+    ///
+    /// let mut textarea = TextArea::from(["fixed in ABC-123"]);
+    /// textarea.add_token_provider(TokenProvider::new("ticket", r"\b[A-Z]+-\d+\b", Style::default()).unwrap());
+    ///
+    /// textarea.move_cursor(CursorMove::End);
+    /// textarea.move_cursor(CursorMove::Back);
+    /// let token = textarea.token_at(textarea.cursor()).unwrap();
+    /// assert_eq!((token.kind.as_str(), token.text.as_str()), ("ticket", "ABC-123"));
+    /// assert_eq!(token.pos.col, 9);
+    /// ```
+    pub fn token_at(&self, pos: impl Into<Position>) -> Option<Token> {
+        let Position { row, col } = pos.into();
+        let line = self.lines.get(row)?;
+        token_at(&self.token_providers, line, row, self.line_offset(row, col))
+    }
+
+    /// Get the text wrapped at `width` columns without modifying the text. Each line is wrapped at spaces and words
+    /// longer than `width` are broken. Tabs are expanded to the tab length for computing widths but kept as `\t`.
+    /// This is useful for exporting the text, copying it to other apps or rendering it in a narrower pane. When
+    /// `width` is 0, the lines are returned as is.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["hello wrapped world", "", "ok"]);
+    ///
+    /// assert_eq!(textarea.wrapped_lines(8), ["hello", "wrapped", "world", "", "ok"]);
+    /// ```
+    pub fn wrapped_lines(&self, width: usize) -> Vec<String> {
+        self.lines
+            .iter()
+            .flat_map(|line| wrap_line(line, width, self.tab_len))
+            .collect()
+    }
+
+    /// Hard wrap all lines longer than the max column in the same way as typing. This is useful for normalizing text
+    /// loaded with [`TextArea::new`], which does not wrap the given lines. Lines are wrapped at spaces and links are
+    /// never broken, so a link longer than the max column stays on one line. Lines in fenced code blocks are not
+    /// wrapped. Links and the cursor are moved to their new positions. Short lines are not joined. The rewrapping is
+    /// recorded in the edit history as one edit so that it can be undone. Returns `true` when some line was wrapped.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tuipaz_textarea::{TextArea, TextAreaTheme};
+    ///
+    /// let lines = vec!["a line too long to fit".to_string(), "ok".to_string()];
+    /// let mut textarea = TextArea::new(lines, HashMap::new(), 11, TextAreaTheme::default());
+    ///
+    /// assert!(textarea.rewrap_all());
+    /// assert_eq!(textarea.lines(), ["a line too", "long to", "fit", "ok"]);
+    ///
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["a line too long to fit", "ok"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn rewrap_all(&mut self) -> bool {
+        let lines_before = self.lines.clone();
+        let links_before = self.links.clone();
+        let (row, col) = self.cursor;
+        let before = Pos::new(row, col, self.line_offset(row, col));
+        if !self.rewrap_lines() {
+            return false;
+        }
+
+        let (row, col) = self.cursor;
+        let offset = self.line_offset(row, col);
+        self.history.begin_group();
+        self.push_history(EditKind::ReplaceLines((lines_before, self.lines.clone())), before, offset);
+        self.history.end_group(links_before, self.links.clone());
+        true
+    }
+
+    /// Set the max column where lines are hard wrapped while typing, and rewrap the lines longer than the new width
+    /// with [`TextArea::rewrap_all`]. This is useful when the pane is resized or the preferred width is changed. Short
+    /// lines are not joined. Undoing the rewrapping restores the lines and links but not the max column. The max
+    /// column is at least 2. Returns `true` when some line was wrapped.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["a line too long to fit"]);
+    ///
+    /// assert!(textarea.set_max_col(11));
+    /// assert_eq!(textarea.lines(), ["a line too", "long to", "fit"]);
+    /// assert_eq!(textarea.max_col(), 11);
+    ///
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["a line too long to fit"]);
+    /// ```
+    pub fn set_max_col(&mut self, max_col: u16) -> bool {
+        self.max_col = cmp::max(max_col, 2);
+        self.rewrap_all()
+    }
+
+    /// Get the max column where lines are hard wrapped while typing. See [`TextArea::set_max_col`].
+    pub fn max_col(&self) -> u16 {
+        self.max_col
+    }
+
+    // Hard wrap the lines longer than the max column and move links and the cursor. Returns `true` when some line was
+    // wrapped.
+    #[allow(deprecated)]
+    fn rewrap_lines(&mut self) -> bool {
+        let width = (self.max_col as usize).saturating_sub(1);
+        let mut lines = Vec::with_capacity(self.lines.len());
+        let mut cursor = self.cursor;
+        let mut wrapped = false;
+        // Links are moved while iterating lines, so collect them by their original rows
+        self.link_rows.invalidate();
+        let mut row_links: HashMap<usize, Vec<usize>> = HashMap::new();
+        for link in self.links.values() {
+            row_links.entry(link.row).or_default().push(link.id);
+        }
+        let mut line_meta = BTreeMap::new();
+        let mut wrap_breaks = BTreeSet::new();
+        let mut paragraph_rows = Vec::with_capacity(self.lines.len());
+        let mut anchors = vec![];
+        let code = self.line_cache.code_rows(&self.lines, 0..self.lines.len());
+        for (row, line) in self.lines.iter().enumerate() {
+            if let Some(value) = self.line_meta.get(&row) {
+                line_meta.insert(lines.len(), value.clone());
+            }
+            if self.wrap_breaks.contains(&row) {
+                wrap_breaks.insert(lines.len());
+            }
+            paragraph_rows.push(lines.len());
+            let byte = |col: usize| line.char_indices().nth(col).map_or(line.len(), |(i, _)| i);
+            let ids = row_links.remove(&row).unwrap_or_default();
+            let keep = ids
+                .iter()
+                .map(|id| &self.links[id])
+                .map(|l| byte(l.start_col)..byte(l.end_col + 1))
+                .collect::<Vec<_>>();
+            // Lines in code blocks are not wrapped with the width 0
+            let width = if code[row] { 0 } else { width };
+            let ranges = wrap_ranges(line, width, self.tab_len, 0, &keep);
+            if ranges.len() == 1 {
+                let new_row = lines.len();
+                for id in ids.iter() {
+                    if let Some(link) = self.links.get_mut(id) {
+                        link.row = new_row;
+                    }
+                }
+                if cursor.0 == row {
+                    cursor.0 = new_row;
+                }
+                for (id, (_, col)) in self.anchors.iter().filter(|(_, (r, _))| *r == row) {
+                    anchors.push((id, (new_row, col)));
+                }
+                lines.push(line.clone());
+                continue;
+            }
+            wrapped = true;
+
+            // Map the column in the line to the row and the column in the wrapped lines
+            let locate = |col: usize| {
+                let offset = byte(col);
+                let i = ranges
+                    .iter()
+                    .rposition(|r| r.start <= offset)
+                    .unwrap_or(0);
+                let r = &ranges[i];
+                let col = line[r.start..cmp::min(offset, r.end)].chars().count();
+                (lines.len() + i, col)
+            };
+            for id in ids.iter() {
+                let link = match self.links.get_mut(id) {
+                    Some(link) => link,
+                    None => continue,
+                };
+                // Links are kept in one wrapped line
+                let (new_row, start_col) = locate(link.start_col);
+                let (end_row, end_col) = locate(link.end_col);
+                debug_assert_eq!(new_row, end_row, "link {} was broken by the wrap", id);
+                link.row = new_row;
+                link.start_col = start_col;
+                link.end_col = end_col;
+            }
+            if cursor.0 == row {
+                cursor = locate(cursor.1);
+            }
+            for (id, (_, col)) in self.anchors.iter().filter(|(_, (r, _))| *r == row) {
+                anchors.push((id, locate(col)));
+            }
+            lines.extend(ranges.into_iter().map(|r| line[r].to_string()));
+        }
+
+        if !wrapped {
+            return false;
+        }
+        self.lines = lines;
+        self.line_meta = line_meta;
+        self.wrap_breaks = wrap_breaks;
+        self.paragraph_ids.map(|row| paragraph_rows[row]);
+        self.sync_paragraph_ids(0..self.lines.len());
+        for (id, pos) in anchors {
+            self.anchors.set(id, pos);
+        }
+        self.cursor = cursor;
+        self.cancel_selection();
+        true
+    }
+
+    /// Export the text as a plain string with lines joined by `\n`. Links are written as specified by `rendering`.
+    /// Links only store their positions in the text, so `target` is called with each link to get its target (e.g. the
+    /// title or the URL of the linked note). Links whose target is `None` are written as their labels.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, LinkRendering, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["see notes"]);
+    /// textarea.move_cursor(CursorMove::Jump(0, 4));
+    /// textarea.init_link();
+    /// textarea.move_cursor(CursorMove::End);
+    /// textarea.move_cursor(CursorMove::Back);
+    /// textarea.insert_link();
+    ///
+    /// let target = |_: &_| Some("notes.md".to_string());
+    /// assert_eq!(textarea.export_plain(LinkRendering::LabelOnly, target), "see notes");
+    /// assert_eq!(textarea.export_plain(LinkRendering::LabelWithTarget, target), "see notes (notes.md)");
+    /// assert_eq!(textarea.export_plain(LinkRendering::Markdown, target), "see [notes](notes.md)");
+    /// ```
+    #[allow(deprecated)]
+    pub fn export_plain<F>(&self, rendering: LinkRendering, target: F) -> String
+    where
+        F: Fn(&Link) -> Option<String>,
+    {
+        let mut out = String::new();
+        for (row, line) in self.lines.iter().enumerate() {
+            if row > 0 && !self.soft_breaks.contains(&row) {
+                out.push('\n');
+            }
+            let mut links = self
+                .links
+                .values()
+                .filter(|l| !l.deleted && l.row == row)
+                .collect::<Vec<_>>();
+            links.sort_by_key(|l| l.start_col);
+
+            let chars = line.chars().collect::<Vec<_>>();
+            let mut col = 0;
+            for link in links {
+                let start = cmp::min(link.start_col, chars.len());
+                let end = cmp::min(link.end_col + 1, chars.len());
+                if start < col || start >= end {
+                    continue; // Overlapping or out of the line
+                }
+                out.extend(&chars[col..start]);
+                let label = chars[start..end].iter().collect::<String>();
+                out.push_str(&rendering.render(&label, target(link).as_deref()));
+                col = end;
+            }
+            out.extend(&chars[col..]);
+        }
+        out
+    }
+
+    /// Convert [`TextArea`] instance into line texts. The lines are the same as [`TextArea::lines`], so segments of
+    /// long lines are separate elements. Use [`TextArea::logical_lines`] to get the original lines.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.insert_char('a');
+    /// textarea.insert_newline();
+    /// textarea.insert_char('b');
+    ///
+    /// assert_eq!(textarea.into_lines(), ["a", "b"]);
+    /// ```
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines
+    }
+
+    /// Get the lines with the segments of long lines joined (see [`TextArea::set_max_line_bytes`]) and the paragraphs
+    /// hard wrapped at the max column while typing joined again (see [`TextArea::is_wrap_break`]). This is useful to
+    /// export the text or to pass it to external tools which wrap paragraphs by themselves. Line breaks typed by the
+    /// user are kept. This is the same as [`TextArea::lines`] when no line was segmented or wrapped.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["a".repeat(40), "b".to_string()]);
+    /// textarea.set_max_line_bytes(Some(16));
+    ///
+    /// assert_eq!(textarea.lines().len(), 4);
+    /// assert_eq!(textarea.logical_lines(), ["a".repeat(40), "b".to_string()]);
+    /// ```
+    pub fn logical_lines(&self) -> Vec<String> {
+        self.join_rows(|row| {
+            if self.soft_breaks.contains(&row) {
+                return Some(0);
+            }
+            if row == 0 || !self.wrap_breaks.contains(&row) {
+                return None;
+            }
+            // Rows wrapped in a quote block repeat the quote prefix
+            let prefix = quote_prefix(&self.lines[row]);
+            if prefix == quote_prefix(&self.lines[row - 1]) {
+                Some(prefix.len())
+            } else {
+                Some(0)
+            }
+        })
+    }
+
+    // Lines with only the segments of long lines joined. Hard wrapped lines are kept as they are shown
+    pub(crate) fn unsegmented_lines(&self) -> Vec<String> {
+        self.join_rows(|row| self.soft_breaks.contains(&row).then(|| 0))
+    }
+
+    // Join each row for which the function returns the byte offset in the row to the previous row
+    fn join_rows(&self, join: impl Fn(usize) -> Option<usize>) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::with_capacity(self.lines.len());
+        for (row, line) in self.lines.iter().enumerate() {
+            match (lines.last_mut(), join(row)) {
+                (Some(last), Some(offset)) => last.push_str(&line[offset..]),
+                _ => lines.push(line.clone()),
+            }
+        }
+        lines
+    }
+
+    /// Set the max number of bytes per line as a safety cap for very long lines such as minified JSON. Lines longer
+    /// than the cap are split at char boundaries into segments which are rendered and edited as separate rows, so that
+    /// operations on them do not cost quadratic time. Segments are not split inside links. [`TextArea::lines`] and
+    /// [`TextArea::into_lines`] return the rows as they are, while [`TextArea::logical_lines`] and the export methods
+    /// join the segments to reconstruct the original lines.
+    ///
+    /// Lines are segmented when this method is called, so call it after the text is loaded. Segments are not split
+    /// again while typing and a newline inserted in a segment is a real line break. Deleting a char at the head or the
+    /// end of a segment deletes the char in the neighbor segment instead of joining the rows, and undo and redo restore
+    /// segments joined by other edits as segments. Changing the cap joins the segments and splits them again, and
+    /// `None` removes the cap. Since rows are changed, the edit history is cleared when some line was split or joined.
+    /// The minimum cap is 16 bytes.
+    pub fn set_max_line_bytes(&mut self, max: Option<usize>) {
+        let max = max.map(|m| m.max(16));
+        let joined = self.join_segments();
+        self.max_line_bytes = max;
+        let split = match max {
+            Some(max) => self.split_long_lines(max),
+            None => false,
+        };
+        if joined || split {
+            self.sync_paragraph_ids(0..self.lines.len());
+            self.history = History::new(self.history.max_items());
+            self.cancel_selection();
+        }
+    }
+
+    /// Get the max number of bytes per line set by [`TextArea::set_max_line_bytes`].
+    pub fn max_line_bytes(&self) -> Option<usize> {
+        self.max_line_bytes
+    }
+
+    /// Check if the row is a segment continuing the long line in the previous row. See
+    /// [`TextArea::set_max_line_bytes`].
+    pub fn is_soft_break(&self, row: usize) -> bool {
+        self.soft_breaks.contains(&row)
+    }
+
+    /// Check if the row continues the paragraph in the previous row, which was hard wrapped when the text got longer
+    /// than the max column while typing. Line breaks typed by the user and by inserting text are not wrap breaks.
+    /// Since wrapped rows keep the space at the wrap point, the paragraph is restored by concatenating the rows as
+    /// done by [`TextArea::logical_lines`]. The mark moves with the row and is dropped when the row is joined into the
+    /// previous row.
+    pub fn is_wrap_break(&self, row: usize) -> bool {
+        self.wrap_breaks.contains(&row)
+    }
+
+    // Move the positions tracking the text to the rows and columns given by the function
+    #[allow(deprecated)]
+    fn remap_positions(&mut self, f: impl Fn((usize, usize)) -> (usize, usize)) {
+        self.link_rows.invalidate();
+        // Links are not split into segments so both ends are in the same row
+        for link in self.links.values_mut() {
+            let (row, start_col) = f((link.row, link.start_col));
+            link.end_col = f((link.row, link.end_col)).1;
+            link.row = row;
+            link.start_col = start_col;
+        }
+        self.anchors.map(&f);
+        self.cursor = f(self.cursor);
+        self.wrap_breaks = std::mem::take(&mut self.wrap_breaks)
+            .into_iter()
+            .map(|row| f((row, 0)).0)
+            .collect();
+        self.paragraph_ids.map(|row| f((row, 0)).0);
+        self.line_meta = std::mem::take(&mut self.line_meta)
+            .into_iter()
+            .map(|(row, value)| (f((row, 0)).0, value))
+            .collect();
+    }
+
+    // Split the lines longer than the max bytes into segments
+    #[allow(deprecated)]
+    fn split_long_lines(&mut self, max: usize) -> bool {
+        if self.lines.iter().all(|l| l.len() <= max) {
+            return false;
+        }
+        // Start row in the new lines and the byte ranges of segments for each original row
+        let mut rows = Vec::with_capacity(self.lines.len());
+        let mut lines = Vec::with_capacity(self.lines.len());
+        for (row, line) in self.lines.iter().enumerate() {
+            // Links are kept in one segment
+            let keep = if line.len() > max {
+                self.links
+                    .values()
+                    .filter(|l| !l.deleted && l.row == row)
+                    .map(|l| byte_offset(line, l.start_col)..byte_offset(line, l.end_col + 1))
+                    .collect()
+            } else {
+                vec![]
+            };
+            let ranges = segment_ranges(line, max, &keep);
+            for i in 1..ranges.len() {
+                self.soft_breaks.insert(lines.len() + i);
+            }
+            rows.push((lines.len(), ranges.clone()));
+            lines.extend(ranges.into_iter().map(|r| line[r].to_string()));
+        }
+        let old = std::mem::replace(&mut self.lines, lines);
+        self.remap_positions(|(row, col)| {
+            let (start, ranges) = match rows.get(row) {
+                Some(r) => r,
+                None => return (row, col),
+            };
+            let line = &old[row];
+            let offset = line.char_indices().nth(col).map_or(line.len(), |(i, _)| i);
+            let i = ranges.iter().rposition(|r| r.start <= offset).unwrap_or(0);
+            let r = &ranges[i];
+            (start + i, line[r.start..offset.min(r.end)].chars().count())
+        });
+        true
+    }
+
+    // Join the segments of long lines
+    fn join_segments(&mut self) -> bool {
+        if self.soft_breaks.is_empty() {
+            return false;
+        }
+        // Row and column offset in the joined lines for each row
+        let mut rows = Vec::with_capacity(self.lines.len());
+        let mut lines: Vec<String> = Vec::with_capacity(self.lines.len());
+        for (row, line) in self.lines.iter().enumerate() {
+            let joined = lines.len();
+            match lines.last_mut() {
+                Some(last) if self.soft_breaks.contains(&row) => {
+                    rows.push((joined - 1, last.chars().count()));
+                    last.push_str(line);
+                }
+                _ => {
+                    rows.push((joined, 0));
+                    lines.push(line.clone());
+                }
+            }
+        }
+        // Metadata of segments is dropped since it belongs to the first segment of the line
+        let soft_breaks = std::mem::take(&mut self.soft_breaks);
+        self.line_meta.retain(|row, _| !soft_breaks.contains(row));
+        self.lines = lines;
+        self.remap_positions(|(row, col)| match rows.get(row) {
+            Some(&(row, offset)) => (row, offset + col),
+            None => (row, col),
+        });
+        true
+    }
+
+    /// Get the current cursor position. 0-base character-wise (row, col) cursor position.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    ///
+    /// textarea.insert_char('a');
+    /// textarea.insert_newline();
+    /// textarea.insert_char('b');
+    ///
+    /// assert_eq!(textarea.cursor(), (1, 1));
+    /// ```
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Get the current cursor position as a [`Position`]. This is the same position as [`TextArea::cursor`].
+    pub fn cursor_position(&self) -> Position {
+        self.cursor.into()
+    }
+
+    /// Get the range of the selected text. The end of the range is exclusive. `None` is returned when no text is
+    /// selected.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea, TextRange};
+    ///
+    /// let mut textarea = TextArea::from(["abc", "def"]);
+    /// textarea.move_cursor(CursorMove::Jump(1, 2));
+    /// textarea.start_selection();
+    /// textarea.move_cursor(CursorMove::Jump(0, 1));
+    ///
+    /// assert_eq!(textarea.selection(), Some(TextRange::new((0, 1), (1, 2))));
+    /// ```
+    pub fn selection(&self) -> Option<TextRange> {
+        self.selection_range().map(|(start, end)| TextRange::new(&start, &end))
+    }
+
+    /// Get the IDs of the links which share some text with the range in order of their positions.
+    /// ```
+    /// use tuipaz_textarea::{Link, TextArea, TextAreaTheme, TextRange};
+    ///
+    /// let link = |id, row, start_col, end_col| Link { id, row, start_col, end_col, edited: false, deleted: false };
+    /// let lines = vec!["abc def".to_string(), "ghi jkl".to_string()];
+    /// let links = vec![(0, link(0, 0, 0, 2)), (1, link(1, 1, 4, 6))];
+    /// let textarea = TextArea::new(lines, links, 80, TextAreaTheme::default());
+    ///
+    /// assert_eq!(textarea.links_in_range((0, 2), (1, 5)), [0, 1]);
+    /// assert_eq!(textarea.links_in_range((0, 3), (1, 4)), [] as [usize; 0]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn links_in_range(&self, start: impl Into<Position>, end: impl Into<Position>) -> Vec<usize> {
+        let range = TextRange::new(start, end);
+        let mut ids: Vec<_> = self
+            .link_rows
+            .ids(&self.links, range.start.row..=range.end.row)
+            .into_iter()
+            .filter(|id| {
+                let link = &self.links[id];
+                !link.deleted && link.range().intersects(&range)
+            })
+            .collect();
+        ids.sort_by_key(|id| self.links[id].start());
+        ids
+    }
+
+    // Positions after the last line are clamped to the end of the text
+    fn clamp_position(&self, pos: Position) -> (usize, usize) {
+        let last = self.lines.len() - 1;
+        let (row, col) = if pos.row > last { (last, usize::MAX) } else { (pos.row, pos.col) };
+        (row, col.min(self.lines[row].chars().count()))
+    }
+
+    // Clamp the range into the text and select it. `false` is returned when the range is empty after clamping
+    fn select_range(&mut self, range: TextRange) -> bool {
+        let (start, end) = (self.clamp_position(range.start), self.clamp_position(range.end));
+        self.cancel_selection();
+        if start == end {
+            return false;
+        }
+        self.selection_start = Some(start);
+        self.cursor = end;
+        true
+    }
+
+    /// Delete the text in the range. The range is clamped into the text. The deleted text is not yanked and the cursor
+    /// moves to the start of the range. This method returns if some text was deleted or not.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, TextRange};
+    ///
+    /// let mut textarea = TextArea::from(["hello", "world"]);
+    ///
+    /// assert!(textarea.delete_range(TextRange::new((0, 2), (1, 3))));
+    /// assert_eq!(textarea.lines(), ["held"]);
+    /// assert_eq!(textarea.cursor(), (0, 2));
+    /// ```
+    pub fn delete_range(&mut self, range: impl Into<TextRange>) -> bool {
+        self.select_range(range.into()) && self.delete_selection(false)
+    }
+
+    /// Replace the text in the range with the string. The range is clamped into the text and is given as a
+    /// [`TextRange`] or a pair of `(row, col)` positions. This is the same as [`TextArea::delete_range`] followed by
+    /// [`TextArea::insert_str`], so links in the range are deleted and links after it are moved, but the replacement
+    /// is undone as one edit. The cursor moves to the end of the inserted string. This method returns if the text was
+    /// modified or not.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello world"]);
+    ///
+    /// assert!(textarea.replace_range(((0, 6), (0, 11)), "there"));
+    /// assert_eq!(textarea.lines(), ["hello there"]);
+    ///
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["hello world"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn replace_range<S: AsRef<str>>(&mut self, range: impl Into<TextRange>, s: S) -> bool {
+        let range = range.into();
+        let links_before = if self.history.is_grouping() {
+            None
+        } else {
+            self.history.begin_group();
+            Some(self.links.clone())
+        };
+        let deleted = self.delete_range(range);
+        if !deleted {
+            self.cursor = self.clamp_position(range.start);
+        }
+        let cursor = self.cursor;
+        let inserted = self.insert_str(s, cursor);
+        if let Some(links_before) = links_before {
+            self.history.end_group(links_before, self.links.clone());
+        }
+        deleted || inserted
+    }
+
+    /// Set text alignment. When [`Alignment::Center`] or [`Alignment::Right`] is set, line number is automatically
+    /// disabled because those alignments don't work well with line numbers.
+    /// ```
+    /// use ratatui::layout::Alignment;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_alignment(Alignment::Center);
+    /// assert_eq!(textarea.alignment(), Alignment::Center);
+    /// ```
+    pub fn set_alignment(&mut self, alignment: Alignment) {
+        if let Alignment::Center | Alignment::Right = alignment {
+            self.line_number_style = None;
+        }
+        self.alignment = alignment;
+    }
+
+    /// Get current text alignment. The default alignment is [`Alignment::Left`].
+    /// ```
+    /// use ratatui::layout::Alignment;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// assert_eq!(textarea.alignment(), Alignment::Left);
+    /// ```
+    pub fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+
+    /// Check if the textarea has a empty content.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::default();
+    /// assert!(textarea.is_empty());
+    ///
+    /// let textarea = TextArea::from(["hello"]);
+    /// assert!(!textarea.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.lines == [""]
+    }
+
+    /// Get the yanked text. Text is automatically yanked when deleting strings by [`TextArea::delete_line_by_head`],
+    /// [`TextArea::delete_line_by_end`], [`TextArea::delete_word`], [`TextArea::delete_next_word`],
+    /// [`TextArea::delete_str`], [`TextArea::copy`], and [`TextArea::cut`]. When multiple lines were yanked, they are
+    /// always joined with `\n`.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["abc"]);
+    /// textarea.delete_next_word();
+    /// assert_eq!(textarea.yank_text(), "abc");
+    ///
+    /// // Multiple lines are joined with \n
+    /// let mut textarea = TextArea::from(["abc", "def"]);
+    /// textarea.delete_str(5);
+    /// assert_eq!(textarea.yank_text(), "abc\nd");
+    /// ```
+    pub fn yank_text(&self) -> String {
+        self.yank.to_string()
+    }
+
+    /// Get the texts in the yank history. The last yanked text comes first. Multiple lines are joined with `\n` as
+    /// [`TextArea::yank_text`] does.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["abc def"]);
+    ///
+    /// textarea.delete_next_word();
+    /// textarea.delete_next_word();
+    /// assert_eq!(textarea.yank_history(), [" def", "abc"]);
+    /// ```
+    pub fn yank_history(&self) -> Vec<String> {
+        self.yank_history.iter().map(|y| y.to_string()).collect()
+    }
+
+    /// Set the maximum number of yanked texts kept in the yank history. The default is 10. Setting 0 disables the
+    /// yank history.
+    pub fn set_yank_history_size(&mut self, size: usize) {
+        self.yank_history_size = size;
+        self.yank_history.truncate(size);
+    }
+
+    /// Get the maximum number of yanked texts kept in the yank history. See [`TextArea::set_yank_history_size`].
+    pub fn yank_history_size(&self) -> usize {
+        self.yank_history_size
+    }
+
+    fn push_yank(&mut self, yank: YankText) {
+        if self.yank_history_size > 0 && !yank.to_string().is_empty() {
+            self.yank_history.push_front(yank.clone());
+            self.yank_history.truncate(self.yank_history_size);
+        }
+        self.yank = yank;
+        self.yank_index = 0;
+    }
+
+    /// Set a yanked text. The text can be inserted by [`TextArea::paste`]. `\n` and `\r\n` are recognized as newline
+    /// but `\r` isn't.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// textarea.set_yank_text("hello\nworld");
+    /// textarea.paste();
+    /// assert_eq!(textarea.lines(), ["hello", "world"]);
+    /// ```
+    pub fn set_yank_text(&mut self, text: impl Into<String>) {
+        // `str::lines` is not available since it strips a newline at end
+        let lines: Vec<_> = text
+            .into()
+            .split('\n')
+            .map(|s| s.strip_suffix('\r').unwrap_or(s).to_string())
+            .collect();
+        self.set_yank(YankContent::Chunk {
+            lines,
+            links: vec![],
+            keys: HashMap::new(),
+            origin: (0, 0),
+        });
+    }
+
+    /// Get the content of the yank buffer with the links in it. The origin of a piece deleted forward such as by
+    /// [`TextArea::delete_next_word`] is the position just after the deleted text in the line before the deletion.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tuipaz_textarea::{TextArea, YankContent};
+    ///
+    /// let mut textarea = TextArea::from(["abc def"]);
+    /// textarea.delete_next_word();
+    ///
+    /// assert_eq!(
+    ///     textarea.yank(),
+    ///     YankContent::Piece { text: "abc".to_string(), links: vec![], keys: HashMap::new(), origin: (0, 3) },
+    /// );
+    /// ```
+    pub fn yank(&self) -> YankContent {
+        let mut content: YankContent = self.yank.clone().into();
+        let (links, keys) = match &mut content {
+            YankContent::Piece { links, keys, .. }
+            | YankContent::Chunk { links, keys, .. }
+            | YankContent::Lines { links, keys, .. } => (links, keys),
+        };
+        for link in links.iter() {
+            let key = self.link_keys.key(link.id).or_else(|| self.yank_keys.get(&link.id).map(String::as_str));
+            if let Some(key) = key {
+                keys.insert(link.id, key.to_string());
+            }
+        }
+        content
+    }
+
+    /// Set the content of the yank buffer. Yank content taken from another textarea with [`TextArea::yank`] can be
+    /// pasted with its links by [`TextArea::paste`]. The pasted links take over their stable keys unless other links
+    /// in this textarea have them.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut src = TextArea::from(["abc", "def"]);
+    /// src.delete_str(5);
+    ///
+    /// let mut dst = TextArea::default();
+    /// dst.set_yank(src.yank());
+    /// dst.paste();
+    /// assert_eq!(dst.lines(), ["abc", "d"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn set_yank(&mut self, mut content: YankContent) {
+        // IDs of the links are reserved in this textarea so that pasting them never restores its own deleted links
+        let (links, keys) = match &mut content {
+            YankContent::Piece { links, keys, .. }
+            | YankContent::Chunk { links, keys, .. }
+            | YankContent::Lines { links, keys, .. } => (links, keys),
+        };
+        for link in links.iter_mut() {
+            if let Some(key) = keys.remove(&link.id) {
+                self.yank_keys.insert(self.next_link_id, key);
+            }
+            link.id = self.next_link_id;
+            self.next_link_id += 1;
+        }
+        self.push_yank(content.into());
+    }
+
+    /// Set a regular expression pattern for text search. Setting an empty string stops the text search.
+    /// When a valid pattern is set, all matches will be highlighted in the textarea. Note that the cursor does not
+    /// move. To move the cursor, use [`TextArea::search_forward`] and [`TextArea::search_back`].
+    ///
+    /// Grammar of regular expression follows [regex crate](https://docs.rs/regex/latest/regex). Patterns don't match
+    /// to newlines so match passes across no newline.
+    ///
+    /// When the pattern is invalid, the search pattern will not be updated and an error will be returned.
+    ///
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello, world", "goodbye, world"]);
+    ///
+    /// // Search "world"
+    /// textarea.set_search_pattern("world").unwrap();
+    ///
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    /// textarea.search_forward(false);
+    /// assert_eq!(textarea.cursor(), (0, 7));
+    /// textarea.search_forward(false);
+    /// assert_eq!(textarea.cursor(), (1, 9));
+    ///
+    /// // Stop the text search
+    /// textarea.set_search_pattern("");
+    ///
+    /// // Invalid search pattern
+    /// assert!(textarea.set_search_pattern("(hello").is_err());
+    /// ```
+
+    pub fn set_search_pattern(&mut self, query: impl AsRef<str>) -> Result<(), regex::Error> {
+        self.search.set_pattern(query.as_ref())
+    }
+
+    /// Get a regular expression which was set by [`TextArea::set_search_pattern`]. When no text search is ongoing, this
+    /// method returns `None`.
+    ///
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// assert!(textarea.search_pattern().is_none());
+    /// textarea.set_search_pattern("hello+").unwrap();
+    /// assert!(textarea.search_pattern().is_some());
+    /// assert_eq!(textarea.search_pattern().unwrap().as_str(), "hello+");
+    /// ```
+
+    pub fn search_pattern(&self) -> Option<&regex::Regex> {
+        self.search.pat.as_ref()
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search.clear_pattern();
+    }
+
+    /// Highlight all matches of the regular expression pattern with the style, independently of the text search set
+    /// by [`TextArea::set_search_pattern`]. Patterns are registered by the key, so several patterns can be highlighted
+    /// at once and each one is replaced or removed by its key. Matches of the text search are drawn on top of them.
+    /// Setting an empty pattern removes the pattern of the key.
+    ///
+    /// When the pattern is invalid, the pattern of the key is not updated and an error is returned.
+    /// ```
+    /// use ratatui::style::{Color, Style};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["TODO: fix", "FIXME later"]);
+    ///
+    /// textarea.add_highlight_pattern("todo", "TODO|FIXME", Style::default().bg(Color::Yellow)).unwrap();
+    /// textarea.set_search_pattern("fix").unwrap();
+    /// assert_eq!(textarea.highlight_pattern("todo").unwrap().as_str(), "TODO|FIXME");
+    ///
+    /// assert!(textarea.remove_highlight_pattern("todo"));
+    /// assert!(textarea.highlight_pattern("todo").is_none());
+    /// ```
+    pub fn add_highlight_pattern(
+        &mut self,
+        key: impl Into<String>,
+        pattern: impl AsRef<str>,
+        style: Style,
+    ) -> Result<(), regex::Error> {
+        let key = key.into();
+        if pattern.as_ref().is_empty() {
+            self.highlight_patterns.remove(&key);
+            return Ok(());
+        }
+        let mut search = Search::default();
+        search.set_pattern(pattern.as_ref())?;
+        search.style = style;
+        self.highlight_patterns.insert(key, search);
+        Ok(())
+    }
+
+    /// Get the highlight pattern registered with the key by [`TextArea::add_highlight_pattern`].
+    pub fn highlight_pattern(&self, key: &str) -> Option<&regex::Regex> {
+        self.highlight_patterns.get(key)?.pat.as_ref()
+    }
+
+    /// Remove the highlight pattern registered with the key. This method returns if the pattern was registered.
+    pub fn remove_highlight_pattern(&mut self, key: &str) -> bool {
+        self.highlight_patterns.remove(key).is_some()
+    }
+
+    /// Remove all highlight patterns registered by [`TextArea::add_highlight_pattern`].
+    pub fn clear_highlight_patterns(&mut self) {
+        self.highlight_patterns.clear();
+    }
+
+    pub fn clear_lines(&mut self) {
+        self.lines = vec!["".to_owned()];
+        self.line_meta.clear();
+        self.soft_breaks.clear();
+        self.wrap_breaks.clear();
+        self.paragraph_ids.clear();
+        self.paragraph_ids.assign(0);
+        self.anchors.clamp(&self.lines);
+    }
+
+    /// Attach a metadata value to the line at the row, such as an ID of the paragraph or a mark that the line came from
+    /// a sync. The value moves with the line when lines are inserted or deleted above it, including by undo and redo.
+    /// It is dropped when the line is deleted or joined into the previous line, and undo puts it back. When a line is
+    /// split, the value stays with the first part unless the line is split at its head. Returns the previous value of
+    /// the line.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["a", "b"]);
+    /// textarea.set_line_meta(1, "synced");
+    ///
+    /// textarea.insert_newline();
+    /// assert_eq!(textarea.line_meta(2), Some("synced"));
+    /// textarea.undo();
+    /// assert_eq!(textarea.line_meta(1), Some("synced"));
+    /// ```
+    pub fn set_line_meta(&mut self, row: usize, value: impl Into<String>) -> Option<String> {
+        self.line_meta.insert(row, value.into())
+    }
+
+    /// Get the metadata value of the line at the row. See [`TextArea::set_line_meta`].
+    pub fn line_meta(&self, row: usize) -> Option<&str> {
+        self.line_meta.get(&row).map(String::as_str)
+    }
+
+    /// Remove the metadata value of the line at the row and return it.
+    pub fn remove_line_meta(&mut self, row: usize) -> Option<String> {
+        self.line_meta.remove(&row)
+    }
+
+    /// Get the rows and metadata values of all lines which have a value, in order of rows.
+    pub fn line_metas(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.line_meta.iter().map(|(row, value)| (*row, value.as_str()))
+    }
+
+    /// Get the paragraphs in the text in order of rows. See [`ParagraphId`] for what a paragraph is. Each paragraph has
+    /// an ID which stays with it while the text is edited, so that sync layers such as a commenting system can address
+    /// paragraphs instead of row numbers which change on every line insertion. When a paragraph is split by a newline,
+    /// the first part keeps the ID and the rest gets a new ID. When a paragraph is joined into the previous one, its ID
+    /// and metadata are dropped.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["a", "b"]);
+    /// let id = textarea.paragraphs()[1].id;
+    ///
+    /// textarea.insert_newline();
+    /// let paragraphs = textarea.paragraphs();
+    /// assert_eq!(paragraphs.len(), 3);
+    /// assert_eq!(paragraphs[2].id, id);
+    /// assert_eq!(paragraphs[2].rows, 2..3);
+    /// ```
+    pub fn paragraphs(&self) -> Vec<Paragraph> {
+        let mut paragraphs: Vec<Paragraph> = vec![];
+        for row in 0..self.lines.len() {
+            match self.paragraph_ids.get(row) {
+                Some(id) if self.is_paragraph_start(row) => paragraphs.push(self.paragraph_from(id, row)),
+                _ => {
+                    if let Some(last) = paragraphs.last_mut() {
+                        last.rows.end = row + 1;
+                    }
+                }
+            }
+        }
+        paragraphs
+    }
+
+    /// Get the paragraph containing the position `(row, col)`, such as the cursor position. `None` is returned when
+    /// the row is out of the text.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::from(["a", "b"]);
+    /// let paragraph = textarea.paragraph_at(textarea.cursor()).unwrap();
+    /// assert_eq!(paragraph.rows, 0..1);
+    /// assert_eq!(textarea.paragraph_at((2, 0)), None);
+    /// ```
+    pub fn paragraph_at(&self, pos: impl Into<Position>) -> Option<Paragraph> {
+        let row = pos.into().row;
+        if row >= self.lines.len() {
+            return None;
+        }
+        let start = (0..=row).rev().find(|r| self.is_paragraph_start(*r)).unwrap_or(0);
+        let id = self.paragraph_ids.get(start)?;
+        Some(self.paragraph_from(id, start))
+    }
+
+    /// Get the paragraph of the ID. `None` is returned when the paragraph no longer exists.
+    pub fn paragraph(&self, id: ParagraphId) -> Option<Paragraph> {
+        let row = self.paragraph_ids.row(id)?;
+        Some(self.paragraph_from(id, row))
+    }
+
+    /// Attach a metadata value to the paragraph, such as an ID of a comment thread. The value stays with the paragraph
+    /// as its ID does and is returned in [`Paragraph::meta`]. The previous value is returned. Nothing is set and `None`
+    /// is returned when the paragraph no longer exists.
+    pub fn set_paragraph_meta(&mut self, id: ParagraphId, value: impl Into<String>) -> Option<String> {
+        self.paragraph_ids.row(id)?;
+        self.paragraph_ids.set_meta(id, value.into())
+    }
+
+    /// Remove the metadata value of the paragraph and return it.
+    pub fn remove_paragraph_meta(&mut self, id: ParagraphId) -> Option<String> {
+        self.paragraph_ids.remove_meta(id)
+    }
+
+    fn is_paragraph_start(&self, row: usize) -> bool {
+        row == 0 || !(self.soft_breaks.contains(&row) || self.wrap_breaks.contains(&row))
+    }
+
+    fn paragraph_from(&self, id: ParagraphId, start: usize) -> Paragraph {
+        let end = (start + 1..self.lines.len())
+            .find(|r| self.is_paragraph_start(*r))
+            .unwrap_or(self.lines.len());
+        Paragraph {
+            id,
+            rows: start..end,
+            meta: self.paragraph_ids.meta(id).map(String::from),
+        }
+    }
+
+    // Give IDs to the rows in the range which start paragraphs and drop IDs of the rows which continue them
+    // Segments joined by an undone or redone edit come back as segments, not as real lines
+    fn restore_soft_breaks(&mut self, edit: &Edit) {
+        if let Some(soft_breaks) = edit.soft_breaks_after() {
+            let changed: Vec<_> = self.soft_breaks.symmetric_difference(soft_breaks).copied().collect();
+            self.soft_breaks = soft_breaks.clone();
+            for row in changed {
+                self.sync_paragraph_ids(row..row + 1);
+            }
+        }
+    }
+
+    #[allow(deprecated)]
+    fn live_link_ids(&self) -> BTreeSet<usize> {
+        self.links.values().filter(|l| !l.deleted).map(|l| l.id).collect()
+    }
+
+    // Links deleted by an undone or redone edit are reported in `deleted_link_ids`, and links restored by it are
+    // taken back from there
+    #[allow(deprecated)]
+    fn sync_deleted_link_ids(&mut self, live_before: &BTreeSet<usize>) {
+        let live_after = self.live_link_ids();
+        for id in live_before.difference(&live_after) {
+            if !self.deleted_link_ids.contains(id) {
+                self.deleted_link_ids.push(*id);
+            }
+        }
+        let restored: Vec<_> = live_after.difference(live_before).copied().collect();
+        self.deleted_link_ids.retain(|id| !restored.contains(id));
+    }
+
+    // Links restored by an edit stay marked as edited until the links are shifted after it. Edits which don't shift
+    // links clear the marks here so that the restored links shift with the following edits
+    #[allow(deprecated)]
+    fn clear_link_edit_marks(&mut self) {
+        for l in self.links.values_mut().filter(|l| l.edited) {
+            l.toggle_edited();
+        }
+    }
+
+    // Keys carried by links moved by an undone or redone edit go back to the links which had them
+    fn restore_link_keys(&mut self, edit: &Edit) {
+        if let Some(keys) = edit.link_keys_after() {
+            self.link_keys.restore(keys);
+        }
+    }
+
+    fn sync_paragraph_ids(&mut self, rows: Range<usize>) {
+        for row in rows.start..cmp::min(rows.end, self.lines.len()) {
+            if self.is_paragraph_start(row) {
+                self.paragraph_ids.assign(row);
+            } else {
+                self.paragraph_ids.remove(row);
+            }
+        }
+    }
+
+    /// Create an anchor at the position `(row, col)`. The anchor moves with the text around it as the text is edited,
+    /// including by undo and redo, so it can remember a place such as "where the TODO was". When the text at the
+    /// anchor is deleted, the anchor moves to the start of the deletion. The position is clamped to the text.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["TODO: write"]);
+    /// let anchor = textarea.create_anchor((0, 6));
+    ///
+    /// textarea.insert_str("- ", (0, 0));
+    /// assert_eq!(textarea.anchor_pos(anchor), Some((0, 8)));
+    /// ```
+    pub fn create_anchor(&mut self, pos: impl Into<Position>) -> AnchorId {
+        let Position { row, col } = pos.into();
+        let row = cmp::min(row, self.lines.len() - 1);
+        let col = cmp::min(col, self.lines[row].chars().count());
+        self.anchors.create((row, col))
+    }
+
+    /// Get the current position `(row, col)` of the anchor. `None` is returned when the anchor was removed.
+    pub fn anchor_pos(&self, id: AnchorId) -> Option<(usize, usize)> {
+        self.anchors.get(id)
+    }
+
+    /// Remove the anchor. Returns `false` when the anchor does not exist.
+    pub fn remove_anchor(&mut self, id: AnchorId) -> bool {
+        self.anchors.remove(id)
+    }
+
+    /// Get all anchors and their current positions.
+    pub fn anchors(&self) -> Vec<(AnchorId, (usize, usize))> {
+        self.anchors.iter().collect()
+    }
+
+    // Update the index of link rows for the links added or moved by undo or redo. Edits replacing the links at once
+    // invalidate the index
+    #[allow(deprecated)]
+    fn track_link_rows(&mut self, edit: &Edit) {
+        match edit.moved_links() {
+            Some(moved) => {
+                for (from, link) in moved {
+                    match from {
+                        Some(from) => self.link_rows.moved(link.id, from, link.row),
+                        None => self.link_rows.insert(&self.links[&link.id]),
+                    }
+                }
+            }
+            None => self.link_rows.invalidate(),
+        }
+    }
+
+    // Move the positions which track the text after the edit was applied. Returns the metadata of the lines removed by
+    // the edit
+    fn track_edit(&mut self, edit: &Edit) -> BTreeMap<usize, String> {
+        self.modified = true;
+        self.revision += 1;
+        self.auto_closers.clear();
+        let removed_meta = edit.shift_line_meta(&mut self.line_meta);
+        let shifts = edit.row_shifts();
+        let (before, after) = (edit.cursor_before().0, edit.cursor_after().0);
+        let edited = (cmp::min(before, after), cmp::max(before, after));
+        self.search.invalidate(edited, &shifts);
+        for pattern in self.highlight_patterns.values_mut() {
+            pattern.invalidate(edited, &shifts);
+        }
+        self.line_cache.track(edited, &shifts, self.lines.len());
+        shift_rows(&mut self.soft_breaks, &shifts);
+        let len = self.lines.len();
+        self.soft_breaks.retain(|row| *row < len);
+        shift_rows(&mut self.wrap_breaks, &shifts);
+        self.wrap_breaks.retain(|row| *row < len);
+        // IDs move with the text of paragraphs split or joined at their heads
+        let text_shifts = edit.text_row_shifts();
+        self.paragraph_ids.shift(&text_shifts);
+        // Rows inserted by the edit get new IDs
+        let first = text_shifts.iter().map(|s| s.0).fold(edited.0, cmp::min);
+        let last = text_shifts
+            .iter()
+            .map(|&(at, delta)| at + cmp::max(delta, 0) as usize)
+            .fold(edited.1 + 1, cmp::max);
+        self.sync_paragraph_ids(first..last);
+        self.anchors.shift(edit);
+        self.anchors.clamp(&self.lines);
+        self.changes.shift(edit);
+        self.changes.clamp(&self.lines);
+        if let Some(pending) = &mut self.pending_insert {
+            pending.pos = edit.shift_pos(pending.pos);
+        }
+        // Byte ranges of hop targets are stale after the edit
+        if self.hop.is_active() {
+            self.label_hop_targets();
+        }
+        if let Some(autosave) = &mut self.autosave {
+            autosave.touch(Instant::now());
+        }
+        #[cfg(feature = "journal")]
+        if let Some(journal) = &mut self.journal {
+            journal.append(edit);
+        }
+        removed_meta
+    }
+
+    /// Get the position `(row, col)` of the most recent edit, which is the cursor position just after the edit. The
+    /// position moves with the text as anchors do.
+    pub fn last_change_pos(&self) -> Option<(usize, usize)> {
+        self.changes.last()
+    }
+
+    /// Move the cursor to the position of the most recent edit, like `g;` in Vim. Calling this method again moves the
+    /// cursor to older edits in turn. The position of an edit is the cursor position just after it, so the cursor
+    /// lands after inserted text. Successive edits in the same line are remembered as one change at the position of
+    /// the latest of them. Returns `false` when there is no older change.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["abc", "def", "ghi"]);
+    /// textarea.insert_char('x');
+    /// // The cursor keeps its column (0, 1) and moves to (2, 1)
+    /// textarea.move_cursor(CursorMove::Bottom);
+    /// textarea.insert_char('y');
+    /// textarea.move_cursor(CursorMove::Jump(1, 1));
+    ///
+    /// assert!(textarea.goto_last_change());
+    /// assert_eq!(textarea.cursor(), (2, 2));
+    /// assert!(textarea.goto_last_change());
+    /// assert_eq!(textarea.cursor(), (0, 1));
+    /// assert!(!textarea.goto_last_change());
+    /// assert!(textarea.goto_next_change());
+    /// assert_eq!(textarea.cursor(), (2, 2));
+    /// ```
+    pub fn goto_last_change(&mut self) -> bool {
+        match self.changes.older() {
+            Some((row, col)) => {
+                self.move_cursor(CursorMove::Jump(row as u16, col as u16));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the cursor to the next newer edit in the change list, like `g,` in Vim. See
+    /// [`TextArea::goto_last_change`]. Returns `false` when there is no newer change.
+    pub fn goto_next_change(&mut self) -> bool {
+        match self.changes.newer() {
+            Some((row, col)) => {
+                self.move_cursor(CursorMove::Jump(row as u16, col as u16));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the callback to autosave the text. Every edit, undo and redo makes the text dirty, and the callback is
+    /// called with the lines of the text on [`TextArea::tick`] once the text is dirty and has not been edited for the
+    /// interval, so that the debounce logic does not need to live in the host.
+    /// ```
+    /// use std::time::Duration;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// let path = std::env::temp_dir().join("note.md");
+    /// textarea.set_autosave(Duration::from_secs(2), move |lines| {
+    ///     std::fs::write(&path, lines.join("\n")).unwrap();
+    /// });
+    ///
+    /// textarea.insert_char('a');
+    /// assert!(textarea.autosave_pending());
+    ///
+    /// // Not idle for 2 seconds yet
+    /// textarea.tick();
+    /// assert!(textarea.autosave_pending());
+    /// ```
+    pub fn set_autosave<F>(&mut self, interval: Duration, callback: F)
+    where
+        F: Fn(&[String]) + Send + Sync + 'static,
+    {
+        self.autosave = Some(Autosave::new(interval, callback));
+    }
+
+    /// Set the handler called when an operation cannot proceed, such as moving the cursor past the edge of the text,
+    /// searching a pattern without matches, or undoing with an empty history. Hosts can flash the block border or
+    /// ring the terminal bell instead of failing silently. See [`BellReason`] for the reasons.
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use tuipaz_textarea::{BellReason, CursorMove, TextArea};
+    ///
+    /// let rung = Arc::new(Mutex::new(vec![]));
+    /// let r = rung.clone();
+    ///
+    /// let mut textarea = TextArea::from(["abc"]);
+    /// textarea.set_bell_handler(move |reason| r.lock().unwrap().push(reason));
+    ///
+    /// textarea.move_cursor(CursorMove::Up);
+    /// textarea.undo();
+    /// assert_eq!(*rung.lock().unwrap(), [BellReason::BufferEdge, BellReason::NothingToUndo]);
+    /// ```
+    pub fn set_bell_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(BellReason) + Send + Sync + 'static,
+    {
+        self.bell = Bell::new(handler);
+    }
+
+    /// Remove the handler set by [`TextArea::set_bell_handler`].
+    pub fn remove_bell_handler(&mut self) {
+        self.bell = Bell::default();
+    }
+
+    /// Set the observer called with the [`EditorState`] when the file name, the cursor position or the text changed.
+    /// Changes are debounced: the observer is called on [`TextArea::tick`] after the state stayed the same for the
+    /// interval, so that a burst of typing or cursor moves is reported once. This is useful to update the terminal
+    /// title or emit OSC sequences from the host.
+    /// ```
+    /// use std::time::Duration;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    /// textarea.set_file_name("note.md");
+    /// textarea.set_state_observer(Duration::from_millis(200), |state| {
+    ///     let name = state.file_name.as_deref().unwrap_or("");
+    ///     let modified = if state.modified { "*" } else { "" };
+    ///     // Set the terminal title with OSC 2
+    ///     print!("\x1b]2;{}{} {}:{}\x07", name, modified, state.cursor.0 + 1, state.cursor.1 + 1);
+    /// });
+    /// ```
+    pub fn set_state_observer<F>(&mut self, interval: Duration, callback: F)
+    where
+        F: Fn(&EditorState) + Send + Sync + 'static,
+    {
+        self.state_observer = Some(StateObserver::new(interval, callback));
+    }
+
+    /// Remove the observer set by [`TextArea::set_state_observer`].
+    pub fn remove_state_observer(&mut self) {
+        self.state_observer = None;
+    }
+
+    /// Get the current state of the editor reported to the observer set by [`TextArea::set_state_observer`].
+    pub fn editor_state(&self) -> EditorState {
+        EditorState {
+            file_name: self.file_name.clone(),
+            cursor: self.cursor,
+            lines: self.lines.len(),
+            modified: self.modified,
+            revision: self.revision,
+        }
+    }
+
+    /// Remove the callback set by [`TextArea::set_autosave`] without calling it.
+    pub fn remove_autosave(&mut self) {
+        self.autosave = None;
+    }
+
+    /// Call the autosave callback immediately when the text is dirty, e.g. before quitting the application. Returns
+    /// `true` when the callback was called.
+    pub fn flush_autosave(&mut self) -> bool {
+        match &mut self.autosave {
+            Some(autosave) => autosave.flush(&self.lines),
+            None => false,
+        }
+    }
+
+    /// Return `true` when the text was edited after the last autosave.
+    pub fn autosave_pending(&self) -> bool {
+        self.autosave.as_ref().map_or(false, |a| a.is_dirty())
+    }
+
+    /// Start appending every edit, undo and redo to the journal file at the path so that the text can be recovered
+    /// with [`crate::recover_from_journal`] after the application crashed. The file is created when it does not exist.
+    /// The journal only records the changes to the text since it was started, so call [`TextArea::reset_journal`]
+    /// each time the text is saved. Links are not recorded. A clone of the textarea does not write to the journal until
+    /// [`TextArea::reset_journal`] is called on it.
+    ///
+    /// This method is available when the `journal` feature is enabled.
+    /// ```no_run
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["saved text"]);
+    /// textarea.set_journal("note.md.journal").unwrap();
+    ///
+    /// textarea.insert_char('!');
+    /// // After saving the text to note.md
+    /// textarea.reset_journal().unwrap();
+    /// ```
+    #[cfg(feature = "journal")]
+    pub fn set_journal(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.journal = Some(Journal::open(path.as_ref())?);
+        Ok(())
+    }
+
+    /// Stop writing the journal set by [`TextArea::set_journal`]. The journal file is not removed.
+    #[cfg(feature = "journal")]
+    pub fn remove_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Truncate the journal file after the text was saved. Does nothing when no journal is set.
+    #[cfg(feature = "journal")]
+    pub fn reset_journal(&mut self) -> std::io::Result<()> {
+        match &mut self.journal {
+            Some(journal) => journal.reset(),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the path of the journal file set by [`TextArea::set_journal`].
+    #[cfg(feature = "journal")]
+    pub fn journal_path(&self) -> Option<&std::path::Path> {
+        self.journal.as_ref().map(Journal::path)
+    }
+
+    /// Get the error of writing the journal. Once writing failed, no edit is written until
+    /// [`TextArea::reset_journal`] succeeds.
+    #[cfg(feature = "journal")]
+    pub fn journal_error(&self) -> Option<&std::io::Error> {
+        self.journal.as_ref().and_then(Journal::error)
+    }
+
+    /// Search the pattern set by [`TextArea::set_search_pattern`] forward and move the cursor to the next match
+    /// position based on the current cursor position. Text search wraps around a text buffer. It returns `true` when
+    /// some match was found. Otherwise it returns `false`.
+    ///
+    /// The `match_cursor` parameter represents if the search matches to the current cursor position or not. When `true`
+    /// is set and the cursor position matches to the pattern, the cursor will not move. When `false`, the cursor will
+    /// move to the next match ignoring the match at the current position.
+    ///
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello", "helloo", "hellooo"]);
+    ///
+    /// textarea.set_search_pattern("hello+").unwrap();
+    ///
+    /// // Move to next position
+    /// let match_found = textarea.search_forward(false);
+    /// assert!(match_found);
+    /// assert_eq!(textarea.cursor(), (1, 0));
+    ///
+    /// // Since the cursor position matches to "hello+", it does not move
+    /// textarea.search_forward(true);
+    /// assert_eq!(textarea.cursor(), (1, 0));
+    ///
+    /// // When `match_current` parameter is set to `false`, match at the cursor position is ignored
+    /// textarea.search_forward(false);
+    /// assert_eq!(textarea.cursor(), (2, 0));
+    ///
+    /// // Text search wrap around the buffer
+    /// textarea.search_forward(false);
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    ///
+    /// // `false` is returned when no match was found
+    /// textarea.set_search_pattern("bye+").unwrap();
+    /// let match_found = textarea.search_forward(false);
+    /// assert!(!match_found);
+    /// ```
+
+    pub fn search_forward(&mut self, match_cursor: bool) -> bool {
+        if let Some(cursor) = self.search.forward(&self.lines, self.cursor, match_cursor) {
+            self.cursor = cursor;
+            true
+        } else {
+            self.ring_no_match();
+            false
+        }
+    }
+
+    /// Search the pattern set by [`TextArea::set_search_pattern`] backward and move the cursor to the next match
+    /// position based on the current cursor position. Text search wraps around a text buffer. It returns `true` when
+    /// some match was found. Otherwise it returns `false`.
+    ///
+    /// The `match_cursor` parameter represents if the search matches to the current cursor position or not. When `true`
+    /// is set and the cursor position matches to the pattern, the cursor will not move. When `false`, the cursor will
+    /// move to the next match ignoring the match at the current position.
+    ///
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["hello", "helloo", "hellooo"]);
+    ///
+    /// textarea.set_search_pattern("hello+").unwrap();
+    ///
+    /// // Move to next position with wrapping around the text buffer
+    /// let match_found = textarea.search_back(false);
+    /// assert!(match_found);
+    /// assert_eq!(textarea.cursor(), (2, 0));
+    ///
+    /// // Since the cursor position matches to "hello+", it does not move
+    /// textarea.search_back(true);
+    /// assert_eq!(textarea.cursor(), (2, 0));
+    ///
+    /// // When `match_current` parameter is set to `false`, match at the cursor position is ignored
+    /// textarea.search_back(false);
+    /// assert_eq!(textarea.cursor(), (1, 0));
+    ///
+    /// // `false` is returned when no match was found
+    /// textarea.set_search_pattern("bye+").unwrap();
+    /// let match_found = textarea.search_back(false);
+    /// assert!(!match_found);
+    /// ```
+
+    pub fn search_back(&mut self, match_cursor: bool) -> bool {
+        if let Some(cursor) = self.search.back(&self.lines, self.cursor, match_cursor) {
+            self.cursor = cursor;
+            true
+        } else {
+            self.ring_no_match();
+            false
+        }
+    }
+
+    fn ring_no_match(&self) {
+        if self.search.pat.is_some() {
+            self.bell.ring(BellReason::NoMatch);
+        }
+    }
+
+    /// Replace the next match of the pattern set by [`TextArea::set_search_pattern`] at or after the cursor with the
+    /// replacement. `$1` or `${name}` in the replacement is substituted with the capture group, and `$$` is a literal
+    /// `$`. The search wraps around the text as [`TextArea::search_forward`] does. The cursor moves to the end of the
+    /// replaced text so that calling this method again replaces the following match. Links in the replaced text are
+    /// deleted and links after it are shifted. The replacement is undone as one edit. This method returns if some
+    /// match was replaced.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["x = 1", "y = 22"]);
+    /// textarea.set_search_pattern(r"(\w) = (\d+)").unwrap();
+    ///
+    /// assert!(textarea.replace_next("$1: $2"));
+    /// assert_eq!(textarea.lines(), ["x: 1", "y = 22"]);
+    /// assert!(textarea.replace_next("$1: $2"));
+    /// assert_eq!(textarea.lines(), ["x: 1", "y: 22"]);
+    /// assert!(!textarea.replace_next("$1: $2"));
+    ///
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["x: 1", "y = 22"]);
+    /// ```
+    pub fn replace_next(&mut self, replacement: impl AsRef<str>) -> bool {
+        let (row, col) = match self.search.forward(&self.lines, self.cursor, true) {
+            Some(pos) => pos,
+            None => return false,
+        };
+        let line = &self.lines[row];
+        let offset = byte_offset(line, col);
+        let (start, end, replaced) = match self.search.replacement_at(line, offset, replacement.as_ref()) {
+            Some(r) => r,
+            None => return false,
+        };
+        let end_col = col + line[start..end].chars().count();
+        self.replace_range(((row, col), (row, end_col)), replaced)
+    }
+
+    /// Replace all matches of the pattern set by [`TextArea::set_search_pattern`] with the replacement. The
+    /// replacement is substituted as [`TextArea::replace_next`] does. All replacements are undone as one edit. The
+    /// cursor stays at the same position as far as possible. This method returns the number of replaced matches.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["foo bar", "bar foo"]);
+    /// textarea.set_search_pattern("foo|bar").unwrap();
+    ///
+    /// assert_eq!(textarea.replace_all("<$0>"), 4);
+    /// assert_eq!(textarea.lines(), ["<foo> <bar>", "<bar> <foo>"]);
+    ///
+    /// textarea.undo();
+    /// assert_eq!(textarea.lines(), ["foo bar", "bar foo"]);
+    /// ```
+    #[allow(deprecated)]
+    pub fn replace_all(&mut self, replacement: impl AsRef<str>) -> usize {
+        let replacement = replacement.as_ref();
+        let cursor = self.cursor;
+        let links_before = if self.history.is_grouping() {
+            None
+        } else {
+            self.history.begin_group();
+            Some(self.links.clone())
+        };
+
+        // Replace from the end of the text so that the positions of the remaining matches do not move
+        let mut count = 0;
+        for row in (0..self.lines.len()).rev() {
+            let line = &self.lines[row];
+            let replacements: Vec<_> = self
+                .search
+                .replacements(line, 0, replacement)
+                .into_iter()
+                .map(|(start, end, replaced)| {
+                    let col = line[..start].chars().count();
+                    (col, col + line[start..end].chars().count(), replaced)
+                })
+                .collect();
+            for (start, end, replaced) in replacements.into_iter().rev() {
+                if self.replace_range(((row, start), (row, end)), replaced) {
+                    count += 1;
+                }
+            }
+        }
+
+        if let Some(links_before) = links_before {
+            self.history.end_group(links_before, self.links.clone());
+        }
+        self.cursor = self.clamp_pos(cursor);
+        count
+    }
+
+    /// Enable or disable previewing matches of incremental search. When it is enabled,
+    /// [`TextArea::preview_search`] scrolls the viewport to the first match without moving the cursor. It is disabled
+    /// by default.
+    pub fn set_search_preview(&mut self, enabled: bool) {
+        self.search_preview = enabled;
+        if !enabled {
+            self.preview = None;
+        }
+    }
+
+    /// Check if matches of incremental search are previewed. See [`TextArea::set_search_preview`].
+    pub fn search_preview(&self) -> bool {
+        self.search_preview
+    }
+
+    /// Set the search pattern while the query is typed. When search preview is enabled, the viewport follows the first
+    /// match at or after the cursor while the cursor stays where it is. Finish the search with
+    /// [`TextArea::commit_search_preview`] or [`TextArea::cancel_search_preview`]. This method returns if some match
+    /// was found. When the pattern is invalid, the error is returned and the preview is not changed.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["foo", "bar", "baz"]);
+    /// textarea.set_search_preview(true);
+    ///
+    /// assert!(textarea.preview_search("ba").unwrap());
+    /// assert_eq!(textarea.search_preview_position(), Some((1, 0)));
+    /// assert!(textarea.preview_search("baz").unwrap());
+    /// assert_eq!(textarea.search_preview_position(), Some((2, 0)));
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    ///
+    /// assert!(textarea.commit_search_preview());
+    /// assert_eq!(textarea.cursor(), (2, 0));
+    /// ```
+    pub fn preview_search(&mut self, query: impl AsRef<str>) -> Result<bool, regex::Error> {
+        self.set_search_pattern(query)?;
+        let found = self.search.forward(&self.lines, self.cursor, true);
+        if self.search_preview {
+            let scroll_top = match self.preview {
+                Some(preview) => preview.scroll_top,
+                None => self.viewport.scroll_top(),
+            };
+            // Start from the original view so that the viewport does not drift while the query is typed
+            self.viewport.set_scroll_top(scroll_top.0, scroll_top.1);
+            self.preview = Some(SearchPreview { pos: found, scroll_top });
+        }
+        Ok(found.is_some())
+    }
+
+    /// Get the position of the match previewed by [`TextArea::preview_search`]. `None` is returned when no match is
+    /// previewed.
+    pub fn search_preview_position(&self) -> Option<(usize, usize)> {
+        self.preview.and_then(|p| p.pos)
+    }
+
+    /// Finish the incremental search by moving the cursor to the previewed match. The search pattern is kept. This
+    /// method returns if the cursor moved or not.
+    pub fn commit_search_preview(&mut self) -> bool {
+        match self.preview.take().and_then(|p| p.pos) {
+            Some(pos) => {
+                self.cancel_selection();
+                self.cursor = pos;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel the incremental search. The search pattern is cleared and the viewport goes back to the view before
+    /// the preview started.
+    /// ```
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::from(["foo", "bar"]);
+    /// textarea.set_search_preview(true);
+    ///
+    /// textarea.preview_search("bar").unwrap();
+    /// textarea.cancel_search_preview();
+    /// assert_eq!(textarea.search_preview_position(), None);
+    /// assert!(textarea.search_pattern().is_none());
+    /// assert_eq!(textarea.cursor(), (0, 0));
+    /// ```
+    pub fn cancel_search_preview(&mut self) {
+        if let Some(SearchPreview { scroll_top: (row, col), .. }) = self.preview.take() {
+            self.viewport.set_scroll_top(row, col);
+        }
+        self.search.clear_pattern();
+    }
+
+    // Position which the viewport follows. It is the previewed match during incremental search, otherwise the cursor
+    pub(crate) fn view_anchor(&self) -> (usize, usize) {
+        self.search_preview_position().unwrap_or(self.cursor)
+    }
+
+    /// Get the text style at matches of text search. The default style is colored with blue in background.
+    ///
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let textarea = TextArea::default();
+    ///
+    /// assert_eq!(textarea.search_style(), Style::default().bg(Color::Blue));
+    /// ```
+
+    pub fn search_style(&self) -> Style {
+        self.search.style
+    }
+
+    /// Set the text style at matches of text search. The default style is colored with blue in background.
+    ///
+    /// ```
+    /// use ratatui::style::{Style, Color};
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea = TextArea::default();
+    ///
+    /// let red_bg = Style::default().bg(Color::Red);
+    /// textarea.set_search_style(red_bg);
+    ///
+    /// assert_eq!(textarea.search_style(), red_bg);
+    /// ```
+
+    pub fn set_search_style(&mut self, style: Style) {
+        self.search.style = style;
+    }
+
+    /// Get the regular expression of the hop pattern.
+    pub fn hop_pattern(&self) -> Option<&regex::Regex> {
+        self.hop.pattern()
+    }
+
+    /// Stop hopping and clear the hop pattern and the labeled targets.
+    pub fn clear_hop(&mut self) {
+        self.hop.clear();
+    }
+
+    /// Start hopping. Matches of the hop pattern in the viewport are labeled with numbers, which can be passed to
+    /// [`TextArea::complete_hop`] to move the cursor to the match.
+    /// ```
+    /// use tuipaz_textarea::{TextArea, HopState};
+    ///
+    /// let mut textarea = TextArea::from(["foo bar", "bar foo"]);
+    /// textarea.set_hop_pattern("foo").unwrap();
+    /// textarea.init_hop();
+    /// assert_eq!(textarea.hop_state(), HopState::Labeled);
+    ///
+    /// let label = textarea.hop_targets()[1].label;
+    /// let outcome = textarea.complete_hop(label).unwrap();
+    /// assert_eq!(outcome.from, (0, 0));
+    /// assert_eq!(textarea.cursor(), (1, 4));
+    /// assert_eq!(textarea.hop_state(), HopState::Inactive);
+    /// ```
+    pub fn init_hop(&mut self) {
+        self.hop.start();
+        self.label_hop_targets();
+    }
+
+    /// Set the hop pattern as a regular expression. While hopping, the targets are labeled again.
+    pub fn set_hop_pattern(&mut self, query: impl AsRef<str>) -> Result<(), regex::Error> {
+        self.hop.set_pattern(query.as_ref())?;
+        self.label_hop_targets();
+        Ok(())
+    }
+
+    pub fn hop_style(&self) -> Style {
+        self.search.style
+    }
+
+    /// Get the current state of hopping.
+    pub fn hop_state(&self) -> HopState {
+        self.hop.state()
+    }
+
+    /// Get the labeled hop targets in order of their positions.
+    pub fn hop_targets(&self) -> &[HopTarget] {
+        self.hop.targets()
+    }
+
+    /// Move the cursor to the hop target with the label and stop hopping. When no target has the label, nothing
+    /// happens and `None` is returned.
+    pub fn complete_hop(&mut self, label: usize) -> Option<HopOutcome> {
+        let target = self.hop.target(label)?;
+        let from = self.cursor;
+        self.cursor = (target.row, target.start_col);
+        self.hop.clear();
+        Some(HopOutcome { from, target })
+    }
+
+    #[deprecated(note = "use TextArea::complete_hop instead")]
+    pub fn hop_to_idx(&mut self, idx: usize) {
+        self.complete_hop(idx);
+    }
+
+    // Label the hop targets in the rows shown in the viewport, or in all rows before the textarea is rendered
+    fn label_hop_targets(&mut self) {
+        let (row_top, _, _, height) = self.viewport.rect();
+        let rows = if height == 0 {
+            0..self.lines.len()
+        } else {
+            let top = cmp::min(row_top as usize, self.lines.len());
+            top..cmp::min(top + height as usize, self.lines.len())
+        };
+        self.hop.label(&self.lines, rows);
+    }
+
+    /// Get the scroll offset `(row, col)` of the viewport, which is the position of the top-left cell of the text
+    /// shown in the textarea.
+    pub fn viewport_offset(&self) -> (u16, u16) {
+        self.viewport.scroll_top()
+    }
+
+    /// Get the size `(width, height)` of the viewport at the last rendering. It is `(0, 0)` before the textarea is
+    /// rendered.
+    pub fn viewport_size(&self) -> (u16, u16) {
+        let (_, _, width, height) = self.viewport.rect();
+        (width, height)
+    }
+
+    /// Get how far the viewport is scrolled vertically, as a ratio from `0.0` (the first line is at the top of the
+    /// viewport) to `1.0` (the last line is in the viewport). When the whole text fits in the viewport, `1.0` is
+    /// returned. Multiply by 100 to render a percentage indicator.
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::layout::Rect;
+    /// # use ratatui::widgets::Widget;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea: TextArea = (0..20).into_iter().map(|i| i.to_string()).collect();
+    /// # let r = Rect { x: 0, y: 0, width: 24, height: 10 };
+    /// # let mut b = Buffer::empty(r.clone());
+    /// # textarea.widget().render(r, &mut b);
+    ///
+    /// assert_eq!(textarea.scroll_progress(), 0.0);
+    /// textarea.set_viewport_offset(5, 0);
+    /// assert_eq!(textarea.scroll_progress(), 0.5);
+    /// textarea.set_viewport_offset(10, 0);
+    /// assert_eq!(textarea.scroll_progress(), 1.0);
+    /// ```
+    pub fn scroll_progress(&self) -> f32 {
+        let (row, _, _, height) = self.viewport.rect();
+        let scrollable = self.lines.len().saturating_sub(height as usize);
+        if scrollable == 0 {
+            return 1.0;
+        }
+        (row as f32 / scrollable as f32).min(1.0)
+    }
+
+    /// Get the number of lines below the bottom of the viewport. It is `0` when the last line is in the viewport.
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::layout::Rect;
+    /// # use ratatui::widgets::Widget;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea: TextArea = (0..50).into_iter().map(|i| i.to_string()).collect();
+    /// # let r = Rect { x: 0, y: 0, width: 24, height: 10 };
+    /// # let mut b = Buffer::empty(r.clone());
+    /// # textarea.widget().render(r, &mut b);
+    ///
+    /// assert_eq!(textarea.lines_below_viewport(), 40);
+    /// textarea.set_viewport_offset(45, 0);
+    /// assert_eq!(textarea.lines_below_viewport(), 0);
+    /// ```
+    pub fn lines_below_viewport(&self) -> usize {
+        let (row, _, _, height) = self.viewport.rect();
+        self.lines.len().saturating_sub(row as usize + height as usize)
+    }
+
+    /// Scroll the viewport so that the cursor line is placed at the center, the top or the bottom of the viewport, like
+    /// Vim's `zz`, `zt` and `zb`. The cursor does not move. The horizontal scroll position is not changed.
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::layout::Rect;
+    /// # use ratatui::widgets::Widget;
+    /// use tuipaz_textarea::{CursorMove, Placement, TextArea};
+    ///
+    /// let mut textarea: TextArea = (0..50).into_iter().map(|i| i.to_string()).collect();
+    /// # let r = Rect { x: 0, y: 0, width: 24, height: 10 };
+    /// # let mut b = Buffer::empty(r.clone());
+    /// # textarea.widget().render(r, &mut b);
+    ///
+    /// textarea.move_cursor(CursorMove::Jump(20, 0));
+    ///
+    /// textarea.recenter_viewport(Placement::Center);
+    /// assert_eq!(textarea.viewport_offset(), (15, 0));
+    /// textarea.recenter_viewport(Placement::Top);
+    /// assert_eq!(textarea.viewport_offset(), (20, 0));
+    /// textarea.recenter_viewport(Placement::Bottom);
+    /// assert_eq!(textarea.viewport_offset(), (11, 0));
+    /// assert_eq!(textarea.cursor(), (20, 0));
+    /// ```
+    pub fn recenter_viewport(&mut self, placement: Placement) {
+        let (_, col, _, height) = self.viewport.rect();
+        let row = placement.scroll_top(self.cursor.0, height);
+        self.scroll_target = None;
+        self.viewport.set_scroll_top(row, col);
+    }
+
+    /// Get the screen cell `(x, y)` of the cursor when the textarea was rendered in `area` at the last rendering.
+    /// The block, line numbers, tabs and wide characters are taken into account. `None` is returned when the cursor
+    /// is outside the viewport.
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::widgets::Widget;
+    /// use ratatui::layout::Rect;
+    /// use ratatui::widgets::{Block, Borders};
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["hello", "world"]);
+    /// textarea.set_block(Block::default().borders(Borders::ALL));
+    /// textarea.move_cursor(CursorMove::Jump(1, 3));
+    ///
+    /// let area = Rect { x: 10, y: 5, width: 24, height: 8 };
+    /// # let mut b = Buffer::empty(area.clone());
+    /// # textarea.widget().render(area, &mut b);
+    /// assert_eq!(textarea.cursor_screen_position(area), Some((14, 7)));
+    /// ```
+    pub fn cursor_screen_position(&self, area: Rect) -> Option<(u16, u16)> {
+        let (inner, _, gutter) = self.render_layout(area);
+        let (top_row, top_col, width, height) = self.viewport.rect();
+        let (row, col) = self.cursor;
+        if row < top_row as usize {
+            return None;
+        }
+        let row = self.screen_rows(top_row as usize, row);
+        if row >= height as usize {
+            return None;
+        }
+
+        let mut x = match self.mask {
+            Some(mask) => col * mask.width().unwrap_or(0),
+            None => display_width(&self.lines[self.cursor.0], col, self.tab_len),
+        };
+        if gutter {
+            x += self.gutter_width() as usize;
+        }
+        let x = x.checked_sub(top_col as usize)?;
+        if x >= width as usize {
+            return None;
+        }
+        Some((inner.x + x as u16, inner.y + row as u16))
+    }
+
+    /// Compute the area of a popup of `size` `(width, height)` anchored at the cursor when the textarea was rendered in
+    /// `area`. The popup is placed below the cursor line, or above it near the bottom edge, within `area`. `None` is
+    /// returned when the cursor is outside the viewport. See [`popup_area`](crate::popup_area) for the placement rules.
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::widgets::Widget;
+    /// use ratatui::layout::Rect;
+    /// use tuipaz_textarea::{CursorMove, Popup, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["hello", "world"]);
+    /// textarea.move_cursor(CursorMove::Jump(0, 2));
+    ///
+    /// let area = Rect { x: 0, y: 0, width: 24, height: 8 };
+    /// # let mut b = Buffer::empty(area.clone());
+    /// # textarea.widget().render(area, &mut b);
+    /// let popup = Popup::new("preview");
+    /// let popup_area = textarea.popup_area(area, popup.size()).unwrap();
+    /// assert_eq!(popup_area, Rect { x: 2, y: 1, width: 7, height: 1 });
+    /// # popup.widget().render(popup_area, &mut b);
+    /// ```
+    pub fn popup_area(&self, area: Rect, size: (u16, u16)) -> Option<Rect> {
+        let anchor = self.cursor_screen_position(area)?;
+        Some(popup_area(anchor, size, area))
+    }
+
+    /// Set the scroll offset `(row, col)` of the viewport directly. This is useful for synchronizing scroll positions
+    /// of multiple panes. The offset is clamped so that it does not go beyond the last line and the longest line. The
+    /// cursor is moved into the new viewport as [`CursorMove::InViewport`] does. The applied offset is returned.
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::layout::Rect;
+    /// # use ratatui::widgets::Widget;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// let mut textarea: TextArea = (0..20).into_iter().map(|i| i.to_string()).collect();
+    /// # let r = Rect { x: 0, y: 0, width: 24, height: 8 };
+    /// # let mut b = Buffer::empty(r.clone());
+    /// # textarea.widget().render(r, &mut b);
+    ///
+    /// assert_eq!(textarea.set_viewport_offset(10, 0), (10, 0));
+    /// assert_eq!(textarea.viewport_offset(), (10, 0));
+    /// assert_eq!(textarea.cursor(), (10, 0));
+    ///
+    /// // Clamped to the last line
+    /// assert_eq!(textarea.set_viewport_offset(100, 100), (19, 2));
+    /// ```
+    pub fn set_viewport_offset(&mut self, row: u16, col: u16) -> (u16, u16) {
+        let max_row = self.lines.len() - 1;
+        let max_col = self.lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let row = cmp::min(row as usize, max_row).min(u16::MAX as usize) as u16;
+        let col = cmp::min(col as usize, max_col).min(u16::MAX as usize) as u16;
+        self.scroll_target = None;
+        self.viewport.set_scroll_top(row, col);
+        self.move_cursor(CursorMove::InViewport);
+        (row, col)
+    }
+
+    /// Set the mark `name` at the current cursor position. Setting a mark with the same name overwrites it.
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["abc", "def"]);
+    ///
+    /// textarea.move_cursor(CursorMove::Jump(1, 2));
+    /// textarea.set_mark('a');
+    /// textarea.move_cursor(CursorMove::Top);
+    ///
+    /// assert!(textarea.jump_to_mark('a'));
+    /// assert_eq!(textarea.cursor(), (1, 2));
+    /// assert!(!textarea.jump_to_mark('b'));
+    /// ```
+    pub fn set_mark(&mut self, name: char) {
+        self.marks.insert(name, self.cursor);
+    }
+
+    /// Get the position of the mark `name`.
+    pub fn mark(&self, name: char) -> Option<(usize, usize)> {
+        self.marks.get(&name).copied()
+    }
+
+    /// Get all marks ordered by their names.
+    pub fn marks(&self) -> &BTreeMap<char, (usize, usize)> {
+        &self.marks
+    }
+
+    /// Remove the mark `name`. The removed position is returned.
+    pub fn remove_mark(&mut self, name: char) -> Option<(usize, usize)> {
+        self.marks.remove(&name)
+    }
+
+    /// Move the cursor to the mark `name`. The position is clamped to the current text. This method returns `false`
+    /// when the mark is not set.
+    pub fn jump_to_mark(&mut self, name: char) -> bool {
+        if let Some(pos) = self.mark(name) {
+            self.cancel_selection();
+            self.cursor = self.clamp_pos(pos);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the current session state: the cursor, the scroll position, the selection, the marks and the search
+    /// pattern. See [`SessionState`].
+    /// ```
+    /// use tuipaz_textarea::{CursorMove, TextArea};
+    ///
+    /// let mut textarea = TextArea::from(["hello", "world"]);
+    /// textarea.move_cursor(CursorMove::Jump(1, 3));
+    /// textarea.set_mark('m');
+    /// let state = textarea.session_state();
+    ///
+    /// let mut reopened = TextArea::from(["hello", "world"]);
+    /// reopened.restore_session_state(&state).unwrap();
+    /// assert_eq!(reopened.cursor(), (1, 3));
+    /// assert_eq!(reopened.mark('m'), Some((1, 3)));
+    /// ```
+    pub fn session_state(&self) -> SessionState {
+        SessionState {
+            cursor: self.cursor,
+            scroll_top: self.viewport.scroll_top(),
+            selection_start: self.selection_start,
+            marks: self.marks.clone(),
+            search_pattern: self.search_pattern().map(|r| r.as_str().to_string()),
+        }
+    }
+
+    /// Restore the session state saved by [`TextArea::session_state`]. Positions are clamped to the current text
+    /// since the text may have been changed after the state was saved. When the search pattern is invalid, the error
+    /// is returned after restoring the other states.
+    pub fn restore_session_state(&mut self, state: &SessionState) -> Result<(), regex::Error> {
+        self.cursor = self.clamp_pos(state.cursor);
+        self.selection_start = state.selection_start.map(|pos| self.clamp_pos(pos));
+        self.marks = state.marks.clone();
+        let (row, col) = state.scroll_top;
+        self.viewport.set_scroll_top(row, col);
+        self.set_search_pattern(state.search_pattern.as_deref().unwrap_or(""))
+    }
+
+    fn clamp_pos(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        let row = cmp::min(row, self.lines.len() - 1);
+        let col = cmp::min(col, self.lines[row].chars().count());
+        (row, col)
+    }
+
+    /// Scroll the textarea. See [`Scrolling`] for the argument.
+    /// The cursor will not move until it goes out the viewport. When the cursor position is outside the viewport after scroll,
+    /// the cursor position will be adjusted to stay in the viewport using the same logic as [`CursorMove::InViewport`].
+    ///
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::layout::Rect;
+    /// # use ratatui::widgets::Widget;
+    /// use tuipaz_textarea::TextArea;
+    ///
+    /// // Let's say terminal height is 8.
+    ///
+    /// // Create textarea with 20 lines "0", "1", "2", "3", ...
+    /// let mut textarea: TextArea = (0..20).into_iter().map(|i| i.to_string()).collect();
+    /// # // Call `render` at least once to populate terminal size
+    /// # let r = Rect { x: 0, y: 0, width: 24, height: 8 };
+    /// # let mut b = Buffer::empty(r.clone());
+    /// # textarea.widget().render(r, &mut b);
+    ///
+    /// // Scroll down by 15 lines. Since terminal height is 8, cursor will go out
+    /// // the viewport.
+    /// textarea.scroll((15, 0));
+    /// // So the cursor position was updated to stay in the viewport after the scrolling.
+    /// assert_eq!(textarea.cursor(), (15, 0));
+    ///
+    /// // Scroll up by 5 lines. Since the scroll amount is smaller than the terminal
+    /// // height, cursor position will not be updated.
+    /// textarea.scroll((-5, 0));
+    /// assert_eq!(textarea.cursor(), (15, 0));
+    ///
+    /// // Scroll up by 5 lines again. The terminal height is 8. So a cursor reaches to
+    /// // the top of viewport after scrolling up by 7 lines. Since we have already
+    /// // scrolled up by 5 lines, scrolling up by 5 lines again makes the cursor overrun
+    /// // the viewport by 5 - 2 = 3 lines. To keep the cursor stay in the viewport, the
+    /// // cursor position will be adjusted from line 15 to line 12.
+    /// textarea.scroll((-5, 0));
+    /// assert_eq!(textarea.cursor(), (12, 0));
+    /// ```
+    pub fn scroll(&mut self, scrolling: impl Into<Scrolling>) {
+        self.scroll_with_shift(scrolling.into(), self.selection_start.is_some());
+    }
+
+    fn scroll_with_shift(&mut self, scrolling: Scrolling, shift: bool) {
+        if shift && self.selection_start.is_none() {
+            self.selection_start = Some(self.cursor);
+        }
+        if self.smooth_scroll {
+            // Scroll from the ongoing target so that consecutive scrolls accumulate
+            let mut viewport = self.viewport.clone();
+            if let Some((row, col)) = self.scroll_target {
+                viewport.set_scroll_top(row, col);
+            }
+            scrolling.scroll(&mut viewport);
+            let (row, col) = viewport.scroll_top();
+            let max_row = self.lines.len() - 1;
+            let max_col = self.lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            let row = cmp::min(row as usize, max_row) as u16;
+            let col = cmp::min(col as usize, max_col) as u16;
+            self.scroll_target = Some((row, col));
+            return;
+        }
+        scrolling.scroll(&mut self.viewport);
+        self.move_cursor_with_shift(CursorMove::InViewport, shift);
+    }
+
+    /// Enable or disable smooth scrolling. When it is enabled, [`TextArea::scroll`] does not move the viewport
+    /// immediately. Instead the viewport approaches the scroll target on each [`TextArea::tick`] call, moving half of
+    /// the remaining distance per tick. This is useful for hosts which render at a steady frame rate. Smooth scrolling
+    /// is disabled by default.
+    ///
+    /// Disabling smooth scrolling jumps to the ongoing scroll target.
+    pub fn set_smooth_scroll(&mut self, enabled: bool) {
+        self.smooth_scroll = enabled;
+        if !enabled {
+            if let Some((row, col)) = self.scroll_target.take() {
+                self.viewport.set_scroll_top(row, col);
+                self.move_cursor_with_shift(CursorMove::InViewport, self.selection_start.is_some());
+            }
+        }
+    }
+
+    /// Check if smooth scrolling is enabled. See [`TextArea::set_smooth_scroll`].
+    pub fn smooth_scroll(&self) -> bool {
+        self.smooth_scroll
+    }
+
+    /// Check if the viewport is still moving towards the scroll target of smooth scrolling.
+    pub fn is_scrolling(&self) -> bool {
+        self.scroll_target.is_some()
+    }
+
+    /// Advance smooth scrolling by one frame. The viewport moves half of the remaining distance to the scroll target
+    /// (at least one row or column) and the cursor is kept in the viewport. Call this once per frame before rendering.
+    /// `true` is returned when the viewport moved and the textarea needs to be redrawn.
+    ///
+    /// This method also calls the autosave callback set by [`TextArea::set_autosave`] when the text has been idle for
+    /// the interval, calls the observer set by [`TextArea::set_state_observer`] when the state changed, and inserts the
+    /// next batch of [`TextArea::insert_str_incremental`]. `true` is also returned when a batch was inserted.
+    /// ```
+    /// # use ratatui::buffer::Buffer;
+    /// # use ratatui::layout::Rect;
+    /// # use ratatui::widgets::Widget;
+    /// use tuipaz_textarea::{Scrolling, TextArea};
+    ///
+    /// let mut textarea: TextArea = (0..50).into_iter().map(|i| i.to_string()).collect();
+    /// # let r = Rect { x: 0, y: 0, width: 24, height: 8 };
+    /// # let mut b = Buffer::empty(r.clone());
+    /// # textarea.widget().render(r, &mut b);
+    ///
+    /// textarea.set_smooth_scroll(true);
+    /// textarea.scroll(Scrolling::PageDown);
+    /// assert_eq!(textarea.viewport_offset(), (0, 0));
+    ///
+    /// assert!(textarea.tick());
+    /// assert_eq!(textarea.viewport_offset(), (4, 0));
+    /// assert_eq!(textarea.cursor(), (4, 0));
+    ///
+    /// while textarea.tick() {}
+    /// assert_eq!(textarea.viewport_offset(), (8, 0));
+    /// assert!(!textarea.is_scrolling());
+    /// ```
+    pub fn tick(&mut self) -> bool {
+        fn step(pos: u16, target: u16) -> u16 {
+            if pos < target {
+                pos + cmp::max((target - pos) / 2, 1)
+            } else if pos > target {
+                pos - cmp::max((pos - target) / 2, 1)
+            } else {
+                pos
+            }
+        }
+
+        if let Some(autosave) = &mut self.autosave {
+            autosave.tick(Instant::now(), &self.lines);
+        }
+        if self.state_observer.is_some() {
+            let state = self.editor_state();
+            if let Some(observer) = &mut self.state_observer {
+                observer.tick(Instant::now(), state);
+            }
+        }
+
+        let inserted = self.insert_next_batch();
+
+        let (target_row, target_col) = match self.scroll_target {
+            Some(target) => target,
+            None => return inserted,
+        };
+        let (row, col) = self.viewport.scroll_top();
+        let next = (step(row, target_row), step(col, target_col));
+        if next == (target_row, target_col) {
+            self.scroll_target = None;
+        }
+        if next == (row, col) {
+            return inserted;
+        }
+        self.viewport.set_scroll_top(next.0, next.1);
+        self.move_cursor_with_shift(CursorMove::InViewport, self.selection_start.is_some());
+        true
+    }
+}
+
+/// Handle to add, update and remove the links of a [`TextArea`] while keeping them consistent with the text. It is
+/// returned by [`TextArea::links_mut`]. Ranges are validated against the text and the other links, and every change
+/// is recorded in the edit history so that it can be undone.
+/// ```
+/// use tuipaz_textarea::{TextArea, LinkError};
+///
+/// let mut textarea = TextArea::from(["foo bar"]);
+/// let mut links = textarea.links_mut();
+///
+/// let id = links.add(0, 0, 2).unwrap();
+/// assert_eq!(links.add(0, 2, 4), Err(LinkError::Overlap(id)));
+/// links.update(id, 0, 4, 6).unwrap();
+/// assert_eq!(links.get(id).map(|l| l.start_col), Some(4));
+/// links.remove(id).unwrap();
+/// assert!(links.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct Links<'t, 'a> {
+    textarea: &'t mut TextArea<'a>,
+}
+
+impl<'t, 'a> Links<'t, 'a> {
+    /// Get the link with the ID. Deleted links are not returned.
+    pub fn get(&self, id: usize) -> Option<&Link> {
+        self.textarea.link(id)
+    }
+
+    /// Get the IDs of the links in ascending order.
+    pub fn ids(&self) -> Vec<usize> {
+        self.textarea.link_ids()
+    }
+
+    /// Get the number of links.
+    #[allow(deprecated)]
+    pub fn len(&self) -> usize {
+        self.textarea.links.values().filter(|l| !l.deleted).count()
+    }
+
+    /// Return if there is no link.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the ID which will be given to the next new link.
+    #[allow(deprecated)]
+    pub fn next_id(&self) -> usize {
+        self.textarea.next_link_id
+    }
+
+    /// Add a link over the columns `start_col..=end_col` of the row and return its ID.
+    pub fn add(&mut self, row: usize, start_col: usize, end_col: usize) -> Result<usize, LinkError> {
+        self.validate(None, row, start_col, end_col)?;
+        Ok(self.textarea.create_link(row, start_col, end_col))
+    }
+
+    /// Move the link with the ID to the columns `start_col..=end_col` of the row.
+    #[allow(deprecated)]
+    pub fn update(&mut self, id: usize, row: usize, start_col: usize, end_col: usize) -> Result<(), LinkError> {
+        let before = *self.get(id).ok_or(LinkError::NoSuchLink(id))?;
+        self.validate(Some(id), row, start_col, end_col)?;
+        let mut after = before;
+        after.row = row;
+        after.start_col = start_col;
+        after.end_col = end_col;
+        self.textarea.links.insert(id, after);
+        self.textarea.link_rows.moved(id, before.row, row);
+
+        let (row, col) = self.textarea.cursor;
+        let offset = self.textarea.line_offset(row, col);
+        self.textarea
+            .push_history(EditKind::UpdateLink((before, after)), Pos::new(row, col, offset), offset);
+        Ok(())
+    }
+
+    /// Remove the link with the ID without changing the text. The removed link is returned.
+    pub fn remove(&mut self, id: usize) -> Result<Link, LinkError> {
+        let link = *self.get(id).ok_or(LinkError::NoSuchLink(id))?;
+        self.textarea.delete_link(id);
+        Ok(link)
+    }
+
+    #[allow(deprecated)]
+    fn validate(&self, id: Option<usize>, row: usize, start_col: usize, end_col: usize) -> Result<(), LinkError> {
+        let in_bounds = self
+            .textarea
+            .lines
+            .get(row)
+            .map_or(false, |line| start_col <= end_col && end_col < line.chars().count());
+        if !in_bounds {
+            return Err(LinkError::OutOfBounds {
+                row,
+                start_col,
+                end_col,
+            });
+        }
+        let overlap = self.textarea.links.values().find(|l| {
+            !l.deleted && Some(l.id) != id && l.row == row && l.start_col <= end_col && start_col <= l.end_col
+        });
+        match overlap {
+            Some(l) => Err(LinkError::Overlap(l.id)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    
+    const THEME_COLOR: Color = Color::Red;
+    const THEME: TextAreaTheme = TextAreaTheme {
+        text: THEME_COLOR,
+        select: THEME_COLOR,
+        links: THEME_COLOR,
+    };
+
+    #[test]
+    fn test_delete_piece_includes_link() {
+        let mut textarea = TextArea::new(vec!["Hello [world]!".into()], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 6, end_col: 11, edited: false, deleted: false });
+
+        textarea.delete_piece(5, 10);
+
+        assert_eq!(textarea.links.get(&0), None);
+    }
+
+    #[test]
+    fn test_delete_entire_link() {
+        let mut textarea = TextArea::new(vec!["[Link]".into()], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 0, end_col: 5, edited: false , deleted: false });
+
+        textarea.delete_piece(0, 5);
+
+        assert_eq!(textarea.links.get(&0), None);
+    }
+
+    #[test]
+    fn test_delete_part_of_link() {
+        let mut textarea = TextArea::new(vec!["[Example Link]".into()], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 0, end_col: 21, edited: false , deleted: false });
+
+        textarea.delete_piece(7, 9);
+
+        assert_eq!(textarea.links.get(&0), None);
+    }
+
+    #[test]
+    fn test_links_shift_on_insert_char() {
+        let mut textarea = TextArea::new(vec!["Hello [world]!".into()], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 6, end_col: 11, edited: false , deleted: false });
+        textarea.move_cursor(CursorMove::Jump(0, 5));
+        textarea.insert_char(' ');
+        
+        assert_eq!(textarea.links.get(&0).unwrap().start_col, 7); 
+        assert_eq!(textarea.links.get(&0).unwrap().end_col, 12); 
+    }
+
+    #[test]
+    fn test_links_shift_on_delete_char() {
+        let mut textarea = TextArea::new(vec!["Hello [world]!".into()], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 6, end_col: 11, edited: false , deleted: false });
+        textarea.move_cursor(CursorMove::Jump(0, 5));
+        textarea.delete_char();
+        
+        assert_eq!(textarea.links.get(&0).unwrap().start_col, 5); 
+        assert_eq!(textarea.links.get(&0).unwrap().end_col, 10); 
+    }
+
+    #[test]
+    fn test_deletion_on_different_rows_does_not_affect_links() {
+        let mut textarea = TextArea::new(vec![
+            "Line without link.".into(),
+            "[Link]".into()
+        ], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 1, start_col: 0, end_col: 5, edited: false , deleted: false });
+
+        textarea.delete_piece(0, 5);
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+    }
+
+    #[test]
+    fn test_deletion_range_does_not_delete_links_but_shifts_it() {
+        let mut textarea = TextArea::new(vec![
+            "Before [link] and after.".into()
+        ], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+
+        textarea.delete_piece(0, 6);
+        textarea.delete_piece(23, 13);
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 6 , edited: false , deleted: false}));
+    }
+
+    #[test]
+    fn test_inserting_newline_above_and_below_link() {
+        let mut textarea = TextArea::new(vec![
+            "[Link]".into(),
+            "Some text below.".into()
+        ], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 0, end_col: 5, edited: false , deleted: false });
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        textarea.insert_newline();
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+        
+        textarea.move_cursor(CursorMove::Jump(0, 0));
+        textarea.insert_newline();
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+    }
+
+    #[test]
+    fn test_deleting_line_by_head_above_and_below_link() {
+        let mut textarea = TextArea::new(vec![
+            "Some text above".into(),
+            "[Link]".into(),
+            "Some text below.".into()
+        ], HashMap::new(), 140, THEME);
+
+        textarea.links.insert(0, Link { id: 0, row: 1, start_col: 0, end_col: 5, edited: false , deleted: false });
+        textarea.move_cursor(CursorMove::Jump(2, 0));
+        textarea.delete_line_by_head();
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+        
+        textarea.move_cursor(CursorMove::Jump(0, 0));
+        textarea.delete_line_by_head();
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+    }
+
+    #[test]
+    fn test_deleting_line_by_end_above_and_below_link() {
+        let mut textarea = TextArea::new(vec![
+            "Some text above".into(),
+            "[Link]".into(),
+            "Some text below.".into()
+        ], HashMap::new(), 140, THEME);
+
+        textarea.links.insert(0, Link { id: 0, row: 1, start_col: 0, end_col: 5, edited: false , deleted: false });
+        textarea.move_cursor(CursorMove::Jump(2, 0));
+        textarea.move_cursor(CursorMove::End);
+        textarea.delete_line_by_end();
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+        
+        textarea.move_cursor(CursorMove::Jump(0, 0));
+        textarea.move_cursor(CursorMove::End);
+        textarea.delete_line_by_end();
 
-                (l.row) = match drow >= 0 {
-                    true => {
-                        l.row.saturating_add(drow as usize)
-                    },
-                    false => {
-                        let positive_drow = drow.unsigned_abs() as usize;
-                        l.row.saturating_sub(positive_drow)
-                    },
-                }
-            }
-            info!("shift_links_after_insert::link AFTER: {:?}", l);
-        }
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
     }
 
-    pub fn shift_links_after_edit(
-        &mut self,
-        (start_row, start_col): (usize, usize),
-        (end_row, end_col): (usize, usize)
-    ) {
-        info!("SHIFT LINKS AFTER EDIT");
-        let drow = end_row as i64 - start_row as i64;
-        let dcol = end_col as i64 - start_col as i64;
-        // Dont shift links that were in the edit or are currently deleted
-        for l in self.links.values_mut().filter(|l| !l.deleted) {
-            info!("shift_links_after_edit::link BEFORE: {:?}", l); 
-            if l.row >= start_row && !l.edited {
-                if (l.row == end_row && l.start_col >= start_col)
-                    || (l.row == start_row && end_row != start_row)
-                {
-                    (l.start_col, l.end_col) = match dcol >= 0 {
-                        true => {
-                            (l.start_col.saturating_add(dcol as usize),
-                            l.end_col.saturating_add(dcol as usize))
-                        },
-                        false => {
-                            let positive_dcol = dcol.unsigned_abs() as usize;
-                            (l.start_col.saturating_sub(positive_dcol),
-                            l.end_col.saturating_sub(positive_dcol))
-                        },
-                    }
-                }
+    #[test]
+    fn test_link_full_row_selection_deletion() {
+        let mut textarea = TextArea::new(vec![
+            "Before [link] after".into()
+        ], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
 
-                (l.row) = match drow >= 0 {
-                    true => {
-                        l.row.saturating_add(drow as usize)
-                    },
-                    false => {
-                        let positive_drow = drow.unsigned_abs() as usize;
-                        l.row.saturating_sub(positive_drow)
-                    },
-                };
-            } else {
-                l.toggle_edited();
-            }
-            info!("shift_links_after_edit::link AFTER: {:?}", l); 
-        }
+        textarea.selection_start = Some((0, 0));
+        textarea.move_cursor(CursorMove::End);
+        textarea.delete_selection(false);
+
+        assert_eq!(textarea.links.get(&0), None);
     }
 
+    #[test]
+    fn test_link_full_link_selection_deletion() {
+        let mut textarea = TextArea::new(vec![
+            "Before [link] after".into()
+        ], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
 
-    pub fn shift_links_prevline(
-        &mut self,
-        (row, col): (usize, usize),
-        dcol: usize
-    ) {
-        info!("SHIFT LINKS PREVLINE");
-        for l in self.links.values_mut() {
-            info!("{}", log_format(&l, "l before shift"));
-            info!("{}", log_format(&dcol, "dcol"));
-            info!("{}", log_format(&(row, col), "(row, col)"));
-            if l.row >= row {
-                l.row = l.row.saturating_sub(1);
-                l.start_col = l.start_col.saturating_add(dcol);
-                l.end_col = l.end_col.saturating_add(dcol);
-            }
-            info!("{}", log_format(&l, "l after shift"));
-        }
-    }
+        textarea.selection_start = Some((0, 7));
+        textarea.move_cursor(CursorMove::Jump(0, 12));
+        textarea.delete_selection(false);
 
-    pub fn shift_links_newline(
-        &mut self,
-        (row, col): (usize, usize),
-    ) {
-        for l in self.links.values_mut() {
-            info!("shift_links_newline::link before shift: {:?}", l);
-            if l.edited {
-                info!("shift_links_newline::ignoring edited link");
-                break;
-            } else if l.row > row {
-                l.row = l.row.saturating_add(1);
-            } else if l.row == row && l.start_col >= col {
-                l.row = l.row.saturating_add(1);
-                l.start_col = l.start_col.saturating_sub(col);
-                l.end_col = l.end_col.saturating_sub(col);
-            }
-            info!("{}", log_format(&l, "l after shift"));
-        }
+        assert_eq!(textarea.links.get(&0), None);
     }
 
-    pub fn delete_links_in_range(&mut self, start: (usize, usize), end: (usize, usize)) -> MaybeLinks {
-        let mut deleted_links = Vec::new();
-        for (id, link) in self.links.iter() {
-            if (link.row < start.0 || link.row > end.0)
-                || (link.row == start.0 && link.end_col < start.1)
-                || (link.row == end.0 && link.start_col > end.1)
-            {
-                continue;
-            } else {
-                let row_offset = link.row - start.0;
-                let (start_col_offset, end_col_offset) = if row_offset == 0 {
-                    (link.start_col - start.1, link.end_col - start.1)
-                } else {
-                    (link.start_col, link.end_col)
-                };
-                
-                
-                let deleted_link = YankedLink {
-                    id: *id,
-                    row_offset,
-                    start_col_offset,
-                    end_col_offset,
-                };
+    #[test]
+    fn test_link_partial_link_selection_deletion() {
+        let mut textarea = TextArea::new(vec![
+            "Before [link] after".into()
+        ], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
 
-                deleted_links.push(deleted_link);
-            }
-        }
+        textarea.selection_start = Some((0, 6));
+        textarea.move_cursor(CursorMove::Jump(0, 8));
+        textarea.delete_selection(false);
 
-        if deleted_links.is_empty() {
-            None
-        } else {
-            for dl in deleted_links.iter() {
-                self.delete_link(dl.id);
-            }
-            Some(deleted_links)
-        }
+        assert_eq!(textarea.links.get(&0), None);
     }
 
-    ///cBuild a ratatui (or tui-rs) widget to render the current state of the textarea. The widget instance returned
-    /// from this method can be rendered with [`ratatui::terminal::Frame::render_widget`].
-    /// ```no_run
-    /// use ratatui::backend::CrosstermBackend;
-    /// use ratatui::layout::{Constraint, Direction, Layout};
-    /// use ratatui::Terminal;
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// let layout = Layout::default()
-    ///     .direction(Direction::Vertical)
-    ///     .constraints([Constraint::Min(1)].as_ref());
-    /// let backend = CrosstermBackend::new(std::io::stdout());
-    /// let mut term = Terminal::new(backend).unwrap();
-    ///
-    /// loop {
-    ///     term.draw(|f| {
-    ///         let chunks = layout.split(f.size());
-    ///         let widget = textarea.widget();
-    ///         f.render_widget(widget, chunks[0]);
-    ///     }).unwrap();
-    ///
-    ///     // ...
-    /// }
-    /// ```
-    pub fn widget(&'a self) -> impl Widget + 'a {
-        Renderer::new(self)
-    }
+    #[test]
+    fn test_link_shift_after_selection_deletion_same_row() {
+        let mut textarea = TextArea::new(vec![
+            "Before [link] after".into()
+        ], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
 
-    /// Set the style of textarea. By default, textarea is not styled.
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    /// let style = Style::default().fg(Color::Red);
-    /// textarea.set_style(style);
-    /// assert_eq!(textarea.style(), style);
-    /// ```
-    pub fn set_style(&mut self, style: Style) {
-        self.style = style;
-    }
+        textarea.selection_start = Some((0, 0));
+        textarea.move_cursor(CursorMove::Jump(0, 5));
+        textarea.delete_selection(false);
 
-    /// Get the current style of textarea.
-    pub fn style(&self) -> Style {
-        self.style
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 2, end_col: 7 , edited: false , deleted: false}));
     }
 
-    /// Set the block of textarea. By default, no block is set.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    /// use ratatui::widgets::{Block, Borders};
-    ///
-    /// let mut textarea = TextArea::default();
-    /// let block = Block::default().borders(Borders::ALL).title("Block Title");
-    /// textarea.set_block(block);
-    /// assert!(textarea.block().is_some());
-    /// ```
-    pub fn set_block(&mut self, block: Block<'a>) {
-        self.block = Some(block);
+    #[test]
+    fn test_link_shift_after_selection_deletion_multiple_rows_above() {
+        let mut textarea = TextArea::new(vec![
+            "Text above 1".into(),
+            "Text above 2".into(),
+            "Before [link] after".into()
+        ], HashMap::new(), 140, THEME);
+
+        textarea.links.insert(0, Link { id: 0, row: 2, start_col: 7, end_col: 12, edited: false , deleted: false });
+
+        textarea.selection_start = Some((0, 0));
+        textarea.move_cursor(CursorMove::Jump(1, 11));
+        textarea.delete_selection(false);
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 7, end_col: 12 , edited: false , deleted: false}));
     }
 
-    /// Remove the block of textarea which was set by [`TextArea::set_block`].
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    /// use ratatui::widgets::{Block, Borders};
-    ///
-    /// let mut textarea = TextArea::default();
-    /// let block = Block::default().borders(Borders::ALL).title("Block Title");
-    /// textarea.set_block(block);
-    /// textarea.remove_block();
-    /// assert!(textarea.block().is_none());
-    /// ```
-    pub fn remove_block(&mut self) {
-        self.block = None;
+    #[test]
+    fn test_link_shift_after_selection_deletion_above_up_to_link_row_and_start_col() {
+        let mut textarea = TextArea::new(vec![
+            "Text above 1".into(),
+            "Text above 2".into(),
+            "Before [link] after".into()
+        ], HashMap::new(), 140, THEME);
+
+        textarea.links.insert(0, Link { id: 0, row: 2, start_col: 7, end_col: 12, edited: false , deleted: false });
+
+        textarea.selection_start = Some((0, 0));
+        textarea.move_cursor(CursorMove::Jump(2, 6));
+        textarea.delete_selection(false);
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
     }
 
-    /// Get the block of textarea if exists.
-    pub fn block<'s>(&'s self) -> Option<&'s Block<'a>> {
-        self.block.as_ref()
+    #[test]
+    fn test_link_shift_after_selection_deletion_below_from_link_row_and_end_col() {
+        let mut textarea = TextArea::new(vec![
+            "Before [link] after".into(),
+            "Text below 1".into(),
+            "Text below 2".into(),
+        ], HashMap::new(), 140, THEME);
+
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+
+        textarea.selection_start = Some((0, 13));
+        textarea.move_cursor(CursorMove::Jump(2, 11));
+        textarea.delete_selection(false);
+
+        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 7, end_col: 12 , edited: false , deleted: false}));
     }
 
-    /// Set the length of tab character. Setting 0 disables tab inputs.
-    /// ```
-    /// use tuipaz_textarea::{TextArea, Input, Key};
-    ///
-    /// let mut textarea = TextArea::default();
-    /// let tab_input = Input { key: Key::Tab, ctrl: false, alt: false, shift: false };
-    ///
-    /// textarea.set_tab_length(8);
-    /// textarea.input(tab_input.clone());
-    /// assert_eq!(textarea.lines(), ["        "]);
-    ///
-    /// textarea.set_tab_length(2);
-    /// textarea.input(tab_input);
-    /// assert_eq!(textarea.lines(), ["          "]);
-    /// ```
-    pub fn set_tab_length(&mut self, len: u8) {
-        self.tab_len = len;
-    }
+    #[test]
+    fn test_styled_lines_range() {
+        let mut textarea = TextArea::new(vec!["abc".into(), "def".into(), "ghi".into()], HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(1, 1));
 
-    /// Get how many spaces are used for representing tab character. The default value is 4.
-    pub fn tab_length(&self) -> u8 {
-        self.tab_len
-    }
+        let lines = textarea.styled_lines(1..3);
+        assert_eq!(lines.len(), 2);
+        let cursor_span = &lines[0].spans[1];
+        assert_eq!(cursor_span.content, "e");
+        assert_eq!(cursor_span.style, textarea.cursor_style());
 
-    /// Set if a hard tab is used or not for indent. When `true` is set, typing a tab key inserts a hard tab instead of
-    /// spaces. By default, hard tab is disabled.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// textarea.set_hard_tab_indent(true);
-    /// textarea.insert_tab();
-    /// assert_eq!(textarea.lines(), ["\t"]);
-    /// ```
-    pub fn set_hard_tab_indent(&mut self, enabled: bool) {
-        self.hard_tab_indent = enabled;
+        assert!(textarea.styled_lines(5..8).is_empty());
     }
 
-    /// Get if a hard tab is used for indent or not.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// assert!(!textarea.hard_tab_indent());
-    /// textarea.set_hard_tab_indent(true);
-    /// assert!(textarea.hard_tab_indent());
-    /// ```
-    pub fn hard_tab_indent(&self) -> bool {
-        self.hard_tab_indent
+    #[test]
+    fn test_styled_lines_cache() {
+        let mut textarea = TextArea::new(vec!["abc".into(), "def".into(), "ghi".into()], HashMap::new(), 140, THEME);
+        let text = |t: &TextArea| -> Vec<String> {
+            t.styled_lines(0..3)
+                .iter()
+                .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+                .collect()
+        };
+        assert_eq!(text(&textarea), ["abc", "def", "ghi"]);
+
+        // Lines after the edited line are rendered from the cache at their new rows
+        textarea.insert_newline();
+        textarea.move_cursor(CursorMove::Bottom);
+        assert_eq!(text(&textarea), ["", "abc", "def"]);
+        assert_eq!(text(&textarea), ["", "abc", "def"]);
+
+        // Links added without editing the text are rendered
+        textarea.links_mut().add(1, 0, 1).unwrap();
+        let lines = textarea.styled_lines(1..2);
+        assert_eq!(lines[0].spans[0].style, textarea.link_style);
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.styled_lines(1..2)[0].spans.len(), 1);
     }
 
-    /// Get a string for indent. It consists of spaces by default. When hard tab is enabled, it is a tab character.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// assert_eq!(textarea.indent(), "    ");
-    /// textarea.set_tab_length(2);
-    /// assert_eq!(textarea.indent(), "  ");
-    /// textarea.set_hard_tab_indent(true);
-    /// assert_eq!(textarea.indent(), "\t");
-    /// ```
-    pub fn indent(&self) -> &'static str {
-        if self.hard_tab_indent {
-            "\t"
-        } else {
-            spaces(self.tab_len)
+    #[test]
+    fn test_gutter_width() {
+        let mut textarea = TextArea::new(vec!["a".to_string(); 1000], HashMap::new(), 140, THEME);
+        assert_eq!(textarea.gutter_width(), 0);
+        textarea.set_line_number_style(Style::default());
+        assert_eq!(textarea.gutter_width(), 6);
+        for row in [0, 999] {
+            let line = &textarea.styled_lines(row..row + 1)[0];
+            assert_eq!(line.spans[0].content.len(), 6);
         }
     }
 
-    /// Set how many modifications are remembered for undo/redo. Setting 0 disables undo/redo.
-    pub fn set_max_histories(&mut self, max: usize) {
-        self.history = History::new(max);
+    #[test]
+    fn test_position_apis() {
+        let lines = vec!["ab [cd]".to_string(), "ef".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 3, 6))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        let link = textarea.links[&0];
+        assert_eq!((link.start(), link.end()), (Position::new(0, 3), Position::new(0, 6)));
+        assert_eq!(textarea.in_link(link.start()), Some(0));
+        assert_eq!(textarea.in_link((0, 4)), Some(0));
+
+        assert_eq!(textarea.selection(), None);
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(0, 5));
+        assert_eq!(textarea.cursor_position(), Position::new(0, 5));
+        assert_eq!(textarea.selection(), Some(TextRange::new((0, 5), (1, 1))));
     }
 
-    /// Get how many modifications are remembered for undo/redo. The default value is 50.
-    pub fn max_histories(&self) -> usize {
-        self.history.max_items()
+    #[test]
+    fn test_text_range_apis() {
+        let lines = vec!["ab [cd] ef".to_string(), "gh [ij]".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 3, 6)), (1, Link::new(1, 1, 3, 6))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        assert_eq!(textarea.links[&0].range(), TextRange::new((0, 3), (0, 7)));
+        assert_eq!(textarea.links_in_range((0, 0), (1, 0)), [0]);
+        assert_eq!(textarea.links_in_range((1, 9), (0, 6)), [0, 1]);
+        assert!(textarea.links_in_range((0, 7), (1, 3)).is_empty());
+
+        // Replacing text before a link moves the link
+        assert!(textarea.replace_range(((0, 0), (0, 2)), "xyz"));
+        assert_eq!(textarea.lines, ["xyz [cd] ef", "gh [ij]"]);
+        assert_eq!(textarea.links[&0].range(), TextRange::new((0, 4), (0, 8)));
+        assert_eq!(textarea.cursor(), (0, 3));
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["ab [cd] ef", "gh [ij]"]);
+        assert_eq!(textarea.links[&0].range(), TextRange::new((0, 3), (0, 7)));
+
+        // Empty range inserts at the start
+        assert!(textarea.replace_range(((0, 2), (0, 2)), "!"));
+        assert_eq!(textarea.lines, ["ab! [cd] ef", "gh [ij]"]);
+        assert!(textarea.undo());
+
+        // Links after a multi-line replacement move to the new rows and links in the range are deleted
+        assert!(textarea.replace_range(((0, 7), (1, 0)), "\nnew\n"));
+        assert_eq!(textarea.lines, ["ab [cd]", "new", "gh [ij]"]);
+        assert_eq!(textarea.links[&1].range(), TextRange::new((2, 3), (2, 7)));
+        assert_eq!(textarea.cursor(), (2, 0));
+        assert!(textarea.replace_range(((0, 2), (2, 4)), " "));
+        assert_eq!(textarea.lines, ["ab ij]"]);
+        assert!(textarea.links[&0].deleted && textarea.links[&1].deleted);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["ab [cd]", "new", "gh [ij]"]);
+        assert!(!textarea.links[&0].deleted && !textarea.links[&1].deleted);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["ab [cd] ef", "gh [ij]"]);
+        assert_eq!(textarea.links[&1].range(), TextRange::new((1, 3), (1, 7)));
+
+        // The range is clamped into the text
+        assert!(textarea.delete_range(((0, 7), (9, 0))));
+        assert_eq!(textarea.lines, ["ab [cd]"]);
+        assert_eq!(textarea.cursor(), (0, 7));
+        assert!(!textarea.delete_range(((0, 9), (3, 3))));
+        assert!(textarea.selection().is_none());
     }
 
-    /// Set the style of line at cursor. By default, the cursor line is styled with underline. To stop styling the
-    /// cursor line, set the default style.
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// let style = Style::default().fg(Color::Red);
-    /// textarea.set_cursor_line_style(style);
-    /// assert_eq!(textarea.cursor_line_style(), style);
-    ///
-    /// // Disable cursor line style
-    /// textarea.set_cursor_line_style(Style::default());
-    /// ```
-    pub fn set_cursor_line_style(&mut self, style: Style) {
-        self.cursor_line_style = style;
+    #[test]
+    fn test_desired_column() {
+        let lines = vec!["abcdef".to_string(), "ab".to_string(), "".to_string(), "abcdefgh".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 5));
+
+        textarea.move_cursor(CursorMove::Down);
+        assert_eq!(textarea.cursor(), (1, 2));
+        textarea.move_cursor(CursorMove::Down);
+        assert_eq!(textarea.cursor(), (2, 0));
+        textarea.move_cursor(CursorMove::Down);
+        assert_eq!(textarea.cursor(), (3, 5));
+        textarea.move_cursor(CursorMove::Up);
+        textarea.move_cursor(CursorMove::Up);
+        assert_eq!(textarea.cursor(), (1, 2));
+
+        // Other moves set a new column
+        textarea.move_cursor(CursorMove::Back);
+        textarea.move_cursor(CursorMove::Up);
+        assert_eq!(textarea.cursor(), (0, 1));
+
+        // Edits forget the column as well
+        textarea.move_cursor(CursorMove::End);
+        textarea.move_cursor(CursorMove::Down);
+        assert_eq!(textarea.cursor(), (1, 2));
+        textarea.insert_char('x');
+        textarea.move_cursor(CursorMove::Up);
+        assert_eq!(textarea.cursor(), (0, 3));
     }
 
-    /// Get the style of cursor line. By default it is styled with underline.
-    pub fn cursor_line_style(&self) -> Style {
-        self.cursor_line_style
+    #[test]
+    fn test_extend_selection_to() {
+        let lines = vec!["abc".to_string(), "defgh".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(1, 2));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Forward);
+
+        // The anchor is preserved
+        textarea.extend_selection_to((0, 1));
+        assert_eq!(textarea.selection(), Some(TextRange::new((0, 1), (1, 2))));
+
+        // The position is clamped
+        textarea.extend_selection_to((5, 9));
+        assert_eq!(textarea.cursor(), (1, 5));
+        assert_eq!(textarea.selection(), Some(TextRange::new((1, 2), (1, 5))));
+
+        // Moving the cursor keeps extending from the same anchor
+        textarea.move_cursor(CursorMove::Head);
+        textarea.copy();
+        assert_eq!(textarea.yank_text(), "de");
     }
 
-    /// Set the style of line number. By setting the style with this method, line numbers are drawn in textarea, meant
-    /// that line numbers are disabled by default. If you want to show line numbers but don't want to style them, set
-    /// the default style.
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// // Show line numbers in dark gray background
-    /// let style = Style::default().bg(Color::DarkGray);
-    /// textarea.set_line_number_style(style);
-    /// assert_eq!(textarea.line_number_style(), Some(style));
-    /// ```
-    pub fn set_line_number_style(&mut self, style: Style) {
-        self.line_number_style = Some(style);
+    #[test]
+    fn test_search_preview() {
+        use crate::ratatui::buffer::Buffer;
+        use crate::ratatui::widgets::Widget;
+
+        let lines = (0..20).map(|i| format!("line{}", i)).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        let area = Rect { x: 0, y: 0, width: 10, height: 4 };
+        let render = |textarea: &TextArea| {
+            let mut buf = Buffer::empty(area);
+            textarea.widget().render(area, &mut buf);
+            textarea.viewport.scroll_top()
+        };
+        assert_eq!(render(&textarea), (0, 0));
+
+        // Without preview, the viewport does not follow matches
+        assert!(textarea.preview_search("line1[05]").unwrap());
+        assert_eq!(textarea.search_preview_position(), None);
+        assert_eq!(render(&textarea), (0, 0));
+
+        textarea.set_search_preview(true);
+        assert!(textarea.preview_search("line1[05]").unwrap());
+        assert_eq!(textarea.search_preview_position(), Some((10, 0)));
+        assert_eq!(render(&textarea), (7, 0));
+        assert!(textarea.preview_search("line15").unwrap());
+        assert_eq!(render(&textarea), (12, 0));
+        assert!(!textarea.preview_search("line99").unwrap());
+        assert_eq!(render(&textarea), (0, 0));
+        assert!(textarea.preview_search("line15").unwrap());
+        assert_eq!(render(&textarea), (12, 0));
+        assert_eq!(textarea.cursor(), (0, 0));
+
+        // Cancel restores the original view
+        textarea.cancel_search_preview();
+        assert_eq!(render(&textarea), (0, 0));
+        assert!(textarea.search_pattern().is_none());
+
+        // Commit moves the cursor to the match
+        textarea.preview_search("line15").unwrap();
+        assert!(textarea.commit_search_preview());
+        assert_eq!(textarea.cursor(), (15, 0));
+        assert_eq!(render(&textarea), (12, 0));
+        assert!(textarea.search_pattern().is_some());
     }
 
-    /// Remove the style of line number which was set by [`TextArea::set_line_number_style`]. After calling this
-    /// method, Line numbers will no longer be shown.
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// textarea.set_line_number_style(Style::default().bg(Color::DarkGray));
-    /// textarea.remove_line_number();
-    /// assert_eq!(textarea.line_number_style(), None);
-    /// ```
-    pub fn remove_line_number(&mut self) {
-        self.line_number_style = None;
+    #[test]
+    fn test_highlight_patterns() {
+        let lines = vec!["TODO fix".to_string(), "later".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Bottom);
+        let todo = Style::default().bg(Color::Yellow);
+        let later = Style::default().bg(Color::Green);
+        textarea.add_highlight_pattern("todo", "TODO", todo).unwrap();
+        textarea.add_highlight_pattern("fix", "fix", later).unwrap();
+        textarea.set_search_pattern("fi").unwrap();
+        assert!(textarea.add_highlight_pattern("bad", "(", todo).is_err());
+        assert!(textarea.highlight_pattern("bad").is_none());
+
+        let styles = |textarea: &TextArea| {
+            let line = &textarea.styled_lines(0..1)[0];
+            line.spans.iter().map(|s| (s.content.to_string(), s.style.bg)).collect::<Vec<_>>()
+        };
+        let search = textarea.search_style().bg;
+        assert_eq!(
+            styles(&textarea),
+            [
+                ("TODO".to_string(), Some(Color::Yellow)),
+                (" ".to_string(), None),
+                ("fi".to_string(), search),
+                ("x".to_string(), Some(Color::Green)),
+            ]
+        );
+
+        // Each pattern is removed independently of the others and the search
+        assert!(textarea.remove_highlight_pattern("fix"));
+        assert!(!textarea.remove_highlight_pattern("fix"));
+        textarea.add_highlight_pattern("todo", "", todo).unwrap();
+        assert_eq!(
+            styles(&textarea),
+            [("TODO ".to_string(), None), ("fi".to_string(), search), ("x".to_string(), None)]
+        );
+
+        // Matches follow edits
+        textarea.add_highlight_pattern("later", "later", later).unwrap();
+        textarea.move_cursor(CursorMove::Top);
+        textarea.insert_newline();
+        let line = &textarea.styled_lines(2..3)[0];
+        assert_eq!(line.spans[0].style.bg, Some(Color::Green));
     }
 
-    /// Get the style of line number if set.
-    pub fn line_number_style(&self) -> Option<Style> {
-        self.line_number_style
+    #[test]
+    fn test_modal_editing() {
+        let lines = vec!["foo(bar, baz)".to_string(), "second".to_string(), "third".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        let key = |key| Input { key, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+        let keys = |textarea: &mut TextArea, keys: &str| {
+            keys.chars().map(|c| textarea.input(key(Key::Char(c)))).fold(false, |a, b| a || b)
+        };
+        assert_eq!(textarea.mode(), Mode::Insert);
+        textarea.set_modal_editing(true);
+        assert_eq!(textarea.mode(), Mode::Normal);
+
+        // Motions do not insert text
+        assert!(!keys(&mut textarea, "wl"));
+        assert_eq!(textarea.cursor(), (0, 4));
+        assert!(keys(&mut textarea, "ci("));
+        assert_eq!(textarea.mode(), Mode::Insert);
+        assert!(keys(&mut textarea, "x"));
+        assert!(!textarea.input(key(Key::Esc)));
+        assert_eq!(textarea.lines, ["foo(x)", "second", "third"]);
+        assert_eq!(textarea.cursor(), (0, 4));
+
+        // Line operators
+        assert!(keys(&mut textarea, "jdd"));
+        assert_eq!(textarea.lines, ["foo(x)", "third"]);
+        assert_eq!(textarea.yank_text(), "second\n");
+        assert!(keys(&mut textarea, "ggP"));
+        assert_eq!(textarea.lines, ["second", "foo(x)", "third"]);
+        assert!(keys(&mut textarea, "u"));
+        assert!(keys(&mut textarea, "p"));
+        assert_eq!(textarea.lines, ["foo(x)", "second", "third"]);
+        assert!(keys(&mut textarea, "u"));
+        assert_eq!(textarea.lines, ["foo(x)", "third"]);
+        assert!(!keys(&mut textarea, "0lyy"));
+        assert_eq!(textarea.yank_text(), "foo(x)\n");
+        assert_eq!(textarea.cursor(), (0, 1));
+
+        // Visual mode
+        assert!(!keys(&mut textarea, "0vl"));
+        assert_eq!(textarea.mode(), Mode::Visual);
+        assert_eq!(textarea.selection(), Some(TextRange::new((0, 0), (0, 1))));
+        // The character under the cursor is included
+        assert!(keys(&mut textarea, "d"));
+        assert_eq!(textarea.mode(), Mode::Normal);
+        assert_eq!(textarea.lines, ["o(x)", "third"]);
+        assert!(!keys(&mut textarea, "v"));
+        assert_eq!(textarea.mode(), Mode::Visual);
+        textarea.input(key(Key::Esc));
+        assert!(textarea.selection().is_none());
+        assert!(!keys(&mut textarea, "0llvhy"));
+        assert_eq!(textarea.yank_text(), "(x");
+        assert_eq!(textarea.cursor(), (0, 1));
+        assert!(keys(&mut textarea, "vx"));
+        assert_eq!(textarea.lines, ["ox)", "third"]);
+
+        // `cw` changes to the end of the word and keeps the space after it
+        textarea.lines[0] = "foo bar".to_string();
+        textarea.cursor = (0, 1);
+        assert!(keys(&mut textarea, "cwx"));
+        textarea.input(key(Key::Esc));
+        assert_eq!(textarea.lines, ["fx bar", "third"]);
+        assert!(keys(&mut textarea, "lcwy"));
+        textarea.input(key(Key::Esc));
+        assert_eq!(textarea.lines, ["fxybar", "third"]);
+
+        // Open a line and disable modal editing
+        assert!(keys(&mut textarea, "oab"));
+        assert_eq!(textarea.lines, ["fxybar", "ab", "third"]);
+        textarea.set_modal_editing(false);
+        assert_eq!(textarea.mode(), Mode::Insert);
     }
 
-    /// Set the placeholder text. The text is set in the textarea when no text is input. Setting a non-empty string `""`
-    /// enables the placeholder. The default value is an empty string so the placeholder is disabled by default.
-    /// To customize the text style, see [`TextArea::set_placeholder_style`].
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    /// assert_eq!(textarea.placeholder_text(), "");
-    /// assert!(textarea.placeholder_style().is_none());
-    ///
-    /// textarea.set_placeholder_text("Hello");
-    /// assert_eq!(textarea.placeholder_text(), "Hello");
-    /// assert!(textarea.placeholder_style().is_some());
-    /// ```
-    pub fn set_placeholder_text(&mut self, placeholder: impl Into<String>) {
-        self.placeholder = placeholder.into();
+    #[test]
+    fn test_render_small_area() {
+        use crate::ratatui::buffer::Buffer;
+        use crate::ratatui::widgets::{Borders, Widget};
+
+        let mut textarea = TextArea::new(vec!["hello".into(), "world".into()], HashMap::new(), 140, THEME);
+        textarea.set_block(Block::default().borders(Borders::ALL));
+        textarea.set_line_number_style(Style::default());
+        assert_eq!(textarea.min_size(), (6, 3));
+        let render = |textarea: &TextArea, width, height| {
+            let area = Rect { x: 0, y: 0, width, height };
+            let mut buf = Buffer::empty(area);
+            textarea.widget().render(area, &mut buf);
+            let rows: Vec<String> = (0..height)
+                .map(|y| (0..width).map(|x| buf.get(x, y).symbol().to_string()).collect())
+                .collect();
+            rows
+        };
+
+        assert_eq!(render(&textarea, 6, 3), ["┌────┐", "│ 1 h│", "└────┘"]);
+        // The gutter is hidden when no text column is left
+        assert_eq!(render(&textarea, 5, 3), ["┌───┐", "│hel│", "└───┘"]);
+        // The block is suppressed when nothing is left inside it
+        assert_eq!(render(&textarea, 6, 2), [" 1 hel", " 2 wor"]);
+        assert_eq!(render(&textarea, 2, 1), ["he"]);
+        assert!(render(&textarea, 0, 0).is_empty());
+        assert_eq!(textarea.cursor_screen_position(Rect { x: 0, y: 0, width: 2, height: 1 }), Some((0, 0)));
     }
 
-    /// Set the style of the placeholder text. The default style is a dark gray text.
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    /// assert_eq!(textarea.placeholder_style(), None); // When the placeholder is disabled
-    ///
-    /// textarea.set_placeholder_text("Enter your message"); // Enable placeholder by setting non-empty text
-    ///
-    /// let style = Style::default().bg(Color::Blue);
-    /// textarea.set_placeholder_style(style);
-    /// assert_eq!(textarea.placeholder_style(), Some(style));
-    /// ```
-    pub fn set_placeholder_style(&mut self, style: Style) {
-        self.placeholder_style = style;
+    #[test]
+    fn test_render_block_status() {
+        use crate::ratatui::buffer::Buffer;
+        use crate::ratatui::widgets::{Borders, Widget};
+
+        let mut textarea = TextArea::new(vec!["hello".into()], HashMap::new(), 140, THEME);
+        textarea.set_block(Block::default().borders(Borders::ALL));
+        textarea.set_file_name("a.md");
+        textarea.set_block_title("{file}{modified}");
+        textarea.set_block_footer("{row}:{col}");
+        let render = |textarea: &TextArea| {
+            let area = Rect { x: 0, y: 0, width: 12, height: 3 };
+            let mut buf = Buffer::empty(area);
+            textarea.widget().render(area, &mut buf);
+            let rows: Vec<String> = (0..3)
+                .map(|y| (0..12).map(|x| buf.get(x, y).symbol().to_string()).collect())
+                .collect();
+            rows
+        };
+
+        assert_eq!(render(&textarea), ["┌a.md──────┐", "│hello     │", "└1:1───────┘"]);
+        textarea.move_cursor(CursorMove::End);
+        textarea.insert_char('!');
+        assert!(textarea.is_modified());
+        assert_eq!(render(&textarea), ["┌a.md[+]───┐", "│hello!    │", "└1:7───────┘"]);
+
+        textarea.set_modified(false);
+        textarea.set_block_footer("");
+        assert_eq!(render(&textarea), ["┌a.md──────┐", "│hello!    │", "└──────────┘"]);
     }
 
-    /// Get the placeholder text. An empty string means the placeholder is disabled. The default value is an empty string.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let textarea = TextArea::default();
-    /// assert_eq!(textarea.placeholder_text(), "");
-    /// ```
-    pub fn placeholder_text(&self) -> &'_ str {
-        self.placeholder.as_str()
+    #[test]
+    fn test_replace() {
+        let lines = vec!["see ab ab".to_string(), "ab".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 5))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        assert!(!textarea.replace_next("x"));
+        textarea.set_search_pattern("s(e+)").unwrap();
+
+        // The link after the replaced text is shifted
+        assert!(textarea.replace_next("S$1$1"));
+        assert_eq!(textarea.lines, ["Seeee ab ab", "ab"]);
+        assert_eq!(textarea.links[&0].range(), TextRange::new((0, 6), (0, 8)));
+        assert_eq!(textarea.cursor(), (0, 5));
+
+        textarea.set_search_pattern("ab").unwrap();
+        assert!(textarea.replace_next("a\nb"));
+        assert_eq!(textarea.lines, ["Seeee a", "b ab", "ab"]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["Seeee ab ab", "ab"]);
+
+        // The link in the replaced text is deleted, and all replacements are undone at once
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        assert_eq!(textarea.replace_all("xyz"), 3);
+        assert_eq!(textarea.lines, ["Seeee xyz xyz", "xyz"]);
+        assert!(textarea.links[&0].deleted);
+        assert_eq!(textarea.cursor(), (1, 1));
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["Seeee ab ab", "ab"]);
+        assert!(!textarea.links[&0].deleted);
+        assert_eq!(textarea.replace_all("$0"), 3);
     }
 
-    /// Get the placeholder style. When the placeholder text is empty, it returns `None` since the placeholder is disabled.
-    /// The default style is a dark gray text.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    /// assert_eq!(textarea.placeholder_style(), None);
-    ///
-    /// textarea.set_placeholder_text("hello");
-    /// assert!(textarea.placeholder_style().is_some());
-    /// ```
-    pub fn placeholder_style(&self) -> Option<Style> {
-        if self.placeholder.is_empty() {
-            None
-        } else {
-            Some(self.placeholder_style)
-        }
+    #[test]
+    fn test_auto_pairs() {
+        let new = |line: &str| {
+            let mut textarea = TextArea::new(vec![line.into()], HashMap::new(), 140, THEME);
+            textarea.set_auto_pairs(true);
+            textarea
+        };
+        let mut textarea = new("");
+        let type_str = |textarea: &mut TextArea, s: &str| {
+            for c in s.chars() {
+                textarea.input(Input { key: Key::Char(c), ..Input::default() });
+            }
+        };
+
+        type_str(&mut textarea, "f({\"");
+        assert_eq!(textarea.lines, ["f({\"\"})"]);
+        assert_eq!(textarea.cursor(), (0, 4));
+        type_str(&mut textarea, "a\"}) ");
+        assert_eq!(textarea.lines, ["f({\"a\"}) "]);
+        assert_eq!(textarea.cursor(), (0, 9));
+
+        // Undo removes the pair at once
+        type_str(&mut textarea, "(");
+        textarea.undo();
+        assert_eq!(textarea.lines, ["f({\"a\"}) "]);
+
+        // No pairing before a word nor for an apostrophe in a word
+        textarea = new("x");
+        type_str(&mut textarea, "(don't");
+        assert_eq!(textarea.lines, ["(don'tx"]);
+
+        // Closers typed without auto-pairing are inserted
+        textarea = new("");
+        type_str(&mut textarea, "))");
+        assert_eq!(textarea.lines, ["))"]);
+
+        // `[` and `]` over the inserted closer still create a link
+        textarea = new("");
+        type_str(&mut textarea, "see [ab]");
+        assert_eq!(textarea.lines, ["see [ab]"]);
+        assert_eq!(textarea.cursor(), (0, 8));
+        let mut plain = TextArea::new(vec!["".into()], HashMap::new(), 140, THEME);
+        type_str(&mut plain, "see [ab]");
+        let ranges = |t: &TextArea| t.links.values().map(|l| l.range()).collect::<Vec<_>>();
+        assert_eq!(ranges(&textarea), [TextRange::new((0, 4), (0, 8))]);
+        assert_eq!(ranges(&textarea), ranges(&plain));
+
+        textarea.set_auto_pairs(false);
+        type_str(&mut textarea, "(");
+        assert_eq!(textarea.lines, ["see [ab]("]);
     }
 
-    /// Specify a character masking the text. All characters in the textarea will be replaced by this character.
-    /// This API is useful for making a kind of credentials form such as a password input.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// textarea.set_mask_char('*');
-    /// assert_eq!(textarea.mask_char(), Some('*'));
-    /// textarea.set_mask_char('●');
-    /// assert_eq!(textarea.mask_char(), Some('●'));
-    /// ```
-    pub fn set_mask_char(&mut self, mask: char) {
-        self.mask = Some(mask);
+    #[test]
+    fn test_accessibility() {
+        let mut textarea = TextArea::new(vec!["".into()], HashMap::new(), 140, THEME);
+        let search = Style::default().bg(Color::Blue);
+        textarea.set_search_style(search);
+        let link = textarea.link_style;
+
+        textarea.set_accessibility(Accessibility::HighContrast);
+        assert_eq!(textarea.accessibility(), Accessibility::HighContrast);
+        assert!(textarea.search_style().add_modifier.contains(Modifier::BOLD));
+        assert!(textarea.link_style.add_modifier.contains(Modifier::UNDERLINED));
+
+        // Switching presets restores the styles of the application at last
+        textarea.set_accessibility(Accessibility::Monochrome);
+        assert_eq!(textarea.style().fg, Some(Color::Reset));
+        textarea.set_accessibility(Accessibility::Standard);
+        assert_eq!(textarea.search_style(), search);
+        assert_eq!(textarea.link_style, link);
+        assert_eq!(textarea.style().fg, Some(THEME.text));
     }
 
-    /// Clear the masking character previously set by [`TextArea::set_mask_char`].
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// textarea.set_mask_char('*');
-    /// assert_eq!(textarea.mask_char(), Some('*'));
-    /// textarea.clear_mask_char();
-    /// assert_eq!(textarea.mask_char(), None);
-    /// ```
-    pub fn clear_mask_char(&mut self) {
-        self.mask = None;
+    #[test]
+    fn test_duplicate() {
+        let lines = vec!["a [b] c".to_string(), "[d] e".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 2, 4)), (1, Link::new(1, 1, 0, 2))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        let ranges = |t: &TextArea| t.links.values().filter(|l| !l.deleted).map(|l| l.range()).collect::<Vec<_>>();
+
+        textarea.move_cursor(CursorMove::Jump(0, 3));
+        assert_eq!(textarea.duplicate_line(), BTreeMap::from([(0, 2)]));
+        assert_eq!(textarea.lines, ["a [b] c", "a [b] c", "[d] e"]);
+        assert_eq!(textarea.cursor(), (1, 3));
+        assert_eq!(
+            ranges(&textarea),
+            [
+                TextRange::new((0, 2), (0, 5)),
+                TextRange::new((2, 0), (2, 3)),
+                TextRange::new((1, 2), (1, 5)),
+            ]
+        );
+        textarea.undo();
+        assert_eq!(textarea.lines, ["a [b] c", "[d] e"]);
+        assert_eq!(ranges(&textarea).len(), 2);
+
+        // Links partially in the selection are not copied
+        textarea.move_cursor(CursorMove::Jump(0, 3));
+        textarea.extend_selection_to((1, 3));
+        assert_eq!(textarea.duplicate_selection(), BTreeMap::from([(1, 3)]));
+        assert_eq!(textarea.lines, ["a [b] c", "[d]b] c", "[d] e"]);
+        assert_eq!(textarea.selection(), Some(TextRange::new((1, 3), (2, 3))));
+        assert_eq!(textarea.links[&3].range(), TextRange::new((2, 0), (2, 3)));
     }
 
-    /// Get the character to mask text. When no character is set, `None` is returned.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// assert_eq!(textarea.mask_char(), None);
-    /// textarea.set_mask_char('*');
-    /// assert_eq!(textarea.mask_char(), Some('*'));
-    /// ```
-    pub fn mask_char(&self) -> Option<char> {
-        self.mask
+    #[test]
+    fn test_describe_state() {
+        let lines = vec!["ab".to_string(), "".to_string(), "cd".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.set_modal_editing(true);
+        textarea.move_cursor(CursorMove::Down);
+        assert_eq!(textarea.describe_state(), "Line 2 of 3, column 1. No selection. Normal mode. Blank line");
+
+        textarea.set_mode(Mode::Visual);
+        textarea.move_cursor(CursorMove::Bottom);
+        textarea.move_cursor(CursorMove::End);
+        assert_eq!(
+            textarea.describe_state(),
+            "Line 3 of 3, column 3. Selected 3 characters on 2 lines. Visual mode. Text: cd",
+        );
+        textarea.set_mode(Mode::Normal);
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Back);
+        assert!(textarea.describe_state().contains(" Selected 1 character. "));
     }
 
-    /// Set the style of cursor. By default, a cursor is rendered in the reversed color. Setting the same style as
-    /// cursor line hides a cursor.
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// let style = Style::default().bg(Color::Red);
-    /// textarea.set_cursor_style(style);
-    /// assert_eq!(textarea.cursor_style(), style);
-    /// ```
-    pub fn set_cursor_style(&mut self, style: Style) {
-        self.cursor_style = style;
+    #[test]
+    fn test_bell() {
+        use std::sync::{Arc, Mutex};
+
+        let mut textarea = TextArea::new(vec!["ab".into(), "c".into()], HashMap::new(), 140, THEME);
+        let rung = Arc::new(Mutex::new(vec![]));
+        let r = rung.clone();
+        textarea.set_bell_handler(move |reason| r.lock().unwrap().push(reason));
+        let take = || std::mem::take(&mut *rung.lock().unwrap());
+
+        textarea.move_cursor(CursorMove::Back);
+        textarea.move_cursor(CursorMove::Bottom);
+        textarea.move_cursor(CursorMove::Down);
+        assert_eq!(take(), [BellReason::BufferEdge, BellReason::BufferEdge]);
+
+        // Searching without a pattern does not ring
+        textarea.search_forward(false);
+        textarea.set_search_pattern("x").unwrap();
+        textarea.search_back(false);
+        assert_eq!(take(), [BellReason::NoMatch]);
+
+        textarea.insert_char('d');
+        textarea.redo();
+        textarea.undo();
+        textarea.undo();
+        assert_eq!(take(), [BellReason::NothingToRedo, BellReason::NothingToUndo]);
+
+        textarea.remove_bell_handler();
+        textarea.move_cursor(CursorMove::Down);
+        assert!(take().is_empty());
     }
 
-    /// Get the style of cursor.
-    pub fn cursor_style(&self) -> Style {
-        self.cursor_style
+    #[test]
+    fn test_sort_selection_lines() {
+        let lines = vec!["c [x]".to_string(), "a".to_string(), "b [y]".to_string(), "z".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 2, 4)), (1, Link::new(1, 2, 2, 4))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        assert!(!textarea.sort_selection_lines(true), "no selection");
+
+        // The selection ends at the head of the last line
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(3, 0));
+        assert!(textarea.sort_selection_lines(true));
+        assert_eq!(textarea.lines, ["a", "b [y]", "c [x]", "z"]);
+        assert_eq!((textarea.links[&0].row, textarea.links[&1].row), (2, 1));
+        assert_eq!(textarea.selection(), Some(TextRange::new((0, 0), (2, 5))));
+        assert!(!textarea.sort_selection_lines(true), "already sorted");
+
+        textarea.undo();
+        assert_eq!(textarea.lines, ["c [x]", "a", "b [y]", "z"]);
+        assert_eq!((textarea.links[&0].row, textarea.links[&1].row), (0, 2));
+        assert_eq!(textarea.in_link((2, 3)), Some(1));
+        textarea.redo();
+        assert_eq!(textarea.lines, ["a", "b [y]", "c [x]", "z"]);
+        assert_eq!(textarea.in_link((1, 3)), Some(1));
     }
 
-    /// Get slice of line texts. This method borrows the content, but not moves. Note that the returned slice will
-    /// never be empty because an empty text means a slice containing one empty line. This is correct since any text
-    /// file must end with a newline.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    /// assert_eq!(textarea.lines(), [""]);
-    ///
-    /// textarea.insert_char('a');
-    /// assert_eq!(textarea.lines(), ["a"]);
-    ///
-    /// textarea.insert_newline();
-    /// assert_eq!(textarea.lines(), ["a", ""]);
-    ///
-    /// textarea.insert_char('b');
-    /// assert_eq!(textarea.lines(), ["a", "b"]);
-    /// ```
-    pub fn lines(&'a self) -> &'a [String] {
-        &self.lines
+    #[test]
+    fn test_toggle_comment() {
+        let lines = vec!["[a] b".to_string(), "c".to_string(), "d".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 4));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(2, 0));
+
+        assert!(textarea.toggle_comment("// "));
+        assert_eq!(textarea.lines(), ["// [a] b", "// c", "d"]);
+        let link = textarea.links[&0];
+        assert_eq!((link.row, link.start_col, link.end_col), (0, 3, 5));
+        assert_eq!(textarea.selection_range().map(|(s, _)| (s.row, s.col)), Some((0, 7)));
+        assert_eq!(textarea.cursor(), (2, 0));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["[a] b", "c", "d"]);
+        assert_eq!(textarea.links[&0].start_col, 0);
+        assert!(textarea.redo());
+        textarea.move_cursor(CursorMove::Jump(0, 0));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+
+        assert!(textarea.toggle_comment("// "));
+        assert_eq!(textarea.lines(), ["[a] b", "c", "d"]);
+        let link = textarea.links[&0];
+        assert_eq!((link.start_col, link.end_col), (0, 2));
+
+        textarea.cancel_selection();
+        assert!(textarea.toggle_comment("# "));
+        assert_eq!(textarea.lines(), ["[a] b", "# c", "d"]);
+        assert!(!textarea.toggle_comment(""));
     }
 
-    /// Convert [`TextArea`] instance into line texts.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// textarea.insert_char('a');
-    /// textarea.insert_newline();
-    /// textarea.insert_char('b');
-    ///
-    /// assert_eq!(textarea.into_lines(), ["a", "b"]);
-    /// ```
-    pub fn into_lines(self) -> Vec<String> {
-        self.lines
+    #[test]
+    fn test_transform_selection() {
+        let lines = vec!["see [the note] now".to_string(), "and more".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 13))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        assert!(!textarea.transform_selection(Case::Upper));
+
+        textarea.move_cursor(CursorMove::Jump(0, 9));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(1, 3));
+        assert!(textarea.transform_selection(Case::Upper));
+        assert_eq!(textarea.lines(), ["see [the NOTE] NOW", "AND more"]);
+        let link = textarea.links[&0];
+        assert_eq!((link.row, link.start_col, link.end_col), (0, 4, 13));
+        assert_eq!(textarea.selection_range().map(|(s, e)| (s.col, e.col)), Some((9, 3)));
+        assert!(!textarea.transform_selection(Case::Upper));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["see [the note] now", "and more"]);
+        assert!(textarea.redo());
+        assert_eq!(textarea.lines(), ["see [the NOTE] NOW", "AND more"]);
+        assert_eq!(textarea.links[&0].end_col, 13);
     }
 
-    /// Get the current cursor position. 0-base character-wise (row, col) cursor position.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    /// assert_eq!(textarea.cursor(), (0, 0));
-    ///
-    /// textarea.insert_char('a');
-    /// textarea.insert_newline();
-    /// textarea.insert_char('b');
-    ///
-    /// assert_eq!(textarea.cursor(), (1, 1));
-    /// ```
-    pub fn cursor(&self) -> (usize, usize) {
-        self.cursor
+    #[test]
+    fn test_paragraph_ids() {
+        let lines = vec!["aa bb".to_string(), "cc".to_string(), "dd".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 9, THEME);
+        let ids = textarea.paragraphs().into_iter().map(|p| p.id).collect::<Vec<_>>();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(textarea.set_paragraph_meta(ids[1], "thread-1"), None);
+
+        // Hard wrapping makes the rows continue the paragraph
+        textarea.move_cursor(CursorMove::End);
+        for c in " ee ff".chars() {
+            textarea.insert_char(c);
+        }
+        assert_eq!(textarea.lines(), ["aa bb ", "ee ff cc", "dd"]);
+        let p = textarea.paragraphs();
+        assert_eq!(p.iter().map(|p| (p.id, p.rows.clone())).collect::<Vec<_>>(), [(ids[0], 0..2), (ids[2], 2..3)]);
+        assert_eq!(textarea.paragraph(ids[1]), None);
+
+        // Lines inserted above move the paragraphs
+        textarea.move_cursor(CursorMove::Top);
+        textarea.move_cursor(CursorMove::Head);
+        textarea.insert_newline();
+        let p = textarea.paragraph_at((3, 1)).unwrap();
+        assert_eq!((p.id, p.rows), (ids[2], 3..4));
+        assert_eq!(textarea.set_paragraph_meta(ids[2], "thread-2"), None);
+        assert_eq!(textarea.paragraph(ids[2]).unwrap().meta.as_deref(), Some("thread-2"));
+        // The paragraph split at its head keeps the ID with its text
+        let new = textarea.paragraph_at((0, 0)).unwrap().id;
+        assert!(!ids.contains(&new));
+        assert_eq!(textarea.paragraph(ids[0]).unwrap().rows, 1..3);
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.paragraph(ids[0]).unwrap().rows, 0..2);
+        assert_eq!(textarea.paragraph(ids[2]).unwrap().rows, 2..3);
+        assert_eq!(textarea.remove_paragraph_meta(ids[2]).as_deref(), Some("thread-2"));
+
+        let mut textarea = TextArea::from(["intro", "para two"]);
+        let id = textarea.paragraph_at((1, 0)).unwrap().id;
+        textarea.set_paragraph_meta(id, "thread");
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        textarea.insert_newline();
+        let p = textarea.paragraph(id).unwrap();
+        assert_eq!((p.rows, p.meta.as_deref()), (2..3, Some("thread")));
+        assert!(textarea.delete_char());
+        assert_eq!(textarea.lines(), ["intro", "para two"]);
+        assert_eq!(textarea.paragraph(id).unwrap().rows, 1..2);
     }
 
-    /// Set text alignment. When [`Alignment::Center`] or [`Alignment::Right`] is set, line number is automatically
-    /// disabled because those alignments don't work well with line numbers.
-    /// ```
-    /// use ratatui::layout::Alignment;
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// textarea.set_alignment(Alignment::Center);
-    /// assert_eq!(textarea.alignment(), Alignment::Center);
-    /// ```
-    pub fn set_alignment(&mut self, alignment: Alignment) {
-        if let Alignment::Center | Alignment::Right = alignment {
-            self.line_number_style = None;
+    #[test]
+    fn test_quote_aware_editing() {
+        let lines = vec!["> aa bb".to_string(), "after".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 10, THEME);
+        textarea.set_quote_aware(true);
+        textarea.move_cursor(CursorMove::End);
+
+        // Wrapped rows keep the prefix
+        for c in " cc dd".chars() {
+            textarea.insert_char(c);
         }
-        self.alignment = alignment;
+        assert_eq!(textarea.lines(), ["> aa bb ", "> cc dd", "after"]);
+        assert_eq!(textarea.cursor(), (1, 7));
+        assert_eq!(textarea.logical_lines(), ["> aa bb cc dd", "after"]);
+
+        // Enter continues the quote and is undone at once
+        textarea.insert_newline();
+        assert_eq!(textarea.lines(), ["> aa bb ", "> cc dd", "> ", "after"]);
+        assert_eq!(textarea.cursor(), (2, 2));
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["> aa bb ", "> cc dd", "after"]);
+        assert!(textarea.redo());
+
+        // Enter on an empty quote line ends the quote
+        textarea.move_cursor(CursorMove::Jump(2, 2));
+        textarea.insert_newline();
+        assert_eq!(textarea.lines(), ["> aa bb ", "> cc dd", "", "after"]);
+        assert_eq!(textarea.cursor(), (2, 0));
+
+        // Enter in the prefix is a plain newline
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        textarea.insert_newline();
+        assert_eq!(textarea.lines(), ["> aa bb ", "", "> cc dd", "", "after"]);
     }
 
-    /// Get current text alignment. The default alignment is [`Alignment::Left`].
-    /// ```
-    /// use ratatui::layout::Alignment;
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// assert_eq!(textarea.alignment(), Alignment::Left);
-    /// ```
-    pub fn alignment(&self) -> Alignment {
-        self.alignment
+    #[test]
+    fn test_quote_level() {
+        let lines = vec!["> a [b]".to_string(), "c".to_string(), "d".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 6))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 5));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+
+        assert!(textarea.increase_quote_level());
+        assert_eq!(textarea.lines(), ["> > a [b]", "> c", "d"]);
+        assert_eq!(textarea.links[&0].start_col, 6);
+        assert_eq!(textarea.cursor(), (1, 3));
+
+        assert!(textarea.decrease_quote_level());
+        assert!(textarea.decrease_quote_level());
+        assert_eq!(textarea.lines(), ["a [b]", "c", "d"]);
+        assert_eq!(textarea.links[&0].start_col, 2);
+        assert!(!textarea.decrease_quote_level());
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["> a [b]", "c", "d"]);
+        assert_eq!(textarea.links[&0].start_col, 4);
     }
 
-    /// Check if the textarea has a empty content.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let textarea = TextArea::default();
-    /// assert!(textarea.is_empty());
-    ///
-    /// let textarea = TextArea::from(["hello"]);
-    /// assert!(!textarea.is_empty());
-    /// ```
-    pub fn is_empty(&self) -> bool {
-        self.lines == [""]
+    #[test]
+    fn test_code_blocks() {
+        let lines = vec!["```".to_string(), "x".to_string(), "```".to_string(), "y".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 6, THEME);
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+
+        // Code is not hard wrapped
+        for c in " = [1, 2]".chars() {
+            textarea.insert_char(c);
+        }
+        assert_eq!(textarea.lines(), ["```", "x = [1, 2]", "```", "y"]);
+        assert_eq!(textarea.code_block_at(textarea.cursor()).map(|b| b.content_rows()), Some(1..2));
+        assert!(!textarea.rewrap_all());
+
+        // Outside the block, lines are wrapped
+        textarea.move_cursor(CursorMove::Jump(3, 1));
+        for c in " zz zz".chars() {
+            textarea.insert_char(c);
+        }
+        assert_eq!(textarea.lines()[3..], ["y zz ", "zz"]);
+        assert_eq!(textarea.code_block_at((3, 0)), None);
+        assert_eq!(textarea.code_blocks().len(), 1);
+
+        // Cached fence states follow edits of the fences
+        textarea.move_cursor(CursorMove::Jump(2, 0));
+        textarea.delete_next_char();
+        assert_eq!(textarea.code_block_at((3, 0)).map(|b| (b.start, b.closed)), Some((0, false)));
+        assert!(textarea.undo());
+        assert_eq!(textarea.code_block_at((3, 0)), None);
+        assert_eq!(textarea.code_block_at((2, 0)).map(|b| (b.start, b.end)), Some((0, 2)));
+
+        // URLs in code are not highlighted
+        textarea.set_url_detection(true);
+        textarea.move_cursor(CursorMove::Jump(3, 0));
+        textarea.insert_str("```\na http://x.y\n", (3, 0));
+        let spans = |textarea: &TextArea| textarea.styled_lines(4..5)[0].spans.len();
+        assert_eq!(spans(&textarea), 1);
+        textarea.move_cursor(CursorMove::Jump(0, 0));
+        textarea.delete_line_by_end();
+        assert_eq!(textarea.lines()[..5], ["", "x = [1, 2]", "```", "```", "a http://x.y"]);
+        assert!(spans(&textarea) > 1);
     }
 
-    /// Get the yanked text. Text is automatically yanked when deleting strings by [`TextArea::delete_line_by_head`],
-    /// [`TextArea::delete_line_by_end`], [`TextArea::delete_word`], [`TextArea::delete_next_word`],
-    /// [`TextArea::delete_str`], [`TextArea::copy`], and [`TextArea::cut`]. When multiple lines were yanked, they are
-    /// always joined with `\n`.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::from(["abc"]);
-    /// textarea.delete_next_word();
-    /// assert_eq!(textarea.yank_text(), "abc");
-    ///
-    /// // Multiple lines are joined with \n
-    /// let mut textarea = TextArea::from(["abc", "def"]);
-    /// textarea.delete_str(5);
-    /// assert_eq!(textarea.yank_text(), "abc\nd");
-    /// ```
-    pub fn yank_text(&self) -> String {
-        self.yank.to_string()
+    #[test]
+    fn test_indent_selection_with_tab() {
+        let lines = vec!["a [b]".to_string(), "  c".to_string(), "d".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 2, 4))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        let key = |key, shift| Input { key, ctrl: false, alt: false, shift, kind: InputKind::Press };
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+
+        assert!(textarea.input(key(Key::Tab, false)));
+        assert_eq!(textarea.lines(), ["    a [b]", "      c", "d"]);
+        let link = textarea.links[&0];
+        assert_eq!((link.start_col, link.end_col), (6, 8));
+        assert_eq!(textarea.cursor(), (1, 5));
+
+        assert!(textarea.input(key(Key::BackTab, false)));
+        assert!(textarea.input(key(Key::Tab, true)));
+        assert_eq!(textarea.lines(), ["a [b]", "c", "d"]);
+        assert_eq!(textarea.links[&0].start_col, 2);
+        assert!(!textarea.dedent_lines());
+
+        // Without selection, Tab inserts spaces and Shift+Tab dedents the cursor line
+        textarea.cancel_selection();
+        textarea.move_cursor(CursorMove::Jump(2, 0));
+        assert!(textarea.input(key(Key::Tab, false)));
+        assert_eq!(textarea.lines()[2], "    d");
+        assert!(textarea.input(key(Key::BackTab, false)));
+        assert_eq!(textarea.lines()[2], "d");
     }
 
-    /// Set a yanked text. The text can be inserted by [`TextArea::paste`]. `\n` and `\r\n` are recognized as newline
-    /// but `\r` isn't.
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// textarea.set_yank_text("hello\nworld");
-    /// textarea.paste();
-    /// assert_eq!(textarea.lines(), ["hello", "world"]);
-    /// ```
-    pub fn set_yank_text(&mut self, text: impl Into<String>) {
-        // `str::lines` is not available since it strips a newline at end
-        let lines: Vec<_> = text
-            .into()
-            .split('\n')
-            .map(|s| s.strip_suffix('\r').unwrap_or(s).to_string())
-            .collect();
-        self.yank = YankText::Chunk((lines, None, (0, 0)));
+    #[test]
+    fn test_format_selection_with() {
+        let lines = vec!["see [a] x  y [b]".to_string(), "z".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 7)), (1, Link::new(1, 0, 13, 16))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        let squeeze = |s: &str| -> Result<String, ()> { Ok(s.replace("  ", " ")) };
+
+        assert_eq!(textarea.format_selection_with(squeeze), Ok(true));
+        assert_eq!(textarea.lines(), ["see [a] x y [b]", "z"]);
+        let spans = |t: &TextArea| t.links.values().filter(|l| !l.deleted).map(|l| (l.row, l.start_col, l.end_col)).collect::<Vec<_>>();
+        assert_eq!(spans(&textarea), [(0, 4, 7), (0, 12, 15)]);
+        assert_eq!(textarea.cursor(), (1, 1));
+        assert_eq!(textarea.format_selection_with(squeeze), Ok(false));
+        assert_eq!(textarea.format_selection_with(|_| Err(())), Err(()));
+        assert_eq!(textarea.lines(), ["see [a] x y [b]", "z"]);
+
+        // Only the selection is passed and the link in the changed text is deleted
+        textarea.move_cursor(CursorMove::Jump(0, 4));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        let mut passed = String::new();
+        let capitalize = |s: &str| -> Result<String, ()> {
+            passed = s.to_string();
+            Ok(s.replace('a', "A"))
+        };
+        assert_eq!(textarea.format_selection_with(capitalize), Ok(true));
+        assert_eq!(passed, "[a] x y [b]\n");
+        assert_eq!(textarea.lines(), ["see [A] x y [b]", "z"]);
+        assert_eq!(spans(&textarea), [(0, 12, 15)]);
+        assert_eq!(textarea.selection(), Some(TextRange::new((0, 4), (1, 0))));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["see [a] x y [b]", "z"]);
+        assert_eq!(spans(&textarea), [(0, 4, 7), (0, 12, 15)]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["see [a] x  y [b]", "z"]);
     }
 
-    /// Set a regular expression pattern for text search. Setting an empty string stops the text search.
-    /// When a valid pattern is set, all matches will be highlighted in the textarea. Note that the cursor does not
-    /// move. To move the cursor, use [`TextArea::search_forward`] and [`TextArea::search_back`].
-    ///
-    /// Grammar of regular expression follows [regex crate](https://docs.rs/regex/latest/regex). Patterns don't match
-    /// to newlines so match passes across no newline.
-    ///
-    /// When the pattern is invalid, the search pattern will not be updated and an error will be returned.
-    ///
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::from(["hello, world", "goodbye, world"]);
-    ///
-    /// // Search "world"
-    /// textarea.set_search_pattern("world").unwrap();
-    ///
-    /// assert_eq!(textarea.cursor(), (0, 0));
-    /// textarea.search_forward(false);
-    /// assert_eq!(textarea.cursor(), (0, 7));
-    /// textarea.search_forward(false);
-    /// assert_eq!(textarea.cursor(), (1, 9));
-    ///
-    /// // Stop the text search
-    /// textarea.set_search_pattern("");
-    ///
-    /// // Invalid search pattern
-    /// assert!(textarea.set_search_pattern("(hello").is_err());
-    /// ```
+    #[test]
+    fn test_hop_targets_after_edit() {
+        let mut textarea = TextArea::from(["hello foo"]);
+        textarea.set_hop_pattern("hello").unwrap();
+        textarea.init_hop();
+        textarea.move_cursor(CursorMove::End);
+        textarea.delete_line_by_head();
+        assert_eq!(textarea.hop_state(), HopState::Pending);
+        textarea.insert_str("ééé hello", (0, 0));
+        let target = textarea.hop_targets()[0];
+        assert_eq!((target.row, target.start_col, target.end_col), (0, 4, 9));
+        let line = textarea.styled_lines(0..1).remove(0);
+        let text = line.spans.iter().map(|s| s.content.as_ref()).collect::<String>();
+        assert!(text.starts_with("ééé 11"), "{:?}", text);
+    }
 
-    pub fn set_search_pattern(&mut self, query: impl AsRef<str>) -> Result<(), regex::Error> {
-        self.search.set_pattern(query.as_ref())
+    #[test]
+    fn test_replace_next_found_match() {
+        let mut textarea = TextArea::from(["abab"]);
+        textarea.set_search_pattern("ab|b").unwrap();
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        assert!(textarea.replace_next("X"));
+        assert_eq!(textarea.lines(), ["aXab"]);
+
+        let mut textarea = TextArea::from(["aaa"]);
+        textarea.set_search_pattern("(a)a").unwrap();
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        assert!(textarea.replace_next("<$1>"));
+        assert_eq!(textarea.lines(), ["a<a>"]);
     }
 
-    /// Get a regular expression which was set by [`TextArea::set_search_pattern`]. When no text search is ongoing, this
-    /// method returns `None`.
-    ///
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// assert!(textarea.search_pattern().is_none());
-    /// textarea.set_search_pattern("hello+").unwrap();
-    /// assert!(textarea.search_pattern().is_some());
-    /// assert_eq!(textarea.search_pattern().unwrap().as_str(), "hello+");
-    /// ```
+    #[test]
+    fn test_enter_behavior_submit() {
+        let mut textarea = TextArea::new(vec!["abc".into()], HashMap::new(), 140, THEME);
+        let enter = |shift| Input { key: Key::Enter, ctrl: false, alt: false, shift, kind: InputKind::Press };
+
+        textarea.move_cursor(CursorMove::End);
+        assert_eq!(textarea.input_result(enter(false)), InputResult::Changed);
+        assert_eq!(textarea.lines, ["abc", ""]);
 
-    pub fn search_pattern(&self) -> Option<&regex::Regex> {
-        self.search.pat.as_ref()
+        textarea.set_enter_behavior(EnterBehavior::Submit);
+        assert_eq!(textarea.input_result(enter(false)), InputResult::Submitted);
+        assert!(!textarea.input(enter(false)));
+        assert_eq!(textarea.lines, ["abc", ""]);
+        assert_eq!(textarea.input_result(enter(true)), InputResult::Changed);
+        assert_eq!(textarea.lines, ["abc", "", ""]);
+
+        let ctrl_m = Input { key: Key::Char('m'), ctrl: true, alt: false, shift: false, kind: InputKind::Press };
+        assert_eq!(textarea.input_result(ctrl_m), InputResult::Submitted);
+        assert_eq!(textarea.lines, ["abc", "", ""]);
+        textarea.set_enter_behavior(EnterBehavior::Newline);
+        assert_eq!(textarea.input_result(ctrl_m), InputResult::Changed);
+        assert_eq!(textarea.lines, ["abc", "", "", ""]);
     }
 
-    pub fn clear_search(&mut self) {
-        self.search.clear_pattern();
+    #[test]
+    fn test_input_ext_effects() {
+        let mut textarea = TextArea::new(vec!["abc def".into()], HashMap::new(), 140, THEME);
+        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 4, end_col: 6, edited: false, deleted: false });
+        textarea.next_link_id = 1;
+        let input = |key, ctrl| Input { key, ctrl, alt: false, shift: false, kind: InputKind::Press };
+
+        let effects = textarea.input_ext(input(Key::Right, true));
+        assert!(effects.cursor_moved);
+        assert_eq!(effects.link_entered, Some(0));
+
+        let effects = textarea.input_ext(input(Key::Char('e'), true));
+        assert_eq!(effects.link_entered, None);
+
+        let effects = textarea.input_ext(input(Key::Char('a'), true));
+        assert!(effects.cursor_moved && !effects.text_changed);
+
+        let effects = textarea.input_ext(input(Key::Char('x'), false));
+        assert!(effects.text_changed && effects.cursor_moved);
+        assert!(!effects.link_created && !effects.link_deleted);
+
+        assert!(textarea.input_ext(input(Key::Esc, false)).is_empty());
     }
 
-    pub fn clear_lines(&mut self) {
-        self.lines = vec!["".to_owned()];
+    #[test]
+    fn test_compose_sequences() {
+        let mut textarea = TextArea::new(vec!["".into()], HashMap::new(), 140, THEME);
+        textarea.set_compose_table(Some(ComposeTable::default()));
+        let input = |c| Input { key: Key::Char(c), ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+
+        assert_eq!(textarea.input_result(input('^')), InputResult::Unchanged);
+        assert!(textarea.input(input('o')));
+        // Not a sequence
+        assert!(!textarea.input(input('\'')));
+        assert!(textarea.input(input('x')));
+        // Same dead key twice
+        textarea.input(input('`'));
+        textarea.input(input('`'));
+        // Other key flushes the dead key
+        textarea.input(input('~'));
+        textarea.input(Input { key: Key::Left, ctrl: false, alt: false, shift: false, kind: InputKind::Press });
+        assert_eq!(textarea.lines, ["ô'x`~"]);
+        assert_eq!(textarea.cursor, (0, 4));
+        assert_eq!(textarea.pending_compose(), None);
     }
 
-    /// Search the pattern set by [`TextArea::set_search_pattern`] forward and move the cursor to the next match
-    /// position based on the current cursor position. Text search wraps around a text buffer. It returns `true` when
-    /// some match was found. Otherwise it returns `false`.
-    ///
-    /// The `match_cursor` parameter represents if the search matches to the current cursor position or not. When `true`
-    /// is set and the cursor position matches to the pattern, the cursor will not move. When `false`, the cursor will
-    /// move to the next match ignoring the match at the current position.
-    ///
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::from(["hello", "helloo", "hellooo"]);
-    ///
-    /// textarea.set_search_pattern("hello+").unwrap();
-    ///
-    /// // Move to next position
-    /// let match_found = textarea.search_forward(false);
-    /// assert!(match_found);
-    /// assert_eq!(textarea.cursor(), (1, 0));
-    ///
-    /// // Since the cursor position matches to "hello+", it does not move
-    /// textarea.search_forward(true);
-    /// assert_eq!(textarea.cursor(), (1, 0));
-    ///
-    /// // When `match_current` parameter is set to `false`, match at the cursor position is ignored
-    /// textarea.search_forward(false);
-    /// assert_eq!(textarea.cursor(), (2, 0));
-    ///
-    /// // Text search wrap around the buffer
-    /// textarea.search_forward(false);
-    /// assert_eq!(textarea.cursor(), (0, 0));
-    ///
-    /// // `false` is returned when no match was found
-    /// textarea.set_search_pattern("bye+").unwrap();
-    /// let match_found = textarea.search_forward(false);
-    /// assert!(!match_found);
-    /// ```
+    #[test]
+    fn test_input_kind_filtering() {
+        let mut textarea = TextArea::new(vec!["".into()], HashMap::new(), 140, THEME);
+        let input = |key, kind| Input { key, ctrl: false, alt: false, shift: false, kind };
 
-    pub fn search_forward(&mut self, match_cursor: bool) -> bool {
-        if let Some(cursor) = self.search.forward(&self.lines, self.cursor, match_cursor) {
-            self.cursor = cursor;
-            true
-        } else {
-            false
-        }
+        assert!(textarea.input(input(Key::Char('a'), InputKind::Press)));
+        assert!(!textarea.input(input(Key::Char('a'), InputKind::Release)));
+        assert!(textarea.input(input(Key::Char('a'), InputKind::Repeat)));
+        assert_eq!(textarea.lines, ["aa"]);
+
+        textarea.set_repeat_behavior(RepeatBehavior::Ignore);
+        assert!(!textarea.input(input(Key::Char('a'), InputKind::Repeat)));
+        assert_eq!(textarea.lines, ["aa"]);
+
+        textarea.set_repeat_behavior(RepeatBehavior::Accelerate(2));
+        textarea.input(input(Key::Left, InputKind::Repeat));
+        assert_eq!(textarea.cursor, (0, 0));
+        assert!(textarea.input(input(Key::Char('b'), InputKind::Repeat)));
+        assert_eq!(textarea.lines, ["baa"]);
     }
 
-    /// Search the pattern set by [`TextArea::set_search_pattern`] backward and move the cursor to the next match
-    /// position based on the current cursor position. Text search wraps around a text buffer. It returns `true` when
-    /// some match was found. Otherwise it returns `false`.
-    ///
-    /// The `match_cursor` parameter represents if the search matches to the current cursor position or not. When `true`
-    /// is set and the cursor position matches to the pattern, the cursor will not move. When `false`, the cursor will
-    /// move to the next match ignoring the match at the current position.
-    ///
-    /// ```
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::from(["hello", "helloo", "hellooo"]);
-    ///
-    /// textarea.set_search_pattern("hello+").unwrap();
-    ///
-    /// // Move to next position with wrapping around the text buffer
-    /// let match_found = textarea.search_back(false);
-    /// assert!(match_found);
-    /// assert_eq!(textarea.cursor(), (2, 0));
-    ///
-    /// // Since the cursor position matches to "hello+", it does not move
-    /// textarea.search_back(true);
-    /// assert_eq!(textarea.cursor(), (2, 0));
-    ///
-    /// // When `match_current` parameter is set to `false`, match at the cursor position is ignored
-    /// textarea.search_back(false);
-    /// assert_eq!(textarea.cursor(), (1, 0));
-    ///
-    /// // `false` is returned when no match was found
-    /// textarea.set_search_pattern("bye+").unwrap();
-    /// let match_found = textarea.search_back(false);
-    /// assert!(!match_found);
-    /// ```
+    #[test]
+    fn test_input_recording_replay() {
+        let mut textarea = TextArea::new(vec!["abc".into(), "def".into()], HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        textarea.enable_input_recording();
+        let input = |key, ctrl| Input { key, ctrl, alt: false, shift: false, kind: InputKind::Press };
+
+        textarea.viewport.store(0, 0, 10, 1);
+        textarea.input(input(Key::Char('x'), false));
+        textarea.input(input(Key::PageUp, false));
+        textarea.input_without_shortcuts(input(Key::Char('y'), false));
+        textarea.input(input(Key::Char('k'), true));
+
+        let recording = textarea.disable_input_recording().unwrap();
+        assert!(textarea.input_recording().is_none());
+        assert_eq!(recording.inputs().len(), 4);
+        assert!(!recording.inputs()[2].shortcuts);
+        assert_eq!(recording.inputs()[1].viewport, (0, 0, 10, 1));
+
+        let replayed = TextArea::replay(&recording);
+        assert_eq!(replayed.lines, textarea.lines);
+        assert_eq!(replayed.cursor, textarea.cursor);
+        assert!(replayed.input_recording().is_none());
+    }
 
-    pub fn search_back(&mut self, match_cursor: bool) -> bool {
-        if let Some(cursor) = self.search.back(&self.lines, self.cursor, match_cursor) {
-            self.cursor = cursor;
-            true
-        } else {
-            false
+    #[test]
+    fn test_apply_op_invariants() {
+        let mut textarea = TextArea::new(vec!["hello".into(), "world".into()], HashMap::new(), 140, THEME);
+        let ops = [
+            Op::Move(CursorMove::End),
+            Op::InsertStr(" there\nnew".into()),
+            Op::StartLink,
+            Op::Move(CursorMove::Head),
+            Op::Move(CursorMove::WordForward),
+            Op::InsertLink,
+            Op::SelectAll,
+            Op::Copy,
+            Op::Move(CursorMove::Bottom),
+            Op::Paste,
+            Op::Undo,
+            Op::Undo,
+            Op::Redo,
+            Op::DeleteLine(true),
+            Op::Scroll(1, 0),
+            Op::DeleteLink(42),
+        ];
+        for op in ops {
+            let op_str = format!("{:?}", op);
+            textarea.apply_op(op);
+            if let Err(err) = textarea.check_invariants() {
+                panic!("invariant broken after {}: {}", op_str, err);
+            }
         }
+
+        textarea.cursor = (9, 0);
+        assert!(matches!(
+            textarea.check_invariants(),
+            Err(InvariantViolation::CursorOutOfBounds { .. }),
+        ));
     }
 
-    /// Get the text style at matches of text search. The default style is colored with blue in background.
-    ///
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let textarea = TextArea::default();
-    ///
-    /// assert_eq!(textarea.search_style(), Style::default().bg(Color::Blue));
-    /// ```
+    #[test]
+    fn test_undo_redo_link_ops() {
+        let mut textarea = TextArea::new(vec!["abc def".into()], HashMap::new(), 140, THEME);
+        textarea.init_link();
+        textarea.move_cursor(CursorMove::Jump(0, 2));
+        textarea.insert_link();
+        assert_eq!(textarea.links.len(), 1);
+
+        assert!(textarea.undo());
+        assert!(textarea.links[&0].deleted);
+        // Undoing the creation reports the link as deleted and redoing it takes the report back
+        assert_eq!(textarea.deleted_link_ids, [0]);
+        assert!(textarea.redo());
+        assert!(!textarea.links[&0].deleted);
+        assert!(textarea.deleted_link_ids.is_empty());
+        assert_eq!((textarea.links[&0].start_col, textarea.links[&0].end_col), (0, 2));
+
+        textarea.delete_link(0);
+        assert!(textarea.links[&0].deleted);
+        assert_eq!(textarea.deleted_link_ids, [0]);
+        assert!(textarea.undo());
+        assert!(!textarea.links[&0].deleted);
+        assert!(textarea.deleted_link_ids.is_empty());
+        assert_eq!(textarea.lines, ["abc def"]);
+        assert_eq!(textarea.cursor, (0, 2));
+
+        // The restored link is not left marked as edited and shifts with the following edits
+        assert!(!textarea.links[&0].edited);
+        textarea.move_cursor(CursorMove::Head);
+        textarea.insert_char('x');
+        assert_eq!((textarea.links[&0].start_col, textarea.links[&0].end_col), (1, 3));
+    }
 
-    pub fn search_style(&self) -> Style {
-        self.search.style
+    #[test]
+    fn test_undo_restores_selection_and_scroll() {
+        let lines = (0..20).map(|i| i.to_string()).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.viewport.store(8, 0, 10, 5);
+        textarea.move_cursor(CursorMove::Jump(10, 0));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(11, 1));
+        assert!(textarea.cut());
+        assert_eq!(textarea.edit_selection, None);
+
+        // An edit after the deletion does not restore the selection
+        textarea.insert_char('x');
+        assert!(textarea.undo());
+        assert!(!textarea.is_selecting());
+
+        textarea.viewport.store(0, 0, 10, 5);
+        textarea.move_cursor(CursorMove::Top);
+        assert!(textarea.undo());
+        assert_eq!(textarea.viewport.scroll_top(), (8, 0));
+        assert_eq!(textarea.cursor, (11, 1));
+        assert!(textarea.is_selecting());
+        assert_eq!(textarea.lines[10], "10");
+
+        textarea.viewport.store(0, 0, 10, 5);
+        assert!(textarea.redo());
+        assert_eq!(textarea.viewport.scroll_top(), (8, 0));
+        assert!(!textarea.is_selecting());
+        assert_eq!(textarea.cursor, (10, 0));
     }
 
-    /// Set the text style at matches of text search. The default style is colored with blue in background.
-    ///
-    /// ```
-    /// use ratatui::style::{Style, Color};
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// let mut textarea = TextArea::default();
-    ///
-    /// let red_bg = Style::default().bg(Color::Red);
-    /// textarea.set_search_style(red_bg);
-    ///
-    /// assert_eq!(textarea.search_style(), red_bg);
-    /// ```
+    #[test]
+    fn test_session_state_round_trip() {
+        let mut textarea = TextArea::new(vec!["abc".into(), "defgh".into()], HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(1, 4));
+        textarea.set_mark('x');
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Head);
+        textarea.set_search_pattern("de").unwrap();
+        let state = textarea.session_state();
+        assert_eq!(state.selection_start, Some((1, 4)));
+
+        // Text was shortened after the state was saved
+        let mut reopened = TextArea::new(vec!["abc".into(), "de".into()], HashMap::new(), 140, THEME);
+        reopened.restore_session_state(&state).unwrap();
+        assert_eq!(reopened.cursor, (1, 0));
+        assert_eq!(reopened.selection_start, Some((1, 2)));
+        assert_eq!(reopened.search_pattern().unwrap().as_str(), "de");
+        assert!(reopened.jump_to_mark('x'));
+        assert_eq!(reopened.cursor, (1, 2));
+        assert_eq!(reopened.mark('x'), Some((1, 4)));
+    }
 
-    pub fn set_search_style(&mut self, style: Style) {
-        self.search.style = style;
+    #[test]
+    fn test_set_viewport_offset() {
+        let lines = (0..10).map(|i| "x".repeat(i)).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.viewport.store(0, 0, 4, 3);
+
+        assert_eq!(textarea.set_viewport_offset(5, 2), (5, 2));
+        assert_eq!(textarea.viewport_offset(), (5, 2));
+        assert_eq!(textarea.viewport_size(), (4, 3));
+        assert_eq!(textarea.cursor, (5, 2));
+
+        assert_eq!(textarea.set_viewport_offset(50, 50), (9, 9));
+        assert_eq!(textarea.cursor, (9, 9));
     }
 
-    pub fn hop_pattern(&self) -> Option<&regex::Regex> {
-        self.hop.pat.as_ref()
+    #[test]
+    fn test_key_bindings_before_builtin() {
+        let lines = (0..20).map(|i| i.to_string()).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.viewport.store(0, 0, 10, 8);
+        let ctrl = |c| Input { key: Key::Char(c), ctrl: true, alt: false, shift: false, kind: InputKind::Press };
+
+        // The default keymap keeps the built-in mappings (delete next char and undo)
+        assert!(textarea.input(ctrl('d')));
+        assert_eq!(textarea.lines[0], "");
+        assert!(textarea.input(ctrl('u')));
+        assert_eq!(textarea.lines[0], "0");
+        textarea.move_cursor(CursorMove::Jump(0, 0));
+
+        textarea.key_bindings_mut().bind_half_page_scroll();
+        assert!(!textarea.input(ctrl('d')));
+        assert_eq!(textarea.cursor, (4, 0));
+        assert!(!textarea.input(ctrl('u')));
+        assert_eq!(textarea.viewport_offset(), (0, 0));
+
+        textarea.insert_char('x');
+        assert!(textarea.input(ctrl('z')));
+        assert_eq!(textarea.lines[4], "4");
+
+        // Fall back to the built-in mapping (delete next char)
+        textarea.set_key_bindings(KeyBindings::empty());
+        assert!(textarea.input(ctrl('d')));
+        assert_eq!(textarea.lines[4], "");
     }
 
-    pub fn clear_hop(&mut self) {
-        self.hopping = false;
-        self.hop_pending = false;
-        self.hop.clear_pattern();
-        self.hop.clear_match_indexes();
-        self.hop.reset_count();
+    #[test]
+    fn test_keymap_presets() {
+        let mut textarea = TextArea::new(vec!["abc def".into()], HashMap::new(), 140, THEME);
+        let input = |key, ctrl, shift| Input { key, ctrl, alt: false, shift, kind: InputKind::Press };
+
+        textarea.set_keymap(Keymap::Standard);
+        // Built-in Emacs mappings are not handled
+        assert!(!textarea.input(input(Key::Char('k'), true, false)));
+        assert_eq!(textarea.lines, ["abc def"]);
+        textarea.input(input(Key::Right, true, true));
+        assert_eq!(textarea.selection_snapshot(), Some(((0, 0), (0, 4))));
+        assert!(textarea.input(input(Key::Char('x'), true, false)));
+        assert_eq!(textarea.lines, ["def"]);
+        assert!(textarea.input(input(Key::Char('z'), true, false)));
+        assert!(textarea.input(input(Key::Char('z'), true, true)));
+        assert_eq!(textarea.lines, ["def"]);
+        assert!(textarea.input(input(Key::Char('a'), false, false)));
+        assert_eq!(textarea.lines, ["adef"]);
+
+        textarea.set_keymap(Keymap::Emacs);
+        assert!(textarea.input(input(Key::Char('k'), true, false)));
+        assert_eq!(textarea.lines, ["a"]);
     }
 
-    pub fn init_hop(&mut self) {
-        self.hopping = true;
-        self.hop_pending = true;
+    #[test]
+    fn test_scroll_progress() {
+        let lines = (0..30).map(|i| i.to_string()).collect();
+        let textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+
+        // Before rendering, the viewport has no height
+        assert_eq!(textarea.lines_below_viewport(), 30);
+
+        textarea.viewport.store(0, 0, 10, 10);
+        assert_eq!(textarea.scroll_progress(), 0.0);
+        assert_eq!(textarea.lines_below_viewport(), 20);
+
+        textarea.viewport.store(15, 0, 10, 10);
+        assert_eq!(textarea.scroll_progress(), 0.75);
+        assert_eq!(textarea.lines_below_viewport(), 5);
+
+        textarea.viewport.store(25, 0, 10, 10);
+        assert_eq!(textarea.scroll_progress(), 1.0);
+        assert_eq!(textarea.lines_below_viewport(), 0);
+
+        // The whole text fits in the viewport
+        textarea.viewport.store(0, 0, 10, 40);
+        assert_eq!(textarea.scroll_progress(), 1.0);
+        assert_eq!(textarea.lines_below_viewport(), 0);
     }
 
-    pub fn set_hop_pattern(&mut self, query: impl AsRef<str>) -> Result<(), regex::Error> {
-        self.hop.set_pattern(query.as_ref())
+    #[test]
+    fn test_recenter_viewport_keys() {
+        let lines = (0..50).map(|i| i.to_string()).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.viewport.store(0, 0, 10, 10);
+        textarea.move_cursor(CursorMove::Jump(8, 0));
+        let key = |c, ctrl, alt| Input { key: Key::Char(c), ctrl, alt, shift: false, kind: InputKind::Press };
+
+        assert!(!textarea.input(key('t', false, true)));
+        assert_eq!(textarea.viewport_offset(), (8, 0));
+        assert!(!textarea.input(key('l', true, false)));
+        assert_eq!(textarea.viewport_offset(), (3, 0));
+        assert!(!textarea.input(key('e', false, true)));
+        assert_eq!(textarea.viewport_offset(), (0, 0));
+        assert_eq!(textarea.cursor, (8, 0));
     }
 
-    pub fn hop_style(&self) -> Style {
-        self.search.style
+    #[test]
+    fn test_smooth_scroll() {
+        let lines = (0..50).map(|i| i.to_string()).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.viewport.store(0, 0, 10, 10);
+        textarea.set_smooth_scroll(true);
+        assert!(!textarea.tick());
+
+        textarea.scroll(Scrolling::PageDown);
+        textarea.scroll(Scrolling::PageDown);
+        assert!(textarea.is_scrolling());
+        assert_eq!(textarea.viewport_offset(), (0, 0));
+
+        let mut rows = vec![];
+        while textarea.tick() {
+            rows.push(textarea.viewport_offset().0);
+        }
+        assert_eq!(rows, [10, 15, 17, 18, 19, 20]);
+        assert_eq!(textarea.cursor, (20, 0));
+        assert!(!textarea.is_scrolling());
+
+        // The target is clamped to the last line
+        textarea.scroll((100, 0));
+        // Disabling smooth scrolling jumps to the target
+        textarea.set_smooth_scroll(false);
+        assert_eq!(textarea.viewport_offset(), (49, 0));
+        assert_eq!(textarea.cursor, (49, 0));
+        assert!(!textarea.is_scrolling());
     }
 
-    pub fn hop_to_idx(&mut self, idx: usize) {
-        info!("hop_to_idx::self.hop: {:?}", self.hop);
-        if let Some(match_idx) = self.hop.match_indexes.borrow().iter().find(|mi| mi.idx == idx) {
-            info!("hop_to_idx: found match! {:?}", match_idx);
-            self.cursor = match_idx.pos;
-        } 
+    #[test]
+    fn test_cursor_screen_position() {
+        let lines = vec!["\tあい".to_string(), "abc".to_string(), "def".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        let area = Rect {
+            x: 3,
+            y: 2,
+            width: 20,
+            height: 2,
+        };
+        textarea.viewport.store(0, 0, 20, 2);
+
+        textarea.move_cursor(CursorMove::Jump(0, 2));
+        assert_eq!(textarea.cursor_screen_position(area), Some((3 + 4 + 2, 2)));
+        textarea.set_line_number_style(Style::default());
+        assert_eq!(textarea.cursor_screen_position(area), Some((3 + 3 + 4 + 2, 2)));
+
+        // Outside the viewport
+        textarea.move_cursor(CursorMove::Jump(2, 0));
+        assert_eq!(textarea.cursor_screen_position(area), None);
+        assert_eq!(textarea.popup_area(area, (4, 1)), None);
+        textarea.viewport.store(1, 0, 20, 2);
+        assert_eq!(textarea.cursor_screen_position(area), Some((3 + 3, 3)));
     }
 
-    /// Scroll the textarea. See [`Scrolling`] for the argument.
-    /// The cursor will not move until it goes out the viewport. When the cursor position is outside the viewport after scroll,
-    /// the cursor position will be adjusted to stay in the viewport using the same logic as [`CursorMove::InViewport`].
-    ///
-    /// ```
-    /// # use ratatui::buffer::Buffer;
-    /// # use ratatui::layout::Rect;
-    /// # use ratatui::widgets::Widget;
-    /// use tuipaz_textarea::TextArea;
-    ///
-    /// // Let's say terminal height is 8.
-    ///
-    /// // Create textarea with 20 lines "0", "1", "2", "3", ...
-    /// let mut textarea: TextArea = (0..20).into_iter().map(|i| i.to_string()).collect();
-    /// # // Call `render` at least once to populate terminal size
-    /// # let r = Rect { x: 0, y: 0, width: 24, height: 8 };
-    /// # let mut b = Buffer::empty(r.clone());
-    /// # textarea.widget().render(r, &mut b);
-    ///
-    /// // Scroll down by 15 lines. Since terminal height is 8, cursor will go out
-    /// // the viewport.
-    /// textarea.scroll((15, 0));
-    /// // So the cursor position was updated to stay in the viewport after the scrolling.
-    /// assert_eq!(textarea.cursor(), (15, 0));
-    ///
-    /// // Scroll up by 5 lines. Since the scroll amount is smaller than the terminal
-    /// // height, cursor position will not be updated.
-    /// textarea.scroll((-5, 0));
-    /// assert_eq!(textarea.cursor(), (15, 0));
-    ///
-    /// // Scroll up by 5 lines again. The terminal height is 8. So a cursor reaches to
-    /// // the top of viewport after scrolling up by 7 lines. Since we have already
-    /// // scrolled up by 5 lines, scrolling up by 5 lines again makes the cursor overrun
-    /// // the viewport by 5 - 2 = 3 lines. To keep the cursor stay in the viewport, the
-    /// // cursor position will be adjusted from line 15 to line 12.
-    /// textarea.scroll((-5, 0));
-    /// assert_eq!(textarea.cursor(), (12, 0));
-    /// ```
-    pub fn scroll(&mut self, scrolling: impl Into<Scrolling>) {
-        self.scroll_with_shift(scrolling.into(), self.selection_start.is_some());
+    #[test]
+    fn test_wide_cursor() {
+        let lines = vec!["a\tb".to_string(), "aあb".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.set_tab_length(4);
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        let reversed = Style::default().add_modifier(Modifier::REVERSED);
+        textarea.set_cursor_style(reversed);
+        let spans = |textarea: &TextArea, row: usize| {
+            textarea.styled_lines(row..row + 1)[0]
+                .spans
+                .iter()
+                .map(|s| (s.content.to_string(), s.style.add_modifier.contains(Modifier::REVERSED)))
+                .collect::<Vec<_>>()
+        };
+        let span = |s: &str, cursor: bool| (s.to_string(), cursor);
+
+        // The cursor covers all cells of an expanded tab, or only the first one
+        assert_eq!(spans(&textarea, 0), [span("a", false), span("   ", true), span("b", false)]);
+        textarea.set_wide_cursor(false);
+        assert_eq!(
+            spans(&textarea, 0),
+            [span("a", false), span(" ", true), span("  ", false), span("b", false)],
+        );
+
+        // A wide character is one glyph which is always covered
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        assert_eq!(spans(&textarea, 1), [span("a", false), span("あ", true), span("b", false)]);
+        textarea.set_wide_cursor(true);
+        assert_eq!(spans(&textarea, 1), [span("a", false), span("あ", true), span("b", false)]);
     }
 
-    fn scroll_with_shift(&mut self, scrolling: Scrolling, shift: bool) {
-        if shift && self.selection_start.is_none() {
-            self.selection_start = Some(self.cursor);
-        }
-        scrolling.scroll(&mut self.viewport);
-        self.move_cursor_with_shift(CursorMove::InViewport, shift);
+    #[test]
+    fn test_highlight_passes() {
+        let lines = vec!["ab cd".to_string(), "ef".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        let red = Style::default().fg(Color::Red);
+        let blue = Style::default().bg(Color::Blue);
+        let first = textarea.add_highlight_pass(move |hl, row| {
+            if row == 0 {
+                hl.decorate(3..5, red);
+            }
+        });
+        textarea.add_highlight_pass(move |hl, _| {
+            hl.decorate(1..4, blue);
+            // Ignored since it is out of the line
+            hl.decorate(0..10, blue);
+        });
+        textarea.set_cursor_line_style(Style::default());
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+
+        let spans = |textarea: &TextArea, row: usize| {
+            textarea.styled_lines(row..row + 1)[0]
+                .spans
+                .iter()
+                .map(|s| (s.content.to_string(), s.style))
+                .collect::<Vec<_>>()
+        };
+        let s = |c: &str, style| (c.to_string(), style);
+        assert_eq!(
+            spans(&textarea, 0),
+            [
+                s("a", Style::default()),
+                s("b ", blue),
+                s("c", red.patch(blue)),
+                s("d", red),
+            ],
+        );
+
+        assert!(textarea.remove_highlight_pass(first));
+        assert!(!textarea.remove_highlight_pass(first));
+        assert_eq!(
+            spans(&textarea, 0),
+            [s("a", Style::default()), s("b c", blue), s("d", Style::default())],
+        );
+        textarea.clear_highlight_passes();
+        assert_eq!(spans(&textarea, 0), [s("ab cd", Style::default())]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    const THEME_COLOR: Color = Color::Red;
-    const THEME: TextAreaTheme = TextAreaTheme {
-        text: THEME_COLOR,
-        select: THEME_COLOR,
-        links: THEME_COLOR,
-    };
+    #[test]
+    fn test_tags() {
+        let lines = vec!["#a b@c @d".to_string(), "x (#e)".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        let tags = textarea
+            .tags()
+            .into_iter()
+            .map(|(t, p)| (t, p.row, p.col, p.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [
+                ("#a".to_string(), 0, 0, 0),
+                ("@d".to_string(), 0, 7, 7),
+                ("#e".to_string(), 1, 3, 3),
+            ],
+        );
+
+        let cyan = Style::default().fg(Color::Cyan);
+        textarea.set_cursor_line_style(Style::default());
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        textarea.set_tag_style(TagKind::Mention, cyan);
+        let spans = textarea.styled_lines(0..1)[0]
+            .spans
+            .iter()
+            .map(|s| (s.content.to_string(), s.style))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                ("#a b@c ".to_string(), Style::default()),
+                ("@d".to_string(), cyan),
+            ],
+        );
+        textarea.remove_tag_style(TagKind::Mention);
+        assert_eq!(textarea.tag_style(TagKind::Mention), None);
+    }
+
+    #[test]
+    fn test_linkify_urls() {
+        let lines = vec!["a http://x.y b".to_string(), "https://z.w.".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+
+        textarea.set_cursor_line_style(Style::default());
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        textarea.set_url_detection(true);
+        let spans = textarea.styled_lines(0..1)[0]
+            .spans
+            .iter()
+            .map(|s| (s.content.to_string(), s.style))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                ("a ".to_string(), Style::default()),
+                ("http://x.y".to_string(), textarea.link_style),
+                (" b".to_string(), Style::default()),
+            ],
+        );
+
+        assert_eq!(textarea.in_url((0, 1)), None);
+        assert_eq!(textarea.in_url((0, 2)).as_deref(), Some("http://x.y"));
+        assert_eq!(textarea.linkify_url((0, 0)), None);
+
+        let ids = textarea.linkify_urls();
+        assert_eq!(ids.len(), 2);
+        let link = textarea.links[&ids[1]];
+        assert_eq!((link.row, link.start_col, link.end_col), (1, 0, 10));
+        assert_eq!(textarea.in_link((0, 11)), Some(ids[0]));
+        assert_eq!(textarea.linkify_urls(), []);
+
+        textarea.undo();
+        assert!(textarea.links[&ids[1]].deleted);
+
+        // A URL partly in a link is not converted even when the position is outside of the link
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 8))]);
+        let mut textarea = TextArea::new(vec!["see https://example.com".to_string()], links, 140, THEME);
+        assert_eq!(textarea.in_link((0, 12)), None);
+        assert!(textarea.in_url((0, 12)).is_some());
+        assert_eq!(textarea.linkify_url((0, 12)), None);
+        assert_eq!(textarea.link_ids(), [0]);
+    }
+
+    #[test]
+    fn test_links_after_multibyte_chars() {
+        let mut textarea = TextArea::new(vec!["ab😀 https://example.com".to_string()], HashMap::new(), 140, THEME);
+        textarea.set_cursor_line_style(Style::default());
+        textarea.move_cursor(CursorMove::End);
+        assert_eq!(textarea.linkify_urls().len(), 1);
+        textarea.links_mut().add(0, 0, 2).unwrap();
+
+        let spans = textarea.styled_lines(0..1)[0]
+            .spans
+            .iter()
+            .map(|s| (s.content.to_string(), s.style))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                ("ab😀".to_string(), textarea.link_style),
+                (" ".to_string(), Style::default()),
+                ("https://example.com".to_string(), textarea.link_style),
+                (" ".to_string(), textarea.cursor_style()),
+            ],
+        );
+    }
 
     #[test]
-    fn test_delete_piece_includes_link() {
-        let mut textarea = TextArea::new(vec!["Hello [world]!".into()], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 6, end_col: 11, edited: false, deleted: false });
+    fn test_token_providers() {
+        let lines = vec!["see AB-1 /a/AB-2".to_string(), "".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        let red = Style::default().fg(Color::Red);
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        textarea.add_token_provider(TokenProvider::new("ticket", r"[A-Z]+-\d+", red).unwrap());
+        textarea.add_token_provider(TokenProvider::file_path(bold));
+
+        let tokens = textarea
+            .tokens()
+            .into_iter()
+            .map(|t| (t.kind, t.text, t.pos.col))
+            .collect::<Vec<_>>();
+        let t = |k: &str, s: &str, c| (k.to_string(), s.to_string(), c);
+        assert_eq!(
+            tokens,
+            [
+                t("ticket", "AB-1", 4),
+                t("ticket", "AB-2", 12),
+                t("file_path", "/a/AB-2", 9),
+            ],
+        );
+        assert_eq!(textarea.token_at((0, 13)).unwrap().kind, "ticket");
+        assert_eq!(textarea.token_at((0, 10)).unwrap().kind, "file_path");
+        assert_eq!(textarea.token_at((0, 8)), None);
 
-        textarea.delete_piece(5, 10);
+        textarea.set_cursor_line_style(Style::default());
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        let spans = textarea.styled_lines(0..1)[0]
+            .spans
+            .iter()
+            .map(|s| (s.content.to_string(), s.style))
+            .collect::<Vec<_>>();
+        let s = |c: &str, style| (c.to_string(), style);
+        assert_eq!(
+            spans,
+            [
+                s("see ", Style::default()),
+                s("AB-1", red),
+                s(" ", Style::default()),
+                s("/a/", bold),
+                s("AB-2", red.patch(bold)),
+            ],
+        );
 
-        assert_eq!(textarea.links.get(&0), None);
+        assert!(textarea.remove_token_provider("ticket"));
+        assert!(!textarea.remove_token_provider("ticket"));
+        assert_eq!(textarea.token_providers().len(), 1);
     }
 
     #[test]
-    fn test_delete_entire_link() {
-        let mut textarea = TextArea::new(vec!["[Link]".into()], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 0, end_col: 5, edited: false , deleted: false });
+    fn test_include_lines() {
+        use crate::ratatui::buffer::Buffer;
+
+        let lines = vec![
+            "a".to_string(),
+            "!include(x)".to_string(),
+            "b".to_string(),
+            "!include(y)".to_string(),
+        ];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.set_include_resolver(|id| match id {
+            "x" => Some(vec!["x1".to_string(), "x2".to_string()]),
+            _ => None,
+        });
+        let area = Rect {
+            x: 0,
+            y: 0,
+            width: 11,
+            height: 4,
+        };
+        let render = |textarea: &TextArea| {
+            let mut buf = Buffer::empty(area);
+            textarea.widget().render(area, &mut buf);
+            (0..area.height)
+                .map(|y| {
+                    (0..area.width)
+                        .map(|x| buf.content[(y * area.width + x) as usize].symbol().to_string())
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
 
-        textarea.delete_piece(0, 5);
+        assert_eq!(render(&textarea), ["a", "!include(x)", "x1", "x2"]);
+        assert_eq!(textarea.screen_rows(0, 3), 5);
 
-        assert_eq!(textarea.links.get(&0), None);
+        // The viewport scrolls by rows so that the cursor is not hidden by the included lines
+        textarea.move_cursor(CursorMove::Bottom);
+        assert_eq!(render(&textarea), ["b", "!include(y)", "", ""]);
+        assert_eq!(textarea.cursor_screen_position(area), Some((0, 1)));
+        textarea.move_cursor(CursorMove::Up);
+        textarea.move_cursor(CursorMove::Up);
+        assert_eq!(render(&textarea), ["!include(x)", "x1", "x2", "b"]);
+        assert_eq!(textarea.cursor_screen_position(area), Some((0, 0)));
+
+        // Included lines are not a part of the text
+        assert_eq!(textarea.lines().len(), 4);
+        textarea.remove_include_resolver();
+        textarea.move_cursor(CursorMove::Top);
+        assert_eq!(render(&textarea), ["a", "!include(x)", "b", "!include(y)"]);
     }
 
     #[test]
-    fn test_delete_part_of_link() {
-        let mut textarea = TextArea::new(vec!["[Example Link]".into()], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 0, end_col: 21, edited: false , deleted: false });
+    fn test_conceal() {
+        let lines = vec!["## Title".to_string(), "a **b** [c](d)".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.set_conceal(true);
+        let text = |textarea: &TextArea, row: usize| {
+            textarea.styled_lines(row..row + 1)[0]
+                .spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect::<String>()
+        };
 
-        textarea.delete_piece(7, 9);
+        // The cursor line is not concealed
+        assert_eq!(text(&textarea, 0), "## Title");
+        assert_eq!(text(&textarea, 1), "a b c");
+        assert_eq!(textarea.display_col(0, 3), 3);
+        assert_eq!(textarea.display_col(1, 5), 3);
+        assert_eq!(textarea.logical_col(1, 4), 9);
 
-        assert_eq!(textarea.links.get(&0), None);
+        textarea.move_cursor(CursorMove::Down);
+        assert_eq!(text(&textarea, 0), "Title");
+        assert_eq!(textarea.display_col(0, 3), 0);
+        assert_eq!(textarea.logical_col(0, 1), 4);
+
+        textarea.set_conceal(false);
+        assert_eq!(text(&textarea, 0), "## Title");
     }
 
     #[test]
-    fn test_links_shift_on_insert_char() {
-        let mut textarea = TextArea::new(vec!["Hello [world]!".into()], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 6, end_col: 11, edited: false , deleted: false });
-        textarea.move_cursor(CursorMove::Jump(0, 5));
-        textarea.insert_char(' ');
-        
-        assert_eq!(textarea.links.get(&0).unwrap().start_col, 7); 
-        assert_eq!(textarea.links.get(&0).unwrap().end_col, 12); 
+    fn test_export_plain() {
+        let lines = vec!["a bc d ef".to_string(), "gh".to_string()];
+        let links = HashMap::from([
+            (0, Link::new(0, 0, 2, 3)),
+            (1, Link::new(1, 0, 7, 8)),
+            (2, Link::new(2, 1, 0, 1)),
+        ]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        textarea.mark_link_deleted(2);
+        let target = |l: &Link| (l.id == 0).then(|| "x".to_string());
+
+        assert_eq!(
+            textarea.export_plain(LinkRendering::LabelOnly, target),
+            "a bc d ef\ngh",
+        );
+        assert_eq!(
+            textarea.export_plain(LinkRendering::LabelWithTarget, target),
+            "a bc (x) d ef\ngh",
+        );
+        assert_eq!(
+            textarea.export_plain(LinkRendering::Markdown, |l| Some(l.id.to_string())),
+            "a [bc](0) d [ef](1)\ngh",
+        );
     }
 
     #[test]
-    fn test_links_shift_on_delete_char() {
-        let mut textarea = TextArea::new(vec!["Hello [world]!".into()], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 6, end_col: 11, edited: false , deleted: false });
-        textarea.move_cursor(CursorMove::Jump(0, 5));
-        textarea.delete_char();
-        
-        assert_eq!(textarea.links.get(&0).unwrap().start_col, 5); 
-        assert_eq!(textarea.links.get(&0).unwrap().end_col, 10); 
+    fn test_export_plain_typed_link() {
+        let mut textarea = TextArea::new(vec![String::new()], HashMap::new(), 140, THEME);
+        for c in "see [note] x".chars() {
+            textarea.insert_char(c);
+        }
+        assert_eq!(textarea.link_ids().len(), 1);
+        let target = |_: &Link| Some("id0".to_string());
+
+        assert_eq!(textarea.export_plain(LinkRendering::LabelOnly, target), "see note x");
+        assert_eq!(textarea.export_plain(LinkRendering::LabelWithTarget, target), "see note (id0) x");
+        assert_eq!(textarea.export_plain(LinkRendering::Markdown, target), "see [note](id0) x");
     }
 
     #[test]
-    fn test_deletion_on_different_rows_does_not_affect_links() {
-        let mut textarea = TextArea::new(vec![
-            "Line without link.".into(),
-            "[Link]".into()
-        ], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 1, start_col: 0, end_col: 5, edited: false , deleted: false });
+    fn test_to_ansi_string() {
+        let lines = vec!["ab cd".to_string(), "e".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 3, 4))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        textarea.set_style(Style::default());
+        textarea.link_style = Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD);
+        textarea.set_selection_style(Style::default().bg(Color::Rgb(1, 2, 3)));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Forward);
+
+        assert_eq!(
+            textarea.to_ansi_string(false),
+            "ab \x1b[1;34mcd\x1b[0m\ne",
+        );
+        assert_eq!(
+            textarea.to_ansi_string(true),
+            "\x1b[48;2;1;2;3ma\x1b[0mb \x1b[1;34mcd\x1b[0m\ne",
+        );
+    }
 
-        textarea.delete_piece(0, 5);
+    #[test]
+    fn test_insert_str_wrapped() {
+        let lines = vec!["ab".to_string(), "xyz".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 8, THEME);
+        textarea.move_cursor(CursorMove::End);
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+        assert!(textarea.insert_str_wrapped(" cd efgh ijklmnopq\nr s"));
+        assert_eq!(textarea.lines(), ["ab cd ", "efgh ", "ijklmno", "pq", "r s", "xyz"]);
+        assert_eq!(textarea.cursor(), (4, 3));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["ab", "xyz"]);
+        assert!(!textarea.insert_str_wrapped(""));
+
+        // The rest of the line after the cursor is rewrapped with the following rows as typing does
+        let lines = vec!["ab xyz".to_string(), "uv".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 8, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 2));
+        assert!(textarea.insert_str_wrapped(" cd efgh"));
+        assert_eq!(textarea.lines(), ["ab cd ", "efgh ", "xyz uv"]);
+        assert_eq!(textarea.cursor(), (1, 4));
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["ab xyz", "uv"]);
+
+        // Rows in a quote block get the prefix
+        let mut textarea = TextArea::new(vec!["> > ab".to_string()], HashMap::new(), 12, THEME);
+        textarea.set_quote_aware(true);
+        textarea.move_cursor(CursorMove::End);
+        assert!(textarea.insert_str_wrapped(" cd efg\nhi"));
+        assert_eq!(textarea.lines(), ["> > ab cd ", "> > efg", "> > hi"]);
+        assert!(textarea.undo());
+
+        // The prefix is not carried when the cursor is in the prefix
+        textarea.move_cursor(CursorMove::Head);
+        assert!(textarea.insert_str_wrapped("x\ny"));
+        assert_eq!(textarea.lines(), ["x", "y> > ab"]);
     }
 
     #[test]
-    fn test_deletion_range_does_not_delete_links_but_shifts_it() {
-        let mut textarea = TextArea::new(vec![
-            "Before [link] and after.".into()
-        ], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+    fn test_insert_char_reflow() {
+        let lines = vec!["aa bb cc".to_string(), "dd ee".to_string(), "".to_string(), "ff".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 9, THEME);
+        textarea.move_cursor(CursorMove::End);
 
-        textarea.delete_piece(0, 6);
-        textarea.delete_piece(23, 13);
+        textarea.insert_char('x');
+        assert_eq!(textarea.lines(), ["aa bb ", "ccx dd ", "ee", "", "ff"]);
+        assert_eq!(textarea.cursor(), (1, 3));
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 6 , edited: false , deleted: false}));
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["aa bb cc", "dd ee", "", "ff"]);
+        assert!(textarea.redo());
+        assert_eq!(textarea.lines(), ["aa bb ", "ccx dd ", "ee", "", "ff"]);
     }
 
     #[test]
-    fn test_inserting_newline_above_and_below_link() {
-        let mut textarea = TextArea::new(vec![
-            "[Link]".into(),
-            "Some text below.".into()
-        ], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 0, end_col: 5, edited: false , deleted: false });
-        textarea.move_cursor(CursorMove::Jump(1, 0));
-        textarea.insert_newline();
+    fn test_wrap_breaks() {
+        let lines = vec!["aa bb cc".to_string(), "dd".to_string(), "".to_string(), "ee".to_string()];
+        let mut textarea = TextArea::new(lines, HashMap::new(), 9, THEME);
+        textarea.move_cursor(CursorMove::End);
+        for c in " ffff gggggggg".chars() {
+            textarea.insert_char(c);
+        }
+        assert_eq!(textarea.lines(), ["aa bb ", "cc ffff ", "gggggggg ", "dd", "", "ee"]);
+        let breaks = (0..6).filter(|r| textarea.is_wrap_break(*r)).collect::<Vec<_>>();
+        assert_eq!(breaks, [1, 2, 3]);
+        assert_eq!(textarea.logical_lines(), ["aa bb cc ffff gggggggg dd", "", "ee"]);
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
-        
-        textarea.move_cursor(CursorMove::Jump(0, 0));
+        // A typed newline is not a wrap break
+        textarea.move_cursor(CursorMove::Jump(1, 8));
         textarea.insert_newline();
+        assert!(!textarea.is_wrap_break(2));
+        assert_eq!(textarea.logical_lines(), ["aa bb cc ffff ", "gggggggg dd", "", "ee"]);
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+        // Joining the rows drops the mark
+        textarea.move_cursor(CursorMove::Jump(3, 0));
+        assert!(textarea.delete_char());
+        assert_eq!(textarea.lines(), ["aa bb ", "cc ffff ", "gggggggg ", "dd", "", "ee"]);
+        assert!(!textarea.is_wrap_break(2));
+        assert_eq!(textarea.logical_lines(), ["aa bb cc ffff ", "gggggggg dd", "", "ee"]);
+        assert_eq!(textarea.clone().into_lines(), textarea.lines());
     }
 
     #[test]
-    fn test_deleting_line_by_head_above_and_below_link() {
-        let mut textarea = TextArea::new(vec![
-            "Some text above".into(),
-            "[Link]".into(),
-            "Some text below.".into()
-        ], HashMap::new(), 140, THEME);
+    fn test_rewrap_all() {
+        let lines = vec![
+            "short".to_string(),
+            "aa bb [cc dd] ee".to_string(),
+            "ff".to_string(),
+        ];
+        let links = HashMap::from([
+            (0, Link::new(0, 1, 6, 12)),
+            (1, Link::new(1, 1, 14, 15)),
+            (2, Link::new(2, 2, 0, 1)),
+        ]);
+        let mut textarea = TextArea::new(lines, links, 9, THEME);
+        textarea.insert_char('x');
+        textarea.move_cursor(CursorMove::Jump(1, 15));
+
+        assert!(textarea.rewrap_all());
+        assert_eq!(textarea.lines(), ["xshort", "aa bb", "[cc dd]", "ee", "ff"]);
+        let pos = |textarea: &TextArea, id: usize| {
+            let l = textarea.links[&id];
+            (l.row, l.start_col, l.end_col)
+        };
+        assert_eq!(pos(&textarea, 0), (2, 0, 6));
+        assert_eq!(pos(&textarea, 1), (3, 0, 1));
+        assert_eq!(pos(&textarea, 2), (4, 0, 1));
+        assert_eq!(textarea.cursor(), (3, 1));
+
+        // The rewrapping is undone as one edit
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["xshort", "aa bb [cc dd] ee", "ff"]);
+        assert_eq!(pos(&textarea, 0), (1, 6, 12));
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["short", "aa bb [cc dd] ee", "ff"]);
+        assert!(textarea.redo());
+        assert!(textarea.redo());
+        assert_eq!(textarea.lines(), ["xshort", "aa bb", "[cc dd]", "ee", "ff"]);
+        assert!(!textarea.rewrap_all());
+
+        // A link longer than the max column is not cut
+        let links = HashMap::from([(0, Link::new(0, 0, 2, 14))]);
+        let mut textarea = TextArea::new(vec!["a [long link x] b".to_string()], links, 9, THEME);
+        assert!(textarea.rewrap_all());
+        assert_eq!(textarea.lines(), ["a", "[long link x]", "b"]);
+        assert_eq!(pos(&textarea, 0), (1, 0, 12));
+    }
 
-        textarea.links.insert(0, Link { id: 0, row: 1, start_col: 0, end_col: 5, edited: false , deleted: false });
-        textarea.move_cursor(CursorMove::Jump(2, 0));
-        textarea.delete_line_by_head();
+    #[test]
+    fn test_logical_line_movement() {
+        let lines = ["aaa bbb", "ccc", "dd", "eee", "gg"].map(String::from).to_vec();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 9, THEME);
+        let key = |key| Input { key, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
-        
+        textarea.move_cursor(CursorMove::Jump(0, 2));
+        textarea.input(key(Key::Down));
+        assert_eq!(textarea.cursor(), (1, 2));
+
+        textarea.set_line_movement(LineMovement::Logical);
         textarea.move_cursor(CursorMove::Jump(0, 0));
-        textarea.delete_line_by_head();
+        textarea.input(key(Key::Down));
+        assert_eq!(textarea.cursor(), (2, 0));
+        textarea.input(key(Key::Down));
+        assert_eq!(textarea.cursor(), (3, 0));
+        textarea.input(key(Key::Down));
+        textarea.input(key(Key::Down));
+        assert_eq!(textarea.cursor(), (4, 0));
+        textarea.move_cursor(CursorMove::Jump(3, 1));
+        textarea.input(key(Key::Up));
+        assert_eq!(textarea.cursor(), (2, 1));
+        textarea.input(key(Key::Up));
+        assert_eq!(textarea.cursor(), (0, 1));
+        textarea.input(key(Key::Up));
+        assert_eq!(textarea.cursor(), (0, 1));
+    }
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+    #[test]
+    fn test_paragraph_start_end() {
+        let lines = ["aaa", "bb", "", "", "ccc", "d"].map(String::from).to_vec();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        textarea.move_cursor(CursorMove::ParagraphEnd);
+        assert_eq!(textarea.cursor(), (1, 1));
+        textarea.move_cursor(CursorMove::ParagraphEnd);
+        assert_eq!(textarea.cursor(), (5, 0));
+        textarea.move_cursor(CursorMove::ParagraphEnd);
+        assert_eq!(textarea.cursor(), (5, 0));
+        textarea.move_cursor(CursorMove::ParagraphStart);
+        assert_eq!(textarea.cursor(), (4, 0));
+        textarea.move_cursor(CursorMove::Jump(3, 0));
+        textarea.move_cursor(CursorMove::ParagraphStart);
+        assert_eq!(textarea.cursor(), (0, 0));
+        textarea.move_cursor(CursorMove::ParagraphStart);
+        assert_eq!(textarea.cursor(), (0, 0));
+
+        textarea.move_cursor(CursorMove::Jump(2, 0));
+        assert!(!textarea.select_paragraph());
+        assert!(!textarea.is_selecting());
+        textarea.move_cursor(CursorMove::Jump(4, 1));
+        assert!(textarea.select_paragraph());
+        assert_eq!(textarea.selection_snapshot(), Some(((4, 0), (5, 1))));
     }
 
     #[test]
-    fn test_deleting_line_by_end_above_and_below_link() {
-        let mut textarea = TextArea::new(vec![
-            "Some text above".into(),
-            "[Link]".into(),
-            "Some text below.".into()
-        ], HashMap::new(), 140, THEME);
+    fn test_text_objects() {
+        let lines = vec!["see [docs] now".to_string(), "(a".to_string(), "b)".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 9))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+
+        textarea.move_cursor(CursorMove::Jump(0, 6));
+        assert_eq!(textarea.text_object(TextObject::InnerLink), Some(((0, 4), (0, 10))));
+        assert_eq!(textarea.text_object(TextObject::InnerWord), Some(((0, 4), (0, 10))));
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        assert_eq!(textarea.text_object(TextObject::InnerLink), None);
+        assert!(!textarea.select_text_object(TextObject::InnerLink));
 
-        textarea.links.insert(0, Link { id: 0, row: 1, start_col: 0, end_col: 5, edited: false , deleted: false });
         textarea.move_cursor(CursorMove::Jump(2, 0));
+        assert!(textarea.delete_text_object(TextObject::InnerBrackets));
+        assert_eq!(textarea.lines(), ["see [docs] now", "()"]);
+        assert_eq!(textarea.cursor(), (1, 1));
+        assert!(!textarea.delete_text_object(TextObject::InnerBrackets));
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["see [docs] now", "(a", "b)"]);
+    }
+
+    #[test]
+    fn test_paste_over_selection_undo() {
+        let lines = vec!["foo bar baz".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 6))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        let snapshot = |t: &TextArea| {
+            let mut links: Vec<_> = t.links.values().map(|l| (l.id, l.row, l.start_col, l.end_col, l.deleted)).collect();
+            links.sort();
+            (t.lines.clone(), links)
+        };
+
+        // Yank "baz" and select "bar" which has a link
+        textarea.move_cursor(CursorMove::Jump(0, 8));
+        textarea.start_selection();
         textarea.move_cursor(CursorMove::End);
-        textarea.delete_line_by_end();
+        textarea.copy();
+        textarea.move_cursor(CursorMove::Jump(0, 4));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(0, 7));
+        let before = snapshot(&textarea);
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 1, start_col: 0, end_col: 5 , edited: false , deleted: false}));
-        
-        textarea.move_cursor(CursorMove::Jump(0, 0));
+        assert!(textarea.paste());
+        assert_eq!(textarea.lines, ["foo baz baz"]);
+        let after = snapshot(&textarea);
+
+        assert!(textarea.undo());
+        assert_eq!(snapshot(&textarea), before);
+        assert_eq!(textarea.selection_snapshot(), Some(((0, 4), (0, 7))));
+        assert!(!textarea.undo());
+
+        assert!(textarea.redo());
+        assert_eq!(snapshot(&textarea), after);
+        assert_eq!(textarea.cursor, (0, 7));
+    }
+
+    #[test]
+    fn test_empty_selection_clipboard() {
+        let lines = ["aaa", "bbb", "ccc"].map(String::from).to_vec();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        textarea.copy();
+        assert_eq!(textarea.yank_text(), "");
+        assert!(!textarea.cut());
+
+        textarea.set_empty_selection_clipboard(true);
+        textarea.copy();
+        assert_eq!(textarea.yank_text(), "bbb\n");
+        assert_eq!(textarea.cursor(), (1, 1));
+        assert!(!textarea.is_selecting());
+
+        textarea.move_cursor(CursorMove::Bottom);
+        textarea.copy();
+        assert_eq!(textarea.yank_text(), "ccc\n");
+        assert!(textarea.cut());
+        assert_eq!(textarea.lines(), ["aaa", "bbb"]);
+        assert_eq!(textarea.yank_text(), "ccc\n");
+        assert_eq!(textarea.cursor(), (1, 0));
+
+        // Lines yanked linewise are pasted above the cursor line wherever the cursor is in the line
         textarea.move_cursor(CursorMove::End);
-        textarea.delete_line_by_end();
+        assert!(textarea.paste());
+        assert_eq!(textarea.lines(), ["aaa", "ccc", "bbb"]);
+        assert!(matches!(textarea.yank(), YankContent::Lines { .. }));
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+        // They replace the selection with the newline
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Forward);
+        assert!(textarea.paste());
+        assert_eq!(textarea.lines(), ["accc", "a", "ccc", "bbb"]);
+
+        assert!(textarea.undo());
+        assert!(textarea.undo());
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["aaa", "bbb", "ccc"]);
     }
 
     #[test]
-    fn test_link_full_row_selection_deletion() {
-        let mut textarea = TextArea::new(vec![
-            "Before [link] after".into()
-        ], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+    fn test_paste_lines_with_links() {
+        let lines = ["a [x]", "b", "c"].map(String::from).to_vec();
+        let links = HashMap::from([(0, Link::new(0, 0, 2, 4))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        textarea.set_empty_selection_clipboard(true);
+
+        assert!(textarea.cut());
+        assert_eq!(textarea.lines, ["b", "c"]);
+        assert!(textarea.links[&0].deleted);
+
+        textarea.move_cursor(CursorMove::Bottom);
+        assert!(textarea.paste_below());
+        assert_eq!(textarea.lines, ["b", "c", "a [x]"]);
+        assert_eq!(textarea.cursor, (2, 0));
+        let link = textarea.links[&0];
+        assert_eq!((link.row, link.start_col, link.end_col, link.deleted), (2, 2, 4, false));
+
+        // The link is copied when it still exists
+        textarea.move_cursor(CursorMove::Top);
+        assert!(textarea.paste_above());
+        assert_eq!(textarea.lines, ["a [x]", "b", "c", "a [x]"]);
+        let link = textarea.links[&1];
+        assert_eq!((link.row, link.start_col, link.end_col), (0, 2, 4));
+        assert_eq!(textarea.links[&0].row, 3);
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["b", "c", "a [x]"]);
+        assert!(!textarea.links.contains_key(&1));
+        assert_eq!(textarea.links[&0].row, 2);
+    }
 
-        textarea.selection_start = Some((0, 0));
-        textarea.move_cursor(CursorMove::End);
-        textarea.delete_selection(false);
+    #[test]
+    fn test_yank_history() {
+        let lines = ["aaa bbb ccc".to_string()];
+        let mut textarea = TextArea::new(lines.to_vec(), HashMap::new(), 140, THEME);
+        textarea.set_yank_history_size(2);
+
+        textarea.delete_next_word();
+        textarea.delete_next_word();
+        textarea.delete_next_word();
+        assert_eq!(textarea.yank_history(), [" ccc", " bbb"]);
+
+        // Cycling replaces the pasted text
+        assert!(textarea.paste());
+        assert!(textarea.cycle_paste());
+        assert_eq!(textarea.lines, [" bbb"]);
+        assert!(textarea.cycle_paste());
+        assert_eq!(textarea.lines, [" ccc"]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, [""]);
+
+        // Cursor moved after paste
+        assert!(textarea.paste());
+        textarea.move_cursor(CursorMove::Head);
+        assert!(!textarea.cycle_paste());
+        assert_eq!(textarea.lines, [" ccc"]);
+    }
 
-        assert_eq!(textarea.links.get(&0), None);
+    #[test]
+    fn test_yank_content_between_textareas() {
+        let links = HashMap::from([(0, Link::new(0, 0, 4, 6))]);
+        let mut src = TextArea::new(vec!["see [a] now".to_string()], links, 140, THEME);
+        src.move_cursor(CursorMove::Jump(0, 4));
+        src.start_selection();
+        src.move_cursor(CursorMove::Jump(0, 7));
+        src.copy();
+        let content = src.yank();
+        assert_eq!(
+            content,
+            YankContent::Piece {
+                text: "[a]".to_string(),
+                links: vec![YankedLink { id: 0, row_offset: 0, start_col_offset: 0, end_col_offset: 2 }],
+                keys: HashMap::new(),
+                origin: (0, 4),
+            },
+        );
+
+        let mut dst = TextArea::new(vec!["".to_string()], HashMap::new(), 140, THEME);
+        dst.set_yank(content.clone());
+        assert!(dst.paste());
+        assert_eq!(dst.lines, ["[a]"]);
+        assert_eq!(dst.links.len(), 1);
+
+        dst.set_yank(YankContent::Chunk {
+            lines: vec!["x".to_string()],
+            links: vec![],
+            keys: HashMap::new(),
+            origin: (0, 0),
+        });
+        assert!(dst.paste());
+        assert_eq!(dst.lines, ["[a]x"]);
+
+        // A foreign link never restores a deleted link having the same ID
+        let links = HashMap::from([(0, Link { deleted: true, ..Link::new(0, 0, 0, 0) })]);
+        let mut dst = TextArea::new(vec!["".to_string()], links, 140, THEME);
+        dst.set_yank(content);
+        assert!(dst.paste());
+        assert!(dst.links[&0].deleted);
+        let pasted: Vec<_> = dst.links.values().filter(|l| !l.deleted).map(|l| (l.id, l.row, l.start_col)).collect();
+        assert_eq!(pasted.len(), 1);
+        assert_ne!(pasted[0].0, 0);
+        assert_eq!((pasted[0].1, pasted[0].2), (0, 0));
+
+        // Links cut and pasted linewise in the same textarea are restored at the pasted lines
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2))]);
+        let mut textarea = TextArea::new(vec!["[a]".to_string(), "b".to_string()], links, 140, THEME);
+        assert!(textarea.yank_line(true));
+        assert!(textarea.links[&0].deleted);
+        assert!(textarea.paste_below());
+        assert_eq!(textarea.lines, ["b", "[a]"]);
+        let link = textarea.links[&0];
+        assert_eq!((link.deleted, link.edited, link.row, link.start_col, link.end_col), (false, false, 1, 0, 2));
     }
 
     #[test]
-    fn test_link_full_link_selection_deletion() {
-        let mut textarea = TextArea::new(vec![
-            "Before [link] after".into()
-        ], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+    fn test_insert_fragment() {
+        let lines = ["[a] b", "c [d]"].map(String::from).to_vec();
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2)), (1, Link::new(1, 1, 2, 4))]);
+        let mut src = TextArea::new(lines, links, 140, THEME);
+        assert!(src.extract_selection().is_none());
+        src.select_all();
+        let fragment = src.extract_selection().unwrap();
+        assert_eq!(fragment.links.len(), 2);
+
+        let links = HashMap::from([(0, Link::new(0, 0, 3, 5))]);
+        let mut dst = TextArea::new(vec!["xy [z]".to_string()], links, 140, THEME);
+        dst.move_cursor(CursorMove::Jump(0, 1));
+        let ids = dst.insert_fragment(fragment);
+        assert_eq!(dst.lines, ["x[a] b", "c [d]y [z]"]);
+        assert_eq!(ids, HashMap::from([(0, 1), (1, 2)]));
+        let pos = |t: &TextArea, id: usize| {
+            let l = t.links[&id];
+            (l.row, l.start_col, l.end_col)
+        };
+        assert_eq!(pos(&dst, 1), (0, 1, 3));
+        assert_eq!(pos(&dst, 2), (1, 2, 4));
+        assert_eq!(pos(&dst, 0), (1, 7, 9));
 
-        textarea.selection_start = Some((0, 7));
-        textarea.move_cursor(CursorMove::Jump(0, 12));
-        textarea.delete_selection(false);
+        assert!(dst.undo());
+        assert_eq!(dst.lines, ["xy [z]"]);
+        assert_eq!(dst.links.len(), 1);
+        assert_eq!(pos(&dst, 0), (0, 3, 5));
+    }
 
-        assert_eq!(textarea.links.get(&0), None);
+    #[test]
+    fn test_append_document() {
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2))]);
+        let mut textarea = TextArea::new(vec!["[a] x".to_string()], links, 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+
+        let mut deleted = Link::new(1, 0, 4, 6);
+        deleted.deleted = true;
+        let links = HashMap::from([(0, Link::new(0, 1, 0, 2)), (1, deleted)]);
+        let ids = textarea.append_document(vec!["[x] [y]".to_string(), "[b]".to_string()], links, HashMap::new());
+        assert_eq!(textarea.lines, ["[a] x", "[x] [y]", "[b]"]);
+        assert_eq!(ids, HashMap::from([(0, 1)]));
+        let link = textarea.links[&1];
+        assert_eq!((link.row, link.start_col, link.end_col), (2, 0, 2));
+        assert_eq!(textarea.links[&0].row, 0);
+        assert_eq!(textarea.cursor, (0, 1));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["[a] x"]);
+        assert_eq!(textarea.links.len(), 1);
+        assert!(!textarea.undo());
     }
 
     #[test]
-    fn test_link_partial_link_selection_deletion() {
-        let mut textarea = TextArea::new(vec![
-            "Before [link] after".into()
-        ], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+    fn test_insert_chunk_keeps_links_before_insertion() {
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2)), (1, Link::new(1, 0, 6, 8))]);
+        let mut textarea = TextArea::new(vec!["[a] x [b]".to_string()], links, 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 4));
+        assert!(textarea.insert_str("y\nz", (0, 4)));
+        assert_eq!(textarea.lines, ["[a] y", "zx [b]"]);
 
-        textarea.selection_start = Some((0, 6));
-        textarea.move_cursor(CursorMove::Jump(0, 8));
-        textarea.delete_selection(false);
+        // The link before the insertion point stays on its row and the link after it moves with the text
+        let pos = |l: &Link| (l.row, l.start_col, l.end_col);
+        assert_eq!(pos(&textarea.links[&0]), (0, 0, 2));
+        assert_eq!(pos(&textarea.links[&1]), (1, 3, 5));
+    }
 
-        assert_eq!(textarea.links.get(&0), None);
+    #[test]
+    fn test_link_keys() {
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2))]);
+        let mut textarea = TextArea::new(vec!["[a] x".to_string()], links, 140, THEME);
+        assert_eq!(textarea.link_key(0), None);
+
+        textarea.set_link_key_mode(LinkKeyMode::Uuid);
+        let key = textarea.link_key(0).unwrap().to_string();
+        assert_eq!(key.len(), 36);
+        assert_eq!(&key[14..15], "4");
+        assert_eq!(textarea.link_by_key(&key), Some(0));
+
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2))]);
+        let ids = textarea.append_document(vec!["[b]".to_string()], links, HashMap::new());
+        let other = textarea.link_key(ids[&0]).unwrap().to_string();
+        assert_ne!(key, other);
+
+        assert_eq!(
+            textarea.set_link_key(ids[&0], key.clone()),
+            Err(LinkKeyError::DuplicateKey { key: key.clone(), id: 0 }),
+        );
+        assert_eq!(textarea.set_link_key(5, "x"), Err(LinkKeyError::NoSuchLink(5)));
+        assert_eq!(textarea.set_link_key(0, ""), Err(LinkKeyError::EmptyKey));
+        assert_eq!(textarea.set_link_key(0, "a"), Ok(()));
+        assert_eq!(textarea.link_by_key("a"), Some(0));
+        assert_eq!(textarea.link_by_key(&key), None);
+        assert_eq!(textarea.link_keys().len(), 2);
     }
 
     #[test]
-    fn test_link_shift_after_selection_deletion_same_row() {
-        let mut textarea = TextArea::new(vec![
-            "Before [link] after".into()
-        ], HashMap::new(), 140, THEME);
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+    fn test_link_keys_carried() {
+        let links = HashMap::from([(0, Link::new(0, 0, 0, 2))]);
+        let mut src = TextArea::new(vec!["[a] x".to_string()], links, 140, THEME);
+        src.set_link_key(0, "a").unwrap();
+        src.start_selection();
+        src.move_cursor(CursorMove::Jump(0, 3));
+        src.copy();
+        let content = src.yank();
+        assert!(matches!(&content, YankContent::Piece { keys, .. } if keys[&0] == "a"));
+
+        // Keys of links pasted from another textarea come along and are moved back by undo and redo
+        let mut dst = TextArea::new(vec!["".to_string()], HashMap::new(), 140, THEME);
+        dst.set_yank(content);
+        assert!(dst.paste());
+        let id = dst.link_by_key("a").unwrap();
+        assert_eq!(dst.links[&id].start_col, 0);
+        assert!(dst.undo());
+        assert_eq!(dst.link_key(id), None);
+        assert!(dst.redo());
+        assert_eq!(dst.link_key(id), Some("a"));
+
+        // A moved link takes over the key of the link it was cut from, while a copy leaves the key to the original
+        src.move_cursor(CursorMove::Head);
+        src.start_selection();
+        src.move_cursor(CursorMove::Jump(0, 3));
+        let fragment = src.extract_selection().unwrap();
+        assert_eq!(fragment.keys, HashMap::from([(0, "a".to_string())]));
+        src.cut();
+        src.move_cursor(CursorMove::End);
+        let ids = src.insert_fragment(fragment.clone());
+        assert_eq!(src.lines, [" x[a]"]);
+        assert_eq!(src.link_by_key("a"), Some(ids[&0]));
+        assert_eq!(src.link_key(0), None);
+        let ids = src.insert_fragment(fragment);
+        assert_eq!(src.link_key(ids[&0]), None);
+        assert!(src.undo());
+        assert!(src.undo());
+        assert_eq!(src.link_key(0), Some("a"));
+        assert!(src.undo());
+        assert_eq!(src.link_by_key("a"), Some(0));
+    }
 
-        textarea.selection_start = Some((0, 0));
-        textarea.move_cursor(CursorMove::Jump(0, 5));
-        textarea.delete_selection(false);
+    #[test]
+    fn test_links_handle() {
+        let mut textarea = TextArea::new(vec!["foo bar".to_string()], HashMap::new(), 140, THEME);
+        let mut links = textarea.links_mut();
+        let id = links.add(0, 0, 2).unwrap();
+        assert_eq!(links.add(0, 2, 4), Err(LinkError::Overlap(id)));
+        assert_eq!(
+            links.add(0, 5, 7),
+            Err(LinkError::OutOfBounds {
+                row: 0,
+                start_col: 5,
+                end_col: 7
+            }),
+        );
+        assert_eq!(links.update(id, 1, 0, 1), Err(LinkError::OutOfBounds { row: 1, start_col: 0, end_col: 1 }));
+        assert_eq!(links.update(id, 0, 4, 6), Ok(()));
+        assert_eq!(links.ids(), [id]);
+        assert!(textarea.take_new_link());
+        assert!(!textarea.take_new_link());
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 2, end_col: 7 , edited: false , deleted: false}));
+        assert!(textarea.undo());
+        assert_eq!(textarea.link(id).map(|l| (l.start_col, l.end_col)), Some((0, 2)));
+        assert!(textarea.redo());
+        assert_eq!(textarea.link(id).map(|l| (l.start_col, l.end_col)), Some((4, 6)));
+
+        assert_eq!(textarea.links_mut().remove(id).map(|l| l.id), Ok(id));
+        assert_eq!(textarea.links_mut().remove(id), Err(LinkError::NoSuchLink(id)));
+        assert_eq!(textarea.take_deleted_link_ids(), [id]);
+        assert!(textarea.link_ids().is_empty());
+        assert!(textarea.undo());
+        assert_eq!(textarea.link_ids(), [id]);
     }
 
     #[test]
-    fn test_link_shift_after_selection_deletion_multiple_rows_above() {
-        let mut textarea = TextArea::new(vec![
-            "Text above 1".into(),
-            "Text above 2".into(),
-            "Before [link] after".into()
-        ], HashMap::new(), 140, THEME);
+    fn test_set_max_col() {
+        let links = HashMap::from([(0, Link::new(0, 0, 11, 14))]);
+        let mut textarea = TextArea::new(vec!["a line too long to fit".to_string()], links, 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 19));
+
+        assert!(textarea.set_max_col(11));
+        assert_eq!(textarea.lines, ["a line too", "long to", "fit"]);
+        let link = textarea.links[&0];
+        assert_eq!((link.row, link.start_col, link.end_col), (1, 0, 3));
+        assert_eq!(textarea.cursor, (2, 0));
+        assert!(!textarea.set_max_col(20));
+        assert_eq!(textarea.max_col(), 20);
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines, ["a line too long to fit"]);
+        let link = textarea.links[&0];
+        assert_eq!((link.row, link.start_col, link.end_col), (0, 11, 14));
+        assert_eq!(textarea.cursor, (0, 19));
+        assert!(textarea.redo());
+        assert_eq!(textarea.lines, ["a line too", "long to", "fit"]);
+        assert_eq!(textarea.links[&0].row, 1);
+    }
 
-        textarea.links.insert(0, Link { id: 0, row: 2, start_col: 7, end_col: 12, edited: false , deleted: false });
+    #[test]
+    fn test_line_meta() {
+        let lines = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.set_line_meta(1, "b");
+        textarea.set_line_meta(3, "d");
+
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        textarea.insert_str("x\ny\nz", (0, 1));
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(3, "b"), (5, "d")]);
+
+        // Deleting the line drops its metadata
+        textarea.move_cursor(CursorMove::Jump(3, 0));
+        textarea.delete_line_by_end();
+        assert!(textarea.delete_next_char());
+        assert_eq!(textarea.lines(), ["ax", "y", "z", "c", "d"]);
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(4, "d")]);
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(3, "b"), (5, "d")]);
+        assert!(textarea.undo());
+        assert!(textarea.undo());
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(1, "b"), (3, "d")]);
+        assert!(textarea.redo());
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(3, "b"), (5, "d")]);
+
+        // Metadata of a deleted line comes back with it on undo
+        let lines = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let mut textarea = TextArea::new(lines, HashMap::new(), 140, THEME);
+        textarea.set_line_meta(1, "b");
+        textarea.set_line_meta(2, "c");
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        assert!(textarea.delete_line(true));
+        assert_eq!(textarea.lines(), ["a", "c"]);
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(1, "c")]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["a", "b", "c"]);
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(1, "b"), (2, "c")]);
+        assert!(textarea.redo());
+        assert_eq!(textarea.lines(), ["a", "c"]);
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(1, "c")]);
+
+        // Joining a line into the previous line drops its metadata until undo
+        textarea.set_line_meta(0, "a");
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        assert!(textarea.delete_char());
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(0, "a")]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.line_metas().collect::<Vec<_>>(), [(0, "a"), (1, "c")]);
+    }
 
-        textarea.selection_start = Some((0, 0));
-        textarea.move_cursor(CursorMove::Jump(1, 11));
-        textarea.delete_selection(false);
+    #[test]
+    fn test_anchors() {
+        let mut textarea = TextArea::new(vec!["abc def".to_string(), "ghi".to_string()], HashMap::new(), 140, THEME);
+        let a = textarea.create_anchor((0, 4));
+        let b = textarea.create_anchor((1, 2));
+        let c = textarea.create_anchor((5, 9));
+        assert_eq!(textarea.anchor_pos(c), Some((1, 3)));
+
+        // Newline before the anchors
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        textarea.insert_newline();
+        assert_eq!(textarea.anchor_pos(a), Some((1, 3)));
+        assert_eq!(textarea.anchor_pos(b), Some((2, 2)));
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 7, end_col: 12 , edited: false , deleted: false}));
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        textarea.delete_next_word();
+        assert_eq!(textarea.lines, ["a", "b def", "ghi"]);
+        assert_eq!(textarea.anchor_pos(a), Some((1, 2)));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.anchor_pos(a), Some((1, 3)));
+        assert!(textarea.undo());
+        assert_eq!(textarea.anchor_pos(a), Some((0, 4)));
+        assert_eq!(textarea.anchor_pos(b), Some((1, 2)));
+        assert!(textarea.redo());
+        assert_eq!(textarea.anchor_pos(a), Some((1, 3)));
+
+        // Deleting text around the anchor moves it to the start of the deletion
+        textarea.move_cursor(CursorMove::Jump(1, 2));
+        textarea.delete_next_word();
+        assert_eq!(textarea.lines, ["a", "bc", "ghi"]);
+        assert_eq!(textarea.anchor_pos(a), Some((1, 2)));
+
+        assert!(textarea.remove_anchor(b));
+        assert_eq!(textarea.anchor_pos(b), None);
     }
 
     #[test]
-    fn test_link_shift_after_selection_deletion_above_up_to_link_row_and_start_col() {
-        let mut textarea = TextArea::new(vec![
-            "Text above 1".into(),
-            "Text above 2".into(),
-            "Before [link] after".into()
-        ], HashMap::new(), 140, THEME);
+    fn test_goto_last_change() {
+        let mut textarea = TextArea::new(
+            vec!["abc".to_string(), "def".to_string(), "ghi".to_string()],
+            HashMap::new(),
+            140,
+            THEME,
+        );
+        assert_eq!(textarea.last_change_pos(), None);
+        assert!(!textarea.goto_last_change());
 
-        textarea.links.insert(0, Link { id: 0, row: 2, start_col: 7, end_col: 12, edited: false , deleted: false });
+        textarea.move_cursor(CursorMove::Jump(2, 1));
+        textarea.insert_char('x');
+        textarea.move_cursor(CursorMove::Top);
+        textarea.insert_newline();
+        assert_eq!(textarea.last_change_pos(), Some((1, 0)));
 
-        textarea.selection_start = Some((0, 0));
-        textarea.move_cursor(CursorMove::Jump(2, 6));
-        textarea.delete_selection(false);
+        // The older change moved down with the inserted line
+        assert!(textarea.goto_last_change());
+        assert!(textarea.goto_last_change());
+        assert_eq!(textarea.cursor(), (3, 2));
+        assert!(!textarea.goto_last_change());
+        assert!(textarea.goto_next_change());
+        assert_eq!(textarea.cursor(), (1, 0));
+        assert!(!textarea.goto_next_change());
+    }
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 0, end_col: 5 , edited: false , deleted: false}));
+    #[test]
+    fn test_autosave() {
+        use std::sync::Mutex;
+
+        let saved = Arc::new(Mutex::new(vec![]));
+        let mut textarea = TextArea::new(vec!["abc".to_string()], HashMap::new(), 140, THEME);
+        assert!(!textarea.tick());
+
+        let s = saved.clone();
+        textarea.set_autosave(Duration::ZERO, move |lines| {
+            s.lock().unwrap().push(lines.join("\n"));
+        });
+        assert!(!textarea.autosave_pending());
+
+        textarea.insert_char('x');
+        assert!(textarea.autosave_pending());
+        textarea.tick();
+        assert!(!textarea.autosave_pending());
+        textarea.tick();
+        textarea.undo();
+        assert!(textarea.flush_autosave());
+        assert_eq!(*saved.lock().unwrap(), ["xabc", "abc"]);
+
+        textarea.set_autosave(Duration::from_secs(3600), |_| {});
+        textarea.insert_char('y');
+        textarea.tick();
+        assert!(textarea.autosave_pending());
+        textarea.remove_autosave();
+        assert!(!textarea.autosave_pending());
+    }
+
+    #[cfg(feature = "journal")]
+    #[test]
+    fn test_journal() {
+        let path = std::env::temp_dir().join(format!("tuipaz-textarea-journal-{}", std::process::id()));
+        let base = vec!["abc".to_string(), "def".to_string()];
+        let mut textarea = TextArea::new(base.clone(), HashMap::new(), 140, THEME);
+        textarea.set_journal(&path).unwrap();
+        textarea.reset_journal().unwrap();
+
+        textarea.insert_char('x');
+        textarea.insert_char('y');
+        textarea.insert_newline();
+        textarea.move_cursor(CursorMove::Bottom);
+        textarea.delete_line_by_end();
+        textarea.undo();
+        textarea.redo();
+        textarea.move_cursor(CursorMove::Top);
+        textarea.select_all();
+        textarea.cut();
+        textarea.insert_char('z');
+        assert!(textarea.journal_error().is_none());
+
+        let recovered = crate::recover_from_journal(&path, base).unwrap();
+        assert_eq!(recovered, textarea.lines());
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn test_link_shift_after_selection_deletion_below_from_link_row_and_end_col() {
-        let mut textarea = TextArea::new(vec![
-            "Before [link] after".into(),
-            "Text below 1".into(),
-            "Text below 2".into(),
-        ], HashMap::new(), 140, THEME);
+    fn test_insert_str_incremental() {
+        let mut textarea = TextArea::new(vec!["<>".to_string()], HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        textarea.set_insert_batch_size(3);
+        assert!(!textarea.tick());
+
+        let text: Vec<_> = (0..10).map(|i| i.to_string()).collect();
+        assert!(textarea.insert_str_incremental(text.join("\n")));
+        assert_eq!(textarea.insert_progress(), Some((3, 10)));
+        // The cursor moved by the host does not change where the next batch is inserted
+        textarea.move_cursor(CursorMove::Top);
+        assert!(textarea.tick());
+        assert_eq!(textarea.insert_progress(), Some((6, 10)));
+        assert!(textarea.tick());
+        assert!(textarea.tick());
+        assert!(!textarea.is_inserting());
+        assert_eq!(textarea.insert_progress(), None);
+        assert!(!textarea.tick());
+
+        let mut expected = text.clone();
+        expected[0].insert(0, '<');
+        expected[9].push('>');
+        assert_eq!(textarea.lines(), expected);
+        assert_eq!(textarea.cursor(), (9, 1));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["<>"]);
+        assert!(textarea.redo());
+        assert_eq!(textarea.lines(), expected);
+
+        // Undo in the middle of the insertion undoes the whole insertion
+        textarea.insert_str_incremental(text.join("\n"));
+        assert!(textarea.is_inserting());
+        assert!(textarea.undo());
+        assert!(!textarea.is_inserting());
+        assert_eq!(textarea.lines(), expected);
+
+        // An edit between the batches is not collected into the insertion and moves where the next batch goes
+        let mut textarea = TextArea::new(vec!["<>".to_string()], HashMap::new(), 140, THEME);
+        textarea.move_cursor(CursorMove::Jump(0, 1));
+        textarea.set_insert_batch_size(2);
+        assert!(textarea.insert_str_incremental("a\nb\nc"));
+        assert!(!textarea.history.is_grouping());
+        textarea.move_cursor(CursorMove::Top);
+        textarea.insert_newline();
+        assert_eq!(textarea.lines(), ["", "<a", "b", ">"]);
+        assert!(textarea.tick());
+        assert!(!textarea.is_inserting());
+        assert_eq!(textarea.lines(), ["", "<a", "b", "c>"]);
 
-        textarea.links.insert(0, Link { id: 0, row: 0, start_col: 7, end_col: 12, edited: false , deleted: false });
+        // The last batch and the first one are undone separately from the edit between them
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["", "<a", "b", ">"]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["<a", "b", ">"]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines(), ["<>"]);
+    }
 
-        textarea.selection_start = Some((0, 13));
-        textarea.move_cursor(CursorMove::Jump(2, 11));
-        textarea.delete_selection(false);
+    #[test]
+    fn test_max_line_bytes() {
+        let long = "0123456789".repeat(4);
+        let mut textarea = TextArea::new(vec![long.clone(), "end".to_string()], HashMap::new(), 140, THEME);
+        textarea.set_line_meta(1, "meta");
+        textarea.move_cursor(CursorMove::Jump(0, 35));
+        let anchor = textarea.create_anchor((0, 25));
+
+        textarea.set_max_line_bytes(Some(16));
+        assert_eq!(textarea.max_line_bytes(), Some(16));
+        assert_eq!(textarea.lines(), [&long[..16], &long[16..32], &long[32..], "end"]);
+        assert!(!textarea.is_soft_break(0));
+        assert!(textarea.is_soft_break(1));
+        assert!(textarea.is_soft_break(2));
+        assert!(!textarea.is_soft_break(3));
+        assert_eq!(textarea.cursor(), (2, 3));
+        assert_eq!(textarea.anchor_pos(anchor), Some((1, 9)));
+        assert_eq!(textarea.line_meta(3), Some("meta"));
+        assert_eq!(textarea.logical_lines(), [long.as_str(), "end"]);
+
+        // Editing a segment edits the original line
+        textarea.insert_char('x');
+        let mut expected = long.clone();
+        expected.insert(35, 'x');
+        assert_eq!(textarea.logical_lines(), [expected.as_str(), "end"]);
+        textarea.move_cursor(CursorMove::Jump(0, 0));
+        textarea.insert_newline();
+        assert!(textarea.is_soft_break(2));
+        assert_eq!(textarea.logical_lines(), ["", expected.as_str(), "end"]);
 
-        assert_eq!(textarea.links.get(&0), Some(&Link { id: 0, row: 0, start_col: 7, end_col: 12 , edited: false , deleted: false}));
+        textarea.set_max_line_bytes(None);
+        assert_eq!(textarea.lines(), ["", expected.as_str(), "end"]);
+        assert_eq!(textarea.anchor_pos(anchor), Some((1, 25)));
+        assert_eq!(textarea.line_meta(2), Some("meta"));
+        assert!(!textarea.is_soft_break(2));
+    }
+
+    #[test]
+    fn test_soft_break_edits() {
+        let long = "0123456789".repeat(4);
+        let without = |range: Range<usize>| format!("{}{}", &long[..range.start], &long[range.end..]);
+        let mut textarea = TextArea::from([long.clone()]);
+        textarea.set_max_line_bytes(Some(16));
+
+        // Deleting at the boundary of segments deletes a char of the line
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        assert!(textarea.delete_char());
+        assert_eq!(textarea.logical_lines(), [without(15..16)]);
+        assert_eq!(textarea.cursor(), (0, 15));
+        assert!(textarea.undo());
+        assert_eq!(textarea.logical_lines(), [long.as_str()]);
+        textarea.move_cursor(CursorMove::Jump(0, 16));
+        assert!(textarea.delete_next_char());
+        assert_eq!(textarea.logical_lines(), [without(16..17)]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.lines().len(), 3);
+
+        // Segments joined by deleting the selection are restored as segments
+        textarea.move_cursor(CursorMove::Jump(0, 14));
+        textarea.start_selection();
+        textarea.move_cursor(CursorMove::Jump(1, 2));
+        assert!(textarea.delete_char());
+        assert_eq!(textarea.logical_lines(), [without(14..18)]);
+        assert!(textarea.undo());
+        assert_eq!(textarea.logical_lines(), [long.as_str()]);
+        assert!(textarea.is_soft_break(1) && textarea.is_soft_break(2));
+        assert!(textarea.redo());
+        assert_eq!(textarea.logical_lines(), [without(14..18)]);
+        assert_eq!(textarea.paragraphs().len(), 1);
+
+        // Links are not split into segments
+        let lines = vec![long.clone()];
+        let links = HashMap::from([(0, Link::new(0, 0, 14, 17))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        textarea.set_max_line_bytes(Some(16));
+        assert_eq!(textarea.lines(), [&long[..14], &long[14..30], &long[30..]]);
+        let link = textarea.links[&0];
+        assert_eq!((link.row, link.start_col, link.end_col), (1, 0, 3));
+        textarea.set_max_line_bytes(None);
+        let link = textarea.links[&0];
+        assert_eq!((link.row, link.start_col, link.end_col), (0, 14, 17));
+    }
+
+    #[test]
+    fn test_link_rows_follow_edits() {
+        let lines = vec!["aa".to_string(), "bb [cc]".to_string(), "dd".to_string()];
+        let links = HashMap::from([(0, Link::new(0, 1, 3, 6)), (1, Link::new(1, 2, 0, 1))]);
+        let mut textarea = TextArea::new(lines, links, 140, THEME);
+        assert_eq!(textarea.in_link((1, 4)), Some(0));
+
+        textarea.insert_newline();
+        assert_eq!(textarea.in_link((1, 4)), None);
+        assert_eq!(textarea.in_link((2, 4)), Some(0));
+        assert_eq!(textarea.in_link((3, 0)), Some(1));
+
+        assert!(textarea.undo());
+        assert_eq!(textarea.in_link((1, 4)), Some(0));
+        assert_eq!(textarea.in_link((2, 0)), Some(1));
+        // Undo and redo keep the index instead of rebuilding it
+        assert!(textarea.link_rows.is_synced(&textarea.links));
+        assert!(textarea.redo());
+        assert!(textarea.link_rows.is_synced(&textarea.links));
+        assert!(textarea.undo());
+
+        // Undoing an edit after a link leaves no mark on the link, so the next insertion before it shifts it
+        textarea.move_cursor(CursorMove::Jump(2, 2));
+        textarea.insert_char('x');
+        assert!(textarea.undo());
+        assert!(textarea.links.values().all(|l| !l.edited));
+        textarea.move_cursor(CursorMove::Jump(1, 0));
+        textarea.insert_char('y');
+        assert_eq!(textarea.in_link((1, 3)), None);
+        assert_eq!(textarea.in_link((1, 4)), Some(0));
+        assert_eq!(textarea.links[&0].end_col, 7);
+    }
+
+    #[test]
+    fn test_links_in_row_ordering() {
+        let lines = vec!["a b c d e f g h".to_string()];
+        let links = (0..8).map(|id| (id, Link::new(id, 0, id * 2, id * 2)));
+        let textarea = TextArea::new(lines, links, 140, THEME);
+        assert_eq!(textarea.links_in_row_after_cursor((0, 0)), Some((1..8).collect()));
+        assert_eq!(textarea.links_in_row_before_cursor((0, 6)), Some(vec![0, 1, 2, 3]));
     }
 
     #[test]
@@ -1,7 +1,9 @@
-use ratatui::{widgets::{Block, Widget, Paragraph}, style::{Style, Color, Modifier}, layout::Alignment, text::Text};
+use ratatui::{widgets::{Block, Widget, Paragraph}, style::{Style, Color, Modifier}, layout::Alignment, text::{Line, Span, Text}};
+use std::cell::Cell;
+use std::ops::Range;
 use unicode_width::UnicodeWidthChar;
 
-use crate::{Input, Key, word::{find_word_start_backward, find_word_end_forward}, util::spaces};
+use crate::{Input, InputKind, InputResult, Key, TextAreaTheme, Validator, word::{find_word_start_backward, find_word_end_forward}, util::spaces};
 
 #[derive(Clone, Debug)]
 pub struct TextInput<'a> {
@@ -15,6 +17,13 @@ pub struct TextInput<'a> {
     pub(crate) placeholder_style: Style,
     cursor_style: Style,
     max_col: u16,
+    // The first character shown in the widget. Updated on rendering to follow the cursor
+    scroll: Cell<usize>,
+    ellipsis: bool,
+    message: Option<(String, Style)>,
+    validator: Option<Validator>,
+    prompt: String,
+    prompt_style: Style,
 }
 
 impl<'a> TextInput<'a> {
@@ -32,11 +41,71 @@ impl<'a> TextInput<'a> {
             placeholder_style: style,
             cursor_style: Style::default().add_modifier(Modifier::REVERSED),
             max_col,
+            scroll: Cell::new(0),
+            ellipsis: false,
+            message: None,
+            validator: None,
+            prompt: String::new(),
+            prompt_style: Style::default(),
         }
     }
 
+    /// Create a text input with the colors of the theme shared with [`TextArea`](crate::TextArea).
+    /// ```
+    /// use tuipaz_textarea::{TextAreaTheme, TextInput};
+    ///
+    /// let theme = TextAreaTheme::default();
+    /// let input = TextInput::with_theme(String::new(), 40, theme, "Title".to_string());
+    /// ```
+    pub fn with_theme(text: String, max_col: u16, theme: TextAreaTheme, placeholder: String) -> Self {
+        let mut input = Self::new(text, max_col, theme.text, placeholder);
+        input.set_theme(theme);
+        input
+    }
+
+    /// Apply the colors of the theme to the text and placeholder styles. Other attributes of the styles are kept.
+    pub fn set_theme(&mut self, theme: TextAreaTheme) {
+        self.style = self.style.fg(theme.text);
+        self.placeholder_style = self.placeholder_style.fg(theme.text);
+    }
+
     pub fn input(&mut self, input: impl Into<Input>) -> bool {
         let input = input.into();
+        if input.kind == InputKind::Release {
+            return false;
+        }
+        self.edit(input)
+    }
+
+    /// Handle a key input as [`TextInput::input`] does, but return an [`InputResult`] instead of `bool`. Enter is
+    /// reported as [`InputResult::Submitted`] and Esc as [`InputResult::Cancelled`] without modifying the text, so
+    /// that prompt flows can pass all keys to the text input.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::{Input, InputKind, InputResult, Key, TextInput};
+    ///
+    /// let key = |key| Input { key, ctrl: false, alt: false, shift: false, kind: InputKind::Press };
+    /// let mut input = TextInput::new(String::new(), 40, Color::White, String::new());
+    ///
+    /// assert_eq!(input.input_result(key(Key::Char('a'))), InputResult::Changed);
+    /// assert_eq!(input.input_result(key(Key::Enter)), InputResult::Submitted);
+    /// assert_eq!(input.input_result(key(Key::Esc)), InputResult::Cancelled);
+    /// assert_eq!(input.text(), "a");
+    /// ```
+    pub fn input_result(&mut self, input: impl Into<Input>) -> InputResult {
+        let input = input.into();
+        if input.kind == InputKind::Release {
+            return InputResult::Unchanged;
+        }
+        match input.key {
+            Key::Enter => InputResult::Submitted,
+            Key::Esc => InputResult::Cancelled,
+            _ if self.edit(input) => InputResult::Changed,
+            _ => InputResult::Unchanged,
+        }
+    }
+
+    fn edit(&mut self, input: Input) -> bool {
         match input {
             Input {
                 key: Key::Char(c),
@@ -183,6 +252,10 @@ impl<'a> TextInput<'a> {
         self.cursor_style = style;
     }
 
+    pub fn cursor_style(&self) -> Style {
+        self.cursor_style
+    }
+
     pub fn set_text_style(&mut self, text_clr: Color) {
         self.style = Style::default().fg(text_clr);
     }
@@ -195,6 +268,119 @@ impl<'a> TextInput<'a> {
         self.text.as_ref()
     }
 
+    /// Set if `…` is shown at the edges of the widget when the text is scrolled horizontally and continues out of
+    /// the widget. The default is `false`.
+    pub fn set_ellipsis(&mut self, enabled: bool) {
+        self.ellipsis = enabled;
+    }
+
+    /// Get if `…` is shown at the edges of the widget when the text continues out of it. See
+    /// [`TextInput::set_ellipsis`].
+    pub fn ellipsis(&self) -> bool {
+        self.ellipsis
+    }
+
+    /// Set a prompt label rendered before the editable text such as `"> "` or `"Search: "`. The prompt is not a part
+    /// of the text and the cursor never moves into it. An empty prompt is not rendered.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::TextInput;
+    ///
+    /// let mut input = TextInput::new(String::new(), 40, Color::White, String::new());
+    /// input.set_prompt("Search: ");
+    /// input.insert_char('a');
+    ///
+    /// assert_eq!(input.prompt(), "Search: ");
+    /// assert_eq!(input.get_text(), "a");
+    /// ```
+    pub fn set_prompt(&mut self, prefix: impl Into<String>) {
+        self.prompt = prefix.into();
+    }
+
+    /// Get the prompt label. See [`TextInput::set_prompt`].
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    /// Set the style of the prompt label.
+    pub fn set_prompt_style(&mut self, style: Style) {
+        self.prompt_style = style;
+    }
+
+    /// Get the style of the prompt label.
+    pub fn prompt_style(&self) -> Style {
+        self.prompt_style
+    }
+
+    /// Set a message such as a validation error shown with the style. With a block, the message is rendered on the
+    /// bottom border of the block. Without a block, it is rendered in the row under the text when the widget has more
+    /// than one row.
+    /// ```
+    /// use ratatui::style::{Color, Style};
+    /// use tuipaz_textarea::TextInput;
+    ///
+    /// let mut input = TextInput::new(String::new(), 40, Color::White, String::new());
+    /// input.set_message("Title must not be empty", Style::default().fg(Color::Red));
+    /// assert_eq!(input.message(), Some("Title must not be empty"));
+    /// ```
+    pub fn set_message(&mut self, text: impl Into<String>, style: Style) {
+        self.message = Some((text.into(), style));
+    }
+
+    /// Remove the message. See [`TextInput::set_message`].
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+
+    /// Get the message. See [`TextInput::set_message`].
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|(text, _)| text.as_str())
+    }
+
+    /// Set the validator of the text used by [`TextInput::validate`].
+    pub fn set_validator(&mut self, validator: Validator) {
+        self.validator = Some(validator);
+    }
+
+    /// Validate the text with the validator set by [`TextInput::set_validator`]. The error is set as the message in
+    /// red, and the message is cleared when the text is valid. Returns `true` when the text is valid or no validator
+    /// is set.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::TextInput;
+    ///
+    /// let mut input = TextInput::new(String::new(), 40, Color::White, String::new());
+    /// input.set_validator(|s| if s.is_empty() { Err("Title must not be empty".to_string()) } else { Ok(()) });
+    ///
+    /// assert!(!input.validate());
+    /// assert_eq!(input.message(), Some("Title must not be empty"));
+    /// input.insert_char('a');
+    /// assert!(input.validate());
+    /// assert_eq!(input.message(), None);
+    /// ```
+    pub fn validate(&mut self) -> bool {
+        let validator = match self.validator {
+            Some(validator) => validator,
+            None => return true,
+        };
+        match validator(&self.text) {
+            Ok(()) => {
+                self.message = None;
+                true
+            }
+            Err(message) => {
+                self.message = Some((message, Style::default().fg(Color::Red)));
+                false
+            }
+        }
+    }
+
+    /// Get the index of the first character shown in the widget. Text longer than the widget is scrolled
+    /// horizontally on rendering so that the cursor is always visible.
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll.get()
+    }
+
     pub fn widget(&'a self) -> impl Widget + 'a {
         Renderer::new(self)
     }
@@ -212,23 +398,193 @@ impl<'a> Widget for Renderer<'a> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
     where Self: Sized 
     {
-        let (text, style) = if !self.0.placeholder.is_empty() && self.0.text.is_empty() {
-            let text = Text::from(self.0.placeholder.as_str());
-            (text, self.0.placeholder_style)
-        } else {
-            (Text::from(self.0.text()), self.0.style)
-        };
-
-        let inner = Paragraph::new(text)
-            .style(style)
-            .alignment(Alignment::Left);
-        
+        let input = self.0;
         let mut text_input = area;
-        if let Some(b) = self.0.block() {
+        if let Some(b) = input.block() {
             text_input = b.inner(area);
             b.clone().render(area, buf)
         }
 
+        if let Some((message, style)) = &input.message {
+            // On the bottom border of the block, or in the row under the text
+            let (x, y, width) = if input.block.is_some() {
+                (area.x + 1, area.bottom().saturating_sub(1), area.width.saturating_sub(2))
+            } else {
+                (text_input.x, text_input.y + 1, text_input.width)
+            };
+            if y > text_input.y && y < area.bottom() && width > 0 {
+                buf.set_stringn(x, y, message, width as usize, *style);
+            }
+            if input.block.is_none() {
+                text_input.height = 1;
+            }
+        }
+
+        if !input.prompt.is_empty() && text_input.width > 0 && text_input.height > 0 {
+            let (x, _) = buf.set_stringn(
+                text_input.x,
+                text_input.y,
+                &input.prompt,
+                text_input.width as usize,
+                input.prompt_style,
+            );
+            let width = x - text_input.x;
+            text_input.x = x;
+            text_input.width -= width;
+        }
+
+        if !input.placeholder.is_empty() && input.text.is_empty() {
+            let inner = Paragraph::new(Text::from(input.placeholder.as_str()))
+                .style(input.placeholder_style)
+                .alignment(Alignment::Left);
+            inner.render(text_input, buf);
+            return;
+        }
+
+        let chars: Vec<char> = input.text.chars().collect();
+        let widths: Vec<usize> = chars.iter().map(|c| c.width().unwrap_or(0)).collect();
+        let cursor = input.cursor.1.min(chars.len());
+        let window = visible_range(
+            &widths,
+            cursor,
+            input.scroll.get(),
+            text_input.width as usize,
+            input.ellipsis,
+        );
+        input.scroll.set(window.start);
+
+        let mut spans = vec![];
+        if input.ellipsis && window.start > 0 {
+            spans.push(Span::raw("…"));
+        }
+        let piece = |r: Range<usize>| chars[r].iter().collect::<String>();
+        if cursor < window.end {
+            spans.push(Span::raw(piece(window.start..cursor)));
+            spans.push(Span::styled(piece(cursor..cursor + 1), input.cursor_style));
+            spans.push(Span::raw(piece(cursor + 1..window.end)));
+        } else {
+            spans.push(Span::raw(piece(window.clone())));
+            if cursor == chars.len() {
+                spans.push(Span::styled(" ", input.cursor_style));
+            }
+        }
+        if input.ellipsis && window.end < chars.len() {
+            spans.push(Span::raw("…"));
+        }
+
+        let inner = Paragraph::new(Line::from(spans))
+            .style(input.style)
+            .alignment(Alignment::Left);
         inner.render(text_input, buf);
     }
 }
+
+// Get the range of characters shown in the widget of `width` cells. The range starts from `scroll` if possible and is
+// moved so that the cursor is always visible. When `ellipsis` is enabled, one cell at each edge is used by `…` when
+// the text continues out of the widget.
+fn visible_range(widths: &[usize], cursor: usize, scroll: usize, width: usize, ellipsis: bool) -> Range<usize> {
+    let len = widths.len();
+    let mut start = scroll.min(cursor);
+    loop {
+        let mut used = if ellipsis && start > 0 { 1 } else { 0 };
+        let mut end = start;
+        while end < len && used + widths[end] <= width {
+            used += widths[end];
+            end += 1;
+        }
+        if ellipsis && end < len {
+            while end > start && used + 1 > width {
+                end -= 1;
+                used -= widths[end];
+            }
+        }
+        // The cursor at the end of the text needs one more cell
+        let visible = cursor < end || (cursor == len && end == len && used < width);
+        if visible || start >= cursor {
+            return start..end;
+        }
+        start += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_result() {
+        let key = |key| Input {
+            key,
+            ctrl: false,
+            alt: false,
+            shift: false,
+            kind: InputKind::Press,
+        };
+        let mut input = TextInput::new(String::new(), 40, Color::White, String::new());
+        assert_eq!(input.input_result(key(Key::Char('a'))), InputResult::Changed);
+        assert_eq!(input.input_result(key(Key::Delete)), InputResult::Unchanged);
+        assert_eq!(input.input_result(key(Key::Enter)), InputResult::Submitted);
+        assert_eq!(input.input_result(key(Key::Esc)), InputResult::Cancelled);
+        assert_eq!(input.text(), "a");
+    }
+
+    #[test]
+    fn render_message() {
+        use ratatui::{buffer::Buffer, layout::Rect, widgets::Borders};
+
+        let mut input = TextInput::new("abc".to_string(), 40, Color::White, String::new());
+        assert!(input.validate());
+        input.set_validator(|s| if s.len() > 2 { Err("too long".to_string()) } else { Ok(()) });
+        assert!(!input.validate());
+        assert_eq!(input.message(), Some("too long"));
+
+        let area = Rect::new(0, 0, 12, 2);
+        let mut buf = Buffer::empty(area);
+        input.widget().render(area, &mut buf);
+        let row = |buf: &Buffer, y: u16| {
+            (0..buf.area.width)
+                .map(|x| buf.content[(y * buf.area.width + x) as usize].symbol().to_string())
+                .collect::<String>()
+        };
+        assert_eq!(row(&buf, 0), "abc         ");
+        assert_eq!(row(&buf, 1), "too long    ");
+
+        input.set_block(Block::default().borders(Borders::ALL));
+        let area = Rect::new(0, 0, 12, 3);
+        let mut buf = Buffer::empty(area);
+        input.widget().render(area, &mut buf);
+        assert_eq!(row(&buf, 2), "└too long──┘");
+    }
+
+    #[test]
+    fn render_prompt() {
+        use ratatui::{buffer::Buffer, layout::Rect};
+
+        let mut input = TextInput::new("abcdef".to_string(), 40, Color::White, String::new());
+        input.set_prompt("> ");
+        input.cursor = (0, 6);
+        let area = Rect::new(0, 0, 6, 1);
+        let mut buf = Buffer::empty(area);
+        input.widget().render(area, &mut buf);
+        let row: String = buf.content.iter().map(|c| c.symbol().to_string()).collect();
+        assert_eq!(row, "> def ");
+        assert_eq!(input.scroll_offset(), 3);
+        assert_eq!(input.get_text(), "abcdef");
+    }
+
+    #[test]
+    fn visible_range_follows_cursor() {
+        let widths = [1; 10];
+        assert_eq!(visible_range(&widths, 0, 0, 4, false), 0..4);
+        assert_eq!(visible_range(&widths, 5, 0, 4, false), 2..6);
+        assert_eq!(visible_range(&widths, 10, 0, 4, false), 7..10);
+        // Scrolling back only when the cursor leaves the window
+        assert_eq!(visible_range(&widths, 4, 2, 4, false), 2..6);
+        assert_eq!(visible_range(&widths, 1, 2, 4, false), 1..5);
+        // Cells for the ellipsis at both edges
+        assert_eq!(visible_range(&widths, 5, 0, 4, true), 4..6);
+        assert_eq!(visible_range(&widths, 0, 0, 4, true), 0..3);
+        // Wide characters
+        assert_eq!(visible_range(&[2, 2, 2], 2, 0, 4, false), 1..3);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::word::find_word_range;
+
+const BRACKETS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+const QUOTES: &[char] = &['"', '\'', '`'];
+
+/// Structured regions of text around the cursor, used by [`TextArea::text_object`],
+/// [`TextArea::select_text_object`] and [`TextArea::delete_text_object`]. They are similar to Vim's text objects.
+///
+/// This type is marked as `#[non_exhaustive]` since more text objects may be added in the future.
+///
+/// [`TextArea::text_object`]: crate::TextArea::text_object
+/// [`TextArea::select_text_object`]: crate::TextArea::select_text_object
+/// [`TextArea::delete_text_object`]: crate::TextArea::delete_text_object
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextObject {
+    /// The word at the cursor. A word is a sequence of word characters, a sequence of punctuations or a sequence of
+    /// whitespaces.
+    InnerWord,
+    /// The word at the cursor with trailing whitespaces. When there is no trailing whitespace, leading whitespaces are
+    /// included instead.
+    AWord,
+    /// The text inside the innermost pair of `()`, `[]`, `{}` or `<>` around the cursor. The pair can span multiple
+    /// lines.
+    InnerBrackets,
+    /// The text inside the innermost pair of `"`, `'` or `` ` `` around the cursor in the cursor line.
+    InnerQuotes,
+    /// The label of the [`Link`](crate::Link) at the cursor.
+    InnerLink,
+    /// The paragraph at the cursor. Paragraph is a chunk of non-empty lines.
+    Paragraph,
+}
+
+type Range = ((usize, usize), (usize, usize));
+
+fn a_word(line: &str, col: usize) -> Option<(usize, usize)> {
+    let (start, end) = find_word_range(line, col)?;
+    let is_space = |col: usize| line.chars().nth(col).map_or(false, char::is_whitespace);
+    if is_space(col) {
+        let end = find_word_range(line, end).map_or(end, |(_, e)| e);
+        return Some((start, end));
+    }
+    if is_space(end) {
+        let (_, end) = find_word_range(line, end)?;
+        Some((start, end))
+    } else if start > 0 && is_space(start - 1) {
+        let (start, _) = find_word_range(line, start - 1)?;
+        Some((start, end))
+    } else {
+        Some((start, end))
+    }
+}
+
+pub(crate) fn word(lines: &[String], (row, col): (usize, usize), around: bool) -> Option<Range> {
+    let line = &lines[row];
+    let (start, end) = if around {
+        a_word(line, col)?
+    } else {
+        find_word_range(line, col)?
+    };
+    Some(((row, start), (row, end)))
+}
+
+pub(crate) fn inner_brackets(lines: &[String], (row, col): (usize, usize)) -> Option<Range> {
+    let mut chars = vec![];
+    for (r, line) in lines.iter().enumerate() {
+        chars.extend(line.chars().enumerate().map(|(c, ch)| (r, c, ch)));
+        chars.push((r, line.chars().count(), '\n'));
+    }
+    let idx = chars.iter().position(|&(r, c, _)| (r, c) == (row, col))?;
+
+    let mut found: Option<(usize, usize)> = None;
+    for &(open, close) in BRACKETS {
+        let mut depth = 0;
+        let mut start = None;
+        for j in (0..=idx).rev() {
+            let ch = chars[j].2;
+            if ch == close && j != idx {
+                depth += 1;
+            } else if ch == open {
+                if depth == 0 {
+                    start = Some(j);
+                    break;
+                }
+                depth -= 1;
+            }
+        }
+        let start = match start {
+            Some(s) => s,
+            None => continue,
+        };
+        let mut depth = 0;
+        let end = chars[start + 1..].iter().position(|&(_, _, ch)| {
+            if ch == open {
+                depth += 1;
+            } else if ch == close {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            false
+        });
+        if let Some(end) = end.map(|e| start + 1 + e) {
+            if found.map_or(true, |(s, _)| s < start) {
+                found = Some((start, end));
+            }
+        }
+    }
+
+    let (start, end) = found?;
+    let (sr, sc, _) = chars[start];
+    let (er, ec, _) = chars[end];
+    Some(((sr, sc + 1), (er, ec)))
+}
+
+// Find the bracket matching the bracket at the cursor or the first bracket after the cursor in the line
+pub(crate) fn matching_pair(lines: &[String], (row, col): (usize, usize)) -> Option<(usize, usize)> {
+    let is_bracket = |ch: char| BRACKETS.iter().any(|&(open, close)| ch == open || ch == close);
+    let (col, ch) = lines[row].chars().enumerate().skip(col).find(|&(_, ch)| is_bracket(ch))?;
+    let &(open, close) = BRACKETS.iter().find(|&&(open, close)| ch == open || ch == close)?;
+
+    let mut depth = 0usize;
+    let mut step = |c: char, same: char, other: char| {
+        if c == same {
+            depth += 1;
+        } else if c == other {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+        false
+    };
+
+    if ch == open {
+        for (r, line) in lines.iter().enumerate().skip(row) {
+            let skip = if r == row { col + 1 } else { 0 };
+            if let Some(c) = line.chars().skip(skip).position(|c| step(c, open, close)) {
+                return Some((r, skip + c));
+            }
+        }
+    } else {
+        for r in (0..=row).rev() {
+            let chars: Vec<char> = lines[r].chars().collect();
+            let end = if r == row { col } else { chars.len() };
+            if let Some(c) = chars[..end].iter().rposition(|&c| step(c, close, open)) {
+                return Some((r, c));
+            }
+        }
+    }
+    None
+}
+
+pub(crate) fn inner_quotes(lines: &[String], (row, col): (usize, usize)) -> Option<Range> {
+    let chars: Vec<char> = lines[row].chars().collect();
+    let mut found: Option<(usize, usize)> = None;
+    for &quote in QUOTES {
+        let cols: Vec<usize> = (0..chars.len()).filter(|c| chars[*c] == quote).collect();
+        for pair in cols.chunks_exact(2) {
+            let (start, end) = (pair[0], pair[1]);
+            if start <= col && col <= end && found.map_or(true, |(s, e)| e - s > end - start) {
+                found = Some((start, end));
+            }
+        }
+    }
+    let (start, end) = found?;
+    Some(((row, start + 1), (row, end)))
+}
+
+pub(crate) fn paragraph(lines: &[String], (row, _): (usize, usize)) -> Option<Range> {
+    if lines[row].is_empty() {
+        return None;
+    }
+    let mut start = row;
+    while start > 0 && !lines[start - 1].is_empty() {
+        start -= 1;
+    }
+    let mut end = row;
+    while end + 1 < lines.len() && !lines[end + 1].is_empty() {
+        end += 1;
+    }
+    Some(((start, 0), (end, lines[end].chars().count())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn words() {
+        let l = lines(&["foo bar,  baz"]);
+        assert_eq!(word(&l, (0, 5), false), Some(((0, 4), (0, 7))));
+        assert_eq!(word(&l, (0, 5), true), Some(((0, 3), (0, 7))));
+        assert_eq!(word(&l, (0, 1), true), Some(((0, 0), (0, 4))));
+        assert_eq!(word(&l, (0, 8), true), Some(((0, 8), (0, 13))));
+        assert_eq!(word(&l, (0, 13), false), None);
+    }
+
+    #[test]
+    fn brackets() {
+        let l = lines(&["f(a, [b]) {", "  x", "}"]);
+        assert_eq!(inner_brackets(&l, (0, 3)), Some(((0, 2), (0, 8))));
+        assert_eq!(inner_brackets(&l, (0, 6)), Some(((0, 6), (0, 7))));
+        assert_eq!(inner_brackets(&l, (0, 5)), Some(((0, 6), (0, 7))));
+        assert_eq!(inner_brackets(&l, (1, 2)), Some(((0, 11), (2, 0))));
+        assert_eq!(inner_brackets(&l, (0, 0)), None);
+    }
+
+    #[test]
+    fn matching_pairs() {
+        let l = lines(&["f(a, [b]) {", "  (x)", "}"]);
+        assert_eq!(matching_pair(&l, (0, 1)), Some((0, 8)));
+        assert_eq!(matching_pair(&l, (0, 8)), Some((0, 1)));
+        assert_eq!(matching_pair(&l, (0, 2)), Some((0, 7)));
+        assert_eq!(matching_pair(&l, (0, 10)), Some((2, 0)));
+        assert_eq!(matching_pair(&l, (2, 0)), Some((0, 10)));
+        assert_eq!(matching_pair(&l, (1, 0)), Some((1, 4)));
+        assert_eq!(matching_pair(&l, (0, 11)), None);
+        assert_eq!(matching_pair(&lines(&["(a"]), (0, 0)), None);
+    }
+
+    #[test]
+    fn quotes() {
+        let l = lines(&[r#"say "it" and 'x'"#]);
+        assert_eq!(inner_quotes(&l, (0, 5)), Some(((0, 5), (0, 7))));
+        assert_eq!(inner_quotes(&l, (0, 14)), Some(((0, 14), (0, 15))));
+        assert_eq!(inner_quotes(&l, (0, 1)), None);
+    }
+
+    #[test]
+    fn paragraphs() {
+        let l = lines(&["a", "bc", "", "d"]);
+        assert_eq!(paragraph(&l, (0, 0)), Some(((0, 0), (1, 2))));
+        assert_eq!(paragraph(&l, (2, 0)), None);
+    }
+}
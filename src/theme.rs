@@ -0,0 +1,360 @@
+use crate::color::ColorSupport;
+use crate::ratatui::style::Color;
+use crate::textarea::{TextArea, TextAreaTheme};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Theme files are flat tables of the color names of `TextAreaTheme` fields. Keys missing in a file fall back to the
+// default theme.
+//
+// ```toml
+// text = "white"
+// select = "#3a3a3a"
+// links = 39
+// ```
+
+/// Error returned when a theme cannot be loaded. See [`TextAreaTheme::from_toml`].
+#[derive(Debug)]
+pub enum ThemeError {
+    /// The theme file cannot be read.
+    Io(io::Error),
+    /// The content is not a valid TOML table.
+    Toml(toml::de::Error),
+    /// The content is not a valid JSON object.
+    Json(serde_json::Error),
+    /// The key is not a field of [`TextAreaTheme`].
+    UnknownKey(String),
+    /// The value of the key is not a color name, `#rrggbb` nor an indexed color from 0 to 255.
+    InvalidColor { key: String, value: String },
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read theme: {}", err),
+            Self::Toml(err) => write!(f, "invalid TOML theme: {}", err),
+            Self::Json(err) => write!(f, "invalid JSON theme: {}", err),
+            Self::UnknownKey(key) => write!(f, "unknown theme key {:?}", key),
+            Self::InvalidColor { key, value } => write!(f, "invalid color {:?} for key {:?}", value, key),
+        }
+    }
+}
+
+impl Error for ThemeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Toml(err) => Some(err),
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ThemeError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ThemeError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
+
+impl From<serde_json::Error> for ThemeError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let c = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(c(0)?, c(2)?, c(4)?));
+    }
+    if let Ok(i) = value.parse::<u8>() {
+        return Some(Color::Indexed(i));
+    }
+    let name: String = value
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect();
+    let color = match name.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+    Some(color)
+}
+
+fn set_color(theme: &mut TextAreaTheme, key: &str, value: &str) -> Result<(), ThemeError> {
+    let field = match key {
+        "text" => &mut theme.text,
+        "select" => &mut theme.select,
+        "links" => &mut theme.links,
+        _ => return Err(ThemeError::UnknownKey(key.to_string())),
+    };
+    *field = parse_color(value).ok_or_else(|| ThemeError::InvalidColor {
+        key: key.to_string(),
+        value: value.to_string(),
+    })?;
+    Ok(())
+}
+
+impl TextAreaTheme {
+    /// Load a theme from the TOML file at the path. The file is a flat table whose keys are the fields of the theme.
+    /// Values are color names such as `"light-blue"`, `"#rrggbb"` strings or indexed colors from 0 to 255. Keys which
+    /// are not in the file keep the [default](TextAreaTheme::default) colors.
+    ///
+    /// ```toml
+    /// text = "white"
+    /// select = "#3a3a3a"
+    /// links = 39
+    /// ```
+    ///
+    /// This method is available when the `theme-config` feature is enabled. Use [`ThemeWatcher`] to apply changes of
+    /// the file while the application is running.
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self, ThemeError> {
+        Self::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Parse a theme from TOML text. See [`TextAreaTheme::from_toml`] for the format.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::TextAreaTheme;
+    ///
+    /// let theme = TextAreaTheme::from_toml_str("text = 'white' # comment\nlinks = \"#00afff\"").unwrap();
+    /// assert_eq!(theme.text, Color::White);
+    /// assert_eq!(theme.links, Color::Rgb(0x00, 0xaf, 0xff));
+    /// assert_eq!(theme.select, TextAreaTheme::default().select);
+    /// ```
+    pub fn from_toml_str(toml: &str) -> Result<Self, ThemeError> {
+        let table: toml::value::Table = toml::from_str(toml)?;
+        let mut theme = Self::default();
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(s) => s,
+                v => v.to_string(),
+            };
+            set_color(&mut theme, &key, &value)?;
+        }
+        Ok(theme)
+    }
+
+    /// Parse a theme from a JSON object. The keys and values are the same as [`TextAreaTheme::from_toml`].
+    ///
+    /// This method is available when the `theme-config` feature is enabled.
+    /// ```
+    /// use ratatui::style::Color;
+    /// use tuipaz_textarea::TextAreaTheme;
+    ///
+    /// let theme = TextAreaTheme::from_json(r#"{ "text": "white", "select": 238 }"#).unwrap();
+    /// assert_eq!(theme.text, Color::White);
+    /// assert_eq!(theme.select, Color::Indexed(238));
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, ThemeError> {
+        let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)?;
+        let mut theme = Self::default();
+        for (key, value) in object {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                v => v.to_string(),
+            };
+            set_color(&mut theme, &key, &value)?;
+        }
+        Ok(theme)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Toml,
+    Json,
+}
+
+/// Theme file which is reloaded when it is modified so that users can tweak the colors of the editor while the
+/// application is running. Call [`ThemeWatcher::apply`] (or [`ThemeWatcher::poll`] for an
+/// [`EditorGroup`](crate::EditorGroup)) on each tick of the event loop. Files with `.json` extension are parsed as
+/// JSON and others as TOML.
+///
+/// This type is available when the `theme-config` feature is enabled.
+/// ```no_run
+/// use tuipaz_textarea::{TextArea, ThemeWatcher};
+///
+/// let mut textarea = TextArea::default();
+/// let mut watcher = ThemeWatcher::new("theme.toml");
+/// loop {
+///     // The theme is applied on the first call and then each time the file is modified
+///     if let Err(err) = watcher.apply(&mut textarea) {
+///         eprintln!("{}", err);
+///     }
+///     // Render the textarea and handle inputs...
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThemeWatcher {
+    path: PathBuf,
+    format: Format,
+    modified: Option<SystemTime>,
+    color_support: ColorSupport,
+}
+
+impl ThemeWatcher {
+    /// Watch the theme file at the path. The file does not need to exist yet.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let format = match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Format::Json,
+            _ => Format::Toml,
+        };
+        Self {
+            path,
+            format,
+            modified: None,
+            color_support: ColorSupport::default(),
+        }
+    }
+
+    /// Map the colors of loaded themes to the colors which the terminal supports. See [`TextAreaTheme::degrade`].
+    /// By default, colors are not mapped.
+    pub fn set_color_support(&mut self, support: ColorSupport) {
+        self.color_support = support;
+    }
+
+    /// Path of the watched theme file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the theme when the file was modified since the last call. `Ok(None)` is returned when the file is not
+    /// modified or does not exist. When the file is broken, the error is returned once and the file is checked again
+    /// after it is modified.
+    pub fn poll(&mut self) -> Result<Option<TextAreaTheme>, ThemeError> {
+        let modified = match fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(t) => t,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        if self.modified == Some(modified) {
+            return Ok(None);
+        }
+        self.modified = Some(modified);
+        let content = fs::read_to_string(&self.path)?;
+        let theme = match self.format {
+            Format::Toml => TextAreaTheme::from_toml_str(&content)?,
+            Format::Json => TextAreaTheme::from_json(&content)?,
+        };
+        Ok(Some(theme.degrade(self.color_support)))
+    }
+
+    /// Reload the theme with [`ThemeWatcher::poll`] and apply it to the textarea with
+    /// [`TextArea::set_theme`]. Returns `true` when the theme was applied.
+    pub fn apply(&mut self, textarea: &mut TextArea<'_>) -> Result<bool, ThemeError> {
+        match self.poll()? {
+            Some(theme) => {
+                textarea.set_theme(theme);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml() {
+        let toml = "# theme\n\ntext = \"light-blue\"\n'select' = 238 # comment\nlinks = '#FF0080'\n";
+        let theme = TextAreaTheme::from_toml_str(toml).unwrap();
+        assert_eq!(theme.text, Color::LightBlue);
+        assert_eq!(theme.select, Color::Indexed(238));
+        assert_eq!(theme.links, Color::Rgb(0xff, 0x00, 0x80));
+
+        for (toml, want) in [
+            ("color = 'red'", "unknown theme key \"color\""),
+            ("text = 'redd'", "invalid color \"redd\" for key \"text\""),
+            ("text = 256", "invalid color \"256\" for key \"text\""),
+            ("text = true", "invalid color \"true\" for key \"text\""),
+        ] {
+            let err = TextAreaTheme::from_toml_str(toml).unwrap_err();
+            assert_eq!(err.to_string(), want, "{:?}", toml);
+        }
+        for toml in ["text", "\ntext = \"red", "text = 'red' x"] {
+            let err = TextAreaTheme::from_toml_str(toml).unwrap_err();
+            assert!(matches!(err, ThemeError::Toml(_)), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn json() {
+        let json = "{\n  \"text\": \"white\",\n  \"links\": \"#00af00\"\n}\n";
+        let theme = TextAreaTheme::from_json(json).unwrap();
+        assert_eq!(theme.text, Color::White);
+        assert_eq!(theme.select, TextAreaTheme::default().select);
+        assert_eq!(theme.links, Color::Rgb(0x00, 0xaf, 0x00));
+        assert_eq!(TextAreaTheme::from_json(" {} ").unwrap(), TextAreaTheme::default());
+
+        for (json, want) in [
+            ("{\"colour\": \"red\"}", "unknown theme key \"colour\""),
+            ("{\"text\": 256}", "invalid color \"256\" for key \"text\""),
+            ("{\"text\": null}", "invalid color \"null\" for key \"text\""),
+        ] {
+            let err = TextAreaTheme::from_json(json).unwrap_err();
+            assert_eq!(err.to_string(), want, "{:?}", json);
+        }
+        for json in ["", "{\n\"text\" \"red\"}", "{\"text\": \"red\"} x", "[\"red\"]"] {
+            let err = TextAreaTheme::from_json(json).unwrap_err();
+            assert!(matches!(err, ThemeError::Json(_)), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn watch() {
+        let dir = std::env::temp_dir().join(format!("tuipaz-textarea-theme-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("theme.json");
+        let mut watcher = ThemeWatcher::new(&path);
+        let mut textarea = TextArea::default();
+
+        assert!(!watcher.apply(&mut textarea).unwrap(), "file does not exist");
+        fs::write(&path, r#"{"text": "green"}"#).unwrap();
+        assert!(watcher.apply(&mut textarea).unwrap());
+        assert_eq!(textarea.style().fg, Some(Color::Green));
+        assert!(!watcher.apply(&mut textarea).unwrap(), "file is not modified");
+
+        watcher = ThemeWatcher::new(&path);
+        watcher.set_color_support(ColorSupport::Ansi16);
+        fs::write(&path, r##"{"text": "#00c000"}"##).unwrap();
+        assert!(watcher.apply(&mut textarea).unwrap());
+        assert_eq!(textarea.style().fg, Some(Color::Green));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
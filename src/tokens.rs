@@ -0,0 +1,154 @@
+use crate::ratatui::style::Style;
+use crate::util::Pos;
+use regex::Regex;
+use std::ops::Range;
+
+const EMAIL_PATTERN: &str = r"\b[\w.%+-]+@[\w-]+(?:\.[\w-]+)*\.[A-Za-z]{2,}\b";
+const FILE_PATH_PATTERN: &str = r#"(?:^|[\s(\["'])((?:~|\.{1,2})?(?:/[\w.-]*\w)+/?)"#;
+
+/// A pattern of tokens such as ticket IDs, email addresses or file paths detected in the text of a
+/// [`TextArea`](crate::TextArea). Tokens matching the pattern are highlighted with the style and can be queried with
+/// [`TextArea::tokens`](crate::TextArea::tokens) and [`TextArea::token_at`](crate::TextArea::token_at).
+///
+/// When the pattern has capture groups, the first group is the token. Otherwise the whole match is the token. This is
+/// useful to require some context around the token which should not be included.
+/// ```
+/// use ratatui::style::{Color, Style};
+/// use tuipaz_textarea::TokenProvider;
+///
+/// let tickets = TokenProvider::new("ticket", r"\b[A-Z]+-\d+\b", Style::default().fg(Color::Yellow)).unwrap();
+/// assert_eq!(tickets.kind(), "ticket");
+/// ```
+#[derive(Clone, Debug)]
+pub struct TokenProvider {
+    kind: String,
+    pattern: Regex,
+    style: Style,
+}
+
+impl TokenProvider {
+    /// Create a token provider of the kind with the regular expression pattern and the style.
+    pub fn new(kind: impl Into<String>, pattern: &str, style: Style) -> Result<Self, regex::Error> {
+        Ok(Self {
+            kind: kind.into(),
+            pattern: Regex::new(pattern)?,
+            style,
+        })
+    }
+
+    /// Create a token provider detecting email addresses like `foo@example.com`. The kind is `"email"`.
+    pub fn email(style: Style) -> Self {
+        Self::new("email", EMAIL_PATTERN, style).unwrap()
+    }
+
+    /// Create a token provider detecting absolute paths like `/etc/hosts` and relative paths starting with `./`,
+    /// `../` or `~/`. The kind is `"file_path"`.
+    pub fn file_path(style: Style) -> Self {
+        Self::new("file_path", FILE_PATH_PATTERN, style).unwrap()
+    }
+
+    /// Get the kind of the tokens.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Get the regular expression pattern of the tokens.
+    pub fn pattern(&self) -> &Regex {
+        &self.pattern
+    }
+
+    /// Get the style of the tokens.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    pub(crate) fn find<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        let group = if self.pattern.captures_len() > 1 {
+            1
+        } else {
+            0
+        };
+        self.pattern
+            .captures_iter(line)
+            .filter_map(move |caps| caps.get(group))
+            .filter(|m| !m.as_str().is_empty())
+            .map(|m| m.range())
+    }
+}
+
+/// A token found by a [`TokenProvider`] in the text of a [`TextArea`](crate::TextArea).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    /// Kind of the [`TokenProvider`] which found the token.
+    pub kind: String,
+    /// Text of the token.
+    pub text: String,
+    /// Position of the start of the token.
+    pub pos: Pos,
+}
+
+impl Token {
+    fn new(provider: &TokenProvider, line: &str, row: usize, range: Range<usize>) -> Self {
+        Self {
+            kind: provider.kind.clone(),
+            text: line[range.clone()].to_string(),
+            pos: Pos::new(row, line[..range.start].chars().count(), range.start),
+        }
+    }
+}
+
+pub(crate) fn collect_tokens(providers: &[TokenProvider], lines: &[String]) -> Vec<Token> {
+    let mut tokens = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        for provider in providers {
+            tokens.extend(
+                provider
+                    .find(line)
+                    .map(|r| Token::new(provider, line, row, r)),
+            );
+        }
+    }
+    tokens
+}
+
+pub(crate) fn token_at(
+    providers: &[TokenProvider],
+    line: &str,
+    row: usize,
+    offset: usize,
+) -> Option<Token> {
+    providers.iter().find_map(|provider| {
+        let range = provider
+            .find(line)
+            .find(|r| r.start <= offset && offset < r.end)?;
+        Some(Token::new(provider, line, row, range))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens<'a>(provider: &TokenProvider, line: &'a str) -> Vec<&'a str> {
+        provider.find(line).map(|r| &line[r]).collect()
+    }
+
+    #[test]
+    fn builtin_providers() {
+        let email = TokenProvider::email(Style::default());
+        assert_eq!(
+            tokens(&email, "mail foo.bar+x@example.co.jp, or @alice"),
+            ["foo.bar+x@example.co.jp"],
+        );
+
+        let path = TokenProvider::file_path(Style::default());
+        assert_eq!(
+            tokens(&path, "/etc/hosts, (./src/lib.rs) ~/notes/ ../a.b."),
+            ["/etc/hosts", "./src/lib.rs", "~/notes/", "../a.b"],
+        );
+        assert_eq!(
+            tokens(&path, "https://example.com/a and/or 1/2"),
+            Vec::<&str>::new()
+        );
+    }
+}
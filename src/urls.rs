@@ -0,0 +1,101 @@
+use crate::util::Pos;
+use std::ops::Range;
+
+const SCHEMES: &[&str] = &["https://", "http://", "ftp://", "file://"];
+
+fn is_url_char(c: char) -> bool {
+    !c.is_whitespace() && !c.is_control() && !matches!(c, '<' | '>' | '"' | '`')
+}
+
+/// Find bare URLs like `https://example.com/path` in the line. A URL starts with one of the schemes `http://`,
+/// `https://`, `ftp://` and `file://` at a word boundary and continues until a whitespace. Trailing punctuation such as
+/// `.` or `,` and unbalanced closing brackets are not included. The returned ranges are byte offsets.
+pub(crate) fn find_urls(line: &str) -> Vec<Range<usize>> {
+    let mut urls = vec![];
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        let scheme = SCHEMES.iter().find(|s| {
+            rest.len() >= s.len() && rest.as_bytes()[..s.len()].eq_ignore_ascii_case(s.as_bytes())
+        });
+        let at_boundary = line[..i]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !c.is_alphanumeric());
+        let scheme = match scheme {
+            Some(scheme) if at_boundary => scheme,
+            _ => {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+                continue;
+            }
+        };
+
+        let len = rest.find(|c| !is_url_char(c)).unwrap_or(rest.len());
+        let end = i + trim_url_end(&rest[..len]);
+        if end > i + scheme.len() {
+            urls.push(i..end);
+        }
+        i += len.max(scheme.len());
+    }
+    urls
+}
+
+// Returns the length of the URL without trailing punctuation
+fn trim_url_end(url: &str) -> usize {
+    let mut end = url.len();
+    while let Some(c) = url[..end].chars().next_back() {
+        let trim = match c {
+            '.' | ',' | ':' | ';' | '!' | '?' | '\'' => true,
+            ')' => url[..end].matches('(').count() < url[..end].matches(')').count(),
+            ']' => url[..end].matches('[').count() < url[..end].matches(']').count(),
+            '}' => url[..end].matches('{').count() < url[..end].matches('}').count(),
+            _ => false,
+        };
+        if !trim {
+            break;
+        }
+        end -= c.len_utf8();
+    }
+    end
+}
+
+pub(crate) fn collect_urls(lines: &[String]) -> Vec<(String, Pos)> {
+    let mut urls = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        for range in find_urls(line) {
+            let col = line[..range.start].chars().count();
+            urls.push((
+                line[range.clone()].to_string(),
+                Pos::new(row, col, range.start),
+            ));
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(line: &str) -> Vec<&str> {
+        find_urls(line).into_iter().map(|r| &line[r]).collect()
+    }
+
+    #[test]
+    fn find_urls_in_line() {
+        assert_eq!(
+            urls("see https://example.com/a?b=c#d and http://x.org."),
+            ["https://example.com/a?b=c#d", "http://x.org"],
+        );
+        // Brackets
+        assert_eq!(
+            urls("(https://en.wikipedia.org/wiki/Rust_(language))"),
+            ["https://en.wikipedia.org/wiki/Rust_(language)"],
+        );
+        assert_eq!(urls("<https://a.b>"), ["https://a.b"]);
+        // Case of scheme and non-ASCII
+        assert_eq!(urls("あ HTTPS://例え.jp/パス"), ["HTTPS://例え.jp/パス"]);
+        // Not URLs
+        assert_eq!(urls("https:// xhttps://a.b ftp:"), Vec::<&str>::new());
+    }
+}
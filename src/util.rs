@@ -1,16 +1,61 @@
+use std::fmt;
+
+const SPACES: &str = "                                                                                                                                                                                                                                                                ";
+
 pub fn spaces(size: u8) -> &'static str {
-    const SPACES: &str = "                                                                                                                                                                                                                                                                ";
     &SPACES[..size as usize]
 }
 
-pub fn num_digits(i: usize) -> u8 {
-    f64::log10(i as f64) as u8 + 1
+/// Padding of any width such as an indent of deeply nested lines or a wide gutter. The padding is made of chunks
+/// borrowed from the static buffer of [`spaces`], so rendering it, formatting it with `Display` or appending it to a
+/// string never allocates for the padding itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Padding(pub(crate) usize);
+
+impl Padding {
+    /// The padding as chunks of spaces. A padding which fits in the static buffer is one chunk.
+    pub(crate) fn chunks(self) -> impl Iterator<Item = &'static str> {
+        let (full, rest) = (self.0 / SPACES.len(), self.0 % SPACES.len());
+        std::iter::repeat(SPACES)
+            .take(full)
+            .chain(Some(&SPACES[..rest]).filter(|s| !s.is_empty()))
+    }
+
+    /// Append the padding to the buffer.
+    pub(crate) fn push_to(self, buf: &mut String) {
+        for chunk in self.chunks() {
+            buf.push_str(chunk);
+        }
+    }
+}
+
+impl fmt::Display for Padding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Number of decimal digits of the number. Zero has one digit.
+pub fn num_digits(mut i: usize) -> u8 {
+    let mut digits = 1;
+    while i >= 10 {
+        i /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+/// A position in the text of a textarea.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Pos {
+    /// Row of the line.
     pub row: usize,
+    /// Column in characters.
     pub col: usize,
+    /// Byte offset in the line.
     pub offset: usize,
 }
 
@@ -20,6 +65,151 @@ impl Pos {
     }
 }
 
+/// A character-wise position `(row, col)` in the text of a textarea. Positions are ordered by row and then by column,
+/// and they convert from and into `(row, col)` tuples so that methods taking a position also accept a tuple.
+/// ```
+/// use tuipaz_textarea::Position;
+///
+/// let pos = Position::new(1, 4);
+/// assert_eq!(pos, Position::from((1, 4)));
+/// assert!(Position::new(0, 9) < pos);
+/// assert_eq!(<(usize, usize)>::from(pos), (1, 4));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// Row of the line.
+    pub row: usize,
+    /// Column in characters.
+    pub col: usize,
+}
+
+impl Position {
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+}
+
+impl From<(usize, usize)> for Position {
+    fn from((row, col): (usize, usize)) -> Self {
+        Self { row, col }
+    }
+}
+
+impl From<Position> for (usize, usize) {
+    fn from(pos: Position) -> Self {
+        (pos.row, pos.col)
+    }
+}
+
+impl From<&Pos> for Position {
+    fn from(pos: &Pos) -> Self {
+        Self {
+            row: pos.row,
+            col: pos.col,
+        }
+    }
+}
+
+/// A range of text between two [`Position`]s. The start is inclusive and the end is exclusive, and the start is never
+/// after the end. Ranges convert from pairs of positions or `(row, col)` tuples so that methods taking a range also
+/// accept them.
+/// ```
+/// use tuipaz_textarea::{Position, TextRange};
+///
+/// let range = TextRange::new((1, 4), (0, 2));
+/// assert_eq!(range.start, Position::new(0, 2));
+/// assert!(range.contains((0, 9)));
+/// assert!(!range.contains((1, 4)));
+/// assert!(range.intersects(&TextRange::new((1, 3), (2, 0))));
+/// assert!(!range.intersects(&TextRange::new((1, 4), (2, 0))));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextRange {
+    /// Start of the range (inclusive).
+    pub start: Position,
+    /// End of the range (exclusive).
+    pub end: Position,
+}
+
+impl TextRange {
+    /// Create a range between the two positions. The positions are swapped when `start` is after `end`.
+    pub fn new(start: impl Into<Position>, end: impl Into<Position>) -> Self {
+        let (start, end) = (start.into(), end.into());
+        if start <= end {
+            Self { start, end }
+        } else {
+            Self { start: end, end: start }
+        }
+    }
+
+    /// Whether the range contains no text.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Whether the position is in the range.
+    pub fn contains(&self, pos: impl Into<Position>) -> bool {
+        let pos = pos.into();
+        self.start <= pos && pos < self.end
+    }
+
+    /// Whether the other range is entirely in this range.
+    pub fn contains_range(&self, other: &TextRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Whether the two ranges share some text. Ranges which only touch each other do not intersect.
+    pub fn intersects(&self, other: &TextRange) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+impl<P: Into<Position>> From<(P, P)> for TextRange {
+    fn from((start, end): (P, P)) -> Self {
+        Self::new(start, end)
+    }
+}
+
+impl From<TextRange> for ((usize, usize), (usize, usize)) {
+    fn from(range: TextRange) -> Self {
+        (range.start.into(), range.end.into())
+    }
+}
+
+/// Byte offset of the character column in the line. The length of the line is returned when the column is at or
+/// after the end of the line.
+pub(crate) fn byte_offset(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
+
+// Closing character inserted when the opening character is typed with auto-pairing
+pub(crate) fn auto_pair_closer(c: char) -> Option<char> {
+    match c {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' | '\'' | '`' => Some(c),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_auto_pair_closer(c: char) -> bool {
+    matches!(c, ')' | ']' | '}' | '"' | '\'' | '`')
+}
+
+// Quote prefix such as `> ` or `> > ` at the head of the line. The space after each `>` is optional
+pub(crate) fn quote_prefix(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let mut end = 0;
+    while bytes.get(end) == Some(&b'>') {
+        end += 1;
+        if bytes.get(end) == Some(&b' ') {
+            end += 1;
+        }
+    }
+    &line[..end]
+}
+
 pub(crate) fn log_format<T: std::fmt::Debug>(data: &T, prefix: &str) -> String {
     let mut s = String::new();
     s.push_str(prefix);
@@ -27,3 +217,67 @@ pub(crate) fn log_format<T: std::fmt::Debug>(data: &T, prefix: &str) -> String {
     s.push_str(&format!("{:?}", data));
     s
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_range() {
+        let range = TextRange::new((2, 0), (1, 3));
+        assert_eq!(range, TextRange::from(((1, 3), (2, 0))));
+        assert!(!range.is_empty());
+        assert!(TextRange::new((1, 1), (1, 1)).is_empty());
+        assert!(range.contains((1, 3)));
+        assert!(range.contains((1, 100)));
+        assert!(!range.contains((2, 0)));
+        assert!(range.contains_range(&TextRange::new((1, 5), (2, 0))));
+        assert!(!range.contains_range(&TextRange::new((1, 2), (1, 5))));
+        assert!(range.intersects(&TextRange::new((0, 0), (1, 4))));
+        assert!(!range.intersects(&TextRange::new((0, 0), (1, 3))));
+        assert!(!range.intersects(&TextRange::new((1, 1), (1, 1))));
+        assert_eq!(<((usize, usize), (usize, usize))>::from(range), ((1, 3), (2, 0)));
+    }
+
+    #[test]
+    fn num_digits_boundaries() {
+        assert_eq!(num_digits(0), 1);
+        assert_eq!(num_digits(9), 1);
+        assert_eq!(num_digits(10), 2);
+        assert_eq!(num_digits(999), 3);
+        assert_eq!(num_digits(1000), 4);
+        assert_eq!(num_digits(999_999_999_999_999), 15);
+        assert_eq!(num_digits(1_000_000_000_000_000), 16);
+        assert_eq!(num_digits(usize::MAX), usize::MAX.to_string().len() as u8);
+    }
+
+    #[test]
+    fn quote_prefixes() {
+        for (line, want) in [
+            ("> a", "> "),
+            ("> > a", "> > "),
+            (">>a", ">>"),
+            (">", ">"),
+            (" > a", ""),
+            ("a > b", ""),
+        ] {
+            assert_eq!(quote_prefix(line), want, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn padding() {
+        assert_eq!(Padding(0).chunks().count(), 0);
+        assert_eq!(Padding(3).chunks().collect::<Vec<_>>(), ["   "]);
+        assert_eq!(Padding(SPACES.len()).chunks().count(), 1);
+
+        let wide = Padding(600);
+        let lens: Vec<_> = wide.chunks().map(str::len).collect();
+        assert_eq!(lens, [256, 256, 88]);
+        assert_eq!(format!("{}|", wide).len(), 601);
+        let mut buf = "x".to_string();
+        wide.push_to(&mut buf);
+        assert_eq!(buf.len(), 601);
+        assert!(buf[1..].bytes().all(|b| b == b' '));
+    }
+}
@@ -1,9 +1,9 @@
 use crate::ratatui::buffer::Buffer;
 use crate::ratatui::layout::Rect;
+use crate::ratatui::style::Style;
 use crate::ratatui::text::Text;
 use crate::ratatui::widgets::{Paragraph, Widget};
 use crate::textarea::TextArea;
-use crate::util::num_digits;
 use std::cmp;
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -53,7 +53,12 @@ impl Viewport {
         )
     }
 
-    fn store(&self, row: u16, col: u16, width: u16, height: u16) {
+    pub(crate) fn set_scroll_top(&self, row: u16, col: u16) {
+        let (_, _, width, height) = self.rect();
+        self.store(row, col, width, height);
+    }
+
+    pub(crate) fn store(&self, row: u16, col: u16, width: u16, height: u16) {
         // Pack four u16 values into one u64 value
         let u =
             ((width as u64) << 48) | ((height as u64) << 32) | ((row as u64) << 16) | col as u64;
@@ -84,25 +89,32 @@ impl<'a> Renderer<'a> {
     }
 
     #[inline]
-    fn text(&self, top_row: usize, height: usize) -> Text<'a> {
-        let lines_len = self.0.lines().len();
-        let lnum_len = num_digits(lines_len);
-        let bottom_row = cmp::min(top_row + height, lines_len);
-        let mut lines = Vec::with_capacity(bottom_row - top_row);
-        for (i, line) in self.0.lines()[top_row..bottom_row].iter().enumerate() {
-            lines.push(self.0.line_spans(line.as_str(), top_row + i, lnum_len));
+    fn text(&self, top_row: usize, height: usize, gutter: bool) -> Text<'a> {
+        let mut lines = vec![];
+        for (i, line) in self
+            .0
+            .render_lines(top_row..top_row + height, gutter)
+            .into_iter()
+            .enumerate()
+        {
+            if lines.len() >= height {
+                break;
+            }
+            lines.push(line);
+            lines.extend(self.0.include_lines(top_row + i));
         }
+        lines.truncate(height);
         Text::from(lines)
     }
 }
 
 impl<'a> Widget for Renderer<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let Rect { width, height, .. } = if let Some(b) = self.0.block() {
-            b.inner(area)
-        } else {
-            area
-        };
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let (text_area, block, gutter) = self.0.render_layout(area);
+        let Rect { width, height, .. } = text_area;
 
         fn next_scroll_top(prev_top: u16, cursor: u16, length: u16) -> u16 {
             if cursor < prev_top {
@@ -114,27 +126,42 @@ impl<'a> Widget for Renderer<'a> {
             }
         }
 
-        let cursor = self.0.cursor();
+        let cursor = self.0.view_anchor();
         let (top_row, top_col) = self.0.viewport.scroll_top();
-        let top_row = next_scroll_top(top_row, cursor.0 as u16, height);
+        let mut top_row = next_scroll_top(top_row, cursor.0 as u16, height);
+        // Included lines above the cursor may push the cursor out of the viewport
+        while (top_row as usize) < cursor.0
+            && self.0.screen_rows(top_row as usize, cursor.0) >= height as usize
+        {
+            top_row += 1;
+        }
         let top_col = next_scroll_top(top_col, cursor.1 as u16, width);
 
         let (text, style) = if !self.0.placeholder.is_empty() && self.0.is_empty() {
             let text = Text::from(self.0.placeholder.as_str());
             (text, self.0.placeholder_style)
         } else {
-            (self.text(top_row as usize, height as usize), self.0.style())
+            (self.text(top_row as usize, height as usize, gutter), self.0.style())
         };
 
         // To get fine control over the text color and the surrrounding block they have to be rendered separately
         // see https://github.com/ratatui-org/ratatui/issues/144
-        let mut text_area = area;
         let mut inner = Paragraph::new(text)
             .style(style)
             .alignment(self.0.alignment());
-        if let Some(b) = self.0.block() {
-            text_area = b.inner(area);
-            b.clone().render(area, buf)
+        if let Some(b) = self.0.block().filter(|_| block) {
+            let (title, footer) = self.0.block_status();
+            match title {
+                Some(title) => b.clone().title(title).render(area, buf),
+                None => b.clone().render(area, buf),
+            }
+            // The footer is written over the bottom border
+            if let Some(footer) = footer {
+                if text_area.bottom() < area.bottom() {
+                    let y = area.bottom() - 1;
+                    buf.set_stringn(text_area.x, y, footer, text_area.width as usize, Style::default());
+                }
+            }
         }
         if top_col != 0 {
             inner = inner.scroll((0, top_col));
@@ -60,3 +60,17 @@ pub fn find_word_start_backward(line: &str, start_col: usize) -> Option<usize> {
     }
     (cur != CharKind::Space).then(|| 0)
 }
+
+pub fn find_word_range(line: &str, col: usize) -> Option<(usize, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+    let kind = CharKind::new(*chars.get(col)?);
+    let start = chars[..col]
+        .iter()
+        .rposition(|c| CharKind::new(*c) != kind)
+        .map_or(0, |i| i + 1);
+    let end = chars[col..]
+        .iter()
+        .position(|c| CharKind::new(*c) != kind)
+        .map_or(chars.len(), |i| col + i);
+    Some((start, end))
+}
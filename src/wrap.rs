@@ -0,0 +1,327 @@
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar as _;
+
+fn char_width(c: char, col: usize, tab_len: u8) -> usize {
+    if c == '\t' {
+        if tab_len == 0 {
+            0
+        } else {
+            tab_len as usize - col % tab_len as usize
+        }
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+fn str_width(s: &str, tab_len: u8) -> usize {
+    s.chars().fold(0, |w, c| w + char_width(c, w, tab_len))
+}
+
+/// Wrap the line at spaces so that each wrapped line fits in `width` columns. Words longer than `width` are broken at
+/// character boundaries. Spaces at wrap points are removed. When `width` is 0, the line is not wrapped.
+pub(crate) fn wrap_line(line: &str, width: usize, tab_len: u8) -> Vec<String> {
+    wrap_line_from(line, width, tab_len, 0)
+}
+
+/// Same as [`wrap_line`] but the first wrapped line starts at the column `start`. When the first word does not fit
+/// after `start`, the first wrapped line is empty.
+pub(crate) fn wrap_line_from(line: &str, width: usize, tab_len: u8, start: usize) -> Vec<String> {
+    wrap_ranges(line, width, tab_len, start, &[])
+        .into_iter()
+        .map(|r| line[r].to_string())
+        .collect()
+}
+
+/// Compute the byte ranges of the wrapped lines in the line. See [`wrap_line_from`] for the wrapping rules. The `keep`
+/// byte ranges are never split so that links are not broken. A kept range longer than `width` overflows it.
+pub(crate) fn wrap_ranges(
+    line: &str,
+    width: usize,
+    tab_len: u8,
+    start: usize,
+    keep: &[Range<usize>],
+) -> Vec<Range<usize>> {
+    if width == 0 {
+        return std::iter::once(0..line.len()).collect();
+    }
+    let trim_end = |r: Range<usize>| r.start..r.start + line[r].trim_end().len();
+
+    let mut ranges = vec![];
+    // Byte offset of the start of the current line
+    let mut current = 0;
+    let mut current_width = start;
+    // Byte offset of the last space in the current line
+    let mut last_space = None;
+    for (i, c) in line.char_indices() {
+        let empty = current == i;
+        let space = c == ' ' && !keep.iter().any(|r| r.contains(&i));
+        // Skip spaces at the start of wrapped lines
+        if c == ' ' && empty && !ranges.is_empty() {
+            current = i + 1;
+            continue;
+        }
+
+        let first_after_start = ranges.is_empty() && start > 0;
+        if (!empty || first_after_start)
+            && current_width + char_width(c, current_width, tab_len) > width
+        {
+            if space {
+                ranges.push(trim_end(current..i));
+                current = i + 1;
+                current_width = 0;
+                last_space = None;
+                continue;
+            }
+            match last_space {
+                Some(s) => {
+                    ranges.push(trim_end(current..s));
+                    current = i - line[s..i].trim_start().len();
+                }
+                // Move the whole word to the next line instead of breaking it at the start column
+                None if first_after_start => ranges.push(current..current),
+                None => match keep.iter().find(|r| r.start < i && i < r.end) {
+                    // A kept range is moved to the next line as a whole, or overflows when it starts the line
+                    Some(r) if r.start > current => {
+                        ranges.push(trim_end(current..r.start));
+                        current = r.start;
+                    }
+                    Some(_) => {
+                        current_width += char_width(c, current_width, tab_len);
+                        continue;
+                    }
+                    None => {
+                        ranges.push(current..i);
+                        current = i;
+                    }
+                },
+            }
+            current_width = str_width(&line[current..i], tab_len);
+            last_space = None;
+        }
+
+        if space {
+            last_space = Some(i);
+        }
+        current_width += char_width(c, current_width, tab_len);
+    }
+    ranges.push(current..line.len());
+    ranges
+}
+
+/// Check if the break between the line and the next line was made by hard wrapping at `width`. It is assumed when
+/// the first word of the next line does not fit in the rest of the line.
+pub(crate) fn is_wrapped(line: &str, next: &str, width: usize, tab_len: u8) -> bool {
+    let line = line.trim_end();
+    if width == 0 || line.is_empty() || next.is_empty() || next.starts_with(char::is_whitespace) {
+        return false;
+    }
+    let word = next.split(char::is_whitespace).next().unwrap_or("");
+    str_width(line, tab_len) + 1 + str_width(word, tab_len) > width
+}
+
+/// Rows reflowed after text was inserted in the first row. See [`reflow`].
+#[derive(Debug)]
+pub(crate) struct Reflow {
+    /// Number of the original rows replaced by the new rows
+    pub(crate) consumed: usize,
+    /// New rows replacing the original rows
+    pub(crate) rows: Vec<String>,
+    // New position `(row, col)` of each character in each consumed row
+    positions: Vec<Vec<(usize, usize)>>,
+}
+
+impl Reflow {
+    /// Map the position `(row, col)` relative to the first row before the reflow to the position after it.
+    pub(crate) fn locate(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        let positions = match self.positions.get(row) {
+            Some(p) => p,
+            None => return (row + self.rows.len() - self.consumed, col),
+        };
+        match positions.get(col) {
+            Some(&pos) => pos,
+            None => match positions.last() {
+                Some(&(row, col)) => (row, col + 1),
+                None => (row, col),
+            },
+        }
+    }
+}
+
+/// Reflow the rows after text was inserted in the first row so that no row has more than `limit` characters except
+/// trailing spaces. The overhang of a row is moved to the head of the next row, and the overhang of the last row of a
+/// paragraph makes a new row. Rows are broken after spaces, or at the limit when a word is longer than it. The `keep`
+/// function returns the character ranges in the row which must not be broken, such as links. Break points of all rows
+/// are computed in one pass. `None` is returned when the first row fits in the limit.
+pub(crate) fn reflow(
+    lines: &[String],
+    limit: usize,
+    keep: impl Fn(usize) -> Vec<Range<usize>>,
+) -> Option<Reflow> {
+    // Characters of the current row with their original positions. Spaces joining rows have no position
+    type Chars = Vec<(char, Option<(usize, usize)>)>;
+    let fits = |chars: &Chars| {
+        chars
+            .iter()
+            .rposition(|(c, _)| *c != ' ')
+            .map_or(0, |i| i + 1)
+            <= limit
+    };
+    let row_chars = |row: usize| {
+        lines[row]
+            .chars()
+            .enumerate()
+            .map(move |(col, c)| (c, Some((row, col))))
+    };
+
+    let mut current: Chars = row_chars(0).collect();
+    if fits(&current) {
+        return None;
+    }
+    let limit = limit.max(1);
+    let mut keeps = vec![keep(0)];
+    let mut rows: Vec<Chars> = vec![];
+    let mut next = 1;
+    while !fits(&current) {
+        // Start index of the range which must not be broken at the index
+        let kept_start = |i: usize| {
+            let (row, col) = current[i].1?;
+            let range = keeps[row].iter().find(|r| r.contains(&col))?;
+            i.checked_sub(col - range.start)
+        };
+        let mut at = match (1..=limit)
+            .rev()
+            .find(|&i| current[i - 1].0 == ' ' && current[i].0 != ' ')
+        {
+            Some(i) => i,
+            None => limit,
+        };
+        match kept_start(at) {
+            Some(start) if start > 0 && start < at => at = start,
+            _ => {}
+        }
+        let tail = current.split_off(at);
+        rows.push(std::mem::replace(&mut current, tail));
+
+        if next < lines.len() && !lines[next].is_empty() {
+            if current.last().map_or(false, |(c, _)| *c != ' ') {
+                current.push((' ', None));
+            }
+            current.extend(row_chars(next));
+            keeps.push(keep(next));
+            next += 1;
+        }
+    }
+    rows.push(current);
+
+    let mut positions: Vec<Vec<(usize, usize)>> = (0..next)
+        .map(|r| Vec::with_capacity(lines[r].len()))
+        .collect();
+    for (new_row, chars) in rows.iter().enumerate() {
+        for (new_col, (_, pos)) in chars.iter().enumerate() {
+            if let Some((row, _)) = pos {
+                positions[*row].push((new_row, new_col));
+            }
+        }
+    }
+    Some(Reflow {
+        consumed: next,
+        rows: rows
+            .into_iter()
+            .map(|r| r.into_iter().map(|(c, _)| c).collect())
+            .collect(),
+        positions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_lines() {
+        for (line, width, want) in [
+            ("", 5, &[""][..]),
+            ("abc", 0, &["abc"]),
+            ("hello world", 20, &["hello world"]),
+            ("hello world", 5, &["hello", "world"]),
+            ("hello world", 8, &["hello", "world"]),
+            ("a b c d e", 3, &["a b", "c d", "e"]),
+            ("abcdefgh", 3, &["abc", "def", "gh"]),
+            ("ab   cdefgh ij", 4, &["ab", "cdef", "gh", "ij"]),
+            ("  indented text", 10, &["  indented", "text"]),
+            ("あいうえお", 4, &["あい", "うえ", "お"]),
+            ("a\tb c", 6, &["a\tb", "c"]),
+        ] {
+            assert_eq!(wrap_line(line, width, 4), want, "{line:?} {width}");
+        }
+    }
+
+    #[test]
+    fn wrap_lines_from_column() {
+        for (line, start, want) in [
+            ("ab cd", 2, &["ab", "cd"][..]),
+            ("ab cd", 3, &["ab", "cd"]),
+            ("ab cd", 4, &["", "ab cd"]),
+            (" ab", 4, &["", "ab"]),
+            ("abcdefg", 4, &["", "abcde", "fg"]),
+        ] {
+            assert_eq!(wrap_line_from(line, 5, 4, start), want, "{line:?} {start}");
+        }
+    }
+
+    #[test]
+    fn wrapped_line_breaks() {
+        assert!(is_wrapped("aaa bb ", "ccc", 8, 4));
+        assert!(!is_wrapped("aaa bb", "cc", 9, 4));
+        assert!(!is_wrapped("aaa bb", "", 8, 4));
+        assert!(!is_wrapped("", "ccc", 8, 4));
+        assert!(!is_wrapped("aaa bb", "ccc", 0, 4));
+    }
+
+    #[test]
+    fn wrap_ranges_keep() {
+        let line = "aa [b c] d";
+        assert_eq!(wrap_ranges(line, 6, 4, 0, &[]), [0..5, 6..10]);
+        assert_eq!(wrap_ranges(line, 6, 4, 0, &[3..8]), [0..2, 3..8, 9..10]);
+
+        // Kept ranges longer than the width are not broken
+        let line = "a [bcdefgh] i";
+        assert_eq!(wrap_ranges(line, 4, 4, 0, &[]), [0..1, 2..6, 6..10, 10..13]);
+        assert_eq!(wrap_ranges(line, 4, 4, 0, &[2..11]), [0..1, 2..11, 12..13]);
+        assert_eq!(wrap_ranges("a:[bcdefgh]", 4, 4, 0, &[2..11]), [0..2, 2..11]);
+    }
+
+    #[test]
+    fn reflow_rows() {
+        let lines = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let no_keep = |_| vec![];
+
+        assert!(reflow(&lines(&["aaa bbb"]), 7, no_keep).is_none());
+        assert!(reflow(&lines(&["aaa bbb  "]), 7, no_keep).is_none());
+
+        let r = reflow(&lines(&["aaa bbbx"]), 7, no_keep).unwrap();
+        assert_eq!(r.consumed, 1);
+        assert_eq!(r.rows, ["aaa ", "bbbx"]);
+        assert_eq!(r.locate((0, 8)), (1, 4));
+        assert_eq!(r.locate((0, 2)), (0, 2));
+        assert_eq!(r.locate((1, 0)), (2, 0));
+
+        // The overhang cascades into the next rows of the paragraph
+        let r = reflow(&lines(&["aa bb ccx", "dd ee", "ff", "", "gg"]), 8, no_keep).unwrap();
+        assert_eq!(r.consumed, 3);
+        assert_eq!(r.rows, ["aa bb ", "ccx dd ", "ee ff"]);
+        assert_eq!(r.locate((1, 0)), (1, 4));
+        assert_eq!(r.locate((1, 3)), (2, 0));
+        assert_eq!(r.locate((1, 4)), (2, 1));
+        assert_eq!(r.locate((2, 0)), (2, 3));
+        assert_eq!(r.locate((3, 0)), (3, 0));
+
+        // A word longer than the limit is broken
+        let r = reflow(&lines(&["abcdefgh"]), 3, no_keep).unwrap();
+        assert_eq!(r.rows, ["abc", "def", "gh"]);
+
+        // A kept range is not broken
+        let r = reflow(&lines(&["aa [b c]"]), 6, |_| vec![3..8]).unwrap();
+        assert_eq!(r.rows, ["aa ", "[b c]"]);
+    }
+}
@@ -1,4 +1,4 @@
-use tui_textarea::{Input, Key, TextArea};
+use tui_textarea::{Input, InputKind, Key, TextArea};
 
 // Sanity test for checking textarea does not crash against all combination of inputs
 #[test]
@@ -9,12 +9,15 @@ fn test_input_all_combinations_sanity() {
         for ctrl in [true, false] {
             for alt in [true, false] {
                 for shift in [true, false] {
-                    inputs.push(Input {
-                        key,
-                        ctrl,
-                        alt,
-                        shift,
-                    });
+                    for kind in [InputKind::Press, InputKind::Repeat, InputKind::Release] {
+                        inputs.push(Input {
+                            key,
+                            ctrl,
+                            alt,
+                            shift,
+                            kind,
+                        });
+                    }
                 }
             }
         }
@@ -75,6 +78,7 @@ fn test_insert_multi_code_unit_emoji() {
             ctrl: false,
             alt: false,
             shift: false,
+            kind: InputKind::Press,
         };
         assert!(t.input(input), "{c:?}");
     }